@@ -0,0 +1,105 @@
+use std::{
+    io::{Error, ErrorKind},
+    str::from_utf8,
+};
+
+use crate::{
+    apps::{
+        apps_mqtt_topics::AppsMqttTopics, heartbeat::Heartbeat, incident_data::incident::Incident,
+        sist_camaras::{camera::Camera, camera_frame::CameraFrame},
+        sist_dron::dron_current_info::DronCurrentInfo,
+    },
+    mqtt::{
+        messages::publish_message::PublishMessage,
+        mqtt_utils::will_message_utils::will_content::WillContent,
+    },
+};
+
+/// Contenido de un `PublishMessage` recibido por las apps, ya decodificado según su topic.
+/// Centraliza la decodificación: un topic desconocido o un payload corrupto resultan en un único error.
+#[derive(Debug)]
+pub enum AppMessage {
+    Camera(Camera),
+    Dron(DronCurrentInfo),
+    Incident(Incident),
+    CameraFrame(CameraFrame),
+    /// Heartbeat de una app, publicado al topic desc.
+    Heartbeat(Heartbeat),
+    /// Will de una app que se desconectó, publicado al topic desc.
+    Disconnect(WillContent),
+}
+
+impl AppMessage {
+    /// Decodifica el payload del `msg` según su topic. Devuelve error si el topic no es de las apps,
+    /// o si el payload no corresponde al topic.
+    pub fn from_publish(msg: &PublishMessage) -> Result<Self, Error> {
+        let payload = msg.get_payload();
+        match AppsMqttTopics::topic_from_str(&msg.get_topic_name())? {
+            AppsMqttTopics::CameraTopic => Ok(AppMessage::Camera(Camera::from_bytes(&payload)?)),
+            AppsMqttTopics::DronTopic => Ok(AppMessage::Dron(DronCurrentInfo::from_bytes(payload)?)),
+            AppsMqttTopics::IncidentTopic => Ok(AppMessage::Incident(Incident::from_bytes(payload)?)),
+            AppsMqttTopics::CameraFrameTopic => {
+                Ok(AppMessage::CameraFrame(CameraFrame::from_bytes(&payload)?))
+            }
+            AppsMqttTopics::DescTopic => {
+                let content =
+                    from_utf8(&payload).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+                match Heartbeat::heartbeat_from_string(content) {
+                    Ok(heartbeat) => Ok(AppMessage::Heartbeat(heartbeat)),
+                    Err(_) => Ok(AppMessage::Disconnect(WillContent::will_content_from_string(
+                        content,
+                    )?)),
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AppMessage;
+    use crate::{
+        apps::{
+            apps_mqtt_topics::AppsMqttTopics,
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            sist_dron::{dron_current_info::DronCurrentInfo, dron_state::DronState},
+        },
+        mqtt::{
+            messages::{publish_flags::PublishFlags, publish_message::PublishMessage},
+            mqtt_utils::will_message_utils::{app_type::AppType, will_content::WillContent},
+        },
+    };
+
+    fn create_publish(topic: &str, payload: &[u8]) -> PublishMessage {
+        PublishMessage::new(PublishFlags::new(0, 0, 0).unwrap(), topic, None, payload).unwrap()
+    }
+
+    #[test]
+    fn test_1_cada_topic_se_decodifica_al_mensaje_que_le_corresponde() {
+        let incident = Incident::new(4, (-34.6037, -58.3816), IncidentSource::Manual);
+        let msg = create_publish(AppsMqttTopics::IncidentTopic.to_str(), &incident.to_bytes());
+        assert!(matches!(AppMessage::from_publish(&msg), Ok(AppMessage::Incident(inc)) if inc.get_id() == 4));
+
+        let dron = DronCurrentInfo::new(2, -34.6037, -58.3816, 100, DronState::ExpectingToRecvIncident);
+        let msg = create_publish(AppsMqttTopics::DronTopic.to_str(), &dron.to_bytes());
+        assert!(matches!(AppMessage::from_publish(&msg), Ok(AppMessage::Dron(d)) if d == dron));
+
+        let will = WillContent::new(AppType::Dron, Some(2));
+        let msg = create_publish(AppsMqttTopics::DescTopic.to_str(), will.to_str().as_bytes());
+        assert!(matches!(AppMessage::from_publish(&msg), Ok(AppMessage::Disconnect(w)) if w == will));
+    }
+
+    #[test]
+    fn test_2_un_payload_que_no_corresponde_al_topic_o_un_topic_desconocido_son_error() {
+        // Un incidente publicado al topic de drones no alcanza para un DronCurrentInfo
+        let incident = Incident::new(4, (-34.6037, -58.3816), IncidentSource::Manual);
+        let msg = create_publish(AppsMqttTopics::DronTopic.to_str(), &incident.to_bytes());
+        assert!(AppMessage::from_publish(&msg).is_err());
+
+        let msg = create_publish(AppsMqttTopics::CameraTopic.to_str(), &[1, 2, 3]);
+        assert!(AppMessage::from_publish(&msg).is_err());
+
+        let msg = create_publish("otro", &incident.to_bytes());
+        assert!(AppMessage::from_publish(&msg).is_err());
+    }
+}
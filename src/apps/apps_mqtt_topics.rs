@@ -1,11 +1,17 @@
 use std::io::Error;
 
+use crate::mqtt::{
+    messages::publish_message::PublishMessage,
+    mqtt_utils::topic::{Topic, TopicFilter},
+};
+
 #[derive(Debug)]
 pub enum AppsMqttTopics {
     IncidentTopic,
     DronTopic,
     CameraTopic,
     DescTopic,
+    CameraFrameTopic,
 }
 
 impl AppsMqttTopics {
@@ -15,17 +21,74 @@ impl AppsMqttTopics {
             AppsMqttTopics::DronTopic => "dron",
             AppsMqttTopics::CameraTopic => "cam",
             AppsMqttTopics::DescTopic => "desc",
+            AppsMqttTopics::CameraFrameTopic => "cam_frame",
         }
     }
 
-    pub fn topic_from_str(str: &str) -> Result<Self, Error> {
+    /// Devuelve el topic correspondiente a la string recibida, o None si no es un topic de las apps.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(str: &str) -> Option<Self> {
         match str {
-            "inc" => Ok(AppsMqttTopics::IncidentTopic),
-            "dron" => Ok(AppsMqttTopics::DronTopic),
-            "cam" => Ok(AppsMqttTopics::CameraTopic),
-            "desc" => Ok(AppsMqttTopics::DescTopic),
-            _ => Err(Error::new(std::io::ErrorKind::InvalidInput, "Error: string inválida para crea un enum AppsMqttTopics."))
+            "inc" => Some(AppsMqttTopics::IncidentTopic),
+            "dron" => Some(AppsMqttTopics::DronTopic),
+            "cam" => Some(AppsMqttTopics::CameraTopic),
+            "desc" => Some(AppsMqttTopics::DescTopic),
+            "cam_frame" => Some(AppsMqttTopics::CameraFrameTopic),
+            _ => None,
+        }
+    }
+
+    /// Devuelve el topic del `PublishMessage` recibido, o None si no es un topic de las apps.
+    pub fn parse_topic(publish_message: &PublishMessage) -> Option<Self> {
+        Self::from_str(&publish_message.get_topic_name())
+    }
+
+    pub fn topic_from_str(str: &str) -> Result<Self, Error> {
+        match Self::from_str(str) {
+            Some(topic) => Ok(topic),
+            None => Err(Error::new(std::io::ErrorKind::InvalidInput, "Error: string inválida para crea un enum AppsMqttTopics."))
+        }
+    }
+}
+
+/// Los topics de las apps son válidos, por lo que no es necesario validarlos.
+impl From<AppsMqttTopics> for Topic {
+    fn from(topic: AppsMqttTopics) -> Self {
+        Topic::from_valid(topic.to_str())
+    }
+}
+
+impl From<AppsMqttTopics> for TopicFilter {
+    fn from(topic: AppsMqttTopics) -> Self {
+        TopicFilter::from_valid(topic.to_str())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AppsMqttTopics;
+    use crate::mqtt::mqtt_utils::topic::{Topic, TopicFilter};
 
+    #[test]
+    fn test_1_from_str_de_topics_conocidos_y_desconocidos() {
+        for topic in [
+            AppsMqttTopics::IncidentTopic,
+            AppsMqttTopics::DronTopic,
+            AppsMqttTopics::CameraTopic,
+            AppsMqttTopics::DescTopic,
+            AppsMqttTopics::CameraFrameTopic,
+        ] {
+            let parsed = AppsMqttTopics::from_str(topic.to_str()).unwrap();
+            assert_eq!(parsed.to_str(), topic.to_str());
         }
+        assert!(AppsMqttTopics::from_str("camaras").is_none());
+        assert!(AppsMqttTopics::topic_from_str("").is_err());
+    }
+
+    #[test]
+    fn test_2_los_topics_de_las_apps_se_convierten_en_topics_y_filters() {
+        assert_eq!(Topic::from(AppsMqttTopics::CameraTopic).as_str(), "cam");
+        assert_eq!(TopicFilter::from(AppsMqttTopics::DronTopic).as_str(), "dron");
+        assert_eq!(TopicFilter::from(Topic::from(AppsMqttTopics::DronTopic)).as_str(), "dron");
     }
 }
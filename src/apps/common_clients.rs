@@ -2,10 +2,14 @@ use std::{
     io::Error,
     net::SocketAddr,
     sync::{mpsc::Receiver, Arc, Mutex},
-    thread::JoinHandle,
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
-use crate::{logging::string_logger::StringLogger, mqtt::client::mqtt_client::MQTTClient};
+use crate::{
+    logging::string_logger::StringLogger,
+    mqtt::{client::publisher::Publisher, mqtt_utils::socket_address::parse_socket_addr},
+};
 
 use super::apps_mqtt_topics::AppsMqttTopics;
 
@@ -33,19 +37,28 @@ fn load_ip_and_port() -> Result<(String, u16), Box<Error>> {
     Ok((ip.to_string(), port))
 }
 
+/// Devuelve la dirección del broker recibida como argumentos (ver `get_broker_address`), o si no se recibió
+/// ningún argumento, la `configured` (ej. la de la configuración de la app).
+pub fn get_broker_address_or(configured: Option<SocketAddr>) -> SocketAddr {
+    match configured {
+        Some(addr) if std::env::args().len() == 1 => addr,
+        _ => get_broker_address(),
+    }
+}
+
+/// Devuelve la dirección del broker, de la IP (v4 o v6, ej. `[::1]`) y el puerto recibidos como argumentos.
 pub fn get_broker_address() -> SocketAddr {
     let (ip, port) = load_ip_and_port().unwrap_or_else(|e| {
         println!("Error al cargar el puerto: {:?}", e);
         std::process::exit(1);
     });
 
-    let broker_addr: String = format!("{}:{}", ip, port);
-    broker_addr.parse().expect("Dirección no válida")
+    parse_socket_addr(&ip, port).expect("Dirección no válida")
 }
 
-pub fn get_app_will_topic() -> String {
-    let will_topic = AppsMqttTopics::DescTopic.to_str();
-    String::from(will_topic)
+/// Devuelve el topic al que las apps publican su will, y al que se suscribe sistema monitoreo.
+pub fn get_app_will_topic() -> AppsMqttTopics {
+    AppsMqttTopics::DescTopic
 }
 
 pub fn join_all_threads(children: Vec<JoinHandle<()>>) {
@@ -61,19 +74,68 @@ pub fn join_all_threads(children: Vec<JoinHandle<()>>) {
     }
 }
 
+/// Tiempo que se espera a los hilos al salir, antes de dejar de esperarlos.
+pub const DEFAULT_SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Cada cuánto se revisa si los hilos terminaron.
+const JOIN_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Análoga a `join_all_threads`, pero espera a lo sumo `timeout` en total, y devuelve las posiciones en `children`
+/// de los hilos que no terminaron a tiempo (que además se muestran por pantalla).
+/// Como `JoinHandle::join` no admite timeout, se consulta periódicamente si cada hilo terminó, y solo se hace join
+/// de los que ya terminaron. Los hilos se enteran de que deben terminar por el `ShutdownToken`, por lo que debe
+/// solicitarse antes de llamar a esta función. Los hilos que no terminaron quedan sin esperar: no se los puede
+/// interrumpir, y se los abandona (terminan junto con el proceso) a cambio de que la app no quede colgada al salir,
+/// ej. por un hilo bloqueado leyendo de un socket que ya no responde.
+pub fn join_all_threads_with_timeout(children: Vec<JoinHandle<()>>, timeout: Duration) -> Vec<usize> {
+    let deadline = Instant::now() + timeout;
+    let mut pending: Vec<(usize, JoinHandle<()>)> = children.into_iter().enumerate().collect();
+
+    loop {
+        let (finished, still_running): (Vec<_>, Vec<_>) =
+            pending.into_iter().partition(|(_, child)| child.is_finished());
+        for (_, child) in finished {
+            if let Err(e) = child.join() {
+                eprintln!("Error al esperar el hilo: {:?}", e);
+            }
+        }
+        pending = still_running;
+
+        if pending.is_empty() || Instant::now() >= deadline {
+            break;
+        }
+        thread::sleep(JOIN_POLL_INTERVAL.min(deadline.saturating_duration_since(Instant::now())));
+    }
+
+    for (position, child) in &pending {
+        eprintln!(
+            "El hilo {} ({:?}) no terminó luego de {:?}, se deja de esperarlo.",
+            position,
+            child.thread().id(),
+            timeout
+        );
+    }
+    pending.into_iter().map(|(position, _)| position).collect()
+}
+
 /// Función a llamar desde un hilo dedicado, para que app escuche si dicha app desea salir.
 /// Al recibir por el rx, se encarga de enviar disconnect de mqtt.
-pub fn exit_when_asked(mqtt_client: Arc<Mutex<MQTTClient>>, exit_rx: Receiver<bool>) {
+pub fn exit_when_asked<P: Publisher + ?Sized>(mqtt_client: Arc<Mutex<P>>, exit_rx: Receiver<bool>) {
     // Espero que otro hilo (ej la ui, ej el abm) me indique que se desea salir
     if let Ok(exit) = exit_rx.recv(){
         // Cuando eso ocurre, envío disconnect por mqtt
         if exit {
-            if let Ok(mut mqtt_locked) = mqtt_client.lock() {
-                match mqtt_locked.mqtt_disconnect() {
-                    Ok(_) => println!("Saliendo exitosamente."),
-                    Err(e) => println!("Error al salir: {:?}", e),
-                }
-            }    
+            disconnect(&mqtt_client);
+        }
+    }
+}
+
+/// Envía disconnect por mqtt, informando si se pudo salir exitosamente.
+pub fn disconnect<P: Publisher + ?Sized>(mqtt_client: &Arc<Mutex<P>>) {
+    if let Ok(mut mqtt_locked) = mqtt_client.lock() {
+        match mqtt_locked.disconnect() {
+            Ok(_) => println!("Saliendo exitosamente."),
+            Err(e) => println!("Error al salir: {:?}", e),
         }
     }
 }
@@ -82,4 +144,34 @@ pub fn exit_when_asked(mqtt_client: Arc<Mutex<MQTTClient>>, exit_rx: Receiver<bo
 pub fn there_are_no_more_publish_msgs(logger: &StringLogger) {
     println!("No hay más PublishMessage's por leer.");
     logger.log("No hay más PublishMessage's por leer.".to_string());
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::mpsc,
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use super::join_all_threads_with_timeout;
+
+    #[test]
+    fn test_1_join_con_timeout_no_queda_bloqueado_por_un_hilo_que_no_termina() {
+        let (_block_tx, block_rx) = mpsc::channel::<()>();
+        let children = vec![
+            thread::spawn(|| {}),
+            // Queda bloqueado mientras exista el tx
+            thread::spawn(move || {
+                let _ = block_rx.recv();
+            }),
+            thread::spawn(|| thread::sleep(Duration::from_millis(20))),
+        ];
+
+        let start = Instant::now();
+        let not_finished = join_all_threads_with_timeout(children, Duration::from_millis(300));
+
+        assert_eq!(not_finished, vec![1]);
+        assert!(start.elapsed() < Duration::from_secs(2));
+    }
+}
@@ -0,0 +1,234 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    net::SocketAddr,
+};
+
+use crate::{
+    apps::{properties::Properties, sist_camaras::topic_qos::TopicQos},
+    logging::string_logger_writer::default_log_file,
+    mqtt::mqtt_utils::socket_address::parse_socket_addr,
+};
+
+/// Qos de los topics a los que no se les indica uno, si tampoco se indica la propiedad `qos`.
+const DEFAULT_QOS: u8 = 0;
+
+/// Propiedades de la dirección del broker: las de los archivos de las apps, y las del cliente de ejemplo.
+const BROKER_IP_KEYS: [&str; 2] = ["ip-server-mqtt", "ip"];
+const BROKER_PORT_KEYS: [&str; 2] = ["port-server-mqtt", "port"];
+
+/// Configuración común a las apps, leída de uno o más archivos de properties. Contiene:
+/// - la dirección del broker (`ip-server-mqtt` y `port-server-mqtt`, o `ip` y `port`), opcional;
+/// - el qos de cada topic (`qos`, y `qos.<topic>` para uno en particular, ver `TopicQos`);
+/// - el archivo de log (`log-file`), opcional;
+/// - si se registran en el log los publish enviados, para reproducirlos (`record-publishes`), por defecto no;
+/// - si se solicitan al server los alias de topic (`topic-aliases`), por defecto no;
+/// - las secciones propias de cada app: las propiedades `<sección>.<clave>` (ver `get_section`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct AppConfig {
+    broker_addr: Option<SocketAddr>,
+    topic_qos: TopicQos,
+    log_file: Option<String>,
+    record_publishes: bool,
+    topic_aliases: bool,
+    properties: HashMap<String, String>,
+}
+
+impl AppConfig {
+    /// Carga la configuración de los archivos `properties_files`, en orden: si una propiedad está en más de
+    /// uno, vale la del último. Devuelve error si algún archivo no puede leerse, o si algún valor no es válido.
+    pub fn from_properties_files(properties_files: &[&str]) -> Result<Self, Error> {
+        let mut properties = HashMap::new();
+        for properties_file in properties_files {
+            for (key, value) in Properties::new(properties_file)?.iter() {
+                properties.insert(key.to_string(), value.to_string());
+            }
+        }
+        Self::from_properties(properties)
+    }
+
+    /// Análoga a `from_properties_files`, pero si la configuración no puede cargarse informa el error
+    /// y devuelve la configuración por defecto.
+    pub fn from_properties_files_or_default(properties_files: &[&str]) -> Self {
+        match Self::from_properties_files(properties_files) {
+            Ok(config) => config,
+            Err(e) => {
+                println!(
+                    "ERROR: no se pudo cargar la configuración de {:?}, se usa la configuración por defecto: {:?}",
+                    properties_files, e
+                );
+                Self::default()
+            }
+        }
+    }
+
+    fn from_properties(properties: HashMap<String, String>) -> Result<Self, Error> {
+        let broker_addr = parse_broker_addr(&properties)?;
+        let default_qos = match properties.get("qos") {
+            Some(qos) => TopicQos::parse_qos(qos)?,
+            None => DEFAULT_QOS,
+        };
+        let topic_qos = TopicQos::from_entries(default_qos, properties.iter())?;
+        let log_file = properties
+            .get("log-file")
+            .filter(|log_file| !log_file.is_empty())
+            .cloned();
+        let record_publishes = parse_bool_property(&properties, "record-publishes")?;
+        let topic_aliases = parse_bool_property(&properties, "topic-aliases")?;
+
+        Ok(Self {
+            broker_addr,
+            topic_qos,
+            log_file,
+            record_publishes,
+            topic_aliases,
+            properties,
+        })
+    }
+
+    /// Devuelve la dirección del broker, si se la configuró.
+    pub fn get_broker_addr(&self) -> Option<SocketAddr> {
+        self.broker_addr
+    }
+
+    /// Devuelve el qos de cada topic.
+    pub fn get_topic_qos(&self) -> &TopicQos {
+        &self.topic_qos
+    }
+
+    /// Devuelve el qos a utilizar para el `topic`.
+    pub fn get_qos_for(&self, topic: &str) -> u8 {
+        self.topic_qos.get_qos_for(topic)
+    }
+
+    /// Devuelve el archivo de log configurado, o el que usa por defecto la app de id `app_id`.
+    pub fn get_log_file(&self, app_id: &str) -> String {
+        match &self.log_file {
+            Some(log_file) => log_file.to_string(),
+            None => default_log_file(app_id),
+        }
+    }
+
+    /// Devuelve si se deben registrar en el log los publish enviados (ver `MQTTClient::set_publish_recording`).
+    pub fn get_record_publishes(&self) -> bool {
+        self.record_publishes
+    }
+
+    /// Devuelve si se deben solicitar al server los alias de topic
+    /// (ver `MQTTClient::mqtt_connect_to_broker_with_retransmission`).
+    pub fn get_topic_aliases(&self) -> bool {
+        self.topic_aliases
+    }
+
+    /// Devuelve el valor de la propiedad `key`.
+    pub fn get(&self, key: &str) -> Option<&String> {
+        self.properties.get(key)
+    }
+
+    /// Devuelve las propiedades de la sección `section`, es decir las `<section>.<clave>`, por clave.
+    pub fn get_section(&self, section: &str) -> HashMap<String, String> {
+        let prefix = format!("{}.", section);
+        self.properties
+            .iter()
+            .filter_map(|(key, value)| key.strip_prefix(&prefix).map(|key| (key.to_string(), value.to_string())))
+            .collect()
+    }
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            broker_addr: None,
+            topic_qos: TopicQos::new(DEFAULT_QOS),
+            log_file: None,
+            record_publishes: false,
+            topic_aliases: false,
+            properties: HashMap::new(),
+        }
+    }
+}
+
+/// Devuelve el valor de la propiedad booleana `key`, o false si no se la indicó; o error si no es true ni false.
+fn parse_bool_property(properties: &HashMap<String, String>, key: &str) -> Result<bool, Error> {
+    match properties.get(key).map(|value| value.parse::<bool>()) {
+        Some(Ok(value)) => Ok(value),
+        Some(Err(_)) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("Propiedad '{}' inválida: debe ser true o false.", key),
+        )),
+        None => Ok(false),
+    }
+}
+
+/// Devuelve la dirección del broker, si se indicaron su ip y su puerto; o error si alguno no es válido,
+/// o si se indicó solamente uno de ellos.
+fn parse_broker_addr(properties: &HashMap<String, String>) -> Result<Option<SocketAddr>, Error> {
+    let ip = BROKER_IP_KEYS.iter().find_map(|key| properties.get(*key));
+    let port = BROKER_PORT_KEYS.iter().find_map(|key| properties.get(*key));
+    match (ip, port) {
+        (Some(ip), Some(port)) => match port.trim_matches('"').parse::<u16>() {
+            Ok(port) => Ok(Some(parse_socket_addr(ip, port)?)),
+            Err(_) => Err(Error::new(
+                ErrorKind::InvalidInput,
+                "El puerto del broker no es válido",
+            )),
+        },
+        (None, None) => Ok(None),
+        _ => Err(Error::new(
+            ErrorKind::NotFound,
+            "Se indicó solamente la ip o solamente el puerto del broker",
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, net::SocketAddr};
+
+    use super::AppConfig;
+
+    fn write_properties(name: &str, content: &str) -> String {
+        let file = std::env::temp_dir().join(format!("test_app_config_{}.properties", name));
+        fs::write(&file, content).unwrap();
+        file.to_str().unwrap().to_string()
+    }
+
+    #[test]
+    fn test_1_se_combinan_los_archivos_de_una_app_en_una_configuracion_tipada() {
+        let app_file = write_properties(
+            "1_app",
+            "ip-server-mqtt=127.0.0.1\nport-server-mqtt=9090\nlog-file=camaras.log\nframes.capture-mode=watch\nqos=0\nrecord-publishes=true",
+        );
+        let qos_file = write_properties("1_qos", "qos=1\nqos.cam=0");
+
+        let config = AppConfig::from_properties_files(&[&app_file, &qos_file]);
+        let _ = (fs::remove_file(&app_file), fs::remove_file(&qos_file));
+        let config = config.unwrap();
+
+        assert_eq!(config.get_broker_addr(), Some("127.0.0.1:9090".parse::<SocketAddr>().unwrap()));
+        // El qos del segundo archivo reemplaza al del primero
+        assert_eq!(config.get_qos_for("inc"), 1);
+        assert_eq!(config.get_qos_for("cam"), 0);
+        assert_eq!(config.get_log_file("Sistema-Camaras"), "camaras.log");
+        assert_eq!(config.get_section("frames").get("capture-mode").unwrap(), "watch");
+        assert!(config.get_record_publishes());
+    }
+
+    #[test]
+    fn test_2_los_valores_invalidos_dan_error_y_los_ausentes_toman_su_valor_por_defecto() {
+        for (name, content) in [("2a", "qos=5"), ("2b", "ip=127.0.0.1"), ("2c", "ip=localhost\nport=9090"), ("2e", "record-publishes=si")] {
+            let file = write_properties(name, content);
+            assert!(AppConfig::from_properties_files(&[&file]).is_err());
+            let _ = fs::remove_file(&file);
+        }
+
+        let file = write_properties("2d", "otra=1");
+        let config = AppConfig::from_properties_files(&[&file]).unwrap();
+        let _ = fs::remove_file(&file);
+        assert_eq!(config.get_broker_addr(), None);
+        assert_eq!(config.get_qos_for("inc"), 0);
+        assert_eq!(config.get_log_file("Sistema-Monitoreo"), "s_log_Sistema-Monitoreo.txt");
+        assert!(!config.get_record_publishes());
+        assert!(!config.get_topic_aliases());
+    }
+}
@@ -0,0 +1,26 @@
+use std::io::{Error, ErrorKind};
+
+/// Verifica que la latitud y la longitud sean números finitos. Un NaN o un infinito (ej. al normalizar
+/// una dirección de longitud cero) se propagaría silenciosamente a los cálculos de distancia y al mapa.
+pub fn check_finite_coordinates(latitude: f64, longitude: f64) -> Result<(), Error> {
+    if latitude.is_finite() && longitude.is_finite() {
+        return Ok(());
+    }
+    Err(Error::new(
+        ErrorKind::InvalidData,
+        format!("Coordenadas no válidas: ({}, {})", latitude, longitude),
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::check_finite_coordinates;
+
+    #[test]
+    fn test_1_solamente_se_aceptan_coordenadas_finitas() {
+        assert!(check_finite_coordinates(-34.6, -58.4).is_ok());
+        assert!(check_finite_coordinates(f64::NAN, -58.4).is_err());
+        assert!(check_finite_coordinates(-34.6, f64::INFINITY).is_err());
+        assert!(check_finite_coordinates(f64::NEG_INFINITY, f64::NAN).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::{
+    io::{Error, ErrorKind},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    logging::string_logger::StringLogger,
+    mqtt::{
+        client::publisher::Publisher,
+        mqtt_utils::{
+            shutdown_token::ShutdownToken,
+            topic::Topic,
+            will_message_utils::{app_type::AppType, will_content::WillContent},
+        },
+    },
+};
+
+use super::common_clients::get_app_will_topic;
+
+/// Cada cuánto publican las apps su heartbeat, si no se indica otro intervalo.
+pub const DEFAULT_HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Prefijo que distingue a un heartbeat de un will, ya que ambos se publican al mismo topic.
+const HEARTBEAT_PREFIX: &str = "heartbeat-";
+
+/// Mensaje que cada app publica periódicamente al topic `desc`, para indicar que sigue funcionando.
+/// A diferencia del will, que se publica solamente al desconectarse, permite a sistema monitoreo detectar
+/// una app que sigue conectada pero dejó de responder (ej. un hilo bloqueado esperando un lock).
+#[derive(Debug, PartialEq)]
+pub struct Heartbeat {
+    app: WillContent,
+    timestamp: u64,
+}
+
+impl Heartbeat {
+    /// Crea el heartbeat de la app de tipo `app_type` (y de id `id`, solamente para los drones),
+    /// con la hora actual en segundos.
+    pub fn new(app_type: AppType, id: Option<u8>) -> Self {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|since_epoch| since_epoch.as_secs())
+            .unwrap_or(0);
+        Self {
+            app: WillContent::new(app_type, id),
+            timestamp,
+        }
+    }
+
+    pub fn get_app_type(&self) -> AppType {
+        self.app.get_app_type_identifier()
+    }
+
+    pub fn get_id(&self) -> Option<u8> {
+        self.app.get_id()
+    }
+
+    /// Devuelve el instante en que se creó el heartbeat, en segundos desde epoch.
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn to_str(&self) -> String {
+        format!("{}{}-{}", HEARTBEAT_PREFIX, self.app.to_str(), self.timestamp)
+    }
+
+    /// Obtiene el heartbeat a partir del string recibido. Devuelve error si no se trata de un heartbeat
+    /// (ej. si es un will).
+    pub fn heartbeat_from_string(string: &str) -> Result<Self, Error> {
+        let error = || Error::new(ErrorKind::InvalidData, "Error al decodear Heartbeat.");
+        let without_prefix = string.trim().strip_prefix(HEARTBEAT_PREFIX).ok_or_else(error)?;
+        let (app_string, timestamp_string) = without_prefix.rsplit_once('-').ok_or_else(error)?;
+        let timestamp = timestamp_string.parse::<u64>().map_err(|_| error())?;
+        let app = WillContent::will_content_from_string(app_string)?;
+
+        Ok(Self { app, timestamp })
+    }
+}
+
+/// Lanza un hilo que publica, cada `interval`, el heartbeat de la app de tipo `app_type` (e `id`, para los drones)
+/// al topic de las apps (`desc`). Termina al solicitarse el `shutdown_token`.
+pub fn spawn_heartbeat_thread<P: Publisher + 'static>(
+    publisher: Arc<Mutex<P>>,
+    app_type: AppType,
+    id: Option<u8>,
+    interval: Duration,
+    shutdown_token: ShutdownToken,
+    logger: StringLogger,
+) -> JoinHandle<()> {
+    thread::spawn(move || loop {
+        let heartbeat = Heartbeat::new(app_type, id);
+        if let Ok(mut publisher_lock) = publisher.lock() {
+            // Con qos 0, ya que si se pierde uno, alcanza con el siguiente.
+            if let Err(e) = publisher_lock.publish(
+                &Topic::from(get_app_will_topic()),
+                heartbeat.to_str().as_bytes(),
+                0,
+            ) {
+                logger.log(format!("Error al publicar el heartbeat: {:?}.", e));
+            }
+        }
+        if shutdown_token.sleep_unless_requested(interval) {
+            break;
+        }
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::{spawn_heartbeat_thread, Heartbeat};
+    use crate::{
+        apps::apps_mqtt_topics::AppsMqttTopics,
+        logging::string_logger::StringLogger,
+        mqtt::{
+            client::publisher::NullPublisher,
+            mqtt_utils::{
+                shutdown_token::ShutdownToken,
+                will_message_utils::{app_type::AppType, will_content::WillContent},
+            },
+        },
+    };
+
+    #[test]
+    fn test_1_heartbeat_to_and_from_string_y_no_se_confunde_con_un_will() {
+        let heartbeat = Heartbeat::new(AppType::Dron, Some(3));
+        let parsed = Heartbeat::heartbeat_from_string(&heartbeat.to_str()).unwrap();
+        assert_eq!(parsed, heartbeat);
+        assert_eq!(parsed.get_id(), Some(3));
+
+        let will = WillContent::new(AppType::Dron, Some(3));
+        assert!(Heartbeat::heartbeat_from_string(&will.to_str()).is_err());
+        assert!(WillContent::will_content_from_string(&heartbeat.to_str()).is_err());
+    }
+
+    #[test]
+    fn test_2_el_hilo_publica_heartbeats_hasta_que_se_solicita_terminar() {
+        let publisher = NullPublisher::new();
+        let token = ShutdownToken::new();
+        let (logger_tx, _logger_rx) = std::sync::mpsc::channel();
+        let handle = spawn_heartbeat_thread(
+            Arc::new(Mutex::new(publisher.clone())),
+            AppType::Cameras,
+            None,
+            Duration::from_millis(50),
+            token.clone(),
+            StringLogger::new(logger_tx),
+        );
+
+        std::thread::sleep(Duration::from_millis(180));
+        token.request();
+        handle.join().unwrap();
+
+        let published = publisher.get_published_to(AppsMqttTopics::DescTopic.to_str());
+        assert!(published.len() >= 2);
+        let heartbeat = Heartbeat::heartbeat_from_string(&String::from_utf8(published[0].clone()).unwrap()).unwrap();
+        assert_eq!(heartbeat.get_app_type(), AppType::Cameras);
+    }
+}
@@ -1,20 +1,51 @@
-use std::io::Error;
+use std::{
+    cmp::Ordering,
+    io::{Error, ErrorKind},
+};
+
+use crate::apps::{vendor::Position, wire_version::split_version};
 
 use super::incident_info::IncidentInfo;
 use super::incident_state::IncidentState;
+use super::incident_status::IncidentStatus;
 use super::incident_source::IncidentSource;
 
-#[derive(Debug, Clone)]
+use serde::Serialize;
+
+#[derive(Debug, Clone, Serialize)]
 /// Struct que representa un incidente, para ser utilizado por las aplicaciones del sistema de vigilancia (sist de monitoreo, sist central de cámaras, y app de drones).
-/// Posee un id, coordenadas x e y, un estado.
+/// Posee un id, coordenadas x e y, un estado, el status de su ciclo de vida, y el monitor dueño del incidente.
+///
+/// Si se ejecuta más de un Sistema Monitoreo, solamente el dueño de un incidente se encarga de resolverlo
+/// (asignar drones, darlo por resuelto o vencido), para que los monitores no se pisen. Un incidente manual es
+/// del monitor que lo creó; uno automático se crea sin dueño, y es del monitor líder (`LEADER_MONITOR_ID`).
 pub struct Incident {
     id: u8, // []
     latitude: f64,
     longitude: f64,
     state: IncidentState,
     source: IncidentSource,
+    status: IncidentStatus,
+    owner: Option<u8>,
 }
 
+/// Versión del formato de los bytes del incidente (ver `split_version`): la 1 termina antes del byte de status,
+/// la 2 lo agrega, y la 3 agrega el byte del monitor dueño.
+const INCIDENT_WIRE_VERSION: u8 = 3;
+
+/// Posición del byte de status en los campos del incidente, a partir de la versión 2.
+const STATUS_BYTE_INDEX: usize = 19;
+
+/// Posición del byte del monitor dueño en los campos del incidente, a partir de la versión 3.
+const OWNER_BYTE_INDEX: usize = 20;
+
+/// Valor del byte del monitor dueño, para un incidente sin dueño. Los ids de monitor comienzan en 1.
+const NO_OWNER: u8 = 0;
+
+/// Id del monitor líder, dueño de los incidentes que no tienen dueño (los automáticos). Con un único
+/// Sistema Monitoreo, éste utiliza el id del líder y se encarga de todos los incidentes.
+pub const LEADER_MONITOR_ID: u8 = 1;
+
 impl Incident {
     pub fn new(id: u8, location: (f64, f64), source: IncidentSource) -> Self {
         Self {
@@ -23,30 +54,102 @@ impl Incident {
             longitude: location.1,
             state: IncidentState::ActiveIncident,
             source,
+            status: IncidentStatus::Open,
+            owner: None,
         }
     }
 
+    /// Devuelve el id del monitor dueño del incidente, o None si no tiene dueño.
+    pub fn get_owner(&self) -> Option<u8> {
+        self.owner
+    }
+
+    /// Establece como dueño del incidente al monitor `monitor_id`.
+    pub fn set_owner(&mut self, monitor_id: u8) {
+        self.owner = Some(monitor_id);
+    }
+
+    /// Devuelve si el monitor `monitor_id` es el encargado de resolver el incidente: su dueño,
+    /// o el líder si el incidente no tiene dueño.
+    pub fn is_owned_by(&self, monitor_id: u8) -> bool {
+        self.owner.unwrap_or(LEADER_MONITOR_ID) == monitor_id
+    }
+
     /// Devuelve coordenadas (x, y) correspondientes a la posición del incidente.
     pub fn get_position(&self) -> (f64, f64) {
         (self.latitude, self.longitude)
     }
 
-    /// Devuelve si el incidente tiene estado resuelto o no.
+    /// Devuelve la posición del incidente, para operar con ella (ej. calcular distancias).
+    pub fn position(&self) -> Position {
+        Position::from_lat_lon(self.latitude, self.longitude)
+    }
+
+    /// Devuelve la distancia en metros desde el incidente hasta `pos`.
+    pub fn distance_to(&self, pos: Position) -> f64 {
+        self.position().distance_meters(&pos)
+    }
+
+    /// Devuelve si el incidente fue resuelto. Un incidente cancelado no se considera resuelto.
     pub fn is_resolved(&self) -> bool {
+        self.state == IncidentState::ResolvedIncident && self.status != IncidentStatus::Cancelled
+    }
+
+    /// Devuelve si el incidente fue cancelado (retirado sin resolverse).
+    pub fn is_cancelled(&self) -> bool {
+        self.status == IncidentStatus::Cancelled
+    }
+
+    /// Devuelve si el incidente ya no está activo, por haberse resuelto o cancelado.
+    pub fn is_closed(&self) -> bool {
         self.state == IncidentState::ResolvedIncident
     }
 
-    /// Cambia el estado del incidente a resuelto.
+    /// Cambia el estado del incidente a resuelto, sin importar en qué status se encontraba.
     pub fn set_resolved(&mut self) {
         self.state = IncidentState::ResolvedIncident;
+        self.status = IncidentStatus::Resolved;
+    }
+
+    /// Cancela el incidente, sin importar en qué status se encontraba. Deja de estar activo como uno resuelto,
+    /// pero se distingue de él por su status.
+    pub fn set_cancelled(&mut self) {
+        self.state = IncidentState::ResolvedIncident;
+        self.status = IncidentStatus::Cancelled;
+    }
+
+    /// Avanza el ciclo de vida del incidente al status `next`. Devuelve error si la transición no es válida
+    /// (ej. de `Resolved` a `Open`). Al pasar a `Resolved` o `Cancelled`, el incidente deja también de estar activo.
+    pub fn transition_to(&mut self, next: IncidentStatus) -> Result<(), Error> {
+        if !self.status.can_transition_to(next) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transición de incidente no válida: de {:?} a {:?}",
+                    self.status, next
+                ),
+            ));
+        }
+        self.status = next;
+        if next == IncidentStatus::Resolved || next == IncidentStatus::Cancelled {
+            self.state = IncidentState::ResolvedIncident;
+        }
+        Ok(())
+    }
+
+    /// Devuelve el status del ciclo de vida del incidente.
+    pub fn get_status(&self) -> IncidentStatus {
+        self.status
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = vec![self.id];
+        let mut bytes = vec![INCIDENT_WIRE_VERSION, self.id];
         bytes.extend_from_slice(&self.latitude.to_le_bytes());
         bytes.extend_from_slice(&self.longitude.to_le_bytes());
         bytes.push(self.state.to_byte()[0]);
         bytes.push(self.source.to_byte()[0]);
+        bytes.push(self.status.to_byte()[0]);
+        bytes.push(self.owner.unwrap_or(NO_OWNER));
         bytes
     }
 
@@ -58,7 +161,22 @@ impl Incident {
         IncidentInfo::new(self.id, self.source)
     }
 
+    /// Lee un incidente de bytes de cualquier versión: los campos posteriores a los que se conocen se ignoran,
+    /// y los que no trae una versión anterior toman su valor por defecto.
     pub fn from_bytes(msg_bytes: Vec<u8>) -> Result<Self, Error> {
+        let (version, msg_bytes) = split_version(&msg_bytes, "Incident")?;
+        // Los campos que trae la versión son obligatorios
+        let expected_len = match version {
+            1 => STATUS_BYTE_INDEX,
+            2 => OWNER_BYTE_INDEX,
+            _ => OWNER_BYTE_INDEX + 1,
+        };
+        if msg_bytes.len() < expected_len {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "No hay suficientes bytes para un Incident",
+            ));
+        }
         let id = msg_bytes[0];
         let latitude = f64::from_le_bytes([
             msg_bytes[1],
@@ -85,12 +203,27 @@ impl Incident {
 
         let source = IncidentSource::from_byte([msg_bytes[18]])?;
 
+        // Si la versión no trae el byte de status, se lo deduce a partir del estado.
+        let status = match version {
+            1 if state == IncidentState::ResolvedIncident => IncidentStatus::Resolved,
+            1 => IncidentStatus::Open,
+            _ => IncidentStatus::from_byte([msg_bytes[STATUS_BYTE_INDEX]])?,
+        };
+
+        // Si la versión no trae el byte del dueño, el incidente no tiene dueño.
+        let owner = match version {
+            1 | 2 => None,
+            _ => Some(msg_bytes[OWNER_BYTE_INDEX]).filter(|owner| *owner != NO_OWNER),
+        };
+
         Ok(Self {
             id,
             latitude,
             longitude,
             state,
             source,
+            status,
+            owner,
         })
     }
 
@@ -103,11 +236,34 @@ impl Incident {
         &self.source
     }
 }
+
+/// Devuelve el incidente de `incidents` más cercano a `pos`, o None si no hay incidentes.
+/// Ante igual distancia se desempata como al despachar incidentes (ver `cmp_incidents_by_distance`).
+pub fn nearest_incident(pos: Position, incidents: &[Incident]) -> Option<&Incident> {
+    incidents
+        .iter()
+        .enumerate()
+        .min_by(|(a_idx, a), (b_idx, b)| {
+            cmp_incidents_by_distance(&(a.distance_to(pos), a.id, *a_idx), &(b.distance_to(pos), b.id, *b_idx))
+        })
+        .map(|(_, incident)| incident)
+}
+
+/// Criterio con que se elige el incidente más cercano. Compara dos candidatos, dados como (distancia, id del
+/// incidente, orden de llegada): es menor el más cercano; ante igual distancia, el de menor id; y ante igual id
+/// (de distinto origen), el que llegó antes, es decir el creado antes. Así la elección no depende del orden en
+/// que se recorren los candidatos.
+pub fn cmp_incidents_by_distance(a: &(f64, u8, usize), b: &(f64, u8, usize)) -> Ordering {
+    a.0.total_cmp(&b.0)
+        .then(a.1.cmp(&b.1))
+        .then(a.2.cmp(&b.2))
+}
 // hacer test de los metodos from_bytes y to_bytes
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::apps::places;
 
     #[test]
     fn test_reverse_to_bytes() {
@@ -117,6 +273,8 @@ mod tests {
             longitude: 2.0,
             state: IncidentState::ActiveIncident,
             source: IncidentSource::Manual,
+            status: IncidentStatus::Assigned,
+            owner: Some(2),
         };
         let bytes = incident.to_bytes();
         let incident_bytes = Incident::from_bytes(bytes).unwrap();
@@ -124,6 +282,108 @@ mod tests {
         assert_eq!(incident_bytes.latitude, incident.latitude);
         assert_eq!(incident_bytes.longitude, incident.longitude);
         assert_eq!(incident_bytes.state, incident.state);
+        assert_eq!(incident_bytes.status, incident.status);
+        assert_eq!(incident_bytes.owner, incident.owner);
     }
-}
 
+    #[test]
+    fn test_el_incidente_es_de_su_duenio_o_si_no_tiene_del_lider() {
+        let mut incident = Incident::new(1, (2.0, 2.0), IncidentSource::Automated);
+        assert!(incident.is_owned_by(LEADER_MONITOR_ID));
+        assert!(!incident.is_owned_by(2));
+
+        incident.set_owner(2);
+        let received = Incident::from_bytes(incident.to_bytes()).unwrap();
+        assert_eq!(received.get_owner(), Some(2));
+        assert!(received.is_owned_by(2));
+        assert!(!received.is_owned_by(LEADER_MONITOR_ID));
+
+        // Un payload de la versión 2, sin el byte del dueño
+        assert_eq!(Incident::from_bytes(bytes_of_version(&incident, 2)).unwrap().get_owner(), None);
+    }
+
+    /// Devuelve los bytes del incidente en la `version` anterior indicada, es decir sin los campos posteriores.
+    fn bytes_of_version(incident: &Incident, version: u8) -> Vec<u8> {
+        let mut bytes = incident.to_bytes();
+        bytes[0] = version;
+        let fields_len = if version == 1 { STATUS_BYTE_INDEX } else { OWNER_BYTE_INDEX };
+        bytes.truncate(1 + fields_len);
+        bytes
+    }
+
+    #[test]
+    fn test_from_bytes_sin_byte_de_status_lo_deduce_del_estado() {
+        let mut incident = Incident::new(1, (2.0, 2.0), IncidentSource::Manual);
+        let bytes = bytes_of_version(&incident, 1);
+        assert_eq!(Incident::from_bytes(bytes).unwrap().get_status(), IncidentStatus::Open);
+
+        incident.set_resolved();
+        let bytes = bytes_of_version(&incident, 1);
+        assert_eq!(Incident::from_bytes(bytes).unwrap().get_status(), IncidentStatus::Resolved);
+    }
+
+    #[test]
+    fn test_from_bytes_de_una_version_mas_nueva_ignora_los_campos_desconocidos() {
+        let mut incident = Incident::new(4, (-34.6, -58.4), IncidentSource::Drone);
+        incident.set_owner(3);
+        let mut bytes = incident.to_bytes();
+        bytes[0] = INCIDENT_WIRE_VERSION + 1;
+        bytes.extend_from_slice(&[9, 9, 9]);
+
+        let received = Incident::from_bytes(bytes).unwrap();
+        assert_eq!(received.get_info(), incident.get_info());
+        assert_eq!(received.get_owner(), Some(3));
+        assert_eq!(received.to_bytes(), incident.to_bytes());
+
+        // Una versión que dice traer el dueño pero no lo trae está mal formada
+        let mut bytes = incident.to_bytes();
+        bytes.pop();
+        assert!(Incident::from_bytes(bytes).is_err());
+    }
+
+    #[test]
+    fn test_transition_to_rechaza_transiciones_invalidas() {
+        let mut incident = Incident::new(1, (2.0, 2.0), IncidentSource::Automated);
+        assert!(incident.transition_to(IncidentStatus::Resolving).is_err());
+        assert!(incident.transition_to(IncidentStatus::Assigned).is_ok());
+        assert!(incident.transition_to(IncidentStatus::Resolving).is_ok());
+        assert!(!incident.is_resolved());
+        assert!(incident.transition_to(IncidentStatus::Resolved).is_ok());
+        assert!(incident.is_resolved());
+        assert!(incident.transition_to(IncidentStatus::Open).is_err());
+    }
+
+    #[test]
+    fn test_incidente_cancelado_no_esta_activo_pero_no_se_considera_resuelto() {
+        let mut incident = Incident::new(1, (2.0, 2.0), IncidentSource::Manual);
+        incident.set_cancelled();
+
+        let received = Incident::from_bytes(incident.to_bytes()).unwrap();
+        assert!(received.is_closed());
+        assert!(received.is_cancelled());
+        assert!(!received.is_resolved());
+        assert_eq!(*received.get_state(), IncidentState::ResolvedIncident);
+    }
+
+    #[test]
+    fn test_distancia_del_incidente_a_una_posicion() {
+        let incident = Incident::new(1, (-34.6037344, -58.3861838), IncidentSource::Manual);
+        assert_eq!(incident.position(), places::obelisco());
+        assert_eq!(incident.distance_to(places::obelisco()), 0.0);
+
+        // Unos 420 metros, hasta el lugar de mantenimiento
+        let distance = incident.distance_to(places::mantenimiento());
+        assert!((distance - 420.0).abs() < 5.0);
+    }
+
+    #[test]
+    fn test_nearest_incident_devuelve_el_incidente_mas_cercano() {
+        let far = Incident::new(1, (-34.6128, -58.3819), IncidentSource::Manual);
+        let near = Incident::new(2, (-34.6040, -58.3860), IncidentSource::Automated);
+        let incidents = vec![far, near];
+
+        let nearest = nearest_incident(places::obelisco(), &incidents).unwrap();
+        assert_eq!(nearest.get_id(), 2);
+        assert!(nearest_incident(places::obelisco(), &[]).is_none());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::HashMap;
+
+use super::{incident_info::IncidentInfo, incident_source::IncidentSource};
+
+/// Asigna ids a los incidentes, con una numeración independiente por cada `IncidentSource`.
+/// Así, un id se repite entre incidentes de distinto origen (ej. el inc 1 Manual y el inc 1 Automated),
+/// pero como se los identifica por su `IncidentInfo` (id y source), no se pisan.
+#[derive(Debug, Default)]
+pub struct IncidentIdAllocator {
+    last_ids: HashMap<IncidentSource, u8>,
+}
+
+impl IncidentIdAllocator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Devuelve el `IncidentInfo` del próximo incidente del origen `source`.
+    /// El id 0 se reserva para indicar "sin incidente" (ver `IncidentInfo::from_bytes`), por lo que se lo saltea.
+    pub fn next_info(&mut self, source: IncidentSource) -> IncidentInfo {
+        let last = self.last_ids.entry(source).or_insert(0);
+        *last = last.wrapping_add(1);
+        if *last == 0 {
+            *last = 1;
+        }
+        IncidentInfo::new(*last, source)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+
+    use super::IncidentIdAllocator;
+    use crate::apps::incident_data::{incident::Incident, incident_source::IncidentSource};
+
+    #[test]
+    fn test_1_la_numeracion_es_independiente_por_origen() {
+        let mut allocator = IncidentIdAllocator::new();
+        assert_eq!(allocator.next_info(IncidentSource::Manual).get_inc_id(), 1);
+        assert_eq!(allocator.next_info(IncidentSource::Automated).get_inc_id(), 1);
+        assert_eq!(allocator.next_info(IncidentSource::Manual).get_inc_id(), 2);
+    }
+
+    #[test]
+    fn test_2_incidentes_de_igual_id_y_distinto_origen_coexisten() {
+        let mut allocator = IncidentIdAllocator::new();
+        let manual_info = allocator.next_info(IncidentSource::Manual);
+        let camera_info = allocator.next_info(IncidentSource::Automated);
+        let manual = Incident::new(manual_info.get_inc_id(), (1.0, 1.0), IncidentSource::Manual);
+        let camera = Incident::new(camera_info.get_inc_id(), (2.0, 2.0), IncidentSource::Automated);
+        assert_eq!(manual.get_id(), camera.get_id());
+
+        let mut incidents = HashMap::new();
+        incidents.insert(manual.get_info(), manual);
+        incidents.insert(camera.get_info(), camera);
+
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[&manual_info].get_position(), (1.0, 1.0));
+        assert_eq!(incidents[&camera_info].get_position(), (2.0, 2.0));
+    }
+
+    #[test]
+    fn test_3_al_dar_la_vuelta_se_saltea_el_id_0() {
+        let mut allocator = IncidentIdAllocator::new();
+        for _ in 0..u8::MAX {
+            allocator.next_info(IncidentSource::Manual);
+        }
+        assert_eq!(allocator.next_info(IncidentSource::Manual).get_inc_id(), 1);
+    }
+}
@@ -1,9 +1,11 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 
 use super::incident_source::IncidentSource;
 
+use serde::Serialize;
+
 /// Este struct se utiliza como clave en hashmaps para identificar a un Incident.
-#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy, Serialize)]
 pub struct IncidentInfo {
     inc_id: u8,
     src: IncidentSource,
@@ -29,6 +31,12 @@ impl IncidentInfo {
 
     /// Obtiene un struct `IncidentSource` a partir de bytes.
     pub fn from_bytes(bytes: Vec<u8>) -> Result<Option<Self>, Error> {
+        if bytes.len() < 2 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "No hay suficientes bytes para un IncidentInfo",
+            ));
+        }
         let inc_id = u8::from_be_bytes([bytes[0]]);
         if inc_id == 0 {
             return Ok(None);
@@ -55,4 +63,22 @@ mod test {
         
         assert_eq!(inc_info, IncidentInfo::from_bytes(inc_info.to_bytes()).unwrap().unwrap());
     }
+
+    #[test]
+    fn test_2_los_incident_info_de_distinto_origen_con_el_mismo_id_son_distintos() {
+        let infos = [
+            IncidentInfo::new(3, IncidentSource::Manual),
+            IncidentInfo::new(3, IncidentSource::Automated),
+            IncidentInfo::new(3, IncidentSource::Drone),
+        ];
+        let unique: std::collections::HashSet<IncidentInfo> = infos.iter().copied().collect();
+        assert_eq!(unique.len(), 3);
+        for inc_info in infos {
+            assert_eq!(IncidentInfo::from_bytes(inc_info.to_bytes()).unwrap(), Some(inc_info));
+        }
+
+        // Bytes incompletos o con un origen desconocido dan error, sin entrar en pánico
+        assert!(IncidentInfo::from_bytes(vec![3]).is_err());
+        assert!(IncidentInfo::from_bytes(vec![3, 9]).is_err());
+    }
 }
\ No newline at end of file
@@ -1,12 +1,16 @@
 use std::io::{Error, ErrorKind};
 
+use serde::Serialize;
+
 /// Representa el origen en el que se generó el incidente:
 /// puede ser `Manual`, si fue generado manualmente desde la ui de sistema de monitoreo;
-/// o `Automated` si se generó automáticamente mediante inteligencia artificial en sistema cámaras.
-#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq)]
+/// `Automated` si se generó automáticamente mediante inteligencia artificial en sistema cámaras;
+/// o `Drone` si lo reportó un dron en el terreno (ej. al detectar algo en su recorrido).
+#[derive(Debug, PartialEq, Clone, Copy, Hash, Eq, Serialize)]
 pub enum IncidentSource {
     Manual,
     Automated,
+    Drone,
 }
 
 impl IncidentSource {
@@ -14,13 +18,21 @@ impl IncidentSource {
         match self {
             IncidentSource::Manual => 1_u8.to_be_bytes(),
             IncidentSource::Automated => 2_u8.to_be_bytes(),
+            IncidentSource::Drone => 3_u8.to_be_bytes(),
         }
     }
 
+    /// Devuelve si el incidente lo creó el operador desde la ui de sistema de monitoreo. Los demás los reportan
+    /// otras apps (cámaras o drones), y sistema monitoreo los recibe por MQTT.
+    pub fn is_created_by_monitoreo(&self) -> bool {
+        *self == IncidentSource::Manual
+    }
+
     pub fn from_byte(byte: [u8; 1]) -> Result<Self, Error> {
         match u8::from_be_bytes(byte) {
             1 => Ok(IncidentSource::Manual),
             2 => Ok(IncidentSource::Automated),
+            3 => Ok(IncidentSource::Drone),
             _ => Err(Error::new(
                 ErrorKind::Other,
                 "Origen de incidente no válido",
@@ -42,5 +54,13 @@ mod test {
         // Variante Automated pasada a bytes y reconstruida es igual a la original
         let src_a = IncidentSource::Automated;
         assert_eq!(src_a, IncidentSource::from_byte(src_a.to_byte()).unwrap());
+
+        // Variante Drone pasada a bytes y reconstruida es igual a la original
+        let src_d = IncidentSource::Drone;
+        assert_eq!(src_d, IncidentSource::from_byte(src_d.to_byte()).unwrap());
+
+        // Un valor desconocido da error, sin entrar en pánico
+        assert!(IncidentSource::from_byte([0]).is_err());
+        assert!(IncidentSource::from_byte([4]).is_err());
     }
 }
\ No newline at end of file
@@ -1,6 +1,8 @@
 use std::io::{Error, ErrorKind};
 
-#[derive(Debug, PartialEq, Clone)]
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub enum IncidentState {
     ActiveIncident,
     ResolvedIncident,
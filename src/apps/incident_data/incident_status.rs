@@ -0,0 +1,92 @@
+use std::io::Error;
+
+use serde::Serialize;
+
+/// Etapa del ciclo de vida de un incidente, compartida por cámaras y monitoreo:
+/// `Open` al crearse, `Assigned` cuando un dron acepta ir a atenderlo,
+/// `Resolving` cuando llega el primero de ellos, y `Resolved` al resolverse (ej. al llegar los drones necesarios). Si se lo retira sin resolverse
+/// (ej. un incidente manual creado por error), pasa a `Cancelled`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Serialize)]
+pub enum IncidentStatus {
+    Open,
+    Assigned,
+    Resolving,
+    Resolved,
+    Cancelled,
+}
+
+impl IncidentStatus {
+    pub fn to_byte(&self) -> [u8; 1] {
+        match self {
+            IncidentStatus::Open => 1_u8.to_be_bytes(),
+            IncidentStatus::Assigned => 2_u8.to_be_bytes(),
+            IncidentStatus::Resolving => 3_u8.to_be_bytes(),
+            IncidentStatus::Resolved => 4_u8.to_be_bytes(),
+            IncidentStatus::Cancelled => 5_u8.to_be_bytes(),
+        }
+    }
+
+    pub fn from_byte(byte: [u8; 1]) -> Result<Self, Error> {
+        match u8::from_be_bytes(byte) {
+            1 => Ok(IncidentStatus::Open),
+            2 => Ok(IncidentStatus::Assigned),
+            3 => Ok(IncidentStatus::Resolving),
+            4 => Ok(IncidentStatus::Resolved),
+            5 => Ok(IncidentStatus::Cancelled),
+            _ => Err(Error::other("Status de incidente no válido")),
+        }
+    }
+
+    /// Devuelve si se puede pasar de este status a `next`: solamente se avanza de a una etapa,
+    /// salvo pasar directo a `Resolved` (ej. si vence o se resuelve manualmente) o a `Cancelled`, que se permite
+    /// desde cualquier status no final. Nunca se sale de `Resolved` ni de `Cancelled`.
+    pub fn can_transition_to(&self, next: IncidentStatus) -> bool {
+        matches!(
+            (self, next),
+            (IncidentStatus::Open, IncidentStatus::Assigned)
+                | (IncidentStatus::Assigned, IncidentStatus::Resolving)
+                | (IncidentStatus::Open, IncidentStatus::Resolved)
+                | (IncidentStatus::Assigned, IncidentStatus::Resolved)
+                | (IncidentStatus::Resolving, IncidentStatus::Resolved)
+                | (IncidentStatus::Open, IncidentStatus::Cancelled)
+                | (IncidentStatus::Assigned, IncidentStatus::Cancelled)
+                | (IncidentStatus::Resolving, IncidentStatus::Cancelled)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::IncidentStatus;
+
+    #[test]
+    fn test_1_incident_status_to_and_from_bytes_works() {
+        for status in [
+            IncidentStatus::Open,
+            IncidentStatus::Assigned,
+            IncidentStatus::Resolving,
+            IncidentStatus::Resolved,
+            IncidentStatus::Cancelled,
+        ] {
+            assert_eq!(status, IncidentStatus::from_byte(status.to_byte()).unwrap());
+        }
+        assert!(IncidentStatus::from_byte([0]).is_err());
+    }
+
+    #[test]
+    fn test_2_solo_se_permiten_las_transiciones_del_ciclo_de_vida() {
+        assert!(IncidentStatus::Open.can_transition_to(IncidentStatus::Assigned));
+        assert!(IncidentStatus::Assigned.can_transition_to(IncidentStatus::Resolving));
+        assert!(IncidentStatus::Resolving.can_transition_to(IncidentStatus::Resolved));
+        assert!(IncidentStatus::Open.can_transition_to(IncidentStatus::Resolved));
+
+        assert!(!IncidentStatus::Resolved.can_transition_to(IncidentStatus::Open));
+        assert!(!IncidentStatus::Resolving.can_transition_to(IncidentStatus::Assigned));
+        assert!(!IncidentStatus::Open.can_transition_to(IncidentStatus::Resolving));
+        assert!(!IncidentStatus::Resolved.can_transition_to(IncidentStatus::Resolved));
+
+        assert!(IncidentStatus::Assigned.can_transition_to(IncidentStatus::Cancelled));
+        assert!(!IncidentStatus::Resolved.can_transition_to(IncidentStatus::Cancelled));
+        assert!(!IncidentStatus::Cancelled.can_transition_to(IncidentStatus::Open));
+    }
+}
@@ -1,4 +1,6 @@
 pub mod incident;
 pub mod incident_state;
+pub mod incident_status;
 pub mod incident_source;
-pub mod incident_info;
\ No newline at end of file
+pub mod incident_info;
+pub mod incident_id_allocator;
\ No newline at end of file
@@ -1,14 +1,20 @@
+pub mod app_message;
 pub mod apps_mqtt_topics;
 pub mod common_client_errors;
 pub mod common_clients;
+pub mod config;
+pub mod coordinates;
+pub mod heartbeat;
 pub mod local_tiles;
 pub mod places;
 pub mod plugins;
 pub mod properties;
+pub mod replay;
 pub mod sist_camaras;
 pub mod sist_dron;
 pub mod sist_monitoreo;
 pub mod vendor;
 pub mod windows;
+pub mod wire_version;
 pub mod incident_data;
 pub mod place_type;
\ No newline at end of file
@@ -11,6 +11,7 @@ pub enum PlaceType {
     Dron,
     ManualIncident,
     AutomatedIncident,
+    DronIncident,
     Mantainance,
 }
 
@@ -20,6 +21,7 @@ impl PlaceType {
         match source {
             IncidentSource::Manual => Self::ManualIncident,
             IncidentSource::Automated => Self::AutomatedIncident,
+            IncidentSource::Drone => Self::DronIncident,
         }
     }
 
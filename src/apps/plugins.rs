@@ -52,7 +52,13 @@ pub fn images(images_plugin_data: &mut ImagesPluginData) -> impl Plugin {
 }
 
 /// Estructura de datos para el plugin de formas personalizadas.
-pub struct CustomShapes {}
+/// `trails` contiene, por cada dron, las posiciones de su recorrido reciente, a dibujar como una línea.
+/// `cones` contiene, por cada cámara orientada, el contorno de su campo de visión (comenzando por la posición
+/// de la cámara), a dibujar como un polígono.
+pub struct CustomShapes {
+    pub trails: Vec<Vec<Position>>,
+    pub cones: Vec<Vec<Position>>,
+}
 
 impl Plugin for CustomShapes {
     /// Dibuja un circulo en la posicion del obelisco.
@@ -75,6 +81,46 @@ impl Plugin for CustomShapes {
             radius,
             Color32::BLACK.gamma_multiply(if hovered { 0.5 } else { 0.2 }),
         );
+
+        // Recorrido reciente de cada dron.
+        for trail in &self.trails {
+            if trail.len() < 2 {
+                continue;
+            }
+            let points = trail
+                .iter()
+                .map(|position| projector.project(*position).to_pos2())
+                .collect();
+            painter.add(egui::Shape::line(
+                points,
+                egui::Stroke::new(2.0, Color32::LIGHT_BLUE),
+            ));
+        }
+
+        // Campo de visión de cada cámara orientada.
+        for cone in &self.cones {
+            if cone.len() < 3 {
+                continue;
+            }
+            let points: Vec<egui::Pos2> = cone
+                .iter()
+                .map(|position| projector.project(*position).to_pos2())
+                .collect();
+            // Con un semiángulo de más de 90° el contorno no es convexo, por lo que se lo rellena
+            // con triángulos desde la posición de la cámara (el primer punto).
+            let mut mesh = egui::Mesh::default();
+            for point in &points {
+                mesh.colored_vertex(*point, Color32::YELLOW.gamma_multiply(0.15));
+            }
+            for i in 1..points.len() as u32 - 1 {
+                mesh.add_triangle(0, i, i + 1);
+            }
+            painter.add(egui::Shape::mesh(mesh));
+            painter.add(egui::Shape::closed_line(
+                points,
+                egui::Stroke::new(1.0, Color32::YELLOW),
+            ));
+        }
     }
 }
 
@@ -45,4 +45,9 @@ impl Properties {
     pub fn get(&self, key: &str) -> Option<&String> {
         self.props.get(key)
     }
+
+    /// Devuelve un iterador sobre todos los pares clave - valor.
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &String)> {
+        self.props.iter()
+    }
 }
@@ -0,0 +1,152 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    thread,
+    time::Duration,
+};
+
+use crate::{
+    apps::apps_mqtt_topics::AppsMqttTopics,
+    logging::{publish_record::PublishRecord, string_logger::StringLogger},
+    mqtt::{client::publisher::Publisher, mqtt_utils::topic::Topic},
+};
+
+/// Reproduce los publish registrados en el log de una app (ver `PublishRecord`), volviéndolos a publicar,
+/// para reproducir un escenario (ej. contra una nueva instancia de Sistema Monitoreo). Se respetan los tiempos
+/// entre los publish según sus timestamps, divididos por `speed` (con 2.0 se reproduce al doble de velocidad).
+#[derive(Debug)]
+pub struct PublishReplay {
+    records: Vec<PublishRecord>,
+    speed: f64,
+    logger: StringLogger,
+}
+
+impl PublishReplay {
+    /// Crea la reproducción de los `records`. Devuelve error si `speed` no es un número positivo.
+    pub fn new(records: Vec<PublishRecord>, speed: f64, logger: StringLogger) -> Result<Self, Error> {
+        if !speed.is_finite() || speed <= 0.0 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "La velocidad de reproducción debe ser un número positivo",
+            ));
+        }
+        Ok(Self {
+            records,
+            speed,
+            logger,
+        })
+    }
+
+    /// Lee del archivo de log `log_file` los registros de publish a topics de incidentes, cámaras y drones,
+    /// en orden. Las demás líneas se ignoran; un registro mal formado se informa y se descarta.
+    pub fn read_records(log_file: &str) -> Result<Vec<PublishRecord>, Error> {
+        let content = fs::read_to_string(log_file)?;
+        let mut records = vec![];
+        for line in content.lines() {
+            match PublishRecord::from_log_line(line) {
+                Ok(Some(record)) if is_replayable(record.get_topic()) => records.push(record),
+                Ok(_) => {}
+                Err(e) => println!("ERROR: Replay: se descarta la línea: {:?}", e),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Devuelve, para cada registro, cuánto esperar antes de publicarlo: el tiempo transcurrido desde el
+    /// anterior según sus timestamps, dividido por la velocidad. Si un timestamp es anterior al del registro
+    /// previo (ej. logs de varias apps concatenados), no se espera.
+    pub fn get_delays(&self) -> Vec<Duration> {
+        let mut previous_ms = self.records.first().map(|record| record.get_timestamp_ms());
+        self.records
+            .iter()
+            .map(|record| {
+                let elapsed_ms = previous_ms
+                    .map(|previous_ms| record.get_timestamp_ms().saturating_sub(previous_ms))
+                    .unwrap_or(0);
+                previous_ms = Some(record.get_timestamp_ms());
+                Duration::from_secs_f64(elapsed_ms as f64 / 1000.0 / self.speed)
+            })
+            .collect()
+    }
+
+    /// Vuelve a publicar los registros por `publisher`, esperando entre ellos según `get_delays`.
+    /// Devuelve la cantidad de publish que se pudieron enviar.
+    pub fn run(&self, publisher: &mut dyn Publisher) -> usize {
+        let mut published = 0;
+        for (record, delay) in self.records.iter().zip(self.get_delays()) {
+            thread::sleep(delay);
+            let res_publish = Topic::try_from(record.get_topic())
+                .and_then(|topic| publisher.publish(&topic, record.get_payload(), record.get_qos()));
+            match res_publish {
+                Ok(_) => {
+                    published += 1;
+                    self.logger
+                        .log(format!("Replay: se publicó nuevamente al topic {}.", record.get_topic()));
+                }
+                Err(e) => {
+                    let msg = format!(
+                        "ERROR: Replay: no se pudo publicar al topic {}: {:?}",
+                        record.get_topic(),
+                        e
+                    );
+                    println!("{}", msg);
+                    self.logger.log(msg);
+                }
+            }
+        }
+        published
+    }
+}
+
+/// Devuelve si se reproducen los publish al `topic`: los de incidentes, cámaras y drones.
+fn is_replayable(topic: &str) -> bool {
+    matches!(
+        AppsMqttTopics::from_str(topic),
+        Some(AppsMqttTopics::IncidentTopic)
+            | Some(AppsMqttTopics::CameraTopic)
+            | Some(AppsMqttTopics::CameraFrameTopic)
+            | Some(AppsMqttTopics::DronTopic)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, sync::mpsc, time::Duration};
+
+    use super::PublishReplay;
+    use crate::{
+        logging::{publish_record::PublishRecord, string_logger::StringLogger},
+        mqtt::client::publisher::NullPublisher,
+    };
+
+    #[test]
+    fn test_1_se_reproducen_los_publish_del_log_con_sus_tiempos_escalados_por_la_velocidad() {
+        let log_file = std::env::temp_dir().join("test_publish_replay_1.txt");
+        let lines = [
+            PublishRecord::new(10_000, "inc", 1, &[1]).to_log_line(),
+            "[16/10/2026 12:00:00] Publico inc: ...".to_string(),
+            PublishRecord::new(11_000, "desc", 1, &[9]).to_log_line(),
+            format!("[16/10/2026 12:00:01] {}", PublishRecord::new(12_000, "dron", 0, &[2]).to_log_line()),
+            PublishRecord::new(11_500, "cam", 1, &[3]).to_log_line(),
+        ];
+        fs::write(&log_file, lines.join("\n")).unwrap();
+        let records = PublishReplay::read_records(log_file.to_str().unwrap());
+        let _ = fs::remove_file(&log_file);
+        let records = records.unwrap();
+        // No se reproduce el will (topic desc)
+        assert_eq!(records.len(), 3);
+
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        assert!(PublishReplay::new(records.clone(), 0.0, StringLogger::new(logger_tx.clone())).is_err());
+        let replay = PublishReplay::new(records, 1000.0, StringLogger::new(logger_tx)).unwrap();
+        assert_eq!(
+            replay.get_delays(),
+            vec![Duration::ZERO, Duration::from_millis(2), Duration::ZERO]
+        );
+
+        let publisher = NullPublisher::new();
+        assert_eq!(replay.run(&mut publisher.clone()), 3);
+        let topics: Vec<String> = publisher.get_published().into_iter().map(|(topic, _, _)| topic).collect();
+        assert_eq!(topics, vec!["inc", "dron", "cam"]);
+    }
+}
@@ -0,0 +1,79 @@
+use std::io::{Error, ErrorKind};
+
+use rustx::apps::replay::publish_replay::PublishReplay;
+use rustx::logging::string_logger::StringLogger;
+use rustx::mqtt::client::mqtt_client::MQTTClient;
+use rustx::mqtt::mqtt_utils::socket_address::parse_socket_addr;
+
+/// Velocidad de reproducción si no se indica otra: se respetan los tiempos originales.
+const DEFAULT_SPEED: f64 = 1.0;
+
+fn get_formatted_app_id() -> String {
+    String::from("Replay")
+}
+
+/// Devuelve el archivo de log a reproducir, la dirección del broker, y la velocidad de reproducción,
+/// recibidos como argumentos: `<archivo de log> <ip> <puerto> [velocidad]`.
+fn get_args() -> Result<(String, std::net::SocketAddr, f64), Error> {
+    let argv = std::env::args().collect::<Vec<String>>();
+    if argv.len() != 4 && argv.len() != 5 {
+        return Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Cantidad de argumentos inválida. Debe ingresar: el archivo de log, la dirección IP y el puerto \
+            del broker, y opcionalmente la velocidad de reproducción (ej. 2.0 para el doble de rápido).",
+        ));
+    }
+    let port = argv[3]
+        .parse::<u16>()
+        .map_err(|_| Error::new(ErrorKind::InvalidInput, "El puerto proporcionado no es válido"))?;
+    let broker_addr = parse_socket_addr(&argv[2], port)?;
+    let speed = match argv.get(4) {
+        Some(speed) => speed
+            .parse::<f64>()
+            .map_err(|_| Error::new(ErrorKind::InvalidInput, "La velocidad proporcionada no es válida"))?,
+        None => DEFAULT_SPEED,
+    };
+
+    Ok((argv[1].to_string(), broker_addr, speed))
+}
+
+/// Vuelve a publicar al broker los publish registrados en el log de una app, para reproducir un escenario.
+/// La app debe haberse ejecutado con `record-publishes=true` para que su log los registre. Se conecta con
+/// el client_id "Replay", que el `topic_acl.properties` del server habilita a publicar los topics reproducidos.
+fn main() -> Result<(), Error> {
+    let (log_file, broker_addr, speed) = get_args()?;
+    let records = PublishReplay::read_records(&log_file)?;
+    println!("Se reproducirán {} publish de {}.", records.len(), log_file);
+
+    // Se crean y configuran ambos extremos del string logger
+    let (mut logger, handle_logger) = StringLogger::create_logger(get_formatted_app_id());
+    let replay = PublishReplay::new(records, speed, logger.clone_ref())?;
+
+    match MQTTClient::mqtt_connect_to_broker(get_formatted_app_id(), &broker_addr, None, logger.clone_ref()) {
+        Ok((mut mqtt_client, _publish_msg_rx, handle)) => {
+            println!("Conectado al broker MQTT.");
+            logger.log("Conectado al broker MQTT".to_string());
+
+            let published = replay.run(&mut mqtt_client);
+            println!("Se publicaron nuevamente {} publish.", published);
+
+            if let Err(e) = mqtt_client.mqtt_disconnect() {
+                println!("Error al desconectarse del broker MQTT: {:?}", e);
+            }
+            if handle.join().is_err() {
+                println!("Error al esperar al hijo listener.");
+            }
+        }
+        Err(e) => println!("Replay: Error al conectar al broker MQTT: {:?}", e),
+    }
+
+    logger.stop_logging();
+    drop(replay); // porque le hicimos clone_ref al logger.
+
+    // Se espera al hijo para el logger writer
+    if handle_logger.join().is_err() {
+        println!("Error al esperar al hijo para string logger writer.")
+    }
+
+    Ok(())
+}
@@ -3,31 +3,26 @@ use reqwest::{
     blocking::Client,
     header::{HeaderMap, CONTENT_TYPE},
 };
-use std::{
-    error::Error,
-    io::ErrorKind,
-    sync::{mpsc, Arc, Mutex},
-};
+use std::{error::Error, io::ErrorKind};
 
 use crate::{
-    apps::{
-        incident_data::{incident::Incident, incident_source::IncidentSource},
-        sist_camaras::{
-            ai_detection::{api_credentials::ApiCredentials, properties::DetectorProperties},
-            types::shareable_cameras_type::ShCamerasType,
+    apps::sist_camaras::{
+        ai_detection::{
+            api_credentials::ApiCredentials, incident_detector::IncidentDetector,
+            properties::DetectorProperties,
         },
+        camera_frame::CameraFrame,
+        types::shareable_cameras_type::ShCamerasType,
     },
     logging::string_logger::StringLogger,
 };
 
 /// Se encarga de comunicarse con el proveedor de inteligencia artificial, enviarle la
 /// imagen de la cámara y evaluar si la respuesta indica que la imagen contiene o no un incidente.
-/// En caso afirmativo envía por el tx el Incidente (Sistema Cámaras lo publicará por MQTT).
+/// En caso afirmativo devuelve la posición del incidente (el `AIDetectorManager` lo creará y enviará).
 #[derive(Debug)]
 pub struct AutomaticIncidentDetector {
     cameras: ShCamerasType,
-    tx: mpsc::Sender<Incident>,
-    last_incident_id: Arc<Mutex<u8>>,
     properties: DetectorProperties,
     logger: StringLogger,
 }
@@ -35,14 +30,11 @@ pub struct AutomaticIncidentDetector {
 impl AutomaticIncidentDetector {
     pub fn new(
         cameras: ShCamerasType,
-        tx: mpsc::Sender<Incident>,
         properties: DetectorProperties,
         logger: StringLogger,
     ) -> Self {
         Self {
             cameras,
-            tx,
-            last_incident_id: Arc::new(Mutex::new(0)),
             properties,
             logger,
         }
@@ -51,16 +43,14 @@ impl AutomaticIncidentDetector {
     pub fn clone_refs(&self) -> Self {
         Self {
             cameras: self.cameras.clone(),
-            tx: self.tx.clone(),
-            last_incident_id: self.last_incident_id.clone(),
             properties: self.properties.clone(),
             logger: self.logger.clone_ref(),
         }
     }
 
-    /// Lee la imagen de `image_path`, se la envía al proveedor de ia y analiza su respuesta para concluir si
-    /// la imagen contiene o no un incidente. En caso afirmativo, se procesa al incidente.
-    pub fn process_image(&mut self, image: Vec<u8>, cam_id: u8) -> Result<(), Box<dyn Error>> {
+    /// Envía la `image` al proveedor de ia y analiza su respuesta para concluir si
+    /// la imagen contiene o no un incidente. En caso afirmativo, devuelve la posición del incidente.
+    pub fn process_image(&self, image: Vec<u8>, cam_id: u8) -> Result<Vec<(f64, f64)>, Box<dyn Error>> {
         let api_credentials = ApiCredentials::new(self.properties.get_api_credentials_file_path());
 
         let (client, headers) = create_client_and_headers(&api_credentials)?;
@@ -84,10 +74,11 @@ impl AutomaticIncidentDetector {
         self.logger
             .log(format!("Detector: Probability: {:?}", incident_probability));
         if incident_probability > self.properties.get_inc_threshold() {
-            self.process_incident(cam_id)?;
+            // Se genera la posición del incidente, dentro del rango de la cámara
+            return Ok(vec![self.get_incident_position(cam_id)?]);
         }
 
-        Ok(())
+        Ok(vec![])
     }
 
     /// Interpreta el res_text recibido como json y devuelve la probabilidad con que el mismo afirma que
@@ -171,23 +162,6 @@ impl AutomaticIncidentDetector {
         None
     }
 
-    /// Recibe el image_path de la imagen en la que se detectó un incidente, crea el Incident y lo envía internamente para
-    /// ser publicado por MQTT.
-    fn process_incident(&mut self, cam_id: u8) -> Result<(), Box<dyn Error>> {
-        // obtenemos la posición
-        let incident_position: (f64, f64) = self.get_incident_position(cam_id)?;
-        // creamos el incidente
-        let inc_id = self.get_next_incident_id()?;
-        let incident = Incident::new(inc_id, incident_position, IncidentSource::Automated);
-
-        println!("Detector: Incidente creado! {:?}", incident);
-        self.logger
-            .log(format!("Detector: Incidente creado! {:?}", incident));
-        // se envía el inc para ser publicado
-        self.tx.send(incident)?;
-        Ok(())
-    }
-
     /// Genera una ubicación de incidente aleatoria
     /// dentro del rango de la camara que detectó el incidente.
     fn get_incident_position(&self, camera_id: u8) -> Result<(f64, f64), std::io::Error> {
@@ -215,19 +189,19 @@ impl AutomaticIncidentDetector {
             "Error al obtener la camera del hashmap en get_incident_position.",
         ))
     }
+}
 
-    /// Obtiene el siguiente incident id disponible para utilizar.
-    /// Al ser éste un programa multihilo, es necesario que el manejo de esta variable sea atómico
-    /// para no tener problemas de concurrencia que lleven a ids duplicados.
-    fn get_next_incident_id(&mut self) -> Result<u8, std::io::Error> {
-        if let Ok(mut last) = self.last_incident_id.lock() {
-            *last += 1;
-            return Ok(*last);
+impl IncidentDetector for AutomaticIncidentDetector {
+    fn detect(&self, frame: &CameraFrame) -> Vec<(f64, f64)> {
+        match self.process_image(frame.get_image().to_vec(), frame.get_cam_id()) {
+            Ok(positions) => positions,
+            Err(e) => {
+                println!("Detector: Error al procesar la imagen: {:?}.", e);
+                self.logger
+                    .log(format!("Detector: Error al procesar la imagen: {:?}.", e));
+                vec![]
+            }
         }
-        Err(std::io::Error::new(
-            ErrorKind::Other,
-            "Detector: Error al tomar el lock",
-        ))
     }
 }
 
@@ -248,7 +222,7 @@ fn create_client_and_headers(
 #[cfg(test)]
 mod test {
     use std::{collections::HashMap, sync::{mpsc, Arc, Mutex}};
-    use crate::{apps::{incident_data::incident::Incident, sist_camaras::ai_detection::properties::DetectorProperties}, logging::string_logger::StringLogger};
+    use crate::{apps::sist_camaras::ai_detection::properties::DetectorProperties, logging::string_logger::StringLogger};
     use super::AutomaticIncidentDetector;
 
     // Devuelve un json de prueba, como una str.
@@ -282,14 +256,12 @@ mod test {
     fn create_detector() -> AutomaticIncidentDetector {
         const PROPERTIES_FILE: &str = "./src/apps/sist_camaras/ai_detection/properties.txt";
         let properties = DetectorProperties::new(PROPERTIES_FILE).unwrap();
-        let (inc_tx, _rx) = mpsc::channel::<Incident>();
         let (string_tx, _rx) = mpsc::channel::<String>();
         let logger = StringLogger::new(string_tx);
         //let (logger, handle_logger) = StringLogger::create_logger("detector_main".to_string());
 
         AutomaticIncidentDetector::new(
             Arc::new(Mutex::new(HashMap::new())),
-            inc_tx,
             properties,
             logger,
         )    
@@ -1,29 +1,58 @@
-use std::{sync::mpsc, thread};
+use std::{
+    sync::{atomic::AtomicBool, mpsc, Arc},
+    thread,
+};
 
 use rustx::{
     apps::{
         incident_data::incident::Incident,
         sist_camaras::{
-            ai_detection::ai_detector_manager::AIDetectorManager,
-            manage_stored_cameras::create_cameras, types::shareable_cameras_type::ShCamerasType,
+            ai_detection::{
+                ai_detector_manager::AIDetectorManager,
+                camera_frame_capturer::CameraFrameCapturer,
+                incident_publish_feedback::IncidentPublishResult,
+            },
+            camera_frame::CameraFrame,
+            manage_stored_cameras::create_cameras,
+            types::shareable_cameras_type::ShCamerasType,
         },
     },
     logging::string_logger::StringLogger,
 };
 
 /// Este main está para llamarlo con el cargo run sin tener que levantar server monitoreo y cámaras.
+/// Los frames capturados se envían directamente al detector, sin pasar por MQTT.
 fn main() {
     println!("Iniciando detector.");
 
     // Crea un AutomaticIncidentDetector y lo pone en funcionamiento.
     let cameras: ShCamerasType = create_cameras();
     let (tx, rx) = mpsc::channel::<Incident>();
+    // Los incidentes no se publican, por lo que no hay resultados de publish que devolverle al detector
+    let (_publish_result_tx, publish_result_rx) = mpsc::channel::<IncidentPublishResult>();
+    let (frame_tx, frame_rx) = mpsc::channel::<CameraFrame>();
     let (_exit_tx, exit_rx) = mpsc::channel::<()>();
     let (logger, handle_logger) = StringLogger::create_logger("detector_main".to_string());
 
+    // Se ejecuta en otro hilo la captura de frames.
+    let cameras_c = cameras.clone();
+    let logger_c = logger.clone_ref();
+    let handle_capturer = thread::spawn(move || {
+        if let Err(e) = CameraFrameCapturer::run(cameras_c, frame_tx, exit_rx, logger_c.clone_ref()) {
+            logger_c.log(format!("Error al ejecutar la captura de frames: {:?}.", e));
+        }
+    });
+
     // Se ejecuta en otro hilo el run.
     let handle = thread::spawn(move || {
-        if let Err(e) = AIDetectorManager::run(cameras, tx, exit_rx, logger.clone_ref()) {
+        if let Err(e) = AIDetectorManager::run(
+            cameras,
+            frame_rx,
+            tx,
+            publish_result_rx,
+            Arc::new(AtomicBool::new(false)),
+            logger.clone_ref(),
+        ) {
             logger.log(format!("Error al ejecutar el detector en Sistema Cámaras: {:?}.", e));
         }
     });
@@ -34,7 +63,10 @@ fn main() {
         // Publicar incidente por mqtt.
     }
 
-    // Esperar al hijo
+    // Esperar a los hijos
+    if handle_capturer.join().is_err() {
+        println!("Error al esperar al hijo de captura.");
+    }
     if handle.join().is_err() {
         println!("Error al esperar al hijo.");
     }
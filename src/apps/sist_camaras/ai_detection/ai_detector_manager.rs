@@ -1,294 +1,565 @@
-use notify::{event::EventKind, RecursiveMode, Watcher};
 use rayon::ThreadPoolBuilder;
 use std::{
     error::Error,
-    ffi::OsStr,
-    fs,
-    io::{Error as ioError, ErrorKind},
-    path::Path,
+    io::Error as ioError,
     sync::{
-        mpsc::{self, Receiver, Sender},
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, Sender},
         Arc, Mutex,
     },
     thread,
+    time::{Duration, Instant},
 };
 
 use crate::{
     apps::{
-        incident_data::incident::Incident,
+        incident_data::{
+            incident::Incident, incident_id_allocator::IncidentIdAllocator,
+            incident_source::IncidentSource,
+        },
         sist_camaras::{
             ai_detection::{
-                ai_detector::AutomaticIncidentDetector, properties::DetectorProperties,
+                ai_detector::AutomaticIncidentDetector,
+                incident_coalescer::IncidentCoalescer,
+                incident_debouncer::IncidentDebouncer,
+                incident_detector::{IncidentDetector, MockIncidentDetector},
+                incident_publish_feedback::{IncidentPublishFeedback, IncidentPublishResult},
+                properties::{DetectorProperties, DetectorType},
             },
+            camera_frame::CameraFrame,
+            state_rate_limiter::{CameraRateLimiter, StateIntervals, SIST_CAMARAS_PROPERTIES_FILE},
             types::shareable_cameras_type::ShCamerasType,
         },
     },
     logging::string_logger::StringLogger,
+    mqtt::mqtt_utils::shutdown_token::ShutdownToken,
 };
 
-const PROPERTIES_FILE: &str = "./src/apps/sist_camaras/ai_detection/properties.txt";
+pub const PROPERTIES_FILE: &str = "./src/apps/sist_camaras/ai_detection/properties.txt";
 
 #[derive(Debug)]
-/// Se encarga de inicializar todo lo relacionado a directorios, monitorearlos, y threads,
-/// y finalmente llama al Automatic Incident Detector cuando se crea una imagen en algún subdirectorio
-/// de alguna cámara.
+/// Se encarga de recibir los `CameraFrame`s publicados por las cámaras, y de analizar cada uno
+/// con el `IncidentDetector` configurado, creando y enviando un incidente por cada posición que éste detecte.
 pub struct AIDetectorManager {
     cameras: ShCamerasType,
     inc_tx: Sender<Incident>,
-    exit_requested: Arc<Mutex<bool>>,
-    properties: DetectorProperties,
+    detector: Arc<dyn IncidentDetector + Send + Sync>,
+    detector_paused: Arc<AtomicBool>,
+    id_allocator: Arc<Mutex<IncidentIdAllocator>>,
+    debouncer: Arc<Mutex<IncidentDebouncer>>,
+    coalescer: Arc<Mutex<IncidentCoalescer>>,
+    detection_intervals: StateIntervals,
+    inc_publish_max_retries: u8,
     logger: StringLogger,
 }
 
 impl AIDetectorManager {
-    /// Crea y ejecuta lo necesario para la detección de incidentes de manera automática haciendo uso de inteligencia artificial,
+    /// Crea y ejecuta lo necesario para la detección de incidentes de manera automática,
+    /// utilizando el detector indicado en el archivo de configuración (`detector_type`).
+    /// Analiza los frames recibidos por `frame_rx`, hasta que el otro extremo del channel se cierre.
+    /// Por `publish_result_rx` recibe el resultado de publicar cada incidente enviado por `inc_tx`, para
+    /// reintentar los que no pudieron publicarse (ver `IncidentPublishFeedback`).
+    /// Mientras `detector_paused` sea true, los frames se consumen sin analizarlos (ver `run_internal`).
     pub fn run(
         cameras: ShCamerasType,
-        inc_tx: mpsc::Sender<Incident>,
-        exit_rx: mpsc::Receiver<()>,
+        frame_rx: Receiver<CameraFrame>,
+        inc_tx: Sender<Incident>,
+        publish_result_rx: Receiver<IncidentPublishResult>,
+        detector_paused: Arc<AtomicBool>,
         logger: StringLogger,
     ) -> Result<Self, ioError> {
         let properties = DetectorProperties::new(PROPERTIES_FILE)?;
+        let detector = create_detector(&properties, cameras.clone(), logger.clone_ref());
+        let detection_intervals =
+            StateIntervals::detection_intervals_from_properties_file(SIST_CAMARAS_PROPERTIES_FILE);
+        Self::run_with_detector(
+            cameras,
+            (frame_rx, publish_result_rx),
+            inc_tx,
+            properties,
+            (detector, detector_paused),
+            detection_intervals,
+            logger,
+        )
+    }
 
-        let er = Arc::new(Mutex::new(false));
+    /// Análoga a `run`, pero utiliza el `detector` recibido (ej. un `MockIncidentDetector` para tests).
+    /// Las `cameras` se consultan para descartar las detecciones en sus regiones excluidas, y para analizar
+    /// los frames de cada una según el intervalo de su estado (ver `detection_intervals`).
+    pub fn run_with_detector(
+        cameras: ShCamerasType,
+        (frame_rx, publish_result_rx): (Receiver<CameraFrame>, Receiver<IncidentPublishResult>),
+        inc_tx: Sender<Incident>,
+        properties: DetectorProperties,
+        (detector, detector_paused): (Box<dyn IncidentDetector + Send + Sync>, Arc<AtomicBool>),
+        detection_intervals: StateIntervals,
+        logger: StringLogger,
+    ) -> Result<Self, ioError> {
+        let debouncer = IncidentDebouncer::new(
+            Duration::from_secs(properties.get_inc_debounce_window_secs()),
+            properties.get_inc_debounce_distance_m(),
+        );
+        let coalescer = IncidentCoalescer::new(
+            Duration::from_millis(properties.get_inc_merge_window_ms()),
+            properties.get_inc_merge_distance_m(),
+        );
         let detector_manager = Self {
             cameras,
             inc_tx,
-            exit_requested: er.clone(),
-            properties,
+            detector: Arc::from(detector),
+            detector_paused,
+            id_allocator: Arc::new(Mutex::new(IncidentIdAllocator::new())),
+            debouncer: Arc::new(Mutex::new(debouncer)),
+            coalescer: Arc::new(Mutex::new(coalescer)),
+            detection_intervals,
+            inc_publish_max_retries: properties.get_inc_publish_max_retries(),
             logger,
         };
 
-        // Lanza hilo que pondrá en true la `er` si se solicita salir desde abm
-        let handle = thread::spawn(move || {
-            modify_if_exit_requested(er, exit_rx);
-        });
-
         // Se ejecuta el detector
-        if let Err(e) = detector_manager.run_internal() {
+        if let Err(e) = detector_manager.run_internal(frame_rx, publish_result_rx) {
             detector_manager
                 .logger
                 .log(format!("Error en ejecución de detector: {:?}.", e));
         }
 
-        // Espera al hilo lanzado
-        if let Err(e) = handle.join() {
-            detector_manager.logger.log(format!(
-                "Error al joinear hilo de exit de detector: {:?}.",
-                e
-            ));
-        }
-
         Ok(detector_manager)
     }
 
-    /// Crea y monitorea los subdirectorios correspondientes a las cámaras, cuando una imagen se crea en alguno de ellos,
-    /// se lanza el procedimiento para analizar mediante proveedor de servicio de inteligencia artificial si la misma
-    /// contiene o no un incidente, y se lo envía internamente a Sistema Cámaras para que sea publicado por MQTT.
-    fn run_internal(&self) -> Result<(), Box<dyn Error>> {
-        // Crea, si no existían, el dir base y los subdirectorios, y los monitorea
-        let path = Path::new(self.properties.get_base_dir());
-        self.create_dirs_tree(path)?;
-        // Comienza a monitorear los directorios
-        let (tx_fs, rx_fs) = mpsc::channel();
-        let mut watcher = notify::recommended_watcher(tx_fs.clone())?;
-        watcher.watch(path, RecursiveMode::Recursive)?;
-        println!("Detector: Monitoreando subdirs.");
+    /// Por cada frame recibido, lanza el procedimiento para analizar si el mismo contiene o no un incidente,
+    /// y se lo envía internamente a Sistema Cámaras para que sea publicado por MQTT.
+    /// Mientras el detector está pausado, los frames se siguen recibiendo, para que no se acumulen,
+    /// pero se descartan sin analizarlos. El estado se consulta en cada frame, por lo que al reanudarlo
+    /// se analiza el siguiente frame que llegue.
+    fn run_internal(
+        &self,
+        frame_rx: Receiver<CameraFrame>,
+        publish_result_rx: Receiver<IncidentPublishResult>,
+    ) -> Result<(), Box<dyn Error>> {
+        println!("Detector: Esperando frames de las cámaras.");
         self.logger
-            .log("Detector: Monitoreando subdirs".to_string());
-
-        // Se inicializa el detector
-        let logger_ai = self.logger.clone_ref();
-        let ai_detector = AutomaticIncidentDetector::new(
-            self.cameras.clone(),
-            self.inc_tx.clone(),
-            self.properties.clone(),
-            logger_ai,
-        );
+            .log("Detector: Esperando frames de las cámaras".to_string());
 
         // Crear un pool de threads con el número de threads deseado
         let pool = ThreadPoolBuilder::new().num_threads(6).build()?;
 
-        for event_res in rx_fs {
-            // Sale, si lo solicitaron desde abm
-            if self.exit_requested() {
-                break;
+        let shutdown_token = ShutdownToken::new();
+        let flush_handle = self.spawn_flush_thread(shutdown_token.clone());
+        let feedback_handle = self.spawn_publish_feedback_thread(publish_result_rx, shutdown_token.clone());
+
+        // Las cámaras en modo ahorro se analizan con menor frecuencia
+        let mut rate_limiter = CameraRateLimiter::new(self.detection_intervals);
+        let mut was_paused = false;
+        for frame in frame_rx {
+            let is_paused = self.detector_paused.load(Ordering::SeqCst);
+            if is_paused != was_paused {
+                self.log_pause_state_change(is_paused);
+                was_paused = is_paused;
             }
-
-            // Procesa el evento, interesa el Create, que es cuando se crea una imagen en algún subdirectorio
-            let event = event_res?;
-            if let EventKind::Create(_) = event.kind {
-                self.logger.log("Detector: event ok: create".to_string());
-                if let Some(path) = event.paths.first() {
-                    if let Err(e) = self.launch_detection_for_image(&ai_detector, &pool, path) {
-                        println!("Detector: Error al procesar la imagen: {:?}, {:?}", path, e);
-                        self.logger.log(format!(
-                            "Detector: Error al procesar la imagen: {:?}, {:?}",
-                            path, e
-                        ));
-                    }
-                }
+            if is_paused {
+                continue;
+            }
+            if rate_limiter.should_process_frame_of(&self.cameras, frame.get_cam_id(), Instant::now()) {
+                self.launch_detection_for_frame(&pool, frame);
             }
         }
 
+        shutdown_token.request();
+        if let Some(handle) = flush_handle {
+            let _ = handle.join();
+        }
+        let _ = feedback_handle.join();
         Ok(())
     }
 
-    /// Crea, si no existía, la estructura de directorios necesaria para las imágenes de las cámaras.
-    fn create_dirs_tree(&self, base_dir: &Path) -> Result<(), ioError> {
-        self.create_basedir(base_dir)?;
-        self.create_subdirs(base_dir)?;
-        Ok(())
+    fn log_pause_state_change(&self, is_paused: bool) {
+        let msg = if is_paused {
+            "Detector: pausado, se descartan los frames recibidos sin analizarlos."
+        } else {
+            "Detector: reanudado, se vuelven a analizar los frames recibidos."
+        };
+        println!("{}", msg);
+        self.logger.log(msg.to_string());
     }
 
-    /// Crea el `base_dir` que contendrá a los subdirectorios de las cámaras, si no existía.
-    fn create_basedir(&self, base_dir: &Path) -> Result<(), ioError> {
-        // Si ya existe, lo borra y a todo su contenido, y
-        if base_dir.exists() {
-            fs::remove_dir_all(base_dir)?;
-        }
-        // lo crea
-        fs::create_dir(base_dir)?;
-
-        Ok(())
+    /// Lanza un hilo que procesa el resultado de publicar cada incidente, reintentando los que no pudieron
+    /// publicarse, hasta que se solicite el `shutdown_token` o se cierre el otro extremo de `publish_result_rx`.
+    fn spawn_publish_feedback_thread(
+        &self,
+        publish_result_rx: Receiver<IncidentPublishResult>,
+        shutdown_token: ShutdownToken,
+    ) -> thread::JoinHandle<()> {
+        let mut feedback = IncidentPublishFeedback::new(
+            self.inc_tx.clone(),
+            self.debouncer.clone(),
+            self.inc_publish_max_retries,
+            self.logger.clone_ref(),
+        );
+        thread::spawn(move || {
+            while let Some(result) = shutdown_token.recv_unless_requested(&publish_result_rx) {
+                feedback.handle(result);
+            }
+        })
     }
 
-    /// Crea subdirectorios de `base_dir`, uno por cada cámara, de nombre "camera_i"
-    /// donde `i` es el id de dicha cámara.
-    fn create_subdirs(&self, base_dir: &Path) -> Result<(), ioError> {
-        if let Ok(cameras) = self.cameras.lock() {
-            for cam in cameras.values() {
-                if cam.is_not_deleted() {
-                    // (para todas va a dar true, porque Sistema Camaras se está iniciando, pero así es más genérico)
-                    let cam_id = cam.get_id();
-                    self.create_subdir(base_dir, cam_id)?;
+    /// Si las detecciones se demoran para unificar las de distintas cámaras, lanza un hilo que periódicamente
+    /// emite las que ya cumplieron su espera, hasta que se solicite el `shutdown_token`.
+    fn spawn_flush_thread(&self, shutdown_token: ShutdownToken) -> Option<thread::JoinHandle<()>> {
+        let window = match self.coalescer.lock() {
+            Ok(coalescer) => coalescer.get_window()?,
+            Err(_) => return None,
+        };
+        let inc_sender = self.create_incident_sender();
+        Some(thread::spawn(move || {
+            while !shutdown_token.sleep_unless_requested(window / 2) {
+                if let Err(e) = inc_sender.send_ready_incidents() {
+                    inc_sender
+                        .logger
+                        .log(format!("Detector: Error al emitir incidentes pendientes: {:?}.", e));
                 }
             }
-        }
+        }))
+    }
 
-        Ok(())
+    fn create_incident_sender(&self) -> IncidentSender {
+        IncidentSender::new(
+            self.cameras.clone(),
+            self.inc_tx.clone(),
+            self.id_allocator.clone(),
+            self.debouncer.clone(),
+            self.coalescer.clone(),
+            self.logger.clone_ref(),
+        )
     }
 
-    /// Crea un subdirectorio de `base_dir` de nombre "camera_i" donde `i` es el u8 recibido.
-    fn create_subdir(&self, base_dir: &Path, i: u8) -> Result<(), ioError> {
-        // Concatena el nombre del subdir a crear, al dir base
-        let subdir = format!("camera_{}", i);
-        let new_dir_path = base_dir.join(subdir);
+    /// Envía el pedido a la threadpool para detectar incidente en el frame.
+    fn launch_detection_for_frame(&self, pool: &rayon::ThreadPool, frame: CameraFrame) {
+        let detector = self.detector.clone();
+        let inc_sender = self.create_incident_sender();
+        let logger_c = self.logger.clone_ref();
+        pool.spawn(move || {
+            if let Err(e) = process_frame(detector.as_ref(), &inc_sender, &frame) {
+                println!("Detector: Error en process_frame: {:?}.", e);
+                logger_c.log(format!("Detector: Error en process_frame: {:?}.", e));
+            }
+        });
+    }
+}
 
-        // Si no existe, lo crea
-        if !new_dir_path.exists() {
-            fs::create_dir(&new_dir_path)?;
+/// Crea el detector de incidentes indicado en las `properties`.
+fn create_detector(
+    properties: &DetectorProperties,
+    cameras: ShCamerasType,
+    logger: StringLogger,
+) -> Box<dyn IncidentDetector + Send + Sync> {
+    match properties.get_detector_type() {
+        DetectorType::Ai => Box::new(AutomaticIncidentDetector::new(
+            cameras,
+            properties.clone(),
+            logger,
+        )),
+        DetectorType::Mock => {
+            // Cada imagen procesada dispara el siguiente incidente de la lista
+            let script = properties
+                .get_mock_incidents()
+                .into_iter()
+                .map(|position| vec![position])
+                .collect();
+            Box::new(MockIncidentDetector::new(script))
         }
+    }
+}
 
-        Ok(())
+/// Crea los incidentes a partir de las posiciones detectadas, y los envía internamente para ser publicados por MQTT.
+struct IncidentSender {
+    cameras: ShCamerasType,
+    inc_tx: Sender<Incident>,
+    id_allocator: Arc<Mutex<IncidentIdAllocator>>,
+    debouncer: Arc<Mutex<IncidentDebouncer>>,
+    coalescer: Arc<Mutex<IncidentCoalescer>>,
+    logger: StringLogger,
+}
+
+impl IncidentSender {
+    fn new(
+        cameras: ShCamerasType,
+        inc_tx: Sender<Incident>,
+        id_allocator: Arc<Mutex<IncidentIdAllocator>>,
+        debouncer: Arc<Mutex<IncidentDebouncer>>,
+        coalescer: Arc<Mutex<IncidentCoalescer>>,
+        logger: StringLogger,
+    ) -> Self {
+        Self {
+            cameras,
+            inc_tx,
+            id_allocator,
+            debouncer,
+            coalescer,
+            logger,
+        }
     }
 
-    /// Envía el pedido a la threadpool para detectar incidente en la imagen.
-    fn launch_detection_for_image(
-        &self,
-        ai_detector: &AutomaticIncidentDetector,
-        pool: &rayon::ThreadPool,
-        path: &Path,
-    ) -> Result<(), Box<dyn Error>> {
-        if path.is_file() {
-            let image_path = path.to_owned();
-            // Validar la extensión del archivo
-            self.is_valid_extension(&image_path)?;
-
-            // Ejecuta el procesamiento de la imagen en un hilo de la threadpool
-            let mut aidetector = ai_detector.clone_refs();
-            let logger_c = self.logger.clone_ref();
-            pool.spawn(move || {
-                if let Err(e) = read_and_process_image(&mut aidetector, &image_path) {
-                    println!("Detector: Error en read_and_process_image: {:?}.", e);
-                    logger_c.log(format!(
-                        "Detector: Error en read_and_process_image: {:?}.",
-                        e
-                    ));
-                }
-            });
+    /// Crea un incidente en cada posición detectada por la cámara `cam_id`, y lo envía para ser publicado;
+    /// salvo que caiga en una región excluida de la cámara, o que se trate de una detección repetida,
+    /// en cuyo caso se la suprime. Las detecciones de un mismo evento desde distintas cámaras
+    /// se unifican en un solo incidente (ver `IncidentCoalescer`).
+    fn send_incidents_at(&self, cam_id: u8, positions: Vec<(f64, f64)>) -> Result<(), Box<dyn Error>> {
+        for position in positions {
+            if self.is_masked(cam_id, position) {
+                println!("Detector: detección en región excluida suprimida, cámara {}, posición {:?}.", cam_id, position);
+                self.logger.log(format!(
+                    "Detector: detección en región excluida suprimida, cámara {}, posición {:?}.",
+                    cam_id, position
+                ));
+                continue;
+            }
+            if !self.passes_debounce(cam_id, position) {
+                println!("Detector: detección repetida suprimida, cámara {}, posición {:?}.", cam_id, position);
+                self.logger.log(format!(
+                    "Detector: detección repetida suprimida, cámara {}, posición {:?}.",
+                    cam_id, position
+                ));
+                continue;
+            }
+            if self.coalesce(cam_id, position) {
+                println!("Detector: detección unificada con la de otra cámara, cámara {}, posición {:?}.", cam_id, position);
+                self.logger.log(format!(
+                    "Detector: detección unificada con la de otra cámara, cámara {}, posición {:?}.",
+                    cam_id, position
+                ));
+            }
         }
-        Ok(())
+        self.send_ready_incidents()
     }
 
-    /// Checkea si la extensión de la imagen es válida.
-    fn is_valid_extension(&self, image_path: &Path) -> Result<(), Box<dyn Error>> {
-        if let Some(img_extension) = image_path.extension().and_then(OsStr::to_str) {
-            // Si es una extensión válida (ej jpg o jpeg), procesar
-            let valid_extensions = self.properties.get_img_valid_extensions();
-            if valid_extensions.contains(&img_extension) {
-                return Ok(());
+    /// Crea y envía un incidente por cada detección pendiente cuya espera ya terminó.
+    fn send_ready_incidents(&self) -> Result<(), Box<dyn Error>> {
+        let ready = match self.coalescer.lock() {
+            Ok(mut coalescer) => coalescer.take_ready(Instant::now()),
+            Err(_) => {
+                return Err(Box::new(ioError::other("Detector: Error al tomar el lock")))
             }
+        };
+        for (position, cam_ids) in ready {
+            let inc_id = self.get_next_incident_id()?;
+            let incident = Incident::new(inc_id, position, IncidentSource::Automated);
+
+            println!("Detector: Incidente creado! {:?}, cámaras {:?}", incident, cam_ids);
+            self.logger
+                .log(format!("Detector: Incidente creado! {:?}, cámaras {:?}", incident, cam_ids));
+            self.inc_tx.send(incident)?;
         }
-        Err(Box::new(ioError::new(
-            ErrorKind::Other,
-            "Extensión inválida.",
-        )))
+        Ok(())
     }
 
-    /// Devuelve si se solicitó salir.
-    fn exit_requested(&self) -> bool {
-        if let Ok(var) = self.exit_requested.lock() {
-            return *var;
+    /// Agrega la detección a las pendientes. Devuelve si se unificó con una detección pendiente cercana.
+    fn coalesce(&self, cam_id: u8, position: (f64, f64)) -> bool {
+        if let Ok(mut coalescer) = self.coalescer.lock() {
+            return coalescer.add(cam_id, position, Instant::now());
         }
         false
     }
-}
 
-/// Si recibe por el `rx` que se solicitó salir, lo deja asentado en la variable compartida `exit_requested`,
-/// para que en la próxima vuelta del for el detector manager se entere y finalice la ejecución.
-fn modify_if_exit_requested(exit_requested: Arc<Mutex<bool>>, rx: Receiver<()>) {
-    if rx.recv().is_ok() {
-        if let Ok(mut var) = exit_requested.lock() {
-            *var = true;
+    /// Devuelve si la posición cae dentro de alguna región excluida de la cámara `cam_id`.
+    fn is_masked(&self, cam_id: u8, position: (f64, f64)) -> bool {
+        if let Ok(cameras) = self.cameras.lock() {
+            if let Some(camera) = cameras.get(&cam_id) {
+                return camera.is_masked(position);
+            }
+        }
+        false
+    }
+
+    /// Devuelve si la detección no es repetida, es decir si debe emitirse como incidente.
+    fn passes_debounce(&self, cam_id: u8, position: (f64, f64)) -> bool {
+        if let Ok(mut debouncer) = self.debouncer.lock() {
+            return debouncer.should_emit(cam_id, position, Instant::now());
+        }
+        true
+    }
+
+    /// Obtiene el siguiente incident id disponible para utilizar.
+    /// Al ser éste un programa multihilo, es necesario que el manejo de esta variable sea atómico
+    /// para no tener problemas de concurrencia que lleven a ids duplicados.
+    /// La numeración es la de los incidentes `Automated`, independiente de la de los `Manual`.
+    fn get_next_incident_id(&self) -> Result<u8, ioError> {
+        if let Ok(mut allocator) = self.id_allocator.lock() {
+            return Ok(allocator.next_info(IncidentSource::Automated).get_inc_id());
         }
+        Err(ioError::other("Detector: Error al tomar el lock"))
     }
 }
 
-/// Lee la imagen del archivo path proporcionado y llama a procesarla.
-fn read_and_process_image(
-    aidetector: &mut AutomaticIncidentDetector,
-    image_path: &Path,
+/// Analiza el `frame` con el `detector`, y envía los incidentes detectados.
+fn process_frame(
+    detector: &(dyn IncidentDetector + Send + Sync),
+    inc_sender: &IncidentSender,
+    frame: &CameraFrame,
 ) -> Result<(), Box<dyn Error>> {
-    let img = read_image(image_path)?;
-    if let Some(cam_id) = extract_camera_id(image_path) {
-        aidetector.process_image(img, cam_id)?;
-    };
-    Ok(())
+    inc_sender.send_incidents_at(frame.get_cam_id(), detector.detect(frame))
 }
 
-/// Lee la imagen del `image_path`.
-fn read_image(image_path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
-    let mut file = std::fs::File::open(image_path)?;
-    let mut image_buffer = Vec::new();
-    std::io::Read::read_to_end(&mut file, &mut image_buffer)?;
-
-    println!("DEBUG: Image size en read_image: {}", image_buffer.len()); // debug
-    if image_buffer.is_empty() {
-        return Err(Box::new(ioError::new(
-            ErrorKind::Other,
-            "La imagen tiene tamaño 0.",
-        )));
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::sync::{atomic::{AtomicBool, Ordering}, mpsc, Arc, Mutex};
+
+    use std::time::Duration;
+
+    use super::{process_frame, AIDetectorManager, IncidentSender, PROPERTIES_FILE};
+    use crate::{
+        apps::{
+            incident_data::{
+                incident::Incident, incident_id_allocator::IncidentIdAllocator,
+                incident_source::IncidentSource,
+            },
+            sist_camaras::{
+                ai_detection::{
+                    incident_coalescer::IncidentCoalescer, incident_debouncer::IncidentDebouncer,
+                    incident_detector::MockIncidentDetector, properties::DetectorProperties,
+                },
+                camera::Camera,
+                camera_frame::CameraFrame,
+                privacy_mask::PrivacyMask,
+                state_rate_limiter::StateIntervals,
+            },
+        },
+        logging::string_logger::StringLogger,
+    };
+
+    #[test]
+    fn test_1_con_mock_detector_se_envian_los_incidentes_del_script() {
+        // Frame de la cámara 3
+        let frame = CameraFrame::new(3, vec![1, 2, 3]);
+
+        let (inc_tx, inc_rx) = mpsc::channel::<Incident>();
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let debouncer = Arc::new(Mutex::new(IncidentDebouncer::new(Duration::from_secs(30), 100.0)));
+        let inc_sender = IncidentSender::new(
+            Arc::new(Mutex::new(HashMap::new())),
+            inc_tx,
+            Arc::new(Mutex::new(IncidentIdAllocator::new())),
+            debouncer,
+            Arc::new(Mutex::new(IncidentCoalescer::new(Duration::ZERO, 0.0))),
+            StringLogger::new(logger_tx),
+        );
+        // La tercera detección está a pocos metros de la primera, por lo que se suprime
+        let detector = MockIncidentDetector::new(vec![
+            vec![(-34.60, -58.38), (-34.61, -58.39)],
+            vec![(-34.6001, -58.38)],
+        ]);
+
+        process_frame(&detector, &inc_sender, &frame).unwrap();
+        process_frame(&detector, &inc_sender, &frame).unwrap();
+        drop(inc_sender);
+
+        let incidents: Vec<Incident> = inc_rx.iter().collect();
+        assert_eq!(incidents.len(), 2);
+        assert_eq!(incidents[0].get_id(), 1);
+        assert_eq!(incidents[1].get_position(), (-34.61, -58.39));
+        assert_eq!(*incidents[1].get_source(), IncidentSource::Automated);
     }
-    Ok(image_buffer)
-}
 
-/// Recibe el path de la imagen que se está procesando, obtiene el id
-/// de la cámara que capturó dicha imagen. Es decir la parte que sigue a "camera_"
-/// de su carpeta padre.
-fn extract_camera_id(path: &Path) -> Option<u8> {
-    // Obtiene el nombre del directorio padre
-    path.parent()
-        .and_then(|parent| parent.file_name())
-        .and_then(|file_name| file_name.to_str())
-        .and_then(|name| {
-            // El nombre del directorio tiene el formato "camera_u8"
-            let prefix = "camera_";
-            if name.starts_with(prefix) {
-                if let Some(cam_id) = name.strip_prefix(prefix) {
-                    return cam_id.parse().ok();
-                }
-            }
-            None
-        })
+    #[test]
+    fn test_2_las_detecciones_en_una_region_excluida_de_la_camara_se_suprimen() {
+        let frame = CameraFrame::new(3, vec![1, 2, 3]);
+        let mut camera = Camera::new(3, -34.60, -58.38, 1);
+        camera.add_privacy_mask(PrivacyMask::Rectangle {
+            min: (-34.601, -58.381),
+            max: (-34.599, -58.379),
+        });
+        let mut cameras = HashMap::new();
+        cameras.insert(3, camera);
+
+        let (inc_tx, inc_rx) = mpsc::channel::<Incident>();
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let debouncer = Arc::new(Mutex::new(IncidentDebouncer::new(Duration::from_secs(30), 100.0)));
+        let inc_sender = IncidentSender::new(
+            Arc::new(Mutex::new(cameras)),
+            inc_tx,
+            Arc::new(Mutex::new(IncidentIdAllocator::new())),
+            debouncer,
+            Arc::new(Mutex::new(IncidentCoalescer::new(Duration::ZERO, 0.0))),
+            StringLogger::new(logger_tx),
+        );
+        let detector = MockIncidentDetector::new(vec![vec![(-34.60, -58.38), (-34.61, -58.39)]]);
+
+        process_frame(&detector, &inc_sender, &frame).unwrap();
+        drop(inc_sender);
+
+        let incidents: Vec<Incident> = inc_rx.iter().collect();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].get_position(), (-34.61, -58.39));
+    }
+
+    #[test]
+    fn test_3_dos_camaras_que_detectan_el_mismo_evento_generan_un_solo_incidente() {
+        let (inc_tx, inc_rx) = mpsc::channel::<Incident>();
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let debouncer = Arc::new(Mutex::new(IncidentDebouncer::new(Duration::from_secs(30), 100.0)));
+        let coalescer = Arc::new(Mutex::new(IncidentCoalescer::new(Duration::from_millis(100), 10.0)));
+        let inc_sender = IncidentSender::new(
+            Arc::new(Mutex::new(HashMap::new())),
+            inc_tx,
+            Arc::new(Mutex::new(IncidentIdAllocator::new())),
+            debouncer,
+            coalescer,
+            StringLogger::new(logger_tx),
+        );
+        // Las cámaras 1 y 2 detectan posiciones a unos 2 metros entre sí
+        let detector = MockIncidentDetector::new(vec![
+            vec![(-34.60370, -58.3816)],
+            vec![(-34.60372, -58.3816)],
+        ]);
+
+        process_frame(&detector, &inc_sender, &CameraFrame::new(1, vec![1, 2, 3])).unwrap();
+        process_frame(&detector, &inc_sender, &CameraFrame::new(2, vec![1, 2, 3])).unwrap();
+        // Durante la espera, todavía no se emitió ningún incidente
+        assert!(inc_rx.try_recv().is_err());
+
+        std::thread::sleep(Duration::from_millis(150));
+        inc_sender.send_ready_incidents().unwrap();
+        drop(inc_sender);
+
+        let incidents: Vec<Incident> = inc_rx.iter().collect();
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].get_id(), 1);
+        assert_eq!(incidents[0].get_position(), (-34.60370, -58.3816));
+    }
+
+    #[test]
+    fn test_4_con_el_detector_pausado_se_consumen_los_frames_sin_emitir_incidentes() {
+        let (frame_tx, frame_rx) = mpsc::channel::<CameraFrame>();
+        let (inc_tx, inc_rx) = mpsc::channel::<Incident>();
+        let (_publish_result_tx, publish_result_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let detector_paused = Arc::new(AtomicBool::new(true));
+        let detector = MockIncidentDetector::new(vec![vec![(-34.6037, -58.3816)]]);
+        let paused_c = detector_paused.clone();
+        let handle = std::thread::spawn(move || {
+            AIDetectorManager::run_with_detector(
+                Arc::new(Mutex::new(HashMap::new())),
+                (frame_rx, publish_result_rx),
+                inc_tx,
+                DetectorProperties::new(PROPERTIES_FILE).unwrap(),
+                (Box::new(detector), paused_c),
+                StateIntervals::new(Duration::ZERO, Duration::ZERO),
+                StringLogger::new(logger_tx),
+            )
+        });
+
+        // Pausado: los frames se consumen, y no se analizan
+        frame_tx.send(CameraFrame::new(1, vec![1, 2, 3])).unwrap();
+        frame_tx.send(CameraFrame::new(1, vec![1, 2, 3])).unwrap();
+        assert!(inc_rx.recv_timeout(Duration::from_millis(2500)).is_err());
+
+        // Al reanudarlo, se analiza el siguiente frame
+        detector_paused.store(false, Ordering::SeqCst);
+        frame_tx.send(CameraFrame::new(1, vec![1, 2, 3])).unwrap();
+        let incident = inc_rx.recv_timeout(Duration::from_secs(5)).unwrap();
+        assert_eq!(incident.get_position(), (-34.6037, -58.3816));
+
+        drop(frame_tx);
+        assert!(handle.join().unwrap().is_ok());
+    }
 }
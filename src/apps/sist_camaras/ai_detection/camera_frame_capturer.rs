@@ -0,0 +1,292 @@
+use notify::{event::EventKind, RecursiveMode, Watcher};
+use std::{
+    error::Error,
+    ffi::OsStr,
+    fs,
+    io::Error as ioError,
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::{
+    apps::sist_camaras::{
+        ai_detection::{
+            ai_detector_manager::PROPERTIES_FILE,
+            capture_config::{CaptureConfig, CaptureMode},
+            frame_poller::{wait_until_stable_size, FramePoller},
+            properties::DetectorProperties,
+        },
+        camera_frame::CameraFrame,
+        state_rate_limiter::SIST_CAMARAS_PROPERTIES_FILE,
+        types::shareable_cameras_type::ShCamerasType,
+    },
+    logging::string_logger::StringLogger,
+};
+
+#[derive(Debug)]
+/// Simula la captura de imágenes de las cámaras: crea un subdirectorio por cámara y lo monitorea,
+/// y cuando se crea una imagen en alguno de ellos, arma el `CameraFrame` correspondiente y lo envía
+/// internamente a Sistema Cámaras para que sea publicado por MQTT.
+/// El directorio y la forma de monitorearlo se configuran en el archivo de Sistema Cámaras (ver `CaptureConfig`).
+pub struct CameraFrameCapturer {
+    cameras: ShCamerasType,
+    frame_tx: Sender<CameraFrame>,
+    exit_requested: Arc<Mutex<bool>>,
+    properties: DetectorProperties,
+    config: CaptureConfig,
+    logger: StringLogger,
+}
+
+impl CameraFrameCapturer {
+    /// Crea y ejecuta el monitoreo de los subdirectorios de las cámaras, hasta que se solicite salir por `exit_rx`.
+    pub fn run(
+        cameras: ShCamerasType,
+        frame_tx: Sender<CameraFrame>,
+        exit_rx: Receiver<()>,
+        logger: StringLogger,
+    ) -> Result<Self, ioError> {
+        let properties = DetectorProperties::new(PROPERTIES_FILE)?;
+        let config =
+            CaptureConfig::from_properties_file(SIST_CAMARAS_PROPERTIES_FILE, properties.get_base_dir());
+        let er = Arc::new(Mutex::new(false));
+        let capturer = Self {
+            cameras,
+            frame_tx,
+            exit_requested: er.clone(),
+            properties,
+            config,
+            logger,
+        };
+
+        // Lanza hilo que pondrá en true la `er` si se solicita salir desde abm
+        let handle = thread::spawn(move || {
+            modify_if_exit_requested(er, exit_rx);
+        });
+
+        if let Err(e) = capturer.run_internal() {
+            capturer
+                .logger
+                .log(format!("Error en ejecución de captura de frames: {:?}.", e));
+        }
+
+        // Espera al hilo lanzado
+        if let Err(e) = handle.join() {
+            capturer.logger.log(format!(
+                "Error al joinear hilo de exit de captura de frames: {:?}.",
+                e
+            ));
+        }
+
+        Ok(capturer)
+    }
+
+    /// Crea y monitorea los subdirectorios correspondientes a las cámaras; cuando una imagen se crea en alguno de ellos,
+    /// la envía como `CameraFrame` de dicha cámara.
+    fn run_internal(&self) -> Result<(), Box<dyn Error>> {
+        // Crea, si no existían, el dir base y los subdirectorios, y los monitorea
+        let path = Path::new(self.config.get_source_dir());
+        self.create_dirs_tree(path)?;
+        match self.config.get_mode() {
+            CaptureMode::Watch => self.watch_dirs(path),
+            CaptureMode::Poll => self.poll_dirs(path),
+        }
+    }
+
+    /// Monitorea los subdirectorios mediante notificaciones del sistema de archivos, reaccionando a cada imagen creada.
+    fn watch_dirs(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let (tx_fs, rx_fs) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx_fs.clone())?;
+        watcher.watch(path, RecursiveMode::Recursive)?;
+        println!("Captura de frames: Monitoreando subdirs.");
+        self.logger
+            .log("Captura de frames: Monitoreando subdirs".to_string());
+
+        for event_res in rx_fs {
+            // Sale, si lo solicitaron desde abm
+            if self.exit_requested() {
+                break;
+            }
+
+            // Procesa el evento, interesa el Create, que es cuando se crea una imagen en algún subdirectorio
+            let event = event_res?;
+            if let EventKind::Create(_) = event.kind {
+                self.logger
+                    .log("Captura de frames: event ok: create".to_string());
+                if let Some(path) = event.paths.first() {
+                    if let Err(e) = self.process_created_image(path) {
+                        self.log_image_error(path, e);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Recorre periódicamente los subdirectorios, enviando las imágenes nuevas que ya terminaron de escribirse.
+    fn poll_dirs(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        let interval = self.config.get_poll_interval();
+        println!("Captura de frames: Recorriendo subdirs cada {:?}.", interval);
+        self.logger
+            .log(format!("Captura de frames: Recorriendo subdirs cada {:?}", interval));
+
+        let mut poller = FramePoller::new();
+        while !self.exit_requested() {
+            for image_path in poller.poll(path)? {
+                let res = self
+                    .is_valid_extension(&image_path)
+                    .and_then(|_| self.send_frame_for_image(&image_path));
+                if let Err(e) = res {
+                    self.log_image_error(&image_path, e);
+                }
+            }
+            thread::sleep(interval);
+        }
+
+        Ok(())
+    }
+
+    /// Envía la imagen recién creada en `path`, una vez que terminó de escribirse.
+    fn process_created_image(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if path.is_file() {
+            self.is_valid_extension(path)?;
+            // Al crearse, la imagen puede estar todavía escribiéndose
+            wait_until_stable_size(path, self.config.get_poll_interval())?;
+            self.send_frame_for_image(path)?;
+        }
+        Ok(())
+    }
+
+    fn log_image_error(&self, path: &Path, e: Box<dyn Error>) {
+        println!("Captura de frames: Error al procesar la imagen: {:?}, {:?}", path, e);
+        self.logger.log(format!(
+            "Captura de frames: Error al procesar la imagen: {:?}, {:?}",
+            path, e
+        ));
+    }
+
+    /// Crea, si no existía, la estructura de directorios necesaria para las imágenes de las cámaras.
+    fn create_dirs_tree(&self, base_dir: &Path) -> Result<(), ioError> {
+        self.create_basedir(base_dir)?;
+        self.create_subdirs(base_dir)?;
+        Ok(())
+    }
+
+    /// Crea el `base_dir` que contendrá a los subdirectorios de las cámaras, si no existía.
+    fn create_basedir(&self, base_dir: &Path) -> Result<(), ioError> {
+        // Si ya existe, lo borra y a todo su contenido, y
+        if base_dir.exists() {
+            fs::remove_dir_all(base_dir)?;
+        }
+        // lo crea
+        fs::create_dir(base_dir)?;
+
+        Ok(())
+    }
+
+    /// Crea subdirectorios de `base_dir`, uno por cada cámara, de nombre "camera_i"
+    /// donde `i` es el id de dicha cámara.
+    fn create_subdirs(&self, base_dir: &Path) -> Result<(), ioError> {
+        if let Ok(cameras) = self.cameras.lock() {
+            for cam in cameras.values() {
+                if cam.is_not_deleted() {
+                    // (para todas va a dar true, porque Sistema Camaras se está iniciando, pero así es más genérico)
+                    let cam_id = cam.get_id();
+                    self.create_subdir(base_dir, cam_id)?;
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Crea un subdirectorio de `base_dir` de nombre "camera_i" donde `i` es el u8 recibido.
+    fn create_subdir(&self, base_dir: &Path, i: u8) -> Result<(), ioError> {
+        // Concatena el nombre del subdir a crear, al dir base
+        let subdir = format!("camera_{}", i);
+        let new_dir_path = base_dir.join(subdir);
+
+        // Si no existe, lo crea
+        if !new_dir_path.exists() {
+            fs::create_dir(&new_dir_path)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lee la imagen de `path`, ya completa, y la envía como `CameraFrame` de la cámara de su subdirectorio.
+    fn send_frame_for_image(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        if path.is_file() {
+            let img = read_image(path)?;
+            if let Some(cam_id) = extract_camera_id(path) {
+                self.frame_tx.send(CameraFrame::new(cam_id, img))?;
+                self.logger
+                    .log(format!("Captura de frames: procesada imagen {:?} de cámara {}.", path, cam_id));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checkea si la extensión de la imagen es válida.
+    fn is_valid_extension(&self, image_path: &Path) -> Result<(), Box<dyn Error>> {
+        if let Some(img_extension) = image_path.extension().and_then(OsStr::to_str) {
+            // Si es una extensión válida (ej jpg o jpeg), procesar
+            let valid_extensions = self.properties.get_img_valid_extensions();
+            if valid_extensions.contains(&img_extension) {
+                return Ok(());
+            }
+        }
+        Err(Box::new(ioError::other("Extensión inválida.")))
+    }
+
+    /// Devuelve si se solicitó salir.
+    fn exit_requested(&self) -> bool {
+        if let Ok(var) = self.exit_requested.lock() {
+            return *var;
+        }
+        false
+    }
+}
+
+/// Si recibe por el `rx` que se solicitó salir, lo deja asentado en la variable compartida `exit_requested`,
+/// para que en la próxima vuelta del for el capturer se entere y finalice la ejecución.
+fn modify_if_exit_requested(exit_requested: Arc<Mutex<bool>>, rx: Receiver<()>) {
+    if rx.recv().is_ok() {
+        if let Ok(mut var) = exit_requested.lock() {
+            *var = true;
+        }
+    }
+}
+
+/// Lee la imagen del `image_path`.
+fn read_image(image_path: &Path) -> Result<Vec<u8>, Box<dyn Error>> {
+    let image_buffer = fs::read(image_path)?;
+    if image_buffer.is_empty() {
+        return Err(Box::new(ioError::other("La imagen tiene tamaño 0.")));
+    }
+    Ok(image_buffer)
+}
+
+/// Recibe el path de la imagen que se está procesando, obtiene el id
+/// de la cámara que capturó dicha imagen. Es decir la parte que sigue a "camera_"
+/// de su carpeta padre.
+fn extract_camera_id(path: &Path) -> Option<u8> {
+    // Obtiene el nombre del directorio padre
+    path.parent()
+        .and_then(|parent| parent.file_name())
+        .and_then(|file_name| file_name.to_str())
+        .and_then(|name| {
+            // El nombre del directorio tiene el formato "camera_u8"
+            let prefix = "camera_";
+            if name.starts_with(prefix) {
+                if let Some(cam_id) = name.strip_prefix(prefix) {
+                    return cam_id.parse().ok();
+                }
+            }
+            None
+        })
+}
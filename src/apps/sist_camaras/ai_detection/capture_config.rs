@@ -0,0 +1,122 @@
+use std::time::Duration;
+
+use crate::apps::properties::Properties;
+
+/// Intervalo entre dos recorridas del directorio de imágenes en modo `Poll`, si no se lo configura.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Cómo se enteran los `CameraFrameCapturer` de las nuevas imágenes de las cámaras.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CaptureMode {
+    /// Mediante notificaciones del sistema de archivos: se reacciona apenas se crea la imagen.
+    Watch,
+    /// Recorriendo periódicamente los subdirectorios de las cámaras.
+    Poll,
+}
+
+/// Configuración de la captura de frames, leída del archivo de configuración de Sistema Cámaras:
+/// el directorio del que se toman las imágenes (`frames-source-dir`), el modo (`frames-capture-mode`,
+/// `watch` o `poll`), y el intervalo entre recorridas en modo poll (`frames-poll-interval-ms`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptureConfig {
+    source_dir: String,
+    mode: CaptureMode,
+    poll_interval: Duration,
+}
+
+impl CaptureConfig {
+    pub fn new(source_dir: String, mode: CaptureMode, poll_interval: Duration) -> Self {
+        Self {
+            source_dir,
+            mode,
+            poll_interval,
+        }
+    }
+
+    /// Lee la configuración del `properties_file`. Ante una propiedad ausente o inválida se usa el valor por defecto:
+    /// `default_source_dir`, modo `Watch`, y `DEFAULT_POLL_INTERVAL`.
+    pub fn from_properties_file(properties_file: &str, default_source_dir: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => Self::from_properties(&properties, default_source_dir),
+            Err(e) => {
+                println!("Error al leer la configuración de captura de frames: {:?}", e);
+                Self::new(default_source_dir.to_string(), CaptureMode::Watch, DEFAULT_POLL_INTERVAL)
+            }
+        }
+    }
+
+    fn from_properties(properties: &Properties, default_source_dir: &str) -> Self {
+        let source_dir = properties
+            .get("frames-source-dir")
+            .map_or(default_source_dir.to_string(), |dir| dir.to_string());
+        let mode = match properties.get("frames-capture-mode").map(|s| s.as_str()) {
+            None | Some("watch") => CaptureMode::Watch,
+            Some("poll") => CaptureMode::Poll,
+            Some(_) => {
+                println!("Propiedad 'frames-capture-mode' inválida, se usa el modo watch.");
+                CaptureMode::Watch
+            }
+        };
+        let poll_interval = match properties
+            .get("frames-poll-interval-ms")
+            .map(|ms| ms.parse::<u64>())
+        {
+            Some(Ok(ms)) if ms > 0 => Duration::from_millis(ms),
+            None => DEFAULT_POLL_INTERVAL,
+            Some(_) => {
+                println!("Propiedad 'frames-poll-interval-ms' inválida, se usa el valor por defecto.");
+                DEFAULT_POLL_INTERVAL
+            }
+        };
+        Self::new(source_dir, mode, poll_interval)
+    }
+
+    /// Devuelve el directorio base que contiene a los subdirectorios de las cámaras.
+    pub fn get_source_dir(&self) -> &str {
+        self.source_dir.as_str()
+    }
+
+    pub fn get_mode(&self) -> CaptureMode {
+        self.mode
+    }
+
+    /// Devuelve el intervalo entre dos recorridas del directorio en modo `Poll`. En modo `Watch`,
+    /// es también el tiempo durante el cual el tamaño de una imagen debe mantenerse para considerarla completa.
+    pub fn get_poll_interval(&self) -> Duration {
+        self.poll_interval
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, time::Duration};
+
+    use super::{CaptureConfig, CaptureMode, DEFAULT_POLL_INTERVAL};
+
+    #[test]
+    fn test_1_se_leen_el_directorio_el_modo_y_el_intervalo_del_archivo() {
+        let file = std::env::temp_dir().join("test_capture_config_1.properties");
+        fs::write(
+            &file,
+            "frames-source-dir=./imagenes\nframes-capture-mode=poll\nframes-poll-interval-ms=250",
+        )
+        .unwrap();
+
+        let config = CaptureConfig::from_properties_file(file.to_str().unwrap(), "./default");
+        let _ = fs::remove_file(&file);
+
+        assert_eq!(
+            config,
+            CaptureConfig::new("./imagenes".to_string(), CaptureMode::Poll, Duration::from_millis(250))
+        );
+    }
+
+    #[test]
+    fn test_2_sin_propiedades_se_monitorea_el_directorio_por_defecto() {
+        let config = CaptureConfig::from_properties_file("./no_existe.properties", "./default");
+
+        assert_eq!(config.get_source_dir(), "./default");
+        assert_eq!(config.get_mode(), CaptureMode::Watch);
+        assert_eq!(config.get_poll_interval(), DEFAULT_POLL_INTERVAL);
+    }
+}
@@ -0,0 +1,101 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+/// Cantidad máxima de veces que se vuelve a medir una imagen que sigue escribiéndose, antes de descartarla.
+const MAX_STABILITY_CHECKS: u32 = 20;
+
+/// Recorre los subdirectorios de las cámaras en busca de imágenes nuevas. Una imagen se considera completa
+/// (y se la devuelve, una única vez) cuando su tamaño no cambió desde la recorrida anterior; así no se lee
+/// una imagen a medio escribir.
+#[derive(Debug, Default)]
+pub struct FramePoller {
+    last_sizes: HashMap<PathBuf, u64>,
+    processed: HashSet<PathBuf>,
+}
+
+impl FramePoller {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Recorre los subdirectorios de `base_dir`, y devuelve las imágenes que quedaron completas desde la recorrida anterior.
+    pub fn poll(&mut self, base_dir: &Path) -> Result<Vec<PathBuf>, Error> {
+        let mut ready = vec![];
+        let mut current_sizes = HashMap::new();
+        for subdir in fs::read_dir(base_dir)? {
+            let subdir = subdir?.path();
+            if !subdir.is_dir() {
+                continue;
+            }
+            for entry in fs::read_dir(&subdir)? {
+                let path = entry?.path();
+                if !path.is_file() || self.processed.contains(&path) {
+                    continue;
+                }
+                let size = fs::metadata(&path)?.len();
+                if size > 0 && self.last_sizes.get(&path) == Some(&size) {
+                    self.processed.insert(path.clone());
+                    ready.push(path);
+                } else {
+                    current_sizes.insert(path, size);
+                }
+            }
+        }
+        // Se olvidan las imágenes que ya no existen
+        self.last_sizes = current_sizes;
+        ready.sort();
+        Ok(ready)
+    }
+}
+
+/// Espera a que el tamaño de la imagen de `path` se mantenga durante `interval`, para no leerla a medio escribir.
+/// Si la imagen sigue creciendo luego de `MAX_STABILITY_CHECKS` mediciones, devuelve error.
+pub fn wait_until_stable_size(path: &Path, interval: Duration) -> Result<(), Error> {
+    let mut last_size = fs::metadata(path)?.len();
+    for _ in 0..MAX_STABILITY_CHECKS {
+        thread::sleep(interval);
+        let size = fs::metadata(path)?.len();
+        if size > 0 && size == last_size {
+            return Ok(());
+        }
+        last_size = size;
+    }
+    Err(Error::new(
+        ErrorKind::TimedOut,
+        "La imagen no terminó de escribirse.",
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::FramePoller;
+
+    #[test]
+    fn test_1_una_imagen_se_devuelve_recien_cuando_su_tamanio_deja_de_cambiar() {
+        let base_dir = std::env::temp_dir().join("test_frame_poller_1");
+        let _ = fs::remove_dir_all(&base_dir);
+        let cam_dir = base_dir.join("camera_1");
+        fs::create_dir_all(&cam_dir).unwrap();
+        let image = cam_dir.join("frame.jpg");
+        let mut poller = FramePoller::new();
+
+        fs::write(&image, [1, 2]).unwrap();
+        assert!(poller.poll(&base_dir).unwrap().is_empty());
+        // Sigue escribiéndose
+        fs::write(&image, [1, 2, 3, 4]).unwrap();
+        assert!(poller.poll(&base_dir).unwrap().is_empty());
+        // Completa: se la devuelve una única vez
+        assert_eq!(poller.poll(&base_dir).unwrap(), vec![image.clone()]);
+        assert!(poller.poll(&base_dir).unwrap().is_empty());
+
+        let _ = fs::remove_dir_all(&base_dir);
+    }
+}
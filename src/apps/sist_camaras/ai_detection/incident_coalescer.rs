@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use crate::apps::vendor::Position;
+
+/// Detección que aún no se emitió como incidente, a la espera de otras cercanas con las cuales unificarse.
+#[derive(Debug)]
+struct PendingDetection {
+    first_seen: Instant,
+    position: Position,
+    cam_ids: Vec<u8>,
+}
+
+/// Unifica las detecciones de distintas cámaras que corresponden a un mismo evento: una detección que ocurre
+/// a menos de `distance_m` metros de otra pendiente se agrega a ésta, y cada detección pendiente se emite
+/// una única vez, pasado `window` desde la primera. Así, dos cámaras superpuestas que ven el mismo incidente
+/// generan un solo incidente (con un solo id), ubicado en la posición de la primera detección.
+#[derive(Debug)]
+pub struct IncidentCoalescer {
+    window: Duration,
+    distance_m: f64,
+    pending: Vec<PendingDetection>,
+}
+
+impl IncidentCoalescer {
+    /// Crea el buffer. Con `window` igual a cero, cada detección queda lista para emitirse inmediatamente.
+    pub fn new(window: Duration, distance_m: f64) -> Self {
+        Self {
+            window,
+            distance_m,
+            pending: vec![],
+        }
+    }
+
+    /// Devuelve cada cuánto conviene consultar `take_ready`, o None si las detecciones nunca quedan en espera.
+    pub fn get_window(&self) -> Option<Duration> {
+        if self.window.is_zero() {
+            return None;
+        }
+        Some(self.window)
+    }
+
+    /// Agrega la detección en `position` de la cámara `cam_id`. Si está cerca de una detección pendiente, se unifica
+    /// con ella y devuelve true; si no, queda pendiente como una nueva y devuelve false.
+    pub fn add(&mut self, cam_id: u8, position: (f64, f64), now: Instant) -> bool {
+        let position = Position::from_lat_lon(position.0, position.1);
+        let distance_m = self.distance_m;
+        if let Some(pending) = self
+            .pending
+            .iter_mut()
+            .find(|pending| pending.position.distance_meters(&position) <= distance_m)
+        {
+            if !pending.cam_ids.contains(&cam_id) {
+                pending.cam_ids.push(cam_id);
+            }
+            return true;
+        }
+
+        self.pending.push(PendingDetection {
+            first_seen: now,
+            position,
+            cam_ids: vec![cam_id],
+        });
+        false
+    }
+
+    /// Quita y devuelve las posiciones de las detecciones pendientes cuya ventana ya venció, en orden de llegada,
+    /// junto con las cámaras que las detectaron.
+    pub fn take_ready(&mut self, now: Instant) -> Vec<((f64, f64), Vec<u8>)> {
+        let window = self.window;
+        let (ready, pending): (Vec<PendingDetection>, Vec<PendingDetection>) = self
+            .pending
+            .drain(..)
+            .partition(|pending| now.saturating_duration_since(pending.first_seen) >= window);
+        self.pending = pending;
+
+        ready
+            .into_iter()
+            .map(|detection| ((detection.position.lat(), detection.position.lon()), detection.cam_ids))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::IncidentCoalescer;
+
+    #[test]
+    fn test_1_detecciones_cercanas_de_distintas_camaras_se_unifican_dentro_de_la_ventana() {
+        let mut coalescer = IncidentCoalescer::new(Duration::from_secs(2), 10.0);
+        let now = Instant::now();
+
+        assert!(!coalescer.add(1, (-34.60370, -58.3816), now));
+        // A unos 2 metros, desde otra cámara: se unifica
+        assert!(coalescer.add(2, (-34.60372, -58.3816), now + Duration::from_millis(500)));
+        // A más de 10 metros: queda pendiente aparte
+        assert!(!coalescer.add(2, (-34.6100, -58.3816), now + Duration::from_secs(1)));
+
+        assert!(coalescer.take_ready(now + Duration::from_secs(1)).is_empty());
+        assert_eq!(
+            coalescer.take_ready(now + Duration::from_secs(2)),
+            vec![((-34.60370, -58.3816), vec![1, 2])]
+        );
+        assert_eq!(
+            coalescer.take_ready(now + Duration::from_secs(3)),
+            vec![((-34.6100, -58.3816), vec![2])]
+        );
+        assert!(coalescer.take_ready(now + Duration::from_secs(10)).is_empty());
+    }
+
+    #[test]
+    fn test_2_sin_ventana_las_detecciones_quedan_listas_inmediatamente() {
+        let mut coalescer = IncidentCoalescer::new(Duration::ZERO, 0.0);
+        let now = Instant::now();
+
+        coalescer.add(1, (-34.60, -58.38), now);
+        assert_eq!(coalescer.get_window(), None);
+        assert_eq!(coalescer.take_ready(now), vec![((-34.60, -58.38), vec![1])]);
+    }
+}
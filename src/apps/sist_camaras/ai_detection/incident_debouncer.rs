@@ -0,0 +1,84 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::apps::vendor::Position;
+
+/// Incidentes emitidos por una cámara: instante de emisión y posición.
+type EmittedIncidents = Vec<(Instant, Position)>;
+
+/// Filtra las detecciones repetidas de una misma cámara: una detección que ocurre a menos de
+/// `distance_m` metros de un incidente emitido por la misma cámara hace menos de `window`, se suprime.
+/// Evita inundar el topic de incidentes cuando el detector se dispara varias veces sobre la misma escena.
+#[derive(Debug)]
+pub struct IncidentDebouncer {
+    window: Duration,
+    distance_m: f64,
+    emitted_by_cam: HashMap<u8, EmittedIncidents>,
+}
+
+impl IncidentDebouncer {
+    /// Crea el filtro. Con `window` igual a cero, no se suprime ninguna detección.
+    pub fn new(window: Duration, distance_m: f64) -> Self {
+        Self {
+            window,
+            distance_m,
+            emitted_by_cam: HashMap::new(),
+        }
+    }
+
+    /// Devuelve si la detección en `position` de la cámara `cam_id` debe emitirse como incidente,
+    /// y en ese caso la registra para filtrar las siguientes.
+    pub fn should_emit(&mut self, cam_id: u8, position: (f64, f64), now: Instant) -> bool {
+        let position = Position::from_lat_lon(position.0, position.1);
+        let window = self.window;
+        let emitted = self.emitted_by_cam.entry(cam_id).or_default();
+        // Se descartan los incidentes que ya salieron de la ventana de tiempo
+        emitted.retain(|(emitted_at, _)| now.duration_since(*emitted_at) < window);
+
+        let is_repeated = emitted
+            .iter()
+            .any(|(_, emitted_pos)| emitted_pos.distance_meters(&position) <= self.distance_m);
+        if is_repeated {
+            return false;
+        }
+
+        if !window.is_zero() {
+            emitted.push((now, position));
+        }
+        true
+    }
+
+    /// Olvida los incidentes emitidos a menos de `distance_m` metros de `position`, por cualquier cámara,
+    /// para que una nueva detección allí vuelva a emitirse (ej. si el incidente no pudo publicarse).
+    pub fn forget(&mut self, position: (f64, f64)) {
+        let position = Position::from_lat_lon(position.0, position.1);
+        for emitted in self.emitted_by_cam.values_mut() {
+            emitted.retain(|(_, emitted_pos)| emitted_pos.distance_meters(&position) > self.distance_m);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::IncidentDebouncer;
+
+    #[test]
+    fn test_1_se_suprimen_detecciones_cercanas_de_la_misma_camara_dentro_de_la_ventana() {
+        let mut debouncer = IncidentDebouncer::new(Duration::from_secs(30), 100.0);
+        let now = Instant::now();
+
+        assert!(debouncer.should_emit(1, (-34.6037, -58.3816), now));
+        // A unos 20 metros, misma cámara: se suprime
+        assert!(!debouncer.should_emit(1, (-34.6035, -58.3816), now + Duration::from_secs(5)));
+        // Misma posición pero de otra cámara: se emite
+        assert!(debouncer.should_emit(2, (-34.6035, -58.3816), now + Duration::from_secs(5)));
+        // A más de 100 metros: se emite
+        assert!(debouncer.should_emit(1, (-34.6100, -58.3816), now + Duration::from_secs(5)));
+        // Pasada la ventana, se vuelve a emitir
+        assert!(debouncer.should_emit(1, (-34.6035, -58.3816), now + Duration::from_secs(31)));
+    }
+}
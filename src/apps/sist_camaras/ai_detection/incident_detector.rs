@@ -0,0 +1,62 @@
+use std::{collections::VecDeque, fmt::Debug, sync::Mutex};
+
+use crate::apps::sist_camaras::camera_frame::CameraFrame;
+
+/// Detector de incidentes utilizado por el `AIDetectorManager`.
+/// Permite intercambiar el detector que consulta al proveedor de inteligencia artificial
+/// por otro (ej. uno de prueba), sin modificar al manager.
+pub trait IncidentDetector: Debug {
+    /// Analiza el `frame` y devuelve las posiciones (lat, lon) de los incidentes detectados en él.
+    /// Si no se detectó ninguno (o hubo un error al analizarlo), devuelve un vector vacío.
+    fn detect(&self, frame: &CameraFrame) -> Vec<(f64, f64)>;
+}
+
+/// Detector determinístico, para tests y ejecuciones sin conexión al proveedor de inteligencia artificial.
+/// Cada llamado a `detect` devuelve el siguiente elemento de la lista configurada, sin importar la imagen;
+/// una vez agotada la lista, no detecta más incidentes.
+#[derive(Debug)]
+pub struct MockIncidentDetector {
+    script: Mutex<VecDeque<Vec<(f64, f64)>>>,
+}
+
+impl MockIncidentDetector {
+    /// Crea el detector, que en el i-ésimo llamado a `detect` devolverá `script[i]`.
+    pub fn new(script: Vec<Vec<(f64, f64)>>) -> Self {
+        Self {
+            script: Mutex::new(script.into()),
+        }
+    }
+}
+
+impl IncidentDetector for MockIncidentDetector {
+    fn detect(&self, _frame: &CameraFrame) -> Vec<(f64, f64)> {
+        if let Ok(mut script) = self.script.lock() {
+            if let Some(positions) = script.pop_front() {
+                return positions;
+            }
+        }
+        vec![]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IncidentDetector, MockIncidentDetector};
+    use crate::apps::sist_camaras::camera_frame::CameraFrame;
+
+    #[test]
+    fn test_1_mock_detector_devuelve_la_lista_en_orden() {
+        let detector = MockIncidentDetector::new(vec![
+            vec![(-34.60, -58.38)],
+            vec![],
+            vec![(-34.61, -58.39), (-34.62, -58.40)],
+        ]);
+        let frame = CameraFrame::new(1, vec![1, 2, 3]);
+
+        assert_eq!(detector.detect(&frame), vec![(-34.60, -58.38)]);
+        assert!(detector.detect(&frame).is_empty());
+        assert_eq!(detector.detect(&frame).len(), 2);
+        // Agotada la lista, no detecta más incidentes
+        assert!(detector.detect(&frame).is_empty());
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    collections::HashMap,
+    sync::{mpsc::Sender, Arc, Mutex},
+};
+
+use crate::{
+    apps::{
+        incident_data::incident::Incident,
+        sist_camaras::ai_detection::incident_debouncer::IncidentDebouncer,
+    },
+    logging::string_logger::StringLogger,
+};
+
+/// Resultado de publicar por MQTT un incidente creado por el detector, que Sistema Cámaras le devuelve
+/// al `AIDetectorManager`.
+#[derive(Debug, Clone)]
+pub enum IncidentPublishResult {
+    /// Se recibió el ack del publish.
+    Delivered(Incident),
+    /// No se pudo publicar (ej. nunca se recibió su ack).
+    Failed(Incident),
+}
+
+/// Procesa los resultados de publicar los incidentes del detector: si un incidente no pudo publicarse,
+/// lo vuelve a enviar para que se publique, hasta `max_retries` veces. Si aun así no se pudo publicar,
+/// desiste, y olvida la detección en el `IncidentDebouncer`, para que la escena no quede como ya reportada
+/// y una nueva detección en ella vuelva a generar un incidente.
+#[derive(Debug)]
+pub struct IncidentPublishFeedback {
+    inc_tx: Sender<Incident>,
+    debouncer: Arc<Mutex<IncidentDebouncer>>,
+    max_retries: u8,
+    retries_by_inc: HashMap<u8, u8>,
+    logger: StringLogger,
+}
+
+impl IncidentPublishFeedback {
+    pub fn new(
+        inc_tx: Sender<Incident>,
+        debouncer: Arc<Mutex<IncidentDebouncer>>,
+        max_retries: u8,
+        logger: StringLogger,
+    ) -> Self {
+        Self {
+            inc_tx,
+            debouncer,
+            max_retries,
+            retries_by_inc: HashMap::new(),
+            logger,
+        }
+    }
+
+    /// Procesa el resultado de publicar un incidente.
+    pub fn handle(&mut self, result: IncidentPublishResult) {
+        match result {
+            IncidentPublishResult::Delivered(inc) => {
+                self.retries_by_inc.remove(&inc.get_id());
+                self.logger
+                    .log(format!("Detector: se confirmó la publicación del inc {}.", inc.get_id()));
+            }
+            IncidentPublishResult::Failed(inc) => self.retry_or_give_up(inc),
+        }
+    }
+
+    fn retry_or_give_up(&mut self, inc: Incident) {
+        let retries = self.retries_by_inc.entry(inc.get_id()).or_insert(0);
+        if *retries < self.max_retries {
+            *retries += 1;
+            self.logger.log(format!(
+                "Detector: no se pudo publicar el inc {}, se reintenta ({} de {}).",
+                inc.get_id(),
+                retries,
+                self.max_retries
+            ));
+            if self.inc_tx.send(inc).is_err() {
+                println!("Detector: Error al reenviar el incidente para publicarlo.");
+            }
+            return;
+        }
+
+        self.retries_by_inc.remove(&inc.get_id());
+        if let Ok(mut debouncer) = self.debouncer.lock() {
+            debouncer.forget(inc.get_position());
+        }
+        let msg = format!(
+            "ERROR: Detector: el inc {} no pudo publicarse luego de {} reintentos, se desiste.",
+            inc.get_id(),
+            self.max_retries
+        );
+        println!("{}", msg);
+        self.logger.log(msg);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{mpsc, Arc, Mutex},
+        time::{Duration, Instant},
+    };
+
+    use super::{IncidentPublishFeedback, IncidentPublishResult};
+    use crate::{
+        apps::{
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            sist_camaras::ai_detection::incident_debouncer::IncidentDebouncer,
+        },
+        logging::string_logger::StringLogger,
+    };
+
+    #[test]
+    fn test_1_un_incidente_que_no_se_publica_se_reintenta_hasta_el_maximo_y_luego_se_olvida() {
+        let (inc_tx, inc_rx) = mpsc::channel::<Incident>();
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let position = (-34.6037, -58.3816);
+        let debouncer = Arc::new(Mutex::new(IncidentDebouncer::new(Duration::from_secs(30), 100.0)));
+        assert!(debouncer.lock().unwrap().should_emit(1, position, Instant::now()));
+        let mut feedback = IncidentPublishFeedback::new(inc_tx, debouncer.clone(), 2, StringLogger::new(logger_tx));
+        let inc = Incident::new(1, position, IncidentSource::Automated);
+
+        // Dos reintentos
+        feedback.handle(IncidentPublishResult::Failed(inc.clone()));
+        feedback.handle(IncidentPublishResult::Failed(inc.clone()));
+        assert_eq!(inc_rx.try_iter().count(), 2);
+        assert!(!debouncer.lock().unwrap().should_emit(1, position, Instant::now()));
+
+        // Se desiste: no se reenvía, y la escena ya no figura como reportada
+        feedback.handle(IncidentPublishResult::Failed(inc.clone()));
+        assert!(inc_rx.try_recv().is_err());
+        assert!(debouncer.lock().unwrap().should_emit(1, position, Instant::now()));
+
+        // Un incidente publicado no se reenvía
+        feedback.handle(IncidentPublishResult::Delivered(Incident::new(2, position, IncidentSource::Automated)));
+        assert!(inc_rx.try_recv().is_err());
+    }
+}
@@ -1,4 +1,11 @@
 pub mod ai_detector_manager;
 pub mod ai_detector;
 pub mod api_credentials;
+pub mod camera_frame_capturer;
+pub mod capture_config;
+pub mod frame_poller;
+pub mod incident_coalescer;
+pub mod incident_debouncer;
+pub mod incident_detector;
+pub mod incident_publish_feedback;
 pub mod properties;
\ No newline at end of file
@@ -2,6 +2,9 @@ use std::io::{Error, ErrorKind};
 
 use crate::apps::properties::Properties;
 
+/// Cantidad de veces que se reintenta publicar un incidente que no pudo publicarse, si no se configura otra.
+const DEFAULT_INC_PUBLISH_MAX_RETRIES: u8 = 3;
+
 /// Struct que posee las constantes para el módulo de detección automática de incidentes del Sistema Cámaras,
 /// cargadas desde su archivo de configuración.
 #[derive(Debug, PartialEq, Clone)]
@@ -12,6 +15,22 @@ pub struct DetectorProperties {
     inc_threshold: f64,
     img_valid_extension1: String,
     img_valid_extension2: String,
+    detector_type: DetectorType,
+    mock_incidents: Vec<(f64, f64)>,
+    inc_debounce_window_secs: u64,
+    inc_debounce_distance_m: f64,
+    inc_merge_window_ms: u64,
+    inc_merge_distance_m: f64,
+    inc_publish_max_retries: u8,
+}
+
+/// Detector de incidentes a utilizar por el `AIDetectorManager`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum DetectorType {
+    /// Consulta al proveedor de inteligencia artificial.
+    Ai,
+    /// Detector de prueba, que devuelve los incidentes de `mock_incidents`.
+    Mock,
 }
 
 impl DetectorProperties {
@@ -86,6 +105,58 @@ impl DetectorProperties {
             ));
         }
 
+        // Opcionales: si no se indica, se utiliza el detector con inteligencia artificial
+        let detector_type = match global_properties.get("detector_type").map(|s| s.as_str()) {
+            None | Some("ai") => DetectorType::Ai,
+            Some("mock") => DetectorType::Mock,
+            Some(_) => {
+                return Err(Error::new(ErrorKind::InvalidInput, "detector_type"));
+            }
+        };
+
+        let mock_incidents = match global_properties.get("mock_incidents") {
+            Some(prop) => parse_positions(prop)?,
+            None => vec![],
+        };
+
+        // Opcionales: ventana y distancia para suprimir detecciones repetidas de una misma cámara
+        let inc_debounce_window_secs: u64 = match global_properties.get("inc_debounce_window_secs") {
+            Some(prop) => prop
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "inc_debounce_window_secs"))?,
+            None => 0,
+        };
+
+        let inc_debounce_distance_m: f64 = match global_properties.get("inc_debounce_distance_m") {
+            Some(prop) => prop
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "inc_debounce_distance_m"))?,
+            None => 0.0,
+        };
+
+        // Opcionales: ventana y distancia para unificar las detecciones de distintas cámaras sobre un mismo evento
+        let inc_merge_window_ms: u64 = match global_properties.get("inc_merge_window_ms") {
+            Some(prop) => prop
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "inc_merge_window_ms"))?,
+            None => 0,
+        };
+
+        let inc_merge_distance_m: f64 = match global_properties.get("inc_merge_distance_m") {
+            Some(prop) => prop
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "inc_merge_distance_m"))?,
+            None => 0.0,
+        };
+
+        // Opcional: cantidad de reintentos para un incidente que no pudo publicarse
+        let inc_publish_max_retries: u8 = match global_properties.get("inc_publish_max_retries") {
+            Some(prop) => prop
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "inc_publish_max_retries"))?,
+            None => DEFAULT_INC_PUBLISH_MAX_RETRIES,
+        };
+
         Ok(Self {
             base_dir,
             api_credentials_file_path,
@@ -93,6 +164,13 @@ impl DetectorProperties {
             inc_threshold,
             img_valid_extension1,
             img_valid_extension2,
+            detector_type,
+            mock_incidents,
+            inc_debounce_window_secs,
+            inc_debounce_distance_m,
+            inc_merge_window_ms,
+            inc_merge_distance_m,
+            inc_publish_max_retries,
         })
     }
 
@@ -128,8 +206,56 @@ impl DetectorProperties {
         self.img_valid_extension2.as_str()
     }
 
+    /// Devuelve qué detector de incidentes se debe utilizar.
+    pub fn get_detector_type(&self) -> DetectorType {
+        self.detector_type
+    }
+
+    /// Devuelve las posiciones de los incidentes que emitirá el detector de prueba, en orden.
+    pub fn get_mock_incidents(&self) -> Vec<(f64, f64)> {
+        self.mock_incidents.clone()
+    }
+
+    /// Devuelve durante cuántos segundos se suprimen las detecciones cercanas a un incidente ya emitido por la misma cámara.
+    pub fn get_inc_debounce_window_secs(&self) -> u64 {
+        self.inc_debounce_window_secs
+    }
+
+    /// Devuelve la distancia en metros dentro de la cual una detección se considera repetida.
+    pub fn get_inc_debounce_distance_m(&self) -> f64 {
+        self.inc_debounce_distance_m
+    }
+
+    /// Devuelve durante cuántos milisegundos se espera a otras detecciones del mismo evento antes de emitir el incidente.
+    pub fn get_inc_merge_window_ms(&self) -> u64 {
+        self.inc_merge_window_ms
+    }
+
+    /// Devuelve la distancia en metros dentro de la cual detecciones de distintas cámaras se unifican en un incidente.
+    pub fn get_inc_merge_distance_m(&self) -> f64 {
+        self.inc_merge_distance_m
+    }
+
+    /// Devuelve cuántas veces se reintenta publicar un incidente que no pudo publicarse, antes de desistir.
+    pub fn get_inc_publish_max_retries(&self) -> u8 {
+        self.inc_publish_max_retries
+    }
+
     /// Devuelve vector con las extensiones de imagen válidas a procesar.
     pub fn get_img_valid_extensions(&self) -> Vec<&str> {
         vec![self.img_valid_extension1.as_str(), self.img_valid_extension2.as_str()]
     }
 }
+
+/// Parsea posiciones con formato "lat,lon;lat,lon;...". Un string vacío no contiene posiciones.
+fn parse_positions(value: &str) -> Result<Vec<(f64, f64)>, Error> {
+    let mut positions = vec![];
+    for position in value.split(';').filter(|p| !p.trim().is_empty()) {
+        let mut coords = position.split(',').map(|c| c.trim().parse::<f64>());
+        match (coords.next(), coords.next(), coords.next()) {
+            (Some(Ok(lat)), Some(Ok(lon)), None) => positions.push((lat, lon)),
+            _ => return Err(Error::new(ErrorKind::InvalidInput, "mock_incidents")),
+        }
+    }
+    Ok(positions)
+}
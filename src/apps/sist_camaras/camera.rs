@@ -1,4 +1,24 @@
-use crate::apps::{incident_data::incident_info::IncidentInfo, sist_camaras::camera_state::CameraState};
+use std::io::{Error, ErrorKind};
+
+use crate::apps::{
+    coordinates::check_finite_coordinates,
+    incident_data::incident_info::IncidentInfo,
+    sist_camaras::{camera_state::CameraState, field_of_view::FieldOfView, privacy_mask::PrivacyMask},
+    vendor::Position,
+    wire_version::split_version,
+};
+
+use serde::Serialize;
+
+/// Distancia en metros que cubre una cámara de rango 0.
+const RANGE_BASE_METERS: f64 = 125.0;
+/// Metros que se agregan al alcance de una cámara por cada unidad de rango (aprox. una cuadra).
+const RANGE_METERS_PER_UNIT: f64 = 110.0;
+/// Cantidad máxima de cámaras lindantes de una cámara: al serializarla, su cantidad se envía en un byte.
+const MAX_BORDER_CAMERAS: usize = u8::MAX as usize;
+/// Versión del formato de los bytes de la cámara (ver `split_version`): la 1 termina en el flag de eliminada,
+/// la 2 agrega las regiones excluidas, y la 3 el campo de visión.
+const CAMERA_WIRE_VERSION: u8 = 3;
 
 #[derive(Debug, PartialEq)]
 /// Struct que representa el estado de una de las cámaras del sistema central de cámaras.
@@ -9,8 +29,12 @@ use crate::apps::{incident_data::incident_info::IncidentInfo, sist_camaras::came
 /// - rango dentro del cual interesará manejar incidentes, simllar a un radio;
 /// - border_cameras: vector con los ids de sus cámaras lindantes;
 /// - deleted: campo que indica si la Camera ha pasado por un borrado lógico en el sistema central de cámaras;
-/// - incs_being_managed: vector con los ids de los incidentes a los que la Camera está prestando atención, esto es, ids de los incidentes que ocasionan que esta Camera esté en estado activo.
-#[derive(Clone)]
+/// - incs_being_managed: vector con los ids (y posiciones) de los incidentes a los que la Camera está prestando atención, esto es, los incidentes que ocasionan que esta Camera esté en estado activo.
+/// - privacy_masks: regiones de su campo en las que no registra incidentes (vacío: no se excluye ninguna).
+/// - field_of_view: cono hacia el que mira, dentro del cual registra incidentes (por defecto, todas las direcciones).
+/// - dirty: campo que indica si la Camera fue modificada desde la última vez que se envió para ser publicada
+///   (esto es, si está marcada como no enviada).
+#[derive(Clone, Serialize)]
 pub struct Camera {
     id: u8,
     latitude: f64,
@@ -19,7 +43,11 @@ pub struct Camera {
     range: u8,
     border_cameras: Vec<u8>,
     deleted: bool,
-    incs_being_managed: Vec<IncidentInfo>, // info (id y src) de los incidentes a los que está prestando atención
+    incs_being_managed: Vec<(IncidentInfo, (f64, f64))>, // info (id y src) y posición de los incidentes a los que está prestando atención
+    privacy_masks: Vec<PrivacyMask>,
+    field_of_view: FieldOfView,
+    #[serde(skip)]
+    dirty: bool,
 }
 
 impl Camera {
@@ -34,27 +62,46 @@ impl Camera {
             border_cameras: vec![],
             deleted: false,
             incs_being_managed: vec![],
+            privacy_masks: vec![],
+            field_of_view: FieldOfView::full(),
+            dirty: true,
         }
     }
 
     /// Pasa un struct Camera a bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = vec![];
-        bytes.push(self.id);
+        let mut bytes = vec![CAMERA_WIRE_VERSION, self.id];
         bytes.extend_from_slice(&self.latitude.to_be_bytes());
         bytes.extend_from_slice(&self.longitude.to_be_bytes());
         bytes.extend_from_slice(&self.state.to_byte());
         bytes.extend_from_slice(&self.range.to_be_bytes());
-        bytes.extend_from_slice(&(self.border_cameras.len() as u8).to_be_bytes());
-        for camera in &self.border_cameras {
-            bytes.push(*camera);
-        }
+        let border_cameras = self.get_serializable_border_cameras();
+        bytes.extend_from_slice(&(border_cameras.len() as u8).to_be_bytes());
+        bytes.extend_from_slice(border_cameras);
         bytes.push(self.deleted as u8);
+        bytes.push(self.privacy_masks.len() as u8);
+        for mask in &self.privacy_masks {
+            bytes.extend_from_slice(&mask.to_bytes());
+        }
+        bytes.extend_from_slice(&self.field_of_view.to_bytes());
         bytes
     }
 
-    /// Lee bytes para devolver un struct Camera.
-    pub fn from_bytes(bytes: &[u8]) -> Self {
+    /// Lee bytes de cualquier versión para devolver un struct Camera, o error si sus coordenadas no son finitas.
+    /// Los campos posteriores a los que se conocen se ignoran, y los que no trae una versión anterior quedan vacíos.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let (version, bytes) = split_version(bytes, "Camera")?;
+        // Hasta la cantidad de cámaras lindantes, luego ellas y el flag de eliminada
+        let has_enough_bytes = match bytes.get(19) {
+            Some(border_cameras_len) => bytes.len() > 20 + *border_cameras_len as usize,
+            None => false,
+        };
+        if !has_enough_bytes {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "No hay suficientes bytes para una Camera",
+            ));
+        }
         let id = bytes[0];
         let latitude = f64::from_be_bytes([
             bytes[1], bytes[2], bytes[3], bytes[4], bytes[5], bytes[6], bytes[7], bytes[8],
@@ -62,7 +109,8 @@ impl Camera {
         let longitude = f64::from_be_bytes([
             bytes[9], bytes[10], bytes[11], bytes[12], bytes[13], bytes[14], bytes[15], bytes[16],
         ]);
-        let state = CameraState::from_byte([bytes[17]]);
+        check_finite_coordinates(latitude, longitude)?;
+        let state = CameraState::from_byte([bytes[17]])?;
         let range = bytes[18];
         let border_cameras_len = bytes[19];
         let mut border_cameras = vec![];
@@ -70,7 +118,16 @@ impl Camera {
             border_cameras.push(bytes[20 + i as usize]);
         }
         let deleted = bytes[20 + border_cameras_len as usize] == 1;
-        Self {
+        let masks_start = 21 + border_cameras_len as usize;
+        let (privacy_masks, masks_len) = match version {
+            1 => (vec![], 0),
+            _ => Self::privacy_masks_from_bytes(&bytes[masks_start..]),
+        };
+        let field_of_view = match version {
+            1 | 2 => FieldOfView::full(),
+            _ => FieldOfView::from_bytes(&bytes[masks_start + masks_len..])?,
+        };
+        Ok(Self {
             id,
             latitude,
             longitude,
@@ -79,7 +136,30 @@ impl Camera {
             border_cameras,
             deleted,
             incs_being_managed: vec![],
+            privacy_masks,
+            field_of_view,
+            dirty: true,
+        })
+    }
+
+    /// Lee las regiones excluidas, precedidas por su cantidad. Devuelve las regiones y la cantidad de bytes leídos.
+    /// Si los bytes no las incluyen, devuelve un vector vacío.
+    fn privacy_masks_from_bytes(bytes: &[u8]) -> (Vec<PrivacyMask>, usize) {
+        let mut privacy_masks = vec![];
+        let mut idx = 0;
+        if let Some(masks_len) = bytes.first() {
+            idx = 1;
+            for _ in 0..*masks_len {
+                match PrivacyMask::from_bytes(&bytes[idx..]) {
+                    Some((mask, read)) => {
+                        privacy_masks.push(mask);
+                        idx += read;
+                    }
+                    None => break,
+                }
+            }
         }
+        (privacy_masks, idx)
     }
 
     /// Muestra por pantalla los datos de la cámara.
@@ -92,16 +172,106 @@ impl Camera {
         println!("Cámaras lindantes: {:?}\n", self.border_cameras);
     }
 
-    /// Devuelve si el incidente de coordenadas `(inc_coord_x, inc_coord_y)`
-    /// está en el rango de la cámara `Self`.
+    /// Devuelve si el incidente de coordenadas `(inc_coord_x, inc_coord_y)` está en el rango de la cámara `Self`,
+    /// dentro de su campo de visión, y fuera de sus regiones excluidas.
     pub fn will_register(&self, (latitude, longitude): (f64, f64)) -> bool {
         self.is_within_range_from_self(latitude, longitude, self.range as f64)
+            && self.field_of_view.contains(self.get_position(), (latitude, longitude))
+            && !self.is_masked((latitude, longitude))
     }
 
-    /// Modifica su estado al recibido por parámetro, y se marca un atributo
+    /// Como `will_register`, pero con el rango extendido en `margin_meters`. Una cámara que ya registra un
+    /// incidente deja de hacerlo recién al salir éste del rango extendido: así, un incidente que oscila en el
+    /// borde del rango no la hace alternar entre Active y SavingMode.
+    pub fn will_keep_registering(&self, position: (f64, f64), margin_meters: f64) -> bool {
+        self.distance_meters_to(position) <= self.get_range_meters() + margin_meters
+            && self.field_of_view.contains(self.get_position(), position)
+            && !self.is_masked(position)
+    }
+
+    /// Establece el cono hacia el que mira la cámara.
+    pub fn set_field_of_view(&mut self, field_of_view: FieldOfView) {
+        if self.field_of_view != field_of_view {
+            self.field_of_view = field_of_view;
+            self.mark_as_not_sent();
+        }
+    }
+
+    /// Devuelve el cono hacia el que mira la cámara.
+    pub fn get_field_of_view(&self) -> FieldOfView {
+        self.field_of_view
+    }
+
+    /// Devuelve si la posición recibida cae dentro de alguna de sus regiones excluidas.
+    pub fn is_masked(&self, position: (f64, f64)) -> bool {
+        self.privacy_masks.iter().any(|mask| mask.contains(position))
+    }
+
+    /// Agrega una región de su campo en la que no se registrarán incidentes.
+    pub fn add_privacy_mask(&mut self, mask: PrivacyMask) {
+        self.privacy_masks.push(mask);
+        self.mark_as_not_sent();
+    }
+
+    /// Devuelve las regiones de su campo en las que no registra incidentes.
+    pub fn get_privacy_masks(&self) -> &Vec<PrivacyMask> {
+        &self.privacy_masks
+    }
+
+    /// Modifica su estado al recibido por parámetro, y si efectivamente cambió, se marca como no enviada
     /// para luego ser detectada como modificada y enviada.
     pub fn set_state_to(&mut self, new_state: CameraState) {
-        self.state = new_state;
+        if self.state != new_state {
+            self.state = new_state;
+            self.mark_as_not_sent();
+        }
+    }
+
+    /// Devuelve si la cámara fue modificada desde la última vez que se envió para ser publicada.
+    pub fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    /// Marca a la cámara como enviada, luego de enviarla para ser publicada.
+    pub fn mark_as_sent(&mut self) {
+        self.dirty = false;
+    }
+
+    /// Marca a la cámara como no enviada, para que se la publique con su modificación.
+    fn mark_as_not_sent(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Devuelve las cámaras lindantes a serializar: como máximo `MAX_BORDER_CAMERAS`, ya que su cantidad
+    /// se envía en un byte. Si hubiera más, se informa y se envían solamente las primeras.
+    fn get_serializable_border_cameras(&self) -> &[u8] {
+        if self.border_cameras.len() > MAX_BORDER_CAMERAS {
+            println!(
+                "ERROR: la cámara {} tiene {} cámaras lindantes, se envían solamente {}.",
+                self.id,
+                self.border_cameras.len(),
+                MAX_BORDER_CAMERAS
+            );
+            return &self.border_cameras[..MAX_BORDER_CAMERAS];
+        }
+        &self.border_cameras
+    }
+
+    /// Agrega `id` a su lista de cámaras lindantes, si no estaba ya en ella y todavía no se alcanzó
+    /// `MAX_BORDER_CAMERAS`. Devuelve si se agregó.
+    fn add_border_camera(&mut self, id: u8) -> bool {
+        if self.border_cameras.contains(&id) {
+            return false;
+        }
+        if self.border_cameras.len() >= MAX_BORDER_CAMERAS {
+            println!(
+                "ERROR: la cámara {} ya tiene el máximo de {} cámaras lindantes, no se agrega la {}.",
+                self.id, MAX_BORDER_CAMERAS, id
+            );
+            return false;
+        }
+        self.border_cameras.push(id);
+        true
     }
 
     /// Devuelve un vector con los ids de sus cámaras lindantes.
@@ -112,9 +282,13 @@ impl Camera {
     /// Agrega el inc_id a su lista de incidentes a los que le presta atención,
     /// y se cambia el estado a activo. Maneja su marcado.
     /// Devuelve si cambió su estado interno (a Activo).
-    pub fn append_to_incs_being_managed(&mut self, inc_info: IncidentInfo) -> bool {
+    pub fn append_to_incs_being_managed(
+        &mut self,
+        inc_info: IncidentInfo,
+        inc_position: (f64, f64),
+    ) -> bool {
         let mut state_has_changed = false;
-        self.incs_being_managed.push(inc_info);
+        self.incs_being_managed.push((inc_info, inc_position));
         // Si ya estaba en estado activo, la dejo como estaba (para no marcarla como modificada)
         if self.state != CameraState::Active {
             self.set_state_to(CameraState::Active);
@@ -128,10 +302,14 @@ impl Camera {
     /// Devuelve si cambió su estado interno (a Ahorro de energía).
     pub fn remove_from_incs_being_managed(&mut self, inc_info: IncidentInfo) -> bool {
         let mut state_has_changed = false;
-        if let Some(pos_de_inc_info) = self.incs_being_managed.iter().position(|&x| x == inc_info) {
+        if let Some(pos_de_inc_info) = self
+            .incs_being_managed
+            .iter()
+            .position(|(info, _)| *info == inc_info)
+        {
             self.incs_being_managed.remove(pos_de_inc_info);
-            // Maneja su lista y se cambia el estado si corresponde
-            if self.incs_being_managed.is_empty() {
+            // Maneja su lista y se cambia el estado si corresponde (una cámara fallada permanece fallada)
+            if self.incs_being_managed.is_empty() && !self.is_failed() {
                 self.set_state_to(CameraState::SavingMode);
                 state_has_changed = true;
             }
@@ -139,9 +317,37 @@ impl Camera {
         state_has_changed
     }
 
+    /// Actualiza la posición del incidente `inc_info`, si la cámara le está prestando atención.
+    /// No cambia su estado, por lo que no la marca como no enviada.
+    pub fn update_inc_position(&mut self, inc_info: IncidentInfo, inc_position: (f64, f64)) {
+        for (info, position) in self.incs_being_managed.iter_mut() {
+            if *info == inc_info {
+                *position = inc_position;
+            }
+        }
+    }
+
+    /// Marca a la cámara como fallada. Deja de prestar atención a los incidentes que manejaba,
+    /// y no participa de la lógica de activación hasta que vuelva a funcionar.
+    pub fn set_failed(&mut self) {
+        self.set_state_to(CameraState::Failed);
+        self.incs_being_managed.clear();
+    }
+
+    /// Devuelve si la cámara se encuentra fallada.
+    pub fn is_failed(&self) -> bool {
+        self.state == CameraState::Failed
+    }
+
     /// Función getter utilizada con propósitos de debugging.
     pub fn get_id_and_incs_for_debug_display(&self) -> (u8, Vec<IncidentInfo>) {
-        (self.id, self.incs_being_managed.to_vec())
+        let incs = self.incs_being_managed.iter().map(|(info, _)| *info).collect();
+        (self.id, incs)
+    }
+
+    /// Devuelve info y posición de los incidentes a los que la cámara está prestando atención.
+    pub fn get_incs_being_managed(&self) -> Vec<(IncidentInfo, (f64, f64))> {
+        self.incs_being_managed.to_vec()
     }
 
     /// Devuelve si la cámara ha pasado o no por un borrado lógico.
@@ -152,6 +358,15 @@ impl Camera {
     /// Hace un borrado lógico de la cámara.
     pub fn delete_camera(&mut self) {
         self.deleted = true;
+        self.mark_as_not_sent();
+    }
+
+    /// Modifica el rango de la cámara.
+    pub fn set_range(&mut self, new_range: u8) {
+        if self.range != new_range {
+            self.range = new_range;
+            self.mark_as_not_sent();
+        }
     }
 
     /// Devuelve el rango ajustado de la cámara.
@@ -196,10 +411,14 @@ impl Camera {
             const_border_range,
         );
 
-        // Si sí, se agregan mutuamente como lindantes
+        // Si sí, se agregan mutuamente como lindantes (si ya lo eran, no se repiten)
         if in_range {
-            self.border_cameras.push(candidate_bordering.get_id());
-            candidate_bordering.border_cameras.push(self.id);
+            if self.add_border_camera(candidate_bordering.get_id()) {
+                self.mark_as_not_sent();
+            }
+            if candidate_bordering.add_border_camera(self.id) {
+                candidate_bordering.mark_as_not_sent();
+            }
         }
     }
 
@@ -212,30 +431,42 @@ impl Camera {
             .position(|id| *id == camera_to_delete.get_id())
         {
             self.border_cameras.remove(pos);
+            self.mark_as_not_sent();
         }
     }
 
-    /// Calcula si se encuentra las coordenadas pasadas se encuentran dentro del rango pasado
+    /// Calcula si se encuentra las coordenadas pasadas se encuentran dentro del rango pasado.
+    /// El rango se mide aproximadamente en cuadras (ver `RANGE_BASE_METERS` y `RANGE_METERS_PER_UNIT`).
     fn is_within_range_from_self(&self, latitude: f64, longitude: f64, range: f64) -> bool {
-        let lat_dist = self.latitude - latitude;
-        let long_dist = self.longitude - longitude;
-        let rad = f64::sqrt(lat_dist.powi(2) + long_dist.powi(2));
+        self.distance_meters_to((latitude, longitude)) <= range_in_meters(range)
+    }
 
-        // Se modifica el range de las cámaras, ahora que son latitudes de verdad y no "3 4".
-        let adjusted_range = 0.00135 + 0.0012 * range;
+    /// Devuelve la distancia en metros desde la cámara hasta `(latitude, longitude)`.
+    fn distance_meters_to(&self, (latitude, longitude): (f64, f64)) -> f64 {
+        let self_position = Position::from_lat_lon(self.latitude, self.longitude);
+        self_position.distance_meters(&Position::from_lat_lon(latitude, longitude))
+    }
 
-        /*println!(
-            "Dio que la cuenta vale: {}, y adj_range vale: {}. Era rango: {}",
-            rad, adjusted_range, range
-        ); // debug []*/
-        rad <= (adjusted_range)
+    /// Devuelve la distancia en metros hasta la que registra incidentes, según su rango.
+    pub fn get_range_meters(&self) -> f64 {
+        range_in_meters(self.range as f64)
     }
 }
 
+/// Distancia en metros que cubre una cámara de rango `range`.
+fn range_in_meters(range: f64) -> f64 {
+    RANGE_BASE_METERS + RANGE_METERS_PER_UNIT * range
+}
+
 #[cfg(test)]
 
 mod test {
-    use super::Camera;
+    use super::{Camera, CAMERA_WIRE_VERSION};
+    use crate::apps::sist_camaras::field_of_view::FieldOfView;
+    use crate::apps::{
+        incident_data::{incident_info::IncidentInfo, incident_source::IncidentSource},
+        sist_camaras::privacy_mask::PrivacyMask,
+    };
 
     #[test]
     fn test_1_camera_to_y_from_bytes() {
@@ -243,9 +474,14 @@ mod test {
 
         let bytes = camera.to_bytes();
 
-        let camera_reconstruida = Camera::from_bytes(&bytes);
+        let camera_reconstruida = Camera::from_bytes(&bytes).unwrap();
 
         assert_eq!(camera_reconstruida, camera);
+
+        // Un estado desconocido es un error, no un panic
+        let mut bytes_estado_invalido = bytes.clone();
+        bytes_estado_invalido[18] = 9;
+        assert!(Camera::from_bytes(&bytes_estado_invalido).is_err());
     }
 
     #[test]
@@ -296,6 +532,151 @@ mod test {
         assert!(!cam_b.border_cameras.contains(&cam_a.get_id()));
     }
 
+    #[test]
+    fn test_3b_una_camara_lindante_evaluada_dos_veces_se_lista_una_sola_vez() {
+        let mut cam_5: Camera = Camera::new(5, -34.6040, -58.3873, 1);
+        let mut cam_6: Camera = Camera::new(6, -34.6039, -58.3837, 1);
+
+        cam_5.mutually_add_if_bordering(&mut cam_6);
+        cam_5.mutually_add_if_bordering(&mut cam_6);
+        cam_6.mutually_add_if_bordering(&mut cam_5);
+
+        assert_eq!(cam_5.border_cameras, vec![6]);
+        assert_eq!(cam_6.border_cameras, vec![5]);
+    }
+
+    #[test]
+    fn test_3c_no_se_serializan_mas_cameras_lindantes_de_las_que_entran_en_un_byte() {
+        let mut camera = Camera::new(1, -34.6040, -58.3873, 1);
+        for id in 0..=u8::MAX {
+            camera.add_border_camera(id);
+        }
+        assert_eq!(camera.border_cameras.len(), 255);
+        // Aun si se agregaran más por otro medio, la serialización no desborda
+        camera.border_cameras.push(0);
+
+        let camera_reconstruida = Camera::from_bytes(&camera.to_bytes()).unwrap();
+        assert_eq!(camera_reconstruida.border_cameras.len(), 255);
+    }
+
+    #[test]
+    fn test_5_camera_fallada_to_y_from_bytes_conserva_el_estado() {
+        let mut camera = Camera::new(7, -34.6040, -58.3873, 1);
+        camera.set_failed();
+
+        let camera_reconstruida = Camera::from_bytes(&camera.to_bytes()).unwrap();
+
+        assert!(camera_reconstruida.is_failed());
+        assert_eq!(camera_reconstruida, camera);
+    }
+
+    #[test]
+    fn test_6_camera_con_regiones_excluidas_no_registra_incidentes_dentro_de_ellas() {
+        let mut camera = Camera::new(5, -34.6040, -58.3873, 1);
+        let inside_mask = (-34.6041, -58.3873);
+        let outside_mask = (-34.6042, -58.3897); // una cuadra a la izq de la cam 5
+        // Sin regiones excluidas, registra ambos
+        assert!(camera.will_register(inside_mask));
+        assert!(camera.will_register(outside_mask));
+
+        camera.add_privacy_mask(PrivacyMask::Circle {
+            center: (-34.6040, -58.3873),
+            radius_meters: 50.0,
+        });
+        assert!(!camera.will_register(inside_mask));
+        assert!(camera.will_register(outside_mask));
+
+        // Las regiones se conservan al pasar a bytes y reconstruir
+        let camera_reconstruida = Camera::from_bytes(&camera.to_bytes()).unwrap();
+        assert_eq!(camera_reconstruida, camera);
+    }
+
+    #[test]
+    fn test_7_camera_sin_bytes_de_regiones_se_lee_sin_regiones() {
+        let mut camera = Camera::new(12, 3.0, 4.0, 5);
+        let mask = PrivacyMask::Circle {
+            center: (3.0, 4.0),
+            radius_meters: 50.0,
+        };
+        let mask_len = mask.to_bytes().len();
+        camera.add_privacy_mask(mask);
+        let mut bytes = camera.to_bytes();
+        // La versión 1, anterior a las regiones y al campo de visión: termina en el flag de eliminada
+        bytes[0] = 1;
+        bytes.truncate(bytes.len() - 1 - mask_len - FieldOfView::full().to_bytes().len());
+
+        let camera_reconstruida = Camera::from_bytes(&bytes).unwrap();
+        assert!(camera_reconstruida.get_privacy_masks().is_empty());
+        assert_eq!(camera_reconstruida, Camera::new(12, 3.0, 4.0, 5));
+    }
+
+    #[test]
+    fn test_7b_camera_de_una_version_mas_nueva_ignora_los_campos_desconocidos() {
+        let mut camera = Camera::new(12, 3.0, 4.0, 5);
+        camera.add_privacy_mask(PrivacyMask::Circle {
+            center: (3.0, 4.0),
+            radius_meters: 50.0,
+        });
+        let mut bytes = camera.to_bytes();
+        bytes[0] = CAMERA_WIRE_VERSION + 1;
+        bytes.extend_from_slice(&[7, 7]);
+
+        assert_eq!(Camera::from_bytes(&bytes).unwrap(), camera);
+    }
+
+    #[test]
+    fn test_8_camera_con_coordenadas_no_finitas_no_se_lee() {
+        let mut bytes = Camera::new(12, 3.0, 4.0, 5).to_bytes();
+        bytes[2..10].copy_from_slice(&f64::NAN.to_be_bytes());
+        assert!(Camera::from_bytes(&bytes).is_err());
+
+        bytes[2..10].copy_from_slice(&3.0_f64.to_be_bytes());
+        bytes[10..18].copy_from_slice(&f64::INFINITY.to_be_bytes());
+        assert!(Camera::from_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    fn test_9_activar_una_camara_ya_activa_por_otro_incidente_no_la_marca_como_no_enviada() {
+        let mut camera = Camera::new(5, -34.6040, -58.3873, 1);
+        assert!(camera.is_dirty());
+        camera.mark_as_sent();
+
+        assert!(camera.append_to_incs_being_managed(IncidentInfo::new(1, IncidentSource::Manual), (-34.6040, -58.3873)));
+        assert!(camera.is_dirty());
+        camera.mark_as_sent();
+
+        // Ya estaba activa: no cambia su estado, no hace falta volver a publicarla
+        assert!(!camera.append_to_incs_being_managed(IncidentInfo::new(2, IncidentSource::Manual), (-34.6040, -58.3873)));
+        assert!(!camera.is_dirty());
+        assert!(!camera.remove_from_incs_being_managed(IncidentInfo::new(1, IncidentSource::Manual)));
+        assert!(!camera.is_dirty());
+
+        // Al resolverse el último, vuelve a ahorro de energía
+        assert!(camera.remove_from_incs_being_managed(IncidentInfo::new(2, IncidentSource::Manual)));
+        assert!(camera.is_dirty());
+    }
+
+    #[test]
+    fn test_10_camera_orientada_solamente_registra_incidentes_dentro_de_su_cono() {
+        let mut camera = Camera::new(5, -34.6040, -58.3873, 1);
+        let north = (-34.6030, -58.3873);
+        let south = (-34.6050, -58.3873);
+        // Sin orientación ve en todas las direcciones
+        assert!(camera.will_register(north));
+        assert!(camera.will_register(south));
+
+        camera.set_field_of_view(FieldOfView::new(0.0, 45.0).unwrap());
+        assert!(camera.will_register(north));
+        assert!(!camera.will_register(south));
+        // Dentro del cono pero fuera de rango
+        assert!(!camera.will_register((-34.5900, -58.3873)));
+
+        // El campo de visión se conserva al pasar a bytes y reconstruir
+        let camera_reconstruida = Camera::from_bytes(&camera.to_bytes()).unwrap();
+        assert_eq!(camera_reconstruida.get_field_of_view(), camera.get_field_of_view());
+        assert!(!camera_reconstruida.will_register(south));
+    }
+
     // #[test]
     // fn test_4_testing_camera_range() {
 
@@ -0,0 +1,125 @@
+use std::{
+    io::{Cursor, Error, ErrorKind},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::mqtt::mqtt_utils::fixed_header::{decode_remaining_length, encode_remaining_length};
+
+/// Imagen capturada por una cámara (jpeg o png), junto con sus dimensiones y el instante de captura.
+/// Se publica por MQTT al topic `CameraFrameTopic`, para ser analizada en busca de incidentes
+/// y, opcionalmente, mostrada en la UI de Sistema Monitoreo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraFrame {
+    cam_id: u8,
+    timestamp: u64, // milisegundos desde epoch
+    width: u16,
+    height: u16,
+    image: Vec<u8>,
+}
+
+impl CameraFrame {
+    /// Crea el frame con la `image` de la cámara `cam_id`, capturada en este instante.
+    /// Si no se pueden leer las dimensiones de la imagen, se las considera 0.
+    pub fn new(cam_id: u8, image: Vec<u8>) -> Self {
+        let (width, height) = read_dimensions(&image).unwrap_or((0, 0));
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as u64)
+            .unwrap_or(0);
+        Self {
+            cam_id,
+            timestamp,
+            width,
+            height,
+            image,
+        }
+    }
+
+    pub fn get_cam_id(&self) -> u8 {
+        self.cam_id
+    }
+
+    pub fn get_timestamp(&self) -> u64 {
+        self.timestamp
+    }
+
+    pub fn get_width(&self) -> u16 {
+        self.width
+    }
+
+    pub fn get_height(&self) -> u16 {
+        self.height
+    }
+
+    pub fn get_image(&self) -> &[u8] {
+        &self.image
+    }
+
+    /// Pasa el frame a bytes: cam_id (1), timestamp (8), width (2), height (2),
+    /// longitud de la imagen en longitud variable (1 a 4), e imagen.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![self.cam_id];
+        bytes.extend_from_slice(&self.timestamp.to_be_bytes());
+        bytes.extend_from_slice(&self.width.to_be_bytes());
+        bytes.extend_from_slice(&self.height.to_be_bytes());
+        bytes.extend(encode_remaining_length(self.image.len()));
+        bytes.extend_from_slice(&self.image);
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        const HEADER_LEN: usize = 1 + 8 + 2 + 2;
+        if bytes.len() < HEADER_LEN + 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "No hay suficientes bytes para un CameraFrame",
+            ));
+        }
+        let cam_id = bytes[0];
+        let mut timestamp_bytes = [0u8; 8];
+        timestamp_bytes.copy_from_slice(&bytes[1..9]);
+        let width = u16::from_be_bytes([bytes[9], bytes[10]]);
+        let height = u16::from_be_bytes([bytes[11], bytes[12]]);
+
+        let (image_len, len_bytes) = decode_remaining_length(&bytes[HEADER_LEN..])?;
+        let image_start = HEADER_LEN + len_bytes;
+        if bytes.len() != image_start + image_len {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "La longitud de la imagen del CameraFrame no coincide",
+            ));
+        }
+
+        Ok(Self {
+            cam_id,
+            timestamp: u64::from_be_bytes(timestamp_bytes),
+            width,
+            height,
+            image: bytes[image_start..].to_vec(),
+        })
+    }
+}
+
+/// Lee ancho y alto de la imagen (jpeg o png), sin decodificarla completa.
+fn read_dimensions(image: &[u8]) -> Option<(u16, u16)> {
+    let reader = image::io::Reader::new(Cursor::new(image))
+        .with_guessed_format()
+        .ok()?;
+    let (width, height) = reader.into_dimensions().ok()?;
+    Some((width as u16, height as u16))
+}
+
+#[cfg(test)]
+mod test {
+    use super::CameraFrame;
+
+    #[test]
+    fn test_1_camera_frame_to_and_from_bytes_con_imagen_grande() {
+        let frame = CameraFrame::new(4, vec![9u8; 40_000]);
+        let bytes = frame.to_bytes();
+
+        assert_eq!(CameraFrame::from_bytes(&bytes).unwrap(), frame);
+        // Bytes truncados dan error
+        assert!(CameraFrame::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    }
+}
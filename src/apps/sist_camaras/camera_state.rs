@@ -1,7 +1,12 @@
-#[derive(Debug, PartialEq, Clone, Copy)]
+use std::io::{Error, ErrorKind};
+
+use serde::Serialize;
+
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
 pub enum CameraState {
     Active,
     SavingMode,
+    Failed, // la cámara no funciona, no participa en la atención de incidentes
 }
 
 impl CameraState {
@@ -9,14 +14,19 @@ impl CameraState {
         match self {
             CameraState::Active => 1_u8.to_be_bytes(),
             CameraState::SavingMode => 2_u8.to_be_bytes(),
+            CameraState::Failed => 3_u8.to_be_bytes(),
         }
     }
 
-    pub fn from_byte(bytes: [u8; 1]) -> Self {
+    pub fn from_byte(bytes: [u8; 1]) -> Result<Self, Error> {
         match u8::from_be_bytes(bytes) {
-            1 => CameraState::Active,
-            2 => CameraState::SavingMode,
-            _ => panic!("Estado de cámara no válido"),
+            1 => Ok(CameraState::Active),
+            2 => Ok(CameraState::SavingMode),
+            3 => Ok(CameraState::Failed),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "Estado de cámara no válido",
+            )),
         }
     }
 }
@@ -0,0 +1,157 @@
+use std::io::{Error, ErrorKind};
+
+use serde::Serialize;
+
+use crate::apps::vendor::Position;
+
+/// Campo de visión de una cámara: un cono con vértice en la cámara, orientado hacia `bearing_degrees`
+/// (0 es el norte, en sentido horario) y que abarca `half_angle_degrees` hacia cada lado. Con un semiángulo
+/// de 180 grados la cámara ve en todas las direcciones, como las cámaras configuradas sin orientación.
+#[derive(Debug, PartialEq, Clone, Copy, Serialize)]
+pub struct FieldOfView {
+    bearing_degrees: f64,
+    half_angle_degrees: f64,
+}
+
+impl FieldOfView {
+    /// Crea un campo de visión. Devuelve error si los ángulos no son finitos, o si el semiángulo
+    /// no está entre 0 (exclusive) y 180 grados.
+    pub fn new(bearing_degrees: f64, half_angle_degrees: f64) -> Result<Self, Error> {
+        // Un semiángulo NaN tampoco es válido
+        let is_valid_half_angle = half_angle_degrees > 0.0 && half_angle_degrees <= 180.0;
+        if !bearing_degrees.is_finite() || !is_valid_half_angle {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Campo de visión no válido: orientación {}, semiángulo {}",
+                    bearing_degrees, half_angle_degrees
+                ),
+            ));
+        }
+        Ok(Self {
+            bearing_degrees: bearing_degrees.rem_euclid(360.0),
+            half_angle_degrees,
+        })
+    }
+
+    /// Campo de visión que abarca todas las direcciones.
+    pub fn full() -> Self {
+        Self {
+            bearing_degrees: 0.0,
+            half_angle_degrees: 180.0,
+        }
+    }
+
+    /// Devuelve si abarca todas las direcciones.
+    pub fn is_full(&self) -> bool {
+        self.half_angle_degrees >= 180.0
+    }
+
+    pub fn get_bearing_degrees(&self) -> f64 {
+        self.bearing_degrees
+    }
+
+    pub fn get_half_angle_degrees(&self) -> f64 {
+        self.half_angle_degrees
+    }
+
+    /// Devuelve si, desde `origin`, la posición `target` (ambas (lat, lon)) está dentro del cono.
+    /// No considera la distancia, que depende del rango de la cámara.
+    pub fn contains(&self, origin: (f64, f64), target: (f64, f64)) -> bool {
+        if self.is_full() || origin == target {
+            return true;
+        }
+        let bearing = Position::from_lat_lon(origin.0, origin.1).bearing_to(&Position::from_lat_lon(target.0, target.1));
+        let difference = (bearing - self.bearing_degrees + 540.0).rem_euclid(360.0) - 180.0;
+        difference.abs() <= self.half_angle_degrees
+    }
+
+    /// Devuelve el contorno del cono, de radio `radius_meters` y con vértice en `origin`, como posiciones
+    /// (lat, lon): el vértice, y `arc_points` puntos del arco, para dibujarlo en el mapa.
+    pub fn cone_outline(&self, origin: (f64, f64), radius_meters: f64, arc_points: usize) -> Vec<(f64, f64)> {
+        let mut outline = vec![origin];
+        let vertex = Position::from_lat_lon(origin.0, origin.1);
+        let start = self.bearing_degrees - self.half_angle_degrees;
+        let step = 2.0 * self.half_angle_degrees / (arc_points.max(2) - 1) as f64;
+        for i in 0..arc_points.max(2) {
+            let arc_point = vertex.destination_point(start + step * i as f64, radius_meters);
+            outline.push((arc_point.lat(), arc_point.lon()));
+        }
+        outline
+    }
+
+    /// Pasa el campo de visión a bytes: la orientación y el semiángulo.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.bearing_degrees.to_be_bytes().to_vec();
+        bytes.extend_from_slice(&self.half_angle_degrees.to_be_bytes());
+        bytes
+    }
+
+    /// Lee un campo de visión desde el comienzo de `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let read_f64 = |start: usize| -> Option<f64> {
+            let value_bytes: [u8; 8] = bytes.get(start..start + 8)?.try_into().ok()?;
+            Some(f64::from_be_bytes(value_bytes))
+        };
+        match (read_f64(0), read_f64(8)) {
+            (Some(bearing_degrees), Some(half_angle_degrees)) => Self::new(bearing_degrees, half_angle_degrees),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "No hay suficientes bytes para un campo de visión",
+            )),
+        }
+    }
+}
+
+impl Default for FieldOfView {
+    fn default() -> Self {
+        Self::full()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::FieldOfView;
+    use crate::apps::vendor::Position;
+
+    #[test]
+    fn test_1_solamente_contiene_las_posiciones_dentro_del_cono() {
+        let camera = (-34.6037, -58.3816);
+        let north = (-34.6000, -58.3816);
+        let east = (-34.6037, -58.3770);
+        let south = (-34.6080, -58.3816);
+
+        // Mirando al norte, 45 grados hacia cada lado
+        let fov = FieldOfView::new(0.0, 45.0).unwrap();
+        assert!(fov.contains(camera, north));
+        assert!(!fov.contains(camera, east));
+        assert!(!fov.contains(camera, south));
+
+        // Mirando al noroeste, con un cono que cruza el norte
+        let fov = FieldOfView::new(-30.0, 60.0).unwrap();
+        assert!(fov.contains(camera, north));
+        assert!(!fov.contains(camera, east));
+
+        assert!(FieldOfView::full().contains(camera, south));
+        assert!(FieldOfView::new(0.0, 0.0).is_err());
+        assert!(FieldOfView::new(0.0, 181.0).is_err());
+    }
+
+    #[test]
+    fn test_2_el_contorno_del_cono_esta_a_la_distancia_del_rango() {
+        let camera = (-34.6037, -58.3816);
+        let fov = FieldOfView::new(90.0, 30.0).unwrap();
+
+        let outline = fov.cone_outline(camera, 500.0, 5);
+
+        // El vértice, y los puntos del arco a 500 metros, de los 60 a los 120 grados
+        assert_eq!(outline.len(), 6);
+        assert_eq!(outline[0], camera);
+        let vertex = Position::from_lat_lon(camera.0, camera.1);
+        for (i, point) in outline[1..].iter().enumerate() {
+            let point = Position::from_lat_lon(point.0, point.1);
+            assert!((vertex.distance_meters(&point) - 500.0).abs() < 1e-6);
+            assert!((vertex.bearing_to(&point) - (60.0 + 15.0 * i as f64)).abs() < 0.01);
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use std::{collections::HashMap, fs, sync::{Arc, Mutex}};
 
-use super::camera::Camera;
+use crate::apps::coordinates::check_finite_coordinates;
+
+use super::{camera::Camera, field_of_view::FieldOfView};
 
 /// Crea el hashmap de cámaras bien inicializado envuelto en un arc mutex, listo para ser usado
 /// por sistema cámaras y sus módulos.
@@ -11,20 +13,28 @@ pub fn create_cameras() -> Arc<Mutex<HashMap<u8, Camera>>> {
 
 /// Lee las cámaras desde el archivo `filename`, las parsea y las crea, configurando también cuáles
 /// son lindantes entre sí. Devuelve un hashmap con el id de cada cámara como clave y la cámara como valor.
+/// Cada línea es `ID:X:Y:RANGE`, u opcionalmente `ID:X:Y:RANGE:ORIENTACION:SEMIANGULO` (en grados, ver `FieldOfView`)
+/// para una cámara que no ve en todas las direcciones.
 fn read_cameras_from_file(filename: &str) -> HashMap<u8, Camera> {
     let mut cameras: HashMap<u8, Camera> = HashMap::new();
     let contents = fs::read_to_string(filename).expect("Error al leer el archivo de properties");
 
     for line in contents.lines() {
         let parts: Vec<&str> = line.split(':').collect();
-        if parts.len() == 4 {
+        if parts.len() == 4 || parts.len() == 6 {
             // Lee los atributos a cargar a la nueva cámara
             let id: u8 = parts[0].trim().parse().expect("Id no válido");
             let latitude = parts[1].trim().parse().expect("Latitud no válida");
             let longitude = parts[2].trim().parse().expect("Longitud no válida");
             let range = parts[3].trim().parse().expect("Rango no válido"); // []
+            check_finite_coordinates(latitude, longitude).expect("Coordenadas no válidas");
 
             let mut new_camera = Camera::new(id, latitude, longitude, range);
+            if parts.len() == 6 {
+                let bearing = parts[4].trim().parse().expect("Orientación no válida");
+                let half_angle = parts[5].trim().parse().expect("Semiángulo no válido");
+                new_camera.set_field_of_view(FieldOfView::new(bearing, half_angle).expect("Campo de visión no válido"));
+            }
 
             // Recorre las cámaras ya existentes, agregando la nueva cámara como lindante de la que corresponda y viceversa, terminando la creación
             for camera in cameras.values_mut() {
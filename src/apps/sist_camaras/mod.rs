@@ -1,10 +1,16 @@
 pub mod ai_detection;
 pub mod camara_errors;
 pub mod camera;
+pub mod camera_frame;
 pub mod camera_state;
+pub mod field_of_view;
 pub mod manage_stored_cameras;
+pub mod privacy_mask;
+pub mod shared_cameras;
 pub mod sist_cams_mqtt_properties;
 pub mod sistema_camaras;
 pub mod sistema_camaras_abm;
 pub mod sistema_camaras_logic;
+pub mod state_rate_limiter;
+pub mod topic_qos;
 pub mod types;
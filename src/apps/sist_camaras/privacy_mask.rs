@@ -0,0 +1,127 @@
+use crate::apps::vendor::Position;
+
+use serde::Serialize;
+
+const RECTANGLE_TAG: u8 = 1;
+const CIRCLE_TAG: u8 = 2;
+
+/// Región del campo de una cámara, en latitud y longitud, en la que no deben registrarse incidentes
+/// (ej. una bandera que flamea, o una avenida muy transitada).
+#[derive(Debug, PartialEq, Clone, Serialize)]
+pub enum PrivacyMask {
+    /// Rectángulo entre las esquinas `min` y `max`, ambas (lat, lon).
+    Rectangle { min: (f64, f64), max: (f64, f64) },
+    /// Círculo de centro `center` (lat, lon) y radio `radius_meters`.
+    Circle { center: (f64, f64), radius_meters: f64 },
+}
+
+impl PrivacyMask {
+    /// Devuelve si la posición (lat, lon) recibida se encuentra dentro de la región.
+    pub fn contains(&self, (latitude, longitude): (f64, f64)) -> bool {
+        match self {
+            PrivacyMask::Rectangle { min, max } => {
+                min.0 <= latitude && latitude <= max.0 && min.1 <= longitude && longitude <= max.1
+            }
+            PrivacyMask::Circle {
+                center,
+                radius_meters,
+            } => {
+                let center_position = Position::from_lat_lon(center.0, center.1);
+                center_position.distance_meters(&Position::from_lat_lon(latitude, longitude)) <= *radius_meters
+            }
+        }
+    }
+
+    /// Pasa la región a bytes: un byte que indica su tipo, seguido de sus coordenadas.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = vec![];
+        match self {
+            PrivacyMask::Rectangle { min, max } => {
+                bytes.push(RECTANGLE_TAG);
+                for value in [min.0, min.1, max.0, max.1] {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+            PrivacyMask::Circle {
+                center,
+                radius_meters,
+            } => {
+                bytes.push(CIRCLE_TAG);
+                for value in [center.0, center.1, *radius_meters] {
+                    bytes.extend_from_slice(&value.to_be_bytes());
+                }
+            }
+        }
+        bytes
+    }
+
+    /// Lee una región desde el comienzo de `bytes`. Devuelve la región y la cantidad de bytes leídos,
+    /// o None si los bytes no corresponden a una región.
+    pub fn from_bytes(bytes: &[u8]) -> Option<(Self, usize)> {
+        let read_f64 = |i: usize| -> Option<f64> {
+            let start = 1 + 8 * i;
+            let value_bytes: [u8; 8] = bytes.get(start..start + 8)?.try_into().ok()?;
+            Some(f64::from_be_bytes(value_bytes))
+        };
+        match *bytes.first()? {
+            RECTANGLE_TAG => {
+                let min = (read_f64(0)?, read_f64(1)?);
+                let max = (read_f64(2)?, read_f64(3)?);
+                Some((PrivacyMask::Rectangle { min, max }, 1 + 8 * 4))
+            }
+            CIRCLE_TAG => {
+                let center = (read_f64(0)?, read_f64(1)?);
+                let radius_meters = read_f64(2)?;
+                Some((
+                    PrivacyMask::Circle {
+                        center,
+                        radius_meters,
+                    },
+                    1 + 8 * 3,
+                ))
+            }
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PrivacyMask;
+
+    #[test]
+    fn test_1_rectangulo_y_circulo_contienen_solamente_las_posiciones_dentro() {
+        let rectangle = PrivacyMask::Rectangle {
+            min: (-34.605, -58.390),
+            max: (-34.603, -58.385),
+        };
+        assert!(rectangle.contains((-34.604, -58.387)));
+        assert!(!rectangle.contains((-34.606, -58.387)));
+
+        // Círculo de 50 metros: 0.0001 grados de latitud son aprox. 11 metros, 0.001 aprox. 111
+        let circle = PrivacyMask::Circle {
+            center: (-34.6040, -58.3873),
+            radius_meters: 50.0,
+        };
+        assert!(circle.contains((-34.6041, -58.3873)));
+        assert!(!circle.contains((-34.6050, -58.3873)));
+    }
+
+    #[test]
+    fn test_2_to_y_from_bytes() {
+        for mask in [
+            PrivacyMask::Rectangle {
+                min: (-34.605, -58.390),
+                max: (-34.603, -58.385),
+            },
+            PrivacyMask::Circle {
+                center: (-34.6040, -58.3873),
+                radius_meters: 50.0,
+            },
+        ] {
+            let bytes = mask.to_bytes();
+            assert_eq!(PrivacyMask::from_bytes(&bytes), Some((mask, bytes.len())));
+        }
+        assert_eq!(PrivacyMask::from_bytes(&[9, 1, 2]), None);
+    }
+}
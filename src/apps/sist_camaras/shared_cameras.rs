@@ -0,0 +1,38 @@
+use std::io::Error;
+
+use super::{camera::Camera, types::shareable_cameras_type::ShCamerasType};
+
+/// Devuelve una copia de las cámaras compartidas, tomando el lock solamente mientras se las copia.
+/// Permite hacer cálculos sobre todas las cámaras (ej. de rango) sin retener el lock.
+pub fn snapshot_cameras(cameras: &ShCamerasType) -> Result<Vec<Camera>, Error> {
+    match cameras.lock() {
+        Ok(cams) => Ok(cams.values().cloned().collect()),
+        Err(_) => Err(Error::other("Error al tomar lock de cámaras para copiarlas.")),
+    }
+}
+
+/// Aplica `update` a cada cámara de `ids` que siga existiendo, en un único lock breve. Como se modifica la cámara
+/// compartida (y no una copia), dos actualizaciones concurrentes sobre la misma cámara no se pisan.
+/// Se devuelve una copia de las que quedaron marcadas como no enviadas (ver `Camera::is_dirty`), marcándolas
+/// como enviadas, para poder publicarlas luego de liberar el lock. Así no se vuelve a publicar una cámara sin cambios.
+pub fn update_cameras<F>(cameras: &ShCamerasType, ids: &[u8], mut update: F) -> Result<Vec<Camera>, Error>
+where
+    F: FnMut(&mut Camera),
+{
+    match cameras.lock() {
+        Ok(mut cams) => {
+            let mut changed_cams = vec![];
+            for id in ids {
+                if let Some(camera) = cams.get_mut(id) {
+                    update(camera);
+                    if camera.is_dirty() {
+                        camera.mark_as_sent();
+                        changed_cams.push(camera.clone());
+                    }
+                }
+            }
+            Ok(changed_cams)
+        }
+        Err(_) => Err(Error::other("Error al tomar lock de cámaras para actualizarlas.")),
+    }
+}
@@ -1,65 +1,92 @@
 use crate::apps::{
+    app_message::AppMessage,
+    config::AppConfig,
     apps_mqtt_topics::AppsMqttTopics,
     common_clients::{exit_when_asked, there_are_no_more_publish_msgs},
+    heartbeat::{spawn_heartbeat_thread, DEFAULT_HEARTBEAT_INTERVAL},
     incident_data::incident::Incident,
     sist_camaras::{
-        ai_detection::ai_detector_manager::AIDetectorManager, camera::Camera,
-        sistema_camaras_abm::ABMCameras, sistema_camaras_logic::CamerasLogic,
-        types::shareable_cameras_type::ShCamerasType,
+        ai_detection::{
+            ai_detector_manager::AIDetectorManager, camera_frame_capturer::CameraFrameCapturer,
+            incident_publish_feedback::IncidentPublishResult,
+        },
+        camera::Camera,
+        camera_frame::CameraFrame,
+        shared_cameras::snapshot_cameras,
+        sistema_camaras_abm::ABMCameras,
+        sistema_camaras_logic::{read_deactivation_margin, CamerasLogic},
+        state_rate_limiter::{CameraRateLimiter, StateIntervals, SIST_CAMARAS_PROPERTIES_FILE},
+        topic_qos::TopicQos,
+        types::{shareable_cameras_type::ShCamerasType, shareable_publisher_type::ShPublisherType},
     },
 };
 use crate::logging::string_logger::StringLogger;
-use crate::mqtt::{client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage};
+use crate::mqtt::{
+    client::{
+        publisher::Publisher,
+        reconnection::{reconnect_with_backoff, DEFAULT_INITIAL_BACKOFF, DEFAULT_MAX_BACKOFF},
+    },
+    messages::publish_message::PublishMessage,
+    mqtt_utils::{
+        shutdown_token::ShutdownToken,
+        topic::{topic_filters_with_qos, Topic},
+        will_message_utils::app_type::AppType,
+    },
+};
 
 use std::collections::HashMap;
 use std::{
-    fs,
-    io::{self, ErrorKind},
     sync::{
+        atomic::AtomicBool,
         mpsc::{self, Receiver, Sender},
         Arc, Mutex,
     },
     thread::{self, JoinHandle},
+    time::{Duration, Instant},
 };
 
 use super::types::channels_type::create_channels;
 
+/// Cantidad de veces que se intenta publicar un incidente, si no se recibe su ack.
+const INC_PUBLISH_ATTEMPTS: u8 = 3;
+
+/// Archivos de configuración de Sistema Cámaras (ver `AppConfig`). Los qos se indican en el segundo.
+pub const SIST_CAMARAS_CONFIG_FILES: [&str; 2] = [
+    SIST_CAMARAS_PROPERTIES_FILE,
+    "src/apps/sist_camaras/qos_sistema_camaras.properties",
+];
+
+/// Cada cuánto se revisa si se perdió la conexión con el broker.
+const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_secs(1);
+
 /// Sistema encargado de responder a incidentes cambiando las cámaras de estado,
 /// proveer un abm por consola, y ejecutar un detector automático de incidentes.
 #[derive(Debug)]
 pub struct SistemaCamaras {
     cameras: Arc<Mutex<HashMap<u8, Camera>>>,
-    qos: u8,
+    qos: TopicQos,
+    // Lo activa y desactiva el abm, para que el detector deje de emitir incidentes (ej. durante un mantenimiento)
+    detector_paused: Arc<AtomicBool>,
+    deactivation_margin_meters: f64, // ver `CamerasLogic::set_deactivation_margin`
     logger: StringLogger,
 }
 
-fn leer_qos_desde_archivo(ruta_archivo: &str) -> Result<u8, io::Error> {
-    let contenido = fs::read_to_string(ruta_archivo)?;
-    let inicio = contenido.find("qos=").ok_or(io::Error::new(
-        ErrorKind::NotFound,
-        "No se encontró la etiqueta 'qos='",
-    ))?;
-    let valor_qos = contenido[inicio + 4..].trim().parse::<u8>().map_err(|_| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            "El valor de QoS no es un número válido",
-        )
-    })?;
-    Ok(valor_qos)
-}
+
 impl SistemaCamaras {
-    /// Crea un Sistema Cámaras.
+    /// Crea un Sistema Cámaras, que publica y se suscribe a cada topic con el qos indicado en `config`.
     pub fn new(
         cameras: Arc<Mutex<HashMap<u8, Camera>>>,
+        config: &AppConfig,
         logger: StringLogger,
     ) -> Self {
         println!("Sistema de Cámaras\n");
-        let qos =
-            leer_qos_desde_archivo("src/apps/sist_camaras/qos_sistema_camaras.properties").unwrap();
+        let qos = config.get_topic_qos().clone();
 
         let sistema_camaras: SistemaCamaras = Self {
             cameras,
             qos,
+            detector_paused: Arc::new(AtomicBool::new(false)),
+            deactivation_margin_meters: read_deactivation_margin(config),
             logger,
         };
 
@@ -67,14 +94,16 @@ impl SistemaCamaras {
     }
 
     /// Inicializa las partes internas del Sistema Cámaras.
+    /// Los publish se realizan a través del `publisher`: un `MQTTClient` conectado al broker,
+    /// o un `NullPublisher` para ejecutar sin broker (los mensajes a procesar se reciben por `publish_msg_rx`).
     pub fn spawn_threads(
         &mut self,
         publish_msg_rx: Receiver<PublishMessage>,
-        mqtt_client: MQTTClient,
+        publisher: Box<dyn Publisher>,
     ) -> Vec<JoinHandle<()>> {
         let mut children: Vec<JoinHandle<()>> = vec![];
 
-        let mqtt_sh = Arc::new(Mutex::new(mqtt_client));
+        let mqtt_sh: ShPublisherType = Arc::new(Mutex::new(publisher));
         let (cameras_tx, cameras_rx, exit_tx, exit_rx, exit_detector_tx, exit_detector_rx) = create_channels();
 
         // Recibe las cámaras que envía el abm y las publica por MQTT
@@ -84,15 +113,46 @@ impl SistemaCamaras {
         children.push(self.spawn_abm_cameras_thread(&self.cameras, cameras_tx.clone(), exit_tx));
 
         // Exit, cuando lo solicita el abm
-        children.push(spawn_exit_when_asked_thread(mqtt_sh.clone(), exit_rx, exit_detector_tx));
+        let shutdown_token = ShutdownToken::new();
+        children.push(spawn_exit_when_asked_thread(
+            mqtt_sh.clone(),
+            exit_rx,
+            exit_detector_tx,
+            shutdown_token.clone(),
+        ));
+
+        // Heartbeat, para que sistema monitoreo detecte si sistema cámaras deja de responder
+        children.push(spawn_heartbeat_thread(
+            mqtt_sh.clone(),
+            AppType::Cameras,
+            None,
+            DEFAULT_HEARTBEAT_INTERVAL,
+            shutdown_token.clone(),
+            self.logger.clone_ref(),
+        ));
 
-        // Incident detector (ai)
+        // Si se pierde la conexión con el broker, se reconecta y recupera las suscripciones
+        children.push(self.spawn_recover_connection_thread(mqtt_sh.clone(), cameras_tx.clone(), shutdown_token));
+
+        // Captura de frames de las cámaras, que se publican por MQTT
+        let (captured_frame_tx, captured_frame_rx) = mpsc::channel::<CameraFrame>();
+        children.push(self.spawn_frame_capturer_thread(captured_frame_tx, exit_detector_rx));
+        children.push(self.spawn_publish_frames_thread(captured_frame_rx, mqtt_sh.clone()));
+
+        // Incident detector (ai), analiza los frames recibidos por MQTT
         let (inc_tx, inc_rx) = mpsc::channel::<Incident>();
-        children.push(self.spawn_ai_detector_thread(inc_tx, exit_detector_rx)); // conexión con proveedor intelig artificial
-        children.push(self.spawn_recv_and_publish_inc_thread(inc_rx, mqtt_sh.clone())); // recibe inc y publica
+        let (publish_result_tx, publish_result_rx) = mpsc::channel::<IncidentPublishResult>();
+        let (received_frame_tx, received_frame_rx) = mpsc::channel::<CameraFrame>();
+        children.push(self.spawn_ai_detector_thread(received_frame_rx, inc_tx, publish_result_rx)); // conexión con proveedor intelig artificial
+        children.push(self.spawn_recv_and_publish_inc_thread(inc_rx, mqtt_sh.clone(), publish_result_tx)); // recibe inc y publica
 
         // Suscribe y recibe mensajes por MQTT
-        children.push(self.spawn_subscribe_to_topics_thread(mqtt_sh.clone(), publish_msg_rx, cameras_tx));
+        children.push(self.spawn_subscribe_to_topics_thread(
+            mqtt_sh.clone(),
+            publish_msg_rx,
+            cameras_tx,
+            received_frame_tx,
+        ));
 
         children
     }
@@ -100,7 +160,7 @@ impl SistemaCamaras {
     /// Hilo que publica las cámaras.
     fn spawn_publish_to_topic_thread(
         &self,
-        mqtt_client_sh: Arc<Mutex<MQTTClient>>,
+        mqtt_client_sh: ShPublisherType,
         cameras_rx: Receiver<Vec<u8>>,
     ) -> JoinHandle<()> {
         let self_clone = self.clone_ref();
@@ -123,49 +183,66 @@ impl SistemaCamaras {
     ) -> JoinHandle<()> {
         // Lanza el hilo para el abm
         let cameras_c = cameras.clone();
+        let detector_paused = self.detector_paused.clone();
         let logger_c = self.logger.clone_ref();
         thread::spawn(move || {
             // Ejecuta el abm
-            let mut abm_cameras = ABMCameras::new(cameras_c, cameras_tx, exit_tx, logger_c);
+            let mut abm_cameras = ABMCameras::new(cameras_c, cameras_tx, exit_tx, detector_paused, logger_c);
             abm_cameras.run();
         })
     }
 
-    /// Pone en ejecución el módulo de detección automática de incidentes.
-    fn spawn_ai_detector_thread(&self, tx: Sender<Incident>, exit_detector_rx: Receiver<()>) -> JoinHandle<()> {
+    /// Pone en ejecución la captura de frames de las cámaras, que se envían por `frame_tx`.
+    fn spawn_frame_capturer_thread(&self, frame_tx: Sender<CameraFrame>, exit_detector_rx: Receiver<()>) -> JoinHandle<()> {
         let cameras_ref = Arc::clone(&self.cameras);
-        let logger_ai = self.logger.clone_ref();
+        let logger_c = self.logger.clone_ref();
         thread::spawn(move || {
-            if let Err(e) = AIDetectorManager::run(cameras_ref, tx, exit_detector_rx, logger_ai.clone_ref()){
-                logger_ai.log(format!("Error al ejecutar el detector en Sistema Cámaras: {:?}.", e));
+            if let Err(e) = CameraFrameCapturer::run(cameras_ref, frame_tx, exit_detector_rx, logger_c.clone_ref()) {
+                logger_c.log(format!("Error al ejecutar la captura de frames en Sistema Cámaras: {:?}.", e));
             }
         })
     }
 
-    /// Recibe los incidentes que envía el detector, y los publica por MQTT al topic de incidentes.
-    fn spawn_recv_and_publish_inc_thread(
+    /// Recibe los frames capturados, y los publica por MQTT al topic de frames.
+    /// De cada cámara se publica a lo sumo un frame por intervalo, según el intervalo de su estado actual.
+    fn spawn_publish_frames_thread(
         &self,
-        rx: Receiver<Incident>,
-        mqtt_client: Arc<Mutex<MQTTClient>>,
+        rx: Receiver<CameraFrame>,
+        mqtt_client: ShPublisherType,
     ) -> JoinHandle<()> {
-        let qos = self.qos;
+        let qos = self.qos.get_qos_for(AppsMqttTopics::CameraFrameTopic.to_str());
         let logger_thread = self.logger.clone_ref();
+        let cameras = Arc::clone(&self.cameras);
+        let mut rate_limiter = CameraRateLimiter::new(
+            StateIntervals::frame_intervals_from_properties_file(SIST_CAMARAS_PROPERTIES_FILE),
+        );
         thread::spawn(move || {
-            for inc in rx {
+            for frame in rx {
+                if !rate_limiter.should_process_frame_of(&cameras, frame.get_cam_id(), Instant::now()) {
+                    logger_thread.log(format!(
+                        "Se descarta frame de cámara {}, por el intervalo de su estado.",
+                        frame.get_cam_id()
+                    ));
+                    continue;
+                }
                 if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
-                    let res_publish = mqtt_client_lock.mqtt_publish(
-                        AppsMqttTopics::IncidentTopic.to_str(),
-                        &inc.to_bytes(),
+                    let res_publish = mqtt_client_lock.publish(
+                        &Topic::from(AppsMqttTopics::CameraFrameTopic),
+                        &frame.to_bytes(),
                         qos,
                     );
                     match res_publish {
-                        Ok(publish_message) => {
-                            logger_thread.log(format!("Publico inc: {:?}", publish_message));
+                        Ok(_) => {
+                            logger_thread.log(format!(
+                                "Publico frame de cámara {}, {} bytes.",
+                                frame.get_cam_id(),
+                                frame.get_image().len()
+                            ));
                         }
                         Err(e) => {
                             // No queremos cortar el loop en caso de error, solo logguearlo.
-                            println!("Error al hacer el publish {:?}", e);
-                            logger_thread.log(format!("Error al hacer el publish {:?}", e));
+                            println!("Error al hacer el publish del frame {:?}", e);
+                            logger_thread.log(format!("Error al hacer el publish del frame {:?}", e));
                         }
                     };
                 }
@@ -173,10 +250,53 @@ impl SistemaCamaras {
         })
     }
 
-    fn subscribe_to_topics(&self, mqtt_client: Arc<Mutex<MQTTClient>>, topics: Vec<(String, u8)>) {
+    /// Pone en ejecución el módulo de detección automática de incidentes, sobre los frames recibidos por `frame_rx`.
+    /// Por `publish_result_rx` recibe el resultado de publicar cada incidente que envía por `tx`.
+    fn spawn_ai_detector_thread(
+        &self,
+        frame_rx: Receiver<CameraFrame>,
+        tx: Sender<Incident>,
+        publish_result_rx: Receiver<IncidentPublishResult>,
+    ) -> JoinHandle<()> {
+        let cameras_ref = Arc::clone(&self.cameras);
+        let detector_paused = self.detector_paused.clone();
+        let logger_ai = self.logger.clone_ref();
+        thread::spawn(move || {
+            if let Err(e) = AIDetectorManager::run(cameras_ref, frame_rx, tx, publish_result_rx, detector_paused, logger_ai.clone_ref()){
+                logger_ai.log(format!("Error al ejecutar el detector en Sistema Cámaras: {:?}.", e));
+            }
+        })
+    }
+
+    /// Recibe los incidentes que envía el detector, y los publica por MQTT al topic de incidentes.
+    /// Le devuelve al detector, por `publish_result_tx`, el resultado de publicar cada uno.
+    fn spawn_recv_and_publish_inc_thread(
+        &self,
+        rx: Receiver<Incident>,
+        mqtt_client: ShPublisherType,
+        publish_result_tx: Sender<IncidentPublishResult>,
+    ) -> JoinHandle<()> {
+        let qos = self.qos.get_qos_for(AppsMqttTopics::IncidentTopic.to_str());
+        let logger_thread = self.logger.clone_ref();
+        thread::spawn(move || {
+            for inc in rx {
+                // No queremos cortar el loop en caso de error, solo logguearlo.
+                let result = if publish_incident(&mqtt_client, &inc, qos, &logger_thread) {
+                    IncidentPublishResult::Delivered(inc)
+                } else {
+                    IncidentPublishResult::Failed(inc)
+                };
+                if publish_result_tx.send(result).is_err() {
+                    logger_thread.log("Error al devolver al detector el resultado del publish del inc.".to_string());
+                }
+            }
+        })
+    }
+
+    fn subscribe_to_topics(&self, mqtt_client: ShPublisherType, topics: Vec<(String, u8)>) {
         let topics_log = topics.to_vec();
         if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
-            let res_subscribe = mqtt_client_lock.mqtt_subscribe(topics);
+            let res_subscribe = topic_filters_with_qos(topics).and_then(|topics| mqtt_client_lock.subscribe(topics));
             match res_subscribe {
                 Ok(_) => {
                     self.logger
@@ -192,17 +312,29 @@ impl SistemaCamaras {
     /// Utiliza la librería MQTT para hacer publish,
     fn publish_to_topic(
         &self,
-        mqtt_client: Arc<Mutex<MQTTClient>>,
+        mqtt_client: ShPublisherType,
         topic: &str,
         rx: Receiver<Vec<u8>>,
     ) {
+        let topic_name = match Topic::try_from(topic) {
+            Ok(topic_name) => topic_name,
+            Err(e) => {
+                self.logger.log(format!("ERROR: topic inválido para publicar: {:?}", e));
+                return;
+            }
+        };
         while let Ok(cam_bytes) = rx.recv() {
             if let Ok(mut mqtt_client_lock) = mqtt_client.lock() {
-                let res_publish = mqtt_client_lock.mqtt_publish(topic, &cam_bytes, self.qos);
+                let res_publish = mqtt_client_lock.publish(&topic_name, &cam_bytes, self.qos.get_qos_for(topic));
                 match res_publish {
                     Ok(publish_msg) => {
                         self.logger.log(format!("Enviado msj: {:?}", publish_msg));
                     }
+                    Err(e) if e.is_ack_timeout() => {
+                        let msg = format!("ERROR: no se recibió el ack del publish a {}: {:?}", topic, e);
+                        println!("{}", msg);
+                        self.logger.log(msg);
+                    }
                     Err(e) => {
                         println!("Error al hacer publish {:?}", e);
                         self.logger.log(format!("Error al hacer publish {:?}", e));
@@ -215,37 +347,157 @@ impl SistemaCamaras {
     /// Hilo que se encarga de suscribirse a los topics y recibir los mensajes.
     fn spawn_subscribe_to_topics_thread(
         &mut self,
-        mqtt_client: Arc<Mutex<MQTTClient>>,
+        mqtt_client: ShPublisherType,
         msg_rx: Receiver<PublishMessage>,
         cameras_tx: Sender<Vec<u8>>,
+        frame_tx: Sender<CameraFrame>,
     ) -> JoinHandle<()> {
         let mut cameras_cloned = self.cameras.clone();
         let mut self_clone = self.clone_ref();
-        let topic = AppsMqttTopics::IncidentTopic.to_str();
+        let topics = self.get_topics_to_subscribe();
         thread::spawn(move || {
-            self_clone.subscribe_to_topics(mqtt_client.clone(), vec![(String::from(topic), self_clone.qos)]);
-            self_clone.receive_messages_from_subscribed_topics(msg_rx, &mut cameras_cloned, cameras_tx);
+            self_clone.subscribe_to_topics(mqtt_client.clone(), topics);
+            self_clone.receive_messages_from_subscribed_topics(msg_rx, &mut cameras_cloned, cameras_tx, frame_tx);
         })
     }
 
-    /// Recibe mensajes de los topics a los que se ha suscrito, y delega el procesamiento a `CamerasLogic`.
+    /// Devuelve los topics a los que se suscribe Sistema Cámaras.
+    fn get_topics_to_subscribe(&self) -> Vec<(String, u8)> {
+        [AppsMqttTopics::IncidentTopic, AppsMqttTopics::CameraFrameTopic]
+            .iter()
+            .map(|topic| (topic.to_str().to_string(), self.qos.get_qos_for(topic.to_str())))
+            .collect()
+    }
+
+    /// Hilo que revisa periódicamente si se perdió la conexión con el broker (ej. porque se reinició),
+    /// y en ese caso la recupera. Termina al solicitarse el `shutdown_token`, o si la conexión se perdió
+    /// por un error con el que no tiene sentido reconectarse (ver `ConnectionEnd::should_reconnect`).
+    fn spawn_recover_connection_thread(
+        &self,
+        mqtt_client: ShPublisherType,
+        cameras_tx: Sender<Vec<u8>>,
+        shutdown_token: ShutdownToken,
+    ) -> JoinHandle<()> {
+        let self_clone = self.clone_ref();
+        thread::spawn(move || {
+            while !shutdown_token.sleep_unless_requested(CONNECTION_CHECK_INTERVAL) {
+                let connection_end = match mqtt_client.lock() {
+                    Ok(mqtt_client_lock) => mqtt_client_lock.get_connection_end(),
+                    Err(_) => break,
+                };
+                match connection_end {
+                    Some(end) if end.should_reconnect() => {
+                        self_clone.recover_connection(&mqtt_client, &cameras_tx, &shutdown_token);
+                    }
+                    Some(end) => {
+                        // Error de protocolo: reconectarse probablemente no lo resuelva
+                        let error_msg = format!(
+                            "ERROR: se perdió la conexión con el broker y no se reconecta: {:?}. Ingrese la opción Salir del menú.",
+                            end
+                        );
+                        println!("{}", error_msg);
+                        self_clone.logger.log(error_msg);
+                        break;
+                    }
+                    None => {}
+                }
+            }
+        })
+    }
+
+    /// Se reconecta al broker, vuelve a suscribirse a los topics (el broker reiniciado no conserva las suscripciones),
+    /// y vuelve a publicar todas las cámaras, para que el broker y sistema monitoreo tengan su estado actual.
+    fn recover_connection(
+        &self,
+        mqtt_client: &ShPublisherType,
+        cameras_tx: &Sender<Vec<u8>>,
+        shutdown_token: &ShutdownToken,
+    ) {
+        println!("Se perdió la conexión con el broker, reconectando...");
+        self.logger
+            .log("Recuperación: se perdió la conexión con el broker, reconectando.".to_string());
+        if !reconnect_with_backoff(
+            mqtt_client,
+            DEFAULT_INITIAL_BACKOFF,
+            DEFAULT_MAX_BACKOFF,
+            shutdown_token,
+            &self.logger,
+        ) {
+            self.logger
+                .log("Recuperación: se solicitó terminar antes de reconectarse.".to_string());
+            return;
+        }
+
+        // Si el broker conservaba la sesión, las suscripciones siguen vigentes
+        let is_session_present = match mqtt_client.lock() {
+            Ok(client) => client.is_session_present(),
+            Err(_) => false,
+        };
+        if is_session_present {
+            self.logger
+                .log("Recuperación: el broker conservaba la sesión, no es necesario volver a suscribirse.".to_string());
+        } else {
+            let topics = self.get_topics_to_subscribe();
+            self.logger
+                .log(format!("Recuperación: volviendo a suscribirse a {:?}.", topics));
+            self.subscribe_to_topics(mqtt_client.clone(), topics);
+        }
+
+        match snapshot_cameras(&self.cameras) {
+            Ok(cameras) => {
+                self.logger.log(format!(
+                    "Recuperación: volviendo a publicar las {} cámaras.",
+                    cameras.len()
+                ));
+                for camera in cameras {
+                    if cameras_tx.send(camera.to_bytes()).is_err() {
+                        self.logger.log(
+                            "Recuperación: error al enviar cámara para ser publicada.".to_string(),
+                        );
+                    }
+                }
+            }
+            Err(e) => self
+                .logger
+                .log(format!("Recuperación: error al obtener las cámaras: {:?}.", e)),
+        }
+        println!("Conexión con el broker recuperada.");
+        self.logger
+            .log("Recuperación: conexión con el broker recuperada.".to_string());
+    }
+
+    /// Recibe mensajes de los topics a los que se ha suscrito: los incidentes los procesa `CamerasLogic`,
+    /// y los frames se envían por `frame_tx` al detector automático de incidentes.
     fn receive_messages_from_subscribed_topics(
         &mut self,
         rx: Receiver<PublishMessage>,
         cameras: &mut ShCamerasType,
         cameras_tx: Sender<Vec<u8>>,
+        frame_tx: Sender<CameraFrame>,
     ) {
         let mut logic = CamerasLogic::new(
             cameras.clone(),
             cameras_tx.clone(),
             self.logger.clone_ref(),
         );
+        logic.set_deactivation_margin(self.deactivation_margin_meters);
 
         for msg in rx {
-            if let Ok(incident) = Incident::from_bytes(msg.get_payload()) {
-                self.logger.log(format!("Inc recibido: {:?}", incident));
-                if let Err(e) = logic.manage_incident(incident) {
-                    self.logger.log(format!("Error al procesar incidente: {:?}.", e));
+            match AppMessage::from_publish(&msg) {
+                Ok(AppMessage::Incident(incident)) => {
+                    self.logger.log(format!("Inc recibido: {:?}", incident));
+                    if let Err(e) = logic.manage_incident(incident) {
+                        self.logger.log(format!("Error al procesar incidente: {:?}.", e));
+                    }
+                }
+                Ok(AppMessage::CameraFrame(frame)) => {
+                    if let Err(e) = frame_tx.send(frame) {
+                        self.logger.log(format!("Error al enviar frame al detector: {:?}.", e));
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    self.logger.log(format!("Error al leer mensaje recibido: {:?}.", e));
                 }
             }
         }
@@ -256,19 +508,64 @@ impl SistemaCamaras {
     fn clone_ref(&self) -> Self {
         Self {
             cameras: self.cameras.clone(),
-            qos: self.qos,
+            qos: self.qos.clone(),
+            detector_paused: self.detector_paused.clone(),
+            deactivation_margin_meters: self.deactivation_margin_meters,
             logger: self.logger.clone_ref(),
         }
     }
 }
 
+/// Publica el incidente `inc` al topic de incidentes. Si no se recibe su ack, lo vuelve a publicar
+/// hasta `INC_PUBLISH_ATTEMPTS` veces en total; si aun así no se recibe, lo informa como error.
+/// Devuelve si se pudo publicar.
+fn publish_incident(mqtt_client: &ShPublisherType, inc: &Incident, qos: u8, logger: &StringLogger) -> bool {
+    for attempt in 1..=INC_PUBLISH_ATTEMPTS {
+        let res_publish = match mqtt_client.lock() {
+            Ok(mut mqtt_client_lock) => mqtt_client_lock.publish(
+                &Topic::from(AppsMqttTopics::IncidentTopic),
+                &inc.to_bytes(),
+                qos,
+            ),
+            Err(_) => return false,
+        };
+        match res_publish {
+            Ok(publish_message) => {
+                logger.log(format!("Publico inc: {:?}", publish_message));
+                return true;
+            }
+            Err(e) if e.is_ack_timeout() => {
+                let msg = format!(
+                    "No se recibió el ack del inc {} (intento {}): {:?}",
+                    inc.get_id(),
+                    attempt,
+                    e
+                );
+                println!("{}", msg);
+                logger.log(msg);
+            }
+            Err(e) => {
+                println!("Error al hacer el publish {:?}", e);
+                logger.log(format!("Error al hacer el publish {:?}", e));
+                return false;
+            }
+        }
+    }
+    let msg = format!("ERROR: el inc {} no pudo publicarse, nunca se recibió su ack.", inc.get_id());
+    println!("{}", msg);
+    logger.log(msg);
+    false
+}
+
 fn spawn_exit_when_asked_thread(
-    mqtt_client_sh: Arc<Mutex<MQTTClient>>,
+    mqtt_client_sh: ShPublisherType,
     exit_rx: Receiver<bool>,
     exit_detector_tx: Sender<()>,
+    shutdown_token: ShutdownToken,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         exit_when_asked(mqtt_client_sh, exit_rx);
+        shutdown_token.request();
         println!("Hilo exit recibe pedido de exit. Por propagarlo al detector...");
         if let Err(e) = exit_detector_tx.send(()) {
             //logger.log(format!("Error al enviar por exit_detector_tx: {:?}.", e)); // podría recibir un logger quizás
@@ -277,3 +574,64 @@ fn spawn_exit_when_asked_thread(
         println!("Hilo exit: Listo.");
     })
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{mpsc, Arc, Mutex},
+    };
+
+    use super::SistemaCamaras;
+    use crate::{
+        apps::{
+            apps_mqtt_topics::AppsMqttTopics,
+            config::AppConfig,
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            sist_camaras::{
+                camera::Camera, camera_frame::CameraFrame, camera_state::CameraState,
+                types::shareable_publisher_type::ShPublisherType,
+            },
+        },
+        logging::string_logger::StringLogger,
+        mqtt::{
+            client::publisher::{NullPublisher, Publisher},
+            mqtt_utils::topic::Topic,
+        },
+    };
+
+    #[test]
+    fn test_1_con_null_publisher_se_publica_la_camara_que_atiende_el_incidente() {
+        let (lat, lon) = (-34.6037344, -58.3861838);
+        let mut cams = HashMap::new();
+        cams.insert(1, Camera::new(1, lat, lon, 5));
+        let mut cameras = Arc::new(Mutex::new(cams));
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        let mut sistema_camaras =
+            SistemaCamaras::new(cameras.clone(), &AppConfig::default(), StringLogger::new(logger_tx));
+
+        // El publisher que recibe Sistema Cámaras comparte los registros con `publisher`
+        let publisher = NullPublisher::new();
+        let publisher_sh: ShPublisherType = Arc::new(Mutex::new(Box::new(publisher.clone())));
+        let (cameras_tx, cameras_rx) = mpsc::channel::<Vec<u8>>();
+        let handle = sistema_camaras.spawn_publish_to_topic_thread(publisher_sh, cameras_rx);
+
+        // Se recibe un incidente en el rango de la cámara
+        let inc = Incident::new(1, (lat, lon), IncidentSource::Manual);
+        let inc_msg = NullPublisher::new()
+            .publish(&Topic::from(AppsMqttTopics::IncidentTopic), &inc.to_bytes(), 1)
+            .unwrap();
+        let (msg_tx, msg_rx) = mpsc::channel();
+        msg_tx.send(inc_msg).unwrap();
+        drop(msg_tx);
+        let (frame_tx, _frame_rx) = mpsc::channel::<CameraFrame>();
+        sistema_camaras.receive_messages_from_subscribed_topics(msg_rx, &mut cameras, cameras_tx, frame_tx);
+        handle.join().unwrap();
+
+        let published = publisher.get_published_to(AppsMqttTopics::CameraTopic.to_str());
+        assert_eq!(published.len(), 1);
+        let camera = Camera::from_bytes(&published[0]).unwrap();
+        assert_eq!(camera.get_id(), 1);
+        assert_eq!(camera.get_state(), CameraState::Active);
+    }
+}
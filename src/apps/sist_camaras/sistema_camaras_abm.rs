@@ -1,10 +1,12 @@
 use std::{
     collections::HashMap, io::{stdin, stdout, Error, Write}, sync::{
+        atomic::{AtomicBool, Ordering},
         mpsc::Sender,
         Arc, Mutex,
     }
 };
 
+use crate::apps::coordinates::check_finite_coordinates;
 use crate::logging::string_logger::StringLogger;
 
 use super::camera::Camera;
@@ -13,21 +15,24 @@ pub struct ABMCameras {
     cameras: Arc<Mutex<HashMap<u8, Camera>>>,
     camera_tx: Sender<Vec<u8>>,
     exit_tx: Sender<bool>,
+    detector_paused: Arc<AtomicBool>,
     logger: StringLogger,
 }
 
 impl ABMCameras {
-    /// Crea un struct `ABMCameras`.
+    /// Crea un struct `ABMCameras`. Con `detector_paused` se pausa y reanuda el detector de incidentes.
     pub fn new(
         cameras: Arc<Mutex<HashMap<u8, Camera>>>,
         camera_tx: Sender<Vec<u8>>,
         exit_tx: Sender<bool>,
+        detector_paused: Arc<AtomicBool>,
         logger: StringLogger,
     ) -> Self {
         ABMCameras {
             cameras,
             camera_tx,
             exit_tx,
+            detector_paused,
             logger,
         }
     }
@@ -52,6 +57,9 @@ impl ABMCameras {
                     self.exit_program_abm();
                     break;
                 }
+                "5" | "list" => self.list_cameras_with_incs_abm(),
+                cmd if cmd.starts_with("mod") => self.modify_camera_abm(cmd),
+                cmd if cmd.starts_with("detector") => self.pause_or_resume_detector_abm(cmd),
                 _ => {
                     println!("Opción no válida. Intente nuevamente.\n");
                 }
@@ -67,6 +75,9 @@ impl ABMCameras {
         2. Mostrar cámaras
         3. Eliminar cámara
         4. Salir
+        5. Listar cámaras con sus incidentes (list)
+        Modificar rango de una cámara: mod <id> range <valor>
+        Pausar o reanudar el detector de incidentes: detector pause | detector resume
         Ingrese una opción:"
         );
     }
@@ -86,6 +97,8 @@ impl ABMCameras {
         let latitude = self.read_input_and_parse_to_f64("la latitud")?;
         let longitude = self.read_input_and_parse_to_f64("la longitud")?;
         let range = self.read_input_and_parse_to_u8("el rango")?;
+        // "NaN" e "inf" se parsean como f64 válidos
+        check_finite_coordinates(latitude, longitude)?;
 
         Ok(Camera::new(id, latitude, longitude, range))
     }
@@ -145,17 +158,19 @@ impl ABMCameras {
 
     /// Procesa una nueva cámara (la inserta en el hashmap de cameras, maneja las lindantes), y la envía por un
     /// channel para que desde el rx el sistema cámaras le pueda hacer publish. Además, logguea la operación.
-    fn process_and_send_camera(&mut self, new_camera: Camera) {
+    fn process_and_send_camera(&mut self, mut new_camera: Camera) {
         match self.cameras.lock() {
             Ok(mut cams) => {
                 // Recorre las cámaras ya existentes, agregando la nueva cámara como lindante de la que corresponda y viceversa, terminando la creación
                 for camera in cams.values_mut() {
-                    camera.mutually_add_if_bordering(&mut new_camera.clone());
+                    camera.mutually_add_if_bordering(&mut new_camera);
                 }
                 self.logger.log(format!("Sistema-Camaras: envió cámara: {:?}", new_camera));
                 // Envía la nueva cámara por tx, para ser publicada por el otro hilo
                 if self.camera_tx.send(new_camera.to_bytes()).is_err() {
                     println!("Error al enviar cámara por tx desde hilo abm.");
+                } else {
+                    new_camera.mark_as_sent();
                 }
                 // Guarda la nueva cámara
                 cams.insert(new_camera.get_id(), new_camera);
@@ -182,6 +197,112 @@ impl ABMCameras {
         }
     }
 
+    /// Opción Listar cámaras del abm. Muestra en forma de tabla, ordenadas por id, todas las cámaras existentes
+    /// junto con su estado, posición, lindantes, y los incidentes a los que están prestando atención.
+    fn list_cameras_with_incs_abm(&self) {
+        // Se toma una copia de las cámaras, para no retener el lock mientras se imprime
+        let cameras_snapshot: Vec<Camera> = match self.cameras.lock() {
+            Ok(cams) => cams
+                .values()
+                .filter(|camera| camera.is_not_deleted())
+                .cloned()
+                .collect(),
+            Err(_) => {
+                println!("Error al tomar lock de cámaras.");
+                return;
+            }
+        };
+
+        println!("{}", format_cameras_table(cameras_snapshot));
+    }
+
+    /// Opción Modificar cámara, del abm. Interpreta el comando `mod <id> range <valor>` y aplica la modificación.
+    fn modify_camera_abm(&self, command: &str) {
+        match parse_modify_range_command(command) {
+            Ok((id, new_range)) => self.modify_camera_range(id, new_range),
+            Err(e) => println!("{} Uso: mod <id> range <valor>.\n", e),
+        }
+    }
+
+    /// Opción Pausar o reanudar el detector, del abm. Interpreta el comando `detector pause|resume`.
+    /// Mientras está pausado, el detector descarta los frames recibidos sin emitir incidentes.
+    fn pause_or_resume_detector_abm(&self, command: &str) {
+        match parse_detector_command(command) {
+            Ok(pause) => self.set_detector_paused(pause),
+            Err(e) => println!("{} Uso: detector pause | detector resume.\n", e),
+        }
+    }
+
+    fn set_detector_paused(&self, pause: bool) {
+        let was_paused = self.detector_paused.swap(pause, Ordering::SeqCst);
+        let state = if pause { "pausado" } else { "reanudado" };
+        if was_paused == pause {
+            println!("El detector ya estaba {}.\n", state);
+            return;
+        }
+        println!("Detector {}.\n", state);
+        self.logger.log(format!("Sistema-Camaras: detector {} desde el abm.", state));
+    }
+
+    /// Modifica el rango de la cámara de id `id`, recalcula sus lindantes, deja de prestar atención a
+    /// los incidentes que hayan quedado fuera de alcance, y envía las cámaras modificadas para que se publiquen.
+    fn modify_camera_range(&self, id: u8, new_range: u8) {
+        match self.cameras.lock() {
+            Ok(mut cams) => {
+                let mut camera = match cams.remove(&id) {
+                    Some(camera) if camera.is_not_deleted() => camera,
+                    Some(deleted_camera) => {
+                        cams.insert(id, deleted_camera);
+                        println!("La cámara no existe.\n");
+                        return;
+                    }
+                    None => {
+                        println!("La cámara no existe.\n");
+                        return;
+                    }
+                };
+                camera.set_range(new_range);
+
+                // Recalcula las lindantes de la cámara modificada contra todas las demás
+                camera.get_bordering_cams().clear();
+                for other in cams.values_mut() {
+                    let (was_bordering, was_dirty) = (other.get_bordering_cams().contains(&id), other.is_dirty());
+                    other.remove_from_list_if_bordering(&mut camera);
+                    if other.is_not_deleted() {
+                        camera.mutually_add_if_bordering(other);
+                    }
+                    // Si sigue siendo lindante (o sigue sin serlo), no cambió: no hace falta volver a publicarla
+                    if was_bordering == other.get_bordering_cams().contains(&id) && !was_dirty {
+                        other.mark_as_sent();
+                    }
+                }
+
+                // Deja de prestar atención a los incidentes que ya no están a su alcance ni al de sus lindantes
+                for (inc_info, inc_position) in camera.get_incs_being_managed() {
+                    let in_own_range = camera.will_register(inc_position);
+                    let in_bordering_range = camera.get_bordering_cams().iter().any(|bordering_id| {
+                        cams.get(bordering_id)
+                            .is_some_and(|bordering| bordering.will_register(inc_position))
+                    });
+                    if !in_own_range && !in_bordering_range {
+                        camera.remove_from_incs_being_managed(inc_info);
+                    }
+                }
+
+                self.logger.log(format!("Sistema-Camaras: modificada cámara: {:?}", camera));
+                if camera.is_dirty() {
+                    self.send_camera_bytes(&mut camera, &self.camera_tx);
+                }
+                for changed_camera in cams.values_mut().filter(|other| other.is_dirty()) {
+                    self.send_camera_bytes(changed_camera, &self.camera_tx);
+                }
+                cams.insert(id, camera);
+                println!("Cámara modificada con éxito.\n");
+            }
+            Err(e) => println!("Error tomando lock en modificar cámara abm, {:?}.\n", e),
+        }
+    }
+
     /// Opción Eliminar cámara, del abm.
     /// Elimina la cámara indicada, manejando sus lindantes, y la envía por tx para que rx haga publish.
     fn delete_camera_abm(&self) {
@@ -204,13 +325,16 @@ impl ABMCameras {
                         }
 
                         // Envía por el tx la cámara a eliminar para que se publique desde el otro hilo
-                        // (con eso es suficiente. Si bien se les eliminó una lindante, no es necesario publicar el cambio
-                        // de las demás ya que eso solo es relevante para sistema camaras)
                         if self.camera_tx.send(camera_to_delete.to_bytes()).is_err() {
                             println!("Error al enviar cámara por tx desde hilo abm.");
                         } else {
                             println!("Cámara eliminada con éxito.\n");
                         }
+                        // Y las lindantes a las que se les eliminó, para que quienes las reciban no sigan
+                        // teniendo en su lista de lindantes a la cámara eliminada
+                        for changed_camera in cams.values_mut().filter(|camera| camera.is_dirty()) {
+                            self.send_camera_bytes(changed_camera, &self.camera_tx);
+                        }
                     };
                 } else {
                     println!("La cámara no existe.\n");
@@ -231,8 +355,8 @@ impl ABMCameras {
     /// Recorre las cámaras y envía cada una por el channel, para que quien lea del rx haga el publish.
     fn send_cameras_from_file_to_publish(&self) {
         match self.cameras.lock() {
-            Ok(cams) => {
-                for camera in (*cams).values() {
+            Ok(mut cams) => {
+                for camera in cams.values_mut() {
                     println!("Iniciando, enviando cámara: {:?}", camera);
                     self.send_camera_bytes(camera, &self.camera_tx);
                 }
@@ -241,9 +365,9 @@ impl ABMCameras {
         }
     }
 
-    /// Envía la cámara recibida, por el channel, para que quien la reciba por rx haga el publish.
-    /// Además logguea la operación.
-    fn send_camera_bytes(&self, camera: &Camera, camera_tx: &Sender<Vec<u8>>) {
+    /// Envía la cámara recibida, por el channel, para que quien la reciba por rx haga el publish,
+    /// y la marca como enviada. Además logguea la operación.
+    fn send_camera_bytes(&self, camera: &mut Camera, camera_tx: &Sender<Vec<u8>>) {
         self.logger
             .log(format!("Sistema-Camaras: envío cámara: {:?}", camera));
 
@@ -251,25 +375,105 @@ impl ABMCameras {
             println!("Error al enviar cámara por tx desde hilo abm.");
             self.logger
                 .log("Sistema-Camaras: error al enviar cámara por tx desde hilo abm.".to_string());
+        } else {
+            camera.mark_as_sent();
         }
     }
 }
 
+/// Parsea un comando de la forma `mod <id> range <valor>`, devolviendo el id y el nuevo rango.
+fn parse_modify_range_command(command: &str) -> Result<(u8, u8), Error> {
+    let parts: Vec<&str> = command.split_whitespace().collect();
+    if parts.len() != 4 || parts[0] != "mod" || parts[2] != "range" {
+        return Err(Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Comando de modificación inválido.",
+        ));
+    }
+    let id = parts[1]
+        .parse::<u8>()
+        .map_err(|_| Error::new(std::io::ErrorKind::InvalidInput, "Id inválido."))?;
+    let new_range = parts[3]
+        .parse::<u8>()
+        .map_err(|_| Error::new(std::io::ErrorKind::InvalidInput, "Rango inválido."))?;
+    Ok((id, new_range))
+}
+
+/// Parsea un comando de la forma `detector pause` o `detector resume`, devolviendo si se pide pausar al detector.
+fn parse_detector_command(command: &str) -> Result<bool, Error> {
+    let parts: Vec<&str> = command.split_whitespace().collect();
+    match parts.as_slice() {
+        ["detector", "pause"] => Ok(true),
+        ["detector", "resume"] => Ok(false),
+        _ => Err(Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "Comando del detector inválido.",
+        )),
+    }
+}
+
+/// Devuelve una tabla, ordenada por id, con los datos de las cámaras recibidas y los incidentes que cada una maneja.
+fn format_cameras_table(mut cameras: Vec<Camera>) -> String {
+    cameras.sort_by_key(|camera| camera.get_id());
+
+    let mut table = format!(
+        "{:<4} | {:<10} | {:<12} | {:<12} | {:<15} | {}\n",
+        "ID", "Estado", "Latitud", "Longitud", "Lindantes", "Incidentes"
+    );
+    table.push_str(&format!("{}\n", "-".repeat(80)));
+
+    for camera in cameras.iter_mut() {
+        let (id, incs) = camera.get_id_and_incs_for_debug_display();
+        let state = format!("{:?}", camera.get_state());
+        let (lat, lon) = camera.get_position();
+        let mut bordering = camera.get_bordering_cams().to_vec();
+        bordering.sort();
+        let incs_str: Vec<String> = incs
+            .iter()
+            .map(|inc| format!("{}({:?})", inc.get_inc_id(), inc.get_src()))
+            .collect();
+
+        table.push_str(&format!(
+            "{:<4} | {:<10} | {:<12.6} | {:<12.6} | {:<15} | {}\n",
+            id,
+            state,
+            lat,
+            lon,
+            format!("{:?}", bordering),
+            incs_str.join(", ")
+        ));
+    }
+
+    table
+}
+
 #[cfg(test)]
 mod test {
     use std::{
         collections::HashMap,
-        sync::{mpsc, Arc, Mutex},
+        sync::{atomic::{AtomicBool, Ordering}, mpsc, Arc, Mutex},
     };
 
-    use crate::{apps::sist_camaras::camera::Camera, logging::string_logger::StringLogger};
+    use crate::{
+        apps::{
+            incident_data::{incident_info::IncidentInfo, incident_source::IncidentSource},
+            sist_camaras::{camera::Camera, camera_state::CameraState},
+        },
+        logging::string_logger::StringLogger,
+    };
 
-    use super::ABMCameras;
+    use super::{format_cameras_table, parse_detector_command, parse_modify_range_command, ABMCameras};
 
     fn create_abm() -> ABMCameras {
+        let (abm, _camera_rx) = create_abm_with_camera_rx();
+        abm
+    }
+
+    /// Crea el abm, y devuelve también el rx por el que envía las cámaras a publicar.
+    fn create_abm_with_camera_rx() -> (ABMCameras, mpsc::Receiver<Vec<u8>>) {
         // Unos tx irrelevantes, para pasar al new de abm
         // (es necesario conservar las variables de rx en el test de todas formas, para que no se cierre el channel antes del assert)
-        let (camera_tx, _camera_rx) = mpsc::channel();
+        let (camera_tx, camera_rx) = mpsc::channel();
         let (exit_tx, _exit_rx) = mpsc::channel();
 
         // Se crea el abm con su cameras
@@ -279,7 +483,8 @@ mod test {
         let (string_logger_tx, _string_logger_rx) = mpsc::channel(); // pero para testing, con esto.
         let logger_for_testing = StringLogger::new(string_logger_tx);
         
-        ABMCameras::new(cameras.clone(), camera_tx, exit_tx, logger_for_testing)
+        let detector_paused = Arc::new(AtomicBool::new(false));
+        (ABMCameras::new(cameras.clone(), camera_tx, exit_tx, detector_paused, logger_for_testing), camera_rx)
     }
 
     #[test]
@@ -322,4 +527,90 @@ mod test {
         // La cámara nueva se ha agregado a cameras
         assert!(!is_cam_to_remove_stored);
     }
+
+    #[test]
+    fn test_3_tabla_de_camaras_esta_ordenada_por_id() {
+        let cameras = vec![
+            Camera::new(3, -34.0, -58.0, 5),
+            Camera::new(1, -34.1, -58.1, 5),
+            Camera::new(2, -34.2, -58.2, 5),
+        ];
+
+        let table = format_cameras_table(cameras);
+
+        // Se salta el encabezado y el separador, y se toma el id de cada fila
+        let ids: Vec<&str> = table
+            .lines()
+            .skip(2)
+            .filter_map(|line| line.split('|').next())
+            .map(|id| id.trim())
+            .collect();
+        assert_eq!(ids, vec!["1", "2", "3"]);
+    }
+
+    #[test]
+    fn test_4_parseo_del_comando_mod_range() {
+        assert_eq!(parse_modify_range_command("mod 3 range 10").unwrap(), (3, 10));
+        assert!(parse_modify_range_command("mod 3 rango 10").is_err());
+        assert!(parse_modify_range_command("mod x range 10").is_err());
+        assert!(parse_modify_range_command("mod 3 range").is_err());
+    }
+
+    #[test]
+    fn test_5_achicar_rango_deja_de_atender_incidentes_fuera_de_alcance() {
+        let mut abm = create_abm();
+        let (lat, lon) = (-34.6040, -58.3873);
+
+        // Cámara con rango 5, prestando atención a un incidente que queda fuera del rango 1
+        let mut camera = Camera::new(1, lat, lon, 5);
+        let inc_info = IncidentInfo::new(1, IncidentSource::Manual);
+        camera.append_to_incs_being_managed(inc_info, (lat + 0.005, lon));
+        abm.process_and_send_camera(camera);
+
+        abm.modify_camera_range(1, 1);
+
+        let cams = abm.cameras.lock().unwrap();
+        let camera = cams.get(&1).unwrap();
+        assert!(camera.get_incs_being_managed().is_empty());
+        assert_eq!(camera.get_state(), CameraState::SavingMode);
+    }
+
+    #[test]
+    fn test_6_al_eliminar_una_camara_sus_lindantes_dejan_de_listarla_y_se_vuelven_a_publicar() {
+        let (mut abm, camera_rx) = create_abm_with_camera_rx();
+        // Las cámaras 5 y 6 son lindantes de la 1; la 9 está lejos
+        abm.process_and_send_camera(Camera::new(1, -34.6040, -58.3873, 1));
+        abm.process_and_send_camera(Camera::new(5, -34.6039, -58.3837, 1));
+        abm.process_and_send_camera(Camera::new(6, -34.6041, -58.3880, 1));
+        abm.process_and_send_camera(Camera::new(9, -34.6500, -58.4500, 1));
+        while camera_rx.try_recv().is_ok() {}
+
+        abm.delete_camera(1);
+
+        let mut published: Vec<Camera> = camera_rx
+            .try_iter()
+            .map(|bytes| Camera::from_bytes(&bytes).unwrap())
+            .collect();
+        let mut published_ids: Vec<u8> = published.iter().map(|camera| camera.get_id()).collect();
+        published_ids.sort();
+        assert_eq!(published_ids, vec![1, 5, 6]);
+        for camera in published.iter_mut().filter(|camera| camera.is_not_deleted()) {
+            assert!(!camera.get_bordering_cams().contains(&1));
+        }
+        let mut cams = abm.cameras.lock().unwrap();
+        assert!(cams.values_mut().all(|camera| !camera.get_bordering_cams().contains(&1)));
+    }
+
+    #[test]
+    fn test_7_el_comando_detector_pausa_y_reanuda_al_detector() {
+        assert!(parse_detector_command("detector pause").unwrap());
+        assert!(!parse_detector_command("detector  resume").unwrap());
+        assert!(parse_detector_command("detector stop").is_err());
+
+        let abm = create_abm();
+        abm.pause_or_resume_detector_abm("detector pause");
+        assert!(abm.detector_paused.load(Ordering::SeqCst));
+        abm.pause_or_resume_detector_abm("detector resume");
+        assert!(!abm.detector_paused.load(Ordering::SeqCst));
+    }
 }
@@ -1,21 +1,26 @@
-use std::{
-    collections::HashMap,
-    io::{Error, ErrorKind},
-    sync::{mpsc::Sender, MutexGuard},
-};
+use std::{collections::HashMap, io::Error, sync::mpsc::Sender};
 
-use crate::{apps::incident_data::incident::Incident, logging::string_logger::StringLogger};
+use crate::{
+    apps::{config::AppConfig, incident_data::incident::Incident},
+    logging::string_logger::StringLogger,
+};
 
 use crate::apps::sist_camaras::{
     camera::Camera,
+    shared_cameras::{snapshot_cameras, update_cameras},
     types::{hashmap_incs_type::HashmapIncsType, shareable_cameras_type::ShCamerasType},
 };
 
+/// Margen en metros, más allá del rango de una cámara, hasta el que sigue a un incidente que se desplaza,
+/// si no se configura otro (ver `read_deactivation_margin`).
+pub const DEFAULT_DEACTIVATION_MARGIN_METERS: f64 = 15.0;
+
 #[derive(Debug)]
 pub struct CamerasLogic {
     cameras: ShCamerasType,
     incs_being_managed: HashmapIncsType,
     cameras_tx: Sender<Vec<u8>>,
+    deactivation_margin_meters: f64,
     logger: StringLogger,
 }
 
@@ -26,10 +31,17 @@ impl CamerasLogic {
             cameras,
             incs_being_managed: HashMap::new(),
             cameras_tx,
+            deactivation_margin_meters: DEFAULT_DEACTIVATION_MARGIN_METERS,
             logger,
         }
     }
 
+    /// Establece el margen en metros, más allá de su rango, hasta el que una cámara sigue a un incidente
+    /// que se desplaza (ver `process_moved_incident`).
+    pub fn set_deactivation_margin(&mut self, margin_meters: f64) {
+        self.deactivation_margin_meters = margin_meters;
+    }
+
     /// Procesa un Incidente recibido.
     pub fn manage_incident(&mut self, incident: Incident) -> Result<(), Error>{
         // Proceso los incidentes
@@ -42,139 +54,152 @@ impl CamerasLogic {
 
     // Aux: (condición "hasta que" del enunciado).
     /// Procesa un incidente cuando un incidente con ese mismo id ya fue recibido anteriormente.
-    /// Si su estado es resuelto, vuelve el estado de la/s cámara/s que lo atendían, a ahorro de energía.
+    /// Si fue resuelto o cancelado, vuelve el estado de la/s cámara/s que lo atendían, a ahorro de energía.
+    /// Si no, su posición pudo haber cambiado (ver `process_moved_incident`).
     fn process_known_incident(&mut self, inc: Incident) -> Result<(), Error> {
-        if inc.is_resolved() {
+        if inc.is_closed() {
             self.logger.log(format!(
-                "Recibo el inc {} de nuevo, ahora con estado resuelto.",
-                inc.get_id()
+                "Recibo el inc {} de nuevo, ahora con status {:?}.",
+                inc.get_id(),
+                inc.get_status()
             ));
-            // Busco la/s cámara/s que atendían este incidente
-            if let Some(cams_managing_inc) = self.incs_being_managed.get(&inc.get_info()) {
-                // sé que existe, por el if de más arriba
-
+            // Elimino la entrada del hashmap que busca por incidente, ya no le doy seguimiento
+            if let Some(cams_managing_inc) = self.incs_being_managed.remove(&inc.get_info()) {
                 // Cambio el estado de las cámaras que lo manejaban, otra vez a ahorro de energía
                 // solamente si el incidente en cuestión era el único que manejaban (si tenía más incidentes en rango, sigue estando activa)
-                for camera_id in cams_managing_inc {
-                    match self.cameras.lock() {
-                        Ok(mut cams) => {
-                            if let Some(cam_to_update) = cams.get_mut(camera_id) {
-                                self.stop_paying_attention_to(&inc, cam_to_update);
-                            }
-                        }
-                        Err(_) => return Err(Error::new(
-                            ErrorKind::Other,
-                            "Error al tomar lock en process_first_time_incident.",
-                        ))
-                    };
+                let changed_cams = update_cameras(&self.cameras, &cams_managing_inc, |camera| {
+                    camera.remove_from_incs_being_managed(inc.get_info());
+                })?;
+                for camera in changed_cams {
+                    self.logger
+                        .log(format!("Cambiado a SavingMode: {:?}", camera));
+                    self.send_camera_bytes(&camera, &self.cameras_tx);
                 }
             }
-            // También elimino la entrada del hashmap que busca por incidente, ya no le doy seguimiento
-            self.incs_being_managed.remove(&inc.get_info());
+        } else {
+            self.process_moved_incident(inc)?;
         }
         Ok(())
     }
 
-    /// Elimina el incidente `inc` de la lista de incs a los que la cámara `cam_to_update` estaba prestando atención.
-    /// Si eso trajo como consecuencia que la misma volviera a estado `SavingMode` (ie el removido era su último incidente),
-    /// entonces envío la cámara para ser publicada por MQTT ya que la misma ha cambiado.
-    fn stop_paying_attention_to(&self, inc: &Incident, cam_to_update: &mut Camera) {
-        // Actualizo la cámara en cuestión
-        let state_has_changed = cam_to_update.remove_from_incs_being_managed(inc.get_info());
+    /// Procesa un incidente ya conocido que sigue abierto, cuya posición pudo haber cambiado.
+    /// Las cámaras comienzan a seguirlo al entrar en su rango, pero las que ya lo seguían dejan de hacerlo recién
+    /// cuando sale de su rango más `deactivation_margin_meters`: así, un incidente que oscila en el borde del rango
+    /// de una cámara no la hace alternar entre Active y SavingMode, publicándola cada vez.
+    fn process_moved_incident(&mut self, inc: Incident) -> Result<(), Error> {
+        let inc_info = inc.get_info();
+        let inc_position = inc.get_position();
+        let previously_following = self.incs_being_managed.get(&inc_info).cloned().unwrap_or_default();
 
-        let info = cam_to_update.get_id_and_incs_for_debug_display();
-        self.logger
-            .log(format!(" la cám queda: cam id y lista de incs: {:?}", info));
+        let cameras_snapshot = snapshot_cameras(&self.cameras)?;
+        let margin_meters = self.deactivation_margin_meters;
+        let following = self.get_id_of_cams_that_follow_inc(cameras_snapshot, |camera| {
+            if previously_following.contains(&camera.get_id()) {
+                camera.will_keep_registering(inc_position, margin_meters)
+            } else {
+                camera.will_register(inc_position)
+            }
+        });
 
-        // La envío si cambió de estado
-        if state_has_changed {
+        // Las que dejan de seguirlo vuelven a ahorro de energía, si no tienen otros incidentes
+        let stopped_following: Vec<u8> = previously_following
+            .iter()
+            .filter(|cam_id| !following.contains(cam_id))
+            .copied()
+            .collect();
+        let mut changed_cams = update_cameras(&self.cameras, &stopped_following, |camera| {
+            camera.remove_from_incs_being_managed(inc_info);
+        })?;
+        // Las que lo siguen actualizan su posición, o comienzan a seguirlo
+        changed_cams.extend(update_cameras(&self.cameras, &following, |camera| {
+            if previously_following.contains(&camera.get_id()) {
+                camera.update_inc_position(inc_info, inc_position);
+            } else if !camera.is_failed() {
+                camera.append_to_incs_being_managed(inc_info, inc_position);
+            }
+        })?);
+        for camera in changed_cams {
             self.logger
-                .log(format!("Cambiado a SavingMode: {:?}", cam_to_update));
-            self.send_camera_bytes(cam_to_update, &self.cameras_tx);
+                .log(format!("Cambiado el estado por desplazamiento del incidente: {:?}", camera));
+            self.send_camera_bytes(&camera, &self.cameras_tx);
         }
+        self.incs_being_managed.insert(inc_info, following);
+        Ok(())
     }
 
     /// Procesa un incidente cuando el mismo fue recibido por primera vez.
     /// Para cada cámara ve si inc.pos está dentro de alcance de dicha cámara o sus lindantes,
     /// en caso afirmativo, se encarga de lo necesario para que la cámara y sus lindanes cambien su estado a activo.
+    /// El cálculo de rango se hace sobre una copia de las cámaras, y el lock se toma solamente para copiarlas
+    /// y para aplicar los cambios, de forma de no serializar el manejo de incidentes.
     fn process_first_time_incident(&mut self, inc: Incident) -> Result<(), Error> {
-        if !inc.is_resolved() {
-            // inc no resuelto
-            match self.cameras.lock() {
-                Ok(mut cams) => {
-                    println!("Proceso el incidente {:?} por primera vez", inc.get_info());
-                    self.logger.log(format!(
-                        "Proceso el incidente {:?} por primera vez",
-                        inc.get_info()
-                    ));
-                    let cameras_that_follow_inc =
-                        self.get_id_of_cams_that_will_change_state_to_active(&mut cams, &inc);
-
-                    // El vector tiene los ids de todas las cámaras que deben cambiar a activo
-                    for cam_id in &cameras_that_follow_inc {
-                        if let Some(bordering_cam) = cams.get_mut(cam_id) {
-                            self.start_paying_attention_to(&inc, bordering_cam);
-                        };
-                    }
-                    // Y se guarda las cámaras que le dan seguimiento al incidente, para luego poder encontrarlas fácilmente sin recorrer
-                    self.incs_being_managed
-                        .insert(inc.get_info(), cameras_that_follow_inc);
-                }
-                Err(_) => {
-                    return Err(Error::new(
-                        ErrorKind::Other,
-                        "Error al tomar lock en process_first_time_incident.",
-                    ))
+        if !inc.is_closed() {
+            // inc no resuelto ni cancelado
+            println!("Proceso el incidente {:?} por primera vez", inc.get_info());
+            self.logger.log(format!(
+                "Proceso el incidente {:?} por primera vez",
+                inc.get_info()
+            ));
+            let cameras_snapshot = snapshot_cameras(&self.cameras)?;
+            let inc_position = inc.get_position();
+            let cameras_that_follow_inc = self
+                .get_id_of_cams_that_follow_inc(cameras_snapshot, |camera| camera.will_register(inc_position));
+
+            // El vector tiene los ids de todas las cámaras que deben cambiar a activo.
+            // Agrega el inc a la lista de incs de cada una, para facilitar que luego puedan volver a su anterior estado
+            // (una cámara que falló luego de tomar la copia, no participa)
+            let changed_cams = update_cameras(&self.cameras, &cameras_that_follow_inc, |camera| {
+                if !camera.is_failed() {
+                    camera.append_to_incs_being_managed(inc.get_info(), inc.get_position());
                 }
+            })?;
+            for camera in changed_cams {
+                self.logger
+                    .log(format!("Cambiando a estado Active: {:?}", camera));
+                self.send_camera_bytes(&camera, &self.cameras_tx);
             }
+            // Y se guarda las cámaras que le dan seguimiento al incidente, para luego poder encontrarlas fácilmente sin recorrer
+            self.incs_being_managed
+                .insert(inc.get_info(), cameras_that_follow_inc);
         }
         Ok(())
     }
 
-    /// Devuelve un vector de u8 con los ids de todas las cámaras que darán seguimiento al incidente `inc`.
-    fn get_id_of_cams_that_will_change_state_to_active(
-        &self,
-        cams: &mut MutexGuard<'_, HashMap<u8, Camera>>,
-        inc: &Incident,
-    ) -> Vec<u8> {
+    /// Devuelve un vector de u8 con los ids de todas las cámaras que darán seguimiento a un incidente,
+    /// calculado a partir de una copia de las cámaras: las que lo registran según `registers`, y sus lindantes.
+    fn get_id_of_cams_that_follow_inc<F>(&self, mut cams: Vec<Camera>, registers: F) -> Vec<u8>
+    where
+        F: Fn(&Camera) -> bool,
+    {
         let mut cameras_that_follow_inc = vec![];
 
+        // Ids de las cámaras falladas, que no participan de la atención de incidentes
+        let failed_cams: Vec<u8> = cams
+            .iter()
+            .filter(|camera| camera.is_failed())
+            .map(|camera| camera.get_id())
+            .collect();
+
         // Recorremos cada una de las cámaras, para ver si el inc está en su rango
-        for (cam_id, camera) in cams.iter_mut() {
-            if camera.will_register(inc.get_position()) {
+        for camera in cams.iter_mut() {
+            if !camera.is_failed() && registers(camera) {
+                let cam_id = camera.get_id();
                 self.logger
                     .log(format!("En rango de cam: {}, cambiando a Activo.", cam_id));
 
                 // Si sí, se agrega ella
-                cameras_that_follow_inc.push(*cam_id);
-                // y sus lindantes
+                push_if_not_present(&mut cameras_that_follow_inc, cam_id);
+                // y sus lindantes que no estén falladas (un solo salto: no se agregan las lindantes de las lindantes)
                 for bordering_cam_id in camera.get_bordering_cams() {
-                    cameras_that_follow_inc.push(*bordering_cam_id);
+                    if !failed_cams.contains(bordering_cam_id) {
+                        push_if_not_present(&mut cameras_that_follow_inc, *bordering_cam_id);
+                    }
                 }
-
-                let info = camera.get_id_and_incs_for_debug_display();
-                self.logger
-                    .log(format!(" la cám queda: cam id y lista de incs: {:?}", info));
             }
         }
         cameras_that_follow_inc
     }
 
-    /// Agrega el incidente `inc` a la lista de incs a los que la cámara `cam_to_update` presta atención.
-    /// Si eso trae como consecuencia que la misma cambiara a estado `Active` (ie el agregado era su primer incidente),
-    /// entonces envío la cámara para ser publicada por MQTT ya que la misma ha cambiado.
-    fn start_paying_attention_to(&self, inc: &Incident, cam_to_update: &mut Camera) {
-        // Agrega el inc a la lista de incs de la cámara, y de sus lindantes, para facilitar que luego puedan volver a su anterior estado
-        let state_has_changed = cam_to_update.append_to_incs_being_managed(inc.get_info());
-
-        // La envío si cambió de estado
-        if state_has_changed {
-            self.logger
-                .log(format!("Cambiando a estado Active: {:?}", cam_to_update));
-            self.send_camera_bytes(cam_to_update, &self.cameras_tx);
-        }
-    }
-
     /// Envía la cámara recibida, por el channel, para que quien la reciba por rx haga el publish.
     /// Además logguea la operación.
     fn send_camera_bytes(&self, camera: &Camera, cameras_tx: &Sender<Vec<u8>>) {
@@ -188,3 +213,189 @@ impl CamerasLogic {
         }
     }
 }
+
+/// Lee de la propiedad `camera-deactivation-margin-m` el margen en metros, más allá de su rango, hasta el que
+/// una cámara sigue a un incidente que se desplaza. Si está ausente o no es válida, se usa el valor por defecto.
+pub fn read_deactivation_margin(config: &AppConfig) -> f64 {
+    match config.get("camera-deactivation-margin-m").map(|margin| margin.parse::<f64>()) {
+        Some(Ok(margin)) if margin >= 0.0 => margin,
+        Some(_) => {
+            println!("Propiedad 'camera-deactivation-margin-m' inválida, se usa el valor por defecto.");
+            DEFAULT_DEACTIVATION_MARGIN_METERS
+        }
+        None => DEFAULT_DEACTIVATION_MARGIN_METERS,
+    }
+}
+
+/// Agrega `cam_id` al vector, solamente si no estaba ya presente.
+/// Evita que una cámara que está en rango y a la vez es lindante de otra en rango
+/// se agregue dos veces, lo cual haría que quede activa luego de resolverse el incidente.
+fn push_if_not_present(cam_ids: &mut Vec<u8>, cam_id: u8) {
+    if !cam_ids.contains(&cam_id) {
+        cam_ids.push(cam_id);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        sync::{mpsc, Arc, Barrier, Mutex},
+        thread,
+    };
+
+    use super::CamerasLogic;
+    use crate::apps::sist_camaras::types::shareable_cameras_type::ShCamerasType;
+    use crate::{
+        apps::{
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            sist_camaras::{camera::Camera, camera_state::CameraState},
+        },
+        logging::string_logger::StringLogger,
+    };
+
+    #[test]
+    fn test_1_camaras_lindantes_en_rango_vuelven_a_ahorro_al_resolverse_el_incidente() {
+        let (lat, lon) = (-34.6037344, -58.3861838);
+        // Dos cámaras lindantes, ambas con el incidente en rango
+        let mut cam_1 = Camera::new(1, lat, lon, 5);
+        let mut cam_2 = Camera::new(2, lat + 0.0001, lon, 5);
+        cam_1.mutually_add_if_bordering(&mut cam_2);
+        let mut cams = HashMap::new();
+        cams.insert(1, cam_1);
+        cams.insert(2, cam_2);
+        let cameras = Arc::new(Mutex::new(cams));
+
+        let (cameras_tx, _cameras_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        let mut logic = CamerasLogic::new(
+            cameras.clone(),
+            cameras_tx,
+            StringLogger::new(logger_tx),
+        );
+
+        let mut inc = Incident::new(1, (lat, lon), IncidentSource::Manual);
+        logic.manage_incident(inc.clone()).unwrap();
+        for camera in cameras.lock().unwrap().values() {
+            assert_eq!(camera.get_state(), CameraState::Active);
+        }
+
+        inc.set_resolved();
+        logic.manage_incident(inc).unwrap();
+        for camera in cameras.lock().unwrap().values() {
+            assert_eq!(camera.get_state(), CameraState::SavingMode);
+        }
+    }
+
+    #[test]
+    fn test_2_un_incidente_cancelado_desactiva_la_camara_como_uno_resuelto() {
+        let (lat, lon) = (-34.6037344, -58.3861838);
+        let mut cams = HashMap::new();
+        cams.insert(1, Camera::new(1, lat, lon, 5));
+        let cameras = Arc::new(Mutex::new(cams));
+
+        let (cameras_tx, _cameras_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        let mut logic = CamerasLogic::new(
+            cameras.clone(),
+            cameras_tx,
+            StringLogger::new(logger_tx),
+        );
+
+        let mut inc = Incident::new(1, (lat, lon), IncidentSource::Manual);
+        logic.manage_incident(inc.clone()).unwrap();
+        assert_eq!(cameras.lock().unwrap()[&1].get_state(), CameraState::Active);
+
+        inc.set_cancelled();
+        logic.manage_incident(inc).unwrap();
+        assert_eq!(cameras.lock().unwrap()[&1].get_state(), CameraState::SavingMode);
+    }
+
+    fn create_logic(cameras: &ShCamerasType) -> CamerasLogic {
+        let (cameras_tx, _cameras_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        CamerasLogic::new(cameras.clone(), cameras_tx, StringLogger::new(logger_tx))
+    }
+
+    #[test]
+    fn test_3_incidentes_concurrentes_sobre_las_mismas_camaras_no_pierden_actualizaciones() {
+        let (lat, lon) = (-34.6037344, -58.3861838);
+        let mut cams = HashMap::new();
+        for id in 1..=4 {
+            cams.insert(id, Camera::new(id, lat + 0.0001 * id as f64, lon, 5));
+        }
+        let cameras: ShCamerasType = Arc::new(Mutex::new(cams));
+
+        for _ in 0..50 {
+            // Dos hilos, cada uno con su incidente en rango de todas las cámaras, lo activan y lo resuelven a la vez.
+            // El hilo del test se sincroniza con ellos para verificar el estado intermedio.
+            let barrier = Arc::new(Barrier::new(3));
+            let handles: Vec<_> = [1, 2]
+                .into_iter()
+                .map(|inc_id| {
+                    let mut logic = create_logic(&cameras);
+                    let barrier = Arc::clone(&barrier);
+                    thread::spawn(move || {
+                        let mut inc = Incident::new(inc_id, (lat, lon), IncidentSource::Manual);
+                        barrier.wait();
+                        logic.manage_incident(inc.clone()).unwrap();
+                        barrier.wait();
+                        barrier.wait();
+                        inc.set_resolved();
+                        logic.manage_incident(inc).unwrap();
+                    })
+                })
+                .collect();
+            barrier.wait();
+            barrier.wait();
+            for camera in cameras.lock().unwrap().values() {
+                assert_eq!(camera.get_incs_being_managed().len(), 2);
+                assert_eq!(camera.get_state(), CameraState::Active);
+            }
+            barrier.wait();
+            for handle in handles {
+                handle.join().unwrap();
+            }
+
+            for camera in cameras.lock().unwrap().values() {
+                assert!(camera.get_incs_being_managed().is_empty());
+                assert_eq!(camera.get_state(), CameraState::SavingMode);
+            }
+        }
+    }
+
+    #[test]
+    fn test_4_un_incidente_que_oscila_en_el_borde_del_rango_no_hace_alternar_a_la_camara() {
+        let (lat, lon) = (-34.6037344, -58.3861838);
+        let camera = Camera::new(1, lat, lon, 5);
+        let range_meters = camera.get_range_meters();
+        let mut cams = HashMap::new();
+        cams.insert(1, camera);
+        let cameras: ShCamerasType = Arc::new(Mutex::new(cams));
+
+        let (cameras_tx, cameras_rx) = mpsc::channel();
+        let (logger_tx, _logger_rx) = mpsc::channel();
+        let mut logic = CamerasLogic::new(cameras.clone(), cameras_tx, StringLogger::new(logger_tx));
+        logic.set_deactivation_margin(15.0);
+        // Incidente a `meters` de la cámara, hacia el norte
+        let inc_at = |meters: f64| Incident::new(1, (lat + meters / 111_320.0, lon), IncidentSource::Manual);
+
+        logic.manage_incident(inc_at(range_meters - 3.0)).unwrap();
+        assert_eq!(cameras.lock().unwrap()[&1].get_state(), CameraState::Active);
+        assert_eq!(cameras_rx.try_iter().count(), 1);
+
+        // Oscila alrededor del rango, sin salir del margen: la cámara sigue activa, y no se vuelve a publicar
+        for meters in [range_meters + 3.0, range_meters - 3.0, range_meters + 10.0, range_meters - 1.0] {
+            logic.manage_incident(inc_at(meters)).unwrap();
+            assert_eq!(cameras.lock().unwrap()[&1].get_state(), CameraState::Active);
+        }
+        assert_eq!(cameras_rx.try_iter().count(), 0);
+
+        // Al salir del rango más el margen, vuelve a ahorro de energía, y para reactivarse debe volver al rango
+        logic.manage_incident(inc_at(range_meters + 30.0)).unwrap();
+        assert_eq!(cameras.lock().unwrap()[&1].get_state(), CameraState::SavingMode);
+        logic.manage_incident(inc_at(range_meters + 3.0)).unwrap();
+        assert_eq!(cameras.lock().unwrap()[&1].get_state(), CameraState::SavingMode);
+        assert_eq!(cameras_rx.try_iter().count(), 1);
+    }
+}
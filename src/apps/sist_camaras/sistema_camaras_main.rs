@@ -1,16 +1,35 @@
 use std::io::Error;
+use std::path::Path;
+use std::sync::mpsc;
+use std::time::Duration;
 
 use rustx::logging::string_logger::StringLogger;
 use rustx::mqtt::mqtt_utils::will_message_utils::will_message::WillMessageData;
 use rustx::mqtt::mqtt_utils::will_message_utils::{app_type::AppType, will_content::WillContent};
 use rustx::{
     apps::{
-        common_clients::{get_app_will_topic, get_broker_address, join_all_threads},
-        sist_camaras::{manage_stored_cameras::create_cameras, sistema_camaras::SistemaCamaras},
+        common_clients::{get_app_will_topic, get_broker_address_or, join_all_threads},
+        config::AppConfig,
+        sist_camaras::{
+            manage_stored_cameras::create_cameras,
+            sistema_camaras::{SistemaCamaras, SIST_CAMARAS_CONFIG_FILES},
+            types::shareable_cameras_type::ShCamerasType,
+        },
+    },
+    mqtt::{
+        client::{
+            mqtt_client::MQTTClient,
+            mqtt_client_retransmitter::{DEFAULT_ACK_WAITING_INTERVAL, DEFAULT_AMOUNT_OF_RETRIES},
+            publisher::NullPublisher,
+            unacked_publishes::DEFAULT_UNACKED_CAPACITY,
+        },
+        messages::publish_message::PublishMessage,
     },
-    mqtt::client::mqtt_client::MQTTClient,
 };
 
+/// Argumento para ejecutar sin conectarse al broker: los publish se descartan, y no se reciben mensajes.
+const DRY_RUN_ARG: &str = "--dry-run";
+
 fn get_formatted_app_id() -> String {
     String::from("Sistema-Camaras")
 }
@@ -20,38 +39,128 @@ fn get_app_will_msg_content() -> WillContent {
 }
 
 fn main() -> Result<(), Error> {
-    let broker_addr = get_broker_address();
     let cameras = create_cameras();
+    let config = AppConfig::from_properties_files_or_default(&SIST_CAMARAS_CONFIG_FILES);
 
     // Se crean y configuran ambos extremos del string logger
-    let (mut logger, handle_logger) = StringLogger::create_logger(get_formatted_app_id());
+    let log_file = config.get_log_file(&get_formatted_app_id());
+    let (mut logger, handle_logger) = StringLogger::create_logger_to_file(get_formatted_app_id(), log_file);
+
+    if std::env::args().any(|arg| arg == DRY_RUN_ARG) {
+        run_dry(cameras, &config, logger.clone_ref());
+    } else {
+        run_connected(cameras, &config, logger.clone_ref());
+    }
+
+    logger.stop_logging();
+
+    // Se espera al hijo para el logger
+    if handle_logger.join().is_err() {
+        println!("Error al esperar al hijo para string logger writer.")
+    }
+    
+    Ok(())
+}
+
+/// Ejecuta Sistema Cámaras sin broker, con un `NullPublisher`.
+fn run_dry(cameras: ShCamerasType, config: &AppConfig, logger: StringLogger) {
+    println!("Ejecutando en modo dry-run, sin conectarse al broker.");
+    logger.log("Ejecutando en modo dry-run".to_string());
+
+    // No se recibirán mensajes, por lo que se cierra el tx (y los hilos que reciben terminan enseguida)
+    let (publish_msg_tx, publish_msg_rx) = mpsc::channel::<PublishMessage>();
+    drop(publish_msg_tx);
+    let mut sistema_camaras = SistemaCamaras::new(cameras, config, logger);
+    let handles = sistema_camaras.spawn_threads(publish_msg_rx, Box::new(NullPublisher::new()));
+    join_all_threads(handles);
+}
+
+/// Ejecuta Sistema Cámaras conectándose al broker indicado por argumentos, o si no se indica, al de la configuración.
+fn run_connected(cameras: ShCamerasType, config: &AppConfig, logger: StringLogger) {
+    let broker_addr = get_broker_address_or(config.get_broker_addr());
 
     let qos = 1; // []
     let client_id = get_formatted_app_id();
     let will_msg_content = get_app_will_msg_content();
     let will_msg_data =
-        WillMessageData::new(will_msg_content.to_str(), get_app_will_topic(), qos, 1);
+        match WillMessageData::for_app_topic(will_msg_content.to_str(), get_app_will_topic(), qos, 1) {
+            Ok(will_msg_data) => will_msg_data,
+            Err(e) => {
+                println!("Error al crear el will message: {:?}", e);
+                return;
+            }
+        };
 
-    match MQTTClient::mqtt_connect_to_broker(client_id, &broker_addr, Some(will_msg_data), logger.clone_ref()) {
-        Ok((mqtt_client, publish_msg_rx, handle)) => {
+    // Los alias de topic se solicitan solamente si se configura `topic-aliases` (ver `AppConfig`)
+    match MQTTClient::mqtt_connect_to_broker_with_retransmission(
+        client_id,
+        &broker_addr,
+        Some(will_msg_data),
+        Duration::from_millis(DEFAULT_ACK_WAITING_INTERVAL),
+        DEFAULT_AMOUNT_OF_RETRIES,
+        0, // Sin keep alive, como con `mqtt_connect_to_broker`
+        config.get_topic_aliases(),
+        logger.clone_ref(),
+    ) {
+        Ok((mut mqtt_client, publish_msg_rx, handle)) => {
             println!("Conectado al broker MQTT.");
             logger.log("Conectado al broker MQTT".to_string());
+            configure_packet_id_state_file(&mut mqtt_client, config, &logger);
+            configure_payload_compression(&mut mqtt_client, config);
+            mqtt_client.set_publish_recording(config.get_record_publishes());
+            configure_unacked_spill(&mut mqtt_client, config, &logger);
 
-            let mut sistema_camaras = SistemaCamaras::new(cameras, logger.clone_ref());
-            let mut handles = sistema_camaras.spawn_threads(publish_msg_rx, mqtt_client);
+            let mut sistema_camaras = SistemaCamaras::new(cameras, config, logger.clone_ref());
+            let mut handles = sistema_camaras.spawn_threads(publish_msg_rx, Box::new(mqtt_client));
 
             handles.push(handle);
             join_all_threads(handles);
         }
         Err(e) => println!("Error al conectar al broker MQTT: {:?}", e),
     }
+}
 
-    logger.stop_logging();
+/// Si se configura `packet-id-file`, persiste allí el último packet id usado, para que al volver a ejecutarse
+/// con la sesión conservada por el server los packet ids continúen en lugar de reiniciarse.
+fn configure_packet_id_state_file(mqtt_client: &mut MQTTClient, config: &AppConfig, logger: &StringLogger) {
+    if let Some(state_file) = config.get("packet-id-file") {
+        if let Err(e) = mqtt_client.set_packet_id_state_file(Path::new(state_file)) {
+            logger.log(format!("Error al configurar la persistencia del packet id: {:?}", e));
+        }
+    }
+}
 
-    // Se espera al hijo para el logger
-    if handle_logger.join().is_err() {
-        println!("Error al esperar al hijo para string logger writer.")
+/// Si se configura `payload-compression-threshold-bytes`, publica comprimidos los payloads (ej. frames) que superen
+/// esa cantidad de bytes. Quienes los reciben deben descomprimirlos, por lo que si no se configura no se comprime.
+fn configure_payload_compression(mqtt_client: &mut MQTTClient, config: &AppConfig) {
+    match config
+        .get("payload-compression-threshold-bytes")
+        .map(|threshold| threshold.parse::<usize>())
+    {
+        Some(Ok(threshold)) => mqtt_client.set_payload_compression_threshold(Some(threshold)),
+        Some(Err(_)) => println!("Propiedad 'payload-compression-threshold-bytes' inválida, no se comprime."),
+        None => {}
+    }
+}
+
+/// Si se configura `unacked-publishes-dir`, persiste allí los publish de qos 1 sin ack (como máximo
+/// `unacked-publishes-capacity`), y vuelve a publicar los que hayan quedado de una ejecución anterior.
+fn configure_unacked_spill(mqtt_client: &mut MQTTClient, config: &AppConfig, logger: &StringLogger) {
+    let spill_dir = match config.get("unacked-publishes-dir") {
+        Some(spill_dir) => spill_dir,
+        None => return,
+    };
+    let capacity = config
+        .get("unacked-publishes-capacity")
+        .and_then(|capacity| capacity.parse::<usize>().ok())
+        .unwrap_or(DEFAULT_UNACKED_CAPACITY);
+
+    if let Err(e) = mqtt_client.set_unacked_spill_dir(Path::new(spill_dir), capacity) {
+        logger.log(format!("Error al configurar la persistencia de publish sin ack: {:?}", e));
+        return;
+    }
+    match mqtt_client.replay_unacked_publishes() {
+        Ok(replayed) => logger.log(format!("Publish sin ack de la ejecución anterior publicados: {}", replayed)),
+        Err(e) => logger.log(format!("Error al volver a publicar publish sin ack: {:?}", e)),
     }
-    
-    Ok(())
 }
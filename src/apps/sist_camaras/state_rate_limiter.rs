@@ -0,0 +1,168 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::apps::properties::Properties;
+
+use super::{camera_state::CameraState, types::shareable_cameras_type::ShCamerasType};
+
+/// Archivo de configuración de Sistema Cámaras, del que se leen los intervalos por estado.
+pub const SIST_CAMARAS_PROPERTIES_FILE: &str = "./sistema_camaras.properties";
+
+/// Intervalo en modo ahorro, si no se lo configura.
+const DEFAULT_SAVING_MODE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Intervalo mínimo entre dos frames procesados de una misma cámara, según el estado de la cámara.
+/// En `Active` se procesan a la frecuencia completa, y en `SavingMode` (o `Failed`) con menor frecuencia.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StateIntervals {
+    active: Duration,
+    saving_mode: Duration,
+}
+
+impl StateIntervals {
+    pub fn new(active: Duration, saving_mode: Duration) -> Self {
+        Self {
+            active,
+            saving_mode,
+        }
+    }
+
+    /// Intervalos para la publicación de frames, de las propiedades `active-frame-interval-ms`
+    /// y `saving-mode-frame-interval-ms`.
+    pub fn frame_intervals_from_properties_file(properties_file: &str) -> Self {
+        Self::from_properties_file(
+            properties_file,
+            "active-frame-interval-ms",
+            "saving-mode-frame-interval-ms",
+        )
+    }
+
+    /// Intervalos para la detección de incidentes, de las propiedades `active-detection-interval-ms`
+    /// y `saving-mode-detection-interval-ms`.
+    pub fn detection_intervals_from_properties_file(properties_file: &str) -> Self {
+        Self::from_properties_file(
+            properties_file,
+            "active-detection-interval-ms",
+            "saving-mode-detection-interval-ms",
+        )
+    }
+
+    /// Lee los intervalos en milisegundos de las propiedades indicadas. Ante una propiedad ausente o inválida
+    /// se usa el valor por defecto: sin intervalo en `Active`, y `DEFAULT_SAVING_MODE_INTERVAL` en modo ahorro.
+    fn from_properties_file(properties_file: &str, active_key: &str, saving_mode_key: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => Self::new(
+                read_interval(&properties, active_key, Duration::ZERO),
+                read_interval(&properties, saving_mode_key, DEFAULT_SAVING_MODE_INTERVAL),
+            ),
+            Err(e) => {
+                println!("Error al leer los intervalos de las cámaras: {:?}", e);
+                Self::new(Duration::ZERO, DEFAULT_SAVING_MODE_INTERVAL)
+            }
+        }
+    }
+
+    /// Devuelve el intervalo correspondiente al `state`.
+    pub fn get_interval_for(&self, state: CameraState) -> Duration {
+        match state {
+            CameraState::Active => self.active,
+            CameraState::SavingMode | CameraState::Failed => self.saving_mode,
+        }
+    }
+}
+
+/// Lee la propiedad `key` como milisegundos, o devuelve `default` si está ausente o es inválida.
+fn read_interval(properties: &Properties, key: &str, default: Duration) -> Duration {
+    match properties.get(key).map(|ms| ms.parse::<u64>()) {
+        Some(Ok(ms)) => Duration::from_millis(ms),
+        Some(Err(_)) => {
+            println!("Propiedad '{}' inválida, se usa el valor por defecto.", key);
+            default
+        }
+        None => default,
+    }
+}
+
+/// Decide, por cámara, si procesar (publicar o analizar) un frame, según el intervalo del estado actual de la cámara.
+/// Se compara contra el último frame procesado, y no contra un próximo instante ya agendado: así, cuando una
+/// cámara pasa a `Active`, su siguiente frame se procesa sin esperar a que termine el intervalo del modo ahorro.
+#[derive(Debug)]
+pub struct CameraRateLimiter {
+    intervals: StateIntervals,
+    last_processed: HashMap<u8, Instant>,
+}
+
+impl CameraRateLimiter {
+    pub fn new(intervals: StateIntervals) -> Self {
+        Self {
+            intervals,
+            last_processed: HashMap::new(),
+        }
+    }
+
+    /// Devuelve si corresponde procesar el frame de la cámara `cam_id`, que se encuentra en estado `state`.
+    /// De ser así, lo registra como el último frame procesado de la cámara.
+    pub fn should_process(&mut self, cam_id: u8, state: CameraState, now: Instant) -> bool {
+        let interval = self.intervals.get_interval_for(state);
+        let should_process = match self.last_processed.get(&cam_id) {
+            Some(last) => now.saturating_duration_since(*last) >= interval,
+            None => true,
+        };
+        if should_process {
+            self.last_processed.insert(cam_id, now);
+        }
+        should_process
+    }
+
+    /// Análoga a `should_process`, con el estado actual de la cámara `cam_id` en `cameras`.
+    /// Los frames de cámaras desconocidas se procesan siempre.
+    pub fn should_process_frame_of(&mut self, cameras: &ShCamerasType, cam_id: u8, now: Instant) -> bool {
+        let state = match cameras.lock() {
+            Ok(cams) => cams.get(&cam_id).map(|camera| camera.get_state()),
+            Err(_) => None,
+        };
+        match state {
+            Some(state) => self.should_process(cam_id, state, now),
+            None => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{CameraRateLimiter, StateIntervals};
+    use crate::apps::sist_camaras::camera_state::CameraState;
+
+    #[test]
+    fn test_1_en_modo_ahorro_se_procesan_menos_frames_que_en_activo() {
+        let mut limiter = CameraRateLimiter::new(StateIntervals::new(
+            Duration::from_secs(1),
+            Duration::from_secs(10),
+        ));
+        let start = Instant::now();
+
+        assert!(limiter.should_process(1, CameraState::SavingMode, start));
+        assert!(!limiter.should_process(1, CameraState::SavingMode, start + Duration::from_secs(5)));
+        assert!(limiter.should_process(1, CameraState::SavingMode, start + Duration::from_secs(10)));
+        // Cada cámara lleva su propio intervalo
+        assert!(limiter.should_process(2, CameraState::SavingMode, start + Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_2_al_pasar_a_activo_el_siguiente_frame_se_procesa_sin_esperar_el_intervalo_de_ahorro() {
+        let mut limiter = CameraRateLimiter::new(StateIntervals::new(
+            Duration::from_secs(1),
+            Duration::from_secs(60),
+        ));
+        let start = Instant::now();
+
+        assert!(limiter.should_process(1, CameraState::SavingMode, start));
+        assert!(limiter.should_process(1, CameraState::Active, start + Duration::from_secs(2)));
+        assert!(!limiter.should_process(1, CameraState::Active, start + Duration::from_millis(2500)));
+        assert!(limiter.should_process(1, CameraState::Active, start + Duration::from_secs(3)));
+    }
+}
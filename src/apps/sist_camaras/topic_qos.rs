@@ -0,0 +1,115 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+};
+
+use crate::apps::properties::Properties;
+
+/// Prefijo de las propiedades que indican el qos de un topic en particular (ej. `qos.inc=1`).
+const TOPIC_QOS_PREFIX: &str = "qos.";
+
+/// Máximo qos soportado por el cliente MQTT.
+const MAX_QOS: u8 = 1;
+
+/// Qos con el que Sistema Cámaras publica y se suscribe a cada topic: el indicado por `qos.<topic>`,
+/// o si no se lo indica, el de la propiedad `qos`. Así, por ejemplo, los incidentes pueden publicarse con qos 1
+/// y las actualizaciones de cámaras, más frecuentes, con qos 0.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TopicQos {
+    default_qos: u8,
+    qos_by_topic: HashMap<String, u8>,
+}
+
+impl TopicQos {
+    pub fn new(default_qos: u8) -> Self {
+        Self {
+            default_qos,
+            qos_by_topic: HashMap::new(),
+        }
+    }
+
+    /// Lee los qos del archivo. La propiedad `qos` es obligatoria; los topics se comparan sin distinguir mayúsculas.
+    pub fn from_properties_file(properties_file: &str) -> Result<Self, Error> {
+        let properties = Properties::new(properties_file)?;
+
+        let default_qos = match properties.get("qos") {
+            Some(prop) => Self::parse_qos(prop)?,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::NotFound,
+                    "No se encontró la etiqueta 'qos='",
+                ))
+            }
+        };
+        Self::from_entries(default_qos, properties.iter())
+    }
+
+    /// Crea los qos con `default_qos` por defecto, y el de cada topic indicado por las propiedades `qos.<topic>`
+    /// de `entries`. Las demás propiedades se ignoran.
+    pub fn from_entries<'a>(
+        default_qos: u8,
+        entries: impl Iterator<Item = (&'a String, &'a String)>,
+    ) -> Result<Self, Error> {
+        let mut topic_qos = Self::new(default_qos);
+        for (key, value) in entries {
+            if let Some(topic) = key.strip_prefix(TOPIC_QOS_PREFIX) {
+                topic_qos
+                    .qos_by_topic
+                    .insert(topic.to_lowercase(), Self::parse_qos(value)?);
+            }
+        }
+        Ok(topic_qos)
+    }
+
+    /// Devuelve el qos a utilizar para el `topic`.
+    pub fn get_qos_for(&self, topic: &str) -> u8 {
+        self.qos_by_topic
+            .get(&topic.to_lowercase())
+            .copied()
+            .unwrap_or(self.default_qos)
+    }
+
+    /// Parsea un qos, que debe ser soportado por el cliente MQTT.
+    pub fn parse_qos(value: &str) -> Result<u8, Error> {
+        match value.parse::<u8>() {
+            Ok(qos) if qos <= MAX_QOS => Ok(qos),
+            _ => Err(Error::new(
+                ErrorKind::InvalidData,
+                "El valor de QoS no es un número válido",
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::TopicQos;
+
+    fn topic_qos_from(test_name: &str, content: &str) -> Result<TopicQos, std::io::Error> {
+        let file = std::env::temp_dir().join(format!("test_topic_qos_{}.properties", test_name));
+        fs::write(&file, content).unwrap();
+        let res = TopicQos::from_properties_file(file.to_str().unwrap());
+        let _ = fs::remove_file(&file);
+        res
+    }
+
+    #[test]
+    fn test_1_cada_topic_usa_su_qos_o_el_qos_por_defecto() {
+        let topic_qos = topic_qos_from("1", "qos=1\nqos.Inc=1\nqos.Cam=0").unwrap();
+
+        assert_eq!(topic_qos.get_qos_for("inc"), 1);
+        assert_eq!(topic_qos.get_qos_for("cam"), 0);
+        assert_eq!(topic_qos.get_qos_for("cam_frame"), 1);
+    }
+
+    #[test]
+    fn test_2_un_qos_invalido_o_la_falta_del_qos_por_defecto_dan_error() {
+        assert!(topic_qos_from("2a", "qos=1\nqos.cam=7").is_err());
+        assert!(topic_qos_from("2b", "qos=uno").is_err());
+        assert!(topic_qos_from("2c", "qos.cam=0").is_err());
+        // Solamente el qos por defecto, como hasta ahora
+        assert_eq!(topic_qos_from("2d", "qos=0").unwrap(), TopicQos::new(0));
+    }
+}
@@ -1,3 +1,4 @@
 pub mod channels_type;
 pub mod hashmap_incs_type;
-pub mod shareable_cameras_type;
\ No newline at end of file
+pub mod shareable_cameras_type;
+pub mod shareable_publisher_type;
\ No newline at end of file
@@ -0,0 +1,5 @@
+use std::sync::{Arc, Mutex};
+
+use crate::mqtt::client::publisher::Publisher;
+
+pub type ShPublisherType = Arc<Mutex<Box<dyn Publisher>>>;
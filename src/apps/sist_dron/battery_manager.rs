@@ -49,18 +49,30 @@ impl BatteryManager {
                     DronState::ExpectingToRecvIncident,
                 )
             };
+            // Si aceptó un incidente pero aún no llegó, lo abandona: al publicarse sin incidente a resolver,
+            // monitoreo deja de contarlo en camino a éste.
+            if state_to_set != DronState::ManagingIncident {
+                self.current_data.unset_inc_id_to_resolve()?;
+            }
+
             // Vuela a mantenimiento
-            self.current_data.set_state(DronState::Mantainance, true)?;
+            self.current_data.set_state(DronState::GoingToMaintenance, true)?;
+            self.publish_current_info()?;
             let maintanence_position = self.dron_properties.get_mantainance_position();
             self.fly_to_mantainance(maintanence_position, true)?;
 
+            // Se recarga, mostrándose en monitoreo como cargando
+            self.current_data.set_state(DronState::Charging, true)?;
+            self.publish_current_info()?;
             sleep(Duration::from_secs(3));
             self.recharge_battery()?;
             self.logger.log("Recargando batería al 100%.".to_string());
 
             // Vuelve a la posición correspondiente
+            self.current_data.set_state(DronState::Mantainance, true)?;
             self.fly_to_mantainance(position_to_go, true)?;
             self.current_data.set_state(state_to_set, true)?;
+            self.publish_current_info()?;
             if let Err(e) = self.process_inc_tx.send(()) {
                 self.logger.log(format!("Error al enviar señal desde mantenimiento: {:?}.", e));
             }
@@ -112,9 +124,6 @@ impl BatteryManager {
             self.current_data.get_current_position()
         ));
 
-        // Llegue a destino entonces debo cambiar a estado --> Manejando Incidente
-        self.current_data.set_state(DronState::ManagingIncident, true)?;
-
         // Publica
         self.publish_current_info()?;
 
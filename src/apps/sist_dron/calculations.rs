@@ -1,26 +1,49 @@
 
+use crate::apps::vendor::Position;
+
 // Funciones que realizan cálculos matemáticos.
 
 pub fn calculate_distance(a: (f64, f64), b: (f64, f64)) -> f64 {
     ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt()
 }
 
-/// Calcula la dirección en la que debe volar desde una posición `origin` hasta `destination`.
-// Aux: esto estaría mejor en un struct posicion quizás? [] ver.
+/// Devuelve si la posición `current` está a no más de `arrival_radius_m` metros de `destination`, ie si se
+/// considera que el dron llegó a destino.
+pub fn has_arrived_at(current: (f64, f64), destination: (f64, f64), arrival_radius_m: f64) -> bool {
+    let current = Position::from_lat_lon(current.0, current.1);
+    current.distance_meters(&Position::from_lat_lon(destination.0, destination.1)) <= arrival_radius_m
+}
+
+/// Calcula la dirección en la que debe volar desde una posición `origin` hasta `destination`, como vector
+/// unitario de (lat, lon) en grados que apunta según el rumbo inicial hacia `destination`.
 pub fn calculate_direction(origin: (f64, f64), destination: (f64, f64)) -> (f64, f64) {
-    // calcular la distancia ('en diagonal') entre los dos puntos
-    let (origin_lat, origin_lon) = (origin.0, origin.1);
-    let (dest_lat, dest_lon) = (destination.0, destination.1);
-
-    // Cálculo de distancia
-    let lat_dist = dest_lat - origin_lat;
-    let lon_dist = dest_lon - origin_lon;
-    let distance = f64::sqrt(lat_dist.powi(2) + lon_dist.powi(2));
-
-    // Vector unitario: (destino - origen) / || distancia ||, para cada coordenada.
-    let unit_lat = lat_dist / distance;
-    let unit_lon = lon_dist / distance;
-    let direction: (f64, f64) = (unit_lat, unit_lon);
-
-    direction
-}
\ No newline at end of file
+    let origin_pos = Position::from_lat_lon(origin.0, origin.1);
+    let bearing = origin_pos
+        .bearing_to(&Position::from_lat_lon(destination.0, destination.1))
+        .to_radians();
+
+    // Un grado de longitud mide menos metros cuanto más lejos del ecuador, por lo que para avanzar
+    // según el rumbo se debe escalar el desplazamiento en longitud.
+    let lat_component = bearing.cos();
+    let lon_component = bearing.sin() / origin.0.to_radians().cos();
+
+    // Vector unitario, para cada coordenada.
+    let norm = f64::sqrt(lat_component.powi(2) + lon_component.powi(2));
+    (lat_component / norm, lon_component / norm)
+}
+
+#[cfg(test)]
+mod test {
+    use super::has_arrived_at;
+
+    #[test]
+    fn test_1_dentro_del_radio_de_llegada_llego_y_justo_afuera_no() {
+        let destination = (-34.6037, -58.3816);
+        let radius_m = 100.0;
+
+        assert!(has_arrived_at(destination, destination, radius_m));
+        // Un grado de latitud son unos 111 km: 0.0008 grados son unos 89 metros, y 0.001 unos 111
+        assert!(has_arrived_at((-34.6037 + 0.0008, -58.3816), destination, radius_m));
+        assert!(!has_arrived_at((-34.6037 + 0.001, -58.3816), destination, radius_m));
+    }
+}
@@ -36,17 +36,17 @@ impl Data {
             "Error al tomar lock de current info.",
         ))
     }
-    /// Toma lock y establece el estado en que se encuentra el dron.
+    /// Toma lock y establece el estado en que se encuentra el dron, si su ciclo de vida lo permite
+    /// (ver `DronCurrentInfo::try_set_state`).
     /// El flag de mantenimiento indica si quien lo llama es o no el módulo de mantenimiento,
     /// y se utiliza para otorgar permisos.
     pub fn set_state(&self, new_state: DronState, flag_maintanance: bool) -> Result<(), Error> {
         if let Ok(mut ci) = self.current_info.lock() {
             let is_mantainance_set = flag_maintanance;
             let is_not_maintainance_set =
-                ci.get_state() != DronState::Mantainance && !flag_maintanance;
+                !ci.get_state().is_in_maintenance() && !flag_maintanance;
             if is_mantainance_set || is_not_maintainance_set {
-                ci.set_state(new_state);
-                return Ok(());
+                return ci.try_set_state(new_state);
             } else {
                 return Err(Error::new(
                     ErrorKind::InvalidData,
@@ -71,7 +71,7 @@ impl Data {
     ) -> Result<(), Error> {
         let is_mantainance_set = flag_maintanance;
         let is_not_maintainance_set =
-            self.get_state()? != DronState::Mantainance && !flag_maintanance;
+            !self.get_state()?.is_in_maintenance() && !flag_maintanance;
         if is_mantainance_set || is_not_maintainance_set {
             let info = DronFlyingInfo::new(dir, speed);
             self.set_flying_info(info)?;
@@ -177,6 +177,31 @@ impl Data {
             "Error al tomar lock de current info.",
         ))
     }
+    /// Toma lock y encola el incidente; devuelve si se lo encoló.
+    pub fn enqueue_incident(&self, inc_info: IncidentInfo) -> Result<bool, Error> {
+        if let Ok(mut ci) = self.current_info.lock() {
+            return Ok(ci.enqueue_incident(inc_info));
+        }
+        Err(Error::other("Error al tomar lock de current info."))
+    }
+    /// Toma lock, finaliza el incidente a resolver y pasa al incidente en espera más cercano, que devuelve.
+    pub fn dequeue_nearest_incident<F>(&self, position_of: F) -> Result<Option<IncidentInfo>, Error>
+    where
+        F: Fn(&IncidentInfo) -> Option<(f64, f64)>,
+    {
+        if let Ok(mut ci) = self.current_info.lock() {
+            return Ok(ci.dequeue_nearest_incident(position_of));
+        }
+        Err(Error::other("Error al tomar lock de current info."))
+    }
+    /// Toma lock y quita el incidente de la cola, ya sea el que está resolviendo o uno en espera.
+    pub fn remove_incident_from_queue(&self, inc_info: &IncidentInfo) -> Result<(), Error> {
+        if let Ok(mut ci) = self.current_info.lock() {
+            ci.remove_incident_from_queue(inc_info);
+            return Ok(());
+        }
+        Err(Error::other("Error al tomar lock de current info."))
+    }
 
     /// Toma lock y obtiene la `current_position`, posición en la que el dron se encuentra actualmente.
     pub fn get_current_position(&self) -> Result<(f64, f64), Error> {
@@ -199,9 +224,9 @@ impl Data {
         if let Ok(mut ci) = self.current_info.lock() {
             let is_mantainance_set = flag_maintanance;
             let is_not_maintainance_set =
-                ci.get_state() != DronState::Mantainance && !flag_maintanance;
+                !ci.get_state().is_in_maintenance() && !flag_maintanance;
             if is_mantainance_set || is_not_maintainance_set {
-                Ok(ci.increment_current_position_in(dir))
+                ci.increment_current_position_in(dir)
             } else {
                 Err(Error::new(
                     ErrorKind::InvalidData,
@@ -6,13 +6,17 @@ use std::sync::mpsc::Receiver as MpscReceiver;
 
 use crate::apps::{
     apps_mqtt_topics::AppsMqttTopics, common_clients::join_all_threads,
+    heartbeat::{spawn_heartbeat_thread, DEFAULT_HEARTBEAT_INTERVAL},
     sist_dron::dron_state::DronState,
 };
 use crate::apps::{
     common_clients::there_are_no_more_publish_msgs, incident_data::incident_info::IncidentInfo,
 };
 use crate::logging::string_logger::StringLogger;
-use crate::mqtt::{client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage};
+use crate::mqtt::{
+    client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage,
+    mqtt_utils::will_message_utils::app_type::AppType,
+};
 
 use super::{
     battery_manager::BatteryManager, data::Data, dron_current_info::DronCurrentInfo,
@@ -63,11 +67,23 @@ impl Dron {
         mqtt_rx: MpscReceiver<PublishMessage>,
     ) -> Result<Vec<JoinHandle<()>>, Error> {
         let mut children: Vec<JoinHandle<()>> = vec![];
+        let shutdown_token = mqtt_client.get_shutdown_token();
         let mqtt_client_sh = Arc::new(Mutex::new(mqtt_client));
         // Publica su posición inicial
         let ci = self.get_current_info()?;
+        let id = ci.get_id();
         self.publish_current_info(ci, &mqtt_client_sh.clone())?;
 
+        // Heartbeat, para que sistema monitoreo detecte si el dron deja de responder
+        children.push(spawn_heartbeat_thread(
+            mqtt_client_sh.clone(),
+            AppType::Dron,
+            Some(id),
+            DEFAULT_HEARTBEAT_INTERVAL,
+            shutdown_token,
+            self.logger.clone_ref(),
+        ));
+
         // Lanza hilos
         let (process_inc_tx, process_inc_rx) = mpsc::channel::<()>();
         let (ci_tx, ci_rx) = mpsc::channel::<DronCurrentInfo>();
@@ -300,6 +316,7 @@ mod test {
     use super::Dron;
     use crate::apps::sist_dron::calculations::calculate_direction;
     use crate::apps::sist_dron::dron_state::DronState;
+    use crate::apps::vendor::Position;
     use crate::logging::string_logger::StringLogger;
     use std::sync::mpsc;
 
@@ -338,19 +355,20 @@ mod test {
 
     #[test]
     fn test_3a_calculate_direction_da_la_direccion_esperada() {
-        // Dados destino y origen
+        // Dados destino y origen, cerca del ecuador
         let origin = (0.0, 0.0); // desde el (0,0)
-        let destination = (4.0, -3.0);
-        let hip = 5.0; // hipotenusa da 5;
+        let destination = (0.004, -0.003);
+        let hip = 0.005; // hipotenusa da 0.005;
 
         let dir = calculate_direction(origin, destination);
 
-        // La dirección calculada es la esperada
-        let expected_dir = (4.0 / hip, -3.0 / hip);
-        assert_eq!(dir, expected_dir);
+        // En el ecuador, y a corta distancia, la dirección es la del plano
+        let expected_dir = (0.004 / hip, -0.003 / hip);
+        assert!((dir.0 - expected_dir.0).abs() < 1e-6);
+        assert!((dir.1 - expected_dir.1).abs() < 1e-6);
         // En "hip" cantidad de pasos, se llega a la posición de destino
-        assert_eq!(origin.0 + dir.0 * hip, destination.0);
-        assert_eq!(origin.1 + dir.1 * hip, destination.1);
+        assert!((origin.0 + dir.0 * hip - destination.0).abs() < 1e-8);
+        assert!((origin.1 + dir.1 * hip - destination.1).abs() < 1e-8);
     }
 
     #[test]
@@ -359,16 +377,17 @@ mod test {
 
         // Dados destino y origen
         let origin = dron.data.get_current_position().unwrap(); // desde (incident_position, candidate_dron) que no es el (0,0)
-        let destination = (origin.0 + 4.0, origin.1 - 3.0);
-        let hip = 5.0; // hipotenusa da 5;
+        let destination = (origin.0 + 0.004, origin.1 - 0.003);
 
         let dir = calculate_direction(origin, destination);
 
-        // La dirección calculada es la esperada
-        let expected_dir = (4.0 / hip, -3.0 / hip);
-        assert_eq!(dir, expected_dir);
-        // En "hip" cantidad de pasos, se llega a la posición de destino
-        assert_eq!(origin.0 + dir.0 * hip, destination.0);
-        assert_eq!(origin.1 + dir.1 * hip, destination.1);
+        // La dirección calculada es un vector unitario
+        assert!((dir.0.powi(2) + dir.1.powi(2) - 1.0).abs() < 1e-9);
+        // Lejos del ecuador se corrige la longitud: un paso en la dirección calculada
+        // sigue el mismo rumbo que lleva hasta la posición de destino
+        let origin_pos = Position::from_lat_lon(origin.0, origin.1);
+        let step = Position::from_lat_lon(origin.0 + dir.0 / 10000.0, origin.1 + dir.1 / 10000.0);
+        let expected_bearing = origin_pos.bearing_to(&Position::from_lat_lon(destination.0, destination.1));
+        assert!((origin_pos.bearing_to(&step) - expected_bearing).abs() < 0.01);
     }
 }
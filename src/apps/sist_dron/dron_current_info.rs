@@ -1,12 +1,28 @@
 use std::io::{Error, ErrorKind};
 
+use crate::apps::coordinates::check_finite_coordinates;
+use crate::apps::incident_data::incident::cmp_incidents_by_distance;
 use crate::apps::incident_data::incident_info::IncidentInfo;
+use crate::apps::vendor::Position;
+use crate::apps::wire_version::split_version;
 
 use super::dron_flying_info::DronFlyingInfo;
 use super::dron_state::DronState;
 
+use serde::Serialize;
+
+/// Cantidad máxima de incidentes que el dron puede tener en espera, además del que está resolviendo.
+pub const MAX_PENDING_INCIDENTS: usize = 4;
+// Bytes de la flying_info: dirección (lat, lon) y velocidad, tres f64.
+const FLYING_INFO_BYTES: usize = 24;
+// Bytes de id, posición, batería, estado, incidente a resolver, y flag de flying_info.
+const MIN_BYTES: usize = 1 + 8 + 8 + 1 + 1 + 2 + 1;
+/// Versión del formato de los bytes del dron (ver `split_version`): la 1 termina en la flying_info,
+/// y la 2 agrega los incidentes en espera.
+const DRON_CURRENT_INFO_WIRE_VERSION: u8 = 2;
+
 /// Struct que contiene los campos que identifican al Dron (el id) y que pueden modificarse durante su funcionamiento.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct DronCurrentInfo {
     id: u8,
     // Posición actual
@@ -17,6 +33,8 @@ pub struct DronCurrentInfo {
     inc_info_to_resolve: Option<IncidentInfo>,
     // Dirección y velocidad de vuelo
     flying_info: Option<DronFlyingInfo>,
+    // Incidentes a resolver luego del actual (que es la cabeza de la cola, `inc_info_to_resolve`)
+    pending_incs: Vec<IncidentInfo>,
 }
 
 impl DronCurrentInfo {
@@ -31,12 +49,13 @@ impl DronCurrentInfo {
             state,
             inc_info_to_resolve: None,
             flying_info: None,
+            pending_incs: vec![],
         }
     }
 
     /// Pasa un struct `DronCurrentInfo` a bytes.
     pub fn to_bytes(&self) -> Vec<u8> {
-        let mut bytes = vec![];
+        let mut bytes = vec![DRON_CURRENT_INFO_WIRE_VERSION];
         bytes.extend_from_slice(&self.id.to_be_bytes());
         bytes.extend_from_slice(&self.latitude.to_be_bytes());
         bytes.extend_from_slice(&self.longitude.to_be_bytes());
@@ -60,11 +79,31 @@ impl DronCurrentInfo {
         } else {
             bytes.extend_from_slice(&0_u8.to_be_bytes()); // avisa que No se enviará más bytes
         }
+
+        // Los incidentes en espera, precedidos por su cantidad (desde la versión 2).
+        bytes.push(self.pending_incs.len() as u8);
+        for inc_info in &self.pending_incs {
+            bytes.extend_from_slice(&inc_info.to_bytes());
+        }
         bytes
     }
 
-    /// Obtiene un struct `DronCurrentInfo` a partir de bytes.
+    /// Obtiene un struct `DronCurrentInfo` a partir de bytes de cualquier versión: los campos posteriores a los
+    /// que se conocen se ignoran, y los que no trae una versión anterior toman su valor por defecto.
     pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, Error> {
+        let (version, bytes) = split_version(&bytes, "DronCurrentInfo")?;
+        // Hasta el flag de flying_info inclusive, y la flying_info si el flag indica que se envió
+        let has_enough_bytes = match bytes.get(MIN_BYTES - 1) {
+            Some(1) => bytes.len() >= MIN_BYTES + FLYING_INFO_BYTES,
+            Some(_) => true,
+            None => false,
+        };
+        if !has_enough_bytes {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "No hay suficientes bytes para un DronCurrentInfo",
+            ));
+        }
         let mut idx = 0;
         let b_size: usize = 1;
 
@@ -94,6 +133,7 @@ impl DronCurrentInfo {
             bytes[idx + 7 * b_size],
         ]);
         idx += 8 * b_size;
+        check_finite_coordinates(latitude, longitude)?;
 
         let battery_lvl = u8::from_be_bytes([bytes[idx]]);
         idx += b_size;
@@ -121,9 +161,15 @@ impl DronCurrentInfo {
 
         if is_there_flying_info == 1 {
             flying_info = Some(DronFlyingInfo::from_bytes(bytes[idx..].to_vec())?);
+            idx += FLYING_INFO_BYTES;
         }
 
-        //idx += b_size; // comentado porque warning is never read. quizás en el futuro agregamos más campos.
+        // Leo los incidentes en espera. La versión 1 termina acá, y la cola queda formada
+        // solamente por el incidente a resolver.
+        let pending_incs = match version {
+            1 => vec![],
+            _ => Self::pending_incs_from_bytes(bytes, idx)?,
+        };
 
         match state_res {
             Ok(state) => Ok(DronCurrentInfo {
@@ -134,6 +180,7 @@ impl DronCurrentInfo {
                 state,
                 inc_info_to_resolve,
                 flying_info,
+                pending_incs,
             }),
             Err(_) => Err(Error::new(
                 ErrorKind::InvalidInput,
@@ -142,6 +189,29 @@ impl DronCurrentInfo {
         }
     }
 
+    /// Lee los incidentes en espera a partir de `idx`: la cantidad, y luego dos bytes por incidente.
+    /// Si no hay más bytes, no hay incidentes en espera.
+    fn pending_incs_from_bytes(bytes: &[u8], idx: usize) -> Result<Vec<IncidentInfo>, Error> {
+        let mut pending_incs = vec![];
+        let amount = match bytes.get(idx) {
+            Some(amount) => *amount as usize,
+            None => return Ok(pending_incs),
+        };
+        let start = idx + 1;
+        if bytes.len() < start + 2 * amount {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Error al leer los incidentes en espera del dron",
+            ));
+        }
+        for inc_bytes in bytes[start..start + 2 * amount].chunks(2) {
+            if let Some(inc_info) = IncidentInfo::from_bytes(inc_bytes.to_vec())? {
+                pending_incs.push(inc_info);
+            }
+        }
+        Ok(pending_incs)
+    }
+
     // Getters
     /// Devuelve el id.
     pub fn get_id(&self) -> u8 {
@@ -165,6 +235,21 @@ impl DronCurrentInfo {
         self.state = new_state;
     }
 
+    /// Setea el estado del dron, o devuelve error si su ciclo de vida no permite pasar del estado actual a `new_state`.
+    pub fn try_set_state(&mut self, new_state: DronState) -> Result<(), Error> {
+        if !self.state.can_transition_to(new_state) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Transición de dron no válida: de {} a {}",
+                    self.state, new_state
+                ),
+            ));
+        }
+        self.state = new_state;
+        Ok(())
+    }
+
     /// Devuelve el id del incidente que el dron se encuentra actualmente resolviendo.
     pub fn get_inc_id_to_resolve(&self) -> Option<IncidentInfo> {
         self.inc_info_to_resolve
@@ -179,6 +264,62 @@ impl DronCurrentInfo {
         self.inc_info_to_resolve = None;
     }
 
+    /// Devuelve la cola de incidentes del dron: primero el que está resolviendo, y luego los que están en espera.
+    pub fn get_incident_queue(&self) -> Vec<IncidentInfo> {
+        let mut queue: Vec<IncidentInfo> = self.inc_info_to_resolve.into_iter().collect();
+        queue.extend_from_slice(&self.pending_incs);
+        queue
+    }
+
+    /// Encola el incidente: si el dron no está resolviendo ninguno pasa a ser el incidente a resolver,
+    /// y si no queda en espera. Devuelve false si ya estaba en la cola, o si la espera está llena.
+    pub fn enqueue_incident(&mut self, inc_info: IncidentInfo) -> bool {
+        if self.get_incident_queue().contains(&inc_info) {
+            return false;
+        }
+        if self.inc_info_to_resolve.is_none() {
+            self.inc_info_to_resolve = Some(inc_info);
+            return true;
+        }
+        if self.pending_incs.len() >= MAX_PENDING_INCIDENTS {
+            return false;
+        }
+        self.pending_incs.push(inc_info);
+        true
+    }
+
+    /// Quita el incidente de la cola, ya sea el que está resolviendo o uno en espera.
+    /// Si era el que estaba resolviendo, la cabeza queda vacía (ver `dequeue_nearest_incident`).
+    pub fn remove_incident_from_queue(&mut self, inc_info: &IncidentInfo) {
+        if self.inc_info_to_resolve.as_ref() == Some(inc_info) {
+            self.inc_info_to_resolve = None;
+        }
+        self.pending_incs.retain(|pending| pending != inc_info);
+    }
+
+    /// Finaliza el incidente que se estaba resolviendo, y pasa a resolver el incidente en espera más cercano
+    /// a la posición actual, que devuelve. `position_of` obtiene la posición de cada incidente; los que no
+    /// tienen posición conocida se toman al final. Ante igual distancia se desempata según
+    /// `cmp_incidents_by_distance`, para que la elección no dependa del orden de la cola.
+    pub fn dequeue_nearest_incident<F>(&mut self, position_of: F) -> Option<IncidentInfo>
+    where
+        F: Fn(&IncidentInfo) -> Option<(f64, f64)>,
+    {
+        let distance_to = |inc_info: &IncidentInfo| {
+            position_of(inc_info).map_or(f64::INFINITY, |position| self.get_distance_to(position))
+        };
+        let nearest_idx = self
+            .pending_incs
+            .iter()
+            .enumerate()
+            .map(|(idx, inc_info)| (distance_to(inc_info), inc_info.get_inc_id(), idx))
+            .min_by(cmp_incidents_by_distance)
+            .map(|(_, _, idx)| idx);
+
+        self.inc_info_to_resolve = nearest_idx.map(|idx| self.pending_incs.remove(idx));
+        self.inc_info_to_resolve
+    }
+
     /// Setea la flying_info recibida.
     pub fn set_flying_info(&mut self, info: DronFlyingInfo) {
         self.flying_info = Some(info);
@@ -190,14 +331,18 @@ impl DronCurrentInfo {
         self.longitude = new_position.1;
     }
 
-    /// Incrementa la posición actual en la dirección recibida, y devuelve la nueva posición actual.
-    pub fn increment_current_position_in(&mut self, dir: (f64, f64)) -> (f64, f64) {
+    /// Incrementa la posición actual en la dirección `dir`, y la devuelve. Si el resultado no fuera finito
+    /// (ej. por una dirección NaN), devuelve error sin modificar la posición.
+    pub fn increment_current_position_in(&mut self, dir: (f64, f64)) -> Result<(f64, f64), Error> {
         // La dirección es un vector unitario, pero para poder sumarlo a la lat y long y que tenga sentido
         // hay que escalarla.
-        self.latitude += dir.0 / 10000.0;
-        self.longitude += dir.1 / 10000.0;
+        let latitude = self.latitude + dir.0 / 10000.0;
+        let longitude = self.longitude + dir.1 / 10000.0;
+        check_finite_coordinates(latitude, longitude)?;
+        self.latitude = latitude;
+        self.longitude = longitude;
 
-        self.get_current_position()
+        Ok(self.get_current_position())
     }
 
     /// Devuelve dirección (lat, lon) y velocidad de vuelo actuales, o None si el dron
@@ -218,11 +363,10 @@ impl DronCurrentInfo {
         self.flying_info = None;
     }
 
+    /// Devuelve la distancia en metros desde la posición actual hasta `destination`.
     pub fn get_distance_to(&self, destination: (f64, f64)) -> f64 {
-        let origin = self.get_current_position();
-        let lat_dist = destination.0 - origin.0;
-        let lon_dist = destination.1 - origin.1;
-        f64::sqrt(lat_dist.powi(2) + lon_dist.powi(2))
+        let (lat, lon) = self.get_current_position();
+        Position::from_lat_lon(lat, lon).distance_meters(&Position::from_lat_lon(destination.0, destination.1))
     }
 
     /// Decrementa la batería, y chequea y devuelve si la batería está por debajo del mínimo.
@@ -248,7 +392,9 @@ impl DronCurrentInfo {
 
 #[cfg(test)]
 mod test {
-    use crate::apps::sist_dron::{dron_current_info::DronCurrentInfo, dron_state::DronState};
+    use crate::apps::sist_dron::{
+        dron_current_info::{DronCurrentInfo, DRON_CURRENT_INFO_WIRE_VERSION}, dron_flying_info::DronFlyingInfo, dron_state::DronState,
+    };
     use crate::apps::incident_data::{incident_info::IncidentInfo, incident_source::IncidentSource};
 
     #[test]
@@ -261,6 +407,7 @@ mod test {
             state: DronState::ExpectingToRecvIncident,
             inc_info_to_resolve: None,
             flying_info: None,
+            pending_incs: vec![],
         };
 
         let bytes = dron.to_bytes();
@@ -279,6 +426,7 @@ mod test {
             state: DronState::ExpectingToRecvIncident,
             inc_info_to_resolve: Some(IncidentInfo::new(18, IncidentSource::Manual)),
             flying_info: None,
+            pending_incs: vec![],
         };
 
         let bytes = dron.to_bytes();
@@ -286,4 +434,112 @@ mod test {
 
         assert_eq!(reconstructed_dron.unwrap(), dron);
     }
+
+    #[test]
+    fn test_2_try_set_state_rechaza_transiciones_no_validas() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::ExpectingToRecvIncident);
+
+        assert!(dron.try_set_state(DronState::ManagingIncident).is_err());
+        assert_eq!(dron.get_state(), DronState::ExpectingToRecvIncident);
+
+        dron.try_set_state(DronState::RespondingToIncident).unwrap();
+        assert_eq!(dron.get_state(), DronState::RespondingToIncident);
+    }
+
+    #[test]
+    fn test_3_dron_con_coordenadas_no_finitas_no_se_lee_ni_se_mueve() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::Flying);
+        let mut bytes = dron.to_bytes();
+        bytes[2..10].copy_from_slice(&f64::NAN.to_be_bytes());
+        assert!(DronCurrentInfo::from_bytes(bytes).is_err());
+
+        // Una dirección NaN (ej. al normalizar un vector de longitud cero) no modifica la posición
+        assert!(dron.increment_current_position_in((f64::NAN, 0.0)).is_err());
+        assert_eq!(dron.get_current_position(), (-34.0, -58.0));
+        assert!(dron.increment_current_position_in((1.0, 0.0)).is_ok());
+    }
+
+    #[test]
+    fn test_4_la_cola_de_incidentes_se_envia_y_la_cabeza_es_el_incidente_a_resolver() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::Flying);
+        dron.set_flying_info(DronFlyingInfo::new((1.0, 0.0), 50.0));
+        let first = IncidentInfo::new(1, IncidentSource::Manual);
+        let second = IncidentInfo::new(2, IncidentSource::Automated);
+        assert!(dron.enqueue_incident(first));
+        assert!(dron.enqueue_incident(second));
+        assert!(!dron.enqueue_incident(second));
+
+        let reconstructed_dron = DronCurrentInfo::from_bytes(dron.to_bytes()).unwrap();
+        assert_eq!(reconstructed_dron, dron);
+        assert_eq!(reconstructed_dron.get_inc_id_to_resolve(), Some(first));
+        assert_eq!(reconstructed_dron.get_incident_queue(), vec![first, second]);
+    }
+
+    #[test]
+    fn test_5_un_payload_sin_cola_se_lee_como_cola_de_un_elemento() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::RespondingToIncident);
+        let inc_info = IncidentInfo::new(18, IncidentSource::Manual);
+        dron.set_inc_id_to_resolve(inc_info);
+
+        // La versión 1 no tenía la cantidad de incidentes en espera al final
+        let mut old_bytes = dron.to_bytes();
+        old_bytes[0] = 1;
+        old_bytes.pop();
+
+        let reconstructed_dron = DronCurrentInfo::from_bytes(old_bytes).unwrap();
+        assert_eq!(reconstructed_dron.get_incident_queue(), vec![inc_info]);
+    }
+
+    #[test]
+    fn test_5b_un_payload_de_una_version_mas_nueva_se_lee_ignorando_los_campos_desconocidos() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::RespondingToIncident);
+        dron.enqueue_incident(IncidentInfo::new(18, IncidentSource::Manual));
+        dron.enqueue_incident(IncidentInfo::new(19, IncidentSource::Drone));
+
+        let mut new_bytes = dron.to_bytes();
+        new_bytes[0] = DRON_CURRENT_INFO_WIRE_VERSION + 1;
+        new_bytes.extend_from_slice(&[5, 5, 5]);
+
+        assert_eq!(DronCurrentInfo::from_bytes(new_bytes).unwrap(), dron);
+    }
+
+    #[test]
+    fn test_6_al_desencolar_se_pasa_al_incidente_en_espera_mas_cercano() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::RespondingToIncident);
+        let current = IncidentInfo::new(1, IncidentSource::Manual);
+        let far = IncidentInfo::new(2, IncidentSource::Manual);
+        let near = IncidentInfo::new(3, IncidentSource::Automated);
+        for inc_info in [current, far, near] {
+            dron.enqueue_incident(inc_info);
+        }
+        let position_of = |inc_info: &IncidentInfo| match inc_info.get_inc_id() {
+            2 => Some((-34.5, -58.5)),
+            3 => Some((-34.01, -58.01)),
+            _ => None,
+        };
+
+        assert_eq!(dron.dequeue_nearest_incident(position_of), Some(near));
+        assert_eq!(dron.dequeue_nearest_incident(position_of), Some(far));
+        assert_eq!(dron.dequeue_nearest_incident(position_of), None);
+        assert!(dron.get_incident_queue().is_empty());
+    }
+
+    #[test]
+    fn test_7_ante_incidentes_a_igual_distancia_se_elige_el_de_menor_id() {
+        let mut dron = DronCurrentInfo::new(1, -34.0, -58.0, 100, DronState::RespondingToIncident);
+        let current = IncidentInfo::new(1, IncidentSource::Manual);
+        let higher_id = IncidentInfo::new(9, IncidentSource::Manual);
+        let lower_id = IncidentInfo::new(4, IncidentSource::Manual);
+        let same_id_later = IncidentInfo::new(4, IncidentSource::Automated);
+        // Llegan en orden inverso al esperado, para que no se elija por orden de llegada
+        for inc_info in [current, higher_id, lower_id, same_id_later] {
+            dron.enqueue_incident(inc_info);
+        }
+        // Todos a la misma distancia del dron
+        let position_of = |_: &IncidentInfo| Some((-34.01, -58.01));
+
+        assert_eq!(dron.dequeue_nearest_incident(position_of), Some(lower_id));
+        assert_eq!(dron.dequeue_nearest_incident(position_of), Some(same_id_later));
+        assert_eq!(dron.dequeue_nearest_incident(position_of), Some(higher_id));
+    }
 }
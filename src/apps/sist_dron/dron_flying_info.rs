@@ -1,7 +1,11 @@
 use std::io::Error;
 
+use crate::apps::coordinates::check_finite_coordinates;
+
+use serde::Serialize;
+
 /// Dirección y velocidad con las que vuela el dron.
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize)]
 pub struct DronFlyingInfo {
     direction: (f64, f64), // vector unitario de dirección al volar, con componentes lat y lon
     speed: f64,            // velocidad de desplazamiento al volar
@@ -53,6 +57,7 @@ impl DronFlyingInfo {
             bytes[idx + 7 * b_size],
         ]);
         idx += 8 * b_size;
+        check_finite_coordinates(latitude, longitude)?;
         let direction = (latitude, longitude);
 
         // Leo la velocidad
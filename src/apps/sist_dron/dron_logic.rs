@@ -9,7 +9,8 @@ use crate::{
         apps_mqtt_topics::AppsMqttTopics,
         incident_data::{
             incident::Incident, incident_info::IncidentInfo, incident_state::IncidentState,
-        }, sist_dron::calculations::{calculate_direction, calculate_distance},
+        }, sist_dron::calculations::{calculate_direction, has_arrived_at},
+        vendor::{Position, EARTH_RADIUS_METERS},
     },
     logging::string_logger::StringLogger,
     mqtt::messages::publish_message::PublishMessage,
@@ -31,6 +32,17 @@ pub struct DronLogic {
     active_incs: Arc<Mutex<VecDeque<(IncidentInfo, Incident, u8)>>>, // el u8 es un contador de cuántos drones recibí que ya están yendo hacia ese inc.
 }
 
+/// Resultado de avanzar un paso del vuelo.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum FlightStep {
+    /// Sigue volando hacia el destino.
+    InFlight,
+    /// Llegó a destino.
+    Arrived,
+    /// El incidente hacia el que volaba dejó de ser el suyo: se interrumpe el vuelo.
+    Recalled,
+}
+
 type DistancesType = Arc<Mutex<HashMap<IncidentInfo, ((f64, f64), Vec<(u8, f64)>)>>>; // (inc_info, ( (inc_pos),(dron_id, distance_to_incident)) )
 
 impl DronLogic {
@@ -76,7 +88,7 @@ impl DronLogic {
             AppsMqttTopics::DronTopic => {
                 let received_ci = DronCurrentInfo::from_bytes(msg.get_payload())?;
                 let not_myself = self.current_data.get_id()? != received_ci.get_id();
-                let recvd_dron_is_not_flying = received_ci.get_state() != DronState::Flying;
+                let recvd_dron_is_not_flying = !received_ci.get_state().is_flying();
                 let recvd_dron_is_not_managing_incident =
                     received_ci.get_state() != DronState::ManagingIncident;
 
@@ -134,13 +146,24 @@ impl DronLogic {
         process_inc_tx: mpsc::Sender<()>,
     ) -> Result<(), Error> {
         let inc = Incident::from_bytes(payload)?;
+        self.process_incident(&inc, process_inc_tx)
+    }
 
+    /// Procesa el incidente `inc` recibido, según esté activo o resuelto, y avisa por `process_inc_tx`
+    /// que se puede procesar el siguiente incidente activo encolado.
+    pub(crate) fn process_incident(
+        &mut self,
+        inc: &Incident,
+        process_inc_tx: mpsc::Sender<()>,
+    ) -> Result<(), Error> {
         match *inc.get_state() {
             IncidentState::ActiveIncident => {
                 // Encolo el inc activo recibido
-                self.push_to_active_incs(&inc)?;
+                self.push_to_active_incs(inc)?;
                 // Se agrega la info del inc encolado, al distances, para que se haga el cálculo de las distancias para él tambiém
-                self.add_incident_to_hashmap(&inc)?;
+                self.add_incident_to_hashmap(inc)?;
+                // Si estoy resolviendo otro, lo dejo en espera en mi cola de incidentes
+                self.enqueue_if_busy(inc)?;
                 // Al incio, y si recibe un inc estando en su pos inicial, va a estar en estado Expecting
                 // Aviso al otro hilo que se puede desacolar y procesar el incidente activo
                 let _ = process_inc_tx.send(());
@@ -152,7 +175,7 @@ impl DronLogic {
                 // Primero remuevo el incidente resuelto de la queue de incs a procesar, para no procesarlo luego
                 self.remove_from_active_incs(inc.get_info())?;
                 // Vuelvo a la posición inicial
-                self.go_back_if_my_inc_was_resolved(&inc)?;
+                self.go_back_if_my_inc_was_resolved(inc)?;
                 // Aviso que ya se puede procesar el siguiente incidente activo encolado
                 let _ = process_inc_tx.send(());
                 println!("DEBUG QUEUE: se resolvió el inc: {:?}, enviando señal", inc.get_source());
@@ -200,6 +223,79 @@ impl DronLogic {
 
     }
 
+    /// Si el dron está resolviendo otro incidente, deja a `inc` en espera en su cola de incidentes, si está en
+    /// su rango, para resolverlo luego si es el más cercano (ver `continue_with_nearest_pending_incident`).
+    fn enqueue_if_busy(&mut self, inc: &Incident) -> Result<(), Error> {
+        let busy = match self.current_data.get_inc_id_to_resolve()? {
+            Some(inc_info) => inc_info != inc.get_info(),
+            None => false,
+        };
+        let (inc_lat, inc_lon) = inc.get_position();
+        if !busy || !self.is_within_range_from_self(inc_lat, inc_lon, self.dron_properties.get_range()) {
+            return Ok(());
+        }
+        if self.current_data.enqueue_incident(inc.get_info())? {
+            self.logger.log(format!("Inc {} en espera en la cola del dron.", inc.get_id()));
+        } else {
+            self.logger.log(format!(
+                "Inc {} no se deja en espera: ya lo estaba, o la cola del dron está llena.",
+                inc.get_id()
+            ));
+        }
+        Ok(())
+    }
+
+    /// Finaliza el incidente a resolver, y pasa al incidente en espera más cercano a su posición actual entre
+    /// los que siguen activos: lo adelanta en `active_incs`, para que sea el próximo en procesarse. Si ninguno
+    /// sigue activo (ej. ya van dos drones hacia ellos), queda sin incidente a resolver.
+    fn continue_with_nearest_pending_incident(&mut self) -> Result<(), Error> {
+        let positions = self.get_active_incs_positions()?;
+        let next = self
+            .current_data
+            .dequeue_nearest_incident(|inc_info| positions.get(inc_info).copied())?;
+        if let Some(next) = next {
+            if self.move_to_front_of_active_incs(next)? {
+                self.logger.log(format!(
+                    "Próximo inc a resolver, el más cercano en espera: {:?}",
+                    next
+                ));
+            } else {
+                self.current_data.unset_inc_id_to_resolve()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Devuelve la posición de cada incidente activo a manejar.
+    fn get_active_incs_positions(&self) -> Result<HashMap<IncidentInfo, (f64, f64)>, Error> {
+        if let Ok(queue) = self.active_incs.lock() {
+            return Ok(queue
+                .iter()
+                .map(|(info, inc, _)| (*info, inc.get_position()))
+                .collect());
+        }
+        Err(Error::other("Error al tomar lock de active_incs."))
+    }
+
+    /// Mueve el incidente `inc_info` al frente de los incidentes activos a manejar, para procesarlo a continuación.
+    /// Devuelve false si no está entre ellos.
+    fn move_to_front_of_active_incs(&mut self, inc_info: IncidentInfo) -> Result<bool, Error> {
+        if let Ok(mut queue) = self.active_incs.lock() {
+            let entry = match queue.iter().position(|(info, _, _)| *info == inc_info) {
+                Some(pos) => queue.remove(pos),
+                None => None,
+            };
+            return Ok(match entry {
+                Some(entry) => {
+                    queue.push_front(entry);
+                    true
+                }
+                None => false,
+            });
+        }
+        Err(Error::other("Error al tomar lock de active_incs."))
+    }
+
     /// Hace pop de la estructure de incidentes activos a manejar, si la misma está vacía devuelve Ok(None).
     /// Y devuelve error si no se pudo tomar el lock.
     fn pop_from_active_incs(&mut self) -> Result<Option<(IncidentInfo, Incident, u8)>, Error>   {
@@ -292,8 +388,9 @@ impl DronLogic {
             if let Some((_incident_position, candidate_drones)) =
                 distances.get_mut(&incident.get_info())
             {
-                // Ordenar por el valor f64 de la tupla, de menor a mayor
-                candidate_drones.sort_by(|a, b| a.1.total_cmp(&b.1));
+                // Ordenar por el valor f64 de la tupla, de menor a mayor. Ante igual distancia, primero el de
+                // menor id, para que todos los drones tomen la misma decisión sin importar el orden en que recibieron los avisos
+                candidate_drones.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.0.cmp(&b.0)));
 
                 // Seleccionar los primeros dos elementos después de ordenar
                 let closest_two_drones: Vec<u8> =
@@ -351,14 +448,7 @@ impl DronLogic {
                     "  está en rango, evaluando si desplazarme a inc {}",
                     inc_id.get_id()
                 ));
-                self.current_data.set_inc_id_to_resolve(inc_id.get_info())?; //
-                self.add_incident_to_hashmap(inc_id)?;
-
-                self.current_data
-                    .set_state(DronState::RespondingToIncident, false)?;
-
-                // Publica su estado (su current info) para que otros drones vean la condición b, y monitoreo lo muestre en mapa
-                self.publish_current_info()?;
+                self.start_responding_to(inc_id)?;
 
                 let should_move =
                     self.decide_if_should_move_to_incident(inc_id)?;
@@ -367,14 +457,18 @@ impl DronLogic {
                     "   debería ir al incidente según cercanía: {}",
                     should_move
                 ));
-                if should_move {
-                    // Setea estado y avisa que quedó como ganador y se moverá al incidente
-                    self.current_data.set_state(DronState::MustRespondToIncident, false)?;
-                    self.publish_current_info()?;
+                // Mientras lo evaluaba, el incidente pudo haberse resuelto o cancelado (ver `recall_from_incident`)
+                let still_assigned = self.current_data.get_inc_id_to_resolve()? == Some(inc_id.get_info());
+                if should_move && !still_assigned {
+                    self.logger.log(format!(
+                        "   el inc {} ya no está asignado, no me desplazo.",
+                        inc_id.get_id()
+                    ));
+                } else if should_move {
+                    self.commit_to_incident()?;
 
                     // Volar hasta la posición del incidente
-                    let destination = inc_id.get_position();
-                    self.fly_to(destination)?;
+                    self.fly_to_incident(inc_id)?;
                     self.remove_incident_from_hashmap(inc_id)?;
                 }
             } else {
@@ -388,24 +482,41 @@ impl DronLogic {
 
             // Volar a la posición de Mantenimiento
             let destination = self.dron_properties.get_range_center_position();
-            self.fly_to(destination)?;
+            self.fly_to(destination, DronState::Flying)?;
         }
 
         Ok(())
     }
 
+    /// Toma al `inc` como el incidente a resolver, y publica que está evaluando responder a él.
+    pub(crate) fn start_responding_to(&mut self, inc: &Incident) -> Result<(), Error> {
+        // Si lo tenía en espera, deja de estarlo
+        self.current_data.remove_incident_from_queue(&inc.get_info())?;
+        self.current_data.set_inc_id_to_resolve(inc.get_info())?;
+        self.add_incident_to_hashmap(inc)?;
+
+        self.current_data
+            .set_state(DronState::RespondingToIncident, false)?;
+
+        // Publica su estado (su current info) para que otros drones vean la condición b, y monitoreo lo muestre en mapa
+        self.publish_current_info()
+    }
+
+    /// Setea estado y avisa que quedó como ganador y se moverá al incidente.
+    pub(crate) fn commit_to_incident(&mut self) -> Result<(), Error> {
+        self.current_data.set_state(DronState::MustRespondToIncident, false)?;
+        self.publish_current_info()
+    }
+
     /// Calcula si se encuentra las coordenadas pasadas se encuentran dentro de su rango.
+    /// El rango se expresa en milésimas de grado (de latitud), y se lo compara en metros.
     fn is_within_range_from_self(&self, latitude: f64, longitude: f64, range: f64) -> bool {
         let (center_lat, center_lon) = self.dron_properties.get_range_center_position();
-        let lat_dist = center_lat - latitude;
-        let long_dist = center_lon - longitude;
-        let rad = f64::sqrt(lat_dist.powi(2) + long_dist.powi(2));
-
-        // Ajuste para aprox dos manzanas en diagonal
-        let adjusted_range = range / 1000.0; // hay que modificar el range de las cámaras, ahora que son latitudes de verdad y no "3 4".
-                                             //println!("Dio que la cuenta vale: {}, y adj_range vale: {}. Era rango: {}", rad, adjusted_range, range); // debug []
+        let center = Position::from_lat_lon(center_lat, center_lon);
+        let distance = center.distance_meters(&Position::from_lat_lon(latitude, longitude));
 
-        rad <= (adjusted_range)
+        let range_meters = (range / 1000.0).to_radians() * EARTH_RADIUS_METERS;
+        distance <= range_meters
     }
 
     /// Analiza si el incidente que se resolvió fue el que el dron self estaba atendiendo.
@@ -418,42 +529,113 @@ impl DronLogic {
         self.logger
             .log(format!("Recibido inc resuelto de id: {}", inc.get_id()));
 
-        if let Some(my_inc_id) = self.current_data.get_inc_id_to_resolve()? {
-            if inc.get_info() == my_inc_id {
-
-                self.logger.log(format!(
-                    "Recibido inc resuelto de id: {}, volviendo a posición inicial",
-                    inc.get_id()
-                ));
-                self.current_data.unset_inc_id_to_resolve()?; // [lo he subido una línea] [] aux
-                self.go_back_to_range_center_position()?;
-                
+        if self.recall_from_incident(inc.get_info())? {
+            self.logger.log(format!(
+                "Recibido inc resuelto de id: {}, volviendo a posición inicial",
+                inc.get_id()
+            ));
+            // Si todavía está volando hacia el incidente (ej. se lo resolvió manualmente desde monitoreo),
+            // el hilo que lo hace volar interrumpe el vuelo y lo hace volver.
+            match self.current_data.get_state()? {
+                DronState::Flying | DronState::GoingToIncident | DronState::MustRespondToIncident => {}
+                _ => self.go_back_to_range_center_position()?,
             }
         }
 
         Ok(())
     }
 
+    /// Cancela la asignación del dron al incidente `inc_info` (ej. porque monitoreo lo resolvió o lo canceló):
+    /// si era su incidente a resolver, deja de serlo, pasando al más cercano que tenga en espera (ver
+    /// `continue_with_nearest_pending_incident`), y se lo quita del cálculo de distancias. Así, lo próximo
+    /// que publique ya no cuenta para ese incidente, y si estaba volando hacia él, el vuelo se interrumpe
+    /// (ver `flight_step`). Volver a su posición inicial queda a cargo de quien lo llama. Si lo tenía en espera,
+    /// lo quita de su cola. Devuelve si el dron estaba asignado a `inc_info`.
+    pub(crate) fn recall_from_incident(&mut self, inc_info: IncidentInfo) -> Result<bool, Error> {
+        if self.current_data.get_inc_id_to_resolve()? != Some(inc_info) {
+            self.current_data.remove_incident_from_queue(&inc_info)?;
+            return Ok(false);
+        }
+        self.continue_with_nearest_pending_incident()?;
+        if let Ok(mut distances) = self.drone_distances_by_incident.lock() {
+            distances.remove(&inc_info);
+        }
+        Ok(true)
+    }
+
     /// Vuelve al centro de su rango (su posición inicial), y una vez que llega actualiza su estado
     /// para continuar escuchando incidentes.
-    fn go_back_to_range_center_position(
+    pub(crate) fn go_back_to_range_center_position(
         &mut self,
     ) -> Result<(), Error> {
         // Volver, volar al range center
         let destination = self.dron_properties.get_range_center_position();
-        self.fly_to(destination)?;
+        self.fly_to(destination, DronState::ReturningToBase)?;
 
-        // Una vez que llegué: Setear estado a nuevamente recibir incidentes
+        // Una vez que llegué: Setear estado a nuevamente recibir incidentes, y publicarlo para que monitoreo lo vea disponible
         self.current_data
             .set_state(DronState::ExpectingToRecvIncident, false)?;
+        self.publish_current_info()?;
 
         Ok(())
     }
 
+    /// Vuela hasta la posición del incidente `inc`. Si mientras tanto el incidente se resuelve
+    /// (deja de ser el incidente a resolver del dron), interrumpe el vuelo y vuelve a su posición inicial.
+    fn fly_to_incident(&mut self, inc: &Incident) -> Result<(), Error> {
+        let arrived =
+            self.fly_to_unless_recalled(inc.get_position(), Some(inc.get_info()), DronState::GoingToIncident)?;
+        if !arrived {
+            self.logger.log(format!(
+                "Inc {} resuelto durante el vuelo, volviendo a posición inicial.",
+                inc.get_id()
+            ));
+            self.current_data.unset_flying_info_values()?;
+            self.go_back_to_range_center_position()?;
+        }
+        Ok(())
+    }
+
+    /// Vuela hasta `destination`, en el estado `flight_state` (ver `start_flight`).
     fn fly_to(
         &mut self,
         destination: (f64, f64),
+        flight_state: DronState,
     ) -> Result<(), Error> {
+        self.fly_to_unless_recalled(destination, None, flight_state)?;
+        Ok(())
+    }
+
+    /// Vuela hasta `destination`, en el estado `flight_state`. Si se indica `inc_info`, antes de cada paso
+    /// verifica que siga siendo el incidente a resolver del dron: si no, interrumpe el vuelo.
+    /// Devuelve si llegó a destino.
+    fn fly_to_unless_recalled(
+        &mut self,
+        destination: (f64, f64),
+        inc_info: Option<IncidentInfo>,
+        flight_state: DronState,
+    ) -> Result<bool, Error> {
+        let dir = self.start_flight(destination, flight_state)?;
+        loop {
+            match self.flight_step(dir, destination, inc_info)? {
+                FlightStep::InFlight => {
+                    // Simula el vuelo, el dron se desplaza
+                    let a = 4/5; // aux
+                    sleep(Duration::from_secs(a));
+                }
+                FlightStep::Arrived => return Ok(true),
+                FlightStep::Recalled => return Ok(false),
+            }
+        }
+    }
+
+    /// Comienza el vuelo hacia `destination`: pasa al estado de vuelo `flight_state` (ej. `GoingToIncident`),
+    /// con la dirección y velocidad del vuelo. Devuelve la dirección, a utilizar en cada `flight_step`.
+    pub(crate) fn start_flight(
+        &mut self,
+        destination: (f64, f64),
+        flight_state: DronState,
+    ) -> Result<(f64, f64), Error> {
         let origin = self.current_data.get_current_position()?;
         let dir = calculate_direction(origin, destination);
         println!("Fly_to: volando"); // se puede borrar
@@ -463,29 +645,43 @@ impl DronLogic {
             self.dron_properties.get_speed()
         ));
 
-        self.current_data.set_state(DronState::Flying, false)?;
+        self.current_data.set_state(flight_state, false)?;
         self.current_data
             .set_flying_info_values(dir, self.dron_properties.get_speed(), false)?;
-        let mut current_pos = origin;
-        let threshold = 0.001; //
-        while calculate_distance(current_pos, destination) > threshold {
-            current_pos = self
-                .current_data
-                .increment_current_position_in(dir, false)?;
-
-            // Simula el vuelo, el dron se desplaza
-            let a = 4/5; // aux
-            sleep(Duration::from_secs(a));
-            self.logger.log(format!(
-                "   incrementada la posición actual: {:?}",
-                self.current_data.get_current_position()
-            ));
+        Ok(dir)
+    }
 
-            // Publica
-            self.publish_current_info()?;
+    /// Avanza un paso del vuelo en la dirección `dir`, y publica la nueva posición. Si ya está a muy poca distancia
+    /// de `destination`, hace el paso final y termina el vuelo. Si se indica `inc_info` y ya no es el incidente
+    /// a resolver del dron, no avanza y devuelve `FlightStep::Recalled`.
+    pub(crate) fn flight_step(
+        &mut self,
+        dir: (f64, f64),
+        destination: (f64, f64),
+        inc_info: Option<IncidentInfo>,
+    ) -> Result<FlightStep, Error> {
+        let current_pos = self.current_data.get_current_position()?;
+        if has_arrived_at(current_pos, destination, self.dron_properties.get_arrival_radius()) {
+            self.finish_flight(destination)?;
+            return Ok(FlightStep::Arrived);
         }
+        if inc_info.is_some() && self.current_data.get_inc_id_to_resolve()? != inc_info {
+            return Ok(FlightStep::Recalled);
+        }
+        self.current_data
+            .increment_current_position_in(dir, false)?;
+        self.logger.log(format!(
+            "   incrementada la posición actual: {:?}",
+            self.current_data.get_current_position()
+        ));
 
-        // Salió del while porque está a muy poca distancia del destino. Hace ahora el paso final.
+        // Publica
+        self.publish_current_info()?;
+        Ok(FlightStep::InFlight)
+    }
+
+    /// Hace el paso final hasta `destination`, y si volaba hacia un incidente, pasa a manejarlo.
+    fn finish_flight(&mut self, destination: (f64, f64)) -> Result<(), Error> {
         self.current_data.set_current_position(destination)?;
 
         // Al llegar, el dron ya no se encuentra en desplazamiento.
@@ -495,9 +691,11 @@ impl DronLogic {
             self.current_data.get_current_position()
         ));
 
-        // Llegue a destino entonces debo cambiar a estado --> Manejando Incidente
-        self.current_data
-            .set_state(DronState::ManagingIncident, false)?;
+        // Si llegué al incidente debo cambiar a estado --> Manejando Incidente
+        if self.current_data.get_state()? == DronState::GoingToIncident {
+            self.current_data
+                .set_state(DronState::ManagingIncident, false)?;
+        }
 
         // Publica
         self.publish_current_info()?;
@@ -27,7 +27,8 @@ fn main() -> Result<(), Error> {
     let qos = 1; // []
     let client_id = get_formatted_app_id(id);
     let will_msg_content = get_app_will_msg_content(id);
-    let will_msg_data = WillMessageData::new(will_msg_content.to_str(), get_app_will_topic(), qos, 1);
+    let will_msg_data =
+        WillMessageData::for_app_topic(will_msg_content.to_str(), get_app_will_topic(), qos, 1)?;
     
     match MQTTClient::mqtt_connect_to_broker(client_id, &broker_addr, Some(will_msg_data), logger.clone_ref()) {
         Ok((mqtt_client, publish_msg_rx, handle)) => {            
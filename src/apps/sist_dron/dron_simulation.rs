@@ -0,0 +1,294 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+};
+
+use crate::{
+    apps::incident_data::{incident::Incident, incident_info::IncidentInfo},
+    logging::string_logger::StringLogger,
+};
+
+use super::{
+    data::Data,
+    dron_current_info::DronCurrentInfo,
+    dron_logic::{DronLogic, FlightStep},
+    dron_state::DronState,
+    sist_dron_properties::SistDronProperties,
+};
+
+/// Vuelo en curso de un dron simulado.
+#[derive(Debug, Clone, Copy)]
+struct ScriptedFlight {
+    dir: (f64, f64),
+    destination: (f64, f64),
+    inc_info: IncidentInfo,
+}
+
+#[derive(Debug)]
+struct SimulatedDron {
+    logic: DronLogic,
+    flight: Option<ScriptedFlight>,
+}
+
+/// Simulación determinística de drones, para testear el flujo de drones e incidentes sin depender de timers.
+/// Utiliza la lógica real de los drones (`DronLogic`): al despacharlos a un incidente pasan por los mismos estados,
+/// y en cada tick avanzan un paso del vuelo con `increment_current_position_in`. En lugar de publicarse por MQTT,
+/// las `DronCurrentInfo` se acumulan en orden, para que el test las procese como lo haría el Sistema Monitoreo.
+#[derive(Debug)]
+pub struct DronSimulation {
+    drones: HashMap<u8, SimulatedDron>,
+    ci_tx: Sender<DronCurrentInfo>,
+    ci_rx: Receiver<DronCurrentInfo>,
+    published: Vec<DronCurrentInfo>,
+    logger: StringLogger,
+}
+
+impl DronSimulation {
+    pub fn new(logger: StringLogger) -> Self {
+        let (ci_tx, ci_rx) = mpsc::channel();
+        Self {
+            drones: HashMap::new(),
+            ci_tx,
+            ci_rx,
+            published: vec![],
+            logger,
+        }
+    }
+
+    /// Agrega un dron con id `id`, en el centro de su rango `position`, con batería al 100% y esperando incidentes.
+    pub fn add_dron(&mut self, id: u8, position: (f64, f64), mut properties: SistDronProperties) {
+        properties.set_range_center_position(position.0, position.1);
+        let current_info = DronCurrentInfo::new(
+            id,
+            position.0,
+            position.1,
+            100,
+            DronState::ExpectingToRecvIncident,
+        );
+        let logic = DronLogic::new(
+            Data::new(current_info),
+            properties,
+            self.logger.clone_ref(),
+            Arc::new(Mutex::new(HashMap::new())),
+            self.ci_tx.clone(),
+        );
+        self.drones.insert(id, SimulatedDron { logic, flight: None });
+    }
+
+    /// Hace que el dron `id` responda al `inc` y comience a volar hacia él, como si hubiera resultado
+    /// ser uno de los más cercanos. El vuelo avanza recién con `advance`.
+    pub fn dispatch_to(&mut self, id: u8, inc: &Incident) -> Result<(), Error> {
+        let dron = self.get_dron(id)?;
+        dron.logic.start_responding_to(inc)?;
+        dron.logic.commit_to_incident()?;
+        let dir = dron.logic.start_flight(inc.get_position(), DronState::GoingToIncident)?;
+        dron.flight = Some(ScriptedFlight {
+            dir,
+            destination: inc.get_position(),
+            inc_info: inc.get_info(),
+        });
+        self.collect_published();
+        Ok(())
+    }
+
+    /// Avanza `ticks` pasos: en cada uno, los drones que están volando avanzan un paso del vuelo.
+    pub fn advance(&mut self, ticks: u32) -> Result<(), Error> {
+        let mut ids: Vec<u8> = self.drones.keys().copied().collect();
+        ids.sort();
+        for _ in 0..ticks {
+            for id in ids.iter() {
+                let dron = self.get_dron(*id)?;
+                if let Some(flight) = dron.flight {
+                    let step = dron
+                        .logic
+                        .flight_step(flight.dir, flight.destination, Some(flight.inc_info))?;
+                    if step != FlightStep::InFlight {
+                        dron.flight = None;
+                    }
+                    // Como el hilo que lo hace volar, al interrumpirse el vuelo lo hace volver a su posición inicial
+                    if step == FlightStep::Recalled {
+                        dron.logic.go_back_to_range_center_position()?;
+                    }
+                }
+            }
+        }
+        self.collect_published();
+        Ok(())
+    }
+
+    /// Llama de vuelta al dron `id` del incidente `inc_info`, como al recibirlo resuelto o cancelado. Si está
+    /// volando hacia él, el vuelo se interrumpe en el próximo `advance`; si no, vuelve a su posición inicial.
+    /// Devuelve si el dron estaba asignado a `inc_info`.
+    pub fn recall(&mut self, id: u8, inc_info: IncidentInfo) -> Result<bool, Error> {
+        let dron = self.get_dron(id)?;
+        let recalled = dron.logic.recall_from_incident(inc_info)?;
+        if recalled && dron.flight.is_none() {
+            dron.logic.go_back_to_range_center_position()?;
+        }
+        self.collect_published();
+        Ok(recalled)
+    }
+
+    /// Hace que el dron `id` reciba el incidente `inc`, activo o resuelto, como si le llegara por MQTT.
+    /// El aviso para procesar el siguiente incidente activo se descarta: los despachos los decide el test.
+    pub fn receive_incident(&mut self, id: u8, inc: &Incident) -> Result<(), Error> {
+        let (process_inc_tx, _process_inc_rx) = mpsc::channel();
+        let dron = self.get_dron(id)?;
+        dron.logic.process_incident(inc, process_inc_tx)?;
+        self.collect_published();
+        Ok(())
+    }
+
+    /// Ejecuta el guion: avanza, en orden, la cantidad de ticks de cada elemento de `script`.
+    pub fn run_script(&mut self, script: &[u32]) -> Result<(), Error> {
+        for ticks in script {
+            self.advance(*ticks)?;
+        }
+        Ok(())
+    }
+
+    /// Devuelve, en orden, las `DronCurrentInfo` que publicaron los drones.
+    pub fn get_published(&self) -> &[DronCurrentInfo] {
+        &self.published
+    }
+
+    /// Devuelve la cantidad de drones que, según lo último que publicaron, están manejando el incidente `inc_info`.
+    pub fn drones_at_incident(&self, inc_info: IncidentInfo) -> usize {
+        let mut last_by_dron: HashMap<u8, &DronCurrentInfo> = HashMap::new();
+        for ci in self.published.iter() {
+            last_by_dron.insert(ci.get_id(), ci);
+        }
+        last_by_dron
+            .values()
+            .filter(|ci| {
+                ci.get_state() == DronState::ManagingIncident && ci.get_inc_id_to_resolve() == Some(inc_info)
+            })
+            .count()
+    }
+
+    fn get_dron(&mut self, id: u8) -> Result<&mut SimulatedDron, Error> {
+        match self.drones.get_mut(&id) {
+            Some(dron) => Ok(dron),
+            None => Err(Error::new(
+                ErrorKind::NotFound,
+                format!("No existe el dron simulado {}", id),
+            )),
+        }
+    }
+
+    fn collect_published(&mut self) {
+        while let Ok(ci) = self.ci_rx.try_recv() {
+            self.published.push(ci);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, time::Duration};
+
+    use super::DronSimulation;
+    use crate::{
+        apps::{
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            sist_dron::{dron_state::DronState, sist_dron_properties::SistDronProperties},
+            sist_monitoreo::incident_source_policy::{IncidentSourcePolicy, SourcePolicy},
+        },
+        logging::string_logger::StringLogger,
+    };
+
+    #[test]
+    fn test_1_el_incidente_se_resuelve_recien_cuando_llegan_los_drones_del_guion() {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let properties = SistDronProperties::new("src/apps/sist_dron/sistema_dron.properties").unwrap();
+        let mut simulation = DronSimulation::new(StringLogger::new(logger_tx));
+        // A 0.002 y 0.004 grados del incidente; cada tick avanza 0.0001 grados
+        simulation.add_dron(1, (-34.6090, -58.3873), properties);
+        simulation.add_dron(2, (-34.6110, -58.3873), properties);
+        let incident = Incident::new(1, (-34.6070, -58.3873), IncidentSource::Manual);
+        let policy = IncidentSourcePolicy::new(2, SourcePolicy::new(2, Duration::ZERO));
+
+        simulation.dispatch_to(1, &incident).unwrap();
+        simulation.dispatch_to(2, &incident).unwrap();
+        simulation.run_script(&[8, 8]).unwrap();
+        // Solamente llegó el dron 1
+        assert_eq!(simulation.drones_at_incident(incident.get_info()), 1);
+        assert!(!policy.is_resolved_by(incident.get_source(), 1));
+
+        simulation.run_script(&[10, 10]).unwrap();
+        let drones_at_incident = simulation.drones_at_incident(incident.get_info());
+        assert_eq!(drones_at_incident, 2);
+        assert!(policy.is_resolved_by(incident.get_source(), drones_at_incident));
+
+        // Los drones publicaron su recorrido, y terminaron en la posición del incidente
+        assert!(simulation.get_published().iter().any(|ci| ci.get_state() == DronState::GoingToIncident));
+        let last = simulation.get_published().last().unwrap();
+        assert_eq!(last.get_state(), DronState::ManagingIncident);
+        assert_eq!(last.get_current_position(), incident.get_position());
+    }
+
+    #[test]
+    fn test_2_un_dron_llamado_de_vuelta_deja_de_contar_para_el_incidente() {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let properties = SistDronProperties::new("src/apps/sist_dron/sistema_dron.properties").unwrap();
+        let mut simulation = DronSimulation::new(StringLogger::new(logger_tx));
+        simulation.add_dron(1, (-34.6090, -58.3873), properties);
+        simulation.add_dron(2, (-34.6110, -58.3873), properties);
+        let incident = Incident::new(1, (-34.6070, -58.3873), IncidentSource::Manual);
+
+        simulation.dispatch_to(1, &incident).unwrap();
+        simulation.dispatch_to(2, &incident).unwrap();
+        simulation.advance(25).unwrap();
+        // El dron 1 llegó, y el 2 sigue en vuelo
+        assert_eq!(simulation.drones_at_incident(incident.get_info()), 1);
+
+        // Se resuelve manualmente desde monitoreo: ambos drones son llamados de vuelta
+        assert!(simulation.recall(1, incident.get_info()).unwrap());
+        assert!(simulation.recall(2, incident.get_info()).unwrap());
+        assert!(!simulation.recall(2, incident.get_info()).unwrap());
+        simulation.advance(30).unwrap();
+
+        // Ninguno cuenta para el incidente, y ambos vuelven a esperar incidentes en su posición inicial
+        assert_eq!(simulation.drones_at_incident(incident.get_info()), 0);
+        assert!(simulation.get_published().iter().any(|ci| ci.get_state() == DronState::ReturningToBase));
+        for (id, position) in [(1, (-34.6090, -58.3873)), (2, (-34.6110, -58.3873))] {
+            let last = simulation.get_published().iter().rev().find(|ci| ci.get_id() == id).unwrap();
+            assert_eq!(last.get_state(), DronState::ExpectingToRecvIncident);
+            assert_eq!(last.get_inc_id_to_resolve(), None);
+            assert_eq!(last.get_current_position(), position);
+        }
+    }
+
+    #[test]
+    fn test_3_al_resolverse_su_incidente_el_dron_pasa_al_mas_cercano_de_los_que_esperaba() {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let properties = SistDronProperties::new("src/apps/sist_dron/sistema_dron.properties").unwrap();
+        let mut simulation = DronSimulation::new(StringLogger::new(logger_tx));
+        simulation.add_dron(1, (-34.6090, -58.3873), properties);
+        let mut incident = Incident::new(1, (-34.6070, -58.3873), IncidentSource::Manual);
+        simulation.receive_incident(1, &incident).unwrap();
+        simulation.dispatch_to(1, &incident).unwrap();
+        simulation.advance(25).unwrap();
+
+        // Mientras maneja el incidente 1 recibe otros dos: quedan en espera
+        let far = Incident::new(2, (-34.6120, -58.3873), IncidentSource::Manual);
+        let near = Incident::new(3, (-34.6065, -58.3873), IncidentSource::Manual);
+        let mut resolved_while_waiting = Incident::new(4, (-34.6068, -58.3873), IncidentSource::Manual);
+        for inc in [&far, &near, &resolved_while_waiting] {
+            simulation.receive_incident(1, inc).unwrap();
+        }
+        resolved_while_waiting.set_resolved();
+        simulation.receive_incident(1, &resolved_while_waiting).unwrap();
+
+        // Al resolverse el 1, pasa al más cercano a su posición, y el otro sigue en espera
+        incident.set_resolved();
+        simulation.receive_incident(1, &incident).unwrap();
+        let last = simulation.get_published().last().unwrap();
+        assert_eq!(last.get_state(), DronState::ExpectingToRecvIncident);
+        assert_eq!(last.get_incident_queue(), vec![near.get_info(), far.get_info()]);
+    }
+}
@@ -1,6 +1,17 @@
-use std::io::{Error, ErrorKind};
+use std::{
+    fmt::Display,
+    io::{Error, ErrorKind},
+};
 
-#[derive(Debug, PartialEq, Copy, Clone)]
+use serde::Serialize;
+
+/// Estado del dron. Su ciclo de vida es: `ExpectingToRecvIncident` mientras espera incidentes, `RespondingToIncident`
+/// al evaluar si atender uno, `MustRespondToIncident` si debe ir, `GoingToIncident` mientras vuela hacia él,
+/// `ManagingIncident` al llegar, `ReturningToBase` mientras vuelve al centro de su rango, y nuevamente
+/// `ExpectingToRecvIncident` al llegar allí. `Flying` es un vuelo que no es hacia un incidente ni de regreso.
+/// Desde cualquier estado puede ir a recargarse: `GoingToMaintenance` mientras vuela hacia mantenimiento,
+/// `Charging` mientras se recarga, y `Mantainance` al volver de allí.
+#[derive(Debug, PartialEq, Copy, Clone, Serialize)]
 pub enum DronState {
     ExpectingToRecvIncident,
     RespondingToIncident, // analizando si se va a mover (se evalúa la condición de los dos más cercanos)
@@ -9,6 +20,10 @@ pub enum DronState {
     Mantainance,
     ManagingIncident, // llegó al incidente
     IncidentResolved,
+    GoingToMaintenance, // volando hacia mantenimiento
+    Charging, // recargando batería en mantenimiento
+    GoingToIncident, // volando hacia el incidente
+    ReturningToBase, // volviendo al centro de su rango
 }
 
 impl DronState {
@@ -21,6 +36,10 @@ impl DronState {
             DronState::Mantainance => 5_u8.to_be_bytes(),
             DronState::ManagingIncident => 6_u8.to_be_bytes(),
             DronState::IncidentResolved => 7_u8.to_be_bytes(),
+            DronState::GoingToMaintenance => 8_u8.to_be_bytes(),
+            DronState::Charging => 9_u8.to_be_bytes(),
+            DronState::GoingToIncident => 10_u8.to_be_bytes(),
+            DronState::ReturningToBase => 11_u8.to_be_bytes(),
         }
     }
 
@@ -33,10 +52,142 @@ impl DronState {
             5 => Ok(DronState::Mantainance),
             6 => Ok(DronState::ManagingIncident),
             7 => Ok(DronState::IncidentResolved),
+            8 => Ok(DronState::GoingToMaintenance),
+            9 => Ok(DronState::Charging),
+            10 => Ok(DronState::GoingToIncident),
+            11 => Ok(DronState::ReturningToBase),
             _ => Err(Error::new(
                 ErrorKind::InvalidInput,
                 "Estado de dron no válido",
             )),
         }
     }
+
+    /// Devuelve si el dron se encuentra en alguna etapa del mantenimiento, durante la cual sólo el módulo
+    /// de mantenimiento puede modificarlo.
+    pub fn is_in_maintenance(&self) -> bool {
+        matches!(
+            self,
+            DronState::Mantainance | DronState::GoingToMaintenance | DronState::Charging
+        )
+    }
+
+    /// Devuelve si el dron se encuentra volando, fuera de mantenimiento.
+    pub fn is_flying(&self) -> bool {
+        matches!(
+            self,
+            DronState::Flying | DronState::GoingToIncident | DronState::ReturningToBase
+        )
+    }
+
+    /// Devuelve si el dron puede pasar de este estado a `next`, según su ciclo de vida.
+    /// Volver a `ExpectingToRecvIncident`, o a su base con `ReturningToBase`, se permite desde cualquier estado
+    /// (ej. si no fue el elegido para ir al incidente, o si se lo resolvió mientras volaba), y al salir de
+    /// mantenimiento el dron retoma lo que estaba haciendo. Permanecer en el mismo estado siempre se permite.
+    pub fn can_transition_to(&self, next: DronState) -> bool {
+        *self == next
+            || matches!(
+                (self, next),
+                (_, DronState::ExpectingToRecvIncident)
+                    | (_, DronState::ReturningToBase)
+                    | (_, DronState::Mantainance)
+                    | (_, DronState::GoingToMaintenance)
+                    | (DronState::GoingToMaintenance, DronState::Charging)
+                    | (DronState::ExpectingToRecvIncident, DronState::RespondingToIncident)
+                    | (DronState::RespondingToIncident, DronState::MustRespondToIncident)
+                    | (DronState::ExpectingToRecvIncident, DronState::GoingToIncident)
+                    | (DronState::MustRespondToIncident, DronState::GoingToIncident)
+                    | (DronState::GoingToIncident, DronState::ManagingIncident)
+                    | (DronState::ReturningToBase, DronState::Charging)
+                    | (DronState::MustRespondToIncident, DronState::Flying)
+                    | (DronState::Flying, DronState::ManagingIncident)
+                    | (DronState::ManagingIncident, DronState::IncidentResolved)
+                    | (DronState::ManagingIncident, DronState::Flying)
+                    | (DronState::IncidentResolved, DronState::Flying)
+            )
+            || (self.is_in_maintenance()
+                && matches!(next, DronState::Flying | DronState::ManagingIncident))
+    }
+}
+
+impl Display for DronState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DronState::ExpectingToRecvIncident => "Esperando incidentes",
+            DronState::RespondingToIncident => "Evaluando incidente",
+            DronState::MustRespondToIncident => "Asignado al incidente",
+            DronState::Flying => "Volando",
+            DronState::Mantainance => "En mantenimiento",
+            DronState::ManagingIncident => "Atendiendo incidente",
+            DronState::IncidentResolved => "Incidente resuelto",
+            DronState::GoingToMaintenance => "Yendo a mantenimiento",
+            DronState::Charging => "Cargando batería",
+            DronState::GoingToIncident => "Yendo al incidente",
+            DronState::ReturningToBase => "Volviendo a la base",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DronState;
+
+    #[test]
+    fn test_1_dron_state_to_and_from_bytes_conserva_los_valores() {
+        for (state, byte) in [
+            (DronState::ExpectingToRecvIncident, 1),
+            (DronState::RespondingToIncident, 2),
+            (DronState::MustRespondToIncident, 3),
+            (DronState::Flying, 4),
+            (DronState::Mantainance, 5),
+            (DronState::ManagingIncident, 6),
+            (DronState::IncidentResolved, 7),
+            (DronState::GoingToMaintenance, 8),
+            (DronState::Charging, 9),
+            (DronState::GoingToIncident, 10),
+            (DronState::ReturningToBase, 11),
+        ] {
+            assert_eq!(state.to_byte(), [byte]);
+            assert_eq!(DronState::from_byte([byte]).unwrap(), state);
+        }
+        assert!(DronState::from_byte([0]).is_err());
+    }
+
+    #[test]
+    fn test_2_solo_se_permiten_las_transiciones_del_ciclo_de_vida() {
+        let lifecycle = [
+            DronState::ExpectingToRecvIncident,
+            DronState::RespondingToIncident,
+            DronState::MustRespondToIncident,
+            DronState::GoingToIncident,
+            DronState::ManagingIncident,
+            DronState::ReturningToBase,
+            DronState::ExpectingToRecvIncident,
+        ];
+        for pair in lifecycle.windows(2) {
+            assert!(pair[0].can_transition_to(pair[1]));
+        }
+        assert!(DronState::Flying.can_transition_to(DronState::Mantainance));
+        assert!(DronState::Mantainance.can_transition_to(DronState::ManagingIncident));
+        assert!(DronState::ManagingIncident.can_transition_to(DronState::GoingToMaintenance));
+        assert!(DronState::GoingToMaintenance.can_transition_to(DronState::Charging));
+        assert!(DronState::Charging.can_transition_to(DronState::ExpectingToRecvIncident));
+        assert!(DronState::ExpectingToRecvIncident.can_transition_to(DronState::GoingToIncident));
+        assert!(DronState::GoingToIncident.can_transition_to(DronState::ReturningToBase));
+        assert!(DronState::ReturningToBase.can_transition_to(DronState::Charging));
+
+        assert!(!DronState::ExpectingToRecvIncident.can_transition_to(DronState::ManagingIncident));
+        assert!(!DronState::RespondingToIncident.can_transition_to(DronState::Flying));
+        assert!(!DronState::Flying.can_transition_to(DronState::RespondingToIncident));
+        assert!(!DronState::ExpectingToRecvIncident.can_transition_to(DronState::Charging));
+        assert!(!DronState::ReturningToBase.can_transition_to(DronState::ManagingIncident));
+        assert!(!DronState::RespondingToIncident.can_transition_to(DronState::GoingToIncident));
+    }
+
+    #[test]
+    fn test_3_display_muestra_el_nombre_del_estado() {
+        assert_eq!(DronState::Mantainance.to_string(), "En mantenimiento");
+        assert_eq!(DronState::ManagingIncident.to_string(), "Atendiendo incidente");
+    }
 }
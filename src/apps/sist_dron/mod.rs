@@ -5,6 +5,8 @@ pub mod dron;
 pub mod dron_current_info;
 pub mod dron_flying_info;
 pub mod dron_logic;
+#[cfg(any(test, feature = "simulation"))]
+pub mod dron_simulation;
 pub mod dron_state;
 pub mod sist_dron_properties;
 pub mod utils;
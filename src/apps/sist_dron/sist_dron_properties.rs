@@ -2,6 +2,9 @@ use std::io::{Error, ErrorKind};
 
 use super::super::properties::Properties;
 
+/// Radio de llegada por defecto, en metros, si no se configura `arrival_radius_m`.
+const DEFAULT_ARRIVAL_RADIUS_M: f64 = 100.0;
+
 #[derive(Debug, PartialEq, Clone, Copy)]
 pub struct SistDronProperties {
     max_battery_lvl: u8,
@@ -16,6 +19,8 @@ pub struct SistDronProperties {
     mantainance_lon: f64,
     // Velocidad de vuelo, en km/h
     speed: f64,
+    // Distancia (en metros) al destino, a partir de la cual se considera que el dron llegó
+    arrival_radius_m: f64,
 }
 
 impl SistDronProperties {
@@ -118,6 +123,20 @@ impl SistDronProperties {
             return Err(Error::new(ErrorKind::Other, "Falta propiedad sist dron."));
         }
 
+        // Opcional: un radio muy grande resuelve incidentes desde lejos, y uno muy chico hace oscilar al dron alrededor del destino
+        let arrival_radius_m: f64 = match global_properties.get("arrival_radius_m") {
+            Some(prop) => prop
+                .parse()
+                .map_err(|_| Error::new(ErrorKind::InvalidInput, "arrival_radius_m"))?,
+            None => DEFAULT_ARRIVAL_RADIUS_M,
+        };
+        if !(arrival_radius_m.is_finite() && arrival_radius_m > 0.0) {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "arrival_radius_m debe ser un número positivo de metros.",
+            ));
+        }
+
         Ok(Self {
             max_battery_lvl,
             min_operational_battery_lvl,
@@ -131,6 +150,7 @@ impl SistDronProperties {
             mantainance_lon,
 
             speed,
+            arrival_radius_m,
         })
     }
 
@@ -159,6 +179,11 @@ impl SistDronProperties {
         self.speed
     }
 
+    /// Devuelve la distancia al destino, en metros, a partir de la cual se considera que el dron llegó
+    pub fn get_arrival_radius(&self) -> f64 {
+        self.arrival_radius_m
+    }
+
     pub fn set_range_center_position(&mut self, lat_inicial: f64, lon_inicial: f64) {
         self.range_center_lat = lat_inicial;
         self.range_center_lon = lon_inicial;
@@ -6,6 +6,8 @@ use std::{
     net::SocketAddr,
 };
 
+use crate::mqtt::mqtt_utils::socket_address::parse_socket_addr;
+
 /// Lee y devuelve, de los argumentos ingresados al correr el programa,
 /// el id del dron, y la IP y el puerto del servidor al que el cliente se va a conectar.
 fn load_id_lat_long_ip_and_port() -> Result<(u8, f64, f64, String, u16), Error> {
@@ -40,13 +42,10 @@ fn load_id_lat_long_ip_and_port() -> Result<(u8, f64, f64, String, u16), Error>
 
 /// Construye y devuelve la broker_address necesaria para conectarse al servidor mqtt,
 /// a partir de los argumentos recibidos de id, latitud, longitud, ip y puerto.
-/// También devuelve la latitud y longitud.
+/// También devuelve la latitud y longitud. La ip puede ser IPv4 o IPv6 (ej. `[::1]`).
 pub fn get_id_lat_long_and_broker_address() -> Result<(u8, f64, f64, SocketAddr), Error> {
     let (id, latitud, longitud, ip, puerto) = load_id_lat_long_ip_and_port()?;
-    let addr: String = format!("{}:{}", ip, puerto);
-    let broker_addr = addr
-        .parse()
-        .map_err(|_| Error::new(ErrorKind::InvalidInput, "Dirección no válida"))?;
+    let broker_addr = parse_socket_addr(&ip, puerto)?;
 
     Ok((id, latitud, longitud, broker_addr))
 }
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+
+use egui::{ColorImage, TextureHandle, TextureOptions};
+
+use crate::apps::sist_camaras::camera_frame::CameraFrame;
+
+/// Ventana de la UI que muestra el último frame recibido de la cámara seleccionada.
+/// Solamente se dibuja si fue habilitada desde el menú.
+pub struct CameraFrameWindow {
+    latest_frames: HashMap<u8, CameraFrame>,
+    selected_cam_id: Option<u8>,
+    open: bool,
+    // Textura del frame mostrado, junto con el id de la cámara y el timestamp del frame del que se creó
+    texture: Option<(u8, u64, TextureHandle)>,
+}
+
+impl CameraFrameWindow {
+    pub fn new() -> Self {
+        Self {
+            latest_frames: HashMap::new(),
+            selected_cam_id: None,
+            open: false,
+            texture: None,
+        }
+    }
+
+    /// Guarda el `frame` como el último de su cámara, si es más nuevo que el que había.
+    pub fn add_frame(&mut self, frame: CameraFrame) {
+        let cam_id = frame.get_cam_id();
+        let is_newest = match self.latest_frames.get(&cam_id) {
+            Some(latest) => latest.get_timestamp() <= frame.get_timestamp(),
+            None => true,
+        };
+        if is_newest {
+            self.latest_frames.insert(cam_id, frame);
+        }
+        if self.selected_cam_id.is_none() {
+            self.selected_cam_id = Some(cam_id);
+        }
+    }
+
+    /// Devuelve el último frame recibido de la cámara `cam_id`, si hay alguno.
+    pub fn get_latest_frame(&self, cam_id: u8) -> Option<&CameraFrame> {
+        self.latest_frames.get(&cam_id)
+    }
+
+    /// Descarta los frames de la cámara `cam_id` (ej. al desconectarse).
+    pub fn remove_camera(&mut self, cam_id: u8) {
+        self.latest_frames.remove(&cam_id);
+        if self.selected_cam_id == Some(cam_id) {
+            self.selected_cam_id = None;
+            self.texture = None;
+        }
+    }
+
+    /// Descarta los frames de todas las cámaras (ej. al desconectarse Sistema Cámaras).
+    pub fn clear(&mut self) {
+        self.latest_frames.clear();
+        self.selected_cam_id = None;
+        self.texture = None;
+    }
+
+    /// Agrega al menú la opción para mostrar u ocultar la ventana.
+    pub fn menu(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.open, "Frames de cámaras");
+    }
+
+    /// Dibuja la ventana con el selector de cámara y su último frame, si está abierta.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        if !self.open {
+            return;
+        }
+
+        let mut open = self.open;
+        egui::Window::new("Frames de cámaras")
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                self.camera_selector(ui);
+                ui.separator();
+                self.show_selected_frame(ui, ctx);
+            });
+        self.open = open;
+    }
+
+    fn camera_selector(&mut self, ui: &mut egui::Ui) {
+        let mut cam_ids: Vec<u8> = self.latest_frames.keys().copied().collect();
+        cam_ids.sort();
+        let selected_text = match self.selected_cam_id {
+            Some(cam_id) => format!("Cámara {}", cam_id),
+            None => "-".to_string(),
+        };
+        egui::ComboBox::from_label("Cámara")
+            .selected_text(selected_text)
+            .show_ui(ui, |ui| {
+                for cam_id in cam_ids {
+                    ui.selectable_value(
+                        &mut self.selected_cam_id,
+                        Some(cam_id),
+                        format!("Cámara {}", cam_id),
+                    );
+                }
+            });
+    }
+
+    fn show_selected_frame(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let frame = match self.selected_cam_id.and_then(|id| self.latest_frames.get(&id)) {
+            Some(frame) => frame,
+            None => {
+                ui.label("No se recibieron frames.");
+                return;
+            }
+        };
+        ui.label(format!(
+            "{}x{}, timestamp: {} ms",
+            frame.get_width(),
+            frame.get_height(),
+            frame.get_timestamp()
+        ));
+
+        // Se decodifica la imagen solamente cuando cambia el frame a mostrar
+        let is_outdated = match &self.texture {
+            Some((cam_id, timestamp, _)) => {
+                *cam_id != frame.get_cam_id() || *timestamp != frame.get_timestamp()
+            }
+            None => true,
+        };
+        if is_outdated {
+            self.texture = decode_frame(frame).map(|image| {
+                let texture = ctx.load_texture("camera_frame", image, TextureOptions::default());
+                (frame.get_cam_id(), frame.get_timestamp(), texture)
+            });
+        }
+
+        match &self.texture {
+            Some((_, _, texture)) => {
+                ui.add(egui::Image::new(texture).shrink_to_fit());
+            }
+            None => {
+                ui.label("No se pudo decodificar la imagen.");
+            }
+        }
+    }
+}
+
+impl Default for CameraFrameWindow {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Decodifica la imagen (jpeg o png) del `frame`, a una imagen que egui puede mostrar.
+fn decode_frame(frame: &CameraFrame) -> Option<ColorImage> {
+    let image = image::load_from_memory(frame.get_image()).ok()?.to_rgba8();
+    let size = [image.width() as usize, image.height() as usize];
+    Some(ColorImage::from_rgba_unmultiplied(size, image.as_raw()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::CameraFrameWindow;
+    use crate::apps::sist_camaras::camera_frame::CameraFrame;
+
+    #[test]
+    fn test_1_conserva_el_ultimo_frame_de_cada_camara() {
+        let mut window = CameraFrameWindow::new();
+        window.add_frame(CameraFrame::new(1, vec![1]));
+        window.add_frame(CameraFrame::new(2, vec![2]));
+        window.add_frame(CameraFrame::new(1, vec![3]));
+
+        assert_eq!(window.get_latest_frame(1).unwrap().get_image(), &[3]);
+        assert_eq!(window.get_latest_frame(2).unwrap().get_image(), &[2]);
+
+        window.remove_camera(2);
+        assert!(window.get_latest_frame(2).is_none());
+    }
+}
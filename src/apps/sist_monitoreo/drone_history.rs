@@ -0,0 +1,104 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    time::Instant,
+};
+
+use crate::apps::sist_dron::dron_current_info::DronCurrentInfo;
+
+/// Cantidad de actualizaciones que se conservan por dron, si no se indica otra.
+pub const DEFAULT_DRONE_HISTORY_LEN: usize = 30;
+
+/// Historial acotado de las últimas actualizaciones recibidas de cada dron, junto con el instante
+/// en que se recibieron. Se utiliza para dibujar en el mapa el recorrido reciente de cada dron.
+#[derive(Debug)]
+pub struct DroneHistory {
+    max_len: usize,
+    history_by_dron: HashMap<u8, VecDeque<(Instant, DronCurrentInfo)>>,
+}
+
+impl DroneHistory {
+    /// Crea un historial que conserva como máximo `max_len` actualizaciones por dron.
+    pub fn new(max_len: usize) -> Self {
+        Self {
+            max_len,
+            history_by_dron: HashMap::new(),
+        }
+    }
+
+    /// Agrega la actualización `dron` al historial de su id, descartando la más antigua si ya estaba lleno.
+    pub fn add(&mut self, dron: DronCurrentInfo) {
+        let history = self.history_by_dron.entry(dron.get_id()).or_default();
+        if history.len() == self.max_len {
+            history.pop_front();
+        }
+        history.push_back((Instant::now(), dron));
+    }
+
+    /// Devuelve las actualizaciones guardadas del dron `dron_id`, de la más antigua a la más reciente.
+    pub fn get_history(&self, dron_id: u8) -> Vec<&(Instant, DronCurrentInfo)> {
+        match self.history_by_dron.get(&dron_id) {
+            Some(history) => history.iter().collect(),
+            None => vec![],
+        }
+    }
+
+    /// Devuelve, para cada dron, las posiciones (lat, lon) de su recorrido reciente.
+    pub fn get_trails(&self) -> Vec<Vec<(f64, f64)>> {
+        self.history_by_dron
+            .values()
+            .map(|history| {
+                history
+                    .iter()
+                    .map(|(_, dron)| dron.get_current_position())
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Devuelve la última actualización recibida de cada dron, ordenadas por id.
+    pub fn get_latest(&self) -> Vec<&DronCurrentInfo> {
+        let mut latest: Vec<&DronCurrentInfo> = self
+            .history_by_dron
+            .values()
+            .filter_map(|history| history.back().map(|(_, dron)| dron))
+            .collect();
+        latest.sort_by_key(|dron| dron.get_id());
+        latest
+    }
+
+    /// Elimina el historial del dron `dron_id` (ej. porque se desconectó).
+    pub fn clear(&mut self, dron_id: u8) {
+        self.history_by_dron.remove(&dron_id);
+    }
+
+    /// Elimina el historial de todos los drones.
+    pub fn clear_all(&mut self) {
+        self.history_by_dron.clear();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::DroneHistory;
+    use crate::apps::sist_dron::{dron_current_info::DronCurrentInfo, dron_state::DronState};
+
+    #[test]
+    fn test_1_historial_acotado_y_borrado_al_desconectarse() {
+        let mut history = DroneHistory::new(3);
+        for i in 0..5 {
+            let lat = -34.60 + (i as f64) * 0.001;
+            history.add(DronCurrentInfo::new(1, lat, -58.38, 100, DronState::Flying));
+        }
+        history.add(DronCurrentInfo::new(2, -34.61, -58.39, 100, DronState::Flying));
+
+        // Se conservan solamente las 3 últimas del dron 1, de la más antigua a la más reciente
+        let dron_1 = history.get_history(1);
+        assert_eq!(dron_1.len(), 3);
+        assert_eq!(dron_1[0].1.get_current_position(), (-34.60 + 0.002, -58.38));
+        assert_eq!(history.get_trails().len(), 2);
+
+        history.clear(1);
+        assert!(history.get_history(1).is_empty());
+        assert_eq!(history.get_trails().len(), 1);
+    }
+}
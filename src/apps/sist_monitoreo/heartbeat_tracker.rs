@@ -0,0 +1,131 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use crate::apps::{heartbeat::Heartbeat, properties::Properties};
+use crate::mqtt::mqtt_utils::will_message_utils::app_type::AppType;
+
+/// Identifica a una app que publica heartbeats: su tipo, y su id en el caso de los drones.
+pub type AppKey = (AppType, Option<u8>);
+
+/// Registra cuándo se recibió el último heartbeat de cada app, para detectar las que siguen conectadas
+/// pero dejaron de responder: una app se considera "sin respuesta" si no se recibe su heartbeat dentro de `stale_after`.
+#[derive(Debug)]
+pub struct HeartbeatTracker {
+    stale_after: Option<Duration>,
+    last_seen: HashMap<AppKey, Instant>,
+}
+
+impl HeartbeatTracker {
+    /// Crea el tracker. Con `stale_after` None, ninguna app se considera sin respuesta.
+    pub fn new(stale_after: Option<Duration>) -> Self {
+        Self {
+            stale_after,
+            last_seen: HashMap::new(),
+        }
+    }
+
+    /// Lee el intervalo de la propiedad `heartbeat_stale_secs`. Si es 0 o está ausente, no se detectan apps sin respuesta.
+    pub fn from_properties_file(properties_file: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => match properties
+                .get("heartbeat_stale_secs")
+                .map(|secs| secs.parse::<u64>())
+            {
+                Some(Ok(0)) | None => Self::new(None),
+                Some(Ok(secs)) => Self::new(Some(Duration::from_secs(secs))),
+                Some(Err(_)) => {
+                    println!("Propiedad 'heartbeat_stale_secs' inválida, no se detectarán apps sin respuesta.");
+                    Self::new(None)
+                }
+            },
+            Err(e) => {
+                println!("Error al leer el intervalo de heartbeats: {:?}", e);
+                Self::new(None)
+            }
+        }
+    }
+
+    /// Registra que se recibió el `heartbeat` en el instante `now`.
+    pub fn record(&mut self, heartbeat: &Heartbeat, now: Instant) {
+        self.last_seen
+            .insert((heartbeat.get_app_type(), heartbeat.get_id()), now);
+    }
+
+    /// Deja de seguir a la app, ej. porque se recibió su will al desconectarse.
+    pub fn forget(&mut self, app: AppKey) {
+        self.last_seen.remove(&app);
+    }
+
+    /// Devuelve las apps cuyo último heartbeat se recibió hace `stale_after` o más, ordenadas.
+    pub fn get_stale_apps(&self, now: Instant) -> Vec<AppKey> {
+        let stale_after = match self.stale_after {
+            Some(stale_after) => stale_after,
+            None => return vec![],
+        };
+        let mut stale: Vec<AppKey> = self
+            .last_seen
+            .iter()
+            .filter(|(_, last_seen)| now.saturating_duration_since(**last_seen) >= stale_after)
+            .map(|(app, _)| *app)
+            .collect();
+        stale.sort_by_key(|(app_type, id)| (app_type.to_str(), *id));
+        stale
+    }
+
+    /// Devuelve cuánto falta para que la próxima app quede sin respuesta, o None si no hay ninguna por quedar.
+    pub fn time_until_next_stale(&self, now: Instant) -> Option<Duration> {
+        let stale_after = self.stale_after?;
+        self.last_seen
+            .values()
+            .map(|last_seen| stale_after.saturating_sub(now.saturating_duration_since(*last_seen)))
+            .filter(|remaining| !remaining.is_zero())
+            .min()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::HeartbeatTracker;
+    use crate::apps::heartbeat::Heartbeat;
+    use crate::mqtt::mqtt_utils::will_message_utils::app_type::AppType;
+
+    #[test]
+    fn test_1_una_app_sin_heartbeat_dentro_del_intervalo_queda_sin_respuesta() {
+        let mut tracker = HeartbeatTracker::new(Some(Duration::from_secs(10)));
+        let start = Instant::now();
+        tracker.record(&Heartbeat::new(AppType::Dron, Some(1)), start);
+        tracker.record(&Heartbeat::new(AppType::Cameras, None), start + Duration::from_secs(5));
+
+        assert!(tracker.get_stale_apps(start + Duration::from_secs(9)).is_empty());
+        assert_eq!(
+            tracker.time_until_next_stale(start + Duration::from_secs(9)),
+            Some(Duration::from_secs(1))
+        );
+        assert_eq!(
+            tracker.get_stale_apps(start + Duration::from_secs(10)),
+            vec![(AppType::Dron, Some(1))]
+        );
+
+        // Al recibir un nuevo heartbeat, o al desconectarse, deja de estar sin respuesta
+        tracker.record(&Heartbeat::new(AppType::Dron, Some(1)), start + Duration::from_secs(10));
+        assert!(tracker.get_stale_apps(start + Duration::from_secs(12)).is_empty());
+        tracker.forget((AppType::Cameras, None));
+        assert_eq!(
+            tracker.get_stale_apps(start + Duration::from_secs(60)),
+            vec![(AppType::Dron, Some(1))]
+        );
+    }
+
+    #[test]
+    fn test_2_sin_intervalo_configurado_ninguna_app_queda_sin_respuesta() {
+        let mut tracker = HeartbeatTracker::new(None);
+        let start = Instant::now();
+        tracker.record(&Heartbeat::new(AppType::Dron, Some(1)), start);
+        assert!(tracker.get_stale_apps(start + Duration::from_secs(3600)).is_empty());
+        assert_eq!(tracker.time_until_next_stale(start), None);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::HashMap;
+
+use crate::apps::{
+    incident_data::{incident::Incident, incident_info::IncidentInfo},
+    properties::Properties,
+};
+
+/// De-duplicación de los incidentes recibidos: un incidente del mismo origen que otro aún no resuelto, y a no más
+/// de `radius_meters` de él (ej. el mismo hecho detectado por dos cámaras), se considera el mismo incidente.
+/// No se lo muestra en el mapa, sino que se lo fusiona en el existente: al cerrarse éste, se cierran también
+/// los fusionados en él, para que quienes los reportaron (ej. las cámaras) los liberen.
+#[derive(Debug)]
+pub struct IncidentDedup {
+    radius_meters: Option<f64>,
+    merged: HashMap<IncidentInfo, Vec<Incident>>, // por incidente conservado, los duplicados fusionados en él
+}
+
+impl IncidentDedup {
+    /// Crea la de-duplicación. Con `radius_meters` None, no se de-duplican incidentes.
+    pub fn new(radius_meters: Option<f64>) -> Self {
+        Self {
+            radius_meters,
+            merged: HashMap::new(),
+        }
+    }
+
+    /// Lee el radio de la propiedad `incident_dedup_radius_m`. Si es 0 o está ausente, no se de-duplican incidentes.
+    pub fn from_properties_file(properties_file: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => match properties
+                .get("incident_dedup_radius_m")
+                .map(|radius| radius.parse::<f64>())
+            {
+                Some(Ok(radius)) if radius >= 0.0 => Self::new(Some(radius).filter(|radius| *radius > 0.0)),
+                None => Self::new(None),
+                Some(_) => {
+                    println!("Propiedad 'incident_dedup_radius_m' inválida, no se de-duplicarán incidentes.");
+                    Self::new(None)
+                }
+            },
+            Err(e) => {
+                println!("Error al leer la de-duplicación de incidentes: {:?}", e);
+                Self::new(None)
+            }
+        }
+    }
+
+    /// Devuelve el incidente de `existing` del que `incident` es duplicado: uno distinto, no resuelto, del mismo
+    /// origen, y a no más del radio configurado. Si hay varios, el más cercano.
+    pub fn find_duplicate_of<'a, I>(&self, incident: &Incident, existing: I) -> Option<&'a Incident>
+    where
+        I: IntoIterator<Item = &'a Incident>,
+    {
+        let radius_meters = self.radius_meters?;
+        existing
+            .into_iter()
+            .filter(|other| {
+                other.get_info() != incident.get_info()
+                    && other.get_source() == incident.get_source()
+                    && !other.is_closed()
+            })
+            .map(|other| (other, other.distance_to(incident.position())))
+            .filter(|(_, distance)| *distance <= radius_meters)
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .map(|(other, _)| other)
+    }
+
+    /// Fusiona el incidente `duplicate` en el incidente conservado `kept`.
+    pub fn merge(&mut self, kept: IncidentInfo, duplicate: Incident) {
+        self.merged.entry(kept).or_default().push(duplicate);
+    }
+
+    /// Devuelve si el `incident` ya se fusionó en otro (ej. si se lo vuelve a recibir).
+    /// Como los ids se reutilizan, se lo compara también por su posición: un incidente nuevo que reutilice
+    /// el id de un duplicado no se confunde con él.
+    pub fn is_merged(&self, incident: &Incident) -> bool {
+        self.merged
+            .values()
+            .any(|duplicates| duplicates.iter().any(|duplicate| is_same_incident(duplicate, incident)))
+    }
+
+    /// Olvida el `incident`, si se lo había fusionado en otro, al recibirse que se lo cerró
+    /// (ej. lo resolvió la cámara que lo reportó), para que su id pueda volver a usarse.
+    pub fn forget_merged(&mut self, incident: &Incident) {
+        for duplicates in self.merged.values_mut() {
+            duplicates.retain(|duplicate| !is_same_incident(duplicate, incident));
+        }
+        self.merged.retain(|_, duplicates| !duplicates.is_empty());
+    }
+
+    /// Quita y devuelve los incidentes fusionados en `kept`, ya cerrado, cerrados como él (resueltos o cancelados).
+    pub fn take_merged_closed_like(&mut self, kept: &Incident) -> Vec<Incident> {
+        let mut duplicates = self.merged.remove(&kept.get_info()).unwrap_or_default();
+        for duplicate in duplicates.iter_mut() {
+            if kept.is_cancelled() {
+                duplicate.set_cancelled();
+            } else {
+                duplicate.set_resolved();
+            }
+        }
+        duplicates
+    }
+}
+
+/// Devuelve si `a` y `b` son el mismo incidente: mismo id y origen, y misma posición.
+fn is_same_incident(a: &Incident, b: &Incident) -> bool {
+    a.get_info() == b.get_info() && a.get_position() == b.get_position()
+}
+
+#[cfg(test)]
+mod test {
+    use super::IncidentDedup;
+    use crate::apps::incident_data::{incident::Incident, incident_source::IncidentSource};
+
+    #[test]
+    fn test_1_dos_incidentes_a_un_metro_producen_un_solo_marcador_y_se_cierran_juntos() {
+        let mut dedup = IncidentDedup::new(Some(10.0));
+        // 1 metro al norte (un grado de latitud son unos 111 km)
+        let first = Incident::new(1, (-34.6037, -58.3816), IncidentSource::Automated);
+        let second = Incident::new(2, (-34.6037 + 1.0 / 111_320.0, -58.3816), IncidentSource::Automated);
+        let manual = Incident::new(3, (-34.6037, -58.3816), IncidentSource::Manual);
+
+        // Como al recibirlos en la ui: solamente se muestran los que no son duplicados de otro
+        let mut shown: Vec<Incident> = vec![];
+        for incident in [first.clone(), second.clone(), manual] {
+            match dedup.find_duplicate_of(&incident, shown.iter()).map(|kept| kept.get_info()) {
+                Some(kept) => dedup.merge(kept, incident),
+                None => shown.push(incident),
+            }
+        }
+        assert_eq!(shown.len(), 2);
+        assert!(dedup.is_merged(&second));
+
+        let mut resolved = first.clone();
+        resolved.set_resolved();
+        let closed = dedup.take_merged_closed_like(&resolved);
+        assert_eq!(closed.len(), 1);
+        assert_eq!(closed[0].get_info(), second.get_info());
+        assert!(closed[0].is_resolved());
+
+        // Sin radio no se de-duplica
+        assert!(IncidentDedup::new(None).find_duplicate_of(&second, [&first]).is_none());
+    }
+
+    #[test]
+    fn test_2_un_duplicado_cerrado_se_olvida_y_su_id_puede_reutilizarse() {
+        let mut dedup = IncidentDedup::new(Some(10.0));
+        let first = Incident::new(1, (-34.6037, -58.3816), IncidentSource::Automated);
+        let second = Incident::new(2, (-34.6037 + 1.0 / 111_320.0, -58.3816), IncidentSource::Automated);
+        dedup.merge(first.get_info(), second.clone());
+
+        // Un incidente nuevo que reutiliza el id en otra posición no es el duplicado
+        let reused = Incident::new(2, (-34.7, -58.5), IncidentSource::Automated);
+        assert!(dedup.is_merged(&second));
+        assert!(!dedup.is_merged(&reused));
+
+        // Al cerrarse el duplicado, se lo olvida
+        let mut closed = second.clone();
+        closed.set_resolved();
+        dedup.forget_merged(&closed);
+        assert!(!dedup.is_merged(&second));
+        assert!(dedup.take_merged_closed_like(&first).is_empty());
+    }
+}
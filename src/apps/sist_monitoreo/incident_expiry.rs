@@ -0,0 +1,78 @@
+use std::time::{Duration, Instant};
+
+use crate::apps::properties::Properties;
+
+/// Criterio de vencimiento de los incidentes que no llegan a resolverse (ej. porque no hay drones disponibles).
+/// Un incidente no resuelto luego de `timeout` desde su creación, se da por vencido:
+/// la UI lo marca como resuelto, lo republica para que las cámaras lo liberen, y lo quita del mapa.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IncidentExpiry {
+    timeout: Option<Duration>,
+}
+
+impl IncidentExpiry {
+    /// Crea el criterio. Con `timeout` None, los incidentes no vencen nunca.
+    pub fn new(timeout: Option<Duration>) -> Self {
+        Self { timeout }
+    }
+
+    /// Lee el timeout de la propiedad `incident_expiry_timeout_secs`. Si es 0 o está ausente, no vencen nunca.
+    pub fn from_properties_file(properties_file: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => match properties
+                .get("incident_expiry_timeout_secs")
+                .map(|secs| secs.parse::<u64>())
+            {
+                Some(Ok(0)) | None => Self::new(None),
+                Some(Ok(secs)) => Self::new(Some(Duration::from_secs(secs))),
+                Some(Err(_)) => {
+                    println!("Propiedad 'incident_expiry_timeout_secs' inválida, los incidentes no vencerán.");
+                    Self::new(None)
+                }
+            },
+            Err(e) => {
+                println!("Error al leer el vencimiento de incidentes: {:?}", e);
+                Self::new(None)
+            }
+        }
+    }
+
+    /// Devuelve si el incidente creado en `created_at` ya venció.
+    pub fn is_expired(&self, created_at: Instant, now: Instant) -> bool {
+        match self.timeout {
+            Some(timeout) => now.saturating_duration_since(created_at) >= timeout,
+            None => false,
+        }
+    }
+
+    /// Devuelve cuánto falta para que venza el incidente creado en `created_at`, o None si no vence.
+    pub fn time_until_expiry(&self, created_at: Instant, now: Instant) -> Option<Duration> {
+        self.timeout
+            .map(|timeout| timeout.saturating_sub(now.saturating_duration_since(created_at)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::IncidentExpiry;
+
+    #[test]
+    fn test_1_el_incidente_vence_pasado_el_timeout() {
+        let expiry = IncidentExpiry::new(Some(Duration::from_secs(60)));
+        let created_at = Instant::now();
+
+        assert!(!expiry.is_expired(created_at, created_at + Duration::from_secs(59)));
+        assert!(expiry.is_expired(created_at, created_at + Duration::from_secs(60)));
+        assert_eq!(
+            expiry.time_until_expiry(created_at, created_at + Duration::from_secs(45)),
+            Some(Duration::from_secs(15))
+        );
+
+        // Sin timeout no vence nunca
+        let never = IncidentExpiry::new(None);
+        assert!(!never.is_expired(created_at, created_at + Duration::from_secs(3600)));
+        assert!(never.time_until_expiry(created_at, created_at).is_none());
+    }
+}
@@ -0,0 +1,149 @@
+use egui::Color32;
+
+use crate::apps::{
+    incident_data::incident_status::IncidentStatus, properties::Properties, vendor::Style,
+};
+
+/// Símbolo y color con que se muestra un incidente en el mapa.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IncidentMarker {
+    pub symbol: char,
+    pub color: Color32,
+}
+
+impl IncidentMarker {
+    pub fn new(symbol: char, color: Color32) -> Self {
+        Self { symbol, color }
+    }
+
+    /// Parsea un marcador de la forma `<símbolo>,<r>,<g>,<b>` (ej. `⚠,255,0,0`).
+    fn parse(value: &str) -> Option<Self> {
+        let fields: Vec<&str> = value.split(',').map(|field| field.trim()).collect();
+        if fields.len() != 4 {
+            return None;
+        }
+        let mut symbol_chars = fields[0].chars();
+        let symbol = match (symbol_chars.next(), symbol_chars.next()) {
+            (Some(symbol), None) => symbol,
+            _ => return None,
+        };
+        let r = fields[1].parse::<u8>().ok()?;
+        let g = fields[2].parse::<u8>().ok()?;
+        let b = fields[3].parse::<u8>().ok()?;
+        Some(Self::new(symbol, Color32::from_rgb(r, g, b)))
+    }
+}
+
+/// Marcador con que se muestra un incidente en el mapa, según el status de su ciclo de vida: por defecto,
+/// rojo al abrirse, naranja al asignarse, y amarillo mientras se resuelve. Cada uno puede configurarse con
+/// la propiedad `incident_marker_<status>` (ej. `incident_marker_assigned=⚠,255,165,0`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncidentMarkerStyles {
+    open: IncidentMarker,
+    assigned: IncidentMarker,
+    resolving: IncidentMarker,
+    resolved: IncidentMarker,
+    cancelled: IncidentMarker,
+}
+
+impl IncidentMarkerStyles {
+    /// Lee los marcadores del archivo de properties. Ante una propiedad ausente o inválida
+    /// se usa el marcador por defecto de ese status.
+    pub fn from_properties_file(properties_file: &str) -> Self {
+        let mut styles = Self::default();
+        let properties = match Properties::new(properties_file) {
+            Ok(properties) => properties,
+            Err(e) => {
+                println!("Error al leer los marcadores de incidentes: {:?}", e);
+                return styles;
+            }
+        };
+        for (status, marker) in styles.markers_mut() {
+            let key = format!("incident_marker_{}", status);
+            if let Some(value) = properties.get(&key) {
+                match IncidentMarker::parse(value) {
+                    Some(parsed) => *marker = parsed,
+                    None => println!("Propiedad '{}' inválida, se usa el valor por defecto.", key),
+                }
+            }
+        }
+        styles
+    }
+
+    /// Devuelve el marcador del `status`.
+    pub fn get(&self, status: IncidentStatus) -> IncidentMarker {
+        match status {
+            IncidentStatus::Open => self.open,
+            IncidentStatus::Assigned => self.assigned,
+            IncidentStatus::Resolving => self.resolving,
+            IncidentStatus::Resolved => self.resolved,
+            IncidentStatus::Cancelled => self.cancelled,
+        }
+    }
+
+    /// Devuelve el símbolo y el `Style` del `Place` de un incidente en el `status`.
+    pub fn symbol_and_style(&self, status: IncidentStatus) -> (char, Style) {
+        let marker = self.get(status);
+        let style = Style {
+            symbol_color: marker.color,
+            ..Default::default()
+        };
+        (marker.symbol, style)
+    }
+
+    fn markers_mut(&mut self) -> [(&str, &mut IncidentMarker); 5] {
+        [
+            ("open", &mut self.open),
+            ("assigned", &mut self.assigned),
+            ("resolving", &mut self.resolving),
+            ("resolved", &mut self.resolved),
+            ("cancelled", &mut self.cancelled),
+        ]
+    }
+}
+
+impl Default for IncidentMarkerStyles {
+    fn default() -> Self {
+        Self {
+            open: IncidentMarker::new('⚠', Color32::from_rgb(255, 0, 0)),
+            assigned: IncidentMarker::new('⚠', Color32::from_rgb(255, 165, 0)),
+            resolving: IncidentMarker::new('⚠', Color32::from_rgb(255, 215, 0)),
+            resolved: IncidentMarker::new('✔', Color32::from_rgb(0, 160, 0)),
+            cancelled: IncidentMarker::new('✖', Color32::from_gray(120)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use egui::Color32;
+
+    use super::{IncidentMarker, IncidentMarkerStyles};
+    use crate::apps::incident_data::incident_status::IncidentStatus;
+
+    #[test]
+    fn test_1_cada_status_tiene_su_marcador_y_los_configurados_reemplazan_a_los_por_defecto() {
+        let defaults = IncidentMarkerStyles::default();
+        assert_ne!(defaults.get(IncidentStatus::Open), defaults.get(IncidentStatus::Assigned));
+        assert_ne!(defaults.get(IncidentStatus::Assigned), defaults.get(IncidentStatus::Resolving));
+
+        let file = std::env::temp_dir().join("test_incident_marker_styles_1.properties");
+        fs::write(
+            &file,
+            "incident_marker_open=🔥,200,0,0\nincident_marker_assigned=no es un marcador\nincident_marker_resolving=⚠,1,2",
+        )
+        .unwrap();
+        let styles = IncidentMarkerStyles::from_properties_file(file.to_str().unwrap());
+        let _ = fs::remove_file(&file);
+
+        assert_eq!(styles.get(IncidentStatus::Open), IncidentMarker::new('🔥', Color32::from_rgb(200, 0, 0)));
+        // Las propiedades inválidas no reemplazan al marcador por defecto
+        assert_eq!(styles.get(IncidentStatus::Assigned), defaults.get(IncidentStatus::Assigned));
+        assert_eq!(styles.get(IncidentStatus::Resolving), defaults.get(IncidentStatus::Resolving));
+
+        let (symbol, style) = styles.symbol_and_style(IncidentStatus::Open);
+        assert_eq!((symbol, style.symbol_color), ('🔥', Color32::from_rgb(200, 0, 0)));
+    }
+}
@@ -0,0 +1,136 @@
+use std::{
+    fmt::Debug,
+    fs::OpenOptions,
+    io::{Error, ErrorKind, Write},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::apps::{incident_data::incident::Incident, properties::Properties};
+
+/// Recibe los eventos de creación y resolución de incidentes del Sistema Monitoreo, para que integraciones
+/// externas (ej. un webhook, un archivo que lee otro sistema) puedan enterarse de ellos.
+/// Se lo invoca desde el hilo de la UI, por lo que las implementaciones no deberían bloquear por mucho tiempo.
+pub trait IncidentNotifier: Debug + Send + Sync {
+    /// Se invoca al crearse un incidente que este monitor va a resolver.
+    fn on_created(&self, inc: &Incident);
+    /// Se invoca al resolverse un incidente de este monitor.
+    fn on_resolved(&self, inc: &Incident);
+}
+
+/// Notificador compartido entre el Sistema Monitoreo y su UI.
+pub type ShIncidentNotifier = Arc<dyn IncidentNotifier>;
+
+/// Notificador que no hace nada. Es el que se usa si no se configura ninguno.
+#[derive(Debug)]
+pub struct NullIncidentNotifier;
+
+impl IncidentNotifier for NullIncidentNotifier {
+    fn on_created(&self, _inc: &Incident) {}
+
+    fn on_resolved(&self, _inc: &Incident) {}
+}
+
+/// Evento escrito por el `FileIncidentNotifier`.
+#[derive(Debug, Serialize)]
+struct IncidentEvent<'a> {
+    event: &'a str,
+    timestamp_secs: u64,
+    incident: &'a Incident,
+}
+
+/// Notificador de ejemplo, que agrega cada evento como una línea json al final de un archivo
+/// (ej. `{"event":"resolved","timestamp_secs":...,"incident":{...}}`), para que otro sistema lo procese.
+#[derive(Debug)]
+pub struct FileIncidentNotifier {
+    file: String,
+}
+
+impl FileIncidentNotifier {
+    pub fn new(file: String) -> Self {
+        Self { file }
+    }
+
+    fn append_event(&self, event: &str, inc: &Incident) -> Result<(), Error> {
+        let timestamp_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let event = IncidentEvent {
+            event,
+            timestamp_secs,
+            incident: inc,
+        };
+        let line = serde_json::to_string(&event).map_err(|e| Error::new(ErrorKind::InvalidData, e))?;
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.file)?;
+        writeln!(file, "{}", line)
+    }
+
+    fn notify(&self, event: &str, inc: &Incident) {
+        if let Err(e) = self.append_event(event, inc) {
+            println!(
+                "ERROR: no se pudo notificar el incidente {} en {}: {:?}",
+                inc.get_id(),
+                self.file,
+                e
+            );
+        }
+    }
+}
+
+impl IncidentNotifier for FileIncidentNotifier {
+    fn on_created(&self, inc: &Incident) {
+        self.notify("created", inc);
+    }
+
+    fn on_resolved(&self, inc: &Incident) {
+        self.notify("resolved", inc);
+    }
+}
+
+/// Lee del archivo de properties el archivo al que se notifican los incidentes (`incident_notifications_file`).
+/// Si la propiedad está ausente o vacía, no se notifica.
+pub fn incident_notifier_from_properties_file(properties_file: &str) -> ShIncidentNotifier {
+    match Properties::new(properties_file) {
+        Ok(properties) => match properties.get("incident_notifications_file") {
+            Some(file) if !file.trim().is_empty() => Arc::new(FileIncidentNotifier::new(file.to_string())),
+            _ => Arc::new(NullIncidentNotifier),
+        },
+        Err(e) => {
+            println!("Error al leer la configuración de notificación de incidentes: {:?}", e);
+            Arc::new(NullIncidentNotifier)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{FileIncidentNotifier, IncidentNotifier};
+    use crate::apps::incident_data::{incident::Incident, incident_source::IncidentSource};
+
+    #[test]
+    fn test_1_se_agrega_una_linea_json_por_cada_evento_del_incidente() {
+        let file = std::env::temp_dir().join("test_incident_notifier_1.jsonl");
+        let _ = fs::remove_file(&file);
+        let notifier = FileIncidentNotifier::new(file.to_str().unwrap().to_string());
+        let mut incident = Incident::new(3, (-34.6037, -58.3816), IncidentSource::Manual);
+
+        notifier.on_created(&incident);
+        incident.set_resolved();
+        notifier.on_resolved(&incident);
+
+        let content = fs::read_to_string(&file).unwrap();
+        let _ = fs::remove_file(&file);
+        let events: Vec<serde_json::Value> = content
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0]["event"], "created");
+        assert_eq!(events[1]["event"], "resolved");
+        assert_eq!(events[1]["incident"]["id"], 3);
+    }
+}
@@ -0,0 +1,156 @@
+use std::time::{Duration, Instant};
+
+use crate::apps::{incident_data::incident_source::IncidentSource, properties::Properties};
+
+/// Cantidad de drones que, por defecto, deben llegar a un incidente para considerarlo resuelto.
+pub const DEFAULT_REQUIRED_DRONES: usize = 2;
+
+/// Criterios a aplicar a los incidentes de un mismo `IncidentSource`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SourcePolicy {
+    /// Cantidad de drones que deben llegar al incidente para considerarlo resuelto.
+    required_drones: usize,
+    /// Tiempo que se espera, desde que se recibe el incidente, antes de mostrarlo en el mapa.
+    confirmation_delay: Duration,
+}
+
+impl SourcePolicy {
+    pub fn new(required_drones: usize, confirmation_delay: Duration) -> Self {
+        Self {
+            required_drones,
+            confirmation_delay,
+        }
+    }
+
+    pub fn get_required_drones(&self) -> usize {
+        self.required_drones
+    }
+
+    pub fn get_confirmation_delay(&self) -> Duration {
+        self.confirmation_delay
+    }
+}
+
+/// Criterios de confirmación y resolución de incidentes, configurables por `IncidentSource`.
+///
+/// Los incidentes `Automated` (detectados por IA en sistema cámaras) pueden requerir una cantidad
+/// distinta de drones, y esperar un tiempo de confirmación antes de mostrarse, para reducir falsos positivos.
+/// Los incidentes `Manual` los da de alta el operador desde la propia UI, por lo que se consideran
+/// confirmados: se muestran de inmediato, ignorando cualquier tiempo de confirmación configurado.
+/// Lo mismo los `Drone`, que los reporta un dron que está en el lugar.
+#[derive(Debug, Clone, PartialEq)]
+pub struct IncidentSourcePolicy {
+    manual: SourcePolicy,
+    automated: SourcePolicy,
+    drone: SourcePolicy,
+}
+
+impl IncidentSourcePolicy {
+    pub fn new(manual_required_drones: usize, automated: SourcePolicy) -> Self {
+        Self {
+            manual: SourcePolicy::new(manual_required_drones, Duration::ZERO),
+            automated,
+            drone: SourcePolicy::new(DEFAULT_REQUIRED_DRONES, Duration::ZERO),
+        }
+    }
+
+    /// Requiere `required_drones` drones para resolver los incidentes reportados por drones.
+    pub fn with_drone_required_drones(mut self, required_drones: usize) -> Self {
+        self.drone = SourcePolicy::new(required_drones, Duration::ZERO);
+        self
+    }
+
+    /// Lee los criterios del archivo de properties; las propiedades ausentes toman sus valores por defecto:
+    /// `manual_required_drones`, `automated_required_drones`, `automated_confirmation_delay_ms`
+    /// y `drone_required_drones`.
+    pub fn from_properties_file(properties_file: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => {
+                let read = |key: &str, default: u64| -> u64 {
+                    match properties.get(key).map(|value| value.parse::<u64>()) {
+                        Some(Ok(value)) => value,
+                        Some(Err(_)) => {
+                            println!("Propiedad '{}' inválida, se usa {}.", key, default);
+                            default
+                        }
+                        None => default,
+                    }
+                };
+                let manual_required_drones =
+                    read("manual_required_drones", DEFAULT_REQUIRED_DRONES as u64) as usize;
+                let automated = SourcePolicy::new(
+                    read("automated_required_drones", DEFAULT_REQUIRED_DRONES as u64) as usize,
+                    Duration::from_millis(read("automated_confirmation_delay_ms", 0)),
+                );
+                let drone_required_drones =
+                    read("drone_required_drones", DEFAULT_REQUIRED_DRONES as u64) as usize;
+                Self::new(manual_required_drones, automated).with_drone_required_drones(drone_required_drones)
+            }
+            Err(e) => {
+                println!("Error al leer criterios de incidentes, se usan los por defecto: {:?}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Devuelve los criterios correspondientes al `source` recibido.
+    pub fn get_policy_for(&self, source: &IncidentSource) -> &SourcePolicy {
+        match source {
+            IncidentSource::Manual => &self.manual,
+            IncidentSource::Automated => &self.automated,
+            IncidentSource::Drone => &self.drone,
+        }
+    }
+
+    /// Devuelve si un incidente de origen `source`, recibido en `received_at`, ya puede mostrarse.
+    pub fn is_confirmed(&self, source: &IncidentSource, received_at: Instant) -> bool {
+        match source {
+            IncidentSource::Manual | IncidentSource::Drone => true,
+            IncidentSource::Automated => {
+                received_at.elapsed() >= self.automated.get_confirmation_delay()
+            }
+        }
+    }
+
+    /// Devuelve si la cantidad de drones `drones_at_incident` alcanza para resolver un incidente de origen `source`.
+    pub fn is_resolved_by(&self, source: &IncidentSource, drones_at_incident: usize) -> bool {
+        drones_at_incident >= self.get_policy_for(source).get_required_drones()
+    }
+}
+
+impl Default for IncidentSourcePolicy {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_REQUIRED_DRONES,
+            SourcePolicy::new(DEFAULT_REQUIRED_DRONES, Duration::ZERO),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{IncidentSourcePolicy, SourcePolicy};
+    use crate::apps::incident_data::incident_source::IncidentSource;
+
+    #[test]
+    fn test_1_automated_espera_confirmacion_y_manual_no() {
+        let policy = IncidentSourcePolicy::new(2, SourcePolicy::new(3, Duration::from_secs(60)));
+        let now = Instant::now();
+
+        // El manual se muestra de inmediato, el automático debe esperar su tiempo de confirmación
+        assert!(policy.is_confirmed(&IncidentSource::Manual, now));
+        assert!(!policy.is_confirmed(&IncidentSource::Automated, now));
+
+        // Cada origen requiere su propia cantidad de drones para resolverse
+        assert!(policy.is_resolved_by(&IncidentSource::Manual, 2));
+        assert!(!policy.is_resolved_by(&IncidentSource::Automated, 2));
+        assert!(policy.is_resolved_by(&IncidentSource::Automated, 3));
+
+        // El reportado por un dron no espera confirmación, y tiene su propia cantidad de drones
+        let policy = policy.with_drone_required_drones(1);
+        assert!(policy.is_confirmed(&IncidentSource::Drone, now));
+        assert!(policy.is_resolved_by(&IncidentSource::Drone, 1));
+    }
+}
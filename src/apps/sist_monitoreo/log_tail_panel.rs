@@ -0,0 +1,145 @@
+use std::{collections::VecDeque, sync::mpsc::Receiver};
+
+/// Cantidad máxima de líneas del log que se conservan para mostrar.
+pub const DEFAULT_LOG_TAIL_LEN: usize = 200;
+
+/// Filtro a aplicar sobre las líneas mostradas del log.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogLevelFilter {
+    Todos,
+    SoloErrores,
+}
+
+impl LogLevelFilter {
+    /// Devuelve si la `line` debe mostrarse con este filtro.
+    /// Los logs no tienen nivel explícito, por lo que se considera error a toda línea que mencione "error".
+    pub fn accepts(&self, line: &str) -> bool {
+        match self {
+            LogLevelFilter::Todos => true,
+            LogLevelFilter::SoloErrores => line.to_lowercase().contains("error"),
+        }
+    }
+
+    fn to_str(self) -> &'static str {
+        match self {
+            LogLevelFilter::Todos => "Todos",
+            LogLevelFilter::SoloErrores => "Solo errores",
+        }
+    }
+}
+
+/// Panel inferior de la UI que muestra las últimas líneas escritas por el `StringLogger`.
+/// Solamente se dibuja si fue habilitado desde el menú.
+#[derive(Debug)]
+pub struct LogTailPanel {
+    log_rx: Option<Receiver<String>>,
+    lines: VecDeque<String>,
+    max_lines: usize,
+    open: bool,
+    autoscroll: bool,
+    filter: LogLevelFilter,
+}
+
+impl LogTailPanel {
+    /// Crea el panel, que recibirá por `log_rx` las líneas del log (si es None, no mostrará nada).
+    pub fn new(log_rx: Option<Receiver<String>>, max_lines: usize) -> Self {
+        Self {
+            log_rx,
+            lines: VecDeque::new(),
+            max_lines,
+            open: false,
+            autoscroll: true,
+            filter: LogLevelFilter::Todos,
+        }
+    }
+
+    /// Recibe, sin bloquear, las líneas nuevas del log, conservando solamente las últimas `max_lines`.
+    pub fn receive_new_lines(&mut self) {
+        if let Some(log_rx) = &self.log_rx {
+            while let Ok(line) = log_rx.try_recv() {
+                if self.lines.len() == self.max_lines {
+                    self.lines.pop_front();
+                }
+                self.lines.push_back(line);
+            }
+        }
+    }
+
+    /// Devuelve las líneas guardadas que pasan el filtro actual.
+    pub fn get_filtered_lines(&self) -> Vec<&String> {
+        self.lines
+            .iter()
+            .filter(|line| self.filter.accepts(line))
+            .collect()
+    }
+
+    pub fn set_filter(&mut self, filter: LogLevelFilter) {
+        self.filter = filter;
+    }
+
+    /// Agrega al menú la opción para mostrar u ocultar el panel.
+    pub fn menu(&mut self, ui: &mut egui::Ui) {
+        ui.checkbox(&mut self.open, "Log");
+    }
+
+    /// Dibuja el panel inferior con las líneas del log, si está abierto.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.receive_new_lines();
+        if !self.open {
+            return;
+        }
+
+        egui::TopBottomPanel::bottom("log_tail_panel")
+            .resizable(true)
+            .default_height(150.0)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.checkbox(&mut self.autoscroll, "Autoscroll");
+                    egui::ComboBox::from_label("Filtro")
+                        .selected_text(self.filter.to_str())
+                        .show_ui(ui, |ui| {
+                            for filter in [LogLevelFilter::Todos, LogLevelFilter::SoloErrores] {
+                                ui.selectable_value(&mut self.filter, filter, filter.to_str());
+                            }
+                        });
+                });
+                ui.separator();
+
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, false])
+                    .stick_to_bottom(self.autoscroll)
+                    .show(ui, |ui| {
+                        for line in self.get_filtered_lines() {
+                            ui.monospace(line);
+                        }
+                    });
+            });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::{LogLevelFilter, LogTailPanel};
+
+    #[test]
+    fn test_1_conserva_las_ultimas_lineas_y_filtra_errores() {
+        let (tx, rx) = mpsc::channel::<String>();
+        let mut panel = LogTailPanel::new(Some(rx), 3);
+
+        tx.send("linea 1".to_string()).unwrap();
+        tx.send("Error al conectar".to_string()).unwrap();
+        tx.send("linea 3".to_string()).unwrap();
+        tx.send("linea 4".to_string()).unwrap();
+        panel.receive_new_lines();
+
+        assert_eq!(
+            panel.get_filtered_lines(),
+            vec!["Error al conectar", "linea 3", "linea 4"]
+        );
+
+        panel.set_filter(LogLevelFilter::SoloErrores);
+        assert_eq!(panel.get_filtered_lines(), vec!["Error al conectar"]);
+    }
+}
@@ -0,0 +1,121 @@
+use std::time::{Duration, Instant};
+
+use crate::apps::properties::Properties;
+
+/// Variable de entorno de la que se lee el token de Mapbox.
+pub const MAPBOX_TOKEN_ENV_VAR: &str = "MAPBOX_ACCESS_TOKEN";
+
+/// Cada cuánto se vuelve a leer el token, si no se configura otro intervalo.
+pub const DEFAULT_MAPBOX_TOKEN_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Token de acceso a Mapbox, leído en tiempo de ejecución: de la variable de entorno `MAPBOX_ACCESS_TOKEN`
+/// o, si no está definida, de la propiedad `mapbox_access_token`. Se vuelve a leer periódicamente (ver `refresh`),
+/// para que la UI agregue o quite los providers de Mapbox según esté disponible o no.
+#[derive(Debug)]
+pub struct MapboxToken {
+    properties_file: String,
+    env_var: &'static str,
+    check_interval: Duration,
+    last_check: Instant,
+    token: Option<String>,
+}
+
+impl MapboxToken {
+    /// Lee el token actual, que se volverá a leer cada `check_interval`.
+    pub fn new(properties_file: &str, check_interval: Duration) -> Self {
+        let mut mapbox_token = Self {
+            properties_file: properties_file.to_string(),
+            env_var: MAPBOX_TOKEN_ENV_VAR,
+            check_interval,
+            last_check: Instant::now(),
+            token: None,
+        };
+        mapbox_token.token = mapbox_token.read_token();
+        mapbox_token
+    }
+
+    /// Devuelve el token, o None si no está disponible.
+    pub fn get(&self) -> Option<&str> {
+        self.token.as_deref()
+    }
+
+    /// Si pasó el intervalo desde la última lectura, vuelve a leer el token.
+    /// Devuelve si cambió, en cuyo caso hay que actualizar los providers de Mapbox.
+    pub fn refresh(&mut self, now: Instant) -> bool {
+        if now.saturating_duration_since(self.last_check) < self.check_interval {
+            return false;
+        }
+        self.last_check = now;
+        let token = self.read_token();
+        if token == self.token {
+            return false;
+        }
+        self.token = token;
+        true
+    }
+
+    fn read_token(&self) -> Option<String> {
+        let configured = match Properties::new(&self.properties_file) {
+            Ok(properties) => properties.get("mapbox_access_token").cloned(),
+            Err(_) => None,
+        };
+        select_token(std::env::var(self.env_var).ok(), configured)
+    }
+}
+
+/// Elige el token de la variable de entorno o, si no está, el configurado. Un token vacío no se considera.
+fn select_token(env_token: Option<String>, configured: Option<String>) -> Option<String> {
+    let is_not_empty = |token: &String| !token.trim().is_empty();
+    env_token
+        .filter(is_not_empty)
+        .or(configured.filter(is_not_empty))
+        .map(|token| token.trim().to_string())
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        time::{Duration, Instant},
+    };
+
+    use super::{select_token, MapboxToken};
+
+    #[test]
+    fn test_1_la_variable_de_entorno_tiene_prioridad_y_un_token_vacio_no_esta_disponible() {
+        let env = Some("pk.env".to_string());
+        let configured = Some("pk.config".to_string());
+
+        assert_eq!(select_token(env.clone(), configured.clone()), env);
+        assert_eq!(select_token(Some(" ".to_string()), configured.clone()), configured);
+        assert_eq!(select_token(None, Some(String::new())), None);
+    }
+
+    #[test]
+    fn test_2_el_token_se_vuelve_a_leer_luego_del_intervalo() {
+        let file = std::env::temp_dir().join("test_mapbox_token_2.properties");
+        let file_str = file.to_str().unwrap();
+        fs::write(&file, "mapbox_access_token=pk.uno").unwrap();
+        let start = Instant::now();
+        let mut mapbox_token = MapboxToken {
+            properties_file: file_str.to_string(),
+            env_var: "TEST_MAPBOX_TOKEN_2_NO_DEFINIDA",
+            check_interval: Duration::from_secs(5),
+            last_check: start,
+            token: None,
+        };
+        mapbox_token.token = mapbox_token.read_token();
+        assert_eq!(mapbox_token.get(), Some("pk.uno"));
+
+        // Antes del intervalo no se vuelve a leer
+        fs::write(&file, "otra=1").unwrap();
+        assert!(!mapbox_token.refresh(start + Duration::from_secs(1)));
+        assert_eq!(mapbox_token.get(), Some("pk.uno"));
+
+        // Luego, deja de estar disponible
+        let changed = mapbox_token.refresh(start + Duration::from_secs(6));
+        let _ = fs::remove_file(&file);
+        assert!(changed);
+        assert_eq!(mapbox_token.get(), None);
+    }
+}
@@ -0,0 +1,126 @@
+use crate::apps::{properties::Properties, vendor::Place};
+
+/// Cantidad de decimales con que se muestran las coordenadas en los labels, si no se configura otra.
+const DEFAULT_COORDINATE_DECIMALS: usize = 2;
+
+/// Desplazamiento mínimo, en metros, para volver a dibujar un marcador, si no se configura otro.
+const DEFAULT_MIN_MOVEMENT_METERS: f64 = 5.0;
+
+/// Precisión con que se muestran los marcadores que se mueven (ej. los drones), para reducir el parpadeo
+/// en el mapa: las coordenadas de los labels se redondean a `coordinate_decimals` decimales, y un marcador
+/// que se desplazó menos de `min_movement_meters` (y cuyo label no cambió) no se vuelve a dibujar.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarkerPrecision {
+    coordinate_decimals: usize,
+    min_movement_meters: f64,
+}
+
+impl MarkerPrecision {
+    pub fn new(coordinate_decimals: usize, min_movement_meters: f64) -> Self {
+        Self {
+            coordinate_decimals,
+            min_movement_meters,
+        }
+    }
+
+    /// Lee los decimales de la propiedad `marker_coordinate_decimals`, y el desplazamiento mínimo de
+    /// `marker_min_movement_m` (con 0 se redibuja ante cualquier desplazamiento). Ante una propiedad ausente
+    /// o inválida se usa el valor por defecto.
+    pub fn from_properties_file(properties_file: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => {
+                let coordinate_decimals = match properties
+                    .get("marker_coordinate_decimals")
+                    .map(|decimals| decimals.parse::<usize>())
+                {
+                    Some(Ok(decimals)) => decimals,
+                    None => DEFAULT_COORDINATE_DECIMALS,
+                    Some(Err(_)) => {
+                        println!("Propiedad 'marker_coordinate_decimals' inválida, se usa el valor por defecto.");
+                        DEFAULT_COORDINATE_DECIMALS
+                    }
+                };
+                let min_movement_meters = match properties
+                    .get("marker_min_movement_m")
+                    .map(|meters| meters.parse::<f64>())
+                {
+                    Some(Ok(meters)) if meters >= 0.0 => meters,
+                    None => DEFAULT_MIN_MOVEMENT_METERS,
+                    Some(_) => {
+                        println!("Propiedad 'marker_min_movement_m' inválida, se usa el valor por defecto.");
+                        DEFAULT_MIN_MOVEMENT_METERS
+                    }
+                };
+                Self::new(coordinate_decimals, min_movement_meters)
+            }
+            Err(e) => {
+                println!("Error al leer la precisión de los marcadores: {:?}", e);
+                Self::default()
+            }
+        }
+    }
+
+    /// Formatea la coordenada `value` con los decimales configurados, para mostrarla en un label.
+    pub fn format_coordinate(&self, value: f64) -> String {
+        format!("{:.*}", self.coordinate_decimals, value)
+    }
+
+    /// Devuelve si hay que reemplazar el marcador `previous` por `updated`: si no había uno, si cambió
+    /// lo que se muestra de él, o si se desplazó al menos el mínimo configurado.
+    pub fn should_redraw(&self, previous: Option<&Place>, updated: &Place) -> bool {
+        match previous {
+            Some(previous) => {
+                previous.label != updated.label
+                    || previous.symbol != updated.symbol
+                    || previous.position.distance_meters(&updated.position) >= self.min_movement_meters
+            }
+            None => true,
+        }
+    }
+}
+
+impl Default for MarkerPrecision {
+    fn default() -> Self {
+        Self::new(DEFAULT_COORDINATE_DECIMALS, DEFAULT_MIN_MOVEMENT_METERS)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MarkerPrecision;
+    use crate::apps::{
+        place_type::PlaceType,
+        vendor::{Place, Position, Style},
+    };
+
+    fn create_dron_place(lat: f64, lon: f64, label: &str) -> Place {
+        Place {
+            position: Position::from_lon_lat(lon, lat),
+            label: label.to_string(),
+            symbol: '🚁',
+            style: Style::default(),
+            id: 1,
+            place_type: PlaceType::Dron,
+        }
+    }
+
+    #[test]
+    fn test_1_el_marcador_se_redibuja_solamente_si_se_movio_lo_suficiente_o_cambio_su_label() {
+        let precision = MarkerPrecision::new(2, 5.0);
+        let previous = create_dron_place(-34.60900, -58.38730, "Dron 1");
+
+        // Menos de un metro: no se redibuja
+        let jitter = create_dron_place(-34.609001, -58.38730, "Dron 1");
+        assert!(!precision.should_redraw(Some(&previous), &jitter));
+        // Unos 11 metros
+        let moved = create_dron_place(-34.60910, -58.38730, "Dron 1");
+        assert!(precision.should_redraw(Some(&previous), &moved));
+        // Cambió su estado
+        let relabeled = create_dron_place(-34.609001, -58.38730, "Dron 1\n   Manejando incidente");
+        assert!(precision.should_redraw(Some(&previous), &relabeled));
+        assert!(precision.should_redraw(None, &jitter));
+
+        assert_eq!(precision.format_coordinate(0.70812), "0.71");
+        assert_eq!(MarkerPrecision::new(0, 0.0).format_coordinate(-0.70812), "-1");
+    }
+}
@@ -1,5 +1,18 @@
+pub mod camera_frame_window;
+pub mod drone_history;
+pub mod heartbeat_tracker;
+pub mod incident_dedup;
+pub mod incident_expiry;
+pub mod incident_marker_styles;
+pub mod incident_notifier;
+pub mod incident_source_policy;
+pub mod log_tail_panel;
+pub mod mapbox_token;
+pub mod marker_precision;
 pub mod monitoreo_errors;
 pub mod order_checker;
 pub mod sist_monit_ui_properties;
 pub mod sistema_monitoreo;
+pub mod state_exporter;
+pub mod subscription_filter;
 pub mod ui_sistema_monitoreo; //
\ No newline at end of file
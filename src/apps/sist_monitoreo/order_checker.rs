@@ -35,7 +35,7 @@ impl OrderChecker {
                 self.update_timestamp_if_newest(msg_topic, id, recvd_timestamp)
             }
             AppsMqttTopics::CameraTopic => {
-                let camera = Camera::from_bytes(&payload);
+                let camera = Camera::from_bytes(&payload)?;
                 let id: u8 = camera.get_id();
                 self.update_timestamp_if_newest(msg_topic, id, recvd_timestamp)
             }
@@ -82,3 +82,39 @@ impl Default for OrderChecker {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::OrderChecker;
+    use crate::{
+        apps::{
+            apps_mqtt_topics::AppsMqttTopics,
+            sist_dron::{dron_current_info::DronCurrentInfo, dron_state::DronState},
+        },
+        mqtt::messages::{publish_flags::PublishFlags, publish_message::PublishMessage},
+    };
+
+    fn create_dron_publish(battery_lvl: u8) -> PublishMessage {
+        let dron = DronCurrentInfo::new(1, -34.6037, -58.3816, battery_lvl, DronState::ExpectingToRecvIncident);
+        PublishMessage::new(
+            PublishFlags::new(0, 0, 0).unwrap(),
+            AppsMqttTopics::DronTopic.to_str(),
+            None,
+            &dron.to_bytes(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_1_de_dos_mensajes_creados_seguidos_el_segundo_es_el_mas_nuevo() {
+        let mut order_checker = OrderChecker::new();
+        // Se crean sin demora entre ellos, por lo que podrían leer el mismo valor del reloj
+        let first = create_dron_publish(100);
+        let second = create_dron_publish(95);
+
+        assert!(order_checker.is_newest(&first).unwrap());
+        assert!(order_checker.is_newest(&second).unwrap());
+        // El primero, recibido tarde, ya no es el más nuevo
+        assert!(!order_checker.is_newest(&first).unwrap());
+    }
+}
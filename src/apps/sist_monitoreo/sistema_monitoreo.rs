@@ -1,26 +1,46 @@
 use std::{
-    io::{self, ErrorKind},
+    io::ErrorKind,
     sync::{mpsc, Arc, Mutex},
     thread::{self, JoinHandle},
 };
 
-use crate::mqtt::{client::mqtt_client::MQTTClient, messages::publish_message::PublishMessage};
+use crate::mqtt::{
+    client::{mqtt_client::MQTTClient, publisher::Publisher},
+    messages::publish_message::PublishMessage,
+    mqtt_utils::{
+        mqtt_error::MqttError,
+        shutdown_token::ShutdownToken,
+        topic::{topic_filters_with_qos, Topic, TopicFilter},
+        will_message_utils::app_type::AppType,
+    },
+};
 use crossbeam_channel::{unbounded, Receiver as CrossbeamReceiver, Sender as CrossbeamSender};
 use std::sync::mpsc::{Receiver as MpscReceiver, Sender as MpscSender};
 
 use crate::{
     apps::{
         apps_mqtt_topics::AppsMqttTopics,
-        common_clients::{exit_when_asked, there_are_no_more_publish_msgs},
+        common_clients::{disconnect, get_app_will_topic, there_are_no_more_publish_msgs},
+        config::AppConfig,
+        heartbeat::{spawn_heartbeat_thread, DEFAULT_HEARTBEAT_INTERVAL},
         incident_data::incident::Incident,
-        sist_monitoreo::{order_checker::OrderChecker, ui_sistema_monitoreo::UISistemaMonitoreo},
+        sist_camaras::types::shareable_publisher_type::ShPublisherType,
+        sist_monitoreo::{
+            incident_notifier::ShIncidentNotifier, order_checker::OrderChecker,
+            subscription_filter::SubscriptionRequest, ui_sistema_monitoreo::UISistemaMonitoreo,
+        },
     },
     logging::string_logger::StringLogger,
 };
 
-use std::fs;
 use std::io::Error;
 
+/// Archivos de configuración de Sistema Monitoreo (ver `AppConfig`). Los qos se indican en el segundo.
+pub const SIST_MONITOREO_CONFIG_FILES: [&str; 2] = [
+    "./sistema_monitoreo.properties",
+    "src/apps/sist_monitoreo/qos_sistema_monitoreo.properties",
+];
+
 /// Sistema encargado de permitir la publicación de incidentes, determinar su estado; recibir información
 /// sobre Cámaras, Drones, e Incidentes creados por el Sistema Cámaras, y mostrarla en una interfaz gráfica.
 #[derive(Debug)]
@@ -29,76 +49,99 @@ pub struct SistemaMonitoreo {
     qos: u8,
     logger: StringLogger,
     topics: Vec<(String, u8)>,
-}
-
-fn leer_qos_desde_archivo(ruta_archivo: &str) -> Result<u8, io::Error> {
-    let contenido = fs::read_to_string(ruta_archivo)?;
-    let inicio = contenido.find("qos=").ok_or(io::Error::new(
-        ErrorKind::NotFound,
-        "No se encontró la etiqueta 'qos='",
-    ))?;
-
-    let valor_qos = contenido[inicio + 4..].trim().parse::<u8>().map_err(|_| {
-        io::Error::new(
-            io::ErrorKind::InvalidData,
-            "El valor de QoS no es un número válido",
-        )
-    })?;
-    println!("Valor de QoS: {}", valor_qos);
-    Ok(valor_qos)
+    /// Topics a los que se está suscripto actualmente, ya que desde la UI pueden desactivarse algunos.
+    subscribed_topics: Arc<Mutex<Vec<(String, u8)>>>,
+    ui_ctx: Arc<Mutex<Option<egui::Context>>>,
+    notifier: ShIncidentNotifier,
 }
 
 impl SistemaMonitoreo {
-    /// Crea un Sistema Monitoreo.
-    pub fn new(logger: StringLogger) -> Self {
-        let qos =
-            leer_qos_desde_archivo("src/apps/sist_monitoreo/qos_sistema_monitoreo.properties")
-                .unwrap_or(0);
+    /// Crea un Sistema Monitoreo. El `notifier` recibe los eventos de creación y resolución de incidentes.
+    /// Se suscribe a cada topic, y publica los incidentes, con el qos indicado en `config`.
+    pub fn new(logger: StringLogger, notifier: ShIncidentNotifier, config: &AppConfig) -> Self {
+        let qos = config.get_qos_for(AppsMqttTopics::IncidentTopic.to_str());
         println!("valor de QoS: {}", qos);
-        let topics = vec![
-            (AppsMqttTopics::CameraTopic.to_str().to_string(), qos),
-            (AppsMqttTopics::DronTopic.to_str().to_string(), qos),
-            (AppsMqttTopics::IncidentTopic.to_str().to_string(), qos),
-            (AppsMqttTopics::DescTopic.to_str().to_string(), qos),
-        ];
+        let topics = [
+            AppsMqttTopics::CameraTopic.to_str().to_string(),
+            AppsMqttTopics::DronTopic.to_str().to_string(),
+            AppsMqttTopics::IncidentTopic.to_str().to_string(),
+            get_app_will_topic().to_str().to_string(),
+            AppsMqttTopics::CameraFrameTopic.to_str().to_string(),
+        ]
+        .into_iter()
+        .map(|topic| {
+            let topic_qos = config.get_qos_for(&topic);
+            (topic, topic_qos)
+        })
+        .collect();
         let sistema_monitoreo: SistemaMonitoreo = Self {
             incidents: Arc::new(Mutex::new(Vec::new())), // []
             qos,
             logger,
             topics,
+            subscribed_topics: Arc::new(Mutex::new(Vec::new())),
+            ui_ctx: Arc::new(Mutex::new(None)),
+            notifier,
         };
 
         sistema_monitoreo
     }
 
     /// Lanza las partes internas del sistema monitoreo y las inicializa.
+    /// Si se recibe `log_rx`, la UI podrá mostrar en un panel las líneas del log recibidas por él.
     pub fn spawn_threads(
         &self,
         publish_message_rx: MpscReceiver<PublishMessage>,
         mqtt_client: MQTTClient,
+        log_rx: Option<MpscReceiver<String>>,
     ) -> Vec<JoinHandle<()>> {
         let (incident_tx, incident_rx) = mpsc::channel::<Incident>();
         let (exit_tx, exit_rx) = mpsc::channel::<bool>();
+        let (subscription_tx, subscription_rx) = mpsc::channel::<SubscriptionRequest>();
 
         let mut children: Vec<JoinHandle<()>> = vec![];
-        let mqtt_client_sh = Arc::new(Mutex::new(mqtt_client));
+        // Token que se solicita al salir, y que observan todos los hilos para terminar
+        let shutdown_token = mqtt_client.get_shutdown_token();
+        let mqtt_client_sh: ShPublisherType = Arc::new(Mutex::new(Box::new(mqtt_client)));
         let (egui_tx, egui_rx) = unbounded::<PublishMessage>();
 
         // Exit, cuando ui lo solicite
-        children.push(self.spawn_exit_thread(mqtt_client_sh.clone(), exit_rx));
+        children.push(self.spawn_exit_thread(mqtt_client_sh.clone(), exit_rx, shutdown_token.clone()));
 
         // Recibe inc de la ui y hace publish
-        children.push(self.spawn_publish_incs_thread(mqtt_client_sh.clone(), incident_rx));
+        children.push(self.spawn_publish_incs_thread(
+            mqtt_client_sh.clone(),
+            incident_rx,
+            shutdown_token.clone(),
+        ));
+
+        // Se suscribe o desuscribe de los topics que se activen o desactiven desde la ui
+        children.push(self.spawn_subscription_filter_thread(
+            mqtt_client_sh.clone(),
+            subscription_rx,
+            shutdown_token.clone(),
+        ));
+
+        // Heartbeat, para que se detecte si la app deja de responder
+        children.push(spawn_heartbeat_thread(
+            mqtt_client_sh.clone(),
+            AppType::Monitoreo,
+            None,
+            DEFAULT_HEARTBEAT_INTERVAL,
+            shutdown_token.clone(),
+            self.logger.clone_ref(),
+        ));
 
         // Recibe msgs por MQTT y los envía para mostrarse en la ui
         children.push(self.spawn_subscribe_to_topics_thread(
             mqtt_client_sh.clone(),
             publish_message_rx,
             egui_tx,
+            shutdown_token,
         ));
 
         // UI
-        self.spawn_ui_thread(incident_tx, egui_rx, exit_tx);
+        self.spawn_ui_thread(incident_tx, egui_rx, exit_tx, subscription_tx, log_rx);
 
         children
     }
@@ -112,16 +155,29 @@ impl SistemaMonitoreo {
         incident_tx: MpscSender<Incident>,
         publish_message_rx: CrossbeamReceiver<PublishMessage>,
         exit_tx: MpscSender<bool>,
+        subscription_tx: MpscSender<SubscriptionRequest>,
+        log_rx: Option<MpscReceiver<String>>,
     ) {
+        let ui_ctx = self.ui_ctx.clone();
+        let notifier = self.notifier.clone();
+        let ui_logger = self.logger.clone_ref();
         if let Err(e) = eframe::run_native(
             "Sistema Monitoreo",
             Default::default(),
-            Box::new(|cc| {
+            Box::new(move |cc| {
+                // Se guarda el contexto, para que el hilo que recibe mensajes pueda pedir que se repinte la UI.
+                if let Ok(mut ui_ctx) = ui_ctx.lock() {
+                    *ui_ctx = Some(cc.egui_ctx.clone());
+                }
                 Box::new(UISistemaMonitoreo::new(
                     cc.egui_ctx.clone(),
                     incident_tx,
                     publish_message_rx,
                     exit_tx,
+                    subscription_tx,
+                    log_rx,
+                    notifier,
+                    ui_logger,
                 ))
             }),
         ) {
@@ -133,12 +189,13 @@ impl SistemaMonitoreo {
     /// Recibe incidente desde la UI, y lo publica por MQTT.
     fn spawn_publish_incs_thread(
         &self,
-        mqtt_client: Arc<Mutex<MQTTClient>>,
+        mqtt_client: ShPublisherType,
         rx: MpscReceiver<Incident>,
+        shutdown_token: ShutdownToken,
     ) -> JoinHandle<()> {
         let self_clone = self.clone_ref();
         thread::spawn(move || {
-            while let Ok(inc) = rx.recv() {
+            while let Some(inc) = shutdown_token.recv_unless_requested(&rx) {
                 self_clone
                     .logger
                     .log(format!("Sistema-Monitoreo: envío incidente: {:?}", inc));
@@ -147,12 +204,71 @@ impl SistemaMonitoreo {
         })
     }
 
+    /// Recibe de la UI los pedidos de activar o desactivar topics, y se suscribe o desuscribe de ellos por MQTT.
+    fn spawn_subscription_filter_thread(
+        &self,
+        mqtt_client: ShPublisherType,
+        rx: MpscReceiver<SubscriptionRequest>,
+        shutdown_token: ShutdownToken,
+    ) -> JoinHandle<()> {
+        let self_clone = self.clone_ref();
+        thread::spawn(move || {
+            while let Some(request) = shutdown_token.recv_unless_requested(&rx) {
+                if let Err(e) = self_clone.apply_subscription_request(&request, &mqtt_client) {
+                    self_clone.logger.log(format!(
+                        "ERROR: no se pudo aplicar el pedido {:?}: {:?}.",
+                        request, e
+                    ));
+                }
+            }
+        })
+    }
+
+    /// Se suscribe o desuscribe del topic del `request`, con el qos configurado para él,
+    /// y actualiza los topics a los que se está suscripto.
+    fn apply_subscription_request(
+        &self,
+        request: &SubscriptionRequest,
+        mqtt_client: &ShPublisherType,
+    ) -> Result<(), Error> {
+        let topic = match request {
+            SubscriptionRequest::Subscribe(topic) | SubscriptionRequest::Unsubscribe(topic) => topic,
+        };
+        let topic_with_qos = match self.topics.iter().find(|(t, _)| t == topic) {
+            Some(topic_with_qos) => topic_with_qos.clone(),
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("El topic {:?} no es uno de los de Sistema Monitoreo", topic),
+                ))
+            }
+        };
+
+        if let Ok(mut mqtt_client) = mqtt_client.lock() {
+            match request {
+                SubscriptionRequest::Subscribe(_) => mqtt_client.subscribe(topic_filters_with_qos(vec![topic_with_qos.clone()])?)?,
+                SubscriptionRequest::Unsubscribe(_) => mqtt_client.unsubscribe(vec![TopicFilter::try_from(topic.as_str())?])?,
+            }
+        }
+        if let Ok(mut subscribed_topics) = self.subscribed_topics.lock() {
+            subscribed_topics.retain(|(t, _)| t != topic);
+            if let SubscriptionRequest::Subscribe(_) = request {
+                subscribed_topics.push(topic_with_qos);
+            }
+            self.logger.log(format!("Suscripto a {:?}", *subscribed_topics));
+        }
+        Ok(())
+    }
+
     fn clone_ref(&self) -> Self {
         Self {
             incidents: self.incidents.clone(),
             qos: self.qos,
             logger: self.logger.clone_ref(),
             topics: self.topics.clone(),
+            subscribed_topics: self.subscribed_topics.clone(),
+            ui_ctx: self.ui_ctx.clone(),
+            notifier: self.notifier.clone(),
         }
     }
 
@@ -160,13 +276,14 @@ impl SistemaMonitoreo {
     /// Delega el procesamiento de cada mensaje recibido por MQTT a otra parte del Sistema Cámaras, enviándolo por un channel.
     fn spawn_subscribe_to_topics_thread(
         &self,
-        mqtt_client: Arc<Mutex<MQTTClient>>,
+        mqtt_client: ShPublisherType,
         mqtt_rx: MpscReceiver<PublishMessage>,
         egui_tx: CrossbeamSender<PublishMessage>,
+        shutdown_token: ShutdownToken,
     ) -> JoinHandle<()> {
         let mut self_clone = self.clone_ref();
         thread::spawn(move || {
-            if let Err(e) = self_clone.subscribe_and_receive_msgs(&mqtt_client, mqtt_rx, egui_tx) {
+            if let Err(e) = self_clone.subscribe_and_receive_msgs(&mqtt_client, mqtt_rx, egui_tx, &shutdown_token) {
                 self_clone.logger.log(format!(
                     "Error en hilo para suscribir y recibir mensajes de MQTT: {:?}.",
                     e
@@ -178,20 +295,24 @@ impl SistemaMonitoreo {
     /// Se suscribe a los topics de interés y permanece escuchando mensajes recibidos de los mismos.
     fn subscribe_and_receive_msgs(
         &mut self,
-        mqtt_client: &Arc<Mutex<MQTTClient>>,
+        mqtt_client: &ShPublisherType,
         mqtt_rx: MpscReceiver<PublishMessage>,
         egui_tx: CrossbeamSender<PublishMessage>,
+        shutdown_token: &ShutdownToken,
     ) -> Result<(), Error> {
         self.subscribe_to_topics(mqtt_client)?;
         self.logger.log(format!("Suscripto a {:?}", &self.topics));
-        self.receive_messages_from_subscribed_topics(mqtt_rx, egui_tx);
+        self.receive_messages_from_subscribed_topics(mqtt_rx, egui_tx, shutdown_token);
         Ok(())
     }
 
     /// Utiliza la librería MQTT para subscribirse a los topics.
-    fn subscribe_to_topics(&self, mqtt_client: &Arc<Mutex<MQTTClient>>) -> Result<(), Error> {
+    fn subscribe_to_topics(&self, mqtt_client: &ShPublisherType) -> Result<(), Error> {
         if let Ok(mut mqtt_client) = mqtt_client.lock() {
-            mqtt_client.mqtt_subscribe(self.topics.clone())?;
+            mqtt_client.subscribe(topic_filters_with_qos(self.topics.clone())?)?;
+            if let Ok(mut subscribed_topics) = self.subscribed_topics.lock() {
+                *subscribed_topics = self.topics.clone();
+            }
             Ok(())
         } else {
             Err(Error::new(
@@ -202,15 +323,16 @@ impl SistemaMonitoreo {
     }
 
     /// Si el mensaje publish recibido por MQTT es más nuevo que el último procesado, entonces
-    /// envía a otra parte del sistema de monitoreo, para ser procesado.
+    /// envía a otra parte del sistema de monitoreo, para ser procesado. Termina al solicitarse el `shutdown_token`.
     fn receive_messages_from_subscribed_topics(
         &mut self,
         mqtt_rx: MpscReceiver<PublishMessage>,
         egui_tx: CrossbeamSender<PublishMessage>,
+        shutdown_token: &ShutdownToken,
     ) {
         let mut time_order_checker = OrderChecker::new();
 
-        for pub_msg in mqtt_rx {
+        while let Some(pub_msg) = shutdown_token.recv_unless_requested(&mqtt_rx) {
             self.logger.log(format!("Publish recibido: {:?}", pub_msg));
             // Chequeo el timestamp del publish_msg, si es nuevo, lo mando a la ui
             // Uso un match, no quiero retornar si fue error xq cortaría el loop, solo lo loggueo
@@ -231,31 +353,73 @@ impl SistemaMonitoreo {
     ) {
         let res_send = egui_tx.send(msg);
         match res_send {
-            Ok(_) => println!("Enviado mensaje a la UI"),
+            Ok(_) => {
+                println!("Enviado mensaje a la UI");
+                self.request_ui_repaint();
+            }
             Err(e) => println!("Error al enviar mensaje a la UI: {:?}", e),
         }
     }
 
-    /// Hilo para salir desde la UI
+    /// Pide a la UI que se repinte, para que procese el mensaje recién enviado.
+    /// Si la UI todavía no se inició, no hace falta: lo procesará al dibujar su primer frame.
+    fn request_ui_repaint(&self) {
+        if let Ok(ui_ctx) = self.ui_ctx.lock() {
+            if let Some(ctx) = ui_ctx.as_ref() {
+                ctx.request_repaint();
+            }
+        }
+    }
+
+    /// Hilo para salir desde la UI. Al salir se desuscribe de los topics a los que esté suscripto y envía disconnect.
+    /// Luego se solicita el `shutdown_token` (aunque haya fallado el disconnect), para que terminen los demás hilos.
     fn spawn_exit_thread(
         &self,
-        mqtt_client: Arc<Mutex<MQTTClient>>,
+        mqtt_client: ShPublisherType,
         exit_rx: MpscReceiver<bool>,
+        shutdown_token: ShutdownToken,
     ) -> JoinHandle<()> {
+        let self_clone = self.clone_ref();
         thread::spawn(move || {
-            exit_when_asked(mqtt_client, exit_rx);
+            if let Ok(true) = exit_rx.recv() {
+                self_clone.unsubscribe_from_all_topics(&mqtt_client);
+                disconnect(&mqtt_client);
+            }
+            shutdown_token.request();
         })
     }
 
-    /// Utiliza la librería MQTT para publicar el `incident` al topic de incidentes.
-    fn publish_incident(&self, incident: Incident, mqtt_client: &Arc<Mutex<MQTTClient>>) {
+    /// Se desuscribe de todos los topics a los que esté suscripto.
+    fn unsubscribe_from_all_topics(&self, mqtt_client: &ShPublisherType) {
+        let topics: Vec<String> = match self.subscribed_topics.lock() {
+            Ok(mut subscribed_topics) => subscribed_topics.drain(..).map(|(topic, _)| topic).collect(),
+            Err(_) => return,
+        };
+        if topics.is_empty() {
+            return;
+        }
+        if let Ok(mut mqtt_client) = mqtt_client.lock() {
+            let res_unsubscribe = topics
+                .iter()
+                .map(|topic| TopicFilter::try_from(topic.as_str()))
+                .collect::<Result<Vec<TopicFilter>, MqttError>>()
+                .and_then(|filters| mqtt_client.unsubscribe(filters));
+            match res_unsubscribe {
+                Ok(_) => self.logger.log(format!("Desuscripto de {:?}", topics)),
+                Err(e) => self.logger.log(format!("ERROR: al desuscribirse de {:?}: {:?}", topics, e)),
+            }
+        }
+    }
+
+    /// Publica el `incident` al topic de incidentes, mediante el `mqtt_client`.
+    fn publish_incident(&self, incident: Incident, mqtt_client: &ShPublisherType) {
         println!("Publicando incidente...");
         self.logger.log("Publicando incidente...".to_string());
 
         // Hago el publish
         if let Ok(mut mqtt_client) = mqtt_client.lock() {
-            let res_publish = mqtt_client.mqtt_publish(
-                AppsMqttTopics::IncidentTopic.to_str(),
+            let res_publish = mqtt_client.publish(
+                &Topic::from(AppsMqttTopics::IncidentTopic),
                 &incident.to_bytes(),
                 self.get_qos(),
             );
@@ -271,3 +435,93 @@ impl SistemaMonitoreo {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::sync::{mpsc, Arc, Mutex};
+
+    use super::SistemaMonitoreo;
+    use crate::{
+        apps::{
+            apps_mqtt_topics::AppsMqttTopics,
+            config::AppConfig,
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+            sist_camaras::types::shareable_publisher_type::ShPublisherType,
+            sist_monitoreo::{
+                incident_notifier::NullIncidentNotifier, subscription_filter::SubscriptionRequest,
+            },
+        },
+        logging::string_logger::StringLogger,
+        mqtt::{client::publisher::NullPublisher, mqtt_utils::shutdown_token::ShutdownToken},
+    };
+
+    #[test]
+    fn test_1_el_incidente_recibido_de_la_ui_se_publica_una_vez_al_topic_de_incidentes() {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let sistema_monitoreo = SistemaMonitoreo::new(
+            StringLogger::new(logger_tx),
+            Arc::new(NullIncidentNotifier),
+            &AppConfig::default(),
+        );
+        let publisher = NullPublisher::new();
+        let publisher_sh: ShPublisherType = Arc::new(Mutex::new(Box::new(publisher.clone())));
+
+        let (incident_tx, incident_rx) = mpsc::channel::<Incident>();
+        let handle = sistema_monitoreo.spawn_publish_incs_thread(
+            publisher_sh,
+            incident_rx,
+            ShutdownToken::new(),
+        );
+        let incident = Incident::new(1, (-34.6037, -58.3816), IncidentSource::Manual);
+        incident_tx.send(incident.clone()).unwrap();
+        // Al cerrarse el channel, el hilo termina
+        drop(incident_tx);
+        handle.join().unwrap();
+
+        assert_eq!(publisher.get_published().len(), 1);
+        assert_eq!(
+            publisher.get_published_to(AppsMqttTopics::IncidentTopic.to_str()),
+            vec![incident.to_bytes()]
+        );
+    }
+
+    #[test]
+    fn test_2_los_topics_desactivados_desde_la_ui_se_desuscriben_y_al_salir_se_desuscriben_todos() {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let sistema_monitoreo = SistemaMonitoreo::new(
+            StringLogger::new(logger_tx),
+            Arc::new(NullIncidentNotifier),
+            &AppConfig::default(),
+        );
+        let publisher = NullPublisher::new();
+        let publisher_sh: ShPublisherType = Arc::new(Mutex::new(Box::new(publisher.clone())));
+        sistema_monitoreo.subscribe_to_topics(&publisher_sh).unwrap();
+        let all_topics = publisher.get_subscribed();
+
+        let (request_tx, request_rx) = mpsc::channel::<SubscriptionRequest>();
+        let handle = sistema_monitoreo.spawn_subscription_filter_thread(
+            publisher_sh.clone(),
+            request_rx,
+            ShutdownToken::new(),
+        );
+        request_tx.send(SubscriptionRequest::Unsubscribe("dron".to_string())).unwrap();
+        request_tx.send(SubscriptionRequest::Unsubscribe("cam".to_string())).unwrap();
+        request_tx.send(SubscriptionRequest::Subscribe("cam".to_string())).unwrap();
+        drop(request_tx);
+        handle.join().unwrap();
+
+        let subscribed: Vec<String> = publisher.get_subscribed().into_iter().map(|(topic, _)| topic).collect();
+        assert_eq!(subscribed.len(), all_topics.len() - 1);
+        assert!(!subscribed.contains(&"dron".to_string()));
+        assert!(subscribed.contains(&"cam".to_string()));
+
+        let (exit_tx, exit_rx) = mpsc::channel::<bool>();
+        let shutdown_token = ShutdownToken::new();
+        let handle = sistema_monitoreo.spawn_exit_thread(publisher_sh, exit_rx, shutdown_token.clone());
+        exit_tx.send(true).unwrap();
+        handle.join().unwrap();
+
+        assert!(publisher.get_subscribed().is_empty());
+        assert!(shutdown_token.is_requested());
+    }
+}
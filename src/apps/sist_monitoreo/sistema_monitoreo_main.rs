@@ -1,33 +1,60 @@
 use std::io::Error;
 
 use rustx::apps::{
-    common_clients::{get_broker_address, join_all_threads},
-    sist_monitoreo::sistema_monitoreo::SistemaMonitoreo,
+    common_clients::{
+        get_app_will_topic, get_broker_address_or, join_all_threads_with_timeout,
+        DEFAULT_SHUTDOWN_TIMEOUT,
+    },
+    config::AppConfig,
+    sist_monitoreo::{
+        incident_notifier::incident_notifier_from_properties_file,
+        sistema_monitoreo::{SistemaMonitoreo, SIST_MONITOREO_CONFIG_FILES},
+        ui_sistema_monitoreo::UI_PROPERTIES_FILE,
+    },
 };
 use rustx::logging::string_logger::StringLogger;
 use rustx::mqtt::client::mqtt_client::MQTTClient;
+use rustx::mqtt::mqtt_utils::will_message_utils::will_message::WillMessageData;
+use rustx::mqtt::mqtt_utils::will_message_utils::{app_type::AppType, will_content::WillContent};
 
 fn get_formatted_app_id() -> String {
     String::from("Sistema-Monitoreo")
 }
 
+fn get_app_will_msg_content() -> WillContent {
+    WillContent::new(AppType::Monitoreo, None)
+}
+
 fn main() -> Result<(), Error> {
-    let broker_addr = get_broker_address();
+    let config = AppConfig::from_properties_files_or_default(&SIST_MONITOREO_CONFIG_FILES);
+    let broker_addr = get_broker_address_or(config.get_broker_addr());
 
     // Se crean y configuran ambos extremos del string logger
-    let (mut logger, handle_logger) = StringLogger::create_logger(get_formatted_app_id());
+    let log_file = config.get_log_file(&get_formatted_app_id());
+    let (mut logger, handle_logger, log_rx) =
+        StringLogger::create_logger_with_ui_receiver_to_file(get_formatted_app_id(), log_file);
 
+    let qos = 1; // []
     let client_id = get_formatted_app_id();
-    let sistema_monitoreo = SistemaMonitoreo::new(logger.clone_ref());
-    match MQTTClient::mqtt_connect_to_broker(client_id, &broker_addr, None, logger.clone_ref()) {
-        Ok((mqtt_client, publish_message_rx, handle)) => {
+    let will_msg_content = get_app_will_msg_content();
+    let will_msg_data =
+        WillMessageData::for_app_topic(will_msg_content.to_str(), get_app_will_topic(), qos, 1)?;
+    let sistema_monitoreo = SistemaMonitoreo::new(
+        logger.clone_ref(),
+        incident_notifier_from_properties_file(UI_PROPERTIES_FILE),
+        &config,
+    );
+    match MQTTClient::mqtt_connect_to_broker(client_id, &broker_addr, Some(will_msg_data), logger.clone_ref()) {
+        Ok((mut mqtt_client, publish_message_rx, handle)) => {
             println!("Conectado al broker MQTT.");
             logger.log("Conectado al broker MQTT".to_string());
+            mqtt_client.set_publish_recording(config.get_record_publishes());
 
-            let mut handles = sistema_monitoreo.spawn_threads(publish_message_rx, mqtt_client);
+            let mut handles = sistema_monitoreo.spawn_threads(publish_message_rx, mqtt_client, Some(log_rx));
 
             handles.push(handle);
-            join_all_threads(handles);
+            // La UI ya se cerró: no se espera indefinidamente a un hilo que haya quedado bloqueado
+            join_all_threads_with_timeout(handles, DEFAULT_SHUTDOWN_TIMEOUT);
 
         }
         Err(e) => println!(
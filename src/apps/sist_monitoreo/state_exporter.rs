@@ -0,0 +1,170 @@
+use std::{
+    fs,
+    io::{Error, ErrorKind},
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use serde::Serialize;
+
+use crate::apps::{
+    incident_data::incident::Incident, properties::Properties, sist_camaras::camera::Camera,
+    sist_dron::dron_current_info::DronCurrentInfo,
+};
+
+/// Archivo al que se exporta el estado, si no se configura `state_snapshot_file`.
+const DEFAULT_SNAPSHOT_FILE: &str = "estado_sistema_monitoreo.json";
+
+/// Vista del sistema que tiene la UI en un instante: las cámaras, la última información de cada dron,
+/// y los incidentes sin resolver. Toma referencias a los datos de la UI, por lo que es consistente
+/// con lo que se está mostrando al momento de crearla.
+#[derive(Debug, Serialize)]
+pub struct StateSnapshot<'a> {
+    pub timestamp_secs: u64,
+    pub cameras: Vec<&'a Camera>,
+    pub drones: Vec<&'a DronCurrentInfo>,
+    pub incidents: Vec<&'a Incident>,
+}
+
+impl<'a> StateSnapshot<'a> {
+    pub fn new(
+        cameras: Vec<&'a Camera>,
+        drones: Vec<&'a DronCurrentInfo>,
+        incidents: Vec<&'a Incident>,
+    ) -> Self {
+        let timestamp_secs = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        Self {
+            timestamp_secs,
+            cameras,
+            drones,
+            incidents,
+        }
+    }
+
+    /// Devuelve el estado en formato json.
+    pub fn to_json(&self) -> Result<String, Error> {
+        serde_json::to_string_pretty(self).map_err(|e| Error::new(ErrorKind::InvalidData, e))
+    }
+}
+
+/// Exporta el estado del sistema a un archivo json, a pedido (desde el menú de la UI) o periódicamente.
+#[derive(Debug)]
+pub struct StateExporter {
+    file: String,
+    interval: Option<Duration>,
+    last_export: Instant,
+}
+
+impl StateExporter {
+    /// Crea el exportador. Con `interval` None, solamente se exporta a pedido.
+    pub fn new(file: String, interval: Option<Duration>) -> Self {
+        Self {
+            file,
+            interval,
+            last_export: Instant::now(),
+        }
+    }
+
+    /// Lee el archivo de la propiedad `state_snapshot_file`, y el intervalo de `state_snapshot_interval_secs`.
+    /// Si el intervalo es 0 o está ausente, no se exporta periódicamente.
+    pub fn from_properties_file(properties_file: &str) -> Self {
+        match Properties::new(properties_file) {
+            Ok(properties) => {
+                let file = properties
+                    .get("state_snapshot_file")
+                    .map_or(DEFAULT_SNAPSHOT_FILE.to_string(), |file| file.to_string());
+                let interval = match properties
+                    .get("state_snapshot_interval_secs")
+                    .map(|secs| secs.parse::<u64>())
+                {
+                    Some(Ok(0)) | None => None,
+                    Some(Ok(secs)) => Some(Duration::from_secs(secs)),
+                    Some(Err(_)) => {
+                        println!("Propiedad 'state_snapshot_interval_secs' inválida, no se exportará periódicamente.");
+                        None
+                    }
+                };
+                Self::new(file, interval)
+            }
+            Err(e) => {
+                println!("Error al leer la configuración de exportación del estado: {:?}", e);
+                Self::new(DEFAULT_SNAPSHOT_FILE.to_string(), None)
+            }
+        }
+    }
+
+    /// Devuelve si corresponde una exportación periódica.
+    pub fn is_due(&self, now: Instant) -> bool {
+        match self.interval {
+            Some(interval) => now.saturating_duration_since(self.last_export) >= interval,
+            None => false,
+        }
+    }
+
+    /// Devuelve cuánto falta para la próxima exportación periódica, o None si no se exporta periódicamente.
+    pub fn time_until_due(&self, now: Instant) -> Option<Duration> {
+        self.interval
+            .map(|interval| interval.saturating_sub(now.saturating_duration_since(self.last_export)))
+    }
+
+    /// Exporta el estado. El json se arma en el momento, y se escribe en el archivo desde otro hilo,
+    /// para no demorar el dibujado de la UI.
+    pub fn export(&mut self, snapshot: &StateSnapshot, now: Instant) -> Result<(), Error> {
+        self.last_export = now;
+        let json = snapshot.to_json()?;
+        let file = self.file.to_string();
+        thread::spawn(move || {
+            if let Err(e) = fs::write(&file, json) {
+                println!("Error al escribir el estado en {}: {:?}", file, e);
+            }
+        });
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::{Duration, Instant};
+
+    use super::{StateExporter, StateSnapshot};
+    use crate::apps::{
+        incident_data::{incident::Incident, incident_source::IncidentSource},
+        sist_camaras::camera::Camera,
+        sist_dron::{dron_current_info::DronCurrentInfo, dron_state::DronState},
+    };
+
+    #[test]
+    fn test_1_el_estado_se_exporta_como_json_con_camaras_drones_e_incidentes() {
+        let camera = Camera::new(1, -34.6037, -58.3816, 5);
+        let dron = DronCurrentInfo::new(3, -34.6090, -58.3873, 100, DronState::ExpectingToRecvIncident);
+        let incident = Incident::new(2, (-34.6037, -58.3816), IncidentSource::Manual);
+
+        let json = StateSnapshot::new(vec![&camera], vec![&dron], vec![&incident])
+            .to_json()
+            .unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["cameras"][0]["id"], 1);
+        assert_eq!(value["cameras"][0]["state"], "SavingMode");
+        assert_eq!(value["drones"][0]["id"], 3);
+        assert_eq!(value["drones"][0]["state"], "ExpectingToRecvIncident");
+        assert_eq!(value["incidents"][0]["id"], 2);
+        assert_eq!(value["incidents"][0]["status"], "Open");
+        assert_eq!(value["incidents"][0]["source"], "Manual");
+    }
+
+    #[test]
+    fn test_2_la_exportacion_periodica_se_hace_pasado_el_intervalo() {
+        let exporter = StateExporter::new("estado.json".to_string(), Some(Duration::from_secs(10)));
+        let now = Instant::now();
+        assert!(!exporter.is_due(now));
+        assert!(exporter.is_due(now + Duration::from_secs(10)));
+
+        // Sin intervalo, solamente a pedido
+        let on_demand = StateExporter::new("estado.json".to_string(), None);
+        assert!(!on_demand.is_due(now + Duration::from_secs(3600)));
+        assert_eq!(on_demand.time_until_due(now), None);
+    }
+}
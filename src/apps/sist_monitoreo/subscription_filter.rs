@@ -0,0 +1,114 @@
+use std::sync::mpsc::Sender;
+
+use crate::apps::apps_mqtt_topics::AppsMqttTopics;
+
+/// Pedido de la UI para suscribirse o desuscribirse de un topic, que Sistema Monitoreo realiza por MQTT.
+#[derive(Debug, Clone, PartialEq)]
+pub enum SubscriptionRequest {
+    Subscribe(String),
+    Unsubscribe(String),
+}
+
+/// Topics que pueden desactivarse desde la UI, con su nombre en el menú. Los de incidentes y desconexiones
+/// no se ofrecen, ya que de ellos depende la resolución de los incidentes.
+const TOGGLEABLE_TOPICS: [(AppsMqttTopics, &str); 3] = [
+    (AppsMqttTopics::CameraTopic, "Cámaras"),
+    (AppsMqttTopics::DronTopic, "Drones"),
+    (AppsMqttTopics::CameraFrameTopic, "Frames de cámaras"),
+];
+
+/// Permite activar y desactivar, mientras la UI está en ejecución, la recepción de algunos topics
+/// (ej. dejar de ver los drones para despejar el mapa): al cambiar uno, envía el pedido de suscribirse
+/// o desuscribirse por `request_tx`. Inicialmente todos están activos.
+#[derive(Debug)]
+pub struct SubscriptionFilter {
+    toggles: Vec<(String, &'static str, bool)>,
+    request_tx: Sender<SubscriptionRequest>,
+}
+
+impl SubscriptionFilter {
+    pub fn new(request_tx: Sender<SubscriptionRequest>) -> Self {
+        let toggles = TOGGLEABLE_TOPICS
+            .iter()
+            .map(|(topic, label)| (topic.to_str().to_string(), *label, true))
+            .collect();
+        Self {
+            toggles,
+            request_tx,
+        }
+    }
+
+    /// Devuelve si se están recibiendo los mensajes del `topic`. Los que no pueden desactivarse, siempre.
+    pub fn is_enabled(&self, topic: &str) -> bool {
+        self.toggles
+            .iter()
+            .find(|(toggle_topic, _, _)| toggle_topic == topic)
+            .is_none_or(|(_, _, enabled)| *enabled)
+    }
+
+    /// Activa o desactiva el `topic`, enviando el pedido correspondiente. Devuelve si cambió.
+    pub fn set_enabled(&mut self, topic: &str, enabled: bool) -> bool {
+        let toggle = match self.toggles.iter_mut().find(|(toggle_topic, _, _)| toggle_topic == topic) {
+            Some(toggle) => toggle,
+            None => return false,
+        };
+        if toggle.2 == enabled {
+            return false;
+        }
+        toggle.2 = enabled;
+        let request = if enabled {
+            SubscriptionRequest::Subscribe(topic.to_string())
+        } else {
+            SubscriptionRequest::Unsubscribe(topic.to_string())
+        };
+        if self.request_tx.send(request).is_err() {
+            println!("UI: error al enviar el pedido de (des)suscripción al topic {:?}.", topic);
+        }
+        true
+    }
+
+    /// Agrega al menú una opción por topic para activarlo o desactivarlo.
+    /// Devuelve los topics que se desactivaron, para que la UI quite lo que se mostraba de ellos.
+    pub fn menu(&mut self, ui: &mut egui::Ui) -> Vec<String> {
+        let mut disabled = vec![];
+        let toggles: Vec<(String, &str, bool)> = self.toggles.clone();
+        for (topic, label, mut enabled) in toggles {
+            if ui.checkbox(&mut enabled, label).changed() && self.set_enabled(&topic, enabled) && !enabled {
+                disabled.push(topic);
+            }
+        }
+        disabled
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::{SubscriptionFilter, SubscriptionRequest};
+
+    #[test]
+    fn test_1_desactivar_y_reactivar_un_topic_envia_los_pedidos_y_los_otros_topics_no_cambian() {
+        let (request_tx, request_rx) = mpsc::channel();
+        let mut filter = SubscriptionFilter::new(request_tx);
+        assert!(filter.is_enabled("dron"));
+
+        assert!(filter.set_enabled("dron", false));
+        assert!(!filter.is_enabled("dron"));
+        // Si ya estaba desactivado, no se vuelve a pedir
+        assert!(!filter.set_enabled("dron", false));
+        assert!(filter.set_enabled("dron", true));
+        // Los incidentes no pueden desactivarse
+        assert!(!filter.set_enabled("inc", false));
+        assert!(filter.is_enabled("inc"));
+
+        let requests: Vec<SubscriptionRequest> = request_rx.try_iter().collect();
+        assert_eq!(
+            requests,
+            vec![
+                SubscriptionRequest::Unsubscribe("dron".to_string()),
+                SubscriptionRequest::Subscribe("dron".to_string()),
+            ]
+        );
+    }
+}
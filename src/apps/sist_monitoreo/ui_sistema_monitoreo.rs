@@ -1,18 +1,34 @@
 use std::collections::HashMap;
-use std::str::{from_utf8, Utf8Error};
 use std::time::{Duration, Instant};
 
+use crate::apps::app_message::AppMessage;
 use crate::apps::apps_mqtt_topics::AppsMqttTopics;
 use crate::apps::incident_data::incident_state::IncidentState;
 use crate::apps::incident_data::{
-    incident::Incident, incident_info::IncidentInfo, incident_source::IncidentSource,
+    incident::{Incident, LEADER_MONITOR_ID}, incident_id_allocator::IncidentIdAllocator, incident_info::IncidentInfo,
+    incident_source::IncidentSource, incident_status::IncidentStatus,
 };
 use crate::apps::place_type::PlaceType;
+use crate::apps::properties::Properties;
 use crate::apps::sist_camaras::camera_state::CameraState;
 use crate::apps::sist_dron::dron_current_info::DronCurrentInfo;
 use crate::apps::sist_dron::dron_state::DronState;
 use crate::mqtt::messages::publish_message::PublishMessage;
 
+use super::camera_frame_window::CameraFrameWindow;
+use super::drone_history::{DroneHistory, DEFAULT_DRONE_HISTORY_LEN};
+use super::heartbeat_tracker::HeartbeatTracker;
+use super::incident_dedup::IncidentDedup;
+use super::incident_expiry::IncidentExpiry;
+use super::incident_marker_styles::IncidentMarkerStyles;
+use super::incident_notifier::ShIncidentNotifier;
+use super::incident_source_policy::IncidentSourcePolicy;
+use super::log_tail_panel::{LogTailPanel, DEFAULT_LOG_TAIL_LEN};
+use super::mapbox_token::{MapboxToken, DEFAULT_MAPBOX_TOKEN_CHECK_INTERVAL};
+use super::marker_precision::MarkerPrecision;
+use super::state_exporter::{StateExporter, StateSnapshot};
+use super::subscription_filter::{SubscriptionFilter, SubscriptionRequest};
+
 use crate::apps::sist_camaras::camera::Camera;
 use crate::apps::vendor::{
     HttpOptions, Map, MapMemory, Place, Places, Position, Style, Tiles, TilesManager,
@@ -20,10 +36,11 @@ use crate::apps::vendor::{
 use crate::apps::{places, plugins::ImagesPluginData};
 use crate::mqtt::mqtt_utils::will_message_utils::app_type::AppType;
 use crate::mqtt::mqtt_utils::will_message_utils::will_content::WillContent;
+use crate::logging::string_logger::StringLogger;
 use crossbeam_channel::{unbounded, Receiver as CrossbeamReceiver, Sender as CrossbeamSender};
 use egui::Color32;
 use egui::Context;
-use std::sync::mpsc::Sender;
+use std::sync::mpsc::{Receiver, Sender};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum Provider {
@@ -34,6 +51,92 @@ pub enum Provider {
     LocalTiles,
 }
 
+impl Provider {
+    /// Devuelve el provider de nombre `str` (sin distinguir mayúsculas), o None si no existe.
+    #[allow(clippy::should_implement_trait)]
+    pub fn from_str(str: &str) -> Option<Self> {
+        match str.trim().to_lowercase().as_str() {
+            "openstreetmap" => Some(Provider::OpenStreetMap),
+            "geoportal" => Some(Provider::Geoportal),
+            "mapboxstreets" => Some(Provider::MapboxStreets),
+            "mapboxsatellite" => Some(Provider::MapboxSatellite),
+            "localtiles" => Some(Provider::LocalTiles),
+            _ => None,
+        }
+    }
+}
+
+/// Elige el provider con el que inicia la UI: el configurado, si existe y está disponible
+/// (ej. Mapbox solamente lo está si hay token, ver `MapboxToken`), u OpenStreetMap en caso contrario.
+fn select_provider(configured: Option<&str>, is_available: impl Fn(&Provider) -> bool) -> Provider {
+    let configured = match configured {
+        Some(name) => name,
+        None => return Provider::OpenStreetMap,
+    };
+    match Provider::from_str(configured) {
+        Some(provider) if is_available(&provider) => provider,
+        Some(provider) => {
+            println!("UI: advertencia: el provider {:?} no está disponible, se usa OpenStreetMap.", provider);
+            Provider::OpenStreetMap
+        }
+        None => {
+            println!("UI: advertencia: provider '{}' inválido, se usa OpenStreetMap.", configured);
+            Provider::OpenStreetMap
+        }
+    }
+}
+
+/// Lee del archivo de properties el provider inicial (`map_provider`), entre los `providers` disponibles.
+fn read_selected_provider(
+    properties_file: &str,
+    providers: &HashMap<Provider, Box<dyn TilesManager + Send>>,
+) -> Provider {
+    match Properties::new(properties_file) {
+        Ok(properties) => select_provider(properties.get("map_provider").map(|s| s.as_str()), |provider| {
+            providers.contains_key(provider)
+        }),
+        Err(e) => {
+            println!("UI: error al leer properties de la UI: {:?}", e);
+            Provider::OpenStreetMap
+        }
+    }
+}
+
+/// Elige el centro del mapa (su "posición inicial"): la latitud y longitud configuradas, si ambas son válidas,
+/// o el Obelisco en caso contrario.
+fn select_map_center(latitude: Option<&str>, longitude: Option<&str>) -> Position {
+    let (latitude, longitude) = match (latitude, longitude) {
+        (Some(latitude), Some(longitude)) => (latitude, longitude),
+        _ => return places::obelisco(),
+    };
+    match (latitude.trim().parse::<f64>(), longitude.trim().parse::<f64>()) {
+        (Ok(lat), Ok(lon)) if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) => {
+            Position::from_lat_lon(lat, lon)
+        }
+        _ => {
+            println!(
+                "UI: advertencia: centro del mapa ({}, {}) inválido, se usa el Obelisco.",
+                latitude, longitude
+            );
+            places::obelisco()
+        }
+    }
+}
+
+/// Lee del archivo de properties el centro del mapa (`map_center_lat` y `map_center_lon`).
+fn read_map_center(properties_file: &str) -> Position {
+    match Properties::new(properties_file) {
+        Ok(properties) => select_map_center(
+            properties.get("map_center_lat").map(|s| s.as_str()),
+            properties.get("map_center_lon").map(|s| s.as_str()),
+        ),
+        Err(e) => {
+            println!("UI: error al leer properties de la UI: {:?}", e);
+            places::obelisco()
+        }
+    }
+}
+
 fn http_options() -> HttpOptions {
     HttpOptions {
         cache: None,
@@ -46,7 +149,8 @@ fn http_options() -> HttpOptions {
     }
 }
 
-fn providers(egui_ctx: Context) -> HashMap<Provider, Box<dyn TilesManager + Send>> {
+/// Crea los providers disponibles. Los de Mapbox, solamente si se tiene el `mapbox_token`.
+fn providers(egui_ctx: Context, mapbox_token: Option<&str>) -> HashMap<Provider, Box<dyn TilesManager + Send>> {
     let mut providers: HashMap<Provider, Box<dyn TilesManager + Send>> = HashMap::default();
 
     providers.insert(
@@ -74,12 +178,22 @@ fn providers(egui_ctx: Context) -> HashMap<Provider, Box<dyn TilesManager + Send
         )),
     );
 
-    // Pass in a mapbox access token at compile time. May or may not be what you want to do,
-    // potentially loading it from application settings instead.
-    let mapbox_access_token = std::option_env!("MAPBOX_ACCESS_TOKEN");
+    set_mapbox_providers(&mut providers, mapbox_token, &egui_ctx);
+
+    providers
+}
+
+/// Reemplaza los providers de Mapbox por los del `mapbox_token`, o los quita si no se lo tiene.
+fn set_mapbox_providers(
+    providers: &mut HashMap<Provider, Box<dyn TilesManager + Send>>,
+    mapbox_token: Option<&str>,
+    egui_ctx: &Context,
+) {
+    providers.remove(&Provider::MapboxStreets);
+    providers.remove(&Provider::MapboxSatellite);
 
     // We only show the mapbox map if we have an access token
-    if let Some(token) = mapbox_access_token {
+    if let Some(token) = mapbox_token {
         providers.insert(
             Provider::MapboxStreets,
             Box::new(Tiles::with_options(
@@ -105,19 +219,159 @@ fn providers(egui_ctx: Context) -> HashMap<Provider, Box<dyn TilesManager + Send
             )),
         );
     }
+}
 
-    providers
+/// Devuelve el provider `selected` si sigue disponible, u OpenStreetMap si no (ej. se quitó el token de Mapbox).
+fn fallback_if_unavailable(selected: Provider, is_available: impl Fn(&Provider) -> bool) -> Provider {
+    if is_available(&selected) {
+        return selected;
+    }
+    println!("UI: advertencia: el provider {:?} dejó de estar disponible, se usa OpenStreetMap.", selected);
+    Provider::OpenStreetMap
 }
 
 #[derive(Debug)]
 struct IncidentWithDrones {
     incident_info: IncidentInfo,
     drones: Vec<DronCurrentInfo>,
+    // Ids de los drones que aceptaron el incidente y están en camino, pero aún no llegaron.
+    drones_en_route: Vec<u8>,
+}
+
+impl IncidentWithDrones {
+    fn new(incident_info: IncidentInfo) -> Self {
+        Self {
+            incident_info,
+            drones: vec![],
+            drones_en_route: vec![],
+        }
+    }
+}
+
+/// Actualiza qué drones están en camino a cada incidente, según el estado recibido del `dron`: un dron que aceptó
+/// un incidente (debe ir, o está volando hacia él) queda en camino a éste; con cualquier otro estado (ej. llegó,
+/// o abandonó el incidente para ir a mantenimiento) deja de estar en camino.
+fn update_drones_en_route(incidents: &mut Vec<IncidentWithDrones>, dron: &DronCurrentInfo) {
+    let dron_id = dron.get_id();
+    for incident in incidents.iter_mut() {
+        incident.drones_en_route.retain(|id| *id != dron_id);
+    }
+
+    if let (true, Some(inc_info)) = (is_en_route(dron), dron.get_inc_id_to_resolve()) {
+        match incidents
+            .iter_mut()
+            .find(|incident| incident.incident_info == inc_info)
+        {
+            Some(incident) => incident.drones_en_route.push(dron_id),
+            None => {
+                let mut incident = IncidentWithDrones::new(inc_info);
+                incident.drones_en_route.push(dron_id);
+                incidents.push(incident);
+            }
+        }
+    }
+}
+
+/// Devuelve si el `dron` aceptó su incidente a resolver (debe ir, o está volando hacia él) y aún no llegó.
+fn is_en_route(dron: &DronCurrentInfo) -> bool {
+    matches!(
+        dron.get_state(),
+        DronState::MustRespondToIncident | DronState::GoingToIncident | DronState::Flying
+    )
+}
+
+/// Avanza el status del `incident` hasta `target` (`Assigned`, `Resolving` o `Resolved`), pasando por las etapas
+/// intermedias (ej. si el primer dron llega sin que se lo haya visto en camino). Nunca lo retrocede.
+/// Devuelve si el status cambió.
+fn advance_incident_status(incident: &mut Incident, target: IncidentStatus) -> bool {
+    let stages = [IncidentStatus::Assigned, IncidentStatus::Resolving, IncidentStatus::Resolved];
+    let stages_until_target = match stages.iter().position(|stage| *stage == target) {
+        Some(idx) => &stages[..=idx],
+        None => return false,
+    };
+    let mut changed = false;
+    for stage in stages_until_target {
+        if incident.get_status().can_transition_to(*stage) {
+            changed |= incident.transition_to(*stage).is_ok();
+        }
+    }
+    changed
+}
+
+pub const UI_PROPERTIES_FILE: &str = "src/apps/sist_monitoreo/ui_sistema_monitoreo.properties";
+
+/// Cantidad de puntos con que se dibuja el arco del campo de visión de una cámara.
+const CAMERA_CONE_ARC_POINTS: usize = 16;
+
+/// Lee del archivo de properties el id de este monitor (`monitor_id`), que lo identifica como dueño de los
+/// incidentes que crea. Si no se configura, o es inválido, se utiliza el id del monitor líder.
+fn read_monitor_id(properties_file: &str) -> u8 {
+    let prop = match Properties::new(properties_file) {
+        Ok(properties) => properties.get("monitor_id").map(|id| id.parse::<u8>()),
+        Err(_) => None,
+    };
+    match prop {
+        Some(Ok(id)) if id > 0 => id,
+        Some(_) => {
+            println!("UI: propiedad 'monitor_id' inválida, se utiliza el id del líder.");
+            LEADER_MONITOR_ID
+        }
+        None => LEADER_MONITOR_ID,
+    }
+}
+
+/// Lee del archivo de properties el radio, en pixels, dentro del cual se agrupan las cámaras y drones
+/// del mapa (`places_cluster_radius_px`). Si no se indica, o es 0, no se agrupan.
+fn read_cluster_radius(properties_file: &str) -> Option<f32> {
+    let prop = match Properties::new(properties_file) {
+        Ok(properties) => properties
+            .get("places_cluster_radius_px")
+            .map(|radius| radius.parse::<f32>()),
+        Err(_) => None,
+    };
+    match prop {
+        Some(Ok(radius)) if radius > 0. => Some(radius),
+        Some(Ok(_)) | None => None,
+        Some(Err(_)) => {
+            println!("UI: propiedad 'places_cluster_radius_px' inválida, no se agrupan los marcadores.");
+            None
+        }
+    }
+}
+
+/// Devuelve si el monitor `monitor_id` debe encargarse de resolver el incidente de `inc_info`. Si el monitor
+/// lo tiene guardado (`stored`), según su dueño; si no, solamente el líder se encarga de los reportados por
+/// cámaras o drones (ej. uno que todavía espera su confirmación), y los manuales son de otro monitor.
+fn monitor_owns_incident(inc_info: &IncidentInfo, stored: Option<&Incident>, monitor_id: u8) -> bool {
+    match stored {
+        Some(incident) => incident.is_owned_by(monitor_id),
+        None => !inc_info.get_src().is_created_by_monitoreo() && monitor_id == LEADER_MONITOR_ID,
+    }
+}
+
+/// Lee del archivo de properties cada cuánto se repinta la UI aunque no lleguen mensajes.
+/// Devuelve None si el intervalo es 0 o no se pudo leer, en cuyo caso solamente se repinta ante eventos.
+fn read_repaint_interval(properties_file: &str) -> Option<Duration> {
+    match Properties::new(properties_file) {
+        Ok(properties) => match properties.get("repaint_interval_ms").map(|ms| ms.parse::<u64>()) {
+            Some(Ok(0)) => None,
+            Some(Ok(ms)) => Some(Duration::from_millis(ms)),
+            _ => {
+                println!("UI: propiedad 'repaint_interval_ms' inválida o ausente, se repinta solo ante eventos.");
+                None
+            }
+        },
+        Err(e) => {
+            println!("UI: error al leer properties de la UI: {:?}", e);
+            None
+        }
+    }
 }
 
 pub struct UISistemaMonitoreo {
     providers: HashMap<Provider, Box<dyn TilesManager + Send>>,
     selected_provider: Provider,
+    mapbox_token: MapboxToken,
     map_memory: MapMemory,
     images_plugin_data: ImagesPluginData,
     click_watcher: super::super::plugins::ClickWatcher,
@@ -127,32 +381,63 @@ pub struct UISistemaMonitoreo {
     publish_incident_tx: Sender<Incident>,
     publish_message_rx: CrossbeamReceiver<PublishMessage>,
     places: Places,
-    last_incident_id: u8,
+    incident_id_allocator: IncidentIdAllocator,
     exit_tx: Sender<bool>,
     incidents_to_resolve: Vec<IncidentWithDrones>, // posicion 0  --> (inc_id_to_resolve, drones(dron1, dron2)) // posicion 1 --> (inc_id_to_resolve 2, drones(dron1, dron2))
-    hashmap_incidents: HashMap<IncidentInfo, Incident>, //
+    hashmap_incidents: HashMap<IncidentInfo, (Incident, Instant)>, // incidente no resuelto, y su instante de creación
     error_tx: CrossbeamSender<String>,
     error_rx: CrossbeamReceiver<String>,
     error_message: Option<String>,
     error_display_start: Option<Instant>,
+    drone_history: DroneHistory,
+    log_tail_panel: LogTailPanel,
+    camera_frame_window: CameraFrameWindow,
+    repaint_interval: Option<Duration>,
+    incident_policy: IncidentSourcePolicy,
+    pending_incidents: Vec<(Instant, Incident)>, // incidentes automáticos recibidos, esperando su confirmación
+    incident_expiry: IncidentExpiry,
+    heartbeat_tracker: HeartbeatTracker,
+    map_center: Position,
+    cameras: HashMap<u8, Camera>, // última información recibida de cada cámara, para exportar el estado
+    state_exporter: StateExporter,
+    monitor_id: u8, // ver `Incident`: este monitor solamente resuelve los incidentes de los que es dueño
+    notifier: ShIncidentNotifier,
+    marker_precision: MarkerPrecision,
+    incident_markers: IncidentMarkerStyles,
+    subscription_filter: SubscriptionFilter,
+    incident_dedup: IncidentDedup,
+    logger: StringLogger,
 }
 
 impl UISistemaMonitoreo {
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         egui_ctx: Context,
         tx: Sender<Incident>,
         publish_message_rx: CrossbeamReceiver<PublishMessage>,
         exit_tx: Sender<bool>,
+        subscription_tx: Sender<SubscriptionRequest>,
+        log_rx: Option<Receiver<String>>,
+        notifier: ShIncidentNotifier,
+        logger: StringLogger,
     ) -> Self {
         egui_extras::install_image_loaders(&egui_ctx);
 
         let images_plugin_data = ImagesPluginData::new(egui_ctx.to_owned());
         let places = Self::initialize_places();
         let (error_tx, error_rx) = unbounded();
+        let repaint_interval = read_repaint_interval(UI_PROPERTIES_FILE);
+        // Se asegura que el primer frame se dibuje aunque todavía no haya llegado ningún mensaje.
+        egui_ctx.request_repaint();
+
+        let mapbox_token = MapboxToken::new(UI_PROPERTIES_FILE, DEFAULT_MAPBOX_TOKEN_CHECK_INTERVAL);
+        let providers = providers(egui_ctx.to_owned(), mapbox_token.get());
+        let selected_provider = read_selected_provider(UI_PROPERTIES_FILE, &providers);
 
         Self {
-            providers: providers(egui_ctx.to_owned()),
-            selected_provider: Provider::OpenStreetMap,
+            providers,
+            selected_provider,
+            mapbox_token,
             map_memory: MapMemory::default(),
             images_plugin_data,
             click_watcher: Default::default(),
@@ -162,7 +447,7 @@ impl UISistemaMonitoreo {
             publish_incident_tx: tx,
             publish_message_rx,
             places,
-            last_incident_id: 0,
+            incident_id_allocator: IncidentIdAllocator::new(),
             exit_tx,
             incidents_to_resolve: Vec::new(),
             hashmap_incidents: HashMap::new(),
@@ -170,6 +455,24 @@ impl UISistemaMonitoreo {
             error_rx,
             error_message: None,
             error_display_start: None,
+            drone_history: DroneHistory::new(DEFAULT_DRONE_HISTORY_LEN),
+            log_tail_panel: LogTailPanel::new(log_rx, DEFAULT_LOG_TAIL_LEN),
+            camera_frame_window: CameraFrameWindow::new(),
+            repaint_interval,
+            incident_policy: IncidentSourcePolicy::from_properties_file(UI_PROPERTIES_FILE),
+            pending_incidents: Vec::new(),
+            incident_expiry: IncidentExpiry::from_properties_file(UI_PROPERTIES_FILE),
+            heartbeat_tracker: HeartbeatTracker::from_properties_file(UI_PROPERTIES_FILE),
+            map_center: read_map_center(UI_PROPERTIES_FILE),
+            cameras: HashMap::new(),
+            state_exporter: StateExporter::from_properties_file(UI_PROPERTIES_FILE),
+            monitor_id: read_monitor_id(UI_PROPERTIES_FILE),
+            notifier,
+            marker_precision: MarkerPrecision::from_properties_file(UI_PROPERTIES_FILE),
+            incident_markers: IncidentMarkerStyles::from_properties_file(UI_PROPERTIES_FILE),
+            subscription_filter: SubscriptionFilter::new(subscription_tx),
+            incident_dedup: IncidentDedup::from_properties_file(UI_PROPERTIES_FILE),
+            logger,
         }
     }
 
@@ -183,7 +486,10 @@ impl UISistemaMonitoreo {
     fn initialize_places() -> Places {
         let mantainance_style = Self::create_style_with_color(255, 165, 0); // Color naranja
         let mantainance_ui = Self::create_maintenance_place(mantainance_style);
-        let mut places = Places::new();
+        let mut places = match read_cluster_radius(UI_PROPERTIES_FILE) {
+            Some(radius) => Places::new().with_clustering(radius),
+            None => Places::new(),
+        };
         places.add_place(mantainance_ui);
         places
     }
@@ -200,9 +506,20 @@ impl UISistemaMonitoreo {
     }
 
     /// Envía internamente a otro hilo el `incident` recibido, para publicarlo por mqtt.
-    fn send_incident_for_publish(&self, incident: Incident) {
+    /// Envía el incidente para que se lo publique. Si se lo cerró, se publican también, cerrados como él,
+    /// los incidentes duplicados fusionados en él (ver `IncidentDedup`).
+    fn send_incident_for_publish(&mut self, incident: Incident) {
+        let duplicates = if incident.is_closed() {
+            self.incident_dedup.take_merged_closed_like(&incident)
+        } else {
+            vec![]
+        };
         println!("Enviando incidente: {:?}", incident);
         let _ = self.publish_incident_tx.send(incident);
+        for duplicate in duplicates {
+            println!("Enviando incidente duplicado: {:?}", duplicate);
+            let _ = self.publish_incident_tx.send(duplicate);
+        }
     }
 
     fn create_camera_style(camera_state: CameraState) -> Style {
@@ -212,6 +529,11 @@ impl UISistemaMonitoreo {
                 ..Default::default()
             },
             CameraState::SavingMode => Style::default(),
+            CameraState::Failed => Style {
+                symbol_color: Color32::from_rgb(128, 128, 128), // Color gris
+                symbol_background: Color32::from_gray(160).gamma_multiply(0.8),
+                ..Default::default()
+            },
         }
     }
 
@@ -238,14 +560,16 @@ impl UISistemaMonitoreo {
             let style = Self::create_camera_style(camera.get_state());
             let camera_ui = Self::create_camera_place(&camera, style);
             self.places.add_place(camera_ui);
+            self.cameras.insert(camera_id, camera);
         } else {
             self.places.remove_place(camera_id, PlaceType::Camera);
+            self.camera_frame_window.remove_camera(camera_id);
+            self.cameras.remove(&camera_id);
         }
     }
 
     /// Se encarga de procesar y agregar o eliminar una cámara recibida al mapa.
-    fn handle_camera_message(&mut self, publish_message: PublishMessage) {
-        let camera = Camera::from_bytes(&publish_message.get_payload());
+    fn handle_camera_message(&mut self, camera: Camera) {
         println!(
             "UI: recibida cámara: {:?}, estado: {:?}",
             camera,
@@ -256,204 +580,511 @@ impl UISistemaMonitoreo {
     }
 
     /// Se encarga de procesar y agregar un dron recibido al mapa.
-    fn handle_drone_message(&mut self, msg: PublishMessage) {
-        if let Ok(dron) = DronCurrentInfo::from_bytes(msg.get_payload()) {
-            /*println!(
-                "UI: recibido dron: {:?}, estado: {:?}",
-                dron,
-                dron.get_state()
-            );*/
-            // Si ya existía el dron, se lo elimina, porque que me llegue nuevamente significa que se está moviendo.
-            let dron_id = dron.get_id();
-            self.places.remove_place(dron_id, PlaceType::Dron);
+    fn handle_drone_message(&mut self, dron: DronCurrentInfo) {
+        /*println!(
+            "UI: recibido dron: {:?}, estado: {:?}",
+            dron,
+            dron.get_state()
+        );*/
+        let dron_id = dron.get_id();
+        self.drone_history.add(dron.clone());
+        update_drones_en_route(&mut self.incidents_to_resolve, &dron);
+        // Con el primer dron en camino, el incidente pasa a estar asignado.
+        if let Some(inc_info) = dron.get_inc_id_to_resolve() {
+            if is_en_route(&dron) {
+                self.advance_stored_incident_status(inc_info, IncidentStatus::Assigned);
+            }
+        }
 
-            if dron.get_state() == DronState::ManagingIncident {
-                // Llegó a la posición del inc.
-                if let Some(inc_info) = dron.get_inc_id_to_resolve() {
-                    // Busca el incidente en el vector.
-                    let incident_index = self
-                        .incidents_to_resolve
-                        .iter()
-                        .position(|incident| incident.incident_info == inc_info);
-                    //.position(|incident| incident.incident_info.get_inc_id() == inc_id); // <--pre refactor decía esto
-
-                    match incident_index {
-                        Some(index) => {
-                            // Si el incidente ya existe, agrega el dron al vector de drones del incidente.
-                            self.incidents_to_resolve[index].drones.push(dron.clone());
-                        }
-                        None => {
-                            // Si no tengo guardado el inc_id_to_res, crea una nueva posicion con el dron respectivo.
-                            self.incidents_to_resolve.push(IncidentWithDrones {
-                                incident_info: inc_info,
-                                drones: vec![dron.clone()],
-                            });
-                        }
+        if dron.get_state().is_in_maintenance() {
+            // Un dron que se va a recargar deja de atender su incidente, no cuenta para resolverlo.
+            for incident in self.incidents_to_resolve.iter_mut() {
+                incident.drones.retain(|d| d.get_id() != dron_id);
+            }
+        } else if dron.get_state() == DronState::ManagingIncident {
+            // Llegó a la posición del inc. Si el incidente es de otro monitor, lo resuelve aquél.
+            let inc_to_resolve = dron.get_inc_id_to_resolve().filter(|inc_info| {
+                let stored = self.hashmap_incidents.get(inc_info).map(|(incident, _)| incident);
+                monitor_owns_incident(inc_info, stored, self.monitor_id)
+            });
+            if let Some(inc_info) = inc_to_resolve {
+                // Busca el incidente en el vector.
+                let incident_index = self
+                    .incidents_to_resolve
+                    .iter()
+                    .position(|incident| incident.incident_info == inc_info);
+                //.position(|incident| incident.incident_info.get_inc_id() == inc_id); // <--pre refactor decía esto
+
+                // Con el primer dron que llega, el incidente pasa a estar resolviéndose.
+                self.advance_stored_incident_status(inc_info, IncidentStatus::Resolving);
+
+                match incident_index {
+                    Some(index) => {
+                        // Si el incidente ya existe, agrega el dron al vector de drones del incidente.
+                        self.incidents_to_resolve[index].drones.push(dron.clone());
+                    }
+                    None => {
+                        // Si no tengo guardado el inc_id_to_res, crea una nueva posicion con el dron respectivo.
+                        let mut incident = IncidentWithDrones::new(inc_info);
+                        incident.drones.push(dron.clone());
+                        self.incidents_to_resolve.push(incident);
                     }
                 }
             }
+        }
 
-            for incident in self.incidents_to_resolve.iter() {
-                if incident.drones.len() == 2 {
-                    let inc_info = &incident.incident_info;
-                    if let Some(mut incident) = self.hashmap_incidents.remove(inc_info) {
-                        incident.set_resolved();
-                        // Obtengo el source del incidente, para pasarle un place_type acorde al remove_place
-                        // y lo remuevo de la lista de places a mostrar en el mapa.
-                        let place_type = PlaceType::from_inc_source(incident.get_source());
-                        self.places.remove_place(inc_info.get_inc_id(), place_type);
-
-                        self.send_incident_for_publish(incident);
-                    }
+        let resolved_by_drones: Vec<IncidentInfo> = self
+            .incidents_to_resolve
+            .iter()
+            .filter(|incident| {
+                self.incident_policy
+                    .is_resolved_by(incident.incident_info.get_src(), incident.drones.len())
+            })
+            .map(|incident| incident.incident_info)
+            .collect();
+        for inc_info in resolved_by_drones {
+            // Al quitarlo del hashmap, se cancela también su vencimiento
+            if let Some((mut incident, _)) = self.hashmap_incidents.remove(&inc_info) {
+                // Llegaron los drones necesarios: se resolvió, y se lo sigue mostrando como resuelto.
+                if !advance_incident_status(&mut incident, IncidentStatus::Resolved) {
+                    incident.set_resolved();
                 }
-            }
+                self.update_incident_place(&incident);
 
-            // Crea lo necesario para dibujar al dron
-            let (lat, lon) = dron.get_current_position();
-            let dron_pos = Position::from_lon_lat(lon, lat);
-
-            // Se crea el label a mostrar por pantalla, según si está o no volando.
-            let dron_label;
-            if let Some((dir, speed)) = dron.get_flying_info() {
-                let (dir_lat, dir_lon) = dir;
-                // El dron está volando.
-                dron_label = format!(
-                    "Dron {}\n   dir: ({:.2}, {:.2})\n   vel: {} km/h",
-                    dron_id, dir_lat, dir_lon, speed
-                );
-            } else {
-                dron_label = format!("Dron {}", dron_id);
+                self.notifier.on_resolved(&incident);
+                self.send_incident_for_publish(incident);
             }
+        }
 
-            // Se crea el place y se lo agrega al mapa.
-            let dron_ui = Place {
-                position: dron_pos,
-                label: dron_label,
-                symbol: '🚁',
-                style: Style::default(),
-                id: dron.get_id(),
-                place_type: PlaceType::Dron, // Para luego buscarlo en el places.
+        // Crea lo necesario para dibujar al dron
+        let (lat, lon) = dron.get_current_position();
+        let dron_pos = Position::from_lon_lat(lon, lat);
+
+        // Se crea el label a mostrar por pantalla, según si está o no volando.
+        let dron_label;
+        if let Some((dir, speed)) = dron.get_flying_info() {
+            let (dir_lat, dir_lon) = dir;
+            // El dron está volando, indicando si lo hace hacia mantenimiento.
+            let destination = if dron.get_state() == DronState::GoingToMaintenance {
+                "\n   → mantenimiento"
+            } else {
+                ""
             };
+            dron_label = format!(
+                "Dron {}{}\n   dir: ({}, {})\n   vel: {} km/h",
+                dron_id,
+                destination,
+                self.marker_precision.format_coordinate(dir_lat),
+                self.marker_precision.format_coordinate(dir_lon),
+                speed
+            );
+        } else {
+            dron_label = format!("Dron {}\n   {}", dron_id, dron.get_state());
+        }
+
+        // Se crea el place y se lo agrega al mapa.
+        // Los drones en mantenimiento se distinguen del resto.
+        let (symbol, style) = if dron.get_state().is_in_maintenance() {
+            ('🔧', Self::create_style_with_color(255, 165, 0))
+        } else {
+            ('🚁', Style::default())
+        };
+        let dron_ui = Place {
+            position: dron_pos,
+            label: dron_label,
+            symbol,
+            style,
+            id: dron.get_id(),
+            place_type: PlaceType::Dron, // Para luego buscarlo en el places.
+        };
 
+        // Si ya existía el dron, se lo reemplaza, porque que me llegue nuevamente significa que se está moviendo.
+        // Si apenas se movió y se muestra igual, se lo deja como está, para que el marcador no parpadee.
+        if self
+            .marker_precision
+            .should_redraw(self.places.get(dron_id, PlaceType::Dron), &dron_ui)
+        {
+            self.places.remove_place(dron_id, PlaceType::Dron);
             self.places.add_place(dron_ui);
         }
         //let _ = self.repaint_tx.send(true);
         //let _ = self.repaint_tx.send(true);
     }
 
-    /// Recibe un PublishMessage de topic Inc, y procesa el incidente recibido
+    /// Procesa el incidente recibido por el topic Inc
     /// (se lo guarda para continuar procesándolo, y lo muestra en la ui).
-    fn handle_incident_message(&mut self, msg: PublishMessage) {
-        if let Ok(inc) = Incident::from_bytes(msg.get_payload()) {
-            // Agregamos el incidente (add_incident) solamente si él no fue creado por sist monitoreo.
-            if *inc.get_source() == IncidentSource::Automated
-                && *inc.get_state() == IncidentState::ActiveIncident
-            {
-                self.add_incident(&inc);
+    fn handle_incident_message(&mut self, mut inc: Incident) {
+        // Agregamos el incidente (add_incident) solamente si él no fue creado por sist monitoreo
+        // (es decir, si lo reportó sistema cámaras o un dron).
+        if !inc.get_source().is_created_by_monitoreo()
+            && *inc.get_state() == IncidentState::ActiveIncident
+        {
+            if !inc.is_owned_by(self.monitor_id) {
+                // Lo resuelve otro monitor: solamente se lo muestra, o se actualiza su status si ya se mostraba.
+                if !self.update_incident_place(&inc) {
+                    let place = self.create_place_for_incident(&inc);
+                    self.places.add_place(place);
+                }
+                return;
+            }
+            // Al resolverlo, se lo publica indicando que este monitor es su dueño
+            inc.set_owner(self.monitor_id);
+            if self.incident_policy.is_confirmed(inc.get_source(), Instant::now()) {
+                self.add_received_incident(&inc);
+            } else {
+                // Se lo mostrará recién cuando pase su tiempo de confirmación.
+                self.pending_incidents.push((Instant::now(), inc));
+            }
+        } else if *inc.get_state() != IncidentState::ActiveIncident {
+            // Si se resolvió antes de confirmarse, ya no hace falta mostrarlo.
+            self.pending_incidents
+                .retain(|(_, pending)| pending.get_info() != inc.get_info());
+            // Si era un duplicado fusionado en otro, se lo olvida: su id puede volver a usarse.
+            self.incident_dedup.forget_merged(&inc);
+            // Si lo resolvió otro monitor, se lo muestra resuelto o cancelado.
+            if !inc.get_source().is_created_by_monitoreo() && !inc.is_owned_by(self.monitor_id) {
+                self.update_incident_place(&inc);
+            }
+        }
+    }
+
+    /// Agrega al mapa los incidentes pendientes cuyo tiempo de confirmación ya pasó.
+    fn add_confirmed_pending_incidents(&mut self, ctx: &egui::Context) {
+        let pending = std::mem::take(&mut self.pending_incidents);
+        for (received_at, inc) in pending {
+            if self.incident_policy.is_confirmed(inc.get_source(), received_at) {
+                self.add_received_incident(&inc);
+            } else {
+                self.pending_incidents.push((received_at, inc));
+            }
+        }
+        // Se repinta al confirmarse el próximo pendiente, aunque no lleguen mensajes.
+        let delay = self.incident_policy.get_policy_for(&IncidentSource::Automated).get_confirmation_delay();
+        if let Some(min_elapsed) = self.pending_incidents.iter().map(|(received_at, _)| received_at.elapsed()).max() {
+            ctx.request_repaint_after(delay.saturating_sub(min_elapsed));
+        }
+    }
+
+    /// Agrega el incidente recibido, salvo que sea duplicado de otro ya mostrado (ver `IncidentDedup`):
+    /// en ese caso se lo fusiona en aquél, y se cerrará junto con él.
+    fn add_received_incident(&mut self, incident: &Incident) {
+        if self.incident_dedup.is_merged(incident) {
+            return;
+        }
+        let existing = self.hashmap_incidents.values().map(|(stored, _)| stored);
+        let kept = self
+            .incident_dedup
+            .find_duplicate_of(incident, existing)
+            .map(|kept| kept.get_info());
+        match kept {
+            Some(kept) => {
+                self.logger.log(format!(
+                    "UI: incidente {} fusionado con el incidente {}, del mismo origen y cercano.",
+                    incident.get_id(),
+                    kept.get_inc_id()
+                ));
+                self.incident_dedup.merge(kept, incident.clone());
             }
+            None => self.add_incident(incident),
         }
     }
 
     /// Crea el Place para el incidente recibido, lo agrega a la ui para que se muestre por pantalla,
-    /// y lo agrega a un hashmap para continuar procesándolo (Aux: rever tema ids que quizás se pisen cuando camaras publiquen incs).
+    /// lo notifica como creado, y lo agrega a un hashmap (por su `IncidentInfo`, ya que los ids se repiten entre orígenes) para continuar procesándolo.
     fn add_incident(&mut self, incident: &Incident) {
-        let custom_style = Self::create_style_with_color(255, 0, 0); // Color rojo
-        let new_place_incident = self.create_place_for_incident(incident, &custom_style);
-        self.places.add_place(new_place_incident);
+        // Reemplaza al de un incidente anterior con el mismo id que se siga mostrando como resuelto
+        if !self.update_incident_place(incident) {
+            let new_place_incident = self.create_place_for_incident(incident);
+            self.places.add_place(new_place_incident);
+        }
         self.store_incident_info(incident);
+        self.notifier.on_created(incident);
     }
 
-    fn create_place_for_incident(&self, incident: &Incident, custom_style: &Style) -> Place {
+    /// Crea el Place del incidente, con el símbolo y color de su status.
+    fn create_place_for_incident(&self, incident: &Incident) -> Place {
         let place_type = PlaceType::from_inc_source(incident.get_source());
         let (lat, lon) = incident.get_position();
+        let (symbol, style) = self.incident_symbol_and_style(incident);
+        let label = match incident.get_source() {
+            IncidentSource::Drone => format!("Incident {}\n   reportado por dron", incident.get_id()),
+            _ => format!("Incident {}", incident.get_id()),
+        };
         Place {
             position: Position::from_lon_lat(lon, lat),
-            label: format!("Incident {}", incident.get_id()),
-            symbol: '⚠',
-            style: custom_style.clone(),
+            label,
+            symbol,
+            style,
             id: incident.get_id(),
             place_type,
         }
     }
 
+    /// Devuelve el símbolo y estilo del Place del incidente según su status. Los reportados por drones
+    /// se distinguen además por el fondo de su símbolo.
+    fn incident_symbol_and_style(&self, incident: &Incident) -> (char, Style) {
+        let (symbol, mut style) = self.incident_markers.symbol_and_style(incident.get_status());
+        if *incident.get_source() == IncidentSource::Drone {
+            style.symbol_background = Color32::from_rgb(173, 216, 230).gamma_multiply(0.8); // Celeste
+        }
+        (symbol, style)
+    }
+
+    /// Actualiza el Place del incidente, con el símbolo y color de su status actual.
+    /// Devuelve false si el incidente no se estaba mostrando.
+    fn update_incident_place(&mut self, incident: &Incident) -> bool {
+        let place_type = PlaceType::from_inc_source(incident.get_source());
+        let updated = self.create_place_for_incident(incident);
+        match self.places.get_mut(incident.get_id(), place_type) {
+            Some(place) => {
+                *place = updated;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Avanza el status del incidente guardado `inc_info` hasta `target` (ver `advance_incident_status`),
+    /// y si cambió actualiza su Place.
+    fn advance_stored_incident_status(&mut self, inc_info: IncidentInfo, target: IncidentStatus) {
+        let advanced = match self.hashmap_incidents.get_mut(&inc_info) {
+            Some((incident, _)) => advance_incident_status(incident, target).then(|| incident.clone()),
+            None => None,
+        };
+        if let Some(incident) = advanced {
+            self.update_incident_place(&incident);
+        }
+    }
+
     fn store_incident_info(&mut self, incident: &Incident) {
-        let inc_info = IncidentInfo::new(incident.get_id(), *incident.get_source());
+        let inc_info = incident.get_info();
         let inc_to_store = incident.clone();
-        self.hashmap_incidents.insert(inc_info, inc_to_store);
+        self.hashmap_incidents.insert(inc_info, (inc_to_store, Instant::now()));
     }
 
-    fn get_next_incident_id(&mut self) -> u8 {
-        self.last_incident_id += 1;
-        self.last_incident_id
+    /// Da por vencidos los incidentes que no se resolvieron dentro del timeout configurado:
+    /// los marca como resueltos, los republica para que las cámaras los liberen, y los muestra resueltos.
+    fn expire_unresolved_incidents(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        let expired: Vec<IncidentInfo> = self
+            .hashmap_incidents
+            .iter()
+            .filter(|(_, (_, created_at))| self.incident_expiry.is_expired(*created_at, now))
+            .map(|(inc_info, _)| *inc_info)
+            .collect();
+
+        for inc_info in expired {
+            println!("UI: incidente {} vencido sin resolverse.", inc_info.get_inc_id());
+            self.close_incident(inc_info);
+        }
+
+        // Se repinta al vencer el próximo incidente, aunque no lleguen mensajes.
+        if let Some(next_expiry) = self
+            .hashmap_incidents
+            .values()
+            .filter_map(|(_, created_at)| self.incident_expiry.time_until_expiry(*created_at, now))
+            .min()
+        {
+            ctx.request_repaint_after(next_expiry);
+        }
+    }
+
+    /// Resuelve el incidente sin esperar a los drones (al vencer, o manualmente desde la ui): lo marca como resuelto,
+    /// lo quita de los incidentes a resolver, lo muestra resuelto, y lo publica. Al recibirlo resuelto, los drones que
+    /// estaban volando hacia él o atendiéndolo vuelven a su posición inicial, a esperar nuevos incidentes.
+    fn close_incident(&mut self, inc_info: IncidentInfo) {
+        if let Some(mut incident) = self.take_incident(inc_info) {
+            incident.set_resolved();
+            self.update_incident_place(&incident);
+            self.notifier.on_resolved(&incident);
+            self.send_incident_for_publish(incident);
+        }
+    }
+
+    /// Cancela el incidente (ej. un incidente manual creado por error): lo quita como al resolverlo, pero lo
+    /// muestra y publica con status cancelado, para que las apps lo descarten sin considerarlo resuelto.
+    fn cancel_incident(&mut self, inc_info: IncidentInfo) {
+        if let Some(mut incident) = self.take_incident(inc_info) {
+            incident.set_cancelled();
+            self.update_incident_place(&incident);
+            self.send_incident_for_publish(incident);
+        }
+    }
+
+    /// Quita el incidente de los incidentes a resolver, y lo devuelve. Su Place se sigue mostrando.
+    fn take_incident(&mut self, inc_info: IncidentInfo) -> Option<Incident> {
+        let (incident, _) = self.hashmap_incidents.remove(&inc_info)?;
+        self.incidents_to_resolve
+            .retain(|with_drones| with_drones.incident_info != inc_info);
+        Some(incident)
     }
 
-    fn handle_disconnection_message(
-        &mut self,
-        publish_message: PublishMessage,
-    ) -> Result<(), Utf8Error> {
-        let will_content_res = WillContent::will_content_from_string(from_utf8(&publish_message.get_payload())?);
-        
-        if let Ok(will_content) = will_content_res {
-            self.process_will_content(will_content)?;
+    /// Muestra una ventana con los incidentes sin resolver, cada uno con un botón para resolverlo manualmente
+    /// (ej. si no hay drones suficientes para atenderlo). Los incidentes manuales pueden además cancelarse.
+    fn show_active_incidents(&mut self, ctx: &egui::Context) {
+        if self.hashmap_incidents.is_empty() {
+            return;
         }
+        let mut active: Vec<(IncidentInfo, usize, usize)> = self
+            .hashmap_incidents
+            .keys()
+            .map(|inc_info| {
+                let (drones, en_route) = self
+                    .incidents_to_resolve
+                    .iter()
+                    .find(|with_drones| with_drones.incident_info == *inc_info)
+                    .map_or((0, 0), |with_drones| {
+                        (with_drones.drones.len(), with_drones.drones_en_route.len())
+                    });
+                (*inc_info, drones, en_route)
+            })
+            .collect();
+        active.sort_by_key(|(inc_info, _, _)| (inc_info.get_src().to_byte(), inc_info.get_inc_id()));
+
+        let mut to_resolve = None;
+        let mut to_cancel = None;
+        egui::Window::new("Incidentes activos")
+            .collapsible(true)
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10.0, -10.0))
+            .show(ctx, |ui| {
+                for (inc_info, drones, en_route) in active {
+                    let required = self
+                        .incident_policy
+                        .get_policy_for(inc_info.get_src())
+                        .get_required_drones();
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "Incident {} ({:?}), drones: {}, {}/{} drones en camino",
+                            inc_info.get_inc_id(),
+                            inc_info.get_src(),
+                            drones,
+                            en_route,
+                            required
+                        ));
+                        if ui.button("Resolver").clicked() {
+                            to_resolve = Some(inc_info);
+                        }
+                        if *inc_info.get_src() == IncidentSource::Manual
+                            && ui.button("Cancelar").clicked()
+                        {
+                            to_cancel = Some(inc_info);
+                        }
+                    });
+                }
+            });
 
-        Ok(())
+        if let Some(inc_info) = to_resolve {
+            println!("UI: incidente {} resuelto manualmente.", inc_info.get_inc_id());
+            self.close_incident(inc_info);
+        }
+        if let Some(inc_info) = to_cancel {
+            println!("UI: incidente {} cancelado.", inc_info.get_inc_id());
+            self.cancel_incident(inc_info);
+        }
     }
 
-    fn process_will_content(&mut self, will_content: WillContent) -> Result<(), Utf8Error> {
+    /// Devuelve el id para el próximo incidente Manual. Los incidentes de las cámaras tienen su propia numeración,
+    /// por lo que un mismo id puede repetirse entre ambos orígenes: se los identifica siempre por su `IncidentInfo`.
+    fn get_next_incident_id(&mut self) -> u8 {
+        self.incident_id_allocator
+            .next_info(IncidentSource::Manual)
+            .get_inc_id()
+    }
+
+    /// Procesa el will de una app que se desconectó, recibido por el topic desc.
+    fn process_will_content(&mut self, will_content: WillContent) {
+        println!("Recibido mensaje de desconexión.");
         let app_type = will_content.get_app_type_identifier();
         let id_option = will_content.get_id(); // es un option porque solo dron tiene id en este contexto.
         let place_type = PlaceType::from_app_type_will_content(&app_type);
+        // Al desconectarse, ya no se espera su heartbeat.
+        self.heartbeat_tracker.forget((app_type, id_option));
 
         match app_type {
             AppType::Cameras => self.handle_camera_disconnection(place_type),
             AppType::Dron => self.handle_drone_disconnection(id_option, place_type),
             AppType::Monitoreo => {},
         }
-        Ok(())
     }
 
     fn handle_camera_disconnection(&mut self, place_type: PlaceType) {
         // Se eliminan Todas las cámaras
-        self.places.remove_places(place_type)
+        self.places.clear_type(place_type);
+        self.camera_frame_window.clear();
+        self.cameras.clear();
     }
 
     fn handle_drone_disconnection(&mut self, id_option: Option<u8>, place_type: PlaceType) {
         if let Some(id) = id_option {
             // Se elimina el dron de id indicado, porque el mismo se desconectó.
-            self.places.remove_place(id, place_type)
+            self.places.remove_place(id, place_type);
+            self.drone_history.clear(id);
         }
     }
 
     fn handle_mqtt_messages(&mut self, ctx: &egui::Context) {
         egui::CentralPanel::default().show(ctx, |_ui| {
-            if let Ok(publish_message) = self.publish_message_rx.try_recv() {
+            // Se procesan todos los mensajes encolados, ya que el próximo frame se dibuja recién cuando llegue otro.
+            while let Ok(publish_message) = self.publish_message_rx.try_recv() {
                 self.route_message(publish_message);
             }
         });
     }
 
     fn route_message(&mut self, publish_message: PublishMessage) {
-        let topic_str = publish_message.get_topic_name();
-        if let Ok(topic) = AppsMqttTopics::topic_from_str(&topic_str) {
-            match topic {
-                AppsMqttTopics::CameraTopic => {
-                    self.handle_camera_message(publish_message)
-                },
-                AppsMqttTopics::DronTopic => {
-                    self.handle_drone_message(publish_message)
-                },
-                AppsMqttTopics::IncidentTopic => {
-                    self.handle_incident_message(publish_message)
-                },
-                AppsMqttTopics::DescTopic => {
-                    println!("Recibido mensaje de desconexión.");
-                    let _ = self.handle_disconnection_message(publish_message);
-                },
+        // Los mensajes ya encolados de un topic desactivado se descartan, para no volver a mostrarlos.
+        if !self.subscription_filter.is_enabled(&publish_message.get_topic_name()) {
+            return;
+        }
+        match AppMessage::from_publish(&publish_message) {
+            Ok(AppMessage::Camera(camera)) => self.handle_camera_message(camera),
+            Ok(AppMessage::Dron(dron)) => self.handle_drone_message(dron),
+            Ok(AppMessage::Incident(inc)) => self.handle_incident_message(inc),
+            // Se guarda el frame recibido, para poder mostrarlo en la ventana de frames de cámaras.
+            Ok(AppMessage::CameraFrame(frame)) => self.camera_frame_window.add_frame(frame),
+            Ok(AppMessage::Heartbeat(heartbeat)) => {
+                self.heartbeat_tracker.record(&heartbeat, Instant::now())
             }
+            Ok(AppMessage::Disconnect(will_content)) => self.process_will_content(will_content),
+            Err(e) => println!(
+                "UI: mensaje recibido no válido, de topic {:?}: {:?}.",
+                publish_message.get_topic_name(),
+                e
+            ),
         }
     }
 
+    /// Devuelve el recorrido reciente de cada dron, como posiciones del mapa.
+    fn get_drone_trails(&self) -> Vec<Vec<Position>> {
+        self.drone_history
+            .get_trails()
+            .into_iter()
+            .map(|trail| {
+                trail
+                    .into_iter()
+                    .map(|(lat, lon)| Position::from_lon_lat(lon, lat))
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// Devuelve el contorno del campo de visión de cada cámara orientada, como posiciones del mapa.
+    /// Las cámaras que ven en todas las direcciones no se incluyen.
+    fn get_camera_cones(&self) -> Vec<Vec<Position>> {
+        self.cameras
+            .values()
+            .filter(|camera| !camera.get_field_of_view().is_full())
+            .map(|camera| {
+                camera
+                    .get_field_of_view()
+                    .cone_outline(camera.get_position(), camera.get_range_meters(), CAMERA_CONE_ARC_POINTS)
+                    .into_iter()
+                    .map(|(lat, lon)| Position::from_lon_lat(lon, lat))
+                    .collect()
+            })
+            .collect()
+    }
+
     fn setup_map(&mut self, ctx: &egui::Context) {
         let rimless = egui::Frame {
             fill: ctx.style().visuals.panel_fill,
@@ -463,19 +1094,24 @@ impl UISistemaMonitoreo {
         egui::CentralPanel::default()
             .frame(rimless)
             .show(ctx, |ui| {
-                let my_position = places::obelisco();
+                let my_position = self.map_center;
+                let trails = self.get_drone_trails();
+                let cones = self.get_camera_cones();
                 let tiles = self
                     .providers
                     .get_mut(&self.selected_provider)
                     .unwrap()
                     .as_mut();
                 let map = Map::new(Some(tiles), &mut self.map_memory, my_position)
-                    .with_plugin(self.places.clone())
+                    .with_plugin(&mut self.places)
                     .with_plugin(super::super::plugins::images(&mut self.images_plugin_data))
-                    .with_plugin(super::super::plugins::CustomShapes {})
+                    .with_plugin(super::super::plugins::CustomShapes { trails, cones })
                     .with_plugin(&mut self.click_watcher);
 
                 ui.add(map);
+                if let Some(request) = self.places.take_cluster_zoom_request() {
+                    self.map_memory.zoom_to(request.center, request.zoom_delta);
+                }
                 self.setup_map_controls(ui);
             });
     }
@@ -497,11 +1133,71 @@ impl UISistemaMonitoreo {
         egui::TopBottomPanel::top("top_menu").show(ctx, |ui| {
             egui::menu::bar(ui, |ui| {
                 self.incident_menu(ui);
+                self.view_menu(ui);
                 self.exit_menu(ui, ctx);
             });
         });
     }
 
+    fn view_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button("Ver", |ui| {
+            self.log_tail_panel.menu(ui);
+            self.camera_frame_window.menu(ui);
+            if ui.button("Exportar estado").clicked() {
+                self.export_state(Instant::now());
+                ui.close_menu();
+            }
+            ui.separator();
+            for topic in self.subscription_filter.menu(ui) {
+                self.clear_topic_places(&topic);
+            }
+        });
+    }
+
+    /// Quita lo que se mostraba de un topic desactivado. Al reactivarlo, se vuelve a recibir su información.
+    fn clear_topic_places(&mut self, topic: &str) {
+        match AppsMqttTopics::from_str(topic) {
+            Some(AppsMqttTopics::CameraTopic) => {
+                self.places.clear_type(PlaceType::Camera);
+                self.cameras.clear();
+            }
+            Some(AppsMqttTopics::DronTopic) => {
+                self.places.clear_type(PlaceType::Dron);
+                self.drone_history.clear_all();
+            }
+            Some(AppsMqttTopics::CameraFrameTopic) => self.camera_frame_window.clear(),
+            _ => {}
+        }
+    }
+
+    /// Exporta el estado que se está mostrando: cámaras, drones, e incidentes sin resolver.
+    fn export_state(&mut self, now: Instant) {
+        let mut cameras: Vec<&Camera> = self.cameras.values().collect();
+        cameras.sort_by_key(|camera| camera.get_id());
+        let mut incidents: Vec<&Incident> = self
+            .hashmap_incidents
+            .values()
+            .map(|(incident, _)| incident)
+            .collect();
+        incidents.sort_by_key(|incident| (incident.get_source().to_byte(), incident.get_id()));
+
+        let snapshot = StateSnapshot::new(cameras, self.drone_history.get_latest(), incidents);
+        if let Err(e) = self.state_exporter.export(&snapshot, now) {
+            println!("UI: error al exportar el estado: {:?}", e);
+        }
+    }
+
+    /// Exporta el estado si corresponde la exportación periódica, y programa el repintado para la próxima.
+    fn export_state_if_due(&mut self, ctx: &egui::Context) {
+        let now = Instant::now();
+        if self.state_exporter.is_due(now) {
+            self.export_state(now);
+        }
+        if let Some(next_export) = self.state_exporter.time_until_due(now) {
+            ctx.request_repaint_after(next_export);
+        }
+    }
+
     fn incident_menu(&mut self, ui: &mut egui::Ui) {
         ui.menu_button("Incidente", |ui| {
             if !self.incident_dialog_open && ui.button("Alta Incidente").clicked() {
@@ -555,11 +1251,13 @@ impl UISistemaMonitoreo {
     }
 
     fn handle_successful_parse(&mut self, location: (f64, f64)) {
-        let incident = Incident::new(
+        let mut incident = Incident::new(
             self.get_next_incident_id(),
             location,
             IncidentSource::Manual,
         );
+        // El monitor que crea un incidente manual es su dueño
+        incident.set_owner(self.monitor_id);
         self.add_incident(&incident);
         self.send_incident_for_publish(incident);
         self.incident_dialog_open = false;
@@ -597,10 +1295,12 @@ impl UISistemaMonitoreo {
         }
     }
 
-    fn request_repaint_after(&mut self, milliseconds: u64, ctx: &egui::Context) {
-        egui::CentralPanel::default().show(ctx, |_ui| {
-            ctx.request_repaint_after(std::time::Duration::from_millis(milliseconds));
-        });
+    /// Los repintados se disparan al recibir mensajes (ver `SistemaMonitoreo`); este repintado periódico,
+    /// si se configuró, sirve para lo que cambia sin recibir mensajes (ej. ventana de error, panel de log).
+    fn request_fallback_repaint(&self, ctx: &egui::Context) {
+        if let Some(interval) = self.repaint_interval {
+            ctx.request_repaint_after(interval);
+        }
     }
     
     fn draw_ui(&mut self, ui: &mut egui::Ui) {
@@ -619,7 +1319,10 @@ impl UISistemaMonitoreo {
     }
 
     fn display_error_window(&mut self, ui: &mut egui::Ui, error: &String) {
-        if self.error_display_start.unwrap().elapsed() < Duration::from_secs(5) {
+        let elapsed = self.error_display_start.unwrap().elapsed();
+        if elapsed < Duration::from_secs(5) {
+            // Se repinta al vencer el tiempo, para ocultar la ventana aunque no lleguen mensajes.
+            ui.ctx().request_repaint_after(Duration::from_secs(5).saturating_sub(elapsed));
             let screen_size = ui.ctx().screen_rect().size();
             let window_size = egui::vec2(200.0, 200.0);
             let pos = self.calculate_center_position(screen_size, window_size);
@@ -645,6 +1348,43 @@ impl UISistemaMonitoreo {
         )
     }
 
+    /// Muestra una ventana con las apps que siguen conectadas pero de las que no se recibe su heartbeat,
+    /// y se repinta cuando la próxima quede sin respuesta, aunque no lleguen mensajes.
+    fn show_stale_apps(&self, ctx: &egui::Context) {
+        let now = Instant::now();
+        let stale_apps = self.heartbeat_tracker.get_stale_apps(now);
+        if !stale_apps.is_empty() {
+            egui::Window::new("Apps sin respuesta")
+                .collapsible(false)
+                .anchor(egui::Align2::RIGHT_TOP, egui::vec2(-10.0, 40.0))
+                .show(ctx, |ui| {
+                    for (app_type, id) in stale_apps {
+                        let label = match id {
+                            Some(id) => format!("{} {}", app_type.to_str(), id),
+                            None => app_type.to_str(),
+                        };
+                        ui.colored_label(Color32::from_rgb(255, 0, 0), label);
+                    }
+                });
+        }
+        if let Some(next_stale) = self.heartbeat_tracker.time_until_next_stale(now) {
+            ctx.request_repaint_after(next_stale);
+        }
+    }
+
+    /// Si cambió el token de Mapbox, agrega o quita sus providers. Si el provider seleccionado
+    /// deja de estar disponible, se pasa a OpenStreetMap.
+    fn update_mapbox_providers(&mut self, ctx: &egui::Context) {
+        if !self.mapbox_token.refresh(Instant::now()) {
+            return;
+        }
+        let available = if self.mapbox_token.get().is_some() { "disponible" } else { "no disponible" };
+        println!("UI: cambió el token de Mapbox, sus providers quedan: {}.", available);
+        set_mapbox_providers(&mut self.providers, self.mapbox_token.get(), ctx);
+        self.selected_provider =
+            fallback_if_unavailable(self.selected_provider, |provider| self.providers.contains_key(provider));
+    }
+
     fn draw_ui_wrapper(&mut self, ctx: &egui::Context) {
         egui::CentralPanel::default().show(ctx, |ui| {
             self.draw_ui(ui);
@@ -654,11 +1394,134 @@ impl UISistemaMonitoreo {
 
 impl eframe::App for UISistemaMonitoreo {
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        self.request_repaint_after(150, ctx);
+        self.log_tail_panel.show(ctx);
+        self.camera_frame_window.show(ctx);
+        self.request_fallback_repaint(ctx);
         self.draw_ui_wrapper(ctx);
         self.handle_mqtt_messages(ctx);
+        self.add_confirmed_pending_incidents(ctx);
+        self.expire_unresolved_incidents(ctx);
+        self.show_stale_apps(ctx);
+        self.show_active_incidents(ctx);
+        self.export_state_if_due(ctx);
+        self.update_mapbox_providers(ctx);
         self.setup_map(ctx);
         self.setup_top_menu(ctx);
         self.check_if_window_is_closed(ctx);
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{
+        advance_incident_status, fallback_if_unavailable, monitor_owns_incident, select_map_center,
+        select_provider, update_drones_en_route, IncidentWithDrones, Provider,
+    };
+    use crate::apps::{places, vendor::Position};
+    use crate::apps::incident_data::{
+        incident::{Incident, LEADER_MONITOR_ID},
+        incident_info::IncidentInfo,
+        incident_source::IncidentSource,
+        incident_status::IncidentStatus,
+    };
+    use crate::apps::sist_dron::{dron_current_info::DronCurrentInfo, dron_state::DronState};
+
+    #[test]
+    fn test_1_se_inicia_con_el_provider_configurado_o_con_openstreetmap() {
+        let without_mapbox = |provider: &Provider| {
+            !matches!(provider, Provider::MapboxStreets | Provider::MapboxSatellite)
+        };
+
+        assert_eq!(select_provider(Some("LocalTiles"), without_mapbox), Provider::LocalTiles);
+        assert_eq!(select_provider(Some(" geoportal "), without_mapbox), Provider::Geoportal);
+        // No disponible (Mapbox sin token), inválido, o ausente: OpenStreetMap
+        assert_eq!(select_provider(Some("MapboxStreets"), without_mapbox), Provider::OpenStreetMap);
+        assert_eq!(select_provider(Some("GoogleMaps"), without_mapbox), Provider::OpenStreetMap);
+        assert_eq!(select_provider(None, without_mapbox), Provider::OpenStreetMap);
+        // Si el seleccionado deja de estar disponible (ej. se quitó el token de Mapbox), se vuelve a OpenStreetMap
+        assert_eq!(fallback_if_unavailable(Provider::MapboxStreets, without_mapbox), Provider::OpenStreetMap);
+        assert_eq!(fallback_if_unavailable(Provider::Geoportal, without_mapbox), Provider::Geoportal);
+    }
+
+    #[test]
+    fn test_2_los_drones_que_aceptan_un_incidente_quedan_en_camino_hasta_llegar_o_abandonarlo() {
+        let inc_info = IncidentInfo::new(1, IncidentSource::Manual);
+        let dron_with_state = |id: u8, state: DronState, inc: Option<IncidentInfo>| {
+            let mut dron = DronCurrentInfo::new(id, -34.0, -58.0, 100, state);
+            if let Some(inc_info) = inc {
+                dron.set_inc_id_to_resolve(inc_info);
+            }
+            dron
+        };
+        let mut incidents: Vec<IncidentWithDrones> = vec![];
+
+        update_drones_en_route(
+            &mut incidents,
+            &dron_with_state(1, DronState::MustRespondToIncident, Some(inc_info)),
+        );
+        update_drones_en_route(
+            &mut incidents,
+            &dron_with_state(2, DronState::Flying, Some(inc_info)),
+        );
+        update_drones_en_route(
+            &mut incidents,
+            &dron_with_state(2, DronState::Flying, Some(inc_info)),
+        );
+        assert_eq!(incidents.len(), 1);
+        assert_eq!(incidents[0].drones_en_route, vec![1, 2]);
+
+        // El dron 1 llega, y el 2 abandona el incidente para ir a recargarse
+        update_drones_en_route(
+            &mut incidents,
+            &dron_with_state(1, DronState::ManagingIncident, Some(inc_info)),
+        );
+        update_drones_en_route(
+            &mut incidents,
+            &dron_with_state(2, DronState::GoingToMaintenance, None),
+        );
+        assert!(incidents[0].drones_en_route.is_empty());
+    }
+
+    #[test]
+    fn test_3_el_centro_del_mapa_es_el_configurado_o_el_obelisco() {
+        assert_eq!(
+            select_map_center(Some("-31.4167"), Some(" -64.1833 ")),
+            Position::from_lat_lon(-31.4167, -64.1833)
+        );
+        // Ausente, fuera de rango, o no numérico: el Obelisco
+        assert_eq!(select_map_center(None, Some("-64.18")), places::obelisco());
+        assert_eq!(select_map_center(Some("-91.0"), Some("-64.18")), places::obelisco());
+        assert_eq!(select_map_center(Some("-31.41"), Some("200")), places::obelisco());
+        assert_eq!(select_map_center(Some("norte"), Some("-64.18")), places::obelisco());
+    }
+
+    #[test]
+    fn test_4_cada_monitor_resuelve_solamente_los_incidentes_de_los_que_es_duenio() {
+        let mut manual = Incident::new(1, (-34.6, -58.4), IncidentSource::Manual);
+        manual.set_owner(2);
+        assert!(monitor_owns_incident(&manual.get_info(), Some(&manual), 2));
+        assert!(!monitor_owns_incident(&manual.get_info(), Some(&manual), LEADER_MONITOR_ID));
+        // Un manual que este monitor no tiene guardado es de otro monitor
+        assert!(!monitor_owns_incident(&manual.get_info(), None, LEADER_MONITOR_ID));
+
+        // Los automáticos sin dueño son del líder
+        let automated = IncidentInfo::new(1, IncidentSource::Automated);
+        assert!(monitor_owns_incident(&automated, None, LEADER_MONITOR_ID));
+        assert!(!monitor_owns_incident(&automated, None, 2));
+    }
+
+    #[test]
+    fn test_5_el_status_avanza_con_los_drones_de_a_una_etapa_y_nunca_retrocede() {
+        let mut incident = Incident::new(1, (-34.6, -58.4), IncidentSource::Manual);
+        // Llega el primer dron sin que se lo haya visto en camino: pasa por Assigned
+        assert!(advance_incident_status(&mut incident, IncidentStatus::Resolving));
+        assert_eq!(incident.get_status(), IncidentStatus::Resolving);
+        // Otro dron en camino no lo vuelve a Assigned
+        assert!(!advance_incident_status(&mut incident, IncidentStatus::Assigned));
+        assert_eq!(incident.get_status(), IncidentStatus::Resolving);
+
+        assert!(advance_incident_status(&mut incident, IncidentStatus::Resolved));
+        assert!(!advance_incident_status(&mut incident, IncidentStatus::Resolved));
+        assert_eq!(incident.get_status(), IncidentStatus::Resolved);
+    }
+}
@@ -0,0 +1,35 @@
+use std::io::{Error, ErrorKind};
+
+/// Los bytes de los structs que publican las apps (`Incident`, `Camera`, `DronCurrentInfo`) comienzan con la
+/// versión del formato con que se escribieron. Cada versión solamente agrega campos al final de la anterior:
+/// así quien lee una versión más nueva que la suya lee los campos que conoce e ignora los que siguen, y quien
+/// lee una versión anterior completa con valores por defecto los campos que ella no trae.
+///
+/// Separa la versión de los bytes de los campos. Devuelve error si no hay bytes, o si la versión es 0
+/// (las versiones comienzan en 1). `type_name` es el nombre del struct, para el mensaje de error.
+pub fn split_version<'a>(bytes: &'a [u8], type_name: &str) -> Result<(u8, &'a [u8]), Error> {
+    match bytes.split_first() {
+        Some((0, _)) => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("Versión de formato no válida para un {}", type_name),
+        )),
+        Some((version, fields)) => Ok((*version, fields)),
+        None => Err(Error::new(
+            ErrorKind::InvalidData,
+            format!("No hay bytes para un {}", type_name),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::split_version;
+
+    #[test]
+    fn test_1_se_separa_la_version_de_los_campos_y_la_version_0_no_es_valida() {
+        assert_eq!(split_version(&[2, 7, 8], "Camera").unwrap(), (2, &[7, 8][..]));
+        assert_eq!(split_version(&[1], "Camera").unwrap(), (1, &[][..]));
+        assert!(split_version(&[0, 7], "Camera").is_err());
+        assert!(split_version(&[], "Camera").is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use std::{
+    io::{Error, ErrorKind},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Marca con que comienza un registro de publish en una línea del log.
+const PUBLISH_RECORD_TAG: &str = "PUBLISH_RECORD";
+
+/// Registro de un publish enviado, que se escribe al log en una línea parseable por máquina, de la forma
+/// `PUBLISH_RECORD <timestamp en ms> <qos> <payload en hex> <topic>`. El timestamp es en milisegundos desde
+/// epoch, para que al reproducir los publish (ver `PublishReplay`) se respeten los tiempos entre ellos.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PublishRecord {
+    timestamp_ms: u64,
+    topic: String,
+    qos: u8,
+    payload: Vec<u8>,
+}
+
+impl PublishRecord {
+    pub fn new(timestamp_ms: u64, topic: &str, qos: u8, payload: &[u8]) -> Self {
+        Self {
+            timestamp_ms,
+            topic: topic.to_string(),
+            qos,
+            payload: payload.to_vec(),
+        }
+    }
+
+    /// Crea el registro de un publish enviado en este momento.
+    pub fn now(topic: &str, qos: u8, payload: &[u8]) -> Self {
+        let timestamp_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_millis() as u64)
+            .unwrap_or(0);
+        Self::new(timestamp_ms, topic, qos, payload)
+    }
+
+    /// Devuelve la línea a escribir al log.
+    pub fn to_log_line(&self) -> String {
+        format!(
+            "{} {} {} {} {}",
+            PUBLISH_RECORD_TAG,
+            self.timestamp_ms,
+            self.qos,
+            hex::encode(&self.payload),
+            self.topic
+        )
+    }
+
+    /// Parsea el registro de una línea del log (que puede comenzar con el timestamp que agrega el logger).
+    /// Devuelve Ok(None) si la línea no es un registro de publish, o error si lo es pero está mal formado.
+    pub fn from_log_line(line: &str) -> Result<Option<Self>, Error> {
+        let start = match line.find(PUBLISH_RECORD_TAG) {
+            Some(start) => start + PUBLISH_RECORD_TAG.len(),
+            None => return Ok(None),
+        };
+        let fields: Vec<&str> = line[start..].trim().splitn(4, ' ').collect();
+        if fields.len() != 4 || fields[3].is_empty() {
+            return Err(invalid_record(line));
+        }
+        let timestamp_ms = fields[0].parse::<u64>().map_err(|_| invalid_record(line))?;
+        let qos = fields[1].parse::<u8>().map_err(|_| invalid_record(line))?;
+        let payload = hex::decode(fields[2]).map_err(|_| invalid_record(line))?;
+
+        Ok(Some(Self::new(timestamp_ms, fields[3], qos, &payload)))
+    }
+
+    pub fn get_timestamp_ms(&self) -> u64 {
+        self.timestamp_ms
+    }
+
+    pub fn get_topic(&self) -> &str {
+        &self.topic
+    }
+
+    pub fn get_qos(&self) -> u8 {
+        self.qos
+    }
+
+    pub fn get_payload(&self) -> &[u8] {
+        &self.payload
+    }
+}
+
+fn invalid_record(line: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("Registro de publish mal formado: {}", line),
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::PublishRecord;
+
+    #[test]
+    fn test_1_el_registro_escrito_al_log_se_vuelve_a_parsear() {
+        let record = PublishRecord::new(1_700_000_000_123, "cam_frame", 1, &[0, 1, 254, 255]);
+        // El logger agrega el timestamp al comienzo de la línea
+        let line = format!("[16/10/2026 12:00:00] {}", record.to_log_line());
+
+        assert_eq!(PublishRecord::from_log_line(&line).unwrap(), Some(record));
+        assert_eq!(PublishRecord::from_log_line("[16/10/2026 12:00:00] Publico inc").unwrap(), None);
+        assert!(PublishRecord::from_log_line("PUBLISH_RECORD 12 1 zz inc").is_err());
+    }
+}
@@ -1,6 +1,6 @@
-use std::{sync::mpsc::{self, Sender}, thread::JoinHandle};
+use std::{sync::mpsc::{self, Receiver, Sender}, thread::JoinHandle};
 
-use super::string_logger_writer::StringLoggerWriter;
+use super::string_logger_writer::{default_log_file, StringLoggerWriter};
 
 #[derive(Debug)]
 pub struct StringLogger {
@@ -20,6 +20,37 @@ impl StringLogger {
         (logger, handle_logger)
     }
 
+    /// Análoga a `create_logger`, pero además devuelve un `Receiver` por el cual se recibe cada línea
+    /// escrita al archivo de log (con su timestamp), para poder mostrarla por ejemplo en una interfaz gráfica.
+    pub fn create_logger_with_ui_receiver(id: String) -> (StringLogger, JoinHandle<()>, Receiver<String>) {
+        let log_file = default_log_file(&id);
+        Self::create_logger_with_ui_receiver_to_file(id, log_file)
+    }
+
+    /// Análoga a `create_logger`, pero escribe al archivo `log_file` (ej. el de la configuración de la app).
+    pub fn create_logger_to_file(id: String, log_file: String) -> (StringLogger, JoinHandle<()>) {
+        let (string_logger_tx, string_logger_rx) = mpsc::channel::<String>();
+        let logger = StringLogger::new(string_logger_tx);
+        let logger_writer = StringLoggerWriter::new(id, string_logger_rx).writing_to(log_file);
+        let handle_logger = logger_writer.spawn_event_listening_thread_to_write_to_file();
+
+        (logger, handle_logger)
+    }
+
+    /// Análoga a `create_logger_with_ui_receiver`, pero escribe al archivo `log_file`.
+    pub fn create_logger_with_ui_receiver_to_file(
+        id: String,
+        log_file: String,
+    ) -> (StringLogger, JoinHandle<()>, Receiver<String>) {
+        let (string_logger_tx, string_logger_rx) = mpsc::channel::<String>();
+        let (ui_tx, ui_rx) = mpsc::channel::<String>();
+        let logger = StringLogger::new(string_logger_tx);
+        let logger_writer = StringLoggerWriter::new_with_ui_tx(id, string_logger_rx, ui_tx).writing_to(log_file);
+        let handle_logger = logger_writer.spawn_event_listening_thread_to_write_to_file();
+
+        (logger, handle_logger, ui_rx)
+    }
+
     /// Extremo de envío del string logger.
     /// Es el encargado de enviar las strings a ser loggueadas.
     pub fn new(tx: Sender<String>) -> Self {
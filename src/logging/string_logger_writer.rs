@@ -1,44 +1,72 @@
 use std::{
     io::{Error, Write},
-    sync::mpsc::Receiver, thread::{self, JoinHandle},
+    sync::mpsc::{Receiver, Sender}, thread::{self, JoinHandle},
 };
 
 use super::time::Time;
 
+/// Devuelve el archivo de log que se usa por defecto para la app de id `id`.
+pub fn default_log_file(id: &str) -> String {
+    format!("s_log_{}.txt", id)
+}
+
 #[derive(Debug)]
 pub struct StringLoggerWriter {
     pub id: String,
     pub logger_rx: Receiver<String>,
+    ui_tx: Option<Sender<String>>,
+    log_file: String,
 }
 
 impl StringLoggerWriter {
     /// Crea el extremo de escritura del string logger.
     /// Es el encargado de recibir lo enviado por el otro extremo, y escribirlo a disco.
     pub fn new(id: String, logger_rx: Receiver<String>) -> Self {
-        Self { id, logger_rx }
+        let log_file = default_log_file(&id);
+        Self { id, logger_rx, ui_tx: None, log_file }
     }
 
-    /// Escribe el mensaje recibido al archivo de log.
-    fn write_to_file(&self, message: String) -> Result<(), Error> {
-        
-        let filename = format!("s_log_{}.txt", self.id);
+    /// Crea el extremo de escritura del string logger, que además de escribir a disco cada línea,
+    /// la reenvía por `ui_tx` para que pueda ser mostrada (ej. en una interfaz gráfica).
+    pub fn new_with_ui_tx(id: String, logger_rx: Receiver<String>, ui_tx: Sender<String>) -> Self {
+        let log_file = default_log_file(&id);
+        Self { id, logger_rx, ui_tx: Some(ui_tx), log_file }
+    }
+
+    /// Escribe al archivo `log_file`, en lugar de al archivo por defecto.
+    pub fn writing_to(mut self, log_file: String) -> Self {
+        self.log_file = log_file;
+        self
+    }
 
-        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(filename)?;
+    /// Escribe el mensaje recibido al archivo de log.
+    fn write_to_file(&self, line: &str) -> Result<(), Error> {
+        let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&self.log_file)?;
 
-        let string_timestamp = Time::now_as_string();
-        writeln!(file, "{} {}",string_timestamp, message)?;
+        writeln!(file, "{}", line)?;
 
         Ok(())
     }
 
+    /// Reenvía la línea a la ui, si se configuró. Si la ui ya no está escuchando, deja de reenviar.
+    fn forward_to_ui(&mut self, line: String) {
+        if let Some(ui_tx) = &self.ui_tx {
+            if ui_tx.send(line).is_err() {
+                self.ui_tx = None;
+            }
+        }
+    }
+
     /// Lanza hilo que recibe por rx cada string a logguear, y la escribe en el archivo.
-    pub fn spawn_event_listening_thread_to_write_to_file(self
+    pub fn spawn_event_listening_thread_to_write_to_file(mut self
     ) -> JoinHandle<()> {
         thread::spawn(move || {
             while let Ok(msg) = self.logger_rx.recv() {
-                if self.write_to_file(msg).is_err() {
+                let line = format!("{} {}", Time::now_as_string(), msg);
+                if self.write_to_file(&line).is_err() {
                     println!("LoggerWriter: error al escribir al archivo de log.");
                 }
+                self.forward_to_ui(line);
             }
         })
     }
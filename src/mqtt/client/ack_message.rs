@@ -1,11 +1,14 @@
 //use std::fmt;
 
-use crate::mqtt::messages::{puback_message::PubAckMessage, suback_message::SubAckMessage};
+use crate::mqtt::messages::{
+    puback_message::PubAckMessage, suback_message::SubAckMessage, unsuback_message::Unsuback,
+};
 
 #[derive(Debug)]
 pub enum ACKMessage {
     PubAck(PubAckMessage),
     SubAck(SubAckMessage),
+    UnsubAck(Unsuback),
 }
 
 impl ACKMessage {
@@ -13,6 +16,7 @@ impl ACKMessage {
         match self {
             ACKMessage::PubAck(pub_ack_message) => Some(pub_ack_message.get_packet_id()),
             ACKMessage::SubAck(sub_ack_message) => Some(sub_ack_message.get_packet_id()),
+            ACKMessage::UnsubAck(unsub_ack_message) => Some(unsub_ack_message.get_packet_id()),
         }
     }
 }
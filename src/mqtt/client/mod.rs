@@ -3,4 +3,8 @@ pub mod mqtt_client_listener;
 pub mod mqtt_client_connector;
 pub mod mqtt_client_msg_creator;
 pub mod ack_message;
-pub mod mqtt_client_retransmitter;
\ No newline at end of file
+pub mod mqtt_client_retransmitter;
+pub mod mqtt_client_stats;
+pub mod publisher;
+pub mod reconnection;
+pub mod unacked_publishes;
@@ -1,25 +1,69 @@
-use crate::logging::string_logger::StringLogger;
+use crate::logging::{publish_record::PublishRecord, string_logger::StringLogger};
 use crate::mqtt::client::{
-    mqtt_client_listener::MQTTClientListener, mqtt_client_retransmitter::Retransmitter,
+    ack_message::ACKMessage,
+    mqtt_client_listener::{ConnectionEnd, ListenerOutcome, MQTTClientListener},
+    mqtt_client_retransmitter::{Retransmitter, DEFAULT_ACK_WAITING_INTERVAL, DEFAULT_AMOUNT_OF_RETRIES},
     mqtt_client_connector::MqttClientConnector,
     mqtt_client_msg_creator::MessageCreator,
+    mqtt_client_stats::{MQTTClientStats, MQTTClientStatsSnapshot},
+    unacked_publishes::{UnackedPublishes, DEFAULT_UNACKED_CAPACITY},
 };
-use crate::mqtt::messages::publish_message::PublishMessage;
+use crate::mqtt::messages::{connack_message::ConnackMessage, publish_message::PublishMessage};
+use crate::mqtt::mqtt_utils::mqtt_error::MqttError;
+use crate::mqtt::mqtt_utils::payload_compression::compress_payload;
+use crate::mqtt::mqtt_utils::shutdown_token::ShutdownToken;
+use crate::mqtt::mqtt_utils::topic::{Topic, TopicFilter};
+use crate::mqtt::mqtt_utils::topic_alias::{OutgoingTopicAliases, TOPIC_ALIAS_MAXIMUM};
 use crate::mqtt::mqtt_utils::will_message_utils::will_message::WillMessageData;
 use std::net::TcpStream;
 use std::{
-    io::Error,
+    io::{Error, ErrorKind},
     net::SocketAddr,
-    sync::mpsc::{self, Receiver},
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
     thread::{self, JoinHandle},
+    time::Duration,
 };
 
 pub type ClientStreamType = TcpStream; // Aux: que solo lo use el cliente por ahora, para hacer refactor más fácil.
 
+/// Cantidad máxima de publish de qos 1 sin ack, si no se configura otra (ver `set_receive_maximum`).
+pub const DEFAULT_RECEIVE_MAXIMUM: u16 = 32;
+
+/// Tx por el que el listener envía los PublishMessages a la app. Mientras la conexión está perdida, el listener
+/// que terminó lo deja guardado acá, junto con el motivo por el que se perdió, para que el listener de la
+/// nueva conexión siga enviando por el mismo channel.
+type LostPublishTxType = Arc<Mutex<Option<(Sender<PublishMessage>, ConnectionEnd)>>>;
+
+/// Parámetros con los que se efectuó la conexión, para volver a utilizarlos al reconectarse.
+#[derive(Debug)]
+struct ConnectionParams {
+    client_id: String,
+    addr: SocketAddr,
+    will: Option<WillMessageData>,
+    ack_timeout: Duration,
+    max_retries: u8,
+    keep_alive: u16,
+    topic_aliases: bool,
+}
+
 #[derive(Debug)]
 pub struct MQTTClient {
     msg_creator: MessageCreator,
     retransmitter: Retransmitter,
+    stats: MQTTClientStats,
+    shutdown_token: ShutdownToken,
+    connection_params: ConnectionParams,
+    lost_publish_tx: LostPublishTxType,
+    session_present: bool,
+    unacked: UnackedPublishes,
+    receive_maximum: u16,
+    topic_aliases: OutgoingTopicAliases, // de la conexión actual, ver `topic_alias`
+    compression_threshold: Option<usize>, // ver `set_payload_compression_threshold`
+    record_publishes: bool, // ver `set_publish_recording`
     logger: StringLogger,
 }
 
@@ -33,50 +77,375 @@ impl MQTTClient {
         will: Option<WillMessageData>,
         logger: StringLogger,
     ) -> Result<(Self, Receiver<PublishMessage>, JoinHandle<()>), Error> {
+        Self::mqtt_connect_to_broker_with_retransmission(
+            client_id,
+            addr,
+            will,
+            Duration::from_millis(DEFAULT_ACK_WAITING_INTERVAL),
+            DEFAULT_AMOUNT_OF_RETRIES,
+            0, // Sin keep alive: el server no desconecta al cliente por inactividad
+            false,
+            logger,
+        )
+    }
+
+    /// Igual que `mqtt_connect_to_broker`, pero permite configurar el tiempo `ack_timeout` que se espera por cada ack,
+    /// la cantidad máxima de retransmisiones `max_retries`, luego de las cuales el publish o subscribe devuelve error,
+    /// y el `keep_alive` en segundos: si es distinto de 0, el server desconecta al cliente (publicando su will)
+    /// si no recibe ningún mensaje suyo en ese tiempo, por lo que ante inactividad debe llamarse a `mqtt_ping`.
+    /// Con `topic_aliases` se solicitan al server los alias de topic (ver `topic_alias`): es una extensión propia,
+    /// que se indica con el flag reservado del connect, por lo que un broker MQTT 3.1.1 estándar rechazaría
+    /// la conexión. Solamente debe habilitarse con el server de este proyecto.
+    #[allow(clippy::too_many_arguments)]
+    pub fn mqtt_connect_to_broker_with_retransmission(
+        client_id: String,
+        addr: &SocketAddr,
+        will: Option<WillMessageData>,
+        ack_timeout: Duration,
+        max_retries: u8,
+        keep_alive: u16,
+        topic_aliases: bool,
+        logger: StringLogger,
+    ) -> Result<(Self, Receiver<PublishMessage>, JoinHandle<()>), Error> {
+        let connection_params = ConnectionParams {
+            client_id,
+            addr: *addr,
+            will,
+            ack_timeout,
+            max_retries,
+            keep_alive,
+            topic_aliases,
+        };
         // Efectúa la conexión al server
-        let stream = MqttClientConnector::mqtt_connect_to_broker(client_id, addr, will, logger.clone_ref())?;
+        let (stream, connack) = connection_params.connect(logger.clone_ref())?;
         // Inicializa sus partes internas
         let writer = MessageCreator::new();
         let (publish_msg_tx, publish_msg_rx) = mpsc::channel::<PublishMessage>();
-        let (retransmitter, ack_tx) = Retransmitter::new(stream.try_clone()?, logger.clone_ref());
-        let mut listener = MQTTClientListener::new(stream.try_clone()?, publish_msg_tx, ack_tx);
-        
-        let logger_c = logger.clone_ref();
+        let stats = MQTTClientStats::new();
+        let (retransmitter, ack_tx) = Retransmitter::new_with_config(
+            stream.try_clone()?,
+            stats.clone_ref(),
+            ack_timeout,
+            max_retries,
+            logger.clone_ref(),
+        );
+        let shutdown_token = ShutdownToken::new();
+
         let mqtt_client = MQTTClient {
             msg_creator: writer,
             retransmitter,
+            stats,
+            shutdown_token,
+            connection_params,
+            lost_publish_tx: Arc::new(Mutex::new(None)),
+            session_present: connack.session_present(),
+            unacked: UnackedPublishes::new(DEFAULT_UNACKED_CAPACITY, logger.clone_ref()),
+            receive_maximum: DEFAULT_RECEIVE_MAXIMUM,
+            topic_aliases: topic_aliases_for(&connack),
+            compression_threshold: None,
+            record_publishes: false,
             logger,
         };
+        let listener_handle = mqtt_client.spawn_listener(stream, publish_msg_tx, ack_tx)?;
+
+        Ok((mqtt_client, publish_msg_rx, listener_handle))
+    }
 
-        let listener_handle = thread::spawn(move || {
-            if let Err(e) = listener.read_from_server(){
+    /// Lanza el hilo del listener, que lee del `stream` y envía los PublishMessages recibidos por `publish_msg_tx`.
+    /// Si el listener termina sin que se haya solicitado el shutdown token, se perdió la conexión (ej. el server
+    /// se reinició): deja guardado el `publish_msg_tx`, con lo que `is_connection_lost` pasa a devolver true,
+    /// y `get_connection_end` el motivo. Si se solicitó, el `publish_msg_tx` se descarta y el channel se cierra.
+    fn spawn_listener(
+        &self,
+        stream: ClientStreamType,
+        publish_msg_tx: Sender<PublishMessage>,
+        ack_tx: Sender<ACKMessage>,
+    ) -> Result<JoinHandle<()>, Error> {
+        let mut listener = MQTTClientListener::new(
+            stream,
+            publish_msg_tx,
+            ack_tx,
+            self.stats.clone_ref(),
+            self.shutdown_token.clone(),
+        );
+        let shutdown_token = self.shutdown_token.clone();
+        let lost_publish_tx = self.lost_publish_tx.clone();
+        let logger_c = self.logger.clone_ref();
+
+        Ok(thread::spawn(move || {
+            let outcome = listener.read_from_server();
+            if let ListenerOutcome::Error(e) = &outcome {
                 logger_c.log(format!("Error al leer, en read_from_server: {:?}", e));
             }
-        });
+            // Se consulta el token con el lock tomado, para no cruzarse con un `mqtt_disconnect` simultáneo
+            if let Ok(mut lost_publish_tx) = lost_publish_tx.lock() {
+                match outcome.to_connection_end() {
+                    Some(connection_end) if !shutdown_token.is_requested() => {
+                        logger_c.log(format!(
+                            "Mqtt: se perdió la conexión con el server: {:?}.",
+                            connection_end
+                        ));
+                        *lost_publish_tx = Some((listener.into_client_tx(), connection_end));
+                    }
+                    _ => {}
+                }
+            }
+        }))
+    }
 
-        Ok((mqtt_client, publish_msg_rx, listener_handle))
+    /// Devuelve si se perdió la conexión con el server sin haberse solicitado `mqtt_disconnect`.
+    pub fn is_connection_lost(&self) -> bool {
+        self.get_connection_end().is_some()
+    }
+
+    /// Devuelve el motivo por el que se perdió la conexión con el server (si el server la cerró, o por un error),
+    /// o None si no se perdió.
+    pub fn get_connection_end(&self) -> Option<ConnectionEnd> {
+        match self.lost_publish_tx.lock() {
+            Ok(lost_publish_tx) => lost_publish_tx
+                .as_ref()
+                .map(|(_, connection_end)| connection_end.clone()),
+            Err(_) => None,
+        }
+    }
+
+    /// Vuelve a conectarse al server, con los mismos parámetros de la conexión original, luego de perderse la conexión.
+    /// Los PublishMessages recibidos se siguen enviando por el rx devuelto al conectarse. Si el server no conservaba
+    /// la sesión (ver `is_session_present`), las suscripciones no se recuperan y la app debe volver a suscribirse.
+    /// Si la conexión no se perdió, o no se pudo reconectar,
+    /// devuelve error (y en el último caso puede volver a intentarse).
+    /// El hilo del nuevo listener no se devuelve: termina al cerrarse la conexión, como el original.
+    pub fn mqtt_reconnect(&mut self) -> Result<(), Error> {
+        let publish_msg_tx = match self.lost_publish_tx.lock() {
+            Ok(mut lost_publish_tx) => lost_publish_tx.take(),
+            Err(_) => None,
+        };
+        let (publish_msg_tx, connection_end) = match publish_msg_tx {
+            Some(lost) => lost,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    "No se perdió la conexión, no es necesario reconectarse.",
+                ))
+            }
+        };
+
+        match self.connection_params.connect(self.logger.clone_ref()) {
+            Ok((stream, connack)) => {
+                self.session_present = connack.session_present();
+                self.topic_aliases = topic_aliases_for(&connack);
+                let (retransmitter, ack_tx) = Retransmitter::new_with_config(
+                    stream.try_clone()?,
+                    self.stats.clone_ref(),
+                    self.connection_params.ack_timeout,
+                    self.connection_params.max_retries,
+                    self.logger.clone_ref(),
+                );
+                self.retransmitter = retransmitter;
+                self.spawn_listener(stream, publish_msg_tx, ack_tx)?;
+                self.logger.log("Mqtt: reconectado al server.".to_string());
+                if let Err(e) = self.replay_unacked_publishes() {
+                    self.logger
+                        .log(format!("Mqtt: error al volver a publicar los publish sin ack: {:?}.", e));
+                }
+                Ok(())
+            }
+            Err(e) => {
+                // Se vuelve a guardar el tx, para un próximo intento
+                if let Ok(mut lost_publish_tx) = self.lost_publish_tx.lock() {
+                    *lost_publish_tx = Some((publish_msg_tx, connection_end));
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Devuelve si, en la última conexión, el server conservaba una sesión del cliente: de ser así,
+    /// mantiene sus suscripciones y no es necesario volver a suscribirse.
+    pub fn is_session_present(&self) -> bool {
+        self.session_present
+    }
+
+    /// Persiste los publish de qos 1 sin ack en archivos de `spill_dir`, conservando como máximo `capacity`,
+    /// para no perderlos si la app termina abruptamente. Carga los que hayan quedado de una ejecución anterior:
+    /// se vuelven a publicar al reconectarse, o al llamar a `replay_unacked_publishes`.
+    pub fn set_unacked_spill_dir(&mut self, spill_dir: &Path, capacity: usize) -> Result<(), Error> {
+        let mut unacked = UnackedPublishes::with_spill_dir(capacity, spill_dir, self.logger.clone_ref())?;
+        for msg in self.unacked.get_pending() {
+            unacked.add(&msg);
+        }
+        self.unacked = unacked;
+        Ok(())
+    }
+
+    /// Persiste en `state_file` el último packet id usado y los de los publish sin ack (ver `MessageCreator`),
+    /// para que un nuevo cliente con el mismo client_id continúe desde allí. Si el server conservaba la sesión del cliente (ver `is_session_present`), los
+    /// packet ids continúan desde los de la ejecución anterior, que el server podría considerar todavía en vuelo;
+    /// si no, comienzan desde 0. Debe llamarse luego de conectarse, antes de enviar mensajes.
+    pub fn set_packet_id_state_file(&mut self, state_file: &Path) -> Result<(), Error> {
+        self.msg_creator = MessageCreator::with_state_file(state_file, !self.session_present)?;
+        Ok(())
+    }
+
+    /// Configura la cantidad máxima de publish de qos 1 sin ack ("en vuelo"): alcanzado el máximo, `mqtt_publish`
+    /// no envía nuevos publish de qos 1 hasta que lleguen acks que liberen lugar.
+    pub fn set_receive_maximum(&mut self, receive_maximum: u16) {
+        self.receive_maximum = receive_maximum;
+    }
+
+    /// Configura que los payloads de más de `threshold` bytes se publiquen comprimidos con gzip (o no, con None,
+    /// que es el valor inicial). Quien los recibe los descomprime al leerlos (ver `PublishMessage::get_payload`);
+    /// los que no superan el umbral, o no se achican al comprimirlos, se publican sin comprimir.
+    pub fn set_payload_compression_threshold(&mut self, threshold: Option<usize>) {
+        self.compression_threshold = threshold;
+    }
+
+    /// Configura si se registra en el log cada publish enviado, con su payload completo, para poder reproducirlos
+    /// luego (ver `PublishReplay`). Inicialmente no se registran: con payloads grandes (ej. frames) el log crece
+    /// rápidamente, y contendría los datos publicados sin encriptar.
+    pub fn set_publish_recording(&mut self, record_publishes: bool) {
+        self.record_publishes = record_publishes;
+    }
+
+    /// Vuelve a publicar, en orden, los publish de qos 1 cuyo ack no se recibió, con su packet id original y el
+    /// flag dup: si el server ya había procesado alguno (y se perdió su ack), no vuelve a propagarlo.
+    /// Si alguno falla, se detiene y devuelve el error: éste y los siguientes quedan para un próximo intento.
+    /// Devuelve la cantidad de publish cuyo ack se recibió.
+    pub fn replay_unacked_publishes(&mut self) -> Result<usize, MqttError> {
+        let pending = self.unacked.get_pending();
+        if !pending.is_empty() {
+            self.logger
+                .log(format!("Mqtt: volviendo a publicar {} publish sin ack.", pending.len()));
+        }
+        let mut replayed = 0;
+        for msg in pending {
+            let msg_to_send = self.topic_aliases.apply(&msg.as_dup()?);
+            self.retransmitter.send_and_retransmit(&msg_to_send)?;
+            if let Some(packet_id) = msg.get_packet_id() {
+                self.release_unacked(packet_id);
+            }
+            self.logger.log(format!("Mqtt: publish sin ack vuelto a publicar: \n   {:?}", msg));
+            replayed += 1;
+        }
+        Ok(replayed)
     }
 
     /// Función de la librería de MQTTClient para realizar un publish.
-    pub fn mqtt_publish(
-        &mut self,
-        topic: &str,
-        payload: &[u8],
-        qos: u8,
-    ) -> Result<PublishMessage, Error> {
+    /// Con qos 1, bloquea hasta recibir el ack (retransmitiendo si es necesario): devolver Ok implica que
+    /// el ack se recibió. Si se agotan las retransmisiones sin recibirlo, devuelve `MqttError::Timeout`
+    /// (ver `MqttError::is_ack_timeout`), para que la app decida si reintentar o alertar; el publish
+    /// se conserva como sin ack, y se lo vuelve a publicar al reconectarse (ver `replay_unacked_publishes`).
+    /// Si ya hay `receive_maximum` publish de qos 1 sin ack, no se envía y devuelve un error de tipo
+    /// `ErrorKind::WouldBlock`: la app puede volver a intentarlo cuando lleguen sus acks.
+    /// El `topic` puede ser un `Topic`, un topic de las apps, o un `&str`/`String` que se valida antes de
+    /// enviar (devolviendo `MqttError::Protocol` si no es un nombre de topic válido).
+    pub fn mqtt_publish<T>(&mut self, topic: T, payload: &[u8], qos: u8) -> Result<PublishMessage, MqttError>
+    where
+        T: TryInto<Topic>,
+        MqttError: From<T::Error>,
+    {
+        let topic: Topic = topic.try_into()?;
+        if qos == 1 {
+            self.check_in_flight_window()?;
+        }
         // Esto solamente crea y devuelve el mensaje
-        let msg = self.msg_creator.create_publish_msg(topic, payload, qos)?;
-        // Se lo paso al retransmitter y que él se encargue de mandarlo, y retransmitirlo si es necesario
-        self.retransmitter.send_and_retransmit(&msg)?;
+        let msg = match self.compress_if_above_threshold(payload) {
+            Some(compressed) => self
+                .msg_creator
+                .create_compressed_publish_msg(topic.as_str(), &compressed, qos)?,
+            None => self.msg_creator.create_publish_msg(topic.as_str(), payload, qos)?,
+        };
+        if qos == 1 {
+            self.unacked.add(&msg);
+        }
+        // Se lo paso al retransmitter y que él se encargue de mandarlo, y retransmitirlo si es necesario.
+        // Se envía con alias de topic si corresponde, pero se conserva y devuelve con el topic completo.
+        let msg_to_send = self.topic_aliases.apply(&msg);
+        self.retransmitter.send_and_retransmit(&msg_to_send)?;
+        if let Some(packet_id) = msg.get_packet_id() {
+            self.release_unacked(packet_id);
+        }
 
         //println!("-----------------\n Mqtt: publish enviado: \n   {:?}", msg);
         self.logger.log(format!("-----------------\n Mqtt: publish enviado: \n   {:?}", msg));
+        // Registro parseable del publish, para poder reproducirlo (ver `PublishReplay`)
+        if self.record_publishes {
+            self.logger.log(PublishRecord::now(topic.as_str(), qos, payload).to_log_line());
+        }
 
         Ok(msg)
     }
 
+    /// Devuelve el `payload` comprimido, si supera el umbral de compresión y comprimido resulta más chico.
+    /// Registra en el log cuánto se lo achicó.
+    fn compress_if_above_threshold(&self, payload: &[u8]) -> Option<Vec<u8>> {
+        let threshold = self.compression_threshold?;
+        if payload.len() <= threshold {
+            return None;
+        }
+        match compress_payload(payload) {
+            Ok(Some(compressed)) => {
+                self.logger.log(format!(
+                    "Mqtt: payload comprimido de {} a {} bytes (ratio {:.2}).",
+                    payload.len(),
+                    compressed.len(),
+                    compressed.len() as f64 / payload.len() as f64
+                ));
+                Some(compressed)
+            }
+            Ok(None) => {
+                self.logger.log(format!(
+                    "Mqtt: payload de {} bytes no se achica al comprimirlo, se lo envía sin comprimir.",
+                    payload.len()
+                ));
+                None
+            }
+            Err(e) => {
+                self.logger.log(format!("ERROR: al comprimir payload: {:?}.", e));
+                None
+            }
+        }
+    }
+
+    /// Quita el publish del `packet_id` de los sin ack, y libera su packet id para que pueda reutilizarse.
+    fn release_unacked(&mut self, packet_id: u16) {
+        self.unacked.remove(packet_id);
+        self.msg_creator.release_packet_id(packet_id);
+    }
+
+    /// Libera los lugares de los publish cuyo ack llegó tarde, y devuelve error si aun así
+    /// no hay lugar para un nuevo publish de qos 1.
+    fn check_in_flight_window(&mut self) -> Result<(), MqttError> {
+        let unacked = &self.unacked;
+        for packet_id in self.retransmitter.take_late_acks(|packet_id| unacked.contains(packet_id)) {
+            self.release_unacked(packet_id);
+        }
+        if self.unacked.len() >= self.receive_maximum as usize {
+            return Err(MqttError::Io(Error::new(
+                ErrorKind::WouldBlock,
+                format!(
+                    "Hay {} publish sin ack, no se envían más hasta recibir sus acks.",
+                    self.unacked.len()
+                ),
+            )));
+        }
+        Ok(())
+    }
+
     /// Función de la librería de MQTTClient para realizar un subscribe.
-    pub fn mqtt_subscribe(&mut self, topics: Vec<(String, u8)>) -> Result<(), Error> {
+    /// Como con el publish, los topic filters pueden ser `TopicFilter` o strings que se validan antes de enviar.
+    pub fn mqtt_subscribe<F>(&mut self, topics: Vec<(F, u8)>) -> Result<(), MqttError>
+    where
+        F: TryInto<TopicFilter>,
+        MqttError: From<F::Error>,
+    {
+        let topics = topics
+            .into_iter()
+            .map(|(filter, qos)| {
+                let filter: TopicFilter = filter.try_into()?;
+                Ok((filter.as_str().to_string(), qos))
+            })
+            .collect::<Result<Vec<(String, u8)>, MqttError>>()?;
         // Esto solamente crea y devuelve el mensaje
         let msg = self.msg_creator.create_subscribe_msg(topics)?;
         // Se lo paso al retransmitter y que él se encargue de mandarlo, y retransmitirlo si es necesario
@@ -88,10 +457,187 @@ impl MQTTClient {
         Ok(())
     }
 
+    /// Función de la librería de MQTTClient para desuscribirse de los `topics`.
+    /// Como con el subscribe, se espera el ack (UnsubAck) y se retransmite si no llega.
+    pub fn mqtt_unsubscribe<F>(&mut self, topics: Vec<F>) -> Result<(), MqttError>
+    where
+        F: TryInto<TopicFilter>,
+        MqttError: From<F::Error>,
+    {
+        let topics = topics
+            .into_iter()
+            .map(|filter| {
+                let filter: TopicFilter = filter.try_into()?;
+                Ok(filter.as_str().to_string())
+            })
+            .collect::<Result<Vec<String>, MqttError>>()?;
+        let msg = self.msg_creator.create_unsubscribe_msg(topics);
+        self.retransmitter.send_and_retransmit(&msg)?;
+
+        self.logger.log(format!("-----------------\n Mqtt: unsubscribe enviado: \n   {:?}", msg));
+
+        Ok(())
+    }
+
+    /// Envía un PingReq al server, para indicarle que el cliente sigue conectado aunque no tenga
+    /// otros mensajes para enviar dentro del keep alive.
+    pub fn mqtt_ping(&mut self) -> Result<(), MqttError> {
+        self.retransmitter.send_ping()
+    }
+
+    /// Devuelve una foto de los contadores de mensajes enviados y recibidos por este cliente.
+    pub fn stats(&self) -> MQTTClientStatsSnapshot {
+        self.stats.snapshot()
+    }
+
+    /// Devuelve el token que se solicita al hacer `mqtt_disconnect`. La app puede observarlo (o solicitarlo)
+    /// desde sus propios hilos, para que todos terminen junto con la conexión.
+    pub fn get_shutdown_token(&self) -> ShutdownToken {
+        self.shutdown_token.clone()
+    }
+
     /// Función de la librería de MQTTClient para terminar de manera voluntaria la conexión con el server.
-    pub fn mqtt_disconnect(&mut self) -> Result<(), Error> {
+    /// Solicita el shutdown token, para que el listener y los hilos de la app que lo observan terminen.
+    pub fn mqtt_disconnect(&mut self) -> Result<(), MqttError> {
+        // Se solicita el token con el lock tomado (ver `spawn_listener`). Si la conexión se había perdido,
+        // se descarta el tx guardado, para que se cierre el rx de la app.
+        let lost_publish_tx = self.lost_publish_tx.lock();
+        self.shutdown_token.request();
+        if let Ok(mut lost_publish_tx) = lost_publish_tx {
+            lost_publish_tx.take();
+        }
         let msg = self.msg_creator.create_disconnect_msg()?;
         self.retransmitter.send_and_shutdown_stream(msg)?;
         Ok(())
     }
 }
+
+/// Devuelve la tabla de alias de topic para la conexión del `connack`: solamente se usan si el server los aceptó.
+fn topic_aliases_for(connack: &ConnackMessage) -> OutgoingTopicAliases {
+    if connack.topic_aliases_accepted() {
+        OutgoingTopicAliases::new(TOPIC_ALIAS_MAXIMUM)
+    } else {
+        OutgoingTopicAliases::disabled()
+    }
+}
+
+impl ConnectionParams {
+    /// Efectúa la conexión al server, y devuelve el stream conectado y el connack.
+    fn connect(&self, logger: StringLogger) -> Result<(ClientStreamType, ConnackMessage), Error> {
+        MqttClientConnector::mqtt_connect_to_broker(
+            self.client_id.to_string(),
+            &self.addr,
+            self.will.clone(),
+            self.keep_alive,
+            self.topic_aliases,
+            logger,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::{ErrorKind, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        time::Duration,
+    };
+
+    use super::MQTTClient;
+    use crate::{
+        logging::string_logger::StringLogger,
+        mqtt::{
+            messages::{connect_message::ConnectMessage, puback_message::PubAckMessage},
+            mqtt_utils::mqtt_error::MqttError,
+        },
+    };
+
+    #[test]
+    fn test_1_con_la_ventana_de_publish_sin_ack_llena_el_siguiente_publish_se_posterga() {
+        // Un "server" que acepta la conexión, pero nunca envía pubacks
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let server = std::thread::spawn(move || {
+            let (mut server_side, _) = listener.accept().unwrap();
+            server_side.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap(); // connack
+            server_side
+        });
+
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let (mut client, _publish_rx, _listener_handle) = MQTTClient::mqtt_connect_to_broker_with_retransmission(
+            "cliente".to_string(),
+            &addr,
+            None,
+            Duration::from_millis(10),
+            0,
+            0,
+            false,
+            StringLogger::new(logger_tx),
+        )
+        .unwrap();
+        let mut server_side = server.join().unwrap();
+        client.set_receive_maximum(2);
+
+        for _ in 0..2 {
+            let res = client.mqtt_publish("inc", &[1], 1);
+            assert!(matches!(res, Err(MqttError::Timeout(_))));
+        }
+        // Ventana llena: no se envía
+        let res = client.mqtt_publish("inc", &[1], 1);
+        assert!(matches!(res, Err(MqttError::Io(ref e)) if e.kind() == ErrorKind::WouldBlock));
+        assert_eq!(client.unacked.len(), 2);
+        // Los de qos 0 no ocupan lugar en la ventana
+        assert!(client.mqtt_publish("cam", &[1], 0).is_ok());
+
+        // Llega tarde el puback del primero: se libera su lugar
+        let packet_id = client.unacked.get_pending()[0].get_packet_id().unwrap();
+        server_side.write_all(&PubAckMessage::new(packet_id, 0).to_bytes()).unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        let res = client.mqtt_publish("inc", &[1], 1);
+        assert!(matches!(res, Err(MqttError::Timeout(_))));
+    }
+
+    /// Acepta la conexión de un cliente, le responde el connack, y devuelve el connect que envió.
+    fn accept_and_read_connect(listener: &TcpListener) -> ConnectMessage {
+        let (mut server_side, _) = listener.accept().unwrap();
+        let mut fixed_header = [0u8; 2];
+        server_side.read_exact(&mut fixed_header).unwrap();
+        let mut connect_bytes = vec![0u8; fixed_header[1] as usize];
+        server_side.read_exact(&mut connect_bytes).unwrap();
+        server_side.write_all(&[0x20, 0x02, 0x00, 0x00]).unwrap(); // connack
+        ConnectMessage::from_bytes(&[fixed_header.to_vec(), connect_bytes].concat())
+    }
+
+    #[test]
+    fn test_2_los_alias_de_topic_se_solicitan_solamente_si_se_habilitan() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let logger = StringLogger::new(logger_tx);
+
+        // Por defecto no se usa el flag reservado del connect
+        let connect_thread = std::thread::spawn(move || {
+            let default_connect = accept_and_read_connect(&listener);
+            let enabled_connect = accept_and_read_connect(&listener);
+            (default_connect, enabled_connect)
+        });
+        let (_client, _publish_rx, _listener_handle) =
+            MQTTClient::mqtt_connect_to_broker("cliente".to_string(), &addr, None, logger.clone_ref()).unwrap();
+        let (_client, _publish_rx, _listener_handle) = MQTTClient::mqtt_connect_to_broker_with_retransmission(
+            "cliente".to_string(),
+            &addr,
+            None,
+            Duration::from_millis(10),
+            0,
+            0,
+            true,
+            logger,
+        )
+        .unwrap();
+        let (default_connect, enabled_connect) = connect_thread.join().unwrap();
+
+        assert!(!default_connect.requests_topic_aliases());
+        assert!(enabled_connect.requests_topic_aliases());
+    }
+}
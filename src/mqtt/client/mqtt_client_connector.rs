@@ -6,7 +6,7 @@ use std::time::Duration;
 use crate::logging::string_logger::StringLogger;
 use crate::mqtt::messages::{
     connack_message::ConnackMessage, connect_message::ConnectMessage,
-    connect_return_code::ConnectReturnCode, packet_type::PacketType,
+    connect_message_builder::ConnectMessageBuilder, packet_type::PacketType,
 };
 use crate::mqtt::mqtt_utils::fixed_header::FixedHeader;
 use crate::mqtt::mqtt_utils::utils::{
@@ -22,12 +22,32 @@ pub struct MqttClientConnector {
 }
 
 impl MqttClientConnector {
+    /// Se conecta al server, y devuelve el stream conectado y el connack recibido, que indica si el server
+    /// conservaba una sesión del cliente (en cuyo caso no es necesario volver a suscribirse), y si acepta
+    /// los alias de topic, que se solicitan solamente si `topic_aliases` es true.
     pub fn mqtt_connect_to_broker(
         client_id: String,
         addr: &SocketAddr,
         will: Option<WillMessageData>,
+        keep_alive: u16,
+        topic_aliases: bool,
         logger: StringLogger,
-    ) -> Result<ClientStreamType, Error> {
+    ) -> Result<(ClientStreamType, ConnackMessage), Error> {
+        // Crea el mensaje tipo Connect (se valida antes de conectar)
+        let mut builder = ConnectMessageBuilder::new(client_id)
+            .credentials("usuario0".to_string(), "rustx123".to_string())
+            .keep_alive(keep_alive)
+            .topic_aliases(topic_aliases);
+        if let Some(will) = will {
+            builder = builder.will(
+                will.get_will_topic(),
+                will.get_will_msg_content(),
+                will.get_qos(),
+                will.get_will_retain() == 1,
+            );
+        }
+        let mut msg = builder.build()?;
+
         // Intenta conectar al servidor MQTT
         let stream = TcpStream::connect(addr)
             .map_err(|_| io::Error::new(io::ErrorKind::Other, "Error para establecer conexión con servidor."))?;
@@ -36,41 +56,22 @@ impl MqttClientConnector {
             logger,
         };
 
-        // Aux: sintaxis es let (a, b) = if condicion { (a_si_true, b_si_true) } else { (a_si_false, b_si_false) };
-        let (will_msg_content, will_topic, will_qos, _will_retain) = if let Some(will) = will {
-            (
-                Some(will.get_will_msg_content()),
-                Some(will.get_will_topic()),
-                will.get_qos(),
-                will.get_will_retain(),
-            )
-        } else {
-            (None, None, 1, 1)
-        };
-
-        // Crea el mensaje tipo Connect y lo pasa a bytes
-        let mut msg = ConnectMessage::new(
-            client_id,
-            will_topic,
-            will_msg_content,
-            Some("usuario0".to_string()),
-            Some("rustx123".to_string()),
-            will_qos,
-        );
-
         connector.logger.log("Mqtt: Enviando connect msg.".to_string());
-        connector.send_and_retransmit(&mut msg)?;
-        connector.logger.log("Mqtt: connack recibido.".to_string());
-
-        Ok(stream)
+        let connack = connector.send_and_retransmit(&mut msg)?;
+        connector.logger.log(format!(
+            "Mqtt: connack recibido, session present: {}, alias de topic: {}.",
+            connack.session_present(),
+            connack.topic_aliases_accepted()
+        ));
+
+        Ok((stream, connack))
     }
     
     /// Envía el mensaje `msg` recibido una vez, espera por el ack, y si es necesario lo retransmite una cierta
-    /// cantidad de veces.
-    fn send_and_retransmit(&mut self, msg: &mut ConnectMessage) -> Result<(), Error> {
+    /// cantidad de veces. Devuelve el connack recibido.
+    fn send_and_retransmit(&mut self, msg: &mut ConnectMessage) -> Result<ConnackMessage, Error> {
         self.send_msg(msg.to_bytes())?;
-        self.wait_for_connack_and_retransmit(msg)?;        
-        Ok(())
+        self.wait_for_connack_and_retransmit(msg)
     }
     
     /// Función para ser usada por `MQTTClient`, cuando el `Retransmitter` haya determinado que el `msg` debe
@@ -81,40 +82,37 @@ impl MqttClientConnector {
     }
     
     /// Espera a recibir el ack para el mensaje `msg`, si no lo recibe, retransmite.
-    fn wait_for_connack_and_retransmit(&mut self, msg: &mut ConnectMessage) -> Result<(), Error> {
+    /// Devuelve el connack recibido.
+    fn wait_for_connack_and_retransmit(&mut self, msg: &mut ConnectMessage) -> Result<ConnackMessage, Error> {
         // Espero la primera vez, para el connect que hicimos arriba. Si se recibió ack, no hay que hacer nada más.
-        let mut received_ack = self.has_connack_arrived()?;
-        if received_ack {
-            return Ok(());
+        let mut received_ack = self.read_connack()?;
+        if let Some(connack) = received_ack {
+            return Ok(connack);
         }
 
         // No recibí ack, entonces tengo que continuar retransmitiendo, hasta un máx de veces.
         const AMOUNT_OF_RETRIES: u8 = 5; // cant de veces que va a reintentar, hasta que desista y dé error.
         let mut remaining_retries = AMOUNT_OF_RETRIES;
 
-        while !received_ack && remaining_retries > 0 {
+        while received_ack.is_none() && remaining_retries > 0 {
             // Lo vuelvo a enviar y a verificar si recibo ack
             self.send_msg(msg.to_bytes())?;
-            received_ack = self.has_connack_arrived()?;
+            received_ack = self.read_connack()?;
             self.logger.log("Mqtt: Retransmitiendo...".to_string());
 
             remaining_retries -= 1;
         }
 
-        if !received_ack {
+        match received_ack {
+            Some(connack) => Ok(connack),
             // Ya salí del while, retransmití muchas veces y nunca recibí el ack, desisto.
-            return Err(Error::new(
-                ErrorKind::Other,
-                "MAXRETRIES, se retransmitió el connect sin éxito.",
-            ));
+            None => Err(Error::other("MAXRETRIES, se retransmitió el connect sin éxito.")),
         }
-
-        Ok(())
     }
 
     /// Lee una vez, con timeout, para esperar recibir el ack en a lo sumo una cierta cantidad de tiempo.
-    /// Retorna Ok de si le llegó el connack.
-    fn has_connack_arrived(&mut self) -> Result<bool, Error> {
+    /// Retorna Ok(None) si no llegó el connack, u Ok con el connack si llegó.
+    fn read_connack(&mut self) -> Result<Option<ConnackMessage>, Error> {
         const FIXED_HEADER_LEN: usize = FixedHeader::fixed_header_len();
         let mut fixed_header_buf: [u8; 2] = [0; FIXED_HEADER_LEN];
 
@@ -133,8 +131,9 @@ impl MqttClientConnector {
                     // es 100% seguro que seguirá el resto del mensaje
                     self.stream.set_read_timeout(None)?;
                     // Continúo leyendo el Connack, devuelvo error si la conexión no fue aceptada por el server
-                    self.complete_connack_read_and_analyze_it(fixed_header_buf, fixed_header)?;
-                    Ok(true)
+                    let connack =
+                        self.complete_connack_read_and_analyze_it(fixed_header_buf, fixed_header)?;
+                    Ok(Some(connack))
                 } else {
                     // No sebería darse
                     Err(Error::new(
@@ -146,7 +145,7 @@ impl MqttClientConnector {
             Err(e) => {
                 if e.kind() == ErrorKind::WouldBlock || e.kind() == ErrorKind::TimedOut {
                     // Este tipo de error es especial de timeout, significa que pasó el tiempo y no llegó el connack
-                    Ok(false)
+                    Ok(None)
                 } else {
                     // Éste es un error real
                     println!("Error al leer: {:?}", e);
@@ -157,12 +156,12 @@ impl MqttClientConnector {
     }
 
     /// Recibe un fixed header de un mensaje de tipo Connack, y completa su lectura.
-    /// Analiza si la conexión fue (Ok) o no (Error) aceptada por el servidor.
+    /// Analiza si la conexión fue (Ok, con el connack) o no (Error) aceptada por el servidor.
     fn complete_connack_read_and_analyze_it(
         &mut self,
         fixed_header_buf: [u8; 2],
         fixed_header: FixedHeader,
-    ) -> Result<(), Error> {
+    ) -> Result<ConnackMessage, Error> {
         // ConnAck
         println!("Mqtt cliente leyendo: recibo conn ack");
         let recvd_bytes = get_whole_message_in_bytes_from_stream(
@@ -174,13 +173,13 @@ impl MqttClientConnector {
         let msg = ConnackMessage::from_bytes(&recvd_bytes)?; //
         println!("   Mensaje conn ack completo recibido: {:?}", msg);
         let ret = msg.get_connect_return_code();
-        if ret == ConnectReturnCode::ConnectionAccepted {
-            Ok(())
-        } else {
-            Err(Error::new(
-                ErrorKind::InvalidData,
-                "La conexión no fue aceptada.",
-            ))
+        match ret.to_connection_error() {
+            None => Ok(msg),
+            Some(e) => {
+                println!("Error: {}", e);
+                self.logger.log(format!("Mqtt: {}", e));
+                Err(e)
+            }
         }
     }
 }
@@ -1,10 +1,11 @@
 use std::sync::mpsc::Sender;
 
-use std::io::{Error, ErrorKind};
+use crate::mqtt::mqtt_utils::mqtt_error::MqttError;
+use crate::mqtt::mqtt_utils::shutdown_token::ShutdownToken;
 
 use crate::mqtt::messages::{
     packet_type::PacketType, puback_message::PubAckMessage, publish_message::PublishMessage,
-    suback_message::SubAckMessage,
+    suback_message::SubAckMessage, unsuback_message::Unsuback,
 };
 
 use crate::mqtt::client::ack_message::ACKMessage;
@@ -14,13 +15,68 @@ use crate::mqtt::mqtt_utils::utils::{
     send_puback, shutdown,
 };
 
-use super::mqtt_client::ClientStreamType;
+use super::{mqtt_client::ClientStreamType, mqtt_client_stats::MQTTClientStats};
+
+/// Cómo terminó la lectura del listener. Hay tres resultados posibles:
+/// - `ShutdownRequested`: la app solicitó terminar (`mqtt_disconnect`). Se cierra el channel de PublishMessages,
+///   por lo que la app deja de recibir mensajes y puede salir.
+/// - `ServerClosed`: el server cerró la conexión limpiamente, ya sea enviando un disconnect o cerrando el stream
+///   entre dos mensajes (EOF), ej. porque se reinició.
+/// - `Error`: error de lectura, o de protocolo (ej. un paquete mal formado, o cortado a la mitad).
+///
+/// En los dos últimos casos la conexión se perdió sin que la app lo solicitara: el channel no se cierra, para
+/// que la app pueda reconectarse y seguir recibiendo por él, y el motivo se informa mediante `ConnectionEnd`.
+#[derive(Debug)]
+pub enum ListenerOutcome {
+    ShutdownRequested,
+    ServerClosed,
+    Error(MqttError),
+}
+
+impl ListenerOutcome {
+    /// Devuelve el motivo por el que se perdió la conexión, o None si la app solicitó terminar.
+    pub fn to_connection_end(&self) -> Option<ConnectionEnd> {
+        match self {
+            ListenerOutcome::ShutdownRequested => None,
+            ListenerOutcome::ServerClosed => Some(ConnectionEnd::ServerClosed),
+            ListenerOutcome::Error(e) => Some(ConnectionEnd::Error {
+                description: e.to_string(),
+                is_recoverable: e.is_recoverable(),
+            }),
+        }
+    }
+}
+
+/// Motivo por el que se perdió la conexión con el server, para que la app decida si reconectarse o salir.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConnectionEnd {
+    /// El server cerró la conexión limpiamente (disconnect o EOF).
+    ServerClosed,
+    /// Error de lectura o de protocolo; `is_recoverable` según `MqttError::is_recoverable`.
+    Error {
+        description: String,
+        is_recoverable: bool,
+    },
+}
+
+impl ConnectionEnd {
+    /// Devuelve si tiene sentido reconectarse: si el server cerró la conexión (ej. se reinició), o ante un
+    /// error de red. Ante un error de protocolo, reconectarse probablemente no lo resuelva.
+    pub fn should_reconnect(&self) -> bool {
+        match self {
+            ConnectionEnd::ServerClosed => true,
+            ConnectionEnd::Error { is_recoverable, .. } => *is_recoverable,
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct MQTTClientListener {
     stream: ClientStreamType,
     client_tx: Sender<PublishMessage>,
     ack_tx: Sender<ACKMessage>,
+    stats: MQTTClientStats,
+    shutdown_token: ShutdownToken,
 }
 
 impl MQTTClientListener {
@@ -28,16 +84,21 @@ impl MQTTClientListener {
         stream: ClientStreamType,
         client_tx: Sender<PublishMessage>,
         ack_tx: Sender<ACKMessage>,
+        stats: MQTTClientStats,
+        shutdown_token: ShutdownToken,
     ) -> Self {
         MQTTClientListener {
             stream,
             client_tx,
             ack_tx,
+            stats,
+            shutdown_token,
         }
     }
 
     /// Función que ejecutará un hilo de MQTTClient, dedicado exclusivamente a la lectura.
-    pub fn read_from_server(&mut self) -> Result<(), Error> {
+    /// Lee hasta que se cierra la conexión, y devuelve cómo terminó (ver `ListenerOutcome`).
+    pub fn read_from_server(&mut self) -> ListenerOutcome {
         let mut fixed_header_info: ([u8; 2], FixedHeader);
 
         loop {
@@ -49,25 +110,46 @@ impl MQTTClientListener {
                     if is_disconnect_msg(&fixed_header_info.1) {
                         println!("Mqtt cliente leyendo: recibo disconnect");
                         shutdown(&self.stream);
-                        break;
+                        return ListenerOutcome::ServerClosed;
                     }
 
-                    self.read_a_message(&fixed_header_info)?; // esta función lee UN mensaje.
+                    // esta función lee UN mensaje.
+                    if let Err(e) = self.read_a_message(&fixed_header_info) {
+                        return self.outcome_for_error(e);
+                    }
+                }
+                Ok(None) if self.shutdown_token.is_requested() => {
+                    return ListenerOutcome::ShutdownRequested;
                 }
                 Ok(None) => {
                     println!("Se cerró la conexión con server.");
-                    break;
+                    return ListenerOutcome::ServerClosed;
                 }
-                Err(_) => todo!(),
+                Err(e) => return self.outcome_for_error(MqttError::from(e)),
+            }
+            if self.shutdown_token.is_requested() {
+                return ListenerOutcome::ShutdownRequested;
             }
         }
+    }
 
-        Ok(())
+    /// Si se solicitó terminar, el error se debe a que se cerró el stream; si no, es un error de lectura o protocolo.
+    fn outcome_for_error(&self, e: MqttError) -> ListenerOutcome {
+        if self.shutdown_token.is_requested() {
+            println!("Mqtt cliente leyendo: se solicitó terminar.");
+            ListenerOutcome::ShutdownRequested
+        } else {
+            ListenerOutcome::Error(e)
+        }
+    }
+
+    /// Consume el listener, y devuelve el tx por el que enviaba los PublishMessages a la app.
+    pub fn into_client_tx(self) -> Sender<PublishMessage> {
+        self.client_tx
     }
 
     /// Función interna que lee un mensaje, analiza su tipo, y lo procesa acorde a él.
-    /// Función interna que lee un mensaje, analiza su tipo, y lo procesa acorde a él.
-    fn read_a_message(&mut self, fixed_header_info: &([u8; 2], FixedHeader)) -> Result<(), Error> {
+    fn read_a_message(&mut self, fixed_header_info: &([u8; 2], FixedHeader)) -> Result<(), MqttError> {
         let (fixed_header_bytes, fixed_header) = fixed_header_info;
         let tipo = fixed_header.get_message_type();
         let msg_bytes = get_whole_message_in_bytes_from_stream(
@@ -80,22 +162,26 @@ impl MQTTClientListener {
             PacketType::Publish => self.handle_publish(msg_bytes)?,
             PacketType::Puback => self.handle_puback(msg_bytes)?,
             PacketType::Suback => self.handle_suback(msg_bytes)?,
+            PacketType::Unsuback => self.handle_unsuback(msg_bytes)?,
+            PacketType::Pingresp => println!("PingResp recibido."),
             _ => {
                 println!(
                     "   ERROR: tipo desconocido: recibido: \n   {:?}",
                     fixed_header
                 );
-                return Err(Error::new(ErrorKind::Other, "Tipo desconocido."));
+                return Err(MqttError::UnexpectedPacket(tipo));
             }
         };
 
         Ok(())
     }
 
-    fn handle_publish(&mut self, msg_bytes: Vec<u8>) -> Result<(), Error> {
+    fn handle_publish(&mut self, msg_bytes: Vec<u8>) -> Result<(), MqttError> {
         println!("Mqtt cliente leyendo: RECIBO MENSAJE TIPO PUBLISH");
-        let msg = PublishMessage::from_bytes(msg_bytes)?;
+        let msg = PublishMessage::from_bytes(msg_bytes)
+            .map_err(|e| MqttError::MalformedPacket(e.to_string()))?;
         send_puback(&msg, &mut self.stream)?;
+        self.stats.increment_msgs_received_for(&msg.get_topic());
         // Envía PublishMessage a la app
         match self.client_tx.send(msg) {
             Ok(_) => println!("Mqtt cliente leyendo: se envía por tx exitosamente."),
@@ -104,8 +190,10 @@ impl MQTTClientListener {
         Ok(())
     }
 
-    fn handle_puback(&self, msg_bytes: Vec<u8>) -> Result<(), Error> {
-        let msg = PubAckMessage::msg_from_bytes(msg_bytes)?;
+    fn handle_puback(&self, msg_bytes: Vec<u8>) -> Result<(), MqttError> {
+        let msg = PubAckMessage::msg_from_bytes(msg_bytes)
+            .map_err(|e| MqttError::MalformedPacket(e.to_string()))?;
+        self.stats.increment_pubacks_received();
         // Avisa que llegó el ack
         match self.ack_tx.send(ACKMessage::PubAck(msg)) {
             Ok(_) => println!("PubAck enviado por tx exitosamente."),
@@ -114,8 +202,9 @@ impl MQTTClientListener {
         Ok(())
     }
 
-    fn handle_suback(&self, msg_bytes: Vec<u8>) -> Result<(), Error> {
-        let msg = SubAckMessage::from_bytes(msg_bytes)?;
+    fn handle_suback(&self, msg_bytes: Vec<u8>) -> Result<(), MqttError> {
+        let msg = SubAckMessage::from_bytes(msg_bytes)
+            .map_err(|e| MqttError::MalformedPacket(e.to_string()))?;
         // Avisa que llegó el ack
         match self.ack_tx.send(ACKMessage::SubAck(msg)) {
             Ok(_) => println!("SubAck enviado por tx exitosamente."),
@@ -123,6 +212,19 @@ impl MQTTClientListener {
         }
         Ok(())
     }
+
+    fn handle_unsuback(&self, msg_bytes: Vec<u8>) -> Result<(), MqttError> {
+        if msg_bytes.len() < 4 {
+            return Err(MqttError::MalformedPacket("UnsubAck incompleto".to_string()));
+        }
+        let msg = Unsuback::from_bytes(&msg_bytes);
+        // Avisa que llegó el ack
+        match self.ack_tx.send(ACKMessage::UnsubAck(msg)) {
+            Ok(_) => println!("UnsubAck enviado por tx exitosamente."),
+            Err(_) => println!("Error al enviar UnsubAck por tx."),
+        }
+        Ok(())
+    }
 }
 
 /*impl Clone for MQTTClientListener {
@@ -133,3 +235,57 @@ impl MQTTClientListener {
         MQTTClientListener { stream, client_tx , ack_tx }
     }
 }*/
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Write,
+        net::{Shutdown, TcpListener, TcpStream},
+        sync::mpsc,
+    };
+
+    use super::{ConnectionEnd, ListenerOutcome, MQTTClientListener};
+    use crate::mqtt::{
+        client::mqtt_client_stats::MQTTClientStats, mqtt_utils::shutdown_token::ShutdownToken,
+    };
+
+    /// Crea un listener conectado a un "server", que escribe los `server_bytes` y cierra la conexión.
+    fn read_with_server_sending(server_bytes: &[u8]) -> ListenerOutcome {
+        let tcp_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(tcp_listener.local_addr().unwrap()).unwrap();
+        let (mut server_side, _) = tcp_listener.accept().unwrap();
+        server_side.write_all(server_bytes).unwrap();
+        server_side.shutdown(Shutdown::Both).unwrap();
+
+        let (client_tx, _client_rx) = mpsc::channel();
+        let (ack_tx, _ack_rx) = mpsc::channel();
+        let mut listener = MQTTClientListener::new(
+            stream,
+            client_tx,
+            ack_tx,
+            MQTTClientStats::new(),
+            ShutdownToken::new(),
+        );
+        listener.read_from_server()
+    }
+
+    #[test]
+    fn test_1_si_el_server_cierra_la_conexion_entre_mensajes_termina_limpiamente() {
+        let outcome = read_with_server_sending(&[]);
+
+        assert!(matches!(outcome, ListenerOutcome::ServerClosed));
+        assert_eq!(outcome.to_connection_end(), Some(ConnectionEnd::ServerClosed));
+    }
+
+    #[test]
+    fn test_2_si_el_server_cierra_la_conexion_a_la_mitad_de_un_mensaje_es_un_error() {
+        // Solamente el primer byte del fixed header de un pingresp
+        let outcome = read_with_server_sending(&[0xD0]);
+
+        assert!(matches!(outcome, ListenerOutcome::Error(_)));
+        assert!(matches!(
+            outcome.to_connection_end(),
+            Some(ConnectionEnd::Error { .. })
+        ));
+    }
+}
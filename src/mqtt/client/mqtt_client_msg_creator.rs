@@ -1,20 +1,61 @@
 use crate::mqtt::messages::{
     disconnect_message::DisconnectMessage, publish_flags::PublishFlags,
     publish_message::PublishMessage, subscribe_message::SubscribeMessage,
+    unsubscribe_message::UnsubscribeMessage,
 };
 
-use std::io::Error;
+use std::{
+    collections::BTreeSet,
+    fs,
+    io::{Error, ErrorKind},
+    path::{Path, PathBuf},
+};
+
+/// Cantidad de packet ids que se reservan cada vez que se persiste (ver `with_state_file`).
+const PACKET_ID_RESERVATION_BLOCK: u16 = 100;
 
 #[derive(Debug)]
 pub struct MessageCreator {
     available_packet_id: u16,
+    state_file: Option<PathBuf>, // donde se persiste el último packet id reservado, ver `with_state_file`
+    reserved_ids_left: u16,      // packet ids que pueden usarse sin volver a persistir
+    outstanding: BTreeSet<u16>,  // packet ids de publish sin ack, que no deben reutilizarse
 }
 
 impl MessageCreator {
     pub fn new() -> MessageCreator {
         MessageCreator {
             available_packet_id: 0,
+            state_file: None,
+            reserved_ids_left: 0,
+            outstanding: BTreeSet::new(),
+        }
+    }
+
+    /// Crea el MessageCreator persistiendo en `state_file` los packet ids usados, para que al volver a crear
+    /// el cliente con el mismo client_id los packet ids continúen en lugar de reiniciarse: con una sesión
+    /// persistente, el server todavía podría considerar en vuelo a los ya usados. Con `clean_session` el server
+    /// descarta la sesión anterior, por lo que los packet ids comienzan nuevamente desde 0.
+    /// Para no escribir el archivo en cada mensaje, se persiste de a bloques de `PACKET_ID_RESERVATION_BLOCK`
+    /// ids reservados: si la app termina abruptamente, el próximo cliente continúa luego del bloque.
+    /// Se persisten también los packet ids de los publish sin ack, para no reutilizarlos al dar la vuelta.
+    pub fn with_state_file(state_file: &Path, clean_session: bool) -> Result<MessageCreator, Error> {
+        let (available_packet_id, outstanding) = if clean_session {
+            (0, BTreeSet::new())
+        } else {
+            read_packet_id_state(state_file)?
+        };
+        if let Some(dir) = state_file.parent() {
+            fs::create_dir_all(dir)?;
         }
+        let creator = MessageCreator {
+            available_packet_id,
+            state_file: Some(state_file.to_path_buf()),
+            reserved_ids_left: 0,
+            outstanding,
+        };
+        creator.persist(available_packet_id)?;
+        Ok(creator)
     }
 
     /// Crea y devuelve el PublishMessage.
@@ -24,39 +65,151 @@ impl MessageCreator {
         payload: &[u8],
         qos: u8,
     ) -> Result<PublishMessage, Error> {
-        let packet_id = self.generate_packet_id();
+        self.create_publish_msg_with(topic, payload, qos, false)
+    }
+
+    /// Crea y devuelve el PublishMessage con el `compressed_payload`, ya comprimido (ver `PublishMessage::new_compressed`).
+    pub fn create_compressed_publish_msg(
+        &mut self,
+        topic: &str,
+        compressed_payload: &[u8],
+        qos: u8,
+    ) -> Result<PublishMessage, Error> {
+        self.create_publish_msg_with(topic, compressed_payload, qos, true)
+    }
+
+    fn create_publish_msg_with(
+        &mut self,
+        topic: &str,
+        payload: &[u8],
+        qos: u8,
+        compressed: bool,
+    ) -> Result<PublishMessage, Error> {
+        // Con qos 0 el publish no lleva packet identifier, ya que no se espera su ack
+        let packet_id = if qos > 0 {
+            let packet_id = self.generate_packet_id();
+            if self.state_file.is_some() {
+                self.outstanding.insert(packet_id);
+            }
+            Some(packet_id)
+        } else {
+            None
+        };
         // Creo un msj publish
         let flags = PublishFlags::new(0, qos, 0)?;
-        let publish_msg = PublishMessage::new(flags, topic, Some(packet_id), payload)?;
+        let publish_msg = if compressed {
+            PublishMessage::new_compressed(flags, topic, packet_id, payload)?
+        } else {
+            PublishMessage::new(flags, topic, packet_id, payload)?
+        };
 
         Ok(publish_msg)
     }
 
     /// Recibe un vector de topics a los cuales cliente desea suscribirse.
-    /// Crea y devuelve el SubscribeMessage.
+    /// Crea y devuelve el SubscribeMessage, con cada topic una sola vez (ver `merge_topic_filters`).
     pub fn create_subscribe_msg(
         &mut self,
         topics_to_subscribe: Vec<(String, u8)>,
     ) -> Result<SubscribeMessage, Error> {
         let packet_id = self.generate_packet_id();
         // Construyo subscribe
-        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe);        
+        let subscribe_msg = SubscribeMessage::new(packet_id, merge_topic_filters(topics_to_subscribe))?;
 
         Ok(subscribe_msg)
     }
 
+    /// Recibe un vector de topics de los cuales el cliente desea desuscribirse.
+    /// Crea y devuelve el UnsubscribeMessage.
+    pub fn create_unsubscribe_msg(&mut self, topics_to_unsubscribe: Vec<String>) -> UnsubscribeMessage {
+        let packet_id = self.generate_packet_id();
+        UnsubscribeMessage::new(packet_id, topics_to_unsubscribe)
+    }
+
     /// Crea y devuelve un DisconnectMessage.
+    /// Persiste el último packet id usado, para que el próximo cliente continúe exactamente desde él.
     pub fn create_disconnect_msg(&mut self) -> Result<DisconnectMessage, Error> {
+        self.persist(self.available_packet_id)?;
+        self.reserved_ids_left = 0;
         let msg = DisconnectMessage::new();
         Ok(msg)
     }
 
+    /// Libera el `packet_id` de un publish cuyo ack se recibió (o que se descartó), para que pueda reutilizarse.
+    pub fn release_packet_id(&mut self, packet_id: u16) {
+        self.outstanding.remove(&packet_id);
+    }
+
     /// Devuelve el packet_id a usar para el siguiente mensaje enviado.
     /// Incrementa en 1 el atributo correspondiente, debido a la llamada anterior, y devuelve el valor a ser usado
     /// en el envío para el cual fue llamada esta función.
+    /// Al llegar al máximo vuelve a comenzar, salteando el 0, que no es un packet id válido, y los packet ids
+    /// de publish que siguen sin ack.
     fn generate_packet_id(&mut self) -> u16 {
-        self.available_packet_id += 1;
-        self.available_packet_id
+        let start = self.available_packet_id;
+        loop {
+            self.available_packet_id = next_packet_id(self.available_packet_id);
+            self.reserve_if_needed();
+            if !self.outstanding.contains(&self.available_packet_id) || self.available_packet_id == start {
+                return self.available_packet_id;
+            }
+        }
+    }
+
+    /// Si ya se usaron los packet ids reservados, persiste la reserva de un nuevo bloque a partir del actual.
+    fn reserve_if_needed(&mut self) {
+        if self.state_file.is_none() {
+            return;
+        }
+        if self.reserved_ids_left > 0 {
+            self.reserved_ids_left -= 1;
+            return;
+        }
+        let mut reserved_until = self.available_packet_id;
+        for _ in 1..PACKET_ID_RESERVATION_BLOCK {
+            reserved_until = next_packet_id(reserved_until);
+        }
+        // Si no puede persistirse se continúa igual: a lo sumo, un próximo cliente repetiría packet ids
+        let _ = self.persist(reserved_until);
+        self.reserved_ids_left = PACKET_ID_RESERVATION_BLOCK - 1;
+    }
+
+    /// Persiste `last_used` como último packet id usado, y los packet ids sin ack, si el MessageCreator
+    /// tiene `state_file`.
+    fn persist(&self, last_used: u16) -> Result<(), Error> {
+        match &self.state_file {
+            Some(state_file) => {
+                let mut bytes = last_used.to_be_bytes().to_vec();
+                for packet_id in self.outstanding.iter() {
+                    bytes.extend_from_slice(&packet_id.to_be_bytes());
+                }
+                fs::write(state_file, bytes)
+            }
+            None => Ok(()),
+        }
+    }
+}
+
+/// Devuelve el packet id siguiente a `packet_id`. Al llegar al máximo vuelve a comenzar, salteando el 0.
+fn next_packet_id(packet_id: u16) -> u16 {
+    packet_id.checked_add(1).unwrap_or(1)
+}
+
+/// Lee el último packet id usado, seguido de los packet ids sin ack, desde `state_file`.
+/// Si el archivo no existe, el cliente todavía no usó ninguno.
+fn read_packet_id_state(state_file: &Path) -> Result<(u16, BTreeSet<u16>), Error> {
+    match fs::read(state_file) {
+        Ok(bytes) if bytes.len() >= 2 && bytes.len() % 2 == 0 => {
+            let mut ids = bytes.chunks(2).map(|id_bytes| u16::from_be_bytes([id_bytes[0], id_bytes[1]]));
+            let last_used = ids.next().unwrap_or(0);
+            Ok((last_used, ids.collect()))
+        }
+        Ok(_) => Err(Error::new(
+            ErrorKind::InvalidData,
+            "El archivo de packet id no contiene un packet id válido",
+        )),
+        Err(e) if e.kind() == ErrorKind::NotFound => Ok((0, BTreeSet::new())),
+        Err(e) => Err(e),
     }
 }
 
@@ -65,3 +218,95 @@ impl Default for MessageCreator {
         Self::new()
     }
 }
+
+/// Quita los topics repetidos, conservando el orden en que aparece cada uno por primera vez. Si un topic
+/// aparece con distintos qos, se toma el mayor; así el server no guarda qos contradictorios para un mismo topic.
+fn merge_topic_filters(topics: Vec<(String, u8)>) -> Vec<(String, u8)> {
+    let mut merged: Vec<(String, u8)> = vec![];
+    for (topic, qos) in topics {
+        match merged.iter_mut().find(|(merged_topic, _)| *merged_topic == topic) {
+            Some((_, merged_qos)) => *merged_qos = (*merged_qos).max(qos),
+            None => merged.push((topic, qos)),
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod test {
+    use super::{MessageCreator, PACKET_ID_RESERVATION_BLOCK};
+
+    #[test]
+    fn test_1_subscribe_con_topics_repetidos_los_envia_una_vez_con_el_mayor_qos() {
+        let mut creator = MessageCreator::new();
+        let topics = vec![
+            ("A".to_string(), 0),
+            ("A".to_string(), 1),
+            ("B".to_string(), 0),
+        ];
+
+        let msg = creator.create_subscribe_msg(topics).unwrap();
+
+        assert_eq!(
+            msg.get_topic_filters(),
+            &vec![("A".to_string(), 1), ("B".to_string(), 0)]
+        );
+    }
+
+    #[test]
+    fn test_2_al_reconectar_sin_clean_session_los_packet_ids_continuan() {
+        let state_file = std::env::temp_dir().join("test_msg_creator_2").join("packet_id");
+        let _ = std::fs::remove_file(&state_file);
+
+        let mut creator = MessageCreator::with_state_file(&state_file, false).unwrap();
+        let first = creator.create_publish_msg("A", b"1", 1).unwrap();
+        let second = creator.create_subscribe_msg(vec![("A".to_string(), 1)]).unwrap();
+        assert_eq!(first.get_packet_id(), Some(1));
+        assert_eq!(second.get_packet_id(), 2);
+
+        // Se vuelve a crear el cliente con el mismo client_id, conservando la sesión, tras desconectarse
+        creator.create_disconnect_msg().unwrap();
+        let mut reconnected = MessageCreator::with_state_file(&state_file, false).unwrap();
+        let msg = reconnected.create_publish_msg("A", b"2", 1).unwrap();
+        assert_eq!(msg.get_packet_id(), Some(3));
+
+        // Si termina abruptamente, se continúa luego del bloque reservado, sin repetir packet ids
+        drop(reconnected);
+        let mut restarted = MessageCreator::with_state_file(&state_file, false).unwrap();
+        let msg = restarted.create_publish_msg("A", b"3", 1).unwrap();
+        assert_eq!(msg.get_packet_id(), Some(3 + PACKET_ID_RESERVATION_BLOCK));
+
+        // Con clean_session se vuelve a comenzar
+        let mut clean = MessageCreator::with_state_file(&state_file, true).unwrap();
+        let msg = clean.create_publish_msg("A", b"3", 1).unwrap();
+        assert_eq!(msg.get_packet_id(), Some(1));
+
+        let _ = std::fs::remove_file(&state_file);
+    }
+
+    #[test]
+    fn test_3_al_dar_la_vuelta_no_se_reutilizan_packet_ids_sin_ack() {
+        let state_file = std::env::temp_dir().join("test_msg_creator_3").join("packet_id");
+        let _ = std::fs::remove_file(&state_file);
+        let mut creator = MessageCreator::with_state_file(&state_file, false).unwrap();
+        creator.available_packet_id = u16::MAX - 1;
+
+        // El publish 1 queda sin ack, el 2 lo recibe
+        let unacked = creator.create_publish_msg("A", b"1", 1).unwrap();
+        let acked = creator.create_publish_msg("A", b"2", 1).unwrap();
+        creator.release_packet_id(acked.get_packet_id().unwrap());
+        assert_eq!(unacked.get_packet_id(), Some(u16::MAX));
+        creator.create_disconnect_msg().unwrap();
+
+        // Al reconectarse, el packet id sin ack se persistió y se saltea
+        let mut reconnected = MessageCreator::with_state_file(&state_file, false).unwrap();
+        assert_eq!(reconnected.create_publish_msg("A", b"3", 1).unwrap().get_packet_id(), Some(2));
+        reconnected.available_packet_id = u16::MAX - 1;
+        assert_eq!(reconnected.create_publish_msg("A", b"4", 0).unwrap().get_packet_id(), None);
+        assert_eq!(reconnected.create_subscribe_msg(vec![("A".to_string(), 1)]).unwrap().get_packet_id(), 1);
+        let msg = reconnected.create_unsubscribe_msg(vec!["A".to_string()]);
+        assert_eq!(msg.get_packet_id(), 3);
+
+        let _ = std::fs::remove_file(&state_file);
+    }
+}
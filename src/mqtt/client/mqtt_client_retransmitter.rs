@@ -1,41 +1,112 @@
-use std::{io::{Error, ErrorKind}, net::Shutdown, sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender}, time::Duration};
+use std::{net::Shutdown, sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender}, time::{Duration, Instant}};
 
-use crate::{logging::string_logger::StringLogger, mqtt::{messages::{disconnect_message::DisconnectMessage, message::Message, packet_type::PacketType, publish_message::PublishMessage}, mqtt_utils::utils::write_message_to_stream}};
+use crate::{logging::string_logger::StringLogger, mqtt::{messages::{disconnect_message::DisconnectMessage, message::Message, packet_type::PacketType, pingreq_message::PingReqMessage, publish_message::PublishMessage}, mqtt_utils::{mqtt_error::MqttError, utils::write_message_to_stream}}};
 
-use super::{ack_message::ACKMessage, mqtt_client::ClientStreamType};
+// Tiempo, en milisegundos, que se espera por el ack antes de retransmitir, si no se configura otro.
+pub const DEFAULT_ACK_WAITING_INTERVAL: u64 = 1000;
+// Cantidad de veces que se retransmite, hasta desistir y dar error, si no se configura otra.
+pub const DEFAULT_AMOUNT_OF_RETRIES: u8 = 5;
+
+use super::{ack_message::ACKMessage, mqtt_client::ClientStreamType, mqtt_client_stats::MQTTClientStats};
 
 /// Parte interna de `MQTTClient` encargada de manejar los ack y las retransmisiones.
 /// Conserva el extramo receptor de un channel (`ack_rx`).
 #[derive(Debug)]
 pub struct Retransmitter {
     ack_rx: Receiver<ACKMessage>,
+    late_acks: Vec<u16>, // packet ids de pubacks que llegaron mientras se esperaba el de otro mensaje
     stream: ClientStreamType,
+    stats: MQTTClientStats,
+    ack_timeout: Duration,
+    max_retries: u8,
     logger: StringLogger,
 }
 
 impl Retransmitter {
     /// Crea y devuelve un Retransmitter, encargado del envío y las retransmisiones, y el extremo de envío de un channel.
-    pub fn new(stream: ClientStreamType, logger: StringLogger) -> (Self, Sender<ACKMessage>) {
+    /// Utiliza el tiempo de espera y la cantidad de retransmisiones por defecto.
+    pub fn new(stream: ClientStreamType, stats: MQTTClientStats, logger: StringLogger) -> (Self, Sender<ACKMessage>) {
+        Self::new_with_config(
+            stream,
+            stats,
+            Duration::from_millis(DEFAULT_ACK_WAITING_INTERVAL),
+            DEFAULT_AMOUNT_OF_RETRIES,
+            logger,
+        )
+    }
+
+    /// Crea y devuelve un Retransmitter que espera `ack_timeout` por cada ack, y retransmite como máximo
+    /// `max_retries` veces antes de desistir y devolver error; y el extremo de envío de un channel.
+    pub fn new_with_config(
+        stream: ClientStreamType,
+        stats: MQTTClientStats,
+        ack_timeout: Duration,
+        max_retries: u8,
+        logger: StringLogger,
+    ) -> (Self, Sender<ACKMessage>) {
         let (ack_tx, ack_rx) = channel::<ACKMessage>();
-        (Self { ack_rx , stream , logger }, ack_tx)
+        (Self { ack_rx, late_acks: vec![], stream , stats, ack_timeout, max_retries, logger }, ack_tx)
+    }
+
+    /// Devuelve (y olvida) los packet ids de los pubacks que llegaron tarde, ej. de un publish cuyas
+    /// retransmisiones ya se habían agotado, para que `MQTTClient` deje de considerarlos sin ack.
+    /// Solamente se devuelven los de publish que siguen en vuelo según `is_in_flight`: los demás son
+    /// pubacks espurios (ej. duplicados, o de un packet id que nunca se envió), y se los registra y descarta.
+    pub fn take_late_acks<F>(&mut self, is_in_flight: F) -> Vec<u16>
+    where
+        F: Fn(u16) -> bool,
+    {
+        // También los que llegaron sin que se estuviera esperando ningún ack
+        while let Ok(ack_message) = self.ack_rx.try_recv() {
+            self.register_late_ack(&ack_message);
+        }
+        let (in_flight, spurious): (Vec<u16>, Vec<u16>) = std::mem::take(&mut self.late_acks)
+            .into_iter()
+            .partition(|packet_id| is_in_flight(*packet_id));
+        for packet_id in spurious {
+            let error_msg = format!(
+                "ERROR: se ignora puback con packet_id {}, que no corresponde a ningún publish en vuelo.",
+                packet_id
+            );
+            println!("{}", error_msg);
+            self.logger.log(error_msg);
+        }
+        in_flight
+    }
+
+    fn register_late_ack(&mut self, ack_message: &ACKMessage) {
+        if let ACKMessage::PubAck(puback) = ack_message {
+            self.late_acks.push(puback.get_packet_id());
+        }
     }
     
     /// Envía el mensaje `msg` recibido una vez, espera por el ack, y si es necesario lo retransmite una cierta
-    /// cantidad de veces.
-    pub fn send_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), Error> {
+    /// cantidad de veces. Si se agotan las retransmisiones sin recibir el ack, devuelve `MqttError::Timeout`.
+    pub fn send_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), MqttError> {
         self.logger.log("Mqtt: Enviando msg.".to_string());
         self.send_msg(msg.to_bytes())?;
+        self.count_sent(msg);
         if let Err(e) = self.wait_for_ack_and_retransmit(msg) {
             println!("Error al esperar ack: {:?}", e);
             self.logger.log(format!("Error al esperar ack: {:?}", e));
+            return Err(e);
         };
         self.logger.log("Mqtt: recibido ack.".to_string());
         Ok(())
     }
 
+    /// Actualiza los contadores de mensajes enviados (no se cuentan las retransmisiones).
+    fn count_sent<T: Message>(&self, msg: &T) {
+        match msg.get_type() {
+            PacketType::Publish => self.stats.increment_publishes_sent(),
+            PacketType::Subscribe => self.stats.increment_subscribes_sent(),
+            _ => {}
+        }
+    }
+
     /// Espera por el ack y si no lo recibe retransmite, teniendo en cuenta el tipo de paquete,
     /// para el publish considera su nivel de qos.
-    fn wait_for_ack_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), Error> {
+    fn wait_for_ack_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), MqttError> {
         match msg.get_type() {
             // Si es publish, ver el qos
             PacketType::Publish => {
@@ -48,7 +119,7 @@ impl Retransmitter {
                     }
                 }
             }
-            PacketType::Subscribe => {
+            PacketType::Subscribe | PacketType::Unsubscribe => {
                 return self.wait_and_retransmit(msg);
             }
             _ => {}
@@ -58,8 +129,13 @@ impl Retransmitter {
     }
 
     /// Espera a recibir el ack para el packet_id del mensaje `msg`, si no lo recibe, retransmite.
-    fn wait_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), Error> {
+    /// Un publish se retransmite con el flag dup, para que el server no vuelva a procesarlo si ya lo había recibido.
+    fn wait_and_retransmit<T: Message>(&mut self, msg: &T) -> Result<(), MqttError> {
         let packet_id = msg.get_packet_id();
+        let retransmission_bytes = match msg.as_any().downcast_ref::<PublishMessage>() {
+            Some(pub_msg) => pub_msg.as_dup()?.to_bytes(),
+            None => msg.to_bytes(),
+        };
         // Espero la primera vez, para el publish que hicimos arriba. Si se recibió ack, no hay que hacer nada más.
         let mut received_ack = self.has_ack_arrived(packet_id)?;
         if received_ack {
@@ -67,13 +143,12 @@ impl Retransmitter {
         }
 
         // No recibí ack, entonces tengo que continuar retransmitiendo, hasta un máx de veces.
-        const AMOUNT_OF_RETRIES: u8 = 5; // cant de veces que va a reintentar, hasta que desista y dé error.
-        let mut remaining_retries = AMOUNT_OF_RETRIES;
+        let mut remaining_retries = self.max_retries;
 
         while !received_ack && remaining_retries > 0 {
             // Lo vuelvo a enviar, y a verificar si llega el ack.
             
-            self.send_msg(msg.to_bytes())?;
+            self.send_msg(retransmission_bytes.clone())?;
             received_ack = self.has_ack_arrived(packet_id)?;
             self.logger.log("Mqtt: Retransmitiendo...".to_string());
 
@@ -82,10 +157,10 @@ impl Retransmitter {
 
         if !received_ack {
             // Ya salí del while, retransmití muchas veces y nunca recibí el ack, desisto.
-            return Err(Error::new(
-                ErrorKind::Other,
-                "MAXRETRIES, se retransmitió sin éxito.",
-            ));
+            return Err(MqttError::Timeout(format!(
+                "MAXRETRIES, se retransmitió {} veces sin recibir el ack del packet_id {:?}.",
+                self.max_retries, packet_id
+            )));
         }
 
         Ok(())
@@ -95,65 +170,148 @@ impl Retransmitter {
     /// Si eso no ocurre, debe retransmitir el mensaje original (el msg cuyo ack está esperando)
     /// hasta que llegue su ack o bien se llegue a una cantidad máxima de intentos definida como constante.
     /// Devuelve si recibió el ack.
-    fn has_ack_arrived(&self, packet_id: Option<u16>) -> Result<bool, Error> {
+    fn has_ack_arrived(&mut self, packet_id: Option<u16>) -> Result<bool, MqttError> {
         // Extrae el packet_id
         if let Some(packet_id) = packet_id {
             self.start_waiting_and_check_for_ack(packet_id)
         } else {
-                Err(Error::new(
-                ErrorKind::Other,
-                "No se pudo obtener el packet id del mensaje publish",
+                Err(MqttError::MalformedPacket(
+                "No se pudo obtener el packet id del mensaje publish".to_string(),
             ))
         }
     }
 
     /// Espera por el ack como máximo un cierto tiempo,
     /// si no se cerró la conexión con listener, devuelve Ok de si llega el ack.
-    fn start_waiting_and_check_for_ack(&self, packet_id: u16) -> Result<bool, Error> {
+    /// Los acks de otros packet ids que lleguen mientras tanto se registran como tardíos, y se sigue esperando.
+    fn start_waiting_and_check_for_ack(&mut self, packet_id: u16) -> Result<bool, MqttError> {
         // Leo esperando un cierto tiempo, si en el período [0, ese tiempo) no me llega el ack, lo quiero retransmitir.
-        const ACK_WAITING_INTERVAL: u64 = 1000;
-        match self.ack_rx.recv_timeout(Duration::from_millis(ACK_WAITING_INTERVAL)){
-            Ok(ack_message) => {
-                // Se recibió el ack
-                if let Some(packet_identifier) = ack_message.get_packet_id() {
-                    if packet_id == packet_identifier {
-                        println!("   llegó el ack {:?}", ack_message); 
+        let deadline = Instant::now() + self.ack_timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.ack_rx.recv_timeout(remaining) {
+                Ok(ack_message) => {
+                    // Se recibió un ack, se verifica que sea el del packet_id esperado
+                    if ack_message.get_packet_id() == Some(packet_id) {
+                        println!("   llegó el ack {:?}", ack_message);
                         return Ok(true);
                     }
+                    self.register_late_ack(&ack_message);
                 }
-            },
-            Err(e) => {
-                match e {
-                    RecvTimeoutError::Timeout => {
-                        // Se cumplió el tiempo y el ack No se recibió.
-                        return Ok(false);
-
-                    },
-                    RecvTimeoutError::Disconnected => {
-                        // Se cerró el channel. Termina el programa.
-                        // Ver.
-                    },
-                }
-            },
+                // Se cumplió el tiempo y el ack No se recibió.
+                Err(RecvTimeoutError::Timeout) => return Ok(false),
+                // Se cerró el channel. Termina el programa.
+                Err(RecvTimeoutError::Disconnected) => return Ok(false),
+            }
         }
-        Ok(false)
     }
 
     /// Función para ser usada por `MQTTClient`, cuando el `Retransmitter` haya determinado que el `msg` debe
     /// enviarse por el stream a server.
-    fn send_msg(&mut self, bytes_msg: Vec<u8>) -> Result<(), Error> {
+    fn send_msg(&mut self, bytes_msg: Vec<u8>) -> Result<(), MqttError> {
         write_message_to_stream(&bytes_msg, &mut self.stream)?;
         Ok(())
     }
     
+    /// Envía un PingReq. No se espera su respuesta: si el server no la envía, la conexión se cerrará igualmente.
+    pub fn send_ping(&mut self) -> Result<(), MqttError> {
+        self.send_msg(PingReqMessage::new().to_bytes())
+    }
+
     /// Envía el mensaje disconnect recibido por parámetro y cierra la conexión.
-    pub fn send_and_shutdown_stream(&mut self, msg: DisconnectMessage) -> Result<(), Error> {
-        self.send_msg(msg.to_bytes())?;
-        // Cerramos la conexión con el servidor
+    pub fn send_and_shutdown_stream(&mut self, msg: DisconnectMessage) -> Result<(), MqttError> {
+        let send_res = self.send_msg(msg.to_bytes());
+        // Cerramos la conexión con el servidor aunque no se haya podido enviar el disconnect,
+        // para que el hilo listener deje de leer y termine.
         self.stream.shutdown(Shutdown::Both)?;
+        send_res?;
         self.logger.log("Mqtt: Conexión cerrada.".to_string());
 
         Ok(())
     }
 
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        io::Read,
+        net::{TcpListener, TcpStream},
+        sync::mpsc,
+        time::Duration,
+    };
+
+    use super::Retransmitter;
+    use crate::mqtt::mqtt_utils::mqtt_error::MqttError;
+    use crate::{
+        logging::string_logger::StringLogger,
+        mqtt::{
+            client::{ack_message::ACKMessage, mqtt_client_stats::MQTTClientStats},
+            messages::{
+                puback_message::PubAckMessage, publish_flags::PublishFlags,
+                publish_message::PublishMessage,
+            },
+        },
+    };
+
+    fn create_retransmitter() -> (Retransmitter, mpsc::Sender<ACKMessage>, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server_side, _) = listener.accept().unwrap();
+
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let (retransmitter, ack_tx) = Retransmitter::new_with_config(
+            stream,
+            MQTTClientStats::new(),
+            Duration::from_millis(10),
+            0,
+            StringLogger::new(logger_tx),
+        );
+        (retransmitter, ack_tx, server_side)
+    }
+
+    #[test]
+    fn test_1_sin_ack_se_devuelve_error_luego_de_las_retransmisiones_configuradas() {
+        // Un "server" que acepta la conexión pero nunca envía el ack
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server_side, _) = listener.accept().unwrap();
+
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let max_retries = 2;
+        let (mut retransmitter, _ack_tx) = Retransmitter::new_with_config(
+            stream,
+            MQTTClientStats::new(),
+            Duration::from_millis(10),
+            max_retries,
+            StringLogger::new(logger_tx),
+        );
+
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        let msg = PublishMessage::new(flags, "inc", Some(1), &[1, 2, 3]).unwrap();
+        let res = retransmitter.send_and_retransmit(&msg);
+
+        assert!(matches!(res, Err(MqttError::Timeout(_))));
+
+        // Se envió una vez, más las retransmisiones configuradas
+        let msg_len = msg.to_bytes().len();
+        let mut received = vec![0; msg_len * (1 + max_retries as usize)];
+        server_side.read_exact(&mut received).unwrap();
+    }
+
+    #[test]
+    fn test_2_se_ignoran_los_pubacks_de_packet_ids_que_no_estan_en_vuelo() {
+        let (mut retransmitter, ack_tx, _server_side) = create_retransmitter();
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        let msg = PublishMessage::new(flags, "inc", Some(1), &[1]).unwrap();
+
+        // Mientras se espera el ack del 1, llegan el de un publish en vuelo (3) y uno espurio (9)
+        ack_tx.send(ACKMessage::PubAck(PubAckMessage::new(3, 0))).unwrap();
+        ack_tx.send(ACKMessage::PubAck(PubAckMessage::new(9, 0))).unwrap();
+        ack_tx.send(ACKMessage::PubAck(PubAckMessage::for_publish(&msg))).unwrap();
+        assert!(retransmitter.send_and_retransmit(&msg).is_ok());
+
+        assert_eq!(retransmitter.take_late_acks(|packet_id| packet_id == 3), vec![3]);
+        assert!(retransmitter.take_late_acks(|_| true).is_empty());
+    }
+}
@@ -0,0 +1,187 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+use crate::apps::apps_mqtt_topics::AppsMqttTopics;
+
+/// Topics de las apps, que se cuentan cada uno con su propio contador atómico (ver `app_topic_index`).
+const APP_TOPICS: [AppsMqttTopics; 5] = [
+    AppsMqttTopics::IncidentTopic,
+    AppsMqttTopics::DronTopic,
+    AppsMqttTopics::CameraTopic,
+    AppsMqttTopics::DescTopic,
+    AppsMqttTopics::CameraFrameTopic,
+];
+
+/// Contadores de mensajes enviados y recibidos por un `MQTTClient`, compartidos entre
+/// el `Retransmitter` (envío) y el `MQTTClientListener` (recepción).
+/// Permiten diagnosticar si una actualización perdida se debe al lado que publica o al que se suscribe.
+#[derive(Debug)]
+pub struct MQTTClientStats {
+    publishes_sent: Arc<AtomicU64>,
+    pubacks_received: Arc<AtomicU64>,
+    subscribes_sent: Arc<AtomicU64>,
+    msgs_received: Arc<AtomicU64>,
+    msgs_received_by_app_topic: Arc<[AtomicU64; APP_TOPICS.len()]>,
+    msgs_received_by_other_topic: Arc<Mutex<HashMap<String, u64>>>, // ej. los will topics, poco frecuentes
+}
+
+/// Foto de los contadores de `MQTTClientStats` en un momento dado.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct MQTTClientStatsSnapshot {
+    publishes_sent: u64,
+    pubacks_received: u64,
+    subscribes_sent: u64,
+    msgs_received: u64,
+    msgs_received_by_topic: HashMap<String, u64>,
+}
+
+impl MQTTClientStats {
+    /// Crea los contadores, todos en cero.
+    pub fn new() -> Self {
+        Self {
+            publishes_sent: Arc::new(AtomicU64::new(0)),
+            pubacks_received: Arc::new(AtomicU64::new(0)),
+            subscribes_sent: Arc::new(AtomicU64::new(0)),
+            msgs_received: Arc::new(AtomicU64::new(0)),
+            msgs_received_by_app_topic: Arc::new(Default::default()),
+            msgs_received_by_other_topic: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn clone_ref(&self) -> Self {
+        Self {
+            publishes_sent: self.publishes_sent.clone(),
+            pubacks_received: self.pubacks_received.clone(),
+            subscribes_sent: self.subscribes_sent.clone(),
+            msgs_received: self.msgs_received.clone(),
+            msgs_received_by_app_topic: self.msgs_received_by_app_topic.clone(),
+            msgs_received_by_other_topic: self.msgs_received_by_other_topic.clone(),
+        }
+    }
+
+    pub fn increment_publishes_sent(&self) {
+        self.publishes_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn increment_pubacks_received(&self) {
+        self.pubacks_received.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn increment_subscribes_sent(&self) {
+        self.subscribes_sent.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Incrementa el total de mensajes recibidos, y el contador del `topic` recibido.
+    /// Los topics de las apps, que se reciben en cada mensaje, se cuentan sin tomar lock;
+    /// solamente los demás topics requieren tomarlo.
+    pub fn increment_msgs_received_for(&self, topic: &str) {
+        self.msgs_received.fetch_add(1, Ordering::Relaxed);
+        match AppsMqttTopics::from_str(topic) {
+            Some(app_topic) => {
+                self.msgs_received_by_app_topic[app_topic_index(&app_topic)].fetch_add(1, Ordering::Relaxed);
+            }
+            None => {
+                if let Ok(mut by_topic) = self.msgs_received_by_other_topic.lock() {
+                    *by_topic.entry(topic.to_string()).or_insert(0) += 1;
+                }
+            }
+        }
+    }
+
+    /// Devuelve una foto de los contadores actuales.
+    /// Los contadores se leen sin tomar lock, salvo el detalle de los topics que no son de las apps.
+    pub fn snapshot(&self) -> MQTTClientStatsSnapshot {
+        let mut msgs_received_by_topic = match self.msgs_received_by_other_topic.lock() {
+            Ok(by_topic) => by_topic.clone(),
+            Err(_) => HashMap::new(),
+        };
+        for app_topic in APP_TOPICS.iter() {
+            let count = self.msgs_received_by_app_topic[app_topic_index(app_topic)].load(Ordering::Relaxed);
+            if count > 0 {
+                msgs_received_by_topic.insert(app_topic.to_str().to_string(), count);
+            }
+        }
+        MQTTClientStatsSnapshot {
+            publishes_sent: self.publishes_sent.load(Ordering::Relaxed),
+            pubacks_received: self.pubacks_received.load(Ordering::Relaxed),
+            subscribes_sent: self.subscribes_sent.load(Ordering::Relaxed),
+            msgs_received: self.msgs_received.load(Ordering::Relaxed),
+            msgs_received_by_topic,
+        }
+    }
+}
+
+/// Devuelve la posición del contador de `app_topic` en `msgs_received_by_app_topic`.
+fn app_topic_index(app_topic: &AppsMqttTopics) -> usize {
+    match app_topic {
+        AppsMqttTopics::IncidentTopic => 0,
+        AppsMqttTopics::DronTopic => 1,
+        AppsMqttTopics::CameraTopic => 2,
+        AppsMqttTopics::DescTopic => 3,
+        AppsMqttTopics::CameraFrameTopic => 4,
+    }
+}
+
+impl Default for MQTTClientStats {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MQTTClientStatsSnapshot {
+    pub fn get_publishes_sent(&self) -> u64 {
+        self.publishes_sent
+    }
+
+    pub fn get_pubacks_received(&self) -> u64 {
+        self.pubacks_received
+    }
+
+    pub fn get_subscribes_sent(&self) -> u64 {
+        self.subscribes_sent
+    }
+
+    pub fn get_msgs_received(&self) -> u64 {
+        self.msgs_received
+    }
+
+    /// Devuelve la cantidad de mensajes recibidos para el `topic`, 0 si no se recibió ninguno.
+    pub fn get_msgs_received_for(&self, topic: &str) -> u64 {
+        *self.msgs_received_by_topic.get(topic).unwrap_or(&0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::MQTTClientStats;
+
+    #[test]
+    fn test_1_los_contadores_se_comparten_entre_clones() {
+        let stats = MQTTClientStats::new();
+        let stats_listener = stats.clone_ref();
+
+        stats.increment_publishes_sent();
+        stats.increment_publishes_sent();
+        stats.increment_subscribes_sent();
+        stats_listener.increment_pubacks_received();
+        stats_listener.increment_msgs_received_for("cam");
+        stats_listener.increment_msgs_received_for("cam");
+        stats_listener.increment_msgs_received_for("inc");
+        stats_listener.increment_msgs_received_for("will/dron");
+
+        let snapshot = stats.snapshot();
+        assert_eq!(snapshot.get_publishes_sent(), 2);
+        assert_eq!(snapshot.get_subscribes_sent(), 1);
+        assert_eq!(snapshot.get_pubacks_received(), 1);
+        assert_eq!(snapshot.get_msgs_received(), 4);
+        assert_eq!(snapshot.get_msgs_received_for("cam"), 2);
+        assert_eq!(snapshot.get_msgs_received_for("inc"), 1);
+        assert_eq!(snapshot.get_msgs_received_for("will/dron"), 1);
+        assert_eq!(snapshot.get_msgs_received_for("dron"), 0);
+    }
+}
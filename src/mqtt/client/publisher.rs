@@ -0,0 +1,210 @@
+use std::{
+    fmt::Debug,
+    sync::{Arc, Mutex},
+};
+
+use crate::mqtt::{
+    client::{
+        mqtt_client::MQTTClient, mqtt_client_listener::ConnectionEnd,
+        mqtt_client_msg_creator::MessageCreator,
+    },
+    messages::publish_message::PublishMessage,
+    mqtt_utils::{
+        mqtt_error::MqttError,
+        topic::{Topic, TopicFilter},
+    },
+};
+
+/// Destino de los publish de una app. Permite que la app funcione tanto con un `MQTTClient` conectado
+/// a un broker, como sin él (ej. con un `NullPublisher`, para tests o para un modo de prueba sin publicar).
+pub trait Publisher: Debug + Send {
+    /// Publica el `payload` al `topic` con el `qos` indicado, y devuelve el mensaje publicado.
+    /// Con qos 1, devolver Ok implica que se recibió el ack; si no se recibió, devuelve `MqttError::Timeout`.
+    /// Para publicar a un topic que se tiene como string, construir antes el `Topic` con `Topic::try_from`.
+    fn publish(&mut self, topic: &Topic, payload: &[u8], qos: u8) -> Result<PublishMessage, MqttError>;
+
+    /// Se suscribe a los `topics`. Por defecto no hace nada.
+    fn subscribe(&mut self, _topics: Vec<(TopicFilter, u8)>) -> Result<(), MqttError> {
+        Ok(())
+    }
+
+    /// Se desuscribe de los `topics`. Por defecto no hace nada.
+    fn unsubscribe(&mut self, _topics: Vec<TopicFilter>) -> Result<(), MqttError> {
+        Ok(())
+    }
+
+    /// Termina la conexión. Por defecto no hace nada.
+    fn disconnect(&mut self) -> Result<(), MqttError> {
+        Ok(())
+    }
+
+    /// Devuelve si se perdió la conexión (sin haberse solicitado terminarla). Por defecto, nunca se pierde.
+    fn is_connection_lost(&self) -> bool {
+        false
+    }
+
+    /// Devuelve el motivo por el que se perdió la conexión, para decidir si reconectarse o salir.
+    /// Por defecto, nunca se pierde.
+    fn get_connection_end(&self) -> Option<ConnectionEnd> {
+        None
+    }
+
+    /// Vuelve a conectarse, luego de perderse la conexión. Por defecto no hace nada.
+    fn reconnect(&mut self) -> Result<(), MqttError> {
+        Ok(())
+    }
+
+    /// Devuelve si el broker conservaba la sesión en la última conexión, con lo que no es necesario volver
+    /// a suscribirse. Por defecto, no la conserva.
+    fn is_session_present(&self) -> bool {
+        false
+    }
+}
+
+impl Publisher for MQTTClient {
+    fn publish(&mut self, topic: &Topic, payload: &[u8], qos: u8) -> Result<PublishMessage, MqttError> {
+        self.mqtt_publish(topic.clone(), payload, qos)
+    }
+
+    fn subscribe(&mut self, topics: Vec<(TopicFilter, u8)>) -> Result<(), MqttError> {
+        self.mqtt_subscribe(topics)
+    }
+
+    fn unsubscribe(&mut self, topics: Vec<TopicFilter>) -> Result<(), MqttError> {
+        self.mqtt_unsubscribe(topics)
+    }
+
+    fn disconnect(&mut self) -> Result<(), MqttError> {
+        self.mqtt_disconnect()
+    }
+
+    fn is_connection_lost(&self) -> bool {
+        MQTTClient::is_connection_lost(self)
+    }
+
+    fn get_connection_end(&self) -> Option<ConnectionEnd> {
+        MQTTClient::get_connection_end(self)
+    }
+
+    fn reconnect(&mut self) -> Result<(), MqttError> {
+        Ok(self.mqtt_reconnect()?)
+    }
+
+    fn is_session_present(&self) -> bool {
+        MQTTClient::is_session_present(self)
+    }
+}
+
+impl<P: Publisher + ?Sized> Publisher for Box<P> {
+    fn publish(&mut self, topic: &Topic, payload: &[u8], qos: u8) -> Result<PublishMessage, MqttError> {
+        (**self).publish(topic, payload, qos)
+    }
+
+    fn subscribe(&mut self, topics: Vec<(TopicFilter, u8)>) -> Result<(), MqttError> {
+        (**self).subscribe(topics)
+    }
+
+    fn unsubscribe(&mut self, topics: Vec<TopicFilter>) -> Result<(), MqttError> {
+        (**self).unsubscribe(topics)
+    }
+
+    fn disconnect(&mut self) -> Result<(), MqttError> {
+        (**self).disconnect()
+    }
+
+    fn is_connection_lost(&self) -> bool {
+        (**self).is_connection_lost()
+    }
+
+    fn get_connection_end(&self) -> Option<ConnectionEnd> {
+        (**self).get_connection_end()
+    }
+
+    fn reconnect(&mut self) -> Result<(), MqttError> {
+        (**self).reconnect()
+    }
+
+    fn is_session_present(&self) -> bool {
+        (**self).is_session_present()
+    }
+}
+
+/// Publish registrado por el `NullPublisher`: topic, payload y qos.
+pub type RecordedPublish = (String, Vec<u8>, u8);
+
+/// Publisher que no envía nada: solamente registra los publish que se le solicitan, para poder consultarlos.
+/// Sus clones comparten los registros, por lo que puede entregarse un clon a la app y consultar desde el otro.
+#[derive(Debug, Clone)]
+pub struct NullPublisher {
+    published: Arc<Mutex<Vec<RecordedPublish>>>,
+    subscribed: Arc<Mutex<Vec<(String, u8)>>>,
+    msg_creator: Arc<Mutex<MessageCreator>>,
+}
+
+impl NullPublisher {
+    pub fn new() -> Self {
+        Self {
+            published: Arc::new(Mutex::new(vec![])),
+            subscribed: Arc::new(Mutex::new(vec![])),
+            msg_creator: Arc::new(Mutex::new(MessageCreator::new())),
+        }
+    }
+
+    /// Devuelve los publish registrados hasta el momento, en orden.
+    pub fn get_published(&self) -> Vec<RecordedPublish> {
+        if let Ok(published) = self.published.lock() {
+            return published.clone();
+        }
+        vec![]
+    }
+
+    /// Devuelve los publish registrados hasta el momento al `topic`, en orden.
+    pub fn get_published_to(&self, topic: &str) -> Vec<Vec<u8>> {
+        self.get_published()
+            .into_iter()
+            .filter(|(t, _, _)| t == topic)
+            .map(|(_, payload, _)| payload)
+            .collect()
+    }
+
+    /// Devuelve los topics a los que se está suscripto: a los que se solicitó suscribirse, y luego no desuscribirse.
+    pub fn get_subscribed(&self) -> Vec<(String, u8)> {
+        if let Ok(subscribed) = self.subscribed.lock() {
+            return subscribed.clone();
+        }
+        vec![]
+    }
+}
+
+impl Default for NullPublisher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Publisher for NullPublisher {
+    fn publish(&mut self, topic: &Topic, payload: &[u8], qos: u8) -> Result<PublishMessage, MqttError> {
+        let msg = match self.msg_creator.lock() {
+            Ok(mut msg_creator) => msg_creator.create_publish_msg(topic.as_str(), payload, qos)?,
+            Err(_) => return Err(MqttError::Protocol("Error al tomar el lock".to_string())),
+        };
+        if let Ok(mut published) = self.published.lock() {
+            published.push((topic.as_str().to_string(), payload.to_vec(), qos));
+        }
+        Ok(msg)
+    }
+
+    fn subscribe(&mut self, topics: Vec<(TopicFilter, u8)>) -> Result<(), MqttError> {
+        if let Ok(mut subscribed) = self.subscribed.lock() {
+            subscribed.extend(topics.into_iter().map(|(filter, qos)| (filter.as_str().to_string(), qos)));
+        }
+        Ok(())
+    }
+
+    fn unsubscribe(&mut self, topics: Vec<TopicFilter>) -> Result<(), MqttError> {
+        if let Ok(mut subscribed) = self.subscribed.lock() {
+            subscribed.retain(|(topic, _)| !topics.iter().any(|filter| filter.as_str() == topic));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use crate::{
+    logging::string_logger::StringLogger,
+    mqtt::{client::publisher::Publisher, mqtt_utils::shutdown_token::ShutdownToken},
+};
+
+/// Espera antes del primer intento de reconexión.
+pub const DEFAULT_INITIAL_BACKOFF: Duration = Duration::from_millis(500);
+/// Espera máxima entre dos intentos de reconexión.
+pub const DEFAULT_MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Intenta reconectar al `publisher` hasta lograrlo, duplicando la espera entre intentos (desde `initial_backoff`
+/// hasta a lo sumo `max_backoff`), para no saturar a un server que se está reiniciando.
+/// Devuelve si se reconectó; devuelve false si antes se solicitó el `shutdown_token`.
+pub fn reconnect_with_backoff<P: Publisher + ?Sized>(
+    publisher: &Arc<Mutex<P>>,
+    initial_backoff: Duration,
+    max_backoff: Duration,
+    shutdown_token: &ShutdownToken,
+    logger: &StringLogger,
+) -> bool {
+    let mut backoff = initial_backoff;
+    let mut attempt: u32 = 1;
+    loop {
+        if shutdown_token.sleep_unless_requested(backoff) {
+            return false;
+        }
+        let res_reconnect = match publisher.lock() {
+            Ok(mut publisher) => publisher.reconnect(),
+            Err(_) => return false,
+        };
+        match res_reconnect {
+            Ok(_) => {
+                logger.log(format!("Reconectado al broker, en el intento {}.", attempt));
+                return true;
+            }
+            Err(e) => {
+                backoff = (backoff * 2).min(max_backoff);
+                logger.log(format!(
+                    "Intento {} de reconexión fallido: {:?}. Próximo intento en {:?}.",
+                    attempt, e, backoff
+                ));
+            }
+        }
+        attempt += 1;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{mpsc, Arc, Mutex},
+        time::Duration,
+    };
+
+    use super::reconnect_with_backoff;
+    use crate::{
+        logging::string_logger::StringLogger,
+        mqtt::{
+            client::publisher::Publisher, messages::publish_message::PublishMessage,
+            mqtt_utils::{mqtt_error::MqttError, shutdown_token::ShutdownToken, topic::Topic},
+        },
+    };
+
+    /// Publisher cuya reconexión falla las primeras `failures` veces.
+    #[derive(Debug)]
+    struct FlakyPublisher {
+        failures: u8,
+        attempts: u8,
+    }
+
+    impl Publisher for FlakyPublisher {
+        fn publish(&mut self, _topic: &Topic, _payload: &[u8], _qos: u8) -> Result<PublishMessage, MqttError> {
+            Err(MqttError::Protocol("No publica".to_string()))
+        }
+
+        fn reconnect(&mut self) -> Result<(), MqttError> {
+            self.attempts += 1;
+            if self.attempts <= self.failures {
+                return Err(MqttError::Timeout("Server no disponible".to_string()));
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_1_se_reintenta_la_reconexion_hasta_lograrla() {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let publisher = Arc::new(Mutex::new(FlakyPublisher { failures: 2, attempts: 0 }));
+
+        let reconnected = reconnect_with_backoff(
+            &publisher,
+            Duration::from_millis(1),
+            Duration::from_millis(4),
+            &ShutdownToken::new(),
+            &StringLogger::new(logger_tx),
+        );
+
+        assert!(reconnected);
+        assert_eq!(publisher.lock().unwrap().attempts, 3);
+    }
+
+    #[test]
+    fn test_2_no_se_reintenta_si_se_solicito_terminar() {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let publisher = Arc::new(Mutex::new(FlakyPublisher { failures: u8::MAX, attempts: 0 }));
+        let shutdown_token = ShutdownToken::new();
+        shutdown_token.request();
+
+        let reconnected = reconnect_with_backoff(
+            &publisher,
+            Duration::from_millis(1),
+            Duration::from_millis(4),
+            &shutdown_token,
+            &StringLogger::new(logger_tx),
+        );
+
+        assert!(!reconnected);
+        assert_eq!(publisher.lock().unwrap().attempts, 0);
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    collections::VecDeque,
+    fs,
+    io::Error,
+    path::{Path, PathBuf},
+};
+
+use crate::{logging::string_logger::StringLogger, mqtt::messages::publish_message::PublishMessage};
+
+/// Cantidad máxima de publish sin ack que se conservan, si no se configura otra.
+pub const DEFAULT_UNACKED_CAPACITY: usize = 64;
+
+/// Extensión de los archivos en que se persisten los publish sin ack.
+const SPILL_FILE_EXTENSION: &str = "publish";
+
+/// Publish de qos 1 cuyo ack todavía no se recibió, para volver a publicarlos al reconectarse.
+/// La cola es acotada: si se llena, se descarta el más antiguo. Opcionalmente se persiste cada publish en un
+/// archivo `<packet_id>.publish` del `spill_dir`, para no perderlos si la app termina abruptamente.
+#[derive(Debug)]
+pub struct UnackedPublishes {
+    capacity: usize,
+    pending: VecDeque<PublishMessage>,
+    spill_dir: Option<PathBuf>,
+    logger: StringLogger,
+}
+
+impl UnackedPublishes {
+    /// Crea la cola, solamente en memoria.
+    pub fn new(capacity: usize, logger: StringLogger) -> Self {
+        Self {
+            capacity,
+            pending: VecDeque::new(),
+            spill_dir: None,
+            logger,
+        }
+    }
+
+    /// Crea la cola, persistiendo los publish en `spill_dir` (que se crea si no existía). Carga los que hayan
+    /// quedado persistidos de una ejecución anterior, en el orden en que se publicaron.
+    pub fn with_spill_dir(capacity: usize, spill_dir: &Path, logger: StringLogger) -> Result<Self, Error> {
+        fs::create_dir_all(spill_dir)?;
+        let mut stored = vec![];
+        for entry in fs::read_dir(spill_dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some(SPILL_FILE_EXTENSION) {
+                continue;
+            }
+            match fs::read(&path).and_then(PublishMessage::from_bytes) {
+                Ok(msg) => stored.push(msg),
+                Err(e) => {
+                    logger.log(format!("Mqtt: se descarta publish sin ack ilegible {:?}: {:?}.", path, e));
+                    let _ = fs::remove_file(&path);
+                }
+            }
+        }
+        stored.sort_by_key(|msg| msg.get_timestamp());
+
+        let mut unacked = Self {
+            capacity,
+            pending: VecDeque::new(),
+            spill_dir: Some(spill_dir.to_path_buf()),
+            logger,
+        };
+        for msg in stored {
+            unacked.push(msg);
+        }
+        Ok(unacked)
+    }
+
+    /// Agrega el `msg`, a la espera de su ack. Si la cola está llena, descarta el publish más antiguo.
+    pub fn add(&mut self, msg: &PublishMessage) {
+        if let Some(packet_id) = msg.get_packet_id() {
+            self.remove(packet_id);
+            self.persist(msg);
+            self.push(msg.clone());
+        }
+    }
+
+    /// Quita el publish del `packet_id`, porque se recibió su ack (o porque se lo volvió a publicar).
+    pub fn remove(&mut self, packet_id: u16) {
+        self.pending.retain(|msg| msg.get_packet_id() != Some(packet_id));
+        if let Some(path) = self.spill_path_for(packet_id) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    /// Devuelve si el publish del `packet_id` está esperando su ack.
+    pub fn contains(&self, packet_id: u16) -> bool {
+        self.pending.iter().any(|msg| msg.get_packet_id() == Some(packet_id))
+    }
+
+    /// Devuelve una copia de los publish sin ack, del más antiguo al más reciente.
+    pub fn get_pending(&self) -> Vec<PublishMessage> {
+        self.pending.iter().cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.pending.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    /// Encola el `msg` (ya persistido), descartando el más antiguo si se supera la capacidad.
+    fn push(&mut self, msg: PublishMessage) {
+        self.pending.push_back(msg);
+        while self.pending.len() > self.capacity {
+            if let Some(oldest) = self.pending.pop_front() {
+                let error_msg = format!(
+                    "ERROR: cola de publish sin ack llena, se descarta el más antiguo: topic {}, packet_id {:?}.",
+                    oldest.get_topic(),
+                    oldest.get_packet_id()
+                );
+                println!("{}", error_msg);
+                self.logger.log(error_msg);
+                if let Some(path) = oldest.get_packet_id().and_then(|id| self.spill_path_for(id)) {
+                    let _ = fs::remove_file(path);
+                }
+            }
+        }
+    }
+
+    /// Persiste el `msg`, si la cola tiene `spill_dir`. Si no puede persistirse, se lo conserva solamente en memoria.
+    fn persist(&self, msg: &PublishMessage) {
+        if let Some(path) = msg.get_packet_id().and_then(|id| self.spill_path_for(id)) {
+            if let Err(e) = fs::write(&path, msg.to_bytes()) {
+                self.logger
+                    .log(format!("Mqtt: error al persistir publish sin ack en {:?}: {:?}.", path, e));
+            }
+        }
+    }
+
+    fn spill_path_for(&self, packet_id: u16) -> Option<PathBuf> {
+        self.spill_dir
+            .as_ref()
+            .map(|dir| dir.join(format!("{}.{}", packet_id, SPILL_FILE_EXTENSION)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, sync::mpsc};
+
+    use super::UnackedPublishes;
+    use crate::{
+        logging::string_logger::StringLogger,
+        mqtt::messages::{publish_flags::PublishFlags, publish_message::PublishMessage},
+    };
+
+    fn create_publish(packet_id: u16) -> PublishMessage {
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        PublishMessage::new(flags, "Inc", Some(packet_id), &[packet_id as u8]).unwrap()
+    }
+
+    fn create_logger() -> StringLogger {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        StringLogger::new(logger_tx)
+    }
+
+    #[test]
+    fn test_1_al_llenarse_la_cola_se_descarta_el_publish_mas_antiguo() {
+        let mut unacked = UnackedPublishes::new(2, create_logger());
+        unacked.add(&create_publish(1));
+        unacked.add(&create_publish(2));
+        unacked.add(&create_publish(3));
+
+        let pending_ids: Vec<_> = unacked.get_pending().iter().map(|msg| msg.get_packet_id()).collect();
+        assert_eq!(pending_ids, vec![Some(2), Some(3)]);
+
+        // Al recibirse el ack, se lo quita
+        unacked.remove(2);
+        assert_eq!(unacked.len(), 1);
+    }
+
+    #[test]
+    fn test_2_los_publish_persistidos_se_recuperan_en_orden_al_volver_a_crear_la_cola() {
+        let spill_dir = std::env::temp_dir().join("test_unacked_publishes_2");
+        let _ = fs::remove_dir_all(&spill_dir);
+
+        let mut unacked = UnackedPublishes::with_spill_dir(4, &spill_dir, create_logger()).unwrap();
+        let (first, second, acked) = (create_publish(7), create_publish(3), create_publish(5));
+        unacked.add(&first);
+        unacked.add(&second);
+        unacked.add(&acked);
+        unacked.remove(5);
+        // Simula que la app termina abruptamente
+        drop(unacked);
+
+        let recovered = UnackedPublishes::with_spill_dir(4, &spill_dir, create_logger()).unwrap();
+        assert_eq!(recovered.get_pending(), vec![first, second]);
+
+        let _ = fs::remove_dir_all(&spill_dir);
+    }
+}
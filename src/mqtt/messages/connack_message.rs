@@ -1,10 +1,16 @@
-use std::io::Error;
+use std::io::{Error, ErrorKind};
 
 use crate::mqtt::messages::{
     connack_fixed_header::FixedHeader, connack_session_present::SessionPresent,
     connack_variable_header::VariableHeader, connect_return_code::ConnectReturnCode,
 };
 
+/// Bit de los connect acknowledge flags que indica session present.
+const SESSION_PRESENT_FLAG: u8 = 0x01;
+/// Bit de los connect acknowledge flags (reservado en MQTT 3.1.1) con que el server acepta los alias de topic
+/// que solicitó el cliente. Solamente se envía si el cliente los solicitó; los demás bits son reservados.
+const TOPIC_ALIASES_FLAG: u8 = 0x02;
+
 #[derive(Debug)]
 pub struct ConnackMessage {
     fixed_header: FixedHeader,
@@ -12,6 +18,7 @@ pub struct ConnackMessage {
 }
 
 impl ConnackMessage {
+    /// Crea el connack. Si la conexión se rechaza, el flag session present se envía siempre en 0.
     pub fn new(session_present: SessionPresent, return_code: ConnectReturnCode) -> Self {
         let fixed_header = FixedHeader {
             message_type: 0b0010_0000, // 0010 for MQTT Control Packet Type (2) and 0000 for reserved
@@ -19,8 +26,9 @@ impl ConnackMessage {
         };
 
         let connect_acknowledge_flags = match session_present {
-            SessionPresent::PresentInLastSession => 0x01,
-            SessionPresent::NotPresentInLastSession => 0x00,
+            SessionPresent::PresentInLastSession
+                if return_code == ConnectReturnCode::ConnectionAccepted => SESSION_PRESENT_FLAG,
+            _ => 0x00,
         };
 
         let variable_header = VariableHeader {
@@ -53,7 +61,17 @@ impl ConnackMessage {
         bytes
     }
 
+    /// Parsea el connack. Devuelve error si faltan bytes, o si los bits reservados de los flags no son 0.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        if bytes.len() < 4 {
+            return Err(Error::new(ErrorKind::InvalidData, "Connack incompleto."));
+        }
+        if bytes[2] & !(SESSION_PRESENT_FLAG | TOPIC_ALIASES_FLAG) != 0 {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Connack con flags reservados distintos de 0.",
+            ));
+        }
         let fixed_header = FixedHeader {
             message_type: bytes[0],
             remaining_length: bytes[1],
@@ -75,6 +93,24 @@ impl ConnackMessage {
     pub fn get_connect_return_code(&self) -> ConnectReturnCode {
         self.variable_header.connect_return_code.clone()
     }
+
+    /// Indica que el server acepta los alias de topic, si la conexión fue aceptada.
+    pub fn accepting_topic_aliases(mut self) -> Self {
+        if self.variable_header.connect_return_code == ConnectReturnCode::ConnectionAccepted {
+            self.variable_header.connect_acknowledge_flags |= TOPIC_ALIASES_FLAG;
+        }
+        self
+    }
+
+    /// Devuelve si el server acepta que el cliente use alias de topic en sus publish.
+    pub fn topic_aliases_accepted(&self) -> bool {
+        self.variable_header.connect_acknowledge_flags & TOPIC_ALIASES_FLAG != 0
+    }
+
+    /// Devuelve si el server conservaba una sesión del cliente (y por lo tanto sus suscripciones).
+    pub fn session_present(&self) -> bool {
+        self.variable_header.connect_acknowledge_flags & SESSION_PRESENT_FLAG != 0
+    }
 }
 
 #[cfg(test)]
@@ -123,4 +159,48 @@ mod tests {
             ConnectReturnCode::ConnectionAccepted
         );
     }
+
+    #[test]
+    fn test_session_present_round_trip() {
+        for (session_present, expected) in [
+            (SessionPresent::PresentInLastSession, true),
+            (SessionPresent::NotPresentInLastSession, false),
+        ] {
+            let bytes = ConnackMessage::new(session_present, ConnectReturnCode::ConnectionAccepted).to_bytes();
+            let connack_packet = ConnackMessage::from_bytes(&bytes).unwrap();
+            assert_eq!(connack_packet.session_present(), expected);
+        }
+
+        // Si se rechaza la conexión, no hay sesión
+        let rejected = ConnackMessage::new(
+            SessionPresent::PresentInLastSession,
+            ConnectReturnCode::NotAuthorized,
+        );
+        assert!(!ConnackMessage::from_bytes(&rejected.to_bytes()).unwrap().session_present());
+    }
+
+    #[test]
+    fn test_from_bytes_con_flags_reservados_da_error() {
+        assert!(ConnackMessage::from_bytes(&[0b0010_0000, 2, 0b0000_0100, 0]).is_err());
+        assert!(ConnackMessage::from_bytes(&[0b0010_0000, 2, 1]).is_err());
+    }
+
+    #[test]
+    fn test_alias_de_topic_solamente_si_se_acepta_la_conexion() {
+        let accepted = ConnackMessage::new(
+            SessionPresent::NotPresentInLastSession,
+            ConnectReturnCode::ConnectionAccepted,
+        )
+        .accepting_topic_aliases();
+        assert!(ConnackMessage::from_bytes(&accepted.to_bytes())
+            .unwrap()
+            .topic_aliases_accepted());
+
+        let rejected = ConnackMessage::new(
+            SessionPresent::NotPresentInLastSession,
+            ConnectReturnCode::NotAuthorized,
+        )
+        .accepting_topic_aliases();
+        assert!(!rejected.topic_aliases_accepted());
+    }
 }
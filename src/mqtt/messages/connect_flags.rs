@@ -7,7 +7,7 @@ pub struct ConnectFlags {
     pub will_qos: u8,        // bits 3-4
     pub will_flag: bool,     // bit 2
     pub clean_session: bool, // bit 1
-    pub reserved: bool,      // bit 0
+    pub reserved: bool,      // bit 0: reservado en MQTT 3.1.1; aquí indica que el cliente solicita alias de topic
 }
 
 impl ConnectFlags {
@@ -49,6 +49,9 @@ impl ConnectFlags {
         if self.clean_session {
             byte |= 0x02;
         }
+        if self.reserved {
+            byte |= 0x01;
+        }
         byte
     }
 
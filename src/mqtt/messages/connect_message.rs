@@ -11,6 +11,8 @@ pub struct ConnectMessage {
 }
 
 impl ConnectMessage {
+    /// Crea el mensaje a partir de sus campos posicionales, con keep alive deshabilitado.
+    /// Es preferible utilizar `ConnectMessageBuilder`, que además valida los campos.
     pub fn new(
         client_id: String,
         will_topic: Option<String>,
@@ -19,23 +21,14 @@ impl ConnectMessage {
         password: Option<String>,
         will_qos: u8,
     ) -> Self {
-        let fixed_header = FixedHeader {
-            message_type: 1 << 4,
-            remaining_length: 0,
-        };
-
-        let variable_header = VariableHeader {
-            protocol_name: [77, 81, 84, 84], // "MQTT" en ASCII
-            protocol_level: 4,               // MQTT 3.1.1
-            connect_flags: ConnectFlags {
-                username_flag: username.is_some(),
-                password_flag: password.is_some(),
-                will_retain: true,
-                will_qos,
-                will_flag: will_topic.is_some() && will_message.is_some(),
-                clean_session: true,
-                reserved: false,
-            },
+        let connect_flags = ConnectFlags {
+            username_flag: username.is_some(),
+            password_flag: password.is_some(),
+            will_retain: true,
+            will_qos,
+            will_flag: will_topic.is_some() && will_message.is_some(),
+            clean_session: true,
+            reserved: false,
         };
 
         let payload = Payload {
@@ -46,6 +39,23 @@ impl ConnectMessage {
             password,
         };
 
+        Self::from_parts(connect_flags, 0, payload)
+    }
+
+    /// Arma el mensaje con los flags, el keep alive (en segundos, 0 lo deshabilita) y el payload recibidos.
+    pub(crate) fn from_parts(connect_flags: ConnectFlags, keep_alive: u16, payload: Payload) -> Self {
+        let fixed_header = FixedHeader {
+            message_type: 1 << 4,
+            remaining_length: 0,
+        };
+
+        let variable_header = VariableHeader {
+            protocol_name: [77, 81, 84, 84], // "MQTT" en ASCII
+            protocol_level: 4,               // MQTT 3.1.1
+            connect_flags,
+            keep_alive,
+        };
+
         let mut connect_message = ConnectMessage {
             fixed_header,
             variable_header,
@@ -59,7 +69,12 @@ impl ConnectMessage {
     }
 
     fn calculate_remaining_length(&self) -> u8 {
-        let variable_header_length = 5 + 1 + 1;
+        self.calculate_remaining_length_usize() as u8
+    }
+
+    /// Calcula el remaining length sin truncarlo, para poder validar que entre en un byte.
+    pub(crate) fn calculate_remaining_length_usize(&self) -> usize {
+        let variable_header_length = 5 + 1 + 1 + 2;
         let length_string_u8 = 1;
         let payload_length = length_string_u8
             + self.payload.client_id.len()
@@ -84,7 +99,7 @@ impl ConnectMessage {
                 .as_ref()
                 .map_or(0, |s| s.len() + length_string_u8);
 
-        (variable_header_length + payload_length) as u8
+        variable_header_length + payload_length
     }
 
     /// Pasa un ConnectMessage a bytes.
@@ -103,6 +118,7 @@ impl ConnectMessage {
         bytes.push(self.variable_header.protocol_level);
         let connect_flags = self.variable_header.connect_flags.to_byte();
         bytes.push(connect_flags);
+        bytes.extend_from_slice(&self.variable_header.keep_alive.to_be_bytes());
 
         // Payload
         bytes.push(self.payload.client_id.len() as u8);
@@ -139,14 +155,15 @@ impl ConnectMessage {
             protocol_name: [bytes[3], bytes[4], bytes[5], bytes[6]],
             protocol_level: bytes[7],
             connect_flags: ConnectFlags::from_byte(bytes[8]),
+            keep_alive: u16::from_be_bytes([bytes[9], bytes[10]]),
         };
 
-        // Indice donde comienza el payload (son 2 bytes de fixed header y 7 bytes de var header)
-        let payload_start_index = 9;
+        // Indice donde comienza el payload (son 2 bytes de fixed header y 9 bytes de var header)
+        let payload_start_index = 11;
 
         // Calcular la longitud del payload
-        let variable_header_len: usize = 7; // (esto podría ser un método del variable header) // es payload_start_index - 2:
-        let payload_length = fixed_header.remaining_length as usize - variable_header_len; // Total - 7 bytes del variable header
+        let variable_header_len: usize = 9; // (esto podría ser un método del variable header) // es payload_start_index - 2:
+        let payload_length = fixed_header.remaining_length as usize - variable_header_len; // Total - 9 bytes del variable header
                                                                                            // Extraer el payload del mensaje
         let payload_bytes = &bytes[payload_start_index..payload_start_index + payload_length];
 
@@ -250,6 +267,26 @@ impl ConnectMessage {
         self.payload.password.as_ref()
     }
 
+    /// Devuelve si el cliente solicitó una sesión limpia (ie no conservar suscripciones de una sesión anterior).
+    pub fn is_clean_session(&self) -> bool {
+        self.variable_header.connect_flags.clean_session
+    }
+
+    /// Devuelve el keep alive solicitado por el cliente, en segundos. Si vale 0, está deshabilitado.
+    pub fn get_keep_alive(&self) -> u16 {
+        self.variable_header.keep_alive
+    }
+
+    /// Devuelve si el cliente solicitó usar alias de topic en los publish.
+    pub fn requests_topic_aliases(&self) -> bool {
+        self.variable_header.connect_flags.reserved
+    }
+
+    /// Setea el flag clean_session del mensaje.
+    pub fn set_clean_session(&mut self, clean_session: bool) {
+        self.variable_header.connect_flags.clean_session = clean_session;
+    }
+
     /// Devuelve el campo client_id del mensaje.
     pub fn get_client_id(&self) -> Option<&String> {
         Some(&self.payload.client_id)
@@ -383,4 +420,26 @@ mod tests {
         // Comprobamos que los mensajes son iguales
         assert_eq!(connect_message.payload, new_connect_message.payload);
     }
+
+    #[test]
+    fn test_to_and_from_bytes_incluye_el_keep_alive() {
+        let mut connect_message = ConnectMessage::from_parts(
+            ConnectFlags::new(true, false, 0, false, false, false, false),
+            300,
+            Payload {
+                client_id: "test_client".to_string(),
+                will_topic: None,
+                will_message: None,
+                username: None,
+                password: None,
+            },
+        );
+        let bytes = connect_message.to_bytes();
+
+        // El keep alive ocupa los dos bytes siguientes a los flags, en big endian
+        assert_eq!(&bytes[9..11], &300_u16.to_be_bytes());
+        let new_connect_message = ConnectMessage::from_bytes(&bytes);
+        assert_eq!(new_connect_message.get_keep_alive(), 300);
+        assert_eq!(connect_message.payload, new_connect_message.payload);
+    }
 }
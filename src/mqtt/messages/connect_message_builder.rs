@@ -0,0 +1,186 @@
+use std::io::{Error, ErrorKind};
+
+use crate::mqtt::messages::{
+    connect_flags::ConnectFlags, connect_message::ConnectMessage, connect_payload::Payload,
+};
+
+/// Will a incluir en el `ConnectMessage`: topic, contenido, qos y retain.
+#[derive(Debug)]
+struct BuilderWill {
+    topic: String,
+    payload: String,
+    qos: u8,
+    retain: bool,
+}
+
+/// Construye un `ConnectMessage` con setters con nombre, evitando confundir los parámetros posicionales
+/// de `ConnectMessage::new` (ej. intercambiar username y password). Por defecto, el mensaje no tiene will
+/// ni credenciales, solicita sesión limpia, tiene keep alive deshabilitado, y no solicita alias de topic.
+#[derive(Debug)]
+pub struct ConnectMessageBuilder {
+    client_id: String,
+    will: Option<BuilderWill>,
+    credentials: Option<(String, String)>,
+    clean_session: bool,
+    keep_alive: u16,
+    topic_aliases: bool,
+}
+
+impl ConnectMessageBuilder {
+    pub fn new(client_id: String) -> Self {
+        Self {
+            client_id,
+            will: None,
+            credentials: None,
+            clean_session: true,
+            keep_alive: 0,
+            topic_aliases: false,
+        }
+    }
+
+    /// Setea el will, que el server publicará en `topic` si el cliente se desconecta inesperadamente.
+    pub fn will(mut self, topic: String, payload: String, qos: u8, retain: bool) -> Self {
+        self.will = Some(BuilderWill {
+            topic,
+            payload,
+            qos,
+            retain,
+        });
+        self
+    }
+
+    /// Setea el usuario y la contraseña con los que el cliente se autentica.
+    pub fn credentials(mut self, user: String, pass: String) -> Self {
+        self.credentials = Some((user, pass));
+        self
+    }
+
+    /// Setea si se solicita una sesión limpia (ie no conservar suscripciones de una sesión anterior).
+    pub fn clean_session(mut self, clean_session: bool) -> Self {
+        self.clean_session = clean_session;
+        self
+    }
+
+    /// Setea el keep alive, en segundos. Con 0, se deshabilita.
+    pub fn keep_alive(mut self, keep_alive: u16) -> Self {
+        self.keep_alive = keep_alive;
+        self
+    }
+
+    /// Setea si se solicita usar alias de topic en los publish (ver `topic_alias`). Un server que no los
+    /// soporta ignora la solicitud, y no los acepta en el connack.
+    pub fn topic_aliases(mut self, topic_aliases: bool) -> Self {
+        self.topic_aliases = topic_aliases;
+        self
+    }
+
+    /// Devuelve el `ConnectMessage`, o error si sus campos no son válidos: un client id vacío sin sesión limpia,
+    /// un qos de will mayor a 2, o un mensaje demasiado largo para codificar su longitud.
+    pub fn build(self) -> Result<ConnectMessage, Error> {
+        if self.client_id.is_empty() && !self.clean_session {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Un client id vacío requiere sesión limpia.",
+            ));
+        }
+        if let Some(will) = &self.will {
+            if will.qos > 2 {
+                return Err(Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("QoS del will no válido: {}", will.qos),
+                ));
+            }
+        }
+
+        let (will_topic, will_message, will_qos, will_retain) = match self.will {
+            Some(will) => (Some(will.topic), Some(will.payload), will.qos, will.retain),
+            None => (None, None, 0, false),
+        };
+        let (username, password) = match self.credentials {
+            Some((user, pass)) => (Some(user), Some(pass)),
+            None => (None, None),
+        };
+
+        let connect_flags = ConnectFlags::new(
+            self.clean_session,
+            will_topic.is_some(),
+            will_qos,
+            will_retain,
+            username.is_some(),
+            password.is_some(),
+            self.topic_aliases,
+        );
+        let payload = Payload {
+            client_id: self.client_id,
+            will_topic,
+            will_message,
+            username,
+            password,
+        };
+
+        let msg = ConnectMessage::from_parts(connect_flags, self.keep_alive, payload);
+        if msg.calculate_remaining_length_usize() > u8::MAX as usize {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Connect demasiado largo.",
+            ));
+        }
+        Ok(msg)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ConnectMessageBuilder;
+    use crate::mqtt::messages::connect_message::ConnectMessage;
+
+    #[test]
+    fn test_1_el_builder_setea_cada_campo_y_sobrevive_to_y_from_bytes() {
+        let mut msg = ConnectMessageBuilder::new("dron-1".to_string())
+            .will("desc".to_string(), "dron-1".to_string(), 1, true)
+            .credentials("usuario0".to_string(), "rustx123".to_string())
+            .clean_session(false)
+            .keep_alive(30)
+            .build()
+            .unwrap();
+
+        let parsed = ConnectMessage::from_bytes(&msg.to_bytes());
+        assert_eq!(parsed.get_client_id().unwrap(), "dron-1");
+        assert_eq!(parsed.get_user().unwrap(), "usuario0");
+        assert_eq!(parsed.get_passwd().unwrap(), "rustx123");
+        assert!(!parsed.is_clean_session());
+        assert_eq!(parsed.get_keep_alive(), 30);
+        let will = parsed.get_will_to_publish().unwrap();
+        assert_eq!(will.get_will_topic(), "desc");
+        assert_eq!(will.get_qos(), 1);
+        assert_eq!(will.get_will_retain(), 1);
+    }
+
+    #[test]
+    fn test_2_por_defecto_no_hay_will_ni_credenciales_y_la_sesion_es_limpia() {
+        let mut msg = ConnectMessageBuilder::new("cliente".to_string()).build().unwrap();
+
+        let parsed = ConnectMessage::from_bytes(&msg.to_bytes());
+        assert!(parsed.get_will_to_publish().is_none());
+        assert!(parsed.get_user().is_none());
+        assert!(parsed.get_passwd().is_none());
+        assert!(parsed.is_clean_session());
+        assert_eq!(parsed.get_keep_alive(), 0);
+    }
+
+    #[test]
+    fn test_3_se_rechazan_campos_no_validos() {
+        let bad_qos = ConnectMessageBuilder::new("cliente".to_string())
+            .will("desc".to_string(), "cliente".to_string(), 3, false)
+            .build();
+        assert!(bad_qos.is_err());
+
+        let empty_id = ConnectMessageBuilder::new(String::new())
+            .clean_session(false)
+            .build();
+        assert!(empty_id.is_err());
+
+        let too_long = ConnectMessageBuilder::new("c".repeat(250)).build();
+        assert!(too_long.is_err());
+    }
+}
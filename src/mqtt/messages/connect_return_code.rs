@@ -23,6 +23,36 @@ impl ConnectReturnCode {
             ConnectReturnCode::UnspecifiedError => 0x80_u8.to_be_bytes(),
         }
     }
+    /// Devuelve una descripción legible del motivo indicado por el código.
+    pub fn description(&self) -> &'static str {
+        match self {
+            ConnectReturnCode::ConnectionAccepted => "conexión aceptada",
+            ConnectReturnCode::ProtocolError => "versión de protocolo inaceptable",
+            ConnectReturnCode::IdentifierRejected => "identificador de cliente rechazado",
+            ConnectReturnCode::ServerUnavailable => "servidor no disponible",
+            ConnectReturnCode::BadUsernameOrPassword => "usuario o contraseña incorrectos",
+            ConnectReturnCode::NotAuthorized => "cliente no autorizado",
+            ConnectReturnCode::UnspecifiedError => "error no especificado",
+        }
+    }
+
+    /// Devuelve el error correspondiente a que el servidor rechazó la conexión con este código,
+    /// o None si la conexión fue aceptada.
+    pub fn to_connection_error(&self) -> Option<Error> {
+        let kind = match self {
+            ConnectReturnCode::ConnectionAccepted => return None,
+            ConnectReturnCode::BadUsernameOrPassword | ConnectReturnCode::NotAuthorized => {
+                ErrorKind::PermissionDenied
+            }
+            ConnectReturnCode::ServerUnavailable => ErrorKind::ConnectionRefused,
+            _ => ErrorKind::InvalidData,
+        };
+        Some(Error::new(
+            kind,
+            format!("La conexión no fue aceptada: {}.", self.description()),
+        ))
+    }
+
     pub fn from_byte(bytes: [u8; 1]) -> Result<Self, Error> {
         match u8::from_be_bytes(bytes) {
             0 => Ok(ConnectReturnCode::ConnectionAccepted),
@@ -39,3 +69,28 @@ impl ConnectReturnCode {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::io::ErrorKind;
+
+    use super::ConnectReturnCode;
+
+    #[test]
+    fn test_1_cada_codigo_de_rechazo_devuelve_un_error_descriptivo() {
+        assert!(ConnectReturnCode::ConnectionAccepted
+            .to_connection_error()
+            .is_none());
+
+        let error = ConnectReturnCode::BadUsernameOrPassword
+            .to_connection_error()
+            .unwrap();
+        assert_eq!(error.kind(), ErrorKind::PermissionDenied);
+        assert!(error.to_string().contains("usuario o contraseña incorrectos"));
+
+        let error = ConnectReturnCode::IdentifierRejected
+            .to_connection_error()
+            .unwrap();
+        assert!(error.to_string().contains("identificador de cliente rechazado"));
+    }
+}
@@ -5,6 +5,7 @@ pub mod connack_variable_header;
 pub mod connect_fixed_header;
 pub mod connect_flags;
 pub mod connect_message;
+pub mod connect_message_builder;
 pub mod connect_payload;
 pub mod connect_return_code;
 pub mod connect_variable_header;
@@ -12,6 +13,8 @@ pub mod disconnect_fixed_header;
 pub mod disconnect_message;
 pub mod message_type;
 pub mod packet_type;
+pub mod pingreq_message;
+pub mod pingresp_message;
 pub mod puback_message;
 pub mod publish_fixed_header;
 pub mod publish_flags;
@@ -0,0 +1,26 @@
+use crate::mqtt::messages::packet_type::PacketType;
+
+/// Mensaje que envía el cliente para indicarle al server que sigue conectado, cuando no tiene
+/// otros mensajes para enviar dentro del keep alive. No tiene variable header ni payload.
+#[derive(Debug, PartialEq, Default)]
+pub struct PingReqMessage;
+
+impl PingReqMessage {
+    pub fn new() -> Self {
+        PingReqMessage
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        vec![(PacketType::Pingreq as u8) << 4, 0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PingReqMessage;
+
+    #[test]
+    fn test_pingreq_to_bytes() {
+        assert_eq!(PingReqMessage::new().to_bytes(), vec![0xC0, 0x00]);
+    }
+}
@@ -0,0 +1,25 @@
+use crate::mqtt::messages::packet_type::PacketType;
+
+/// Respuesta del server a un `PingReqMessage`. No tiene variable header ni payload.
+#[derive(Debug, PartialEq, Default)]
+pub struct PingRespMessage;
+
+impl PingRespMessage {
+    pub fn new() -> Self {
+        PingRespMessage
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        vec![(PacketType::Pingresp as u8) << 4, 0]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::PingRespMessage;
+
+    #[test]
+    fn test_pingresp_to_bytes() {
+        assert_eq!(PingRespMessage::new().to_bytes(), vec![0xD0, 0x00]);
+    }
+}
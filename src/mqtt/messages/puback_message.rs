@@ -2,6 +2,9 @@ use std::{
     io::{Error, ErrorKind},
     mem::size_of,
 };
+
+use super::publish_message::PublishMessage;
+
 #[derive(Debug, PartialEq)]
 pub struct PubAckMessage {
     // Fixed header
@@ -21,6 +24,17 @@ impl PubAckMessage {
         }
     }
 
+    /// Crea el PubAck (exitoso) para el publish `msg`, con su mismo packet id.
+    /// Un publish de qos 0 no lleva packet id ni se confirma; si igualmente se lo llama, se usa el id 0.
+    pub fn for_publish(msg: &PublishMessage) -> Self {
+        Self::new(msg.get_packet_id().unwrap_or(0), 0)
+    }
+
+    /// Devuelve si este PubAck confirma al publish `msg`, ie si repite su packet id.
+    pub fn acknowledges(&self, msg: &PublishMessage) -> bool {
+        msg.get_packet_id() == Some(self.packet_id)
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         let mut msg_bytes: Vec<u8> = vec![];
 
@@ -103,6 +117,7 @@ impl PubAckMessage {
 #[cfg(test)]
 mod test {
     use super::PubAckMessage;
+    use crate::mqtt::messages::{publish_flags::PublishFlags, publish_message::PublishMessage};
 
     #[test]
     fn test_1a_puback_msg_caso_success_tiene_rem_len_acorde() {
@@ -127,4 +142,19 @@ mod test {
 
         assert_eq!(msg_reconstruido.unwrap(), msg);
     }
+
+    #[test]
+    fn test_3_el_puback_creado_para_un_publish_repite_su_packet_id() {
+        let create_publish = |packet_id| {
+            let flags = PublishFlags::new(0, 1, 0).unwrap();
+            PublishMessage::new(flags, "inc", Some(packet_id), &[1]).unwrap()
+        };
+        let (publish, other_publish) = (create_publish(7), create_publish(8));
+
+        let ack = PubAckMessage::for_publish(&publish);
+
+        assert_eq!(ack, PubAckMessage::new(7, 0));
+        assert!(ack.acknowledges(&publish));
+        assert!(!ack.acknowledges(&other_publish));
+    }
 }
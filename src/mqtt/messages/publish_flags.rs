@@ -18,13 +18,36 @@ pub struct PublishFlags {
 impl PublishFlags {
     /// Recibe todos los valores de flags para mensaje publish excepto el tipo que siempre vale 3.
     /// Devuelve un struct PublishFlags creado, que contiene el byte de flags.
+    /// Devuelve error si la combinación de flags no es válida (ver `validate`).
     pub fn new(dup: u8, qos: u8, retain: u8) -> Result<Self, Error> {
-        if dup > 1 || qos > 2 || retain > 1 {
-            return Err(Error::new(ErrorKind::Other, "Flags para publish inválidos"));
-        }
+        Self::validate(dup, qos, retain)?;
         Ok(PublishFlags { msg_type: 3, dup, qos, retain })
     }
 
+    /// Verifica que los flags sean válidos según el protocolo: dup y retain deben ser 0 o 1,
+    /// qos debe ser 0, 1 o 2 (el 3 está prohibido), y con qos 0 el flag dup debe ser 0.
+    fn validate(dup: u8, qos: u8, retain: u8) -> Result<(), Error> {
+        if dup > 1 || retain > 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Flags para publish inválidos: dup y retain deben ser 0 o 1.",
+            ));
+        }
+        if qos > 2 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!("Flags para publish inválidos: qos {} no permitido.", qos),
+            ));
+        }
+        if qos == 0 && dup == 1 {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Flags para publish inválidos: con qos 0, dup debe ser 0.",
+            ));
+        }
+        Ok(())
+    }
+
     //Chequea si qos > 0
     pub fn is_qos_greater_than_0(&self) -> bool {
         self.qos > 0
@@ -51,6 +74,7 @@ impl PublishFlags {
         if msg_type != 3 {
             return Err(Error::new(ErrorKind::Other, "Flags para publish leídos con tipo inválido."));
         }
+        Self::validate(dup, qos, retain)?;
 
         Ok(PublishFlags { msg_type, dup, qos, retain })
     }
@@ -59,6 +83,16 @@ impl PublishFlags {
     pub fn get_qos(&self) -> u8 {
         self.qos
     }
+
+    /// Devuelve el flag dup.
+    pub fn get_dup(&self) -> u8 {
+        self.dup
+    }
+
+    /// Devuelve el flag retain.
+    pub fn get_retain(&self) -> u8 {
+        self.retain
+    }
 }
 
 #[cfg(test)]
@@ -115,6 +149,31 @@ mod test {
         assert!(flags_reconstruido_b.is_err());
     }
 
+    #[test]
+    fn test_5_qos_3_y_dup_con_qos_0_dan_error() {
+        // qos 3 (ambos bits en 1) está prohibido
+        assert!(PublishFlags::new(0, 3, 0).is_err());
+        assert!(PublishFlags::new(1, 3, 1).is_err());
+        // con qos 0, dup debe ser 0
+        assert!(PublishFlags::new(1, 0, 0).is_err());
+        assert!(PublishFlags::new(1, 0, 1).is_err());
+
+        // Tampoco se aceptan al leerlos de un byte: | 0011 | 0 | 11 | 0 | y | 0011 | 1 | 00 | 0 |
+        assert!(PublishFlags::from_flags_byte(0b0011_0110).is_err());
+        assert!(PublishFlags::from_flags_byte(0b0011_1000).is_err());
+    }
+
+    #[test]
+    fn test_6_combinaciones_validas_se_crean_correctamente() {
+        for (dup, qos, retain) in [(0, 0, 0), (0, 0, 1), (0, 1, 0), (1, 1, 0), (1, 2, 1), (0, 2, 1)] {
+            let flags = PublishFlags::new(dup, qos, retain).unwrap();
+            assert_eq!(flags.get_dup(), dup);
+            assert_eq!(flags.get_qos(), qos);
+            assert_eq!(flags.get_retain(), retain);
+            assert_eq!(PublishFlags::from_flags_byte(flags.to_flags_byte()).unwrap(), flags);
+        }
+    }
+
 
 }
 
@@ -3,6 +3,7 @@ extern crate des;
 extern crate hex;
 
 use std::io::{Error, ErrorKind};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::time::{SystemTime, UNIX_EPOCH};
 
 // use des::cipher::generic_array::GenericArray;
@@ -22,10 +23,40 @@ use crate::mqtt::messages::publish_fixed_header::FixedHeader;
 use crate::mqtt::messages::publish_flags::PublishFlags;
 use crate::mqtt::messages::publish_payload::Payload;
 use crate::mqtt::messages::publish_variable_header::VariableHeader;
+use crate::mqtt::mqtt_utils::payload_compression::{
+    add_compression_header, remove_compression_header, MAX_DECOMPRESSED_PAYLOAD_SIZE,
+};
+use crate::mqtt::mqtt_utils::fixed_header::{decode_remaining_length, encode_remaining_length};
+use crate::mqtt::mqtt_utils::topic_validation::{topic_from_utf8, validate_topic_name};
 
 type TimestampType = u128;
 const  TIMESTAMP_LENGHT: usize = 16;
 
+// Bit más significativo de la longitud del topic_name, que indica que luego del topic se envía su alias.
+const TOPIC_ALIAS_FLAG: u16 = 0x8000;
+// Longitud máxima del topic_name, ya que el bit más significativo de su longitud es `TOPIC_ALIAS_FLAG`.
+const MAX_TOPIC_NAME_LENGTH: usize = (TOPIC_ALIAS_FLAG - 1) as usize;
+
+// Último timestamp asignado a un publish creado por este proceso.
+static LAST_TIMESTAMP: AtomicU64 = AtomicU64::new(0);
+
+/// Devuelve el timestamp para un publish que se está creando: los nanosegundos desde UNIX_EPOCH, según el reloj
+/// del sistema. Como dos publish creados seguidos pueden leer el mismo valor del reloj (o uno menor, si el reloj
+/// se ajusta), se garantiza que sea estrictamente mayor al último asignado, para que el `OrderChecker` de quien
+/// los reciba no descarte como viejo al más nuevo de ellos.
+fn next_timestamp() -> TimestampType {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+    let mut timestamp = now;
+    // El closure siempre devuelve Some, por lo que fetch_update no falla
+    let _ = LAST_TIMESTAMP.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+        timestamp = now.max(last + 1);
+        Some(timestamp)
+    });
+    timestamp as TimestampType
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct PublishMessage {
     fixed_header: FixedHeader,
@@ -93,11 +124,34 @@ impl<'a> PublishMessage {
     //     (rem_len_in_two_bytes + topic_name_length + packet_identifier_length + payload_length) as u8
     // }
 
+    /// Crea el publish con el payload `content`, sin comprimir.
     pub fn new(
         flags: PublishFlags,
         topic_name: &'a str,
         packet_identifier: Option<u16>,
         content: &'a [u8],
+    ) -> Result<Self, Error> {
+        Self::build(flags, topic_name, packet_identifier, content, false)
+    }
+
+    /// Crea el publish con el payload `compressed_content`, ya comprimido con `compress_payload`:
+    /// quien lo reciba lo descomprime al leerlo (ver `get_payload`).
+    pub fn new_compressed(
+        flags: PublishFlags,
+        topic_name: &'a str,
+        packet_identifier: Option<u16>,
+        compressed_content: &'a [u8],
+    ) -> Result<Self, Error> {
+        Self::build(flags, topic_name, packet_identifier, compressed_content, true)
+    }
+
+    /// Crea el publish. El payload lleva primero el byte que indica si `content` está comprimido.
+    fn build(
+        flags: PublishFlags,
+        topic_name: &'a str,
+        packet_identifier: Option<u16>,
+        content: &'a [u8],
+        compressed: bool,
     ) -> Result<Self, Error> {
         if !flags.is_qos_greater_than_0() && packet_identifier.is_some() {
             return Err(Error::new(
@@ -105,13 +159,21 @@ impl<'a> PublishMessage {
                 "El packet_identifier debe ser None si qos = 0".to_string(),
             ));
         }
+        validate_topic_name(topic_name)?;
+        if topic_name.len() > MAX_TOPIC_NAME_LENGTH {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "El topic del publish es demasiado largo".to_string(),
+            ));
+        }
 
         let variable_header = VariableHeader {
             topic_name: topic_name.to_string(),
             packet_identifier,
+            topic_alias: None,
         };
 
-        let content = encrypt_3des(content);
+        let content = encrypt_3des(&add_compression_header(content, compressed));
 
         let payload = Payload {
             content: content.to_vec(),
@@ -122,10 +184,7 @@ impl<'a> PublishMessage {
             remaining_length: 0, // se actualizará más adelante
         };
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_nanos();
+        let timestamp = next_timestamp();
 
         let mut publish_message = PublishMessage {
             fixed_header,
@@ -140,7 +199,7 @@ impl<'a> PublishMessage {
         Ok(publish_message)
     }
 
-    fn calculate_remaining_length_2(&self) -> u8 {
+    fn calculate_remaining_length_2(&self) -> usize {
         //aux: remaining length = variable header + payload
         //aux: variable header = topic_name + packet_identifier
         let rem_len_in_two_bytes = 2;
@@ -149,14 +208,16 @@ impl<'a> PublishMessage {
             Some(_) => 2, //si qos > 0
             None => 0,    //si qos = 0
         };
+        let topic_alias_length = 2 * self.variable_header.topic_alias.is_some() as usize;
         let payload_length = self.payload.content.len();
         let timestamp_length = TIMESTAMP_LENGHT; // tamaño de u128
 
-        (rem_len_in_two_bytes
+        rem_len_in_two_bytes
             + topic_name_length
+            + topic_alias_length
             + packet_identifier_length
             + payload_length
-            + timestamp_length) as u8
+            + timestamp_length
     }
 
     pub fn get_packet_id(&self) -> Option<u16> {
@@ -211,19 +272,20 @@ impl<'a> PublishMessage {
         let first_byte = self.fixed_header.flags.to_flags_byte();
         bytes.push(first_byte);
 
-        let topic_name_length = self.variable_header.topic_name.len() as u8;
-        let remaining_length = 2
-            + topic_name_length
-            + 2 * self.variable_header.packet_identifier.is_some() as u8
-            + self.payload.content.len() as u8
-            + TIMESTAMP_LENGHT as u8; // tamaño del timestamp
-        bytes.push(remaining_length);
-
-        let topic_name_length_msb = ((topic_name_length as u16 >> 8) & 0xFF) as u8;
-        let topic_name_length_lsb = topic_name_length;
-        bytes.push(topic_name_length_msb);
-        bytes.push(topic_name_length_lsb);
+        let topic_name_length = self.variable_header.topic_name.len();
+        // La remaining length se codifica en longitud variable, para permitir payloads grandes.
+        bytes.extend(encode_remaining_length(self.calculate_remaining_length_2()));
+
+        // Si se envía el alias, se lo indica en el bit más significativo de la longitud del topic.
+        let mut topic_name_length_field = topic_name_length as u16;
+        if self.variable_header.topic_alias.is_some() {
+            topic_name_length_field |= TOPIC_ALIAS_FLAG;
+        }
+        bytes.extend(topic_name_length_field.to_be_bytes());
         bytes.extend_from_slice(self.variable_header.topic_name.as_bytes());
+        if let Some(topic_alias) = self.variable_header.topic_alias {
+            bytes.extend(topic_alias.to_be_bytes());
+        }
         if let Some(packet_identifier) = self.variable_header.packet_identifier {
             bytes.push((packet_identifier >> 8) as u8);
             bytes.push(packet_identifier as u8);
@@ -237,44 +299,68 @@ impl<'a> PublishMessage {
         bytes
     }
 
+    /// Interpreta los bytes de un publish. Valida las longitudes antes de leer cada campo, por lo que un paquete
+    /// truncado o inconsistente devuelve error `InvalidData` (que se informa como `MqttError::MalformedPacket`)
+    /// en lugar de provocar un panic.
     pub fn from_bytes(bytes: Vec<u8>) -> Result<PublishMessage, std::io::Error> {
-        if bytes.len() < 13 {
-            // Mínimo 5 bytes + 8 bytes de timestamp
-            return Err(std::io::Error::new(
-                std::io::ErrorKind::InvalidData,
+        let first_byte = match bytes.first() {
+            Some(byte) => *byte,
+            None => return Err(malformed_publish("No hay bytes para un mensaje válido")),
+        };
+        let flags = PublishFlags::from_flags_byte(first_byte)?;
+        let (remaining_length, rem_len_bytes) = decode_remaining_length(&bytes[1..])?;
+        // Posición del variable header, luego del byte de tipo y de los bytes de la remaining length
+        let vh = 1 + rem_len_bytes;
+        // Fin del paquete según la remaining length; el timestamp ocupa sus últimos bytes
+        let packet_end = vh + remaining_length;
+        if bytes.len() < packet_end || remaining_length < 2 + TIMESTAMP_LENGHT {
+            return Err(malformed_publish(
                 "No hay suficientes bytes para un mensaje válido",
             ));
         }
-
-        let first_byte = bytes[0];
-        let flags = PublishFlags::from_flags_byte(first_byte)?;
-        let remaining_length = bytes[1];
-
-        let topic_name_length = ((bytes[2] as usize) << 8) | (bytes[3] as usize);
-        let topic_name = match String::from_utf8(bytes[4..4 + topic_name_length].to_vec()) {
-            Ok(v) => v,
-            Err(_) => {
-                return Err(std::io::Error::new(
-                    std::io::ErrorKind::InvalidData,
-                    "El nombre del tema no es válido UTF-8",
-                ))
-            }
-        };
+        let payload_end = packet_end - TIMESTAMP_LENGHT;
+
+        let topic_name_length_field = u16::from_be_bytes([bytes[vh], bytes[vh + 1]]);
+        let has_topic_alias = topic_name_length_field & TOPIC_ALIAS_FLAG != 0;
+        let topic_name_length = (topic_name_length_field & !TOPIC_ALIAS_FLAG) as usize;
+        let topic_name_end = vh + 2 + topic_name_length;
+        // Si se envía el alias, ocupa los dos bytes siguientes al topic
+        let topic_end = topic_name_end + 2 * has_topic_alias as usize;
+        if topic_end > payload_end {
+            return Err(malformed_publish(
+                "No hay suficientes bytes para el nombre del tema",
+            ));
+        }
+        let topic_name = topic_from_utf8(&bytes[vh + 2..topic_name_end])?;
+        let mut topic_alias = None;
+        if has_topic_alias {
+            topic_alias = Some(u16::from_be_bytes([
+                bytes[topic_name_end],
+                bytes[topic_name_end + 1],
+            ]));
+        }
+        // Con alias, el topic puede omitirse: es el registrado previamente para el alias.
+        if topic_alias.is_none() || !topic_name.is_empty() {
+            validate_topic_name(&topic_name)?;
+        }
 
         let mut packet_identifier = None;
-        if remaining_length > (topic_name_length + 2) as u8 {
-            packet_identifier = Some(
-                ((bytes[4 + topic_name_length] as u16) << 8)
-                    | (bytes[5 + topic_name_length] as u16),
-            );
+        if flags.is_qos_greater_than_0() {
+            if topic_end + 2 > payload_end {
+                return Err(malformed_publish(
+                    "No hay suficientes bytes para el packet identifier",
+                ));
+            }
+            packet_identifier = Some(((bytes[topic_end] as u16) << 8) | (bytes[topic_end + 1] as u16));
         }
 
-        let payload_start = 4 + topic_name_length + 2 * packet_identifier.is_some() as usize;
-        let payload_end = bytes.len() - TIMESTAMP_LENGHT;
+        let payload_start = topic_end + 2 * packet_identifier.is_some() as usize;
         let payload_content = bytes[payload_start..payload_end].to_vec();
 
         // Cambiar el u128 en caso de que se cambie el tipo de dato del TIMESTAMP
-        let timestamp = u128::from_be_bytes(bytes[payload_end..].try_into().unwrap());
+        let mut timestamp_bytes = [0u8; TIMESTAMP_LENGHT];
+        timestamp_bytes.copy_from_slice(&bytes[payload_end..packet_end]);
+        let timestamp = u128::from_be_bytes(timestamp_bytes);
 
         Ok(Self {
             fixed_header: FixedHeader {
@@ -284,6 +370,7 @@ impl<'a> PublishMessage {
             variable_header: VariableHeader {
                 topic_name,
                 packet_identifier,
+                topic_alias,
             },
             payload: Payload {
                 content: payload_content,
@@ -347,24 +434,126 @@ impl<'a> PublishMessage {
         self.variable_header.topic_name.to_string()
     }
 
+    /// Devuelve el payload, desencriptado, y descomprimido si se lo publicó comprimido
+    /// (ver `MQTTClient::set_payload_compression_threshold`). Si no se lo puede leer (ej. descomprimido
+    /// supera `MAX_DECOMPRESSED_PAYLOAD_SIZE`), se lo descarta y devuelve un payload vacío.
     pub fn get_payload(&self) -> Vec<u8> {
-        decrypt_3des(&self.payload.content)
+        match remove_compression_header(&decrypt_3des(&self.payload.content), MAX_DECOMPRESSED_PAYLOAD_SIZE) {
+            Ok(payload) => payload,
+            Err(e) => {
+                println!("ERROR: no se pudo leer el payload del publish a {}: {:?}", self.get_topic(), e);
+                vec![]
+            }
+        }
         //aux: self.payload.content.to_vec()
     }
 
+    /// Devuelve el nivel de qos del mensaje, leído de sus flags.
     pub fn get_qos(&self) -> u8 {
         self.fixed_header.flags.get_qos()
     }
 
+    /// Devuelve si el mensaje tiene el flag dup seteado, es decir si se trata de un reenvío.
+    pub fn is_dup(&self) -> bool {
+        self.fixed_header.flags.get_dup() == 1
+    }
+
+    /// Devuelve si el mensaje tiene el flag retain seteado, es decir si el server debe conservarlo para el topic.
+    pub fn is_retain(&self) -> bool {
+        self.fixed_header.flags.get_retain() == 1
+    }
+
+    /// Devuelve el mensaje a entregar a un suscriptor al que se le otorgó `max_qos`: si el qos del mensaje
+    /// es mayor, una copia con el qos reducido (y sin packet identifier, si pasa a ser 0); si no, una copia idéntica.
+    pub fn downgraded_to(&self, max_qos: u8) -> Result<PublishMessage, Error> {
+        if self.get_qos() <= max_qos {
+            return Ok(self.clone());
+        }
+        let dup = if max_qos == 0 { 0 } else { self.fixed_header.flags.get_dup() };
+        let flags = PublishFlags::new(dup, max_qos, self.fixed_header.flags.get_retain())?;
+        let packet_identifier = if max_qos == 0 {
+            None
+        } else {
+            self.variable_header.packet_identifier
+        };
+
+        let mut downgraded = self.clone();
+        downgraded.fixed_header.flags = flags;
+        downgraded.variable_header.packet_identifier = packet_identifier;
+        downgraded.fixed_header.remaining_length = downgraded.calculate_remaining_length_2();
+        Ok(downgraded)
+    }
+
+    /// Devuelve el mensaje a entregar a un suscriptor: con el menor qos entre el del mensaje y `granted_qos`
+    /// y, si el mismo es mayor a 0, con el `packet_id` generado por el server para esa entrega.
+    /// Es una entrega nueva, por lo que no lleva el flag dup aunque el mensaje recibido fuera un reenvío.
+    pub fn to_deliver_with(&self, granted_qos: u8, packet_id: u16) -> Result<PublishMessage, Error> {
+        let mut to_deliver = self.downgraded_to(granted_qos)?.with_dup_flag(0)?;
+        if to_deliver.get_qos() > 0 {
+            to_deliver.variable_header.packet_identifier = Some(packet_id);
+        }
+        Ok(to_deliver)
+    }
+
+    /// Devuelve el mensaje con el flag dup seteado, para reenviarlo con el mismo packet id (ej. al retransmitirlo,
+    /// o al volver a publicarlo tras reconectarse), de modo que el server pueda descartarlo si ya lo procesó.
+    /// Un publish de qos 0 no lleva el flag dup, por lo que se lo devuelve sin cambios.
+    pub fn as_dup(&self) -> Result<PublishMessage, Error> {
+        if self.get_qos() == 0 {
+            return Ok(self.clone());
+        }
+        self.with_dup_flag(1)
+    }
+
+    fn with_dup_flag(&self, dup: u8) -> Result<PublishMessage, Error> {
+        let flags = PublishFlags::new(dup, self.get_qos(), self.fixed_header.flags.get_retain())?;
+        let mut msg = self.clone();
+        msg.fixed_header.flags = flags;
+        Ok(msg)
+    }
+
+    /// Devuelve el alias del topic, si se lo envía (ver `topic_alias`).
+    pub fn get_topic_alias(&self) -> Option<u16> {
+        self.variable_header.topic_alias
+    }
+
+    /// Devuelve una copia del publish que envía el alias `topic_alias`. Si `send_topic`, envía además el topic,
+    /// para que quien lo reciba registre el alias; si no, lo omite, y quien lo reciba debe tenerlo registrado.
+    pub fn with_topic_alias(&self, topic_alias: u16, send_topic: bool) -> PublishMessage {
+        let mut aliased = self.clone();
+        aliased.variable_header.topic_alias = Some(topic_alias);
+        if !send_topic {
+            aliased.variable_header.topic_name = String::new();
+        }
+        aliased.fixed_header.remaining_length = aliased.calculate_remaining_length_2();
+        aliased
+    }
+
+    /// Devuelve una copia del publish con el topic `topic_name` completo, y sin alias.
+    pub fn with_resolved_topic(&self, topic_name: &str) -> PublishMessage {
+        let mut resolved = self.clone();
+        resolved.variable_header.topic_name = topic_name.to_string();
+        resolved.variable_header.topic_alias = None;
+        resolved.fixed_header.remaining_length = resolved.calculate_remaining_length_2();
+        resolved
+    }
+
     pub fn get_topic_name(&self) -> String {
         self.variable_header.topic_name.to_string()
     }
 
+    /// Devuelve el timestamp asignado al crear el mensaje (ver `next_timestamp`). Viaja al final del paquete, y se
+    /// conserva al reenviarlo a los suscriptores, por lo que identifica el momento en que el emisor lo publicó.
     pub fn get_timestamp(&self) -> TimestampType {
         self.timestamp
     }
 }
 
+/// Error de un publish mal formado.
+fn malformed_publish(description: &str) -> Error {
+    Error::new(ErrorKind::InvalidData, description)
+}
+
 use super::packet_type::PacketType;
 use crate::mqtt::messages::message::Message;
 //Trait Message
@@ -437,6 +626,25 @@ mod tests {
         assert_eq!(publish_message.timestamp, deserialized_message.timestamp);
     }
 
+    #[test]
+    fn test_getters_de_qos_dup_y_retain_leen_los_flags() {
+        for (dup, qos, retain) in [(0, 0, 0), (0, 1, 1), (1, 1, 0), (1, 2, 1), (0, 2, 0)] {
+            let flags = PublishFlags::new(dup, qos, retain).unwrap();
+            let packet_id = if qos > 0 { Some(7) } else { None };
+            let msg = PublishMessage::new(flags, "test/topic", packet_id, b"payload").unwrap();
+
+            assert_eq!(msg.get_qos(), qos);
+            assert_eq!(msg.is_dup(), dup == 1);
+            assert_eq!(msg.is_retain(), retain == 1);
+
+            // Se conservan al pasar a bytes y reconstruir el mensaje
+            let deserialized = PublishMessage::from_bytes(msg.to_bytes()).unwrap();
+            assert_eq!(deserialized.get_qos(), qos);
+            assert_eq!(deserialized.is_dup(), dup == 1);
+            assert_eq!(deserialized.is_retain(), retain == 1);
+        }
+    }
+
     #[test]
     fn test_bytes_and_comparison() {
         let publish_message = create_test_publish_message().unwrap();
@@ -451,6 +659,23 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_payload_grande_usa_remaining_length_de_longitud_variable() {
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        let content = vec![7u8; 50_000];
+        let publish_message = PublishMessage::new(flags, "cam_frame", Some(3), &content).unwrap();
+        let bytes = publish_message.to_bytes();
+
+        // 50 mil bytes requieren tres bytes de remaining length
+        assert_eq!(bytes[1] & 0x80, 0x80);
+        assert_eq!(bytes[2] & 0x80, 0x80);
+        assert_eq!(bytes[3] & 0x80, 0);
+
+        let deserialized_message = PublishMessage::from_bytes(bytes).unwrap();
+        assert_eq!(deserialized_message, publish_message);
+        assert_eq!(deserialized_message.get_payload(), content);
+    }
+
     #[test]
     fn test_timestamp_comparison() {
         let msg1 = create_test_publish_message().unwrap();
@@ -492,6 +717,68 @@ mod tests {
     //     assert_eq!(recovered_message, original_message);
     // }
 
+    #[test]
+    fn test_topics_no_validos_se_rechazan_al_crear_y_al_interpretar_bytes() {
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        for topic in ["", "inc\u{0}", "dron/+", "dron/#"] {
+            assert!(PublishMessage::new(flags.clone(), topic, Some(1), b"payload").is_err());
+        }
+
+        // Un topic que no es UTF-8 válido se rechaza al interpretar los bytes
+        let mut bytes = PublishMessage::new(flags, "inc", Some(1), b"payload")
+            .unwrap()
+            .to_bytes();
+        bytes[4] = 0xff;
+        assert!(PublishMessage::from_bytes(bytes).is_err());
+    }
+
+    #[test]
+    fn test_downgraded_to_reduce_el_qos_sin_modificar_el_contenido() {
+        let flags = PublishFlags::new(0, 2, 1).unwrap();
+        let msg = PublishMessage::new(flags, "inc", Some(5), b"payload").unwrap();
+
+        let qos_1 = msg.downgraded_to(1).unwrap();
+        assert_eq!(qos_1.get_qos(), 1);
+        assert_eq!(qos_1.get_packet_id(), Some(5));
+        assert!(qos_1.is_retain());
+        let qos_0 = msg.downgraded_to(0).unwrap();
+        assert_eq!(qos_0.get_qos(), 0);
+        assert_eq!(qos_0.get_packet_id(), None);
+        assert_eq!(msg.downgraded_to(2).unwrap(), msg);
+
+        let deserialized = PublishMessage::from_bytes(qos_0.to_bytes()).unwrap();
+        assert_eq!(deserialized.get_payload(), b"payload".to_vec());
+        assert_eq!(deserialized.get_qos(), 0);
+    }
+
+    #[test]
+    fn test_from_bytes_de_un_publish_truncado_devuelve_error() {
+        let bytes = create_test_publish_message().unwrap().to_bytes();
+        for len in 0..bytes.len() {
+            assert!(PublishMessage::from_bytes(bytes[..len].to_vec()).is_err());
+        }
+        assert!(PublishMessage::from_bytes(bytes).is_ok());
+    }
+
+    #[test]
+    fn test_from_bytes_con_bytes_alterados_al_azar_no_hace_panic() {
+        use rand::{rngs::StdRng, Rng, SeedableRng};
+
+        let bytes = create_test_publish_message().unwrap().to_bytes();
+        let mut rng = StdRng::seed_from_u64(1840);
+        for _ in 0..2000 {
+            // Se trunca en una posición al azar, y se alteran algunos bytes (incluidos los de longitudes)
+            let mut altered = bytes[..rng.gen_range(0..=bytes.len())].to_vec();
+            for _ in 0..rng.gen_range(1..4) {
+                if !altered.is_empty() {
+                    let i = rng.gen_range(0..altered.len());
+                    altered[i] = rng.gen();
+                }
+            }
+            let _ = PublishMessage::from_bytes(altered);
+        }
+    }
+
     #[test]
     /// Testeo de la funcion encriptar
     fn test_encrypt() {
@@ -510,4 +797,20 @@ mod tests {
 
         assert_eq!(content.to_vec(), decrypted_content);
     }
+
+    #[test]
+    fn test_un_payload_comprimido_se_lee_descomprimido_del_otro_lado() {
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        let content = b"cam_frame ".repeat(200);
+        let compressed = crate::mqtt::mqtt_utils::payload_compression::compress_payload(&content)
+            .unwrap()
+            .unwrap();
+        let msg = PublishMessage::new_compressed(flags, "cam_frame", Some(1), &compressed).unwrap();
+
+        let received = PublishMessage::from_bytes(msg.to_bytes()).unwrap();
+        assert_eq!(received.get_payload(), content);
+        // Sin comprimir, se lee tal cual aunque comience como un stream gzip
+        let msg = PublishMessage::new(PublishFlags::new(0, 1, 0).unwrap(), "cam_frame", Some(2), &[0x1f, 0x8b, 1]).unwrap();
+        assert_eq!(msg.get_payload(), vec![0x1f, 0x8b, 1]);
+    }
 }
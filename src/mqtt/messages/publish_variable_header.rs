@@ -6,4 +6,8 @@ pub struct VariableHeader {
     pub topic_name: String, // Cambiado de &'a str a String //bytes 1-5,(ejemplo topic_name: "a/b")
 
     pub packet_identifier: Option<u16>, // bytes 6-7, solo si qos > 0 ,1 o 2
+
+    // Extensión propia (no es de MQTT 3.1.1), solo si se negoció al conectarse: alias del topic,
+    // que se envía luego del topic_name. Si el topic_name es vacío, el topic es el registrado para el alias.
+    pub topic_alias: Option<u16>,
 }
@@ -1,8 +1,9 @@
 use std::{
     io::{Error, ErrorKind},
     mem::size_of,
-    str::from_utf8,
 };
+
+use crate::mqtt::mqtt_utils::topic_validation::{topic_from_utf8, validate_topic_filter};
 /* [] Siendo que el variable header igualmente es diferente para cada tipo de mensaje,
  * no veo ganancia en crear un subscribe_variable_header.rs, xq no se va a poder poner comportamiento ahí
  * (en este caso incluso sería medio trivial, mandar un u16 y listo).
@@ -22,13 +23,17 @@ pub struct SubscribeMessage {
 }
 
 impl SubscribeMessage {
-    pub fn new(packet_id: u16, topics: Vec<(String, u8)>) -> Self {
-        SubscribeMessage {
+    /// Crea el mensaje, o devuelve error si alguno de los topic filters no es válido.
+    pub fn new(packet_id: u16, topics: Vec<(String, u8)>) -> Result<Self, Error> {
+        for (filter, _qos) in &topics {
+            validate_topic_filter(filter)?;
+        }
+        Ok(SubscribeMessage {
             message_type: 8,
             reserved_flags: 2,
             packet_identifier: packet_id,
             topic_filters: topics // Convertimos cada tema en una tupla con QoS 1
-        }
+        })
     }
 
     fn remaining_length(&self) -> u8 {
@@ -102,15 +107,15 @@ impl SubscribeMessage {
             let elem_string_len = u16::from_be_bytes([msg_bytes[idx], msg_bytes[idx + size_of_u8]]); // forma 2
             idx += size_of_u16;
             // Leo la string, de tam "elem_string_len"
-            let string_leida =
-                from_utf8(&msg_bytes[idx..idx + (elem_string_len as usize)]).unwrap();
+            let string_leida = topic_from_utf8(&msg_bytes[idx..idx + (elem_string_len as usize)])?;
+            validate_topic_filter(&string_leida)?;
             idx += elem_string_len as usize;
             // Leo el u8
             let elem_qos = (&msg_bytes[idx..idx + size_of_u8])[0];
             idx += size_of_u8;
 
             // Terminé de leer, agrego el elemento leído al vector de topics
-            let elemento = (String::from(string_leida), elem_qos);
+            let elemento = (string_leida, elem_qos);
             topics.push(elemento);
             // Avanzo la rem_len_leida para saber cuándo termino de leer todos los elementos
             rem_len_leida += 2 + elem_string_len as u8 + 1;
@@ -168,7 +173,7 @@ mod test {
     fn test_1_subscribe_msg_se_crea_con_tipo_y_flag_adecuados() {
         let packet_id: u16 = 1;
         let topics_to_subscribe: Vec<(String, u8)> = vec![(String::from("topic1"), 1)];
-        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe);
+        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe).unwrap();
 
         // Estos valores siempre son 8 y 2 respectivamente, para este tipo de mensaje
         assert_eq!(subscribe_msg.message_type, 8);
@@ -179,7 +184,7 @@ mod test {
     fn test_2_subscribe_msg_se_pasa_a_bytes_y_se_interpreta_correctamente() {
         let packet_id: u16 = 1;
         let topics_to_subscribe: Vec<(String, u8)> = vec![(String::from("topic1"), 1)];
-        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe);
+        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe).unwrap();
 
         let bytes_msg = subscribe_msg.to_bytes();
 
@@ -193,11 +198,30 @@ mod test {
         let mut topics_to_subscribe: Vec<(String, u8)> = vec![(String::from("topic1"), 1)];
         topics_to_subscribe.push((String::from("topic2"), 1));// agrego más topics al vector
         topics_to_subscribe.push((String::from("topic3"), 1));
-        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe);
+        let subscribe_msg = SubscribeMessage::new(packet_id, topics_to_subscribe).unwrap();
 
         let bytes_msg = subscribe_msg.to_bytes();
 
         let msg_reconstruido = SubscribeMessage::from_bytes(bytes_msg);
         assert_eq!(msg_reconstruido.unwrap(), subscribe_msg);
     }
+
+    #[test]
+    fn test_4_subscribe_msg_rechaza_topic_filters_no_validos_pero_acepta_comodines() {
+        for filter in ["", "inc\u{0}"] {
+            assert!(SubscribeMessage::new(1, vec![(String::from(filter), 1)]).is_err());
+        }
+        let subscribe_msg =
+            SubscribeMessage::new(1, vec![(String::from("dron/+"), 1), (String::from("#"), 1)])
+                .unwrap();
+        let msg_reconstruido = SubscribeMessage::from_bytes(subscribe_msg.to_bytes());
+        assert_eq!(msg_reconstruido.unwrap(), subscribe_msg);
+
+        // Un topic filter que no es UTF-8 válido se rechaza al interpretar los bytes
+        let mut bytes_msg = SubscribeMessage::new(1, vec![(String::from("inc"), 1)])
+            .unwrap()
+            .to_bytes();
+        bytes_msg[6] = 0xff;
+        assert!(SubscribeMessage::from_bytes(bytes_msg).is_err());
+    }
 }
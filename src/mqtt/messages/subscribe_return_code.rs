@@ -9,6 +9,25 @@ pub enum SubscribeReturnCode {
     Failure = 0x80,
 }
 impl SubscribeReturnCode {
+    /// Devuelve el código que indica que se otorgó la suscripción con el nivel de qos `granted_qos`.
+    /// Un qos mayor a 2 no es válido, por lo que se lo considera como una suscripción fallida.
+    pub fn from_granted_qos(granted_qos: u8) -> SubscribeReturnCode {
+        match granted_qos {
+            0 => SubscribeReturnCode::QoS0,
+            1 => SubscribeReturnCode::QoS1,
+            2 => SubscribeReturnCode::QoS2,
+            _ => SubscribeReturnCode::Failure,
+        }
+    }
+
+    /// Devuelve el nivel de qos otorgado, o None si la suscripción falló.
+    pub fn get_granted_qos(&self) -> Option<u8> {
+        match self {
+            SubscribeReturnCode::Failure => None,
+            granted => Some(*granted as u8),
+        }
+    }
+
     /// Recibe un número u16 y 'lo convierte' a (devuelve) la variante del enum correspondiente.
     /// Utillizado al leer el `ret_code` desde bytes.
     pub fn from_bytes(ret_code: u16) -> Result<SubscribeReturnCode, Error> {
@@ -24,3 +43,19 @@ impl SubscribeReturnCode {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::SubscribeReturnCode;
+
+    #[test]
+    fn test_1_el_codigo_refleja_el_qos_otorgado() {
+        for qos in 0..=2 {
+            let code = SubscribeReturnCode::from_granted_qos(qos);
+            assert_eq!(code.get_granted_qos(), Some(qos));
+            assert_eq!(SubscribeReturnCode::from_bytes(code as u16).unwrap(), code);
+        }
+        assert_eq!(SubscribeReturnCode::from_granted_qos(3), SubscribeReturnCode::Failure);
+        assert_eq!(SubscribeReturnCode::Failure.get_granted_qos(), None);
+    }
+}
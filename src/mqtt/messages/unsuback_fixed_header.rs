@@ -1,3 +1,4 @@
+#[derive(Debug)]
 pub struct FixedHeader {
     //Message Type para UNSUBACK = 11
     pub message_type: u8, //1er byte : 4bits
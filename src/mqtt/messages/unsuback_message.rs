@@ -2,6 +2,7 @@ use crate::mqtt::messages::{
     unsuback_fixed_header::FixedHeader, unsuback_variable_header::VariableHeader,
 };
 
+#[derive(Debug)]
 pub struct Unsuback {
     fixed_header: FixedHeader,
     variable_header: VariableHeader,
@@ -36,6 +37,14 @@ impl Unsuback {
         ]
     }
 
+    /// Devuelve el packet id del UnsubscribeMessage al que responde.
+    pub fn get_packet_id(&self) -> u16 {
+        u16::from_be_bytes([
+            self.variable_header.packet_type_identifier_msb,
+            self.variable_header.packet_type_identifier_lsb,
+        ])
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Unsuback {
         let fixed_header = FixedHeader {
             message_type: bytes[0] >> 4,
@@ -1,3 +1,4 @@
+#[derive(Debug)]
 pub struct VariableHeader {
     pub packet_type_identifier_msb: u8, //1er byte
     pub packet_type_identifier_lsb: u8, //2do byte
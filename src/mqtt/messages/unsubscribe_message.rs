@@ -1,6 +1,6 @@
 use crate::mqtt::messages::{
-    unsubscribe_fixed_header::FixedHeader, unsubscribe_payload::Payload,
-    unsubscribe_variable_header::VariableHeader,
+    message::Message, packet_type::PacketType, unsubscribe_fixed_header::FixedHeader,
+    unsubscribe_payload::Payload, unsubscribe_variable_header::VariableHeader,
 };
 
 // UNSUBSCRIBE MESSAGE
@@ -48,14 +48,13 @@ impl UnsubscribeMessage {
         packet_identifier_length + topics_length
     }
 
-    pub fn to_bytes(&mut self) -> Vec<u8> {
+    pub fn to_bytes(&self) -> Vec<u8> {
         let mut bytes = Vec::new();
 
         // Fixed Header
         let combined = (self.fixed_header.message_type << 4) | self.fixed_header.reserved;
         bytes.push(combined);
-        self.fixed_header.remaining_length = self.calculate_remaining_length();
-        bytes.push(self.fixed_header.remaining_length as u8);
+        bytes.push(self.calculate_remaining_length() as u8);
 
         // Variable Header
         bytes.push((self.variable_header.packet_identifier >> 8) as u8); // MSB
@@ -92,8 +91,15 @@ impl UnsubscribeMessage {
         let mut index = 4;
         while index < bytes.len() {
             let topic_length = bytes[index] as usize;
-            let topic =
-                String::from_utf8(bytes[index + 1..index + 1 + topic_length].to_vec()).unwrap();
+            let topic_bytes = bytes.get(index + 1..index + 1 + topic_length).ok_or_else(|| {
+                std::io::Error::new(
+                    std::io::ErrorKind::InvalidData,
+                    "El largo de un topic excede al del mensaje",
+                )
+            })?;
+            let topic = String::from_utf8(topic_bytes.to_vec()).map_err(|_| {
+                std::io::Error::new(std::io::ErrorKind::InvalidData, "Topic no es utf-8 válido")
+            })?;
             topics.push(topic);
             index += 1 + topic_length;
         }
@@ -108,6 +114,33 @@ impl UnsubscribeMessage {
             payload: Payload { topics },
         })
     }
+
+    pub fn get_packet_id(&self) -> u16 {
+        self.variable_header.packet_identifier
+    }
+
+    /// Devuelve los topics de los que el cliente desea desuscribirse.
+    pub fn get_topics(&self) -> &Vec<String> {
+        &self.payload.topics
+    }
+}
+
+impl Message for UnsubscribeMessage {
+    fn get_packet_id(&self) -> Option<u16> {
+        Some(self.get_packet_id())
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        self.to_bytes()
+    }
+
+    fn get_type(&self) -> PacketType {
+        PacketType::Unsubscribe
+    }
+
+    fn as_any(&self) -> &dyn std::any::Any {
+        self
+    }
 }
 
 #[cfg(test)]
@@ -128,7 +161,7 @@ mod test {
     fn test_unsubscribe_message_to_bytes() {
         let packet_identifier = 10;
         let topics = vec!["topic1".to_string(), "topic2".to_string()];
-        let mut unsubscribe_message = UnsubscribeMessage::new(packet_identifier, topics);
+        let unsubscribe_message = UnsubscribeMessage::new(packet_identifier, topics);
         let bytes = unsubscribe_message.to_bytes();
         let expected_bytes = vec![
             0b1010_0010, // Fixed Header 10 y 2 de reserved
@@ -193,7 +226,7 @@ mod test {
     fn test_unsubscribe_message_to_bytes_and_back() {
         let packet_identifier = 12;
         let topics = vec!["topic1".to_string(), "topic2".to_string()];
-        let mut unsubscribe_message = UnsubscribeMessage::new(packet_identifier, topics);
+        let unsubscribe_message = UnsubscribeMessage::new(packet_identifier, topics);
 
         let bytes = unsubscribe_message.to_bytes();
 
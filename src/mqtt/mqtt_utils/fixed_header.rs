@@ -1,13 +1,24 @@
+use std::io::{Error, ErrorKind};
+
 use crate::mqtt::messages::packet_type::PacketType;
 
-/// Struct que contiene los primeros dos bytes de cualquier tipo de mensaje del protocolo MQTT.
+/// Bit que indica, en cada byte de la remaining length codificada, si le sigue otro byte.
+pub const CONTINUATION_BIT: u8 = 0b1000_0000;
+/// Cantidad máxima de bytes que puede ocupar la remaining length codificada.
+pub const MAX_REMAINING_LENGTH_BYTES: usize = 4;
+/// Máxima remaining length representable (con 4 bytes).
+pub const MAX_REMAINING_LENGTH: usize = 268_435_455;
+
+/// Struct que contiene los primeros bytes de cualquier tipo de mensaje del protocolo MQTT.
 /// El byte 1 contiene el tipo de mensaje en sus 4 bits más significativos,
 /// y ceros o posiblemente flags (dependiendo del tipo de mensaje) en sus 4 bits menos significativos.
-/// El byte 2 contiene la `remaining_length` que es la longitud de la porción restante del mensaje.
+/// A continuación viene la `remaining_length` que es la longitud de la porción restante del mensaje.
+/// Para los Publish se codifica en longitud variable (de 1 a 4 bytes, como indica el protocolo),
+/// para poder enviar payloads grandes (ej. imágenes); para los demás tipos de mensaje ocupa siempre un byte.
 #[derive(Debug, PartialEq, Copy, Clone)]
 pub struct FixedHeader {
     message_type_byte: u8, // byte 1, el tipo está en los 4 MSBits.
-    remaining_length: u8,  // byte 2
+    remaining_length: usize,
 }
 
 impl FixedHeader {
@@ -16,12 +27,27 @@ impl FixedHeader {
     }
 
     pub fn to_bytes(&self) -> Vec<u8> {
-        vec![self.message_type_byte, self.remaining_length]
+        let mut bytes = vec![self.message_type_byte];
+        if uses_variable_remaining_length(self.message_type_byte) {
+            bytes.extend(encode_remaining_length(self.remaining_length));
+        } else {
+            bytes.push(self.remaining_length as u8);
+        }
+        bytes
     }
 
+    /// Recibe los bytes del fixed header: el tipo, y todos los bytes de la remaining length.
     pub fn from_bytes(msg_bytes: Vec<u8>) -> Self {
         let tipo = u8::from_be_bytes([msg_bytes[0]]);
-        let rem_len = u8::from_be_bytes([msg_bytes[1]]);
+        let rem_len = if uses_variable_remaining_length(tipo) {
+            match decode_remaining_length(&msg_bytes[1..]) {
+                Ok((rem_len, _)) => rem_len,
+                // Todavía no se leyeron todos los bytes de la remaining length
+                Err(_) => (msg_bytes[1] & !CONTINUATION_BIT) as usize,
+            }
+        } else {
+            u8::from_be_bytes([msg_bytes[1]]) as usize
+        };
 
         Self {
             message_type_byte: tipo,
@@ -29,6 +55,12 @@ impl FixedHeader {
         }
     }
 
+    /// Devuelve si, habiendo leído `header_bytes`, faltan leer bytes de la remaining length.
+    pub fn has_more_remaining_length_bytes(&self, header_bytes: &[u8]) -> bool {
+        uses_variable_remaining_length(self.message_type_byte)
+            && decode_remaining_length(&header_bytes[1..]).is_err()
+    }
+
     pub fn get_message_type_byte(&self) -> u8 {
         self.message_type_byte >> 4
     }
@@ -38,10 +70,79 @@ impl FixedHeader {
     }
 
     pub const fn get_rem_len(&self) -> usize {
-        self.remaining_length as usize
+        self.remaining_length
     }
 
     pub fn is_not_null(&self) -> bool {
         !((self.message_type_byte == 0) & (self.remaining_length == 0))
     }
 }
+
+/// Devuelve si el tipo de mensaje del `message_type_byte` codifica su remaining length en longitud variable.
+fn uses_variable_remaining_length(message_type_byte: u8) -> bool {
+    PacketType::from(message_type_byte >> 4) == PacketType::Publish
+}
+
+/// Codifica `len` en longitud variable: 7 bits por byte, el bit más significativo indica si sigue otro byte.
+pub fn encode_remaining_length(len: usize) -> Vec<u8> {
+    let mut bytes = vec![];
+    let mut remaining = len;
+    loop {
+        let mut byte = (remaining % 128) as u8;
+        remaining /= 128;
+        if remaining > 0 {
+            byte |= CONTINUATION_BIT;
+        }
+        bytes.push(byte);
+        if remaining == 0 {
+            return bytes;
+        }
+    }
+}
+
+/// Decodifica una remaining length en longitud variable, a partir del inicio de `bytes`.
+/// Devuelve la longitud y la cantidad de bytes que ocupaba, o error si está incompleta o es inválida.
+pub fn decode_remaining_length(bytes: &[u8]) -> Result<(usize, usize), Error> {
+    let mut len: usize = 0;
+    let mut multiplier: usize = 1;
+    for (i, byte) in bytes.iter().take(MAX_REMAINING_LENGTH_BYTES).enumerate() {
+        len += (byte & !CONTINUATION_BIT) as usize * multiplier;
+        if byte & CONTINUATION_BIT == 0 {
+            return Ok((len, i + 1));
+        }
+        multiplier *= 128;
+    }
+    Err(Error::new(
+        ErrorKind::InvalidData,
+        "Remaining length incompleta o inválida",
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{decode_remaining_length, encode_remaining_length, FixedHeader, MAX_REMAINING_LENGTH};
+
+    #[test]
+    fn test_1_remaining_length_en_longitud_variable_ida_y_vuelta() {
+        for len in [0, 127, 128, 16_383, 16_384, 2_097_152, MAX_REMAINING_LENGTH] {
+            let bytes = encode_remaining_length(len);
+            assert_eq!(decode_remaining_length(&bytes).unwrap(), (len, bytes.len()));
+        }
+        assert_eq!(encode_remaining_length(321), vec![0xC1, 0x02]);
+        // Incompleta: el último byte indica que sigue otro
+        assert!(decode_remaining_length(&[0x80]).is_err());
+    }
+
+    #[test]
+    fn test_2_solo_publish_usa_longitud_variable() {
+        // Publish (tipo 3) con remaining length 200 ocupa dos bytes
+        let publish_header = FixedHeader::from_bytes(vec![0x30, 0xC8, 0x01]);
+        assert_eq!(publish_header.get_rem_len(), 200);
+        assert_eq!(publish_header.to_bytes(), vec![0x30, 0xC8, 0x01]);
+
+        // Connect (tipo 1) con remaining length 200 sigue ocupando un byte
+        let connect_header = FixedHeader::from_bytes(vec![0x10, 200]);
+        assert_eq!(connect_header.get_rem_len(), 200);
+        assert_eq!(connect_header.to_bytes(), vec![0x10, 200]);
+    }
+}
@@ -1,4 +1,11 @@
 pub mod utils;
 pub mod broker_errors;
+pub mod mqtt_error;
 pub mod fixed_header;
+pub mod payload_compression;
+pub mod shutdown_token;
+pub mod socket_address;
+pub mod topic;
+pub mod topic_alias;
+pub mod topic_validation;
 pub mod will_message_utils;
\ No newline at end of file
@@ -0,0 +1,112 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::Display;
+use std::io::ErrorKind;
+
+use crate::mqtt::messages::packet_type::PacketType;
+
+/// Errores de la capa MQTT, para que quien los recibe pueda distinguir
+/// un error de red de uno de protocolo o de un ack que nunca llegó.
+#[derive(Debug)]
+pub enum MqttError {
+    /// Error de entrada/salida, ej. al leer o escribir en el stream.
+    Io(std::io::Error),
+    /// Se violó alguna regla del protocolo, ej. un nivel de qos no soportado.
+    Protocol(String),
+    /// Se agotaron los intentos esperando un ack.
+    Timeout(String),
+    /// Se recibió un paquete de un tipo que no se esperaba en ese momento.
+    UnexpectedPacket(PacketType),
+    /// No se pudo interpretar un paquete recibido o a enviar.
+    MalformedPacket(String),
+}
+
+impl MqttError {
+    /// Devuelve si tiene sentido reintentar la operación que devolvió este error
+    /// (ej. reconectarse), es decir si no se trata de un error de protocolo.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self, MqttError::Io(_) | MqttError::Timeout(_))
+    }
+
+    /// Devuelve si el error indica que no se recibió el ack dentro de la ventana de retransmisiones
+    /// (el mensaje pudo haber llegado o no al server).
+    pub fn is_ack_timeout(&self) -> bool {
+        matches!(self, MqttError::Timeout(_))
+    }
+}
+
+impl Error for MqttError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            MqttError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl Display for MqttError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MqttError::Io(e) => write!(f, "Error de entrada/salida: {}", e),
+            MqttError::Protocol(msg) => write!(f, "Error de protocolo: {}", msg),
+            MqttError::Timeout(msg) => write!(f, "Timeout: {}", msg),
+            MqttError::UnexpectedPacket(packet_type) => {
+                write!(f, "Paquete inesperado: {:?}", packet_type)
+            }
+            MqttError::MalformedPacket(msg) => write!(f, "Paquete mal formado: {}", msg),
+        }
+    }
+}
+
+impl From<std::io::Error> for MqttError {
+    fn from(e: std::io::Error) -> Self {
+        match e.kind() {
+            ErrorKind::TimedOut => MqttError::Timeout(e.to_string()),
+            _ => MqttError::Io(e),
+        }
+    }
+}
+
+/// Conversiones que no pueden fallar (ej. de un topic de las apps a un `Topic`), para aceptarlas junto con
+/// las que sí pueden fallar donde se recibe un `TryInto<Topic>`.
+impl From<Infallible> for MqttError {
+    fn from(never: Infallible) -> Self {
+        match never {}
+    }
+}
+
+/// Permite que el código que trabaja con `std::io::Error` (ej. las apps) siga usando `?`.
+impl From<MqttError> for std::io::Error {
+    fn from(e: MqttError) -> Self {
+        match e {
+            MqttError::Io(io_error) => io_error,
+            MqttError::Timeout(msg) => std::io::Error::new(ErrorKind::TimedOut, msg),
+            other => std::io::Error::new(ErrorKind::InvalidData, other.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::io::{Error, ErrorKind};
+
+    use super::MqttError;
+    use crate::mqtt::messages::packet_type::PacketType;
+
+    #[test]
+    fn test_1_conversion_desde_y_hacia_io_error() {
+        let io_error = Error::new(ErrorKind::ConnectionReset, "se cerró la conexión");
+        let mqtt_error = MqttError::from(io_error);
+        assert!(matches!(mqtt_error, MqttError::Io(_)));
+        assert!(mqtt_error.is_recoverable());
+
+        let timeout = MqttError::from(Error::new(ErrorKind::TimedOut, "no llegó el ack"));
+        assert!(matches!(timeout, MqttError::Timeout(_)));
+        assert!(timeout.is_ack_timeout());
+        assert_eq!(Error::from(timeout).kind(), ErrorKind::TimedOut);
+
+        let unexpected = MqttError::UnexpectedPacket(PacketType::Connect);
+        assert!(!unexpected.is_recoverable());
+        assert_eq!(Error::from(unexpected).kind(), ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,99 @@
+use std::io::{Error, Read, Write};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+/// Byte con que comienza el payload de todo publish, que indica si el resto está comprimido.
+const UNCOMPRESSED_HEADER: u8 = 0;
+const GZIP_HEADER: u8 = 1;
+
+/// Máximo tamaño de un payload descomprimido. Igual al máximo tamaño de paquete que acepta el server por
+/// defecto: un payload comprimido que se descomprima a más es rechazado (ej. una "zip bomb"), ya que
+/// descomprimirlo completo podría agotar la memoria de cada suscriptor.
+pub const MAX_DECOMPRESSED_PAYLOAD_SIZE: usize = 16 * 1024 * 1024;
+
+/// Comprime el `payload` con gzip. Devuelve None si comprimido no resulta más chico
+/// (ej. una imagen ya comprimida), en cuyo caso conviene enviarlo sin comprimir.
+pub fn compress_payload(payload: &[u8]) -> Result<Option<Vec<u8>>, Error> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(payload)?;
+    let compressed = encoder.finish()?;
+    if compressed.len() < payload.len() {
+        Ok(Some(compressed))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Devuelve el `content` precedido por el byte que indica si está comprimido (ver `compress_payload`).
+pub fn add_compression_header(content: &[u8], compressed: bool) -> Vec<u8> {
+    let header = if compressed { GZIP_HEADER } else { UNCOMPRESSED_HEADER };
+    let mut payload = Vec::with_capacity(content.len() + 1);
+    payload.push(header);
+    payload.extend_from_slice(content);
+    payload
+}
+
+/// Quita el byte de compresión del `payload`, y lo descomprime si corresponde, sin superar `max_len` bytes.
+/// Devuelve error si el byte no es válido, si no se lo puede descomprimir, o si descomprimido supera `max_len`.
+pub fn remove_compression_header(payload: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+    match payload.split_first() {
+        Some((&UNCOMPRESSED_HEADER, content)) => Ok(content.to_vec()),
+        Some((&GZIP_HEADER, content)) => decompress_payload(content, max_len),
+        Some((header, _)) => Err(Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("Byte de compresión de payload inválido: {}.", header),
+        )),
+        None => Err(Error::new(
+            std::io::ErrorKind::InvalidData,
+            "Payload sin byte de compresión.",
+        )),
+    }
+}
+
+/// Descomprime el payload gzip `compressed`, leyendo a lo sumo `max_len` bytes más uno para detectar si lo supera.
+fn decompress_payload(compressed: &[u8], max_len: usize) -> Result<Vec<u8>, Error> {
+    let mut decompressed = Vec::new();
+    GzDecoder::new(compressed)
+        .take(max_len as u64 + 1)
+        .read_to_end(&mut decompressed)?;
+    if decompressed.len() > max_len {
+        return Err(Error::new(
+            std::io::ErrorKind::InvalidData,
+            format!("El payload descomprimido supera el máximo de {} bytes.", max_len),
+        ));
+    }
+    Ok(decompressed)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{add_compression_header, compress_payload, remove_compression_header};
+
+    #[test]
+    fn test_1_un_payload_comprimido_se_recupera_y_uno_sin_comprimir_queda_igual() {
+        let payload = b"cam_frame ".repeat(200);
+
+        let compressed = compress_payload(&payload).unwrap().unwrap();
+        assert!(compressed.len() < payload.len());
+        let with_header = add_compression_header(&compressed, true);
+        assert_eq!(remove_compression_header(&with_header, 4096).unwrap(), payload);
+
+        // Sin comprimir se lee tal cual, aunque casualmente comience como un stream gzip
+        let raw = vec![0x1f, 0x8b, 1, 2];
+        assert_eq!(remove_compression_header(&add_compression_header(&raw, false), 4096).unwrap(), raw);
+        // Comprimir pocos bytes no los achica
+        assert_eq!(compress_payload(&[1, 2, 3]).unwrap(), None);
+        assert!(remove_compression_header(&[7, 1, 2], 4096).is_err());
+    }
+
+    #[test]
+    fn test_2_no_se_descomprime_un_payload_que_supera_el_maximo() {
+        // 1 MB de ceros se comprime a unos pocos KB
+        let payload = vec![0u8; 1024 * 1024];
+        let with_header = add_compression_header(&compress_payload(&payload).unwrap().unwrap(), true);
+        assert!(with_header.len() < 16 * 1024);
+
+        assert!(remove_compression_header(&with_header, 64 * 1024).is_err());
+        assert_eq!(remove_compression_header(&with_header, payload.len()).unwrap().len(), payload.len());
+    }
+}
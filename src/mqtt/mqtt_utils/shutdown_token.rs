@@ -0,0 +1,96 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::{Receiver, RecvTimeoutError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+/// Cada cuánto los hilos que esperan mensajes por un channel se fijan si se solicitó terminar.
+pub const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Señal de terminación compartida por todos los hilos de una app.
+/// Una vez solicitada (ej. al presionar "Salir" en la UI), cada hilo que la observa termina su loop,
+/// para que el `join` de todos ellos finalice.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownToken {
+    requested: Arc<AtomicBool>,
+}
+
+impl ShutdownToken {
+    pub fn new() -> Self {
+        Self {
+            requested: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Solicita la terminación a todos los hilos que comparten este token.
+    pub fn request(&self) {
+        self.requested.store(true, Ordering::SeqCst);
+    }
+
+    /// Devuelve si ya se solicitó la terminación.
+    pub fn is_requested(&self) -> bool {
+        self.requested.load(Ordering::SeqCst)
+    }
+
+    /// Espera el próximo elemento del `rx`, fijándose periódicamente si se solicitó la terminación.
+    /// Devuelve None si se solicitó terminar, o si el otro extremo del channel se cerró.
+    pub fn recv_unless_requested<T>(&self, rx: &Receiver<T>) -> Option<T> {
+        loop {
+            if self.is_requested() {
+                return None;
+            }
+            match rx.recv_timeout(SHUTDOWN_POLL_INTERVAL) {
+                Ok(elem) => return Some(elem),
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => return None,
+            }
+        }
+    }
+
+    /// Espera `duration`, fijándose periódicamente si se solicitó la terminación.
+    /// Devuelve si se solicitó terminar (en cuyo caso deja de esperar antes).
+    pub fn sleep_unless_requested(&self, duration: Duration) -> bool {
+        let start = Instant::now();
+        while !self.is_requested() {
+            let elapsed = start.elapsed();
+            if elapsed >= duration {
+                return false;
+            }
+            thread::sleep(SHUTDOWN_POLL_INTERVAL.min(duration - elapsed));
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{sync::mpsc, thread};
+
+    use super::ShutdownToken;
+
+    #[test]
+    fn test_1_el_hilo_que_espera_por_el_channel_termina_al_solicitar_shutdown() {
+        let token = ShutdownToken::new();
+        let (tx, rx) = mpsc::channel::<u8>();
+        tx.send(1).unwrap();
+
+        let token_c = token.clone();
+        let handle = thread::spawn(move || {
+            let mut received = vec![];
+            while let Some(elem) = token_c.recv_unless_requested(&rx) {
+                received.push(elem);
+            }
+            received
+        });
+
+        // El tx sigue vivo, por lo que sin el token el hilo no terminaría nunca
+        thread::sleep(std::time::Duration::from_millis(300));
+        token.request();
+        assert_eq!(handle.join().unwrap(), vec![1]);
+        drop(tx);
+    }
+}
@@ -0,0 +1,88 @@
+use std::{
+    io::{Error, ErrorKind},
+    net::{IpAddr, Ipv6Addr, SocketAddr},
+};
+
+use crate::apps::properties::Properties;
+
+/// Arma la dirección de socket a partir de una IP y un puerto. La IP puede ser IPv4 (`127.0.0.1`) o IPv6,
+/// con o sin corchetes (`::1` o `[::1]`), y puede estar entre comillas como en los archivos de configuración.
+/// No se arma un string `ip:puerto`, ya que para IPv6 sin corchetes sería ambiguo (`::1:9090`).
+pub fn parse_socket_addr(ip: &str, port: u16) -> Result<SocketAddr, Error> {
+    let ip = ip.trim().trim_matches('"');
+    let ip = ip
+        .strip_prefix('[')
+        .and_then(|ip| ip.strip_suffix(']'))
+        .unwrap_or(ip);
+    match ip.parse::<IpAddr>() {
+        Ok(ip_addr) => Ok(SocketAddr::new(ip_addr, port)),
+        Err(_) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            format!("La dirección IP '{}' no es válida", ip),
+        )),
+    }
+}
+
+/// Lee del archivo de properties la dirección del broker, de las propiedades `ip` y `port`.
+pub fn socket_addr_from_properties_file(properties_file: &str) -> Result<SocketAddr, Error> {
+    let properties = Properties::new(properties_file)?;
+    let (ip, port) = match (properties.get("ip"), properties.get("port")) {
+        (Some(ip), Some(port)) => (ip, port),
+        _ => {
+            return Err(Error::new(
+                ErrorKind::NotFound,
+                "Faltan las propiedades 'ip' y 'port'",
+            ))
+        }
+    };
+    match port.trim_matches('"').parse::<u16>() {
+        Ok(port) => parse_socket_addr(ip, port),
+        Err(_) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "El puerto proporcionado no es válido",
+        )),
+    }
+}
+
+/// Devuelve la dirección para escuchar en todas las interfaces IPv6 (`[::]:port`). Donde el sistema operativo
+/// lo permite (ej. por defecto en Linux), el socket es dual-stack y acepta también conexiones IPv4.
+pub fn dual_stack_addr(port: u16) -> SocketAddr {
+    SocketAddr::new(IpAddr::V6(Ipv6Addr::UNSPECIFIED), port)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        fs,
+        net::{IpAddr, Ipv6Addr, SocketAddr},
+    };
+
+    use super::{parse_socket_addr, socket_addr_from_properties_file};
+
+    #[test]
+    fn test_1_se_aceptan_ips_v4_y_v6_con_o_sin_corchetes() {
+        let ipv6: SocketAddr = "[::1]:9090".parse().unwrap();
+        assert_eq!(parse_socket_addr("::1", 9090).unwrap(), ipv6);
+        assert_eq!(parse_socket_addr("[::1]", 9090).unwrap(), ipv6);
+        assert_eq!(
+            parse_socket_addr("\"127.0.0.1\"", 9090).unwrap(),
+            "127.0.0.1:9090".parse::<SocketAddr>().unwrap()
+        );
+        assert!(parse_socket_addr("[::1]:9090", 9090).is_err());
+        assert!(parse_socket_addr("localhost", 9090).is_err());
+    }
+
+    #[test]
+    fn test_2_se_lee_una_direccion_ipv6_del_broker_del_archivo_de_configuracion() {
+        let file = std::env::temp_dir().join("test_socket_address_2.properties");
+        fs::write(&file, "ip=\"[::1]\"\nport=\"9090\"").unwrap();
+
+        let addr = socket_addr_from_properties_file(file.to_str().unwrap());
+        let _ = fs::remove_file(&file);
+
+        assert_eq!(
+            addr.unwrap(),
+            SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 9090)
+        );
+    }
+}
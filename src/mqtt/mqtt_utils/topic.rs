@@ -0,0 +1,123 @@
+use std::fmt::Display;
+
+use crate::mqtt::mqtt_utils::{
+    mqtt_error::MqttError,
+    topic_validation::{validate_topic_filter, validate_topic_name},
+};
+
+/// Nombre de un topic al que se publica. Se valida al construirlo (ver `validate_topic_name`):
+/// no es vacío, no contiene el caracter U+0000, ni comodines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Topic(String);
+
+/// Topic filter de un subscribe o unsubscribe. Se valida al construirlo (ver `validate_topic_filter`):
+/// no es vacío ni contiene el caracter U+0000, pero a diferencia de `Topic` puede contener comodines.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TopicFilter(String);
+
+impl Topic {
+    /// Crea el topic sin validarlo, para nombres que se sabe que son válidos (ej. los topics de las apps).
+    pub(crate) fn from_valid(topic: &str) -> Self {
+        Self(topic.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TopicFilter {
+    /// Crea el topic filter sin validarlo, para filters que se sabe que son válidos (ej. los topics de las apps).
+    pub(crate) fn from_valid(filter: &str) -> Self {
+        Self(filter.to_string())
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl TryFrom<String> for Topic {
+    type Error = MqttError;
+
+    fn try_from(topic: String) -> Result<Self, Self::Error> {
+        validate_topic_name(&topic)?;
+        Ok(Self(topic))
+    }
+}
+
+impl TryFrom<&str> for Topic {
+    type Error = MqttError;
+
+    fn try_from(topic: &str) -> Result<Self, Self::Error> {
+        Self::try_from(topic.to_string())
+    }
+}
+
+impl TryFrom<String> for TopicFilter {
+    type Error = MqttError;
+
+    fn try_from(filter: String) -> Result<Self, Self::Error> {
+        validate_topic_filter(&filter)?;
+        Ok(Self(filter))
+    }
+}
+
+impl TryFrom<&str> for TopicFilter {
+    type Error = MqttError;
+
+    fn try_from(filter: &str) -> Result<Self, Self::Error> {
+        Self::try_from(filter.to_string())
+    }
+}
+
+/// Todo nombre de topic es también un topic filter válido (uno que coincide solamente con ese topic).
+impl From<Topic> for TopicFilter {
+    fn from(topic: Topic) -> Self {
+        Self(topic.0)
+    }
+}
+
+impl Display for Topic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Display for TopicFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// Convierte los pares (topic filter, qos) de las apps, que los leen como strings (ej. de su configuración),
+/// validando cada topic filter.
+pub fn topic_filters_with_qos(topics: Vec<(String, u8)>) -> Result<Vec<(TopicFilter, u8)>, MqttError> {
+    topics
+        .into_iter()
+        .map(|(filter, qos)| Ok((TopicFilter::try_from(filter)?, qos)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{topic_filters_with_qos, Topic, TopicFilter};
+
+    #[test]
+    fn test_1_se_valida_al_construir_y_los_comodines_solamente_se_permiten_en_filters() {
+        assert_eq!(Topic::try_from("inc").unwrap().as_str(), "inc");
+        assert!(Topic::try_from("").is_err());
+        assert!(Topic::try_from("dron/\u{0}").is_err());
+        assert!(Topic::try_from("dron/+").is_err());
+        assert!(Topic::try_from(String::from("dron/#")).is_err());
+
+        assert_eq!(TopicFilter::try_from("dron/+").unwrap().as_str(), "dron/+");
+        assert!(TopicFilter::try_from("").is_err());
+
+        assert_eq!(TopicFilter::from(Topic::try_from("dron").unwrap()).as_str(), "dron");
+
+        let filters = topic_filters_with_qos(vec![("inc".to_string(), 1), ("dron/#".to_string(), 0)]).unwrap();
+        assert_eq!(filters[1], (TopicFilter::try_from("dron/#").unwrap(), 0));
+        assert!(topic_filters_with_qos(vec![("".to_string(), 1)]).is_err());
+    }
+}
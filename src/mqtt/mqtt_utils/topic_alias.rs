@@ -0,0 +1,144 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+};
+
+use crate::mqtt::messages::publish_message::PublishMessage;
+
+/// Cantidad máxima de alias de topic por conexión. Los alias válidos van de 1 a este valor.
+pub const TOPIC_ALIAS_MAXIMUM: u16 = 16;
+
+/// Alias de topic del lado que publica, para no repetir el topic en cada publish frecuente (ej. de cámaras
+/// y drones). Al publicar por primera vez en un topic se le asigna un alias, y se envían ambos; los siguientes
+/// publish a ese topic envían solamente el alias. Solamente se usan si se negociaron al conectarse, y valen
+/// mientras dure la conexión: al reconectarse se crea una tabla nueva.
+#[derive(Debug)]
+pub struct OutgoingTopicAliases {
+    maximum: u16,
+    aliases: HashMap<String, u16>,
+}
+
+impl OutgoingTopicAliases {
+    /// Crea la tabla, que asigna como máximo `maximum` alias (con 0 no se asigna ninguno).
+    pub fn new(maximum: u16) -> Self {
+        Self {
+            maximum: maximum.min(TOPIC_ALIAS_MAXIMUM),
+            aliases: HashMap::new(),
+        }
+    }
+
+    /// Crea la tabla para una conexión en que no se negociaron alias: los publish se envían sin cambios.
+    pub fn disabled() -> Self {
+        Self::new(0)
+    }
+
+    /// Devuelve el publish a enviar para `msg`: con solamente el alias si su topic ya tiene uno; con el topic
+    /// y un nuevo alias si todavía quedan alias por asignar; o sin cambios en otro caso.
+    pub fn apply(&mut self, msg: &PublishMessage) -> PublishMessage {
+        let topic = msg.get_topic();
+        if let Some(alias) = self.aliases.get(&topic) {
+            return msg.with_topic_alias(*alias, false);
+        }
+        if self.aliases.len() < self.maximum as usize {
+            let alias = self.aliases.len() as u16 + 1;
+            self.aliases.insert(topic, alias);
+            return msg.with_topic_alias(alias, true);
+        }
+        msg.clone()
+    }
+}
+
+/// Alias de topic del lado que recibe los publish: registra el alias de los publish que envían topic y alias,
+/// y completa el topic de los que envían solamente el alias.
+#[derive(Debug)]
+pub struct IncomingTopicAliases {
+    topics: HashMap<u16, String>,
+}
+
+impl IncomingTopicAliases {
+    pub fn new() -> Self {
+        Self {
+            topics: HashMap::new(),
+        }
+    }
+
+    /// Devuelve `msg` con su topic completo y sin alias. Devuelve error si el alias no es válido,
+    /// o si el publish envía solamente un alias que no se había registrado.
+    pub fn resolve(&mut self, msg: PublishMessage) -> Result<PublishMessage, Error> {
+        let alias = match msg.get_topic_alias() {
+            Some(alias) => alias,
+            None => return Ok(msg),
+        };
+        if alias == 0 || alias > TOPIC_ALIAS_MAXIMUM {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Alias de topic fuera de rango: {}", alias),
+            ));
+        }
+        let topic = msg.get_topic();
+        if !topic.is_empty() {
+            self.topics.insert(alias, topic.to_string());
+            return Ok(msg.with_resolved_topic(&topic));
+        }
+        match self.topics.get(&alias) {
+            Some(topic) => Ok(msg.with_resolved_topic(topic)),
+            None => Err(Error::new(
+                ErrorKind::InvalidData,
+                format!("Alias de topic no registrado: {}", alias),
+            )),
+        }
+    }
+}
+
+impl Default for IncomingTopicAliases {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{IncomingTopicAliases, OutgoingTopicAliases};
+    use crate::mqtt::messages::{publish_flags::PublishFlags, publish_message::PublishMessage};
+
+    fn create_publish(topic: &str) -> PublishMessage {
+        let flags = PublishFlags::new(0, 1, 0).unwrap();
+        PublishMessage::new(flags, topic, Some(1), &[1, 2, 3]).unwrap()
+    }
+
+    /// Simula el envío por la red.
+    fn send(msg: &PublishMessage) -> PublishMessage {
+        PublishMessage::from_bytes(msg.to_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_1_luego_del_primer_publish_a_un_topic_se_envia_solamente_su_alias() {
+        let mut outgoing = OutgoingTopicAliases::new(1);
+        let mut incoming = IncomingTopicAliases::new();
+        let (first, second) = (
+            create_publish("camaras/frame"),
+            create_publish("camaras/frame"),
+        );
+
+        let first_sent = outgoing.apply(&first);
+        let second_sent = outgoing.apply(&second);
+        assert_eq!(first_sent.get_topic_alias(), Some(1));
+        assert!(second_sent.to_bytes().len() < second.to_bytes().len());
+
+        assert_eq!(incoming.resolve(send(&first_sent)).unwrap(), first);
+        assert_eq!(incoming.resolve(send(&second_sent)).unwrap(), second);
+
+        // Ya no quedan alias para asignar: se envía el topic completo
+        let other = create_publish("dron");
+        assert_eq!(outgoing.apply(&other), other);
+        assert_eq!(OutgoingTopicAliases::disabled().apply(&first), first);
+    }
+
+    #[test]
+    fn test_2_un_alias_no_registrado_da_error() {
+        let mut incoming = IncomingTopicAliases::new();
+        let only_alias = create_publish("dron").with_topic_alias(3, false);
+
+        assert!(incoming.resolve(send(&only_alias)).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::mqtt::mqtt_utils::mqtt_error::MqttError;
+
+/// Caracteres comodín, válidos solamente en los topic filters de un subscribe.
+const WILDCARDS: [char; 2] = ['+', '#'];
+
+/// Valida el nombre del topic de un publish: no debe ser vacío, ni contener el caracter U+0000, ni comodines.
+pub fn validate_topic_name(topic: &str) -> Result<(), MqttError> {
+    validate_topic(topic)?;
+    if topic.contains(WILDCARDS) {
+        return Err(MqttError::Protocol(format!(
+            "El topic '{}' de un publish no puede contener comodines.",
+            topic
+        )));
+    }
+    Ok(())
+}
+
+/// Valida el topic filter de un subscribe: no debe ser vacío ni contener el caracter U+0000.
+/// A diferencia del nombre de un topic, puede contener comodines.
+pub fn validate_topic_filter(filter: &str) -> Result<(), MqttError> {
+    validate_topic(filter)
+}
+
+/// Valida lo que comparten los nombres de topic y los topic filters.
+fn validate_topic(topic: &str) -> Result<(), MqttError> {
+    if topic.is_empty() {
+        return Err(MqttError::Protocol("El topic no puede ser vacío.".to_string()));
+    }
+    if topic.contains('\u{0}') {
+        return Err(MqttError::Protocol(
+            "El topic no puede contener el caracter U+0000.".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Interpreta los bytes recibidos como un topic, que debe estar codificado en UTF-8 válido.
+pub fn topic_from_utf8(bytes: &[u8]) -> Result<String, MqttError> {
+    match String::from_utf8(bytes.to_vec()) {
+        Ok(topic) => Ok(topic),
+        Err(_) => Err(MqttError::Protocol(
+            "El topic no es UTF-8 válido.".to_string(),
+        )),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{topic_from_utf8, validate_topic_filter, validate_topic_name};
+
+    #[test]
+    fn test_1_topics_vacios_o_con_caracter_nulo_se_rechazan() {
+        assert!(validate_topic_name("").is_err());
+        assert!(validate_topic_filter("").is_err());
+        assert!(validate_topic_name("inc\u{0}").is_err());
+        assert!(validate_topic_filter("dron/\u{0}").is_err());
+        assert!(validate_topic_name("inc").is_ok());
+        assert!(validate_topic_filter("inc").is_ok());
+    }
+
+    #[test]
+    fn test_2_los_comodines_se_permiten_solamente_en_topic_filters() {
+        assert!(validate_topic_name("dron/+").is_err());
+        assert!(validate_topic_name("dron/#").is_err());
+        assert!(validate_topic_filter("dron/+").is_ok());
+        assert!(validate_topic_filter("dron/#").is_ok());
+    }
+
+    #[test]
+    fn test_3_topics_que_no_son_utf8_valido_se_rechazan() {
+        assert_eq!(topic_from_utf8("cámaras".as_bytes()).unwrap(), "cámaras");
+        assert!(topic_from_utf8(&[0x69, 0x6e, 0xff, 0xfe]).is_err());
+    }
+}
@@ -6,7 +6,9 @@ use std::{
 use crate::mqtt::messages::{
     packet_type::PacketType, puback_message::PubAckMessage, publish_message::PublishMessage,
 };
-use crate::mqtt::mqtt_utils::fixed_header::FixedHeader;
+use crate::mqtt::mqtt_utils::fixed_header::{
+    FixedHeader, CONTINUATION_BIT, MAX_REMAINING_LENGTH_BYTES,
+};
 type StreamType = TcpStream;
 
 // Este archivo contiene funciones que utilizan para hacer read y write desde el stream
@@ -18,7 +20,7 @@ type StreamType = TcpStream;
 /// Escribe el mensaje en bytes `msg_bytes` por el stream hacia el cliente.
 /// Puede devolver error si falla la escritura o el flush.
 pub fn write_message_to_stream(msg_bytes: &[u8], stream: &mut StreamType) -> Result<(), Error> {
-    let _ = stream.write(msg_bytes)?;
+    stream.write_all(msg_bytes)?;
     stream.flush()?;
 
     Ok(())
@@ -36,17 +38,26 @@ pub fn get_fixed_header_from_stream(
     match res {
         Ok(b) if b.len() == 2 => {
             // He leído bytes de un fixed_header, tengo que ver de qué tipo es.
-            let fixed_header = FixedHeader::from_bytes(b.to_vec());
             let fixed_header_buf = [b[0], b[1]];
+            let mut header_bytes = b;
+            // Si la remaining length ocupa más de un byte, se leen los restantes.
+            let first_header = FixedHeader::from_bytes(header_bytes.clone());
+            if first_header.has_more_remaining_length_bytes(&header_bytes) {
+                read_remaining_length_continuation(stream, &mut header_bytes)?;
+            }
+            let fixed_header = FixedHeader::from_bytes(header_bytes);
 
             //println!("DEVOLVIENDO FIXED HEADER");
             Ok(Some((fixed_header_buf, fixed_header)))
         }
         Err(e) => Err(e),
-        _ => {
-            //println!("READ NUEVO: Fixed header rama None, vale: {:?}");
-            Ok(None)
-        }
+        // EOF entre dos mensajes: se cerró la conexión
+        Ok(b) if b.is_empty() => Ok(None),
+        // EOF a la mitad del fixed header: el mensaje quedó cortado
+        Ok(_) => Err(Error::new(
+            ErrorKind::UnexpectedEof,
+            "Se cerró la conexión a la mitad del fixed header.",
+        )),
     }
 }
 
@@ -61,11 +72,14 @@ pub fn get_whole_message_in_bytes_from_stream(
 ) -> Result<Vec<u8>, Error> {
     // Siendo que ya hemos leído fixed_header, sabemos que el resto del mensaje está disponible para ser leído.
     let msg_rem_len: usize = fixed_header.get_rem_len();
-    let rem_buf: Result<Vec<u8>, Error> = stream.bytes().take(msg_rem_len).collect();
+    let mut rem_buf = vec![0; msg_rem_len];
+    let rem_buf: Result<Vec<u8>, Error> = stream.read_exact(&mut rem_buf).map(|_| rem_buf);
     //println!("obteniendo mensaje completo");
     match rem_buf {
         Ok(b) if b.len() == msg_rem_len => {
+            // Los bytes de la remaining length que siguen a los dos ya leídos (si ocupaba más de uno).
             let mut buf = fixed_header_bytes.to_vec();
+            buf.extend(&fixed_header.to_bytes()[fixed_header_bytes.len()..]);
             buf.extend(b);
 
             Ok(buf)
@@ -77,10 +91,32 @@ pub fn get_whole_message_in_bytes_from_stream(
     }
 }
 
+/// Lee del `stream` los bytes de la remaining length que siguen a los ya leídos en `header_bytes`,
+/// agregándolos a éste, hasta encontrar uno sin el bit de continuación.
+fn read_remaining_length_continuation(
+    stream: &mut StreamType,
+    header_bytes: &mut Vec<u8>,
+) -> Result<(), Error> {
+    let mut byte = [0u8; 1];
+    loop {
+        if header_bytes.len() > MAX_REMAINING_LENGTH_BYTES {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                "Remaining length de más de 4 bytes",
+            ));
+        }
+        stream.read_exact(&mut byte)?;
+        header_bytes.push(byte[0]);
+        if byte[0] & CONTINUATION_BIT == 0 {
+            return Ok(());
+        }
+    }
+}
+
 /// Envía un mensaje de tipo PubAck por el stream.
 pub fn send_puback(msg: &PublishMessage, stream: &mut TcpStream) -> Result<(), Error> {
-    if let Some(packet_id) = msg.get_packet_id() {
-        let ack = PubAckMessage::new(packet_id, 0);
+    if msg.get_packet_id().is_some() {
+        let ack = PubAckMessage::for_publish(msg);
         let ack_msg_bytes = ack.to_bytes();
         write_message_to_stream(&ack_msg_bytes, stream)?;
         println!("   tipo publish: Enviado el ack: {:?}", ack);
@@ -1,6 +1,6 @@
 use std::io::Error;
 
-#[derive(Debug, PartialEq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Hash, Clone, Copy)]
 pub enum AppType {
     Cameras,
     Dron,
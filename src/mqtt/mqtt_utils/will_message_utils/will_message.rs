@@ -1,8 +1,11 @@
+use crate::apps::apps_mqtt_topics::AppsMqttTopics;
+use crate::mqtt::mqtt_utils::mqtt_error::MqttError;
+
 /// Struct utilizado por el MQTTServer, y por las Apps antes de llamar a la función que efectúa el connect.
 /// Contiene la información relacionada al will_message extraída del ConnectMessage.
 /// Se almacena en un User del MQTTServer, y es necesaria para posteriormente construir el PublishMessage
 /// a enviar a los suscriptores del will_topic.
-#[derive(Debug, PartialEq)]
+#[derive(Debug, PartialEq, Clone)]
 pub struct WillMessageData {
     will_message_content: String,
     will_topic: String,
@@ -15,6 +18,25 @@ impl WillMessageData {
         Self {will_message_content, will_topic, qos, will_retain }
     }
 
+    /// Crea el will que las apps envían al conectarse, a publicarse en el topic `will_topic` de las apps.
+    /// Devuelve error si el `qos` no es 0, 1 o 2.
+    pub fn for_app_topic(
+        will_message_content: String,
+        will_topic: AppsMqttTopics,
+        qos: u8,
+        will_retain: u8,
+    ) -> Result<Self, MqttError> {
+        if qos > 2 {
+            return Err(MqttError::Protocol(format!("QoS del will no válido: {}", qos)));
+        }
+        Ok(Self::new(
+            will_message_content,
+            will_topic.to_str().to_string(),
+            qos,
+            will_retain,
+        ))
+    }
+
     pub fn get_will_msg_content(&self) -> String {
         String::from(&self.will_message_content)
     }
@@ -28,4 +50,22 @@ impl WillMessageData {
     pub fn get_will_retain(&self) -> u8 {
         self.will_retain
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::WillMessageData;
+    use crate::apps::apps_mqtt_topics::AppsMqttTopics;
+    use crate::mqtt::mqtt_utils::mqtt_error::MqttError;
+
+    #[test]
+    fn test_1_for_app_topic_usa_el_topic_de_las_apps_y_valida_el_qos() {
+        let will = WillMessageData::for_app_topic("dron-1-3".to_string(), AppsMqttTopics::DescTopic, 1, 1)
+            .unwrap();
+        assert_eq!(will.get_will_topic(), "desc");
+        assert_eq!(will.get_qos(), 1);
+
+        let res = WillMessageData::for_app_topic("dron-1-3".to_string(), AppsMqttTopics::DescTopic, 3, 1);
+        assert!(matches!(res, Err(MqttError::Protocol(_))));
+    }
+}
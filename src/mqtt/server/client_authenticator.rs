@@ -1,28 +1,66 @@
-use std::{io::Error, path::Path};
+use std::io::{Error, ErrorKind};
 
+use crate::apps::properties::Properties;
 use crate::logging::string_logger::StringLogger;
 use crate::mqtt::messages::{
     connack_message::ConnackMessage, connack_session_present::SessionPresent,
     connect_message::ConnectMessage, connect_return_code::ConnectReturnCode,
 };
-use crate::mqtt::mqtt_utils::utils::write_message_to_stream;
+use crate::mqtt::mqtt_utils::utils::{shutdown, write_message_to_stream};
 use crate::mqtt::stream_type::StreamType;
 
-use super::file_helper::read_lines;
+use super::credentials::Credentials;
 use super::mqtt_server::MQTTServer;
 
 #[derive(Debug)]
 pub struct AuthenticateClient {
+    credentials: Credentials,
+    allow_anonymous: bool,
     logger: StringLogger,
 }
 
 impl AuthenticateClient {
-    pub fn new(logger: StringLogger) -> Self {
+    /// Crea un AuthenticateClient que valida contra las `credentials` recibidas,
+    /// y que acepta conexiones sin usuario ni contraseña si `allow_anonymous` es true.
+    pub fn new(credentials: Credentials, allow_anonymous: bool, logger: StringLogger) -> Self {
         AuthenticateClient {
+            credentials,
+            allow_anonymous,
             logger,
         }
     }
 
+    /// Carga del archivo de configuración del servidor el archivo de credenciales (`credentials_file`)
+    /// y si se permiten conexiones anónimas (`allow_anonymous`, por defecto true por compatibilidad).
+    pub fn from_properties_file(properties_file: &str, logger: StringLogger) -> Result<Self, Error> {
+        let properties = Properties::new(properties_file)?;
+
+        let credentials_file = match properties.get("credentials_file") {
+            Some(prop) => prop,
+            None => {
+                return Err(Error::other("Falta propiedad credentials_file."))
+            }
+        };
+        let credentials = Credentials::from_file(credentials_file)?;
+
+        let allow_anonymous = match properties.get("allow_anonymous") {
+            Some(prop) => prop.parse::<bool>().map_err(|_| {
+                Error::new(ErrorKind::InvalidData, "Propiedad allow_anonymous inválida.")
+            })?,
+            None => true,
+        };
+
+        Ok(Self::new(credentials, allow_anonymous, logger))
+    }
+
+    pub fn clone_ref(&self) -> Self {
+        Self {
+            credentials: self.credentials.clone(),
+            allow_anonymous: self.allow_anonymous,
+            logger: self.logger.clone_ref(),
+        }
+    }
+
     /// Procesa el mensaje de conexión recibido, autentica al cliente y envía un mensaje de conexión de vuelta.
     pub fn is_it_a_valid_connection(
         &self,
@@ -31,7 +69,7 @@ impl AuthenticateClient {
         mqtt_server: &MQTTServer,
     ) -> Result<bool, Error> {
         let (is_authentic, connack_response) =
-            self.was_the_session_created_succesfully(connect_msg)?;
+            self.was_the_session_created_succesfully(connect_msg, mqtt_server)?;
 
         self.send_connection_response(&connack_response, stream)?; // aux: y si mejor le devuelve el connack? []
 
@@ -39,6 +77,8 @@ impl AuthenticateClient {
             self.handle_successful_authentication(connect_msg, stream, mqtt_server)
         // aux: llama todo de server adentro, para mí iría mejor en mqtt_server []
         } else {
+            // Se rechazó la conexión, se cierra el stream
+            shutdown(stream);
             Ok(false)
         }
     }
@@ -60,7 +100,7 @@ impl AuthenticateClient {
     ) -> Result<bool, Error> {
         if let Some(username) = connect_msg.get_client_id() {
             let is_reconnection =
-                mqtt_server.manage_possible_reconnecting_or_duplicate_user(username, stream)?;
+                mqtt_server.manage_possible_reconnecting_or_duplicate_user(connect_msg, stream)?;
             if !is_reconnection {
                 println!("Agregando nuevo user al server con username {:?}", username);
                 self.logger.log(format!("Agregando nuevo user al server con username {:?}", username));
@@ -73,25 +113,53 @@ impl AuthenticateClient {
     }
 
     /// Verifica si la sesión fue creada exitosamente: usuario valido o invitado
-    /// y devuelve un mensaje CONNACK acorde.
+    /// y devuelve un mensaje CONNACK acorde, indicando si se continúa una sesión existente del cliente.
     fn was_the_session_created_succesfully(
         &self,
         connect_msg: &ConnectMessage,
+        mqtt_server: &MQTTServer,
     ) -> Result<(bool, ConnackMessage), Error> {
-        if self.is_guest_mode_active(connect_msg.get_user(), connect_msg.get_passwd())
-            || self.authenticate(connect_msg.get_user(), connect_msg.get_passwd())
-        {
-            let connack_response = ConnackMessage::new(
-                SessionPresent::NotPresentInLastSession,
-                ConnectReturnCode::ConnectionAccepted,
-            );
-            Ok((true, connack_response))
+        let return_code = self.get_connect_return_code_for(connect_msg);
+        let is_accepted = return_code == ConnectReturnCode::ConnectionAccepted;
+        if !is_accepted {
+            self.logger.log(format!(
+                "Conexión rechazada para {:?}: {}.",
+                connect_msg.get_client_id(),
+                return_code.description()
+            ));
+        }
+        let session_present = if is_accepted && mqtt_server.has_session_to_resume(connect_msg) {
+            SessionPresent::PresentInLastSession
         } else {
-            let connack_response = ConnackMessage::new(
-                SessionPresent::NotPresentInLastSession,
-                ConnectReturnCode::NotAuthorized,
-            );
-            Ok((false, connack_response))
+            SessionPresent::NotPresentInLastSession
+        };
+        let mut connack_response = ConnackMessage::new(session_present, return_code);
+        // Si el cliente los solicitó, se aceptan los alias de topic (ver `ClientReader`).
+        if connect_msg.requests_topic_aliases() {
+            connack_response = connack_response.accepting_topic_aliases();
+        }
+        Ok((is_accepted, connack_response))
+    }
+
+    /// Devuelve el código con el que se responderá al connect: rechaza la conexión si el client_id
+    /// es vacío, si se conecta sin credenciales y no se permiten anónimos, o si las credenciales no son válidas.
+    fn get_connect_return_code_for(&self, connect_msg: &ConnectMessage) -> ConnectReturnCode {
+        let has_client_id = connect_msg
+            .get_client_id()
+            .is_some_and(|client_id| !client_id.is_empty());
+        if !has_client_id {
+            return ConnectReturnCode::IdentifierRejected;
+        }
+        if self.is_guest_mode_active(connect_msg.get_user(), connect_msg.get_passwd()) {
+            if self.allow_anonymous {
+                ConnectReturnCode::ConnectionAccepted
+            } else {
+                ConnectReturnCode::NotAuthorized
+            }
+        } else if self.authenticate(connect_msg.get_user(), connect_msg.get_passwd()) {
+            ConnectReturnCode::ConnectionAccepted
+        } else {
+            ConnectReturnCode::BadUsernameOrPassword
         }
     }
 
@@ -99,42 +167,87 @@ impl AuthenticateClient {
         user.is_none() && passwd.is_none()
     }
 
-    /// Autentica al usuario con las credenciales almacenadas en el archivo credentials.txt
+    /// Autentica al usuario con las credenciales cargadas al iniciar el servidor.
     fn authenticate(&self, user: Option<&String>, passwd: Option<&String>) -> bool {
-        let credentials = self.read_credentials_from_file("credentials.txt");
-        self.verify_authentication(user, passwd, &credentials)
+        if let (Some(u), Some(p)) = (user, passwd) {
+            self.credentials.are_valid(u, p)
+        } else {
+            false
+        }
     }
+}
 
-    /// Lee las credenciales del archivo especificado y devuelve un vector de pares (usuario, contraseña)
-    fn read_credentials_from_file(&self, file_path: &str) -> Vec<(String, String)> {
-        let path = Path::new(file_path);
-        let mut credentials = Vec::new();
-
-        if let Ok(lines) = read_lines(path) {
-            for line in lines.map_while(Result::ok) {
-                let parts: Vec<&str> = line.split_whitespace().collect();
-                if parts.len() == 2 {
-                    credentials.push((parts[0].to_string(), parts[1].to_string()));
-                }
-            }
-        }
+#[cfg(test)]
+mod test {
+    use std::sync::mpsc;
+
+    use super::AuthenticateClient;
+    use crate::{
+        logging::string_logger::StringLogger,
+        mqtt::{
+            messages::{connect_message::ConnectMessage, connect_return_code::ConnectReturnCode},
+            server::credentials::{hash_password, Credentials},
+        },
+    };
+
+    fn create_authenticator_with(allow_anonymous: bool) -> AuthenticateClient {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let content = format!("usuario0:{}", hash_password("rustx123"));
+        let credentials = Credentials::parse(&content).unwrap();
+        AuthenticateClient::new(credentials, allow_anonymous, StringLogger::new(logger_tx))
+    }
 
-        credentials
+    fn create_authenticator() -> AuthenticateClient {
+        create_authenticator_with(true)
     }
 
-    /// Verifica si el usuario y la contraseña proporcionados coinciden con alguna de las credenciales almacenadas
-    fn verify_authentication(
-        &self,
-        user: Option<&String>,
-        passwd: Option<&String>,
-        credentials: &[(String, String)],
-    ) -> bool {
-        if let (Some(u), Some(p)) = (user, passwd) {
-            credentials
-                .iter()
-                .any(|(username, password)| u == username && p == password)
-        } else {
-            false
-        }
+    fn create_connect_msg(client_id: &str, user: &str, passwd: &str) -> ConnectMessage {
+        ConnectMessage::new(
+            client_id.to_string(),
+            None,
+            None,
+            Some(user.to_string()),
+            Some(passwd.to_string()),
+            1,
+        )
+    }
+
+    #[test]
+    fn test_1_se_rechaza_con_el_codigo_correspondiente() {
+        let authenticator = create_authenticator();
+
+        let bad_credentials = create_connect_msg("dron-1", "usuario0", "incorrecta");
+        assert_eq!(
+            authenticator.get_connect_return_code_for(&bad_credentials),
+            ConnectReturnCode::BadUsernameOrPassword
+        );
+
+        let empty_client_id = create_connect_msg("", "usuario0", "rustx123");
+        assert_eq!(
+            authenticator.get_connect_return_code_for(&empty_client_id),
+            ConnectReturnCode::IdentifierRejected
+        );
+    }
+
+    #[test]
+    fn test_2_credenciales_validas_y_conexion_anonima_segun_configuracion() {
+        let valid_credentials = create_connect_msg("dron-1", "usuario0", "rustx123");
+        let anonymous = ConnectMessage::new("dron-1".to_string(), None, None, None, None, 1);
+
+        let authenticator = create_authenticator_with(false);
+        assert_eq!(
+            authenticator.get_connect_return_code_for(&valid_credentials),
+            ConnectReturnCode::ConnectionAccepted
+        );
+        assert_eq!(
+            authenticator.get_connect_return_code_for(&anonymous),
+            ConnectReturnCode::NotAuthorized
+        );
+
+        let authenticator = create_authenticator_with(true);
+        assert_eq!(
+            authenticator.get_connect_return_code_for(&anonymous),
+            ConnectReturnCode::ConnectionAccepted
+        );
     }
-}
\ No newline at end of file
+}
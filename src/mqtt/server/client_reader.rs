@@ -1,7 +1,10 @@
 use crate::logging::string_logger::StringLogger;
-use crate::mqtt::messages::{connect_message::ConnectMessage, packet_type::PacketType};
+use crate::mqtt::messages::{
+    connect_message::ConnectMessage, packet_type::PacketType, publish_message::PublishMessage,
+};
 use crate::mqtt::mqtt_utils::{
     fixed_header::FixedHeader,
+    topic_alias::IncomingTopicAliases,
     utils::{
         get_fixed_header_from_stream, get_fixed_header_from_stream_for_conn,
         get_whole_message_in_bytes_from_stream, is_disconnect_msg, shutdown,
@@ -15,11 +18,16 @@ use crate::mqtt::server::{
 use crate::mqtt::stream_type::StreamType;
 
 use std::{
-    io::Error,
+    io::{Error, ErrorKind},
+    net::SocketAddr,
     sync::mpsc::{Receiver, Sender},
-    thread::JoinHandle,
+    thread::{self, JoinHandle},
+    time::Duration,
 };
 
+// Cada cuánto se revisa si se liberó lugar para un nuevo publish del cliente (ver `wait_for_inbound_slot`).
+const INBOUND_SLOT_WAIT_INTERVAL: Duration = Duration::from_millis(10);
+
 #[derive(Debug)]
 pub struct ClientReader {
     stream: StreamType,
@@ -44,7 +52,7 @@ impl ClientReader {
     pub fn handle_client(&mut self, stream: &mut StreamType) -> Result<(), Error> {
         let (fixed_header_buf, fixed_header) = self.read_and_validate_header(stream)?;
 
-        let authenticator = AuthenticateClient::new(self.logger.clone_ref());
+        let authenticator = self.mqtt_server.get_authenticator();
         self.authenticate_and_handle_connection(
             &fixed_header,
             &fixed_header_buf,
@@ -69,6 +77,10 @@ impl ClientReader {
         stream: &mut StreamType,
     ) -> Result<(), Error> {
         match fixed_header.get_message_type() {
+            // Se controla antes de autenticarlo, para no reservar memoria por lo que anuncie un cliente desconocido
+            PacketType::Connect if self.exceeds_max_packet_size(fixed_header, "cliente sin autenticar") => {
+                shutdown(stream);
+            }
             PacketType::Connect => {
                 let connect_msg = get_connect_message(fixed_header, stream, fixed_header_buf)?;
                 if authenticator.is_it_a_valid_connection(
@@ -122,35 +134,44 @@ impl ClientReader {
     fn spawn_stream_handler(&self, client_id: String, tx_1: Sender<Packet>) -> JoinHandle<()> {
         let mut self_clone = self.clone_ref(); // []
         let logger_c = self.logger.clone_ref();
+        // Se obtiene ahora, mientras la conexión está abierta, para luego identificar a esta conexión
+        let peer_addr = self.stream.peer_addr().ok();
         std::thread::spawn(move || {
             if let Ok(disconnect_reason) =
                 self_clone.read_packets_from_stream(client_id.as_str(), tx_1)
                 {
                 match disconnect_reason {
                     DisconnectReason::Voluntaria => {
-                        if let Err(e) = self_clone.server_handle_disconnect(client_id.as_str()){
-                            logger_c.log(format!("Error al manejar disconnect: {:?}.", e));
-                        }
+                        // El DisconnectMessage fue enviado al MessageProcessor, que remueve al user sin publicar su will
+                        logger_c.log(format!("Desconexión voluntaria del cliente: {:?}.", client_id));
                     }
                     DisconnectReason::Involuntaria => {
-                        if let Err(e) = self_clone.server_handle_client_disconnection(client_id.as_str()){
+                        if let Err(e) = self_clone.server_handle_client_disconnection(client_id.as_str(), peer_addr){
                             logger_c.log(format!("Error al manejar desconexión involuntaria: {:?}.", e));
                         }
+                        // Si venció el keep alive, el cliente puede seguir del otro lado: se cierra la conexión
+                        shutdown(&self_clone.stream);
                     }
                 }
             }
         })
     }
 
-    /// Desconexión voluntaria.
-    fn server_handle_disconnect(&mut self, client_id: &str) -> Result<(), Error> {
-        self.mqtt_server.publish_users_will_message(client_id)?;
-        self.mqtt_server.remove_user(client_id);
-        Ok(())
-    }
-
-    /// Desconexión involuntaria (ie se le fue internet).
-    fn server_handle_client_disconnection(&mut self, client_id: &str) -> Result<(), Error> {
+    /// Desconexión involuntaria (ie se le fue internet). Es el único caso en que se publica el will message.
+    /// Si la conexión ya había sido reemplazada por otra con el mismo client_id (takeover), no se hace nada,
+    /// ya que el user corresponde a la nueva conexión.
+    fn server_handle_client_disconnection(
+        &mut self,
+        client_id: &str,
+        peer_addr: Option<SocketAddr>,
+    ) -> Result<(), Error> {
+        if !self.mqtt_server.is_current_connection_of(client_id, peer_addr) {
+            self.logger.log(format!(
+                "Se cerró una conexión reemplazada de {:?}, se ignora.",
+                client_id
+            ));
+            return Ok(());
+        }
         self.mqtt_server
             .set_user_as_temporally_disconnected(client_id)?;
         self.mqtt_server.publish_users_will_message(client_id)?;
@@ -174,19 +195,26 @@ impl ClientReader {
         println!("Eperando más mensajes.");
         self.logger.log("Esperando más mensajes.".to_string());
 
+        // Si el cliente indicó un keep alive, la lectura falla al no recibir nada dentro del mismo
+        self.stream
+            .set_read_timeout(self.mqtt_server.get_keep_alive_timeout_of(client_id))?;
+        // Los alias de topic valen mientras dure esta conexión
+        let mut topic_aliases = IncomingTopicAliases::new();
+
         loop {
             match get_fixed_header_from_stream(&mut self.stream) {
                 Ok(Some((fixed_h_buf, fixed_h))) => {
                     if is_disconnect_msg(&fixed_h) {
-                        self.handle_disconnect(client_id)?; // aux: llama a mqtt []
+                        self.handle_disconnect(fixed_h_buf, client_id, &tx_1)?;
                         return Ok(DisconnectReason::Voluntaria);
-                        // AUX, hace:
-                        // aux: self.mqtt_server.publish_users_will_message(client_id)?;
-                        // aux: self.mqtt_server.remove_user(client_id);
-                        //break;
+                    }
+                    if self.exceeds_max_packet_size(&fixed_h, client_id) {
+                        // No se lee el resto del paquete, por lo que la conexión no puede continuar
+                        shutdown(&self.stream);
+                        return Ok(DisconnectReason::Involuntaria);
                     }
                     // Completa la lectura del stream, y envía al otro hilo para ser procesado
-                    self.handle_packet(fixed_h, fixed_h_buf, client_id, &tx_1)?;
+                    self.handle_packet(fixed_h, fixed_h_buf, client_id, &tx_1, &mut topic_aliases)?;
                 }
                 Ok(None) => {
                     self.handle_client_disconnection(client_id)?; // aux: llama a mqtt []
@@ -196,18 +224,66 @@ impl ClientReader {
                     //aux: self.mqtt_server.publish_users_will_message(client_id)?;
                     //break;
                 }
-                Err(_) => todo!(),
+                Err(e) if is_timeout(&e) => {
+                    // No se recibió ningún mensaje dentro del keep alive: se lo considera desconectado
+                    // involuntariamente, por lo que se publica su will.
+                    println!("Keep alive vencido para el cliente: {:?}.", client_id);
+                    self.logger
+                        .log(format!("Keep alive vencido para el cliente: {:?}.", client_id));
+                    return Ok(DisconnectReason::Involuntaria);
+                }
+                Err(e) => {
+                    // Ej. se cerró la conexión a la mitad del fixed header, o éste es inválido: como no se
+                    // puede seguir leyendo el stream, se lo trata como una desconexión involuntaria.
+                    self.logger.log(format!(
+                        "ERROR: al leer el fixed header del cliente {:?}: {:?}.",
+                        client_id, e
+                    ));
+                    self.handle_client_disconnection(client_id)?;
+                    return Ok(DisconnectReason::Involuntaria);
+                }
             }
         }
         //Ok(())
     }
 
-    /// Desconexión voluntaria.
-    fn handle_disconnect(&mut self, _client_id: &str) -> Result<(), Error> {
-        //self.mqtt_server.publish_users_will_message(client_id)?;
-        //self.mqtt_server.remove_user(client_id);
+    /// Devuelve si el paquete cuyo fixed header es `fixed_h` supera el tamaño máximo configurado en el server,
+    /// en cuyo caso se lo rechaza antes de reservar memoria para leerlo.
+    fn exceeds_max_packet_size(&self, fixed_h: &FixedHeader, client_id: &str) -> bool {
+        let max_packet_size = self.mqtt_server.get_max_packet_size();
+        if fixed_h.get_rem_len() <= max_packet_size {
+            return false;
+        }
+        let error_msg = format!(
+            "ERROR: paquete de {:?} de {} bytes supera el máximo de {} bytes, se desconecta al cliente.",
+            client_id,
+            fixed_h.get_rem_len(),
+            max_packet_size
+        );
+        println!("{}", error_msg);
+        self.logger.log(error_msg);
+        true
+    }
+
+    /// Desconexión voluntaria. Envía el DisconnectMessage al hilo que procesa los mensajes,
+    /// para que el user sea removido sin publicar su will message, y cierra el stream.
+    fn handle_disconnect(
+        &mut self,
+        fixed_h_buf: [u8; 2],
+        client_id: &str,
+        tx_1: &Sender<Packet>,
+    ) -> Result<(), Error> {
         println!("Recibo disconnect");
         self.logger.log("Recibo disconnect.".to_string());
+        let packet = Packet::new(
+            PacketType::Disconnect,
+            fixed_h_buf.to_vec(),
+            client_id.to_string(),
+            self.stream.peer_addr().ok(),
+        );
+        if let Err(e) = tx_1.send(packet) {
+            self.logger.log(format!("Error al enviar disconnect por channel interno: {:?}.", e));
+        }
         shutdown(&self.stream);
         Ok(())
     }
@@ -218,14 +294,46 @@ impl ClientReader {
         fixed_h_buf: [u8; 2],
         client_id: &str,
         tx_1: &Sender<Packet>,
+        topic_aliases: &mut IncomingTopicAliases,
     ) -> Result<(), Error> {
         let packet = create_packet(&fixed_h, &mut self.stream, &fixed_h_buf, client_id)?;
+        self.mqtt_server
+            .add_bytes_received_from(client_id, packet.get_msg_len());
+        // Se resuelven acá, y no al procesarlo, porque los paquetes se procesan en paralelo y sin orden
+        let packet = match resolve_topic_alias(packet, topic_aliases) {
+            Ok(packet) => packet,
+            Err(e) => {
+                let error_msg = format!("ERROR: se descarta publish de {:?}: {:?}.", client_id, e);
+                println!("{}", error_msg);
+                self.logger.log(error_msg);
+                return Ok(());
+            }
+        };
+        if packet.is_publish_with_qos() {
+            self.wait_for_inbound_slot(client_id);
+        }
         if let Err(e) = tx_1.send(packet) {
             self.logger.log(format!("Error al enviar por channel interno, en handle_packet: {:?}.", e));
         }
         Ok(())
     }
 
+    /// Espera a que el cliente tenga menos publish sin responder que el máximo configurado en el server.
+    /// Mientras tanto no se lee de su stream, por lo que el cliente no puede seguir enviando.
+    fn wait_for_inbound_slot(&self, client_id: &str) {
+        let mut has_waited = false;
+        while !self.mqtt_server.try_start_inbound_publish_from(client_id) {
+            if !has_waited {
+                self.logger.log(format!(
+                    "Cliente {:?} con el máximo de publish sin responder, se espera para seguir leyendo.",
+                    client_id
+                ));
+                has_waited = true;
+            }
+            thread::sleep(INBOUND_SLOT_WAIT_INTERVAL);
+        }
+    }
+
     /// Desconexión involuntaria (ie se le fue internet).
     fn handle_client_disconnection(&mut self, client_id: &str) -> Result<(), Error> {
         println!("Se desconectó el cliente: {:?}.", client_id);
@@ -245,6 +353,11 @@ impl ClientReader {
     }
 }
 
+/// Devuelve si el error se debe a que venció el read timeout del stream.
+fn is_timeout(error: &Error) -> bool {
+    matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut)
+}
+
 fn create_packet(
     fixed_header: &FixedHeader,
     stream: &mut StreamType, // []
@@ -254,7 +367,36 @@ fn create_packet(
     let msg_bytes =
         get_whole_message_in_bytes_from_stream(fixed_header, stream, fixed_header_bytes)?;
     let message_type = fixed_header.get_message_type();
-    Ok(Packet::new(message_type, msg_bytes, client_id.to_string()))
+    Ok(Packet::new(
+        message_type,
+        msg_bytes,
+        client_id.to_string(),
+        stream.peer_addr().ok(),
+    ))
+}
+
+/// Si el `packet` es un publish que envía un alias de topic, lo reemplaza por el mismo publish con el topic
+/// completo, para que el resto del server no tenga que conocer los alias. Los demás paquetes no se modifican.
+/// Un publish mal formado tampoco: se lo descartará al procesarlo.
+fn resolve_topic_alias(
+    packet: Packet,
+    topic_aliases: &mut IncomingTopicAliases,
+) -> Result<Packet, Error> {
+    if packet.get_message_type() != PacketType::Publish {
+        return Ok(packet);
+    }
+    match PublishMessage::from_bytes(packet.get_msg_bytes()) {
+        Ok(msg) if msg.get_topic_alias().is_some() => {
+            let resolved = topic_aliases.resolve(msg)?;
+            Ok(Packet::new(
+                PacketType::Publish,
+                resolved.to_bytes(),
+                packet.get_username().to_string(),
+                packet.get_peer_addr(),
+            ))
+        }
+        _ => Ok(packet),
+    }
 }
 
 /// Completa la lectura y devuelve el `ConnectMessage`.
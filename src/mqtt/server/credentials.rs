@@ -0,0 +1,96 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    path::Path,
+    sync::Arc,
+};
+
+use sha2::{Digest, Sha256};
+
+use super::file_helper::read_lines;
+
+/// Credenciales de los usuarios habilitados a conectarse al servidor.
+/// Se cargan desde un archivo con una línea `usuario:hash_de_la_contraseña` por usuario,
+/// donde el hash es el sha256 de la contraseña, en hexadecimal.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    hashed_passwords: Arc<HashMap<String, String>>,
+}
+
+impl Credentials {
+    /// Lee las credenciales del archivo `file_path`.
+    pub fn from_file(file_path: &str) -> Result<Self, Error> {
+        let mut content = String::new();
+        for line in read_lines(Path::new(file_path))? {
+            content.push_str(&line?);
+            content.push('\n');
+        }
+        Self::parse(&content)
+    }
+
+    /// Interpreta el contenido de un archivo de credenciales.
+    /// Devuelve error si alguna línea no vacía no tiene el formato `usuario:hash`.
+    pub fn parse(content: &str) -> Result<Self, Error> {
+        let mut hashed_passwords = HashMap::new();
+        for line in content.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            match line.split_once(':') {
+                Some((user, hash)) if !user.is_empty() && !hash.is_empty() => {
+                    hashed_passwords.insert(user.to_string(), hash.to_lowercase());
+                }
+                _ => {
+                    return Err(Error::new(
+                        ErrorKind::InvalidData,
+                        format!("Línea de credenciales inválida: {:?}.", line),
+                    ))
+                }
+            }
+        }
+        Ok(Self {
+            hashed_passwords: Arc::new(hashed_passwords),
+        })
+    }
+
+    /// Devuelve si `user` existe y el hash de `passwd` coincide con el almacenado para él.
+    /// La comparación de los hashes se hace en tiempo constante.
+    pub fn are_valid(&self, user: &str, passwd: &str) -> bool {
+        match self.hashed_passwords.get(user) {
+            Some(stored_hash) => {
+                constant_time_eq(stored_hash.as_bytes(), hash_password(passwd).as_bytes())
+            }
+            None => false,
+        }
+    }
+}
+
+/// Devuelve el sha256 de la contraseña, en hexadecimal.
+pub fn hash_password(passwd: &str) -> String {
+    hex::encode(Sha256::digest(passwd.as_bytes()))
+}
+
+/// Compara los bytes sin cortar en la primera diferencia, para no filtrar información por el tiempo de respuesta.
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    if a.len() != b.len() {
+        return false;
+    }
+    a.iter().zip(b.iter()).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+#[cfg(test)]
+mod test {
+    use super::{hash_password, Credentials};
+
+    #[test]
+    fn test_1_valida_usuario_y_contrasenia_contra_el_hash() {
+        let content = format!("usuario0:{}\n\nusuario1:{}\n", hash_password("rustx123"), hash_password("otra"));
+        let credentials = Credentials::parse(&content).unwrap();
+
+        assert!(credentials.are_valid("usuario0", "rustx123"));
+        assert!(!credentials.are_valid("usuario0", "otra"));
+        assert!(!credentials.are_valid("inexistente", "rustx123"));
+    }
+
+    #[test]
+    fn test_2_linea_sin_separador_es_error() {
+        assert!(Credentials::parse("usuario0 rustx123").is_err());
+    }
+}
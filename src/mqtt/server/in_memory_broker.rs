@@ -0,0 +1,215 @@
+use std::{
+    collections::HashMap,
+    sync::{
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex,
+    },
+    thread,
+};
+
+use crate::mqtt::messages::{
+    message::Message, publish_message::PublishMessage, subscribe_message::SubscribeMessage,
+};
+
+// Máximo qos que otorga el broker en las suscripciones, igual que el MQTTServer por defecto.
+const MAX_QOS: u8 = 1;
+
+/// Cliente conectado al `InMemoryBroker`: el canal por el que recibe los publish, y sus suscripciones.
+#[derive(Debug)]
+struct InMemoryClient {
+    client_tx: Sender<PublishMessage>,
+    granted_qos_by_topic: HashMap<String, u8>,
+    next_packet_id: u16,
+}
+
+impl InMemoryClient {
+    /// Envía el `msg` al cliente, con el qos otorgado en su suscripción y un packet id propio.
+    fn deliver(&mut self, msg: &PublishMessage, granted_qos: u8) {
+        let packet_id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.checked_add(1).unwrap_or(1);
+        match msg.to_deliver_with(granted_qos, packet_id) {
+            // Si el cliente ya no escucha, se lo removerá al cerrarse su canal
+            Ok(to_deliver) => {
+                let _ = self.client_tx.send(to_deliver);
+            }
+            Err(e) => println!("   ERROR al entregar publish en memoria: {:?}", e),
+        }
+    }
+}
+
+/// Estado compartido del broker: los clientes conectados, y los mensajes recibidos por topic.
+#[derive(Debug, Default)]
+struct BrokerState {
+    clients: HashMap<String, InMemoryClient>,
+    messages_by_topic: HashMap<String, Vec<PublishMessage>>,
+}
+
+/// Broker para tests, que rutea subscribe y publish como el `MQTTServer` pero mediante channels, sin sockets.
+/// Al igual que el server, entrega a un nuevo suscriptor los mensajes ya publicados en el topic, por lo que
+/// el orden en que se procesan los mensajes de distintos clientes no altera lo que recibe cada uno.
+#[derive(Debug, Default)]
+pub struct InMemoryBroker {
+    state: Arc<Mutex<BrokerState>>,
+}
+
+impl InMemoryBroker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Conecta al cliente `client_id`. Devuelve el extremo por el que el cliente envía sus mensajes
+    /// (`SubscribeMessage` o `PublishMessage`), y el extremo por el que recibe los publish de sus suscripciones.
+    /// El cliente se desconecta al soltar el `Sender`.
+    pub fn connect_client(
+        &self,
+        client_id: &str,
+    ) -> (Sender<Box<dyn Message>>, Receiver<PublishMessage>) {
+        let (msg_tx, msg_rx) = mpsc::channel::<Box<dyn Message>>();
+        let (client_tx, client_rx) = mpsc::channel::<PublishMessage>();
+
+        if let Ok(mut state) = self.state.lock() {
+            state.clients.insert(
+                client_id.to_string(),
+                InMemoryClient {
+                    client_tx,
+                    granted_qos_by_topic: HashMap::new(),
+                    next_packet_id: 1,
+                },
+            );
+        }
+
+        let state = Arc::clone(&self.state);
+        let client_id = client_id.to_string();
+        thread::spawn(move || {
+            for msg in msg_rx {
+                if let Ok(mut state) = state.lock() {
+                    state.handle_message_from(&client_id, msg.as_ref());
+                }
+            }
+            if let Ok(mut state) = state.lock() {
+                state.clients.remove(&client_id);
+            }
+        });
+
+        (msg_tx, client_rx)
+    }
+}
+
+impl BrokerState {
+    /// Procesa el mensaje recibido del cliente `client_id`; los de otros tipos se ignoran.
+    fn handle_message_from(&mut self, client_id: &str, msg: &dyn Message) {
+        if let Some(publish_msg) = msg.as_any().downcast_ref::<PublishMessage>() {
+            self.handle_publish(publish_msg);
+        } else if let Some(subscribe_msg) = msg.as_any().downcast_ref::<SubscribeMessage>() {
+            self.handle_subscribe(client_id, subscribe_msg);
+        } else {
+            println!(
+                "   Mensaje de tipo {:?} no soportado por el broker en memoria, se ignora.",
+                msg.get_type()
+            );
+        }
+    }
+
+    /// Almacena el publish y lo entrega a los suscriptores de su topic.
+    fn handle_publish(&mut self, msg: &PublishMessage) {
+        let topic = msg.get_topic();
+        for client in self.clients.values_mut() {
+            if let Some(granted_qos) = client.granted_qos_by_topic.get(&topic).copied() {
+                client.deliver(msg, granted_qos);
+            }
+        }
+        self.messages_by_topic
+            .entry(topic)
+            .or_default()
+            .push(msg.clone());
+    }
+
+    /// Suscribe al cliente a los topics, con el menor qos entre el solicitado y el máximo, y le entrega
+    /// los mensajes ya publicados en ellos.
+    fn handle_subscribe(&mut self, client_id: &str, msg: &SubscribeMessage) {
+        if let Some(client) = self.clients.get_mut(client_id) {
+            for (topic, requested_qos) in msg.get_topic_filters() {
+                let granted_qos = (*requested_qos).min(MAX_QOS);
+                client.granted_qos_by_topic.insert(topic.to_string(), granted_qos);
+                if let Some(topic_messages) = self.messages_by_topic.get(topic) {
+                    for topic_msg in topic_messages {
+                        client.deliver(topic_msg, granted_qos);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::InMemoryBroker;
+    use crate::{
+        apps::{
+            apps_mqtt_topics::AppsMqttTopics,
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+        },
+        mqtt::messages::{
+            publish_flags::PublishFlags, publish_message::PublishMessage,
+            subscribe_message::SubscribeMessage,
+        },
+    };
+
+    fn create_incident_publish(qos: u8) -> (Incident, PublishMessage) {
+        let incident = Incident::new(1, (-34.6037, -58.3816), IncidentSource::Automated);
+        let flags = PublishFlags::new(0, qos, 0).unwrap();
+        let packet_id = if qos == 0 { None } else { Some(9) };
+        let msg = PublishMessage::new(
+            flags,
+            AppsMqttTopics::IncidentTopic.to_str(),
+            packet_id,
+            &incident.to_bytes(),
+        )
+        .unwrap();
+        (incident, msg)
+    }
+
+    #[test]
+    fn test_1_el_incidente_publicado_por_camaras_llega_a_monitoreo() {
+        let broker = InMemoryBroker::new();
+        let (camaras_tx, _camaras_rx) = broker.connect_client("Sistema-Camaras");
+        let (monitoreo_tx, monitoreo_rx) = broker.connect_client("Sistema-Monitoreo");
+
+        let topic = AppsMqttTopics::IncidentTopic.to_str().to_string();
+        monitoreo_tx
+            .send(Box::new(SubscribeMessage::new(1, vec![(topic, 2)]).unwrap()))
+            .unwrap();
+        let (incident, msg) = create_incident_publish(1);
+        camaras_tx.send(Box::new(msg)).unwrap();
+
+        // Se entrega con el qos máximo del broker y un packet id propio del suscriptor
+        let received = monitoreo_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(received.get_payload(), incident.to_bytes());
+        assert_eq!(received.get_qos(), 1);
+        assert_eq!(received.get_packet_id(), Some(1));
+    }
+
+    #[test]
+    fn test_2_un_suscriptor_con_qos_0_recibe_los_mensajes_previos_sin_packet_id() {
+        let broker = InMemoryBroker::new();
+        let (camaras_tx, _camaras_rx) = broker.connect_client("Sistema-Camaras");
+        let (_, msg) = create_incident_publish(1);
+        camaras_tx.send(Box::new(msg)).unwrap();
+
+        let (dron_tx, dron_rx) = broker.connect_client("dron-1");
+        let topic = AppsMqttTopics::IncidentTopic.to_str().to_string();
+        dron_tx
+            .send(Box::new(SubscribeMessage::new(1, vec![(topic, 0)]).unwrap()))
+            .unwrap();
+
+        let received = dron_rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(received.get_qos(), 0);
+        assert_eq!(received.get_packet_id(), None);
+
+        // Al soltar el sender, el cliente se desconecta y se cierra su canal
+        drop(dron_tx);
+        assert!(dron_rx.recv_timeout(Duration::from_secs(2)).is_err());
+    }
+}
@@ -1,13 +1,15 @@
 use rustx::logging::string_logger::StringLogger;
 use rustx::mqtt::server::mqtt_server::MQTTServer;
+use rustx::mqtt::server::server_admin::ServerAdmin;
 use std::env::args;
 use std::io::{Error, ErrorKind};
 
-/// Lee el puerto por la consola, y devuelve la dirección IP y el puerto.
+/// Lee el puerto, y opcionalmente la dirección IP (v4 o v6, ej. `::1`), por la consola, y devuelve la dirección IP y el puerto.
+/// Si no se indica la IP, se utiliza localhost.
 pub fn load_port() -> Result<(String, u16), Error> {
     let argv = args().collect::<Vec<String>>();
-    if argv.len() != 2 {
-        return Err(Error::new(ErrorKind::InvalidInput, "Cantidad de argumentos inválido. Debe ingresar el puerto en el que desea correr el servidor."));
+    if argv.len() != 2 && argv.len() != 3 {
+        return Err(Error::new(ErrorKind::InvalidInput, "Cantidad de argumentos inválido. Debe ingresar el puerto en el que desea correr el servidor, y opcionalmente la IP."));
     }
     let port = match argv[1].parse::<u16>() {
         Ok(port) => port,
@@ -18,9 +20,12 @@ pub fn load_port() -> Result<(String, u16), Error> {
             ))
         }
     };
-    let localhost = "127.0.0.1".to_string();
+    let ip = match argv.get(2) {
+        Some(ip) => ip.to_string(),
+        None => "127.0.0.1".to_string(),
+    };
 
-    Ok((localhost, port))
+    Ok((ip, port))
 }
 
 
@@ -30,19 +35,28 @@ fn main() -> Result<(), Error> {
     // Se crean y configuran ambos extremos del string logger
     let (mut logger, handle_logger) = StringLogger::create_logger(get_formatted_app_id());
 
-    let mqtt_server = MQTTServer::new(logger.clone_ref());
-    mqtt_server.run(ip, port)?;
+    let res = match MQTTServer::new(logger.clone_ref()) {
+        Ok(mqtt_server) => {
+            // Hilo para la consola de administración, que comparte el estado del server
+            let admin = ServerAdmin::new(mqtt_server.clone_ref(), logger.clone_ref());
+            std::thread::spawn(move || admin.run());
+            mqtt_server.run(ip, port)
+        }
+        Err(e) => {
+            println!("Error al crear el servidor: {:?}", e);
+            Err(e)
+        }
+    };
 
     // Se cierra el logger
     logger.stop_logging();
-    drop(mqtt_server);
 
     // Se espera al hijo para el logger
     if handle_logger.join().is_err() {
         println!("Error al esperar al hijo para string logger writer.")
     }
 
-    Ok(())
+    res
 }
 
 fn get_formatted_app_id() -> String {
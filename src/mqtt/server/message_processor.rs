@@ -7,9 +7,11 @@ use rayon::ThreadPool;
 use crate::mqtt::messages::{
         packet_type::PacketType, puback_message::PubAckMessage, publish_message::PublishMessage,
         subscribe_message::SubscribeMessage, subscribe_return_code::SubscribeReturnCode,
+        unsubscribe_message::UnsubscribeMessage,
 };
 
-use std::io::Error;
+use crate::mqtt::mqtt_utils::mqtt_error::MqttError;
+use std::{io::Error, net::SocketAddr};
 
 use super::{
     mqtt_server::MQTTServer,
@@ -30,7 +32,7 @@ impl MessageProcessor {
         MessageProcessor { mqtt_server }
     }
 
-    pub fn handle_packets(&mut self, rx_1: Receiver<Packet>) -> Result<(), Error> {
+    pub fn handle_packets(&mut self, rx_1: Receiver<Packet>) -> Result<(), MqttError> {
 
         // Con threadpool sería:
         match create_thread_pool_with(20) {
@@ -62,10 +64,19 @@ impl MessageProcessor {
         let msg_bytes = packet.get_msg_bytes();
         let client_id = packet.get_username();
         match packet.get_message_type() {
-            PacketType::Publish => self.handle_publish(msg_bytes, client_id),
+            PacketType::Publish => {
+                self.handle_publish(msg_bytes, client_id);
+                // Ya se respondió (o se descartó por mal formado): se libera su lugar
+                if packet.is_publish_with_qos() {
+                    self.mqtt_server.finish_inbound_publish_from(client_id);
+                }
+            }
             PacketType::Subscribe => self.handle_subscribe(msg_bytes, client_id),
-            PacketType::Puback => self.handle_puback(msg_bytes),
-            _ => println!("   ERROR: Tipo de mensaje desconocido\n "),
+            PacketType::Unsubscribe => self.handle_unsubscribe(msg_bytes, client_id),
+            PacketType::Puback => self.handle_puback(msg_bytes, client_id),
+            PacketType::Disconnect => self.handle_disconnect(client_id, packet.get_peer_addr()),
+            PacketType::Pingreq => self.handle_pingreq(client_id),
+            other => println!("   ERROR: {}\n ", MqttError::UnexpectedPacket(other)),
         };
     }
 
@@ -78,13 +89,14 @@ impl MessageProcessor {
                 if let Err(e) = puback_res {
                     println!("   Error en handle_publish: {:?}", e);
                 }
-                if let Err(e) = self.mqtt_server.handle_publish_message(&publish_msg){
+                // Si es un reenvío de un publish ya procesado, solamente se reenvía el puback (arriba).
+                if let Err(e) = self.mqtt_server.handle_publish_message_from(client_id, &publish_msg){
                     // No quiero retornar si falló alguna operación hacia Un user, solamente logguearlo.
                     println!("   Error en handle_publish: {:?}", e);
                 };                
 
             }
-            Err(e) => println!("   Error en handle_publish: {:?}", e),
+            Err(e) => println!("   Error en handle_publish: {}", MqttError::MalformedPacket(e.to_string())),
         }
     }
 
@@ -105,15 +117,50 @@ impl MessageProcessor {
                     println!("   ERROR: {:?}", e);
                 }
             }
-            Err(e) => println!("   ERROR: {:?}", e),
+            Err(e) => println!("   ERROR: {}", MqttError::MalformedPacket(e.to_string())),
+        }
+    }
+
+    /// Quita los topics del suscriptor, y le responde con un UnsubAck.
+    fn handle_unsubscribe(&self, msg_bytes: Vec<u8>, client_id: &str) {
+        match UnsubscribeMessage::from_bytes(msg_bytes) {
+            Ok(msg) => {
+                self.mqtt_server.remove_topics_from_subscriber(client_id, msg.get_topics());
+                if let Err(e) = self.mqtt_server.send_unsuback_to(client_id, msg.get_packet_id()) {
+                    println!("   ERROR: {:?}", e);
+                }
+            }
+            Err(e) => println!("   ERROR: {}", MqttError::MalformedPacket(e.to_string())),
         }
     }
 
-    fn handle_puback(&self, msg_bytes: Vec<u8>) {
+    fn handle_puback(&self, msg_bytes: Vec<u8>, client_id: &str) {
         let puback_msg_res = PubAckMessage::msg_from_bytes(msg_bytes);
         match puback_msg_res {
-            Ok(puback_msg) => println!("Pub ack recibido, packet_id: {:?}", puback_msg.get_packet_id()),
-            Err(e) => println!("   ERROR: {:?}", e),
+            Ok(puback_msg) => {
+                let packet_id = puback_msg.get_packet_id();
+                println!("Pub ack recibido de {:?}, packet_id: {:?}", client_id, packet_id);
+                match self.mqtt_server.handle_puback_from(client_id, packet_id) {
+                    Ok(true) => {}
+                    Ok(false) => println!("   Pub ack no esperado de {:?}, packet_id: {:?}", client_id, packet_id),
+                    Err(e) => println!("   ERROR: {:?}", e),
+                }
+            }
+            Err(e) => println!("   ERROR: {}", MqttError::MalformedPacket(e.to_string())),
+        }
+    }
+
+    /// Desconexión voluntaria: el cliente envió un DisconnectMessage, por lo que
+    /// se lo remueve sin publicar su will message.
+    fn handle_disconnect(&self, client_id: &str, peer_addr: Option<SocketAddr>) {
+        println!("Disconnect recibido, de: {:?}", client_id);
+        self.mqtt_server.handle_clean_disconnect(client_id, peer_addr);
+    }
+
+    /// El cliente indica que sigue conectado, se le responde con un PingResp.
+    fn handle_pingreq(&self, client_id: &str) {
+        if let Err(e) = self.mqtt_server.send_pingresp_to(client_id) {
+            println!("   Error en handle_pingreq: {:?}", e);
         }
     }
 
@@ -121,7 +168,7 @@ impl MessageProcessor {
         &self,
         client_id: &str,
         publish_msg: &PublishMessage,
-    ) -> Result<(), Error> {
+    ) -> Result<(), MqttError> {
         self.mqtt_server.send_puback_to(client_id, publish_msg)?;
 
         Ok(())
@@ -132,7 +179,7 @@ impl MessageProcessor {
         client_id: &str,
         return_codes_res: Result<Vec<SubscribeReturnCode>, Error>,
         packet_id: u16,
-    ) -> Result<(), Error> {
+    ) -> Result<(), MqttError> {
         self.mqtt_server
             .send_suback_to(client_id, &return_codes_res, packet_id)?;
         Ok(())
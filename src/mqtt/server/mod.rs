@@ -1,10 +1,16 @@
 pub mod client_authenticator;
 pub mod client_reader;
+pub mod credentials;
 pub mod disconnect_reason;
 pub mod file_helper;
+pub mod in_memory_broker;
 pub mod incoming_connections;
 pub mod message_processor;
 pub mod mqtt_server;
 pub mod packet;
+pub mod puback_batcher;
+pub mod server_admin;
+pub mod server_health;
+pub mod topic_acl;
 pub mod user;
 pub mod user_state;
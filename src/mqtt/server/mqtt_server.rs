@@ -1,26 +1,45 @@
 use crate::logging::string_logger::StringLogger;
 use crate::mqtt::messages::connect_message::ConnectMessage;
 use crate::mqtt::messages::{
-    disconnect_message::DisconnectMessage, puback_message::PubAckMessage,
-    publish_message::PublishMessage, suback_message::SubAckMessage,
+    disconnect_message::DisconnectMessage, pingresp_message::PingRespMessage,
+    puback_message::PubAckMessage, publish_message::PublishMessage, suback_message::SubAckMessage,
     subscribe_message::SubscribeMessage, subscribe_return_code::SubscribeReturnCode,
+    unsuback_message::Unsuback,
 };
 
+use crate::apps::properties::Properties;
+use crate::mqtt::mqtt_utils::shutdown_token::ShutdownToken;
+use crate::mqtt::mqtt_utils::socket_address::{dual_stack_addr, parse_socket_addr};
 use crate::mqtt::server::{
-    incoming_connections::ClientListener, user::User, user_state::UserState,
+    client_authenticator::AuthenticateClient, incoming_connections::ClientListener,
+    puback_batcher::{load_puback_batcher, PubAckBatcher},
+    server_health::{serve_health_checks, HealthStatus, ServerHealth},
+    topic_acl::TopicAcl, user::{User, UserSummary}, user_state::UserState,
 };
 use crate::mqtt::stream_type::StreamType;
 use std::{
     collections::{hash_map::ValuesMut, HashMap, VecDeque},
     fs::File,
     io::{Error, ErrorKind, Write},
-    net::TcpListener,
+    net::{SocketAddr, TcpListener},
     sync::{Arc, Mutex},
     thread,
+    time::{Duration, Instant},
 };
 
 const TOPIC_MESSAGES_LEN: usize = 50;
+const SERVER_PROPERTIES_FILE: &str = "./message_broker_server_config.properties";
+// Máximo qos que otorga el server en las suscripciones, si no se configura `max_qos`.
+const DEFAULT_MAX_QOS: u8 = 1;
+// Máximo de publish con qos > 0 recibidos de cada cliente y aún sin responder, si no se configura `receive_maximum`.
+const DEFAULT_RECEIVE_MAXIMUM: u16 = 32;
+// Máxima remaining length, en bytes, de un paquete recibido de un cliente, si no se configura `max_packet_size`.
+// Alcanza para los frames de las cámaras.
+const DEFAULT_MAX_PACKET_SIZE: usize = 16 * 1024 * 1024;
+// Tiempo durante el cual se recuerda un (client_id, packet_id) recibido, para detectar reenvíos con dup.
+const RECENT_PUBLISH_IDS_WINDOW: Duration = Duration::from_secs(30);
 type ShareableUsers = Arc<Mutex<HashMap<String, User>>>;
+type RecentPublishIds = Arc<Mutex<HashMap<(String, u16), Instant>>>; // (client_id, packet_id) y cuándo se recibió
 type TopicMessages = VecDeque<PublishMessage>; // Se guardaran todos los mensajes, y se enviaran en caso de reconexión o si un cliente no recibio ciertos mensajes.
 
 fn clean_file(file_path: &str) -> Result<(), Error> {
@@ -34,35 +53,81 @@ pub struct MQTTServer {
     connected_users: ShareableUsers,
     available_packet_id: u16,                                      //
     messages_by_topic: Arc<Mutex<HashMap<String, TopicMessages>>>, // String = topic
+    recent_publish_ids: RecentPublishIds,
+    authenticator: AuthenticateClient,
+    topic_acl: TopicAcl,
+    max_qos: u8,
+    receive_maximum: u16,
+    max_packet_size: usize,
+    health: ServerHealth,
+    puback_batcher: Option<PubAckBatcher>, // si se agrupan los PubAcks a cada cliente, ver `load_puback_batcher`
     logger: StringLogger,
 }
 
 impl MQTTServer {
-    pub fn new(logger: StringLogger) -> Self {
+    /// Crea el server, cargando las credenciales de los usuarios desde su archivo de configuración.
+    pub fn new(logger: StringLogger) -> Result<Self, Error> {
         let file_path = "log.txt";
         if let Err(e) = clean_file(file_path) {
             println!("Error al limpiar el archivo: {:?}", e);
         }
 
-        Self {
+        let authenticator =
+            AuthenticateClient::from_properties_file(SERVER_PROPERTIES_FILE, logger.clone_ref())?;
+        let topic_acl = load_topic_acl(SERVER_PROPERTIES_FILE)?;
+        let max_qos = load_max_qos(SERVER_PROPERTIES_FILE)?;
+        let receive_maximum = load_receive_maximum(SERVER_PROPERTIES_FILE)?;
+        let max_packet_size = load_max_packet_size(SERVER_PROPERTIES_FILE)?;
+        let puback_batcher = load_puback_batcher(SERVER_PROPERTIES_FILE)?;
+
+        Ok(Self {
             connected_users: Arc::new(Mutex::new(HashMap::new())),
             available_packet_id: 0,
             messages_by_topic: Arc::new(Mutex::new(HashMap::new())),
+            recent_publish_ids: Arc::new(Mutex::new(HashMap::new())),
+            authenticator,
+            topic_acl,
+            max_qos,
+            receive_maximum,
+            max_packet_size,
+            health: ServerHealth::new(),
+            puback_batcher,
             logger,
-        }
+        })
     }
 
+    /// Corre el server en `ip`:`port`. Si se configura `health_port`, atiende además allí los health checks
+    /// (ver `serve_health_checks`). Si se agrupan los PubAcks, lanza el hilo que envía los que vencen.
     pub fn run(&self, ip: String, port: u16) -> Result<(), Error> {
 
-        let listener = create_server(ip, port)?;
+        let listener = create_server(ip.clone(), port)?;
+        if let Some(health_port) = load_health_port(SERVER_PROPERTIES_FILE)? {
+            let health_listener = TcpListener::bind(parse_socket_addr(&ip, health_port)?)?;
+            println!("Health check en el puerto {}.", health_port);
+            let self_clone = self.clone_ref();
+            let logger_c = self.logger.clone_ref();
+            thread::spawn(move || serve_health_checks(health_listener, self_clone, logger_c));
+        }
+        if let Some(puback_batcher) = &self.puback_batcher {
+            let self_clone = self.clone_ref();
+            // Como el server, corre hasta que termina el proceso
+            puback_batcher.spawn_flusher(ShutdownToken::new(), move |client_id, acks_bytes| {
+                if let Err(e) = self_clone.write_to_user(client_id, &acks_bytes) {
+                    self_clone.logger.log(format!("ERROR: al enviar PubAcks agrupados a {}: {:?}.", client_id, e));
+                }
+            });
+        }
         let mut incoming_connections = ClientListener::new(self.logger.clone_ref());
         let self_clone = self.clone_ref();
         let logger_c = self.logger.clone_ref();
+        let health = self.health.clone();
         // Hilo para manejar las conexiones entrantes
         let thread_incoming = thread::spawn(move || {
+            health.set_accept_loop_alive(true);
             if let Err(e) = incoming_connections.handle_incoming_connections(listener, self_clone) {
                 logger_c.log(format!("Error en handle_incoming_connections, en run: {:?}.", e));
             }
+            health.set_accept_loop_alive(false);
         });
 
         if let Err(e) = thread_incoming.join(){
@@ -89,27 +154,66 @@ impl MQTTServer {
         topic_messages.push_back(publish_msg);
     }
 
+    /// Devuelve si, al aceptarse el `connect_msg`, se continuará una sesión existente del cliente (conservando
+    /// sus suscripciones), con el mismo criterio que `manage_possible_reconnecting_or_duplicate_user`.
+    /// Se informa al cliente en el flag session present del connack.
+    pub fn has_session_to_resume(&self, connect_msg: &ConnectMessage) -> bool {
+        let client_id = match connect_msg.get_client_id() {
+            Some(client_id) => client_id.as_str(),
+            None => return false,
+        };
+        match self.connected_users.lock() {
+            Ok(connected_users_locked) => match connected_users_locked.get(client_id) {
+                Some(client) => match client.get_state() {
+                    UserState::Active => !connect_msg.is_clean_session(),
+                    UserState::TemporallyDisconnected => true,
+                },
+                None => false,
+            },
+            Err(_) => false,
+        }
+    }
+
     /// Busca al client_id en el hashmap de conectados, si ya existía analiza su estado:
-    /// si ya estaba como activo, es un usuario duplicado por lo que le envía disconnect al stream anterior;
-    /// si estaba como desconectado temporalmente (ie ctrl+C), se está reconectando.
-    /// Devuelve true si era reconexión, false si no era reconexión.
+    /// si ya estaba como activo, es un usuario duplicado por lo que le envía disconnect al stream anterior
+    /// y lo cierra (takeover); si estaba como desconectado temporalmente (ie ctrl+C), se está reconectando.
+    /// En el takeover, primero se cierra la conexión anterior y recién luego se acepta la nueva: si el connect
+    /// pide clean_session se descarta la sesión anterior, y si no, la nueva conexión hereda sus suscripciones.
+    /// Devuelve true si se continúa la sesión existente, false si debe crearse un nuevo user.
     pub fn manage_possible_reconnecting_or_duplicate_user(
         &self,
-        client_id: &str,
+        connect_msg: &ConnectMessage,
         new_stream_of_reconnected_user: &StreamType,
     ) -> Result<bool, Error> {
+        let client_id = match connect_msg.get_client_id() {
+            Some(client_id) => client_id.as_str(),
+            None => return Ok(false),
+        };
         if let Ok(mut connected_users_locked) = self.connected_users.lock() {
             if let Some(client) = connected_users_locked.get_mut(client_id) {
                 match client.get_state() {
                     UserState::Active => {
                         // El cliente ya se encontraba activo ==> Es duplicado.
                         self.handle_duplicate_user(client)?;
-                        let _ = connected_users_locked.remove(client_id);
                         println!("Se conecta usuario duplicado: {:?}, desconectando el anterior.", client_id);
+                        self.logger.log(format!(
+                            "Takeover de {:?}: se desconectó la conexión anterior.",
+                            client_id
+                        ));
+                        if connect_msg.is_clean_session() {
+                            let _ = connected_users_locked.remove(client_id);
+                        } else {
+                            // Se transfieren las suscripciones a la nueva conexión
+                            client.update_stream_with(new_stream_of_reconnected_user.try_clone()?);
+                            client.set_will_message(connect_msg.get_will_to_publish());
+                            client.set_keep_alive(connect_msg.get_keep_alive());
+                            return Ok(true);
+                        }
                     }
                     UserState::TemporallyDisconnected => {
                         // El cliente se encontraba temp desconectado ==> Se está reconectando.
                         self.handle_reconnecting_user(client, new_stream_of_reconnected_user)?;
+                        client.set_keep_alive(connect_msg.get_keep_alive());
                         println!("Se reconecta el usuario: {:?}, emviándole mensajes.", client_id);
                         // Único caso en que devuelve true.
                         return Ok(true);
@@ -186,7 +290,8 @@ impl MQTTServer {
 
         let username_c = username.to_string();
         //[] Aux: Nos guardamos el stream, volver a ver esto.
-        let user = User::new(stream.try_clone()?, username_c.to_owned(), will_msg_info); //[]
+        let mut user = User::new(stream.try_clone()?, username_c.to_owned(), will_msg_info); //[]
+        user.set_keep_alive(connect_msg.get_keep_alive());
         if let Ok(mut users) = self.connected_users.lock() {
             println!("Username agregado a la lista del server: {:?}", username);
             users.insert(username_c, user); //inserta el usuario en el hashmap
@@ -200,10 +305,28 @@ impl MQTTServer {
             connected_users: self.connected_users.clone(),
             available_packet_id: self.available_packet_id,
             messages_by_topic: self.messages_by_topic.clone(),
+            recent_publish_ids: self.recent_publish_ids.clone(),
+            authenticator: self.authenticator.clone_ref(),
+            topic_acl: self.topic_acl.clone(),
+            max_qos: self.max_qos,
+            receive_maximum: self.receive_maximum,
+            max_packet_size: self.max_packet_size,
+            health: self.health.clone(),
+            puback_batcher: self.puback_batcher.clone(),
             logger: self.logger.clone_ref(),
         }
     }
 
+    /// Devuelve la máxima remaining length que se acepta en un paquete recibido de un cliente.
+    pub fn get_max_packet_size(&self) -> usize {
+        self.max_packet_size
+    }
+
+    /// Devuelve el encargado de autenticar a los clientes que se conectan.
+    pub fn get_authenticator(&self) -> AuthenticateClient {
+        self.authenticator.clone_ref()
+    }
+
     /// Envía el will_message del user que se está desconectando, si tenía uno.
     pub fn publish_users_will_message(&self, username: &str) -> Result<(), Error> {
         let packet_id = 1000; // <-- aux: rever esto []: generate_packet_id requiere self mut, pero esto es multihilo, no tiene mucho sentido. Quizás un arc mutex u16, volver.
@@ -231,7 +354,73 @@ impl MQTTServer {
         Ok(())
     }
 
-    /// Agrega los topics al suscriptor correspondiente. y devuelve los códigos de retorno(qos)
+    /// Procesa el PublishMessage recibido del cliente `client_id`, salvo que se trate de un reenvío (flag dup)
+    /// de un mensaje que ya fue procesado recientemente, en cuyo caso se lo ignora para no distribuirlo dos veces.
+    pub fn handle_publish_message_from(
+        &self,
+        client_id: &str,
+        msg: &PublishMessage,
+    ) -> Result<(), Error> {
+        if !self.topic_acl.can_publish(client_id, &msg.get_topic()) {
+            println!(
+                "   Publish de {:?} al topic {:?} denegado por acl, se descarta.",
+                client_id,
+                msg.get_topic()
+            );
+            self.logger.log(format!(
+                "Publish de {:?} al topic {:?} denegado por acl, se descarta.",
+                client_id,
+                msg.get_topic()
+            ));
+            return Ok(());
+        }
+        if self.is_already_processed_publish(client_id, msg)? {
+            println!(
+                "   Publish duplicado de {:?} con packet_id: {:?}, no se vuelve a procesar.",
+                client_id,
+                msg.get_packet_id()
+            );
+            self.logger.log(format!(
+                "Publish duplicado de {:?} con packet_id: {:?}, no se vuelve a procesar.",
+                client_id,
+                msg.get_packet_id()
+            ));
+            return Ok(());
+        }
+        self.handle_publish_message(msg)
+    }
+
+    /// Devuelve si el `msg` es un reenvío (flag dup) de un publish con el mismo packet_id ya recibido
+    /// del mismo `client_id` dentro de la ventana de tiempo. Si no lo es, lo registra como recibido.
+    /// Los mensajes sin packet_id (qos 0) nunca se consideran duplicados.
+    fn is_already_processed_publish(
+        &self,
+        client_id: &str,
+        msg: &PublishMessage,
+    ) -> Result<bool, Error> {
+        let packet_id = match msg.get_packet_id() {
+            Some(packet_id) => packet_id,
+            None => return Ok(false),
+        };
+
+        if let Ok(mut recent_ids) = self.recent_publish_ids.lock() {
+            // Se olvidan los ids que ya salieron de la ventana de tiempo
+            recent_ids.retain(|_, received_at| received_at.elapsed() < RECENT_PUBLISH_IDS_WINDOW);
+
+            let key = (client_id.to_string(), packet_id);
+            if msg.is_dup() && recent_ids.contains_key(&key) {
+                return Ok(true);
+            }
+            recent_ids.insert(key, Instant::now());
+            return Ok(false);
+        }
+        Err(Error::other(
+            "Error: no se pudo tomar lock a recent_publish_ids.",
+        ))
+    }
+
+    /// Agrega los topics al suscriptor correspondiente, y devuelve los códigos de retorno: el qos otorgado
+    /// para cada topic es el menor entre el solicitado y el máximo del server.
     pub fn add_topics_to_subscriber(
         &self,
         username: &str,
@@ -242,9 +431,18 @@ impl MQTTServer {
         // Agrega los topics a los que se suscribió el usuario
         if let Ok(mut connected_users) = self.connected_users.lock() {
             if let Some(user) = connected_users.get_mut(username) {
-                for (topic, _qos) in msg.get_topic_filters() {
-                    user.add_topic(topic.to_string());
-                    return_codes.push(SubscribeReturnCode::QoS1);
+                for (topic, requested_qos) in msg.get_topic_filters() {
+                    if !self.topic_acl.can_subscribe(username, topic) {
+                        return_codes.push(SubscribeReturnCode::Failure);
+                        self.logger.log(format!(
+                            "Subscribe de {:?} al topic {:?} denegado por acl.",
+                            username, topic
+                        ));
+                        continue;
+                    }
+                    let granted_qos = (*requested_qos).min(self.max_qos);
+                    user.add_topic(topic.to_string(), granted_qos);
+                    return_codes.push(SubscribeReturnCode::from_granted_qos(granted_qos));
                     println!(
                         "   Se agregó el topic {:?} al suscriptor {:?}",
                         topic, username
@@ -280,6 +478,32 @@ impl MQTTServer {
         Ok(())
     }
 
+    /// Quita los `topics` de los topics a los que está suscripto el user `username`.
+    pub fn remove_topics_from_subscriber(&self, username: &str, topics: &[String]) {
+        if let Ok(mut connected_users) = self.connected_users.lock() {
+            if let Some(user) = connected_users.get_mut(username) {
+                for topic in topics {
+                    if user.remove_topic(topic) {
+                        println!("   Se quitó el topic {:?} del suscriptor {:?}", topic, username);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Envía un mensaje de tipo UnsubAck al cliente.
+    pub fn send_unsuback_to(&self, client_id: &str, packet_id: u16) -> Result<(), Error> {
+        let [packet_id_msb, packet_id_lsb] = packet_id.to_be_bytes();
+        let ack = Unsuback::new(packet_id_msb, packet_id_lsb);
+        if let Ok(mut connected_users_locked) = self.get_connected_users().lock() {
+            if let Some(user) = connected_users_locked.get_mut(client_id) {
+                user.write_message(&ack.to_bytes())?;
+            }
+        }
+        println!("   tipo unsubscribe: Enviando el ack: {:?}", ack);
+        Ok(())
+    }
+
     /// Almacena el `PublishMessage` en la estructura del server para su topic, y lo envía a sus suscriptores.
     fn store_and_distribute_publish_msg(&self, msg: &PublishMessage) -> Result<(), Error> {
         // Vamos a recorrer todos los usuarios
@@ -453,6 +677,35 @@ impl MQTTServer {
         }
     }
 
+    /// Maneja la desconexión voluntaria del usuario `username` (se recibió su DisconnectMessage por la conexión
+    /// con el cliente en `peer_addr`): se lo remueve sin publicar su will message, ya que la aplicación no se cayó.
+    /// Si esa conexión ya había sido reemplazada por otra con el mismo client_id (takeover), no se hace nada.
+    pub fn handle_clean_disconnect(&self, username: &str, peer_addr: Option<SocketAddr>) {
+        if !self.is_current_connection_of(username, peer_addr) {
+            self.logger.log(format!(
+                "Disconnect recibido por una conexión reemplazada de {:?}, se ignora.",
+                username
+            ));
+            return;
+        }
+        self.logger.log(format!(
+            "Desconexión voluntaria de {:?}, no se publica su will message.",
+            username
+        ));
+        self.remove_user(username);
+    }
+
+    /// Devuelve si la conexión con el cliente en `peer_addr` es la conexión actual del usuario `username`.
+    /// Permite ignorar el cierre de una conexión que ya fue reemplazada por otra con el mismo client_id.
+    pub fn is_current_connection_of(&self, username: &str, peer_addr: Option<SocketAddr>) -> bool {
+        if let Ok(users) = self.connected_users.lock() {
+            if let Some(user) = users.get(username) {
+                return peer_addr.is_some() && user.get_peer_addr() == peer_addr;
+            }
+        }
+        false
+    }
+
     /// Cambia el estado del usuario del server con username `username` a TemporallyDisconnected,
     /// para que no se le envíen mensajes si se encuentra en dicho estado y de esa forma evitar errores en writes.
     pub fn set_user_as_temporally_disconnected(&self, username: &str) -> Result<(), Error> {
@@ -468,18 +721,136 @@ impl MQTTServer {
         Ok(())
     }
 
-    // Aux: esta función está comentada solo temporalmente mientras probamos algo, dsp se volverá a usar [].
-    /// Envía un mensaje de tipo PubAck al cliente.
-    pub fn send_puback_to(&self, client_id: &str, msg: &PublishMessage) -> Result<(), Error> {
-        let option_packet_id = msg.get_packet_id();
-        let packet_id = option_packet_id.unwrap_or(0);
+    /// Devuelve el tiempo máximo sin recibir mensajes del usuario `username` antes de considerarlo desconectado,
+    /// según el keep alive de su sesión, o None si no tiene límite.
+    pub fn get_keep_alive_timeout_of(&self, username: &str) -> Option<Duration> {
+        if let Ok(users) = self.connected_users.lock() {
+            if let Some(user) = users.get(username) {
+                return user.get_keep_alive_timeout();
+            }
+        }
+        None
+    }
 
-        let ack = PubAckMessage::new(packet_id, 0);
-        let ack_msg_bytes = ack.to_bytes();
+    /// Registra un publish con qos > 0 recibido del usuario `username`, si tiene menos de `receive_maximum`
+    /// sin responder. Devuelve si se lo registró; si no, el lector de su stream debe esperar antes de seguir
+    /// leyendo, con lo que el cliente deja de poder enviar más. Si el usuario no existe, no se lo limita.
+    pub fn try_start_inbound_publish_from(&self, username: &str) -> bool {
+        if let Ok(mut users) = self.connected_users.lock() {
+            if let Some(user) = users.get_mut(username) {
+                return user.try_start_inbound_publish(self.receive_maximum);
+            }
+        }
+        true
+    }
+
+    /// Registra que se respondió un publish con qos > 0 del usuario `username`, liberando su lugar.
+    pub fn finish_inbound_publish_from(&self, username: &str) {
+        if let Ok(mut users) = self.connected_users.lock() {
+            if let Some(user) = users.get_mut(username) {
+                user.finish_inbound_publish();
+            }
+        }
+    }
+
+    /// Suma `amount` a los bytes recibidos desde el usuario `username`.
+    pub fn add_bytes_received_from(&self, username: &str, amount: usize) {
+        if let Ok(mut users) = self.connected_users.lock() {
+            if let Some(user) = users.get_mut(username) {
+                user.add_bytes_received(amount);
+            }
+        }
+    }
+
+    /// Devuelve el resumen de cada usuario del server, ordenados por username.
+    pub fn get_users_summary(&self) -> Vec<UserSummary> {
+        let mut summaries: Vec<UserSummary> = match self.connected_users.lock() {
+            Ok(users) => users.values().map(|user| user.get_summary()).collect(),
+            Err(_) => vec![],
+        };
+        summaries.sort_by(|a, b| a.username.cmp(&b.username));
+        summaries
+    }
+
+    /// Devuelve el estado de salud del server. Solamente toma el lock de los usuarios para contar los conectados.
+    pub fn get_health_status(&self) -> HealthStatus {
+        let connected_clients = match self.connected_users.lock() {
+            Ok(users) => users
+                .values()
+                .filter(|user| *user.get_state() != UserState::TemporallyDisconnected)
+                .count(),
+            Err(_) => 0,
+        };
+        self.health.status(connected_clients)
+    }
+
+    /// Devuelve los topics a los que está suscripto el usuario `username`, o None si no existe.
+    pub fn get_topics_of(&self, username: &str) -> Option<Vec<String>> {
+        if let Ok(users) = self.connected_users.lock() {
+            return users.get(username).map(|user| user.get_topics().to_vec());
+        }
+        None
+    }
+
+    /// Desconecta forzosamente al usuario `username` (ej. desde la consola del server): publica su will message
+    /// si tenía uno, cierra su conexión, y lo remueve junto con sus suscripciones. Devuelve si el usuario existía.
+    pub fn kick_user(&self, username: &str) -> Result<bool, Error> {
+        let exists = match self.connected_users.lock() {
+            Ok(users) => users.contains_key(username),
+            Err(_) => false,
+        };
+        if !exists {
+            return Ok(false);
+        }
+
+        // Se publica su will antes de removerlo, ya que se obtiene del user
+        self.publish_users_will_message(username)?;
+        if let Ok(mut users) = self.connected_users.lock() {
+            if let Some(mut user) = users.remove(username) {
+                user.shutdown();
+            }
+        }
+        self.logger.log(format!(
+            "Usuario {:?} desconectado desde la consola del server.",
+            username
+        ));
+        Ok(true)
+    }
+
+    /// Responde con un PingResp al PingReq del cliente.
+    pub fn send_pingresp_to(&self, client_id: &str) -> Result<(), Error> {
         if let Ok(mut connected_users_locked) = self.get_connected_users().lock() {
             if let Some(user) = connected_users_locked.get_mut(client_id) {
-                user.write_message(&ack_msg_bytes)?;
+                user.write_message(&PingRespMessage::new().to_bytes())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Registra el puback del cliente `client_id` para un publish que se le entregó. Devuelve si se lo estaba esperando.
+    pub fn handle_puback_from(&self, client_id: &str, packet_id: u16) -> Result<bool, Error> {
+        if let Ok(mut connected_users) = self.connected_users.lock() {
+            if let Some(user) = connected_users.get_mut(client_id) {
+                return Ok(user.acknowledge(packet_id));
+            }
+            return Ok(false);
+        }
+        Err(Error::other("Error: no se pudo tomar lock a users para registrar un PubAck."))
+    }
+
+    // Aux: esta función está comentada solo temporalmente mientras probamos algo, dsp se volverá a usar [].
+    /// Envía un mensaje de tipo PubAck al cliente, para el publish `msg`. Si se agrupan los PubAcks, lo agrega a los pendientes del
+    /// cliente, y los envía juntos si con él se completa el grupo (si no, los envía el hilo de `run` al vencer).
+    pub fn send_puback_to(&self, client_id: &str, msg: &PublishMessage) -> Result<(), Error> {
+        let ack = PubAckMessage::for_publish(msg);
+        let ack_msg_bytes = ack.to_bytes();
+        match &self.puback_batcher {
+            Some(puback_batcher) => {
+                if let Some(acks_bytes) = puback_batcher.add(client_id, &ack_msg_bytes) {
+                    self.write_to_user(client_id, &acks_bytes)?;
+                }
             }
+            None => self.write_to_user(client_id, &ack_msg_bytes)?,
         }
         println!(
             "   tipo publish: Enviado el ack para packet_id: {:?}",
@@ -488,6 +859,16 @@ impl MQTTServer {
         Ok(())
     }
 
+    /// Escribe los `msg_bytes` al cliente `client_id`, si está conectado.
+    fn write_to_user(&self, client_id: &str, msg_bytes: &[u8]) -> Result<(), Error> {
+        if let Ok(mut connected_users_locked) = self.get_connected_users().lock() {
+            if let Some(user) = connected_users_locked.get_mut(client_id) {
+                user.write_message(msg_bytes)?;
+            }
+        }
+        Ok(())
+    }
+
     /// Recorre la estructura de mensajes para el topic al que el suscriptor `username` se está suscribiendo con el `msg`,
     /// y le envía todos los mensajes que se publicaron a dicho topic previo a la suscripción.
     pub fn send_preexisting_msgs_to_new_subscriber(
@@ -528,9 +909,94 @@ impl MQTTServer {
 }
 
 /// Crea un servidor en la dirección ip y puerto especificados.
+/// Carga la acl de topics del archivo indicado por la propiedad `acl_file`, si no está configurada se permite todo.
+fn load_topic_acl(properties_file: &str) -> Result<TopicAcl, Error> {
+    let properties = Properties::new(properties_file)?;
+    match properties.get("acl_file") {
+        Some(acl_file) => TopicAcl::from_file(acl_file),
+        None => Ok(TopicAcl::allow_all()),
+    }
+}
+
+/// Lee el máximo qos a otorgar en las suscripciones de la propiedad `max_qos`. Si no está configurada, es 1.
+fn load_max_qos(properties_file: &str) -> Result<u8, Error> {
+    let properties = Properties::new(properties_file)?;
+    match properties.get("max_qos").map(|qos| qos.parse::<u8>()) {
+        Some(Ok(qos)) if qos <= 2 => Ok(qos),
+        Some(_) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Propiedad 'max_qos' inválida: debe ser 0, 1 o 2.",
+        )),
+        None => Ok(DEFAULT_MAX_QOS),
+    }
+}
+
+/// Lee el máximo de publish con qos > 0 sin responder por cliente, de la propiedad `receive_maximum`.
+/// Si no está configurada, es `DEFAULT_RECEIVE_MAXIMUM`.
+fn load_receive_maximum(properties_file: &str) -> Result<u16, Error> {
+    let properties = Properties::new(properties_file)?;
+    match properties.get("receive_maximum").map(|max| max.parse::<u16>()) {
+        Some(Ok(max)) if max > 0 => Ok(max),
+        Some(_) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Propiedad 'receive_maximum' inválida: debe ser un número mayor a 0.",
+        )),
+        None => Ok(DEFAULT_RECEIVE_MAXIMUM),
+    }
+}
+
+/// Lee la máxima remaining length de un paquete recibido de un cliente, de la propiedad `max_packet_size`.
+/// Si no está configurada, es `DEFAULT_MAX_PACKET_SIZE`.
+fn load_max_packet_size(properties_file: &str) -> Result<usize, Error> {
+    let properties = Properties::new(properties_file)?;
+    match properties.get("max_packet_size").map(|max| max.parse::<usize>()) {
+        Some(Ok(max)) if max > 0 => Ok(max),
+        Some(_) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Propiedad 'max_packet_size' inválida: debe ser un número mayor a 0.",
+        )),
+        None => Ok(DEFAULT_MAX_PACKET_SIZE),
+    }
+}
+
+/// Lee de la propiedad `dual_stack` si el server debe escuchar en todas las interfaces IPv6 (y, donde el
+/// sistema operativo lo permite, también IPv4), en lugar de en la ip indicada. Si no está configurada, es false.
+fn load_dual_stack(properties_file: &str) -> Result<bool, Error> {
+    let properties = Properties::new(properties_file)?;
+    match properties.get("dual_stack").map(|dual_stack| dual_stack.parse::<bool>()) {
+        Some(Ok(dual_stack)) => Ok(dual_stack),
+        Some(Err(_)) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Propiedad 'dual_stack' inválida: debe ser true o false.",
+        )),
+        None => Ok(false),
+    }
+}
+
+/// Lee de la propiedad `health_port` el puerto en el que atender los health checks. Si no está configurada,
+/// no se los atiende.
+fn load_health_port(properties_file: &str) -> Result<Option<u16>, Error> {
+    let properties = Properties::new(properties_file)?;
+    match properties.get("health_port").map(|port| port.parse::<u16>()) {
+        Some(Ok(port)) => Ok(Some(port)),
+        Some(Err(_)) => Err(Error::new(
+            ErrorKind::InvalidInput,
+            "Propiedad 'health_port' inválida: debe ser un puerto.",
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Enlaza el puerto en la ip indicada, que puede ser IPv4 o IPv6 (ej. `::1` o `[::1]`),
+/// o en `[::]` si está configurado `dual_stack`.
 fn create_server(ip: String, port: u16) -> Result<TcpListener, Error> {
-    let listener =
-        TcpListener::bind(format!("{}:{}", ip, port)).expect("Error al enlazar el puerto");
+    let addr = if load_dual_stack(SERVER_PROPERTIES_FILE)? {
+        dual_stack_addr(port)
+    } else {
+        parse_socket_addr(&ip, port)?
+    };
+    let listener = TcpListener::bind(addr)?;
+    println!("Server escuchando en {}.", addr);
     Ok(listener)
 }
 
@@ -575,7 +1041,9 @@ fn send_unreceived_messages_to_user(
     for _ in 0..diff {
         let next_message_index = user.get_last_id_by_topic(topic);
         if let Some(msg) = topic_messages.get(next_message_index as usize) {
-            user.write_message(&msg.to_bytes())?;
+            // Se entrega con el qos otorgado al suscribirse, si es menor al del mensaje, y un packet id propio del user
+            let msg_to_deliver = user.prepare_delivery_of(msg, topic)?;
+            user.write_message(&msg_to_deliver.to_bytes())?;
             user.update_last_id_by_topic(topic, next_message_index + 1);
         } else {
             println!("ERROR NO SE ENCUENTRA EL TOPIC_MSGS.GET(TOPIC) A ENVIAR!!!");
@@ -583,3 +1051,539 @@ fn send_unreceived_messages_to_user(
     }
     Ok(())
 }
+
+#[cfg(test)]
+mod test {
+    use std::{
+        collections::HashMap,
+        io::{Read, Write},
+        net::{TcpListener, TcpStream},
+        sync::{mpsc, Arc, Mutex},
+        thread,
+        time::Duration,
+    };
+
+    use super::{MQTTServer, DEFAULT_MAX_PACKET_SIZE, DEFAULT_MAX_QOS, DEFAULT_RECEIVE_MAXIMUM};
+    use crate::mqtt::server::{
+        client_authenticator::AuthenticateClient, client_reader::ClientReader, credentials::Credentials,
+        disconnect_reason::DisconnectReason, server_health::ServerHealth, topic_acl::TopicAcl,
+    };
+    use crate::{
+        apps::{
+            apps_mqtt_topics::AppsMqttTopics,
+            incident_data::{incident::Incident, incident_source::IncidentSource},
+        },
+        logging::string_logger::StringLogger,
+        mqtt::{
+            client::{
+                ack_message::ACKMessage, mqtt_client_retransmitter::Retransmitter,
+                mqtt_client_stats::MQTTClientStats,
+            },
+            messages::{
+                connect_message::ConnectMessage, connect_message_builder::ConnectMessageBuilder,
+                puback_message::PubAckMessage, publish_flags::PublishFlags, publish_message::PublishMessage,
+                subscribe_message::SubscribeMessage, subscribe_return_code::SubscribeReturnCode,
+            },
+            mqtt_utils::will_message_utils::will_message::WillMessageData,
+            server::user::User,
+        },
+    };
+
+    // Crea el server sin pasar por `new`, para no limpiar el archivo de log en los tests.
+    fn create_server_for_testing() -> MQTTServer {
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let logger = StringLogger::new(logger_tx);
+        MQTTServer {
+            connected_users: Arc::new(Mutex::new(HashMap::new())),
+            available_packet_id: 0,
+            messages_by_topic: Arc::new(Mutex::new(HashMap::new())),
+            recent_publish_ids: Arc::new(Mutex::new(HashMap::new())),
+            authenticator: AuthenticateClient::new(
+                Credentials::parse("").unwrap(),
+                true,
+                logger.clone_ref(),
+            ),
+            topic_acl: TopicAcl::allow_all(),
+            max_qos: DEFAULT_MAX_QOS,
+            receive_maximum: DEFAULT_RECEIVE_MAXIMUM,
+            max_packet_size: DEFAULT_MAX_PACKET_SIZE,
+            health: ServerHealth::new(),
+            puback_batcher: None,
+            logger,
+        }
+    }
+
+    fn create_incident_publish(dup: u8, packet_id: u16) -> PublishMessage {
+        let incident = Incident::new(1, (-34.6037, -58.3816), IncidentSource::Automated);
+        let flags = PublishFlags::new(dup, 1, 0).unwrap();
+        PublishMessage::new(
+            flags,
+            AppsMqttTopics::IncidentTopic.to_str(),
+            Some(packet_id),
+            &incident.to_bytes(),
+        )
+        .unwrap()
+    }
+
+    // Agrega al server un user con will message en el topic desc, con un stream local.
+    fn add_user_with_will(server: &MQTTServer, username: &str) -> TcpListener {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let will = WillMessageData::new(
+            username.to_string(),
+            AppsMqttTopics::DescTopic.to_str().to_string(),
+            1,
+            1,
+        );
+        let user = User::new(stream, username.to_string(), Some(will));
+        server
+            .connected_users
+            .lock()
+            .unwrap()
+            .insert(username.to_string(), user);
+        listener
+    }
+
+    fn amount_of_messages_for(server: &MQTTServer, topic: &str) -> usize {
+        let msgs_by_topic = server.messages_by_topic.lock().unwrap();
+        msgs_by_topic.get(topic).map(|msgs| msgs.len()).unwrap_or(0)
+    }
+
+    #[test]
+    fn test_1_publish_reenviado_con_dup_no_se_propaga_dos_veces() {
+        let server = create_server_for_testing();
+        let client_id = "Sistema-Camaras";
+        let topic = AppsMqttTopics::IncidentTopic.to_str();
+
+        // El cliente envía el incidente, y al no recibir el puback lo reenvía con dup y el mismo packet_id
+        let original = create_incident_publish(0, 7);
+        let resent = create_incident_publish(1, 7);
+
+        server.handle_publish_message_from(client_id, &original).unwrap();
+        server.handle_publish_message_from(client_id, &resent).unwrap();
+
+        // Solamente se propagó un incidente
+        assert_eq!(amount_of_messages_for(&server, topic), 1);
+    }
+
+    #[test]
+    fn test_2_publish_con_dup_de_otro_cliente_o_packet_id_si_se_propaga() {
+        let server = create_server_for_testing();
+        let topic = AppsMqttTopics::IncidentTopic.to_str();
+
+        server
+            .handle_publish_message_from("Sistema-Camaras", &create_incident_publish(0, 7))
+            .unwrap();
+        // Mismo packet_id pero otro cliente
+        server
+            .handle_publish_message_from("Sistema-Monitoreo", &create_incident_publish(1, 7))
+            .unwrap();
+        // Mismo cliente pero otro packet_id
+        server
+            .handle_publish_message_from("Sistema-Camaras", &create_incident_publish(1, 8))
+            .unwrap();
+
+        assert_eq!(amount_of_messages_for(&server, topic), 3);
+    }
+
+    #[test]
+    fn test_3_desconexion_involuntaria_publica_el_will_message() {
+        let server = create_server_for_testing();
+        let _listener = add_user_with_will(&server, "Sistema-Camaras");
+
+        // Se dejó de recibir por el stream (ej se cayó la aplicación)
+        server
+            .set_user_as_temporally_disconnected("Sistema-Camaras")
+            .unwrap();
+        server.publish_users_will_message("Sistema-Camaras").unwrap();
+
+        assert_eq!(
+            amount_of_messages_for(&server, AppsMqttTopics::DescTopic.to_str()),
+            1
+        );
+    }
+
+    #[test]
+    fn test_4_desconexion_voluntaria_no_publica_el_will_message() {
+        let server = create_server_for_testing();
+        let _listener = add_user_with_will(&server, "Sistema-Camaras");
+        let peer_addr = server.connected_users.lock().unwrap()["Sistema-Camaras"].get_peer_addr();
+
+        // Se recibió el DisconnectMessage
+        server.handle_clean_disconnect("Sistema-Camaras", peer_addr);
+        // Si luego además se detectara el cierre del stream, ya no hay will para publicar
+        server.publish_users_will_message("Sistema-Camaras").unwrap();
+
+        assert_eq!(
+            amount_of_messages_for(&server, AppsMqttTopics::DescTopic.to_str()),
+            0
+        );
+        assert!(server.get_connected_users().lock().unwrap().is_empty());
+    }
+
+    // Crea el connect con el que se conecta nuevamente un client_id, y el stream de la nueva conexión.
+    fn create_takeover_connection(
+        client_id: &str,
+        clean_session: bool,
+    ) -> (ConnectMessage, TcpStream, TcpListener) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let mut connect_msg = ConnectMessage::new(client_id.to_string(), None, None, None, None, 1);
+        connect_msg.set_clean_session(clean_session);
+        (connect_msg, stream, listener)
+    }
+
+    #[test]
+    fn test_5_takeover_sin_clean_session_conserva_suscripciones_y_reemplaza_la_conexion() {
+        let server = create_server_for_testing();
+        let _old_listener = add_user_with_will(&server, "Sistema-Camaras");
+        let old_addr = {
+            let mut users = server.connected_users.lock().unwrap();
+            let user = users.get_mut("Sistema-Camaras").unwrap();
+            user.add_topic(AppsMqttTopics::IncidentTopic.to_str().to_string(), 1);
+            user.get_peer_addr()
+        };
+
+        // Orden del takeover: se desconecta la conexión anterior y luego se acepta la nueva
+        let (connect_msg, new_stream, _new_listener) =
+            create_takeover_connection("Sistema-Camaras", false);
+        let continues_session = server
+            .manage_possible_reconnecting_or_duplicate_user(&connect_msg, &new_stream)
+            .unwrap();
+
+        assert!(continues_session);
+        let users = server.connected_users.lock().unwrap();
+        let user = users.get("Sistema-Camaras").unwrap();
+        assert_eq!(user.get_topics(), &vec![AppsMqttTopics::IncidentTopic.to_str().to_string()]);
+        assert_eq!(user.get_peer_addr(), new_stream.peer_addr().ok());
+        drop(users);
+        // El cierre posterior de la conexión anterior ya no afecta al user
+        assert!(!server.is_current_connection_of("Sistema-Camaras", old_addr));
+    }
+
+    #[test]
+    fn test_6_takeover_con_clean_session_descarta_la_sesion_anterior() {
+        let server = create_server_for_testing();
+        let _old_listener = add_user_with_will(&server, "Sistema-Camaras");
+
+        let (connect_msg, new_stream, _new_listener) =
+            create_takeover_connection("Sistema-Camaras", true);
+        let continues_session = server
+            .manage_possible_reconnecting_or_duplicate_user(&connect_msg, &new_stream)
+            .unwrap();
+
+        // Se debe crear un user nuevo para la nueva conexión
+        assert!(!continues_session);
+        assert!(server.get_connected_users().lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_7_el_server_guarda_el_keep_alive_de_cada_sesion() {
+        let server = create_server_for_testing();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+        let connect_msg = ConnectMessageBuilder::new("dron-1".to_string())
+            .keep_alive(10)
+            .build()
+            .unwrap();
+        server.add_new_user(&stream, "dron-1", &connect_msg).unwrap();
+        let anonymous_msg = ConnectMessageBuilder::new("dron-2".to_string()).build().unwrap();
+        server.add_new_user(&stream, "dron-2", &anonymous_msg).unwrap();
+
+        // Se lo desconecta luego de una vez y media el keep alive; con keep alive 0, nunca
+        assert_eq!(
+            server.get_keep_alive_timeout_of("dron-1"),
+            Some(Duration::from_secs(15))
+        );
+        assert_eq!(server.get_keep_alive_timeout_of("dron-2"), None);
+    }
+
+    #[test]
+    fn test_8_kick_publica_el_will_y_remueve_al_usuario_con_sus_suscripciones() {
+        let server = create_server_for_testing();
+        let _listener = add_user_with_will(&server, "Sistema-Camaras");
+        server.add_bytes_received_from("Sistema-Camaras", 10);
+
+        let summaries = server.get_users_summary();
+        assert_eq!(summaries.len(), 1);
+        assert_eq!(summaries[0].username, "Sistema-Camaras");
+        assert_eq!(summaries[0].bytes_received, 10);
+        assert!(summaries[0].is_active);
+
+        assert!(server.kick_user("Sistema-Camaras").unwrap());
+        assert_eq!(
+            amount_of_messages_for(&server, AppsMqttTopics::DescTopic.to_str()),
+            1
+        );
+        assert_eq!(server.get_topics_of("Sistema-Camaras"), None);
+        assert!(server.get_users_summary().is_empty());
+        // Un cliente que no existe no se puede desconectar
+        assert!(!server.kick_user("Sistema-Camaras").unwrap());
+    }
+
+    #[test]
+    fn test_9_el_suback_y_las_entregas_usan_el_menor_qos_entre_el_pedido_y_el_maximo() {
+        let mut server = create_server_for_testing();
+        server.max_qos = 1;
+        let listener = add_user_with_will(&server, "Sistema-Monitoreo");
+        let topic = AppsMqttTopics::IncidentTopic.to_str();
+
+        let subscribe_msg = SubscribeMessage::new(1, vec![(topic.to_string(), 2)]).unwrap();
+        let return_codes = server
+            .add_topics_to_subscriber("Sistema-Monitoreo", &subscribe_msg)
+            .unwrap();
+        assert_eq!(return_codes, vec![SubscribeReturnCode::QoS1]);
+
+        // Se publica con qos 2, y el suscriptor lo recibe con el qos otorgado
+        let incident = Incident::new(1, (-34.6037, -58.3816), IncidentSource::Automated);
+        let flags = PublishFlags::new(0, 2, 0).unwrap();
+        let msg = PublishMessage::new(flags, topic, Some(3), &incident.to_bytes()).unwrap();
+        server
+            .handle_publish_message_from("Sistema-Camaras", &msg)
+            .unwrap();
+
+        let received = read_delivered_publish(&listener);
+        assert_eq!(received.get_qos(), 1);
+        assert_eq!(received.get_payload(), incident.to_bytes());
+    }
+
+    // Lee el publish que el server le entregó al user conectado a `listener`.
+    fn read_delivered_publish(listener: &TcpListener) -> PublishMessage {
+        let (mut stream, _) = listener.accept().unwrap();
+        stream
+            .set_read_timeout(Some(Duration::from_secs(2)))
+            .unwrap();
+        let mut buf = [0u8; 256];
+        let read = stream.read(&mut buf).unwrap();
+        PublishMessage::from_bytes(buf[..read].to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_10_cada_suscriptor_recibe_su_qos_y_un_packet_id_propio() {
+        let server = create_server_for_testing();
+        let topic = AppsMqttTopics::IncidentTopic.to_str();
+        let listener_qos_0 = add_user_with_will(&server, "Sistema-Monitoreo");
+        let listener_qos_1 = add_user_with_will(&server, "dron-1");
+        for (username, qos) in [("Sistema-Monitoreo", 0), ("dron-1", 1)] {
+            let subscribe_msg = SubscribeMessage::new(1, vec![(topic.to_string(), qos)]).unwrap();
+            server.add_topics_to_subscriber(username, &subscribe_msg).unwrap();
+        }
+
+        // El publicador usa el packet id 7, pero cada suscriptor recibe uno generado por el server
+        server
+            .handle_publish_message_from("Sistema-Camaras", &create_incident_publish(0, 7))
+            .unwrap();
+
+        let received_qos_0 = read_delivered_publish(&listener_qos_0);
+        assert_eq!(received_qos_0.get_qos(), 0);
+        assert_eq!(received_qos_0.get_packet_id(), None);
+
+        let received_qos_1 = read_delivered_publish(&listener_qos_1);
+        assert_eq!(received_qos_1.get_qos(), 1);
+        assert_eq!(received_qos_1.get_packet_id(), Some(1));
+
+        // Solamente el suscriptor con qos 1 debe enviar el puback
+        assert!(!server.handle_puback_from("Sistema-Monitoreo", 1).unwrap());
+        assert!(server.handle_puback_from("dron-1", 1).unwrap());
+        assert!(!server.handle_puback_from("dron-1", 1).unwrap());
+    }
+
+    #[test]
+    fn test_11_cada_cliente_tiene_a_lo_sumo_receive_maximum_publish_sin_responder() {
+        let mut server = create_server_for_testing();
+        server.receive_maximum = 2;
+        let _listener_camaras = add_user_with_will(&server, "Sistema-Camaras");
+        let _listener_dron = add_user_with_will(&server, "dron-1");
+
+        assert!(server.try_start_inbound_publish_from("Sistema-Camaras"));
+        assert!(server.try_start_inbound_publish_from("Sistema-Camaras"));
+        // Ventana llena: el siguiente se posterga, sin afectar a los demás clientes
+        assert!(!server.try_start_inbound_publish_from("Sistema-Camaras"));
+        assert!(server.try_start_inbound_publish_from("dron-1"));
+
+        // Al responderse uno, se libera su lugar
+        server.finish_inbound_publish_from("Sistema-Camaras");
+        assert!(server.try_start_inbound_publish_from("Sistema-Camaras"));
+    }
+
+    #[test]
+    fn test_12_un_paquete_que_supera_el_tamano_maximo_no_se_lee_y_se_desconecta_al_cliente() {
+        let mut server = create_server_for_testing();
+        server.max_packet_size = 1024;
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server_stream, _) = listener.accept().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut reader =
+            ClientReader::new(server_stream, server.clone_ref(), server.logger.clone_ref()).unwrap();
+
+        // Publish que anuncia la máxima remaining length (unos 256 MB), sin enviarlos
+        client.write_all(&[0x30, 0xFF, 0xFF, 0xFF, 0x7F]).unwrap();
+        let reason = reader.read_packets_from_stream("dron-1", tx).unwrap();
+
+        assert!(matches!(reason, DisconnectReason::Involuntaria));
+        assert!(rx.try_recv().is_err());
+        // El server cierra la conexión: el cliente lee fin de stream
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        let mut buf = [0u8; 1];
+        assert_eq!(client.read(&mut buf).unwrap(), 0);
+
+        // Lo mismo si el paquete que supera el máximo es el connect, antes de autenticar al cliente
+        // (su remaining length ocupa un solo byte, por lo que se baja el máximo para superarlo)
+        server.max_packet_size = 100;
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (mut server_stream, _) = listener.accept().unwrap();
+        let mut reader =
+            ClientReader::new(server_stream.try_clone().unwrap(), server.clone_ref(), server.logger.clone_ref())
+                .unwrap();
+        client.write_all(&[0x10, 0xFF]).unwrap();
+        assert!(reader.handle_client(&mut server_stream).is_ok());
+        client.set_read_timeout(Some(Duration::from_secs(5))).unwrap();
+        assert_eq!(client.read(&mut buf).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_13_tras_desuscribirse_no_se_entregan_mensajes_y_al_volver_a_suscribirse_se_reciben() {
+        let server = create_server_for_testing();
+        let listener = add_user_with_will(&server, "Sistema-Monitoreo");
+        let topic = AppsMqttTopics::IncidentTopic.to_str();
+        let subscribe_msg = SubscribeMessage::new(1, vec![(topic.to_string(), 1)]).unwrap();
+        server.add_topics_to_subscriber("Sistema-Monitoreo", &subscribe_msg).unwrap();
+
+        server.remove_topics_from_subscriber("Sistema-Monitoreo", &[topic.to_string()]);
+        server
+            .handle_publish_message_from("Sistema-Camaras", &create_incident_publish(0, 7))
+            .unwrap();
+        {
+            let users = server.connected_users.lock().unwrap();
+            let user = users.get("Sistema-Monitoreo").unwrap();
+            assert!(user.get_topics().is_empty());
+            // No se le entregó el publish
+            assert!(!user.is_awaiting_ack(1));
+        }
+
+        // Al volver a suscribirse, recibe lo que el server conserva del topic
+        server.add_topics_to_subscriber("Sistema-Monitoreo", &subscribe_msg).unwrap();
+        server
+            .send_preexisting_msgs_to_new_subscriber("Sistema-Monitoreo", &subscribe_msg)
+            .unwrap();
+        let received = read_delivered_publish(&listener);
+        assert_eq!(received.get_topic(), topic);
+    }
+
+    #[test]
+    fn test_14_si_el_cliente_se_desconecta_a_la_mitad_del_fixed_header_es_una_desconexion_involuntaria() {
+        let server = create_server_for_testing();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server_stream, _) = listener.accept().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut reader =
+            ClientReader::new(server_stream, server.clone_ref(), server.logger.clone_ref()).unwrap();
+
+        // Solamente el primer byte del fixed header de un publish
+        client.write_all(&[0x30]).unwrap();
+        drop(client);
+        let reason = reader.read_packets_from_stream("dron-1", tx).unwrap();
+
+        assert!(matches!(reason, DisconnectReason::Involuntaria));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_15_una_remaining_length_de_mas_de_4_bytes_desconecta_al_cliente_sin_leer_el_paquete() {
+        let server = create_server_for_testing();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server_stream, _) = listener.accept().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut reader =
+            ClientReader::new(server_stream, server.clone_ref(), server.logger.clone_ref()).unwrap();
+
+        // Publish cuya remaining length ocupa 5 bytes, con la conexión todavía abierta
+        client.write_all(&[0x30, 0xFF, 0xFF, 0xFF, 0xFF, 0x7F]).unwrap();
+        let reason = reader.read_packets_from_stream("dron-1", tx).unwrap();
+
+        assert!(matches!(reason, DisconnectReason::Involuntaria));
+        assert!(rx.try_recv().is_err());
+    }
+
+    #[test]
+    fn test_16_un_incidente_retransmitido_por_el_cliente_se_propaga_una_sola_vez() {
+        let server = create_server_for_testing();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client_stream = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server_stream, _) = listener.accept().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut reader =
+            ClientReader::new(server_stream, server.clone_ref(), server.logger.clone_ref()).unwrap();
+        let reader_handle = thread::spawn(move || reader.read_packets_from_stream("Sistema-Camaras", tx));
+
+        // El cliente publica el incidente con un Retransmitter, que lo reenvía si no recibe el puback a tiempo
+        let (logger_tx, _logger_rx) = mpsc::channel::<String>();
+        let (mut retransmitter, ack_tx) = Retransmitter::new_with_config(
+            client_stream,
+            MQTTClientStats::new(),
+            Duration::from_millis(50),
+            3,
+            StringLogger::new(logger_tx),
+        );
+        let sender_handle = thread::spawn(move || {
+            let res = retransmitter.send_and_retransmit(&create_incident_publish(0, 7));
+            (res, retransmitter)
+        });
+
+        // El server procesa el publish, pero su puback se pierde
+        let original = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        let original = PublishMessage::from_bytes(original.get_msg_bytes()).unwrap();
+        server.handle_publish_message_from("Sistema-Camaras", &original).unwrap();
+        // Recibe la retransmisión, con dup y el mismo packet id, y esta vez el puback llega
+        let resent = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        let resent = PublishMessage::from_bytes(resent.get_msg_bytes()).unwrap();
+        assert!(resent.is_dup());
+        assert_eq!(resent.get_packet_id(), original.get_packet_id());
+        server.handle_publish_message_from("Sistema-Camaras", &resent).unwrap();
+        ack_tx.send(ACKMessage::PubAck(PubAckMessage::new(7, 0))).unwrap();
+
+        let (res, retransmitter) = sender_handle.join().unwrap();
+        assert!(res.is_ok());
+        // Solamente se propagó un incidente
+        assert_eq!(amount_of_messages_for(&server, AppsMqttTopics::IncidentTopic.to_str()), 1);
+
+        // Al cerrarse la conexión del cliente, termina la lectura
+        drop(retransmitter);
+        assert!(reader_handle.join().unwrap().is_ok());
+    }
+
+    #[test]
+    fn test_17_disconnect_por_una_conexion_reemplazada_no_remueve_al_user() {
+        let server = create_server_for_testing();
+        // La conexión actual del user, que reemplazó a la anterior por un takeover
+        let _current_listener = add_user_with_will(&server, "Sistema-Camaras");
+
+        // La conexión anterior sigue abierta, y su cliente envía el DisconnectMessage
+        let old_listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let mut old_client_stream = TcpStream::connect(old_listener.local_addr().unwrap()).unwrap();
+        let (old_server_stream, _) = old_listener.accept().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut reader =
+            ClientReader::new(old_server_stream, server.clone_ref(), server.logger.clone_ref()).unwrap();
+        // Fixed header del disconnect: tipo 14 y remaining length 0
+        old_client_stream.write_all(&[0b1110_0000, 0]).unwrap();
+
+        let disconnect_reason = reader.read_packets_from_stream("Sistema-Camaras", tx).unwrap();
+        assert!(matches!(disconnect_reason, DisconnectReason::Voluntaria));
+        // Se procesa el disconnect como lo hace el MessageProcessor
+        let packet = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(packet.get_peer_addr(), old_client_stream.local_addr().ok());
+        server.handle_clean_disconnect(packet.get_username(), packet.get_peer_addr());
+
+        // El user de la conexión actual no se removió
+        assert!(server
+            .get_connected_users()
+            .lock()
+            .unwrap()
+            .contains_key("Sistema-Camaras"));
+    }
+}
@@ -1,17 +1,26 @@
 use crate::mqtt::messages::packet_type::PacketType;
+use std::net::SocketAddr;
 
 pub struct Packet {
     message_type: PacketType,
     msg_bytes: Vec<u8>,
     username: String,
+    // Dirección del cliente en la conexión por la que llegó, para distinguirla de una que la haya reemplazado
+    peer_addr: Option<SocketAddr>,
 }
 
 impl Packet {
-    pub fn new(message_type: PacketType, msg_bytes: Vec<u8>, username: String) -> Packet {
+    pub fn new(
+        message_type: PacketType,
+        msg_bytes: Vec<u8>,
+        username: String,
+        peer_addr: Option<SocketAddr>,
+    ) -> Packet {
         Packet {
             message_type,
             msg_bytes,
             username,
+            peer_addr,
         }
     }
 
@@ -23,7 +32,27 @@ impl Packet {
         self.msg_bytes.clone()
     }
 
+    /// Devuelve la cantidad de bytes del mensaje.
+    pub fn get_msg_len(&self) -> usize {
+        self.msg_bytes.len()
+    }
+
     pub fn get_username(&self) -> &str {
         self.username.as_str()
     }
+
+    /// Devuelve la dirección del cliente en la conexión por la que llegó el paquete, si se pudo obtener.
+    pub fn get_peer_addr(&self) -> Option<SocketAddr> {
+        self.peer_addr
+    }
+
+    /// Devuelve si es un publish con qos mayor a 0, es decir que el server debe responderle con un puback.
+    pub fn is_publish_with_qos(&self) -> bool {
+        // El qos está en los bits 1 y 2 del primer byte del fixed header
+        self.message_type == PacketType::Publish
+            && self
+                .msg_bytes
+                .first()
+                .is_some_and(|first_byte| (first_byte >> 1) & 0b11 > 0)
+    }
 }
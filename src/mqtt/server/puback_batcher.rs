@@ -0,0 +1,204 @@
+use std::{
+    collections::HashMap,
+    io::{Error, ErrorKind},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::{apps::properties::Properties, mqtt::mqtt_utils::shutdown_token::ShutdownToken};
+
+/// Máxima demora configurable de un PubAck. Muy por debajo del intervalo con el que el cliente retransmite
+/// un publish sin ack (ver `DEFAULT_ACK_WAITING_INTERVAL`), para que agrupar acks nunca provoque retransmisiones.
+pub const MAX_PUBACK_BATCH_DELAY: Duration = Duration::from_millis(50);
+
+/// PubAcks pendientes de enviar a un cliente: sus bytes concatenados, cuántos son, y cuándo vence el más antiguo.
+#[derive(Debug)]
+struct PendingAcks {
+    bytes: Vec<u8>,
+    count: usize,
+    deadline: Instant,
+}
+
+/// Agrupa los PubAcks a un mismo cliente para enviarlos en una sola escritura, en lugar de una por publish.
+/// Los acks de un cliente se envían juntos al acumularse `max_acks`, o a lo sumo `max_delay` después de
+/// agregado el primero de ellos (ver `spawn_flusher`), lo que ocurra primero.
+/// Sus clones comparten los acks pendientes.
+#[derive(Debug, Clone)]
+pub struct PubAckBatcher {
+    max_acks: usize,
+    max_delay: Duration,
+    pending: Arc<Mutex<HashMap<String, PendingAcks>>>,
+}
+
+impl PubAckBatcher {
+    /// Crea el batcher. Devuelve error si `max_acks` es 0, o si `max_delay` supera `MAX_PUBACK_BATCH_DELAY`.
+    pub fn new(max_acks: usize, max_delay: Duration) -> Result<Self, Error> {
+        if max_acks == 0 || max_delay > MAX_PUBACK_BATCH_DELAY {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                format!(
+                    "Agrupamiento de PubAcks inválido: {} acks, demora de {:?} (máximo {:?}).",
+                    max_acks, max_delay, MAX_PUBACK_BATCH_DELAY
+                ),
+            ));
+        }
+        Ok(Self {
+            max_acks,
+            max_delay,
+            pending: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
+
+    /// Agrega los bytes del PubAck `ack_bytes` a los pendientes del cliente `client_id`. Si con él se completan
+    /// `max_acks`, los quita y devuelve para enviarlos ya; si no, devuelve None.
+    pub fn add(&self, client_id: &str, ack_bytes: &[u8]) -> Option<Vec<u8>> {
+        let mut pending = self.pending.lock().ok()?;
+        let client_acks = pending.entry(client_id.to_string()).or_insert_with(|| PendingAcks {
+            bytes: vec![],
+            count: 0,
+            deadline: Instant::now() + self.max_delay,
+        });
+        client_acks.bytes.extend_from_slice(ack_bytes);
+        client_acks.count += 1;
+        if client_acks.count >= self.max_acks {
+            return pending.remove(client_id).map(|client_acks| client_acks.bytes);
+        }
+        None
+    }
+
+    /// Quita y devuelve, por cliente, los acks pendientes cuyo plazo venció a las `now`.
+    pub fn take_due(&self, now: Instant) -> Vec<(String, Vec<u8>)> {
+        let mut due = vec![];
+        if let Ok(mut pending) = self.pending.lock() {
+            let due_clients: Vec<String> = pending
+                .iter()
+                .filter(|(_, client_acks)| client_acks.deadline <= now)
+                .map(|(client_id, _)| client_id.to_string())
+                .collect();
+            for client_id in due_clients {
+                if let Some(client_acks) = pending.remove(&client_id) {
+                    due.push((client_id, client_acks.bytes));
+                }
+            }
+        }
+        due
+    }
+
+    /// Devuelve cuánto esperar, desde `now`, hasta que venza el plazo de los próximos acks pendientes.
+    /// Sin acks pendientes, `max_delay`: cualquier ack que se agregue mientras tanto vencerá después.
+    fn time_until_next_due(&self, now: Instant) -> Duration {
+        match self.pending.lock() {
+            Ok(pending) => pending
+                .values()
+                .map(|client_acks| client_acks.deadline.saturating_duration_since(now))
+                .min()
+                .unwrap_or(self.max_delay),
+            Err(_) => self.max_delay,
+        }
+    }
+
+    /// Lanza el hilo que envía los acks pendientes al vencer su plazo, escribiéndolos con `write` (que recibe
+    /// el client_id y los bytes de sus acks), hasta que se solicite el `shutdown_token`.
+    /// Como nunca espera más que hasta el próximo vencimiento, ningún ack se demora más que `max_delay`.
+    pub fn spawn_flusher<F>(&self, shutdown_token: ShutdownToken, mut write: F) -> JoinHandle<()>
+    where
+        F: FnMut(&str, Vec<u8>) + Send + 'static,
+    {
+        let batcher = self.clone();
+        thread::spawn(move || loop {
+            for (client_id, acks_bytes) in batcher.take_due(Instant::now()) {
+                write(&client_id, acks_bytes);
+            }
+            if shutdown_token.sleep_unless_requested(batcher.time_until_next_due(Instant::now())) {
+                break;
+            }
+        })
+    }
+}
+
+/// Lee el agrupamiento de PubAcks de las propiedades `puback_batch_size` (cuántos acks a un cliente se envían
+/// juntos) y `puback_batch_delay_ms` (cuánto puede demorarse un ack, como máximo `MAX_PUBACK_BATCH_DELAY`).
+/// Si `puback_batch_size` no está configurada o es 1, cada ack se envía apenas se procesa su publish.
+pub fn load_puback_batcher(properties_file: &str) -> Result<Option<PubAckBatcher>, Error> {
+    let properties = Properties::new(properties_file)?;
+    let max_acks = match properties.get("puback_batch_size").map(|size| size.parse::<usize>()) {
+        Some(Ok(size)) if size > 1 => size,
+        Some(Ok(1)) | None => return Ok(None),
+        Some(_) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Propiedad 'puback_batch_size' inválida: debe ser un número mayor a 0.",
+            ))
+        }
+    };
+    let max_delay = match properties.get("puback_batch_delay_ms").map(|delay| delay.parse::<u64>()) {
+        Some(Ok(delay_ms)) => Duration::from_millis(delay_ms),
+        Some(Err(_)) => {
+            return Err(Error::new(
+                ErrorKind::InvalidInput,
+                "Propiedad 'puback_batch_delay_ms' inválida: debe ser un número de milisegundos.",
+            ))
+        }
+        None => MAX_PUBACK_BATCH_DELAY / 5,
+    };
+    PubAckBatcher::new(max_acks, max_delay).map(Some)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::mpsc,
+        time::{Duration, Instant},
+    };
+
+    use super::PubAckBatcher;
+    use crate::mqtt::{messages::puback_message::PubAckMessage, mqtt_utils::shutdown_token::ShutdownToken};
+
+    #[test]
+    fn test_1_todos_los_acks_se_entregan_agrupados_y_sin_superar_la_demora_maxima() {
+        let max_delay = Duration::from_millis(20);
+        let batcher = PubAckBatcher::new(3, max_delay).unwrap();
+        let (written_tx, written_rx) = mpsc::channel::<(String, Vec<u8>, Instant)>();
+        let written_tx_flusher = written_tx.clone();
+        let shutdown_token = ShutdownToken::new();
+        let flusher = batcher.spawn_flusher(shutdown_token.clone(), move |client_id, bytes| {
+            let _ = written_tx_flusher.send((client_id.to_string(), bytes, Instant::now()));
+        });
+
+        // 7 acks a "cam" y uno a "dron": se envían dos grupos de 3 al completarse, y el resto al vencer su plazo
+        let added_at = Instant::now();
+        for packet_id in 1..=7 {
+            if let Some(bytes) = batcher.add("cam", &PubAckMessage::new(packet_id, 0).to_bytes()) {
+                let _ = written_tx.send(("cam".to_string(), bytes, Instant::now()));
+            }
+        }
+        let _ = batcher.add("dron", &PubAckMessage::new(8, 0).to_bytes());
+
+        let mut writes = vec![];
+        while let Ok(write) = written_rx.recv_timeout(Duration::from_millis(500)) {
+            writes.push(write);
+            if writes.len() == 4 {
+                break;
+            }
+        }
+        shutdown_token.request();
+        flusher.join().unwrap();
+
+        assert_eq!(writes.len(), 4);
+        let mut acked_ids: Vec<u16> = vec![];
+        for (client_id, bytes, written_at) in writes {
+            // Margen para la planificación de los hilos
+            assert!(written_at.duration_since(added_at) < max_delay + Duration::from_millis(100));
+            for ack_bytes in bytes.chunks(4) {
+                let packet_id = PubAckMessage::msg_from_bytes(ack_bytes.to_vec()).unwrap().get_packet_id();
+                assert_eq!(client_id == "dron", packet_id == 8);
+                acked_ids.push(packet_id);
+            }
+        }
+        acked_ids.sort();
+        assert_eq!(acked_ids, (1..=8).collect::<Vec<u16>>());
+
+        assert!(PubAckBatcher::new(3, Duration::from_secs(1)).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+use std::io::{stdin, stdout, Write};
+
+use crate::logging::string_logger::StringLogger;
+
+use super::{mqtt_server::MQTTServer, user::UserSummary};
+
+/// Consola de administración del server: permite consultar los clientes conectados
+/// y desconectarlos forzosamente, mientras el server está corriendo.
+pub struct ServerAdmin {
+    mqtt_server: MQTTServer,
+    logger: StringLogger,
+}
+
+impl ServerAdmin {
+    /// Crea la consola, que opera sobre el estado compartido del `mqtt_server` recibido.
+    pub fn new(mqtt_server: MQTTServer, logger: StringLogger) -> Self {
+        ServerAdmin {
+            mqtt_server,
+            logger,
+        }
+    }
+
+    /// Lee comandos desde la entrada estándar y muestra su resultado, hasta que la misma se cierre.
+    pub fn run(&self) {
+        self.print_menu();
+        loop {
+            print!("> ");
+            let _ = stdout().flush();
+
+            let mut input = String::new();
+            match stdin().read_line(&mut input) {
+                Ok(0) => break,
+                Ok(_) => println!("{}", self.execute(input.trim())),
+                Err(e) => {
+                    self.logger
+                        .log(format!("Error al leer comando de consola: {:?}.", e));
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Muestra por pantalla los comandos disponibles.
+    fn print_menu(&self) {
+        println!(
+            "      COMANDOS DEL SERVER
+        clients: Listar clientes, con sus bytes enviados y recibidos
+        subs <client_id>: Listar suscripciones del cliente
+        kick <client_id>: Desconectar al cliente (se publica su will)
+        health: Estado de salud del server, en una línea (clientes conectados, uptime y accept loop)"
+        );
+    }
+
+    /// Ejecuta el comando recibido y devuelve el texto a mostrar.
+    pub fn execute(&self, command: &str) -> String {
+        let parts: Vec<&str> = command.split_whitespace().collect();
+        match parts.as_slice() {
+            ["clients"] => format_clients(&self.mqtt_server.get_users_summary()),
+            ["health"] => self.mqtt_server.get_health_status().to_line(),
+            ["subs", client_id] => match self.mqtt_server.get_topics_of(client_id) {
+                Some(topics) if topics.is_empty() => {
+                    format!("{} no está suscripto a ningún topic.", client_id)
+                }
+                Some(topics) => topics.join("\n"),
+                None => format!("No existe el cliente {}.", client_id),
+            },
+            ["kick", client_id] => match self.mqtt_server.kick_user(client_id) {
+                Ok(true) => format!("Cliente {} desconectado.", client_id),
+                Ok(false) => format!("No existe el cliente {}.", client_id),
+                Err(e) => format!("Error al desconectar a {}: {:?}", client_id, e),
+            },
+            [] => String::new(),
+            _ => "Comando no válido. Intente nuevamente.".to_string(),
+        }
+    }
+}
+
+/// Da formato a los resúmenes de los clientes, uno por línea.
+fn format_clients(summaries: &[UserSummary]) -> String {
+    if summaries.is_empty() {
+        return "No hay clientes conectados.".to_string();
+    }
+    summaries
+        .iter()
+        .map(|summary| {
+            format!(
+                "{} [{}] topics: {}, enviados: {} B, recibidos: {} B",
+                summary.username,
+                if summary.is_active { "activo" } else { "desconectado" },
+                summary.topics.len(),
+                summary.bytes_sent,
+                summary.bytes_received
+            )
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::format_clients;
+    use crate::mqtt::server::user::UserSummary;
+
+    #[test]
+    fn test_1_format_clients_muestra_un_cliente_por_linea() {
+        let summaries = vec![
+            UserSummary {
+                username: "dron-1".to_string(),
+                is_active: true,
+                topics: vec!["inc".to_string(), "dron".to_string()],
+                bytes_sent: 120,
+                bytes_received: 40,
+            },
+            UserSummary {
+                username: "dron-2".to_string(),
+                is_active: false,
+                topics: vec![],
+                bytes_sent: 0,
+                bytes_received: 0,
+            },
+        ];
+
+        assert_eq!(
+            format_clients(&summaries),
+            "dron-1 [activo] topics: 2, enviados: 120 B, recibidos: 40 B\n\
+             dron-2 [desconectado] topics: 0, enviados: 0 B, recibidos: 0 B"
+        );
+        assert_eq!(format_clients(&[]), "No hay clientes conectados.");
+    }
+}
@@ -0,0 +1,119 @@
+use std::{
+    io::Write,
+    net::TcpListener,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::{Duration, Instant},
+};
+
+use crate::logging::string_logger::StringLogger;
+
+use super::mqtt_server::MQTTServer;
+
+/// Datos para el health check del server, compartidos entre sus hilos: desde cuándo corre, y si el hilo que
+/// acepta conexiones sigue vivo. Se leen sin tomar los locks del server (ver `MQTTServer::get_health_status`).
+#[derive(Debug, Clone)]
+pub struct ServerHealth {
+    started_at: Instant,
+    accept_loop_alive: Arc<AtomicBool>,
+}
+
+impl ServerHealth {
+    pub fn new() -> Self {
+        Self {
+            started_at: Instant::now(),
+            accept_loop_alive: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Indica si el hilo que acepta conexiones está corriendo.
+    pub fn set_accept_loop_alive(&self, alive: bool) {
+        self.accept_loop_alive.store(alive, Ordering::Relaxed);
+    }
+
+    /// Devuelve el estado de salud, con la cantidad `connected_clients` de clientes conectados.
+    pub fn status(&self, connected_clients: usize) -> HealthStatus {
+        HealthStatus {
+            connected_clients,
+            uptime: self.started_at.elapsed(),
+            accept_loop_alive: self.accept_loop_alive.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Default for ServerHealth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Estado de salud del server en un instante.
+#[derive(Debug, PartialEq)]
+pub struct HealthStatus {
+    pub connected_clients: usize,
+    pub uptime: Duration,
+    pub accept_loop_alive: bool,
+}
+
+impl HealthStatus {
+    /// El server está sano mientras siga aceptando conexiones.
+    pub fn is_healthy(&self) -> bool {
+        self.accept_loop_alive
+    }
+
+    /// Devuelve el estado en una línea, de pares clave=valor separados por espacios:
+    /// `status=<ok|error> clients=<clientes conectados> uptime_s=<segundos desde que se creó el server> accept_loop=<alive|dead>`.
+    /// `status` es `ok` solamente si el server está sano (ver `is_healthy`).
+    pub fn to_line(&self) -> String {
+        format!(
+            "status={} clients={} uptime_s={} accept_loop={}",
+            if self.is_healthy() { "ok" } else { "error" },
+            self.connected_clients,
+            self.uptime.as_secs(),
+            if self.accept_loop_alive { "alive" } else { "dead" }
+        )
+    }
+}
+
+/// Atiende los health checks por TCP (ej. la liveness probe de un contenedor): a cada conexión le responde
+/// el estado de salud del server en una línea (ver `HealthStatus::to_line`) y la cierra, sin pasar por el
+/// protocolo MQTT. Termina si el `listener` deja de aceptar conexiones.
+pub fn serve_health_checks(listener: TcpListener, mqtt_server: MQTTServer, logger: StringLogger) {
+    for stream in listener.incoming() {
+        match stream {
+            Ok(mut stream) => {
+                let line = format!("{}\n", mqtt_server.get_health_status().to_line());
+                if let Err(e) = stream.write_all(line.as_bytes()) {
+                    logger.log(format!("Error al responder health check: {:?}.", e));
+                }
+            }
+            Err(e) => {
+                logger.log(format!("ERROR: el listener de health check dejó de aceptar conexiones: {:?}.", e));
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::time::Duration;
+
+    use super::HealthStatus;
+
+    #[test]
+    fn test_1_el_estado_se_muestra_en_una_linea_y_sin_el_accept_loop_no_esta_sano() {
+        let mut status = HealthStatus {
+            connected_clients: 3,
+            uptime: Duration::from_millis(125_900),
+            accept_loop_alive: true,
+        };
+        assert_eq!(status.to_line(), "status=ok clients=3 uptime_s=125 accept_loop=alive");
+
+        status.accept_loop_alive = false;
+        assert!(!status.is_healthy());
+        assert_eq!(status.to_line(), "status=error clients=3 uptime_s=125 accept_loop=dead");
+    }
+}
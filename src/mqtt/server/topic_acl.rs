@@ -0,0 +1,184 @@
+use std::{
+    io::{Error, ErrorKind},
+    sync::Arc,
+};
+
+use crate::apps::{apps_mqtt_topics::AppsMqttTopics, properties::Properties};
+
+/// Topics en los que un cliente (o grupo de clientes) puede publicar y a los que puede suscribirse.
+#[derive(Debug, Clone, PartialEq)]
+struct AclRule {
+    client_id_pattern: String, // client_id exacto, o prefijo terminado en '*' (ej "dron-*")
+    publish_topics: Vec<String>,
+    subscribe_topics: Vec<String>,
+}
+
+/// Lista de control de acceso a los topics de las apps (`AppsMqttTopics`).
+/// Cada línea del archivo tiene el formato `client_id=pub:topic1,topic2;sub:topic3`.
+/// Solamente se restringen los topics conocidos de las apps, y un cliente que no figura en el archivo
+/// no puede publicar ni suscribirse a ninguno de ellos. Si no se configura, se permite todo.
+#[derive(Debug, Clone)]
+pub struct TopicAcl {
+    rules: Option<Arc<Vec<AclRule>>>,
+}
+
+impl TopicAcl {
+    /// Crea una ACL que permite todas las operaciones.
+    pub fn allow_all() -> Self {
+        Self { rules: None }
+    }
+
+    /// Carga la ACL desde el archivo `acl_file`.
+    pub fn from_file(acl_file: &str) -> Result<Self, Error> {
+        let properties = Properties::new(acl_file)?;
+        let mut rules = vec![];
+        for (client_id_pattern, value) in properties.iter() {
+            rules.push(parse_rule(client_id_pattern, value)?);
+        }
+        Ok(Self {
+            rules: Some(Arc::new(rules)),
+        })
+    }
+
+    /// Devuelve si `client_id` puede publicar en `topic`.
+    pub fn can_publish(&self, client_id: &str, topic: &str) -> bool {
+        self.is_allowed(client_id, topic, |rule| &rule.publish_topics)
+    }
+
+    /// Devuelve si `client_id` puede suscribirse a `topic`.
+    pub fn can_subscribe(&self, client_id: &str, topic: &str) -> bool {
+        self.is_allowed(client_id, topic, |rule| &rule.subscribe_topics)
+    }
+
+    fn is_allowed(
+        &self,
+        client_id: &str,
+        topic: &str,
+        allowed_topics_of: impl Fn(&AclRule) -> &Vec<String>,
+    ) -> bool {
+        let rules = match &self.rules {
+            Some(rules) => rules,
+            None => return true,
+        };
+        // Los topics que no son de las apps no se restringen
+        if AppsMqttTopics::topic_from_str(topic).is_err() {
+            return true;
+        }
+        rules
+            .iter()
+            .filter(|rule| matches_client_id(&rule.client_id_pattern, client_id))
+            .any(|rule| allowed_topics_of(rule).iter().any(|allowed| allowed == topic))
+    }
+}
+
+/// Devuelve si el `client_id` corresponde al patrón (exacto, o prefijo si el patrón termina en '*').
+fn matches_client_id(pattern: &str, client_id: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => client_id.starts_with(prefix),
+        None => pattern == client_id,
+    }
+}
+
+/// Interpreta el valor `pub:topic1,topic2;sub:topic3` de la regla del `client_id_pattern`.
+fn parse_rule(client_id_pattern: &str, value: &str) -> Result<AclRule, Error> {
+    let mut rule = AclRule {
+        client_id_pattern: client_id_pattern.to_string(),
+        publish_topics: vec![],
+        subscribe_topics: vec![],
+    };
+    for part in value.split(';').map(str::trim).filter(|part| !part.is_empty()) {
+        let (kind, topics) = match part.split_once(':') {
+            Some(kind_and_topics) => kind_and_topics,
+            None => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Regla de acl inválida para {:?}.", client_id_pattern),
+                ))
+            }
+        };
+        let topics = parse_topics(topics)?;
+        match kind.trim() {
+            "pub" => rule.publish_topics = topics,
+            "sub" => rule.subscribe_topics = topics,
+            _ => {
+                return Err(Error::new(
+                    ErrorKind::InvalidData,
+                    format!("Tipo de regla de acl inválido: {:?}.", kind),
+                ))
+            }
+        }
+    }
+    Ok(rule)
+}
+
+/// Interpreta una lista de topics separados por coma, validando que sean topics de las apps.
+fn parse_topics(topics: &str) -> Result<Vec<String>, Error> {
+    topics
+        .split(',')
+        .map(str::trim)
+        .filter(|topic| !topic.is_empty())
+        .map(|topic| AppsMqttTopics::topic_from_str(topic).map(|t| t.to_str().to_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+
+    use super::{parse_rule, TopicAcl};
+    use crate::apps::common_clients::get_app_will_topic;
+
+    fn create_acl() -> TopicAcl {
+        let rules = vec![
+            parse_rule("Sistema-Camaras", "pub:cam,inc;sub:inc").unwrap(),
+            parse_rule("Sistema-Monitoreo", "pub:inc;sub:cam,dron,inc,desc").unwrap(),
+            parse_rule("dron-*", "pub:dron;sub:inc,dron").unwrap(),
+        ];
+        TopicAcl {
+            rules: Some(Arc::new(rules)),
+        }
+    }
+
+    #[test]
+    fn test_1_solamente_camaras_publica_en_cam_y_monitoreo_solo_se_suscribe() {
+        let acl = create_acl();
+
+        assert!(acl.can_publish("Sistema-Camaras", "cam"));
+        assert!(!acl.can_publish("Sistema-Monitoreo", "cam"));
+        assert!(acl.can_subscribe("Sistema-Monitoreo", "cam"));
+        assert!(acl.can_publish("dron-3", "dron"));
+        assert!(!acl.can_publish("dron-3", "cam"));
+        assert!(!acl.can_subscribe("desconocido", "inc"));
+    }
+
+    #[test]
+    fn test_2_topic_desconocido_en_la_regla_es_error() {
+        assert!(parse_rule("Sistema-Camaras", "pub:camaras").is_err());
+        assert!(TopicAcl::allow_all().can_publish("desconocido", "cam"));
+    }
+
+    #[test]
+    fn test_3_se_carga_el_archivo_de_acl_del_servidor() {
+        let acl = TopicAcl::from_file("./topic_acl.properties").unwrap();
+        assert!(acl.can_publish("Sistema-Camaras", "cam"));
+        assert!(!acl.can_publish("Sistema-Monitoreo", "cam"));
+    }
+
+    #[test]
+    fn test_4_con_el_archivo_del_servidor_todas_las_apps_publican_sus_heartbeats() {
+        let acl = TopicAcl::from_file("./topic_acl.properties").unwrap();
+        let heartbeat_topic = get_app_will_topic();
+        for client_id in ["Sistema-Camaras", "Sistema-Monitoreo", "dron-1"] {
+            assert!(acl.can_publish(client_id, heartbeat_topic.to_str()));
+        }
+    }
+
+    #[test]
+    fn test_5_con_el_archivo_del_servidor_el_replay_publica_los_topics_que_reproduce() {
+        let acl = TopicAcl::from_file("./topic_acl.properties").unwrap();
+        for topic in ["inc", "cam", "cam_frame", "dron"] {
+            assert!(acl.can_publish("Replay", topic));
+        }
+        assert!(!acl.can_subscribe("Replay", "inc"));
+    }
+}
@@ -1,6 +1,7 @@
 use std::{
-    collections::HashMap,
-    io::{Error, Write}, net::Shutdown,
+    collections::{HashMap, HashSet},
+    io::{Error, Write}, net::{Shutdown, SocketAddr},
+    time::Duration,
 };
 
 use crate::mqtt::{
@@ -21,7 +22,24 @@ pub struct User {
     state: UserState,
     will_message: Option<WillMessageData>,
     topics: Vec<String>,                    // topics a los que esta suscripto
+    granted_qos_by_topic: HashMap<String, u8>, // por cada topic, el qos otorgado en el suback.
     last_id_by_topic: HashMap<String, u32>, // por cada topic tiene el ultimo id de mensaje enviado.
+    keep_alive: u16,                        // en segundos, indicado en el connect; 0 lo deshabilita.
+    bytes_sent: u64,                        // enviados por el server al user.
+    bytes_received: u64,                    // recibidos por el server desde el user.
+    next_packet_id: u16,                    // próximo packet id para los publish que se le entregan.
+    pending_acks: HashSet<u16>,             // packet ids de los publish entregados con qos > 0, sin puback aún.
+    inbound_in_flight: u16,                 // publish con qos > 0 recibidos del user, a los que aún no se respondió el puback.
+}
+
+/// Resumen de un `User`, para reportarlo por la consola del server.
+#[derive(Debug, PartialEq)]
+pub struct UserSummary {
+    pub username: String,
+    pub is_active: bool,
+    pub topics: Vec<String>,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
 }
 
 impl User {
@@ -37,7 +55,14 @@ impl User {
             state: UserState::Active,
             will_message: will_msg_and_topic,
             topics: Vec::new(),
+            granted_qos_by_topic: HashMap::new(),
             last_id_by_topic: HashMap::new(),
+            keep_alive: 0,
+            bytes_sent: 0,
+            bytes_received: 0,
+            next_packet_id: 1,
+            pending_acks: HashSet::new(),
+            inbound_in_flight: 0,
         }
     }
 
@@ -85,6 +110,11 @@ impl User {
         self.last_id_by_topic.insert(topic.to_owned(), last_id);
     }
 
+    /// Devuelve el qos otorgado al suscribirse al `topic`, con el que se le entregan sus mensajes.
+    pub fn get_granted_qos(&self, topic: &str) -> u8 {
+        self.granted_qos_by_topic.get(topic).copied().unwrap_or(0)
+    }
+
     /// Devuelve los topics a los que el user está suscripto.
     pub fn get_topics(&self) -> &Vec<String> {
         &self.topics
@@ -95,24 +125,63 @@ impl User {
         self.stream = new_stream
     }
 
+    /// Reemplaza el will message, por el recibido en una nueva conexión.
+    pub fn set_will_message(&mut self, will_message: Option<WillMessageData>) {
+        self.will_message = will_message;
+    }
+
+    /// Setea el keep alive, en segundos, indicado por el cliente en su connect.
+    pub fn set_keep_alive(&mut self, keep_alive: u16) {
+        self.keep_alive = keep_alive;
+    }
+
+    /// Devuelve el tiempo máximo sin recibir mensajes del cliente, luego del cual se lo considera desconectado:
+    /// una vez y media el keep alive, según el estándar. Devuelve None si el keep alive es 0 (sin límite).
+    pub fn get_keep_alive_timeout(&self) -> Option<Duration> {
+        if self.keep_alive == 0 {
+            return None;
+        }
+        Some(Duration::from_millis(u64::from(self.keep_alive) * 1500))
+    }
+
+    /// Devuelve la dirección del cliente del otro lado del stream actual, si se puede obtener.
+    pub fn get_peer_addr(&self) -> Option<SocketAddr> {
+        self.stream.peer_addr().ok()
+    }
+
     /// Setea el estado del user.
     pub fn set_state(&mut self, state: UserState) {
         self.state = state;
     }
 
-    /// Agrega el topic a los topics a los que user está suscripto.
-    pub fn add_topic(&mut self, topic: String) {
-        self.topics.push(topic.clone());
+    /// Agrega el topic a los topics a los que user está suscripto, con el qos otorgado para la entrega de sus mensajes.
+    pub fn add_topic(&mut self, topic: String, granted_qos: u8) {
+        self.granted_qos_by_topic.insert(topic.clone(), granted_qos);
+        if !self.topics.contains(&topic) {
+            self.topics.push(topic.clone());
+        }
         // Inicializa su last_id para ese topic en 0 si el mismo no existía.
         self.last_id_by_topic.entry(topic).or_insert(0);
     }
 
+    /// Quita el topic de los topics a los que user está suscripto. Se descarta también su last_id, por lo que
+    /// si vuelve a suscribirse recibe nuevamente los mensajes que el server conserve del topic.
+    /// Devuelve si estaba suscripto.
+    pub fn remove_topic(&mut self, topic: &str) -> bool {
+        self.granted_qos_by_topic.remove(topic);
+        self.last_id_by_topic.remove(topic);
+        let previous_len = self.topics.len();
+        self.topics.retain(|subscribed| subscribed != topic);
+        self.topics.len() != previous_len
+    }
+
     /// Escribe el mensaje en bytes `msg_bytes` por el stream hacia el cliente.
     /// Puede devolver error si falla la escritura o el flush.
     pub fn write_message(&mut self, msg_bytes: &[u8]) -> Result<(), Error> {
         if self.is_not_disconnected() {
-            let _ = self.stream.write(msg_bytes)?;
+            self.stream.write_all(msg_bytes)?;
             self.stream.flush()?;
+            self.bytes_sent += msg_bytes.len() as u64;
             return Ok(());
         }
         Err(Error::new(
@@ -121,6 +190,81 @@ impl User {
         ))
     }
 
+    /// Devuelve el `msg` a entregarle al user, con el qos otorgado en su suscripción al `topic`. Si el qos resultante
+    /// es mayor a 0, le asigna un packet id propio de este user y queda a la espera de su puback.
+    pub fn prepare_delivery_of(
+        &mut self,
+        msg: &PublishMessage,
+        topic: &str,
+    ) -> Result<PublishMessage, Error> {
+        let granted_qos = msg.get_qos().min(self.get_granted_qos(topic));
+        if granted_qos == 0 {
+            return msg.downgraded_to(0);
+        }
+        let packet_id = self.take_next_packet_id()?;
+        self.pending_acks.insert(packet_id);
+        msg.to_deliver_with(granted_qos, packet_id)
+    }
+
+    /// Devuelve un packet id que no esté esperando puback, y avanza al siguiente (el 0 no es válido).
+    /// Devuelve error si todos los packet ids están esperando puback.
+    fn take_next_packet_id(&mut self) -> Result<u16, Error> {
+        let start = self.next_packet_id;
+        while self.pending_acks.contains(&self.next_packet_id) {
+            self.next_packet_id = self.next_packet_id.checked_add(1).unwrap_or(1);
+            if self.next_packet_id == start {
+                return Err(Error::new(
+                    std::io::ErrorKind::WouldBlock,
+                    "Error: todos los packet ids del user están esperando puback.",
+                ));
+            }
+        }
+        let packet_id = self.next_packet_id;
+        self.next_packet_id = self.next_packet_id.checked_add(1).unwrap_or(1);
+        Ok(packet_id)
+    }
+
+    /// Registra el puback del user para el `packet_id`. Devuelve si se estaba esperando.
+    pub fn acknowledge(&mut self, packet_id: u16) -> bool {
+        self.pending_acks.remove(&packet_id)
+    }
+
+    /// Devuelve si se está esperando el puback del user para el `packet_id`.
+    pub fn is_awaiting_ack(&self, packet_id: u16) -> bool {
+        self.pending_acks.contains(&packet_id)
+    }
+
+    /// Registra un publish con qos > 0 recibido del user, si tiene menos de `receive_maximum` sin responder.
+    /// Devuelve si se lo registró; si no, debe esperarse a que se respondan los anteriores.
+    pub fn try_start_inbound_publish(&mut self, receive_maximum: u16) -> bool {
+        if self.inbound_in_flight >= receive_maximum {
+            return false;
+        }
+        self.inbound_in_flight += 1;
+        true
+    }
+
+    /// Registra que se respondió el puback de un publish recibido del user, liberando su lugar.
+    pub fn finish_inbound_publish(&mut self) {
+        self.inbound_in_flight = self.inbound_in_flight.saturating_sub(1);
+    }
+
+    /// Suma `amount` a los bytes recibidos desde el user.
+    pub fn add_bytes_received(&mut self, amount: usize) {
+        self.bytes_received += amount as u64;
+    }
+
+    /// Devuelve el resumen del user: su estado, sus suscripciones, y los bytes enviados y recibidos.
+    pub fn get_summary(&self) -> UserSummary {
+        UserSummary {
+            username: self.username.to_string(),
+            is_active: self.is_not_disconnected(),
+            topics: self.topics.clone(),
+            bytes_sent: self.bytes_sent,
+            bytes_received: self.bytes_received,
+        }
+    }
+
     // Aux: Usado para debugging.
     /// Devuelve el username.
     pub fn get_username(&self) -> String {